@@ -0,0 +1,507 @@
+// Package bindgen generates typed Go client bindings for runtime modules from the schema metadata
+// reported by the core.RuntimeMetadata query, so that clients can be regenerated whenever the
+// runtime is upgraded.
+//
+// The generated package mirrors the hand-written module packages: it contains a V1 interface with
+// a transaction builder for each call and a typed wrapper for each query, together with event
+// codes, event types and an event decoder. Module types are resolved through the type schemas
+// included in the metadata, with a Go struct generated for each referenced struct type.
+package bindgen
+
+import (
+	"bytes"
+	"fmt"
+	"go/format"
+	"sort"
+	"strings"
+	"unicode"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/modules/core"
+)
+
+const (
+	importCbor    = "github.com/oasisprotocol/oasis-core/go/common/cbor"
+	importClient  = "github.com/oasisprotocol/oasis-sdk/client-sdk/go/client"
+	importTypes   = "github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+	importContext = "context"
+	importFmt     = "fmt"
+
+	// unitType is the name of the Rust unit type.
+	unitType = "()"
+)
+
+// DefaultTypes are the default mappings of type names, as reported in the runtime metadata, to Go
+// types. Types without a mapping are resolved through their schemas.
+var DefaultTypes = map[string]string{
+	"oasis_runtime_sdk::types::address::Address":        "types.Address",
+	"oasis_runtime_sdk::types::token::BaseUnits":        "types.BaseUnits",
+	"oasis_runtime_sdk::types::token::Denomination":     "types.Denomination",
+	"oasis_runtime_sdk::types::transaction::Fee":        "types.Fee",
+	"oasis_runtime_sdk::types::transaction::CallFormat": "types.CallFormat",
+}
+
+// Config configures binding generation.
+type Config struct {
+	// Package is the name of the generated Go package.
+	Package string
+	// Types maps type names, as reported in the runtime metadata, to Go types. The mappings take
+	// precedence over DefaultTypes and the type schemas.
+	//
+	// Arguments of types that can't be resolved (e.g. enums and opaque types) are passed as
+	// interface{} while results and event fields of such types are returned as cbor.RawMessage.
+	Types map[string]string
+	// Imports are the import paths of additional packages referenced by Types.
+	Imports []string
+}
+
+// Generate generates typed bindings for the given module from its runtime metadata and returns
+// the formatted Go source.
+func Generate(cfg *Config, module string, meta *core.ModuleMetadata) ([]byte, error) {
+	if cfg.Package == "" {
+		return nil, fmt.Errorf("bindgen: missing package name")
+	}
+
+	g := &generator{
+		cfg:     cfg,
+		module:  module,
+		imports: map[string]bool{importClient: true},
+	}
+	for _, imp := range cfg.Imports {
+		g.imports[imp] = true
+	}
+	if err := g.generate(meta); err != nil {
+		return nil, err
+	}
+
+	if g.err != nil {
+		return nil, g.err
+	}
+
+	var out bytes.Buffer
+	fmt.Fprintf(&out, "// Code generated by bindgen from the metadata of the %s module. DO NOT EDIT.\n\n", module)
+	fmt.Fprintf(&out, "package %s\n\n", cfg.Package)
+	out.WriteString("import (\n")
+	imports := make([]string, 0, len(g.imports))
+	for imp := range g.imports {
+		imports = append(imports, imp)
+	}
+	sort.Strings(imports)
+	for _, imp := range imports {
+		fmt.Fprintf(&out, "\t%q\n", imp)
+	}
+	out.WriteString(")\n\n")
+	out.Write(g.body.Bytes())
+	out.Write(g.types.Bytes())
+
+	src, err := format.Source(out.Bytes())
+	if err != nil {
+		return nil, fmt.Errorf("bindgen: failed to format generated source: %w", err)
+	}
+	return src, nil
+}
+
+type method struct {
+	core.MethodMetadata
+
+	goName string
+}
+
+type generator struct {
+	cfg     *Config
+	module  string
+	imports map[string]bool
+	body    bytes.Buffer
+
+	// schemas are the type schemas of the module.
+	schemas map[string]core.TypeSchema
+	// structs are the Go names of the generated structs, keyed by type name.
+	structs map[string]string
+	// names are the type names of the declared Go identifiers, keyed by Go identifier.
+	names map[string]string
+	// types holds the declarations of the generated structs.
+	types bytes.Buffer
+	// err is the first error encountered while resolving types.
+	err error
+}
+
+func (g *generator) p(format string, args ...interface{}) {
+	fmt.Fprintf(&g.body, format, args...)
+	g.body.WriteByte('\n')
+}
+
+// goType returns the Go type for the given type name or the fallback in case the type can't be
+// resolved.
+func (g *generator) goType(name, fallback string) string {
+	if ty, ok := g.resolve(name); ok {
+		return ty
+	}
+	if fallback == "cbor.RawMessage" {
+		g.imports[importCbor] = true
+	}
+	return fallback
+}
+
+// resolve resolves the given type name into a Go type, either through the configured mappings or
+// through the type's schema.
+func (g *generator) resolve(name string) (string, bool) {
+	if ty, ok := g.cfg.Types[name]; ok {
+		return ty, true
+	}
+	if ty, ok := DefaultTypes[name]; ok {
+		g.imports[importTypes] = true
+		return ty, true
+	}
+	if ty, ok := g.structs[name]; ok {
+		return ty, true
+	}
+
+	schema, ok := g.schemas[name]
+	if !ok {
+		return "", false
+	}
+	switch schema.Kind {
+	case core.TypeKindBool:
+		return "bool", true
+	case core.TypeKindString:
+		return "string", true
+	case core.TypeKindUint:
+		switch schema.Size {
+		case 8, 16, 32, 64:
+			return fmt.Sprintf("uint%d", schema.Size), true
+		case 128:
+			g.imports[importTypes] = true
+			return "types.Quantity", true
+		}
+	case core.TypeKindInt:
+		switch schema.Size {
+		case 8, 16, 32, 64:
+			return fmt.Sprintf("int%d", schema.Size), true
+		}
+	case core.TypeKindBytes:
+		if schema.Size > 0 {
+			return fmt.Sprintf("[%d]byte", schema.Size), true
+		}
+		return "[]byte", true
+	case core.TypeKindArray:
+		if len(schema.Items) != 1 {
+			break
+		}
+		elem := g.goType(schema.Items[0], "cbor.RawMessage")
+		if schema.Size > 0 {
+			return fmt.Sprintf("[%d]%s", schema.Size, elem), true
+		}
+		return "[]" + elem, true
+	case core.TypeKindOptional:
+		if len(schema.Items) != 1 {
+			break
+		}
+		return "*" + g.goType(schema.Items[0], "cbor.RawMessage"), true
+	case core.TypeKindMap:
+		if len(schema.Items) != 2 {
+			break
+		}
+		key, ok := g.resolve(schema.Items[0])
+		if !ok || !isComparable(key) {
+			break
+		}
+		return fmt.Sprintf("map[%s]%s", key, g.goType(schema.Items[1], "cbor.RawMessage")), true
+	case core.TypeKindStruct:
+		return g.generateStruct(name, &schema), true
+	}
+	return "", false
+}
+
+// generateStruct generates a Go struct for the given struct type and returns its name.
+func (g *generator) generateStruct(name string, schema *core.TypeSchema) string {
+	goName := exportedName(baseName(name))
+	if other, ok := g.names[goName]; ok {
+		if g.err == nil {
+			g.err = fmt.Errorf("bindgen: %s and %s map to the same name", other, name)
+		}
+		return goName
+	}
+	g.names[goName] = name
+	// Register the struct before resolving its fields so that recursive types terminate.
+	g.structs[name] = goName
+
+	fields := make([]string, 0, len(schema.Fields))
+	for _, f := range schema.Fields {
+		tag := f.Name
+		if f.Optional {
+			tag += ",omitempty"
+		}
+		fields = append(fields, fmt.Sprintf("%s %s `json:%q`", exportedName(f.Name), g.goType(f.Type, "cbor.RawMessage"), tag))
+	}
+
+	fmt.Fprintf(&g.types, "// %s is the %s type.\n", goName, name)
+	fmt.Fprintf(&g.types, "type %s struct {\n", goName)
+	for _, f := range fields {
+		fmt.Fprintf(&g.types, "%s\n", f)
+	}
+	g.types.WriteString("}\n\n")
+	return goName
+}
+
+func (g *generator) generate(meta *core.ModuleMetadata) error {
+	g.schemas = meta.Types
+	g.structs = make(map[string]string)
+	g.names = map[string]string{
+		"ModuleName":  "the module name",
+		"V1":          "the module interface",
+		"NewV1":       "the module interface",
+		"Event":       "the module events",
+		"DecodeEvent": "the module events",
+	}
+	for _, ev := range meta.Events {
+		g.names[exportedName(ev.Name)+"Event"] = "event " + ev.Name
+		g.names[exportedName(ev.Name)+"EventCode"] = "event " + ev.Name
+	}
+
+	var calls, queries []*method
+	names := make(map[string]string)
+	for _, m := range meta.Methods {
+		goName := exportedName(m.Name[strings.LastIndex(m.Name, ".")+1:])
+		if goName == "" {
+			return fmt.Errorf("bindgen: malformed method name: %s", m.Name)
+		}
+		if m.Kind == core.MethodHandlerKindMessageResult {
+			continue
+		}
+		if other, ok := names[goName]; ok {
+			return fmt.Errorf("bindgen: methods %s and %s map to the same name", other, m.Name)
+		}
+		names[goName] = m.Name
+
+		switch m.Kind {
+		case core.MethodHandlerKindCall:
+			calls = append(calls, &method{m, goName})
+		case core.MethodHandlerKindQuery:
+			queries = append(queries, &method{m, goName})
+		}
+	}
+
+	g.p("// ModuleName is the %s module name.", g.module)
+	g.p("const ModuleName = %q", g.module)
+	g.p("")
+
+	if len(calls)+len(queries) > 0 {
+		g.imports[importTypes] = true
+		g.p("var (")
+		if len(calls) > 0 {
+			g.p("// Callable methods.")
+			for _, m := range calls {
+				g.p("method%s = types.MethodName(%q)", m.goName, m.Name)
+			}
+		}
+		if len(queries) > 0 {
+			if len(calls) > 0 {
+				g.p("")
+			}
+			g.p("// Queries.")
+			for _, m := range queries {
+				g.p("method%s = types.MethodName(%q)", m.goName, m.Name)
+			}
+		}
+		g.p(")")
+		g.p("")
+	}
+
+	// Interface.
+	g.p("// V1 is the v1 %s module interface.", g.module)
+	g.p("type V1 interface {")
+	if len(meta.Events) > 0 {
+		g.p("client.EventDecoder")
+		g.p("")
+	}
+	for _, m := range calls {
+		g.p("// %s generates a %s transaction.", m.goName, m.Name)
+		g.p("%s", g.callSignature(m))
+		g.p("")
+	}
+	for _, m := range queries {
+		g.p("// %s queries %s.", m.goName, m.Name)
+		g.p("%s", g.querySignature(m))
+		g.p("")
+	}
+	g.p("}")
+	g.p("")
+	g.p("type v1 struct {")
+	g.p("rc client.RuntimeClient")
+	g.p("}")
+	g.p("")
+
+	// Implementation.
+	for _, m := range calls {
+		body := "body"
+		if m.Args == unitType {
+			body = "nil"
+		}
+		g.p("// Implements V1.")
+		g.p("func (a *v1) %s {", g.callSignature(m))
+		g.p("return client.NewTransactionBuilder(a.rc, method%s, %s)", m.goName, body)
+		g.p("}")
+		g.p("")
+	}
+	for _, m := range queries {
+		args := "args"
+		if m.Args == unitType {
+			args = "nil"
+		}
+		g.p("// Implements V1.")
+		g.p("func (a *v1) %s {", g.querySignature(m))
+		if m.Result == unitType {
+			g.p("return a.rc.Query(ctx, round, method%s, %s, nil)", m.goName, args)
+		} else {
+			g.p("var rsp %s", g.goType(m.Result, "cbor.RawMessage"))
+			g.p("if err := a.rc.Query(ctx, round, method%s, %s, &rsp); err != nil {", m.goName, args)
+			g.p("return rsp, err")
+			g.p("}")
+			g.p("return rsp, nil")
+		}
+		g.p("}")
+		g.p("")
+	}
+
+	if len(meta.Events) > 0 {
+		g.generateEvents(meta.Events)
+	}
+
+	g.p("// NewV1 generates a V1 client helper for the %s module.", g.module)
+	g.p("func NewV1(rc client.RuntimeClient) V1 {")
+	g.p("return &v1{rc: rc}")
+	g.p("}")
+	return nil
+}
+
+func (g *generator) callSignature(m *method) string {
+	if m.Args == unitType {
+		return fmt.Sprintf("%s() *client.TransactionBuilder", m.goName)
+	}
+	return fmt.Sprintf("%s(body %s) *client.TransactionBuilder", m.goName, g.goType(m.Args, "interface{}"))
+}
+
+func (g *generator) querySignature(m *method) string {
+	g.imports[importContext] = true
+
+	params := "ctx context.Context, round uint64"
+	if m.Args != unitType {
+		params += ", args " + g.goType(m.Args, "interface{}")
+	}
+	if m.Result == unitType {
+		return fmt.Sprintf("%s(%s) error", m.goName, params)
+	}
+	return fmt.Sprintf("%s(%s) (%s, error)", m.goName, params, g.goType(m.Result, "cbor.RawMessage"))
+}
+
+func (g *generator) generateEvents(events []core.EventInfo) {
+	g.imports[importCbor] = true
+	g.imports[importFmt] = true
+	g.imports[importTypes] = true
+
+	g.p("const (")
+	for _, ev := range events {
+		g.p("// %sEventCode is the event code for the %s event.", exportedName(ev.Name), ev.Name)
+		g.p("%sEventCode = %d", exportedName(ev.Name), ev.Code)
+	}
+	g.p(")")
+	g.p("")
+
+	for _, ev := range events {
+		name := exportedName(ev.Name)
+		g.p("// %sEvent is a %s event.", name, ev.Name)
+		if len(ev.Fields) == 0 || ev.Fields[0].Name == "" {
+			// Events without named fields are exposed in their raw form.
+			g.p("type %sEvent struct {", name)
+			g.p("Raw cbor.RawMessage")
+			g.p("}")
+			g.p("")
+			continue
+		}
+		g.p("type %sEvent struct {", name)
+		for _, f := range ev.Fields {
+			g.p("%s %s `json:%q`", exportedName(f.Name), g.goType(f.Type, "cbor.RawMessage"), f.Name)
+		}
+		g.p("}")
+		g.p("")
+	}
+
+	g.p("// Event is a %s module event.", g.module)
+	g.p("type Event struct {")
+	for _, ev := range events {
+		g.p("%s *%sEvent", exportedName(ev.Name), exportedName(ev.Name))
+	}
+	g.p("}")
+	g.p("")
+
+	g.p("// Implements client.EventDecoder.")
+	g.p("func (a *v1) DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {")
+	g.p("return DecodeEvent(event)")
+	g.p("}")
+	g.p("")
+
+	g.p("// DecodeEvent decodes a %s module event.", g.module)
+	g.p("func DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {")
+	g.p("if event.Module != ModuleName {")
+	g.p("return nil, nil")
+	g.p("}")
+	g.p("var events []client.DecodedEvent")
+	g.p("switch event.Code {")
+	for _, ev := range events {
+		name := exportedName(ev.Name)
+		g.p("case %sEventCode:", name)
+		if len(ev.Fields) == 0 || ev.Fields[0].Name == "" {
+			g.p("var evs []cbor.RawMessage")
+		} else {
+			g.p("var evs []*%sEvent", name)
+		}
+		g.p("if err := cbor.Unmarshal(event.Value, &evs); err != nil {")
+		g.p("return nil, fmt.Errorf(\"decode %s %s event value: %%w\", err)", g.module, ev.Name)
+		g.p("}")
+		g.p("for _, ev := range evs {")
+		if len(ev.Fields) == 0 || ev.Fields[0].Name == "" {
+			g.p("events = append(events, &Event{%s: &%sEvent{Raw: ev}})", name, name)
+		} else {
+			g.p("events = append(events, &Event{%s: ev})", name)
+		}
+		g.p("}")
+	}
+	g.p("default:")
+	g.p("return nil, fmt.Errorf(\"invalid %s event code: %%v\", event.Code)", g.module)
+	g.p("}")
+	g.p("return events, nil")
+	g.p("}")
+	g.p("")
+}
+
+// baseName returns the last path segment of the given type name without any generic arguments.
+func baseName(name string) string {
+	if i := strings.IndexByte(name, '<'); i >= 0 {
+		name = name[:i]
+	}
+	return name[strings.LastIndex(name, ":")+1:]
+}
+
+// isComparable returns whether the given Go type can be used as a map key.
+func isComparable(ty string) bool {
+	return !strings.HasPrefix(ty, "[]") && !strings.HasPrefix(ty, "map[") && ty != "cbor.RawMessage"
+}
+
+// exportedName converts a Rust identifier (e.g. `debond_end_time`) into an exported Go identifier
+// (e.g. `DebondEndTime`).
+func exportedName(name string) string {
+	var b strings.Builder
+	upper := true
+	for _, r := range name {
+		switch {
+		case r == '_':
+			upper = true
+		case unicode.IsLetter(r) || unicode.IsDigit(r):
+			if upper {
+				r = unicode.ToUpper(r)
+				upper = false
+			}
+			b.WriteRune(r)
+		}
+	}
+	return b.String()
+}
@@ -0,0 +1,126 @@
+package bindgen
+
+import (
+	"go/parser"
+	"go/token"
+	"strings"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/modules/core"
+)
+
+func TestGenerate(t *testing.T) {
+	require := require.New(t)
+
+	meta := &core.ModuleMetadata{
+		Version: 1,
+		Methods: []core.MethodMetadata{
+			{Name: "example.Transfer", Kind: core.MethodHandlerKindCall, Args: "example::types::Transfer", Result: "()"},
+			{Name: "example.Reset", Kind: core.MethodHandlerKindCall, Args: "()", Result: "()"},
+			{Name: "example.Balance", Kind: core.MethodHandlerKindQuery, Args: "example::types::BalanceQuery", Result: "u128"},
+			{Name: "example.Unknown", Kind: core.MethodHandlerKindQuery, Args: "()", Result: "example::types::Unknown"},
+			{Name: "example.TransferResult", Kind: core.MethodHandlerKindMessageResult, Args: "example::types::Context", Result: "()"},
+		},
+		Events: []core.EventInfo{
+			{Module: "example", Code: 1, Name: "Transfer", Fields: []core.EventFieldInfo{
+				{Name: "from", Type: "oasis_runtime_sdk::types::address::Address"},
+				{Name: "amount_sent", Type: "oasis_runtime_sdk::types::token::BaseUnits"},
+				{Name: "memo", Type: "example::types::Memo"},
+			}},
+			{Module: "example", Code: 2, Name: "Raw", Fields: []core.EventFieldInfo{{Type: "String"}}},
+		},
+	}
+	types := make(map[string]core.TypeSchema)
+	types["()"] = core.TypeSchema{Kind: core.TypeKindUnit}
+	types["u64"] = core.TypeSchema{Kind: core.TypeKindUint, Size: 64}
+	types["u128"] = core.TypeSchema{Kind: core.TypeKindUint, Size: 128}
+	types["String"] = core.TypeSchema{Kind: core.TypeKindString}
+	types["[u8; 32]"] = core.TypeSchema{Kind: core.TypeKindBytes, Size: 32}
+	types["Vec<u64>"] = core.TypeSchema{Kind: core.TypeKindArray, Items: []string{"u64"}}
+	types["Option<u64>"] = core.TypeSchema{Kind: core.TypeKindOptional, Items: []string{"u64"}}
+	types["BTreeMap<String, example::types::Memo>"] = core.TypeSchema{Kind: core.TypeKindMap, Items: []string{"String", "example::types::Memo"}}
+	types["Option<example::types::Transfer>"] = core.TypeSchema{Kind: core.TypeKindOptional, Items: []string{"example::types::Transfer"}}
+	types["oasis_runtime_sdk::types::address::Address"] = core.TypeSchema{Kind: core.TypeKindBytes, Size: 21}
+	types["example::types::Transfer"] = core.TypeSchema{Kind: core.TypeKindStruct, Fields: []core.FieldSchema{
+		{Name: "to", Type: "oasis_runtime_sdk::types::address::Address"},
+		{Name: "amounts", Type: "Vec<u64>"},
+		{Name: "nonce", Type: "Option<u64>", Optional: true},
+		{Name: "memos", Type: "BTreeMap<String, example::types::Memo>"},
+		{Name: "next", Type: "Option<example::types::Transfer>", Optional: true},
+	}}
+	types["example::types::BalanceQuery"] = core.TypeSchema{Kind: core.TypeKindStruct, Fields: []core.FieldSchema{
+		{Name: "account", Type: "[u8; 32]"},
+	}}
+	types["example::types::Memo"] = core.TypeSchema{Kind: core.TypeKindEnum}
+	types["example::types::Unknown"] = core.TypeSchema{Kind: core.TypeKindOpaque}
+	meta.Types = types
+
+	cfg := &Config{
+		Package: "example",
+		Types: map[string]string{
+			"example::types::Unknown": "*example.Unknown",
+		},
+		Imports: []string{"example.com/example"},
+	}
+
+	src, err := Generate(cfg, "example", meta)
+	require.NoError(err, "Generate")
+	code := string(src)
+	// Compare declarations independently of their alignment.
+	normalized := strings.Join(strings.Fields(code), " ")
+
+	_, err = parser.ParseFile(token.NewFileSet(), "example.go", src, parser.AllErrors)
+	require.NoError(err, "generated code should parse")
+
+	require.Contains(code, `methodTransfer = types.MethodName("example.Transfer")`)
+	require.Contains(code, "Transfer(body Transfer) *client.TransactionBuilder")
+	require.Contains(code, "Reset() *client.TransactionBuilder")
+	require.Contains(code, "Balance(ctx context.Context, round uint64, args BalanceQuery) (types.Quantity, error)")
+	require.Contains(code, "Unknown(ctx context.Context, round uint64) (*example.Unknown, error)", "configured mappings should take precedence")
+	require.NotContains(code, "TransferResult", "message result handlers should be skipped")
+
+	// Struct types are generated from their schemas.
+	require.Contains(code, "type Transfer struct {")
+	require.Contains(normalized, "To types.Address `json:\"to\"`")
+	require.Contains(normalized, "Amounts []uint64 `json:\"amounts\"`")
+	require.Contains(normalized, "Nonce *uint64 `json:\"nonce,omitempty\"`")
+	require.Contains(normalized, "Memos map[string]cbor.RawMessage `json:\"memos\"`", "unresolved types should be raw")
+	require.Contains(normalized, "Next *Transfer `json:\"next,omitempty\"`")
+	require.Contains(normalized, "Account [32]byte `json:\"account\"`")
+
+	require.Contains(code, "TransferEventCode = 1")
+	require.Contains(normalized, "AmountSent types.BaseUnits `json:\"amount_sent\"`")
+	require.Contains(normalized, "From types.Address `json:\"from\"`")
+	require.Contains(code, "events = append(events, &Event{Raw: &RawEvent{Raw: ev}})")
+	require.Contains(code, `"example.com/example"`)
+
+	// Methods mapping to the same Go name should be rejected.
+	meta.Methods = append(meta.Methods, core.MethodMetadata{Name: "other.Transfer", Kind: core.MethodHandlerKindQuery, Args: "()", Result: "()"})
+	_, err = Generate(cfg, "example", meta)
+	require.Error(err, "Generate should reject conflicting method names")
+	meta.Methods = meta.Methods[:len(meta.Methods)-1]
+
+	// Types mapping to the same Go name as other declarations should be rejected.
+	meta.Types["example::types::Event"] = core.TypeSchema{Kind: core.TypeKindStruct}
+	meta.Methods = append(meta.Methods, core.MethodMetadata{Name: "example.Events", Kind: core.MethodHandlerKindQuery, Args: "()", Result: "example::types::Event"})
+	_, err = Generate(cfg, "example", meta)
+	require.Error(err, "Generate should reject conflicting type names")
+}
+
+func TestExportedName(t *testing.T) {
+	require := require.New(t)
+
+	require.Equal("DebondEndTime", exportedName("debond_end_time"))
+	require.Equal("Transfer", exportedName("Transfer"))
+	require.Equal("GasUsed", exportedName("GasUsed"))
+}
+
+func TestBaseName(t *testing.T) {
+	require := require.New(t)
+
+	require.Equal("Transfer", baseName("oasis_runtime_sdk::modules::accounts::types::Transfer"))
+	require.Equal("Page", baseName("example::types::Page<u64, String>"))
+	require.Equal("u64", baseName("u64"))
+}
@@ -1,13 +1,16 @@
 package callformat
 
 import (
+	"crypto/hmac"
 	"crypto/rand"
+	"crypto/sha512"
 	"fmt"
 
 	"github.com/oasisprotocol/curve25519-voi/primitives/x25519"
 	"github.com/oasisprotocol/deoxysii"
 	"github.com/oasisprotocol/oasis-core/go/common/cbor"
 	mraeDeoxysii "github.com/oasisprotocol/oasis-core/go/common/crypto/mrae/deoxysii"
+	"golang.org/x/crypto/chacha20poly1305"
 
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
 )
@@ -27,6 +30,13 @@ type metaEncryptedX25519DeoxysII struct {
 	pk *x25519.PublicKey
 }
 
+type metaEncryptedX25519XChaCha20Poly1305 struct {
+	// sk is the ephemeral secret key for X25519.
+	sk *x25519.PrivateKey
+	// pk is the current calldata X25519 public key.
+	pk *x25519.PublicKey
+}
+
 // EncodeCall encodes a call based on its configured call format.
 //
 // It returns the encoded call and any metadata needed to successfully decode the result.
@@ -35,7 +45,7 @@ func EncodeCall(call *types.Call, cf types.CallFormat, cfg *EncodeConfig) (*type
 	case types.CallFormatPlain:
 		// In case of the plain-text data format, we simply pass on the call unchanged.
 		return call, nil, nil
-	case types.CallFormatEncryptedX25519DeoxysII:
+	case types.CallFormatEncryptedX25519DeoxysII, types.CallFormatEncryptedX25519XChaCha20Poly1305:
 		// We require the runtime's call data public key to be configured.
 		if cfg == nil || cfg.PublicKey == nil {
 			return nil, nil, fmt.Errorf("callformat: runtime call data public key not set")
@@ -46,30 +56,60 @@ func EncodeCall(call *types.Call, cf types.CallFormat, cfg *EncodeConfig) (*type
 		if err != nil {
 			return nil, nil, fmt.Errorf("callformat: failed to generate ephemeral X25519 key pair: %w", err)
 		}
-		// Generate random nonce.
-		var nonce [deoxysii.NonceSize]byte
-		if _, err := rand.Read(nonce[:]); err != nil {
-			return nil, nil, fmt.Errorf("callformat: failed to generate random nonce: %w", err)
-		}
 
 		// Seal serialized plain call.
 		rawCall := cbor.Marshal(call)
-		sealedCall := mraeDeoxysii.Box.Seal(nil, nonce[:], rawCall, nil, &cfg.PublicKey.PublicKey, sk)
+		var (
+			envelope interface{}
+			meta     interface{}
+		)
+		switch cf {
+		case types.CallFormatEncryptedX25519DeoxysII:
+			// Generate random nonce.
+			var nonce [deoxysii.NonceSize]byte
+			if _, err = rand.Read(nonce[:]); err != nil {
+				return nil, nil, fmt.Errorf("callformat: failed to generate random nonce: %w", err)
+			}
+			sealedCall := mraeDeoxysii.Box.Seal(nil, nonce[:], rawCall, nil, &cfg.PublicKey.PublicKey, sk)
 
-		encoded := &types.Call{
-			Format: types.CallFormatEncryptedX25519DeoxysII,
-			Method: "",
-			Body: cbor.Marshal(&types.CallEnvelopeX25519DeoxysII{
+			envelope = &types.CallEnvelopeX25519DeoxysII{
 				Pk:    *pk,
 				Nonce: nonce,
 				Epoch: cfg.Epoch,
 				Data:  sealedCall,
-			}),
-			ReadOnly: call.ReadOnly,
+			}
+			meta = &metaEncryptedX25519DeoxysII{
+				sk: sk,
+				pk: &cfg.PublicKey.PublicKey,
+			}
+		default:
+			// Generate random nonce, as XChaCha20-Poly1305 is not nonce-misuse resistant.
+			var nonce [types.XChaCha20Poly1305NonceSize]byte
+			if _, err = rand.Read(nonce[:]); err != nil {
+				return nil, nil, fmt.Errorf("callformat: failed to generate random nonce: %w", err)
+			}
+			var sealedCall []byte
+			if sealedCall, err = xchacha20Poly1305BoxSeal(nonce[:], rawCall, nil, &cfg.PublicKey.PublicKey, sk); err != nil {
+				return nil, nil, err
+			}
+
+			envelope = &types.CallEnvelopeX25519XChaCha20Poly1305{
+				Pk:    *pk,
+				Nonce: nonce,
+				Epoch: cfg.Epoch,
+				Data:  sealedCall,
+			}
+			meta = &metaEncryptedX25519XChaCha20Poly1305{
+				sk: sk,
+				pk: &cfg.PublicKey.PublicKey,
+			}
 		}
-		meta := &metaEncryptedX25519DeoxysII{
-			sk: sk,
-			pk: &cfg.PublicKey.PublicKey,
+
+		encoded := &types.Call{
+			Format:   cf,
+			Method:   "",
+			Body:     cbor.Marshal(envelope),
+			ReadOnly: call.ReadOnly,
 		}
 		return encoded, meta, nil
 	default:
@@ -85,35 +125,104 @@ func DecodeResult(result *types.CallResult, meta interface{}) (*types.CallResult
 		return result, nil
 	case *metaEncryptedX25519DeoxysII:
 		var envelope types.ResultEnvelopeX25519DeoxysII
-		switch {
-		case result.IsUnknown():
-			if err := cbor.Unmarshal(result.Unknown, &envelope); err != nil {
-				return nil, fmt.Errorf("callformat: malformed result envelope: %w", err)
-			}
-		case result.IsSuccess():
-			if err := cbor.Unmarshal(result.Ok, &envelope); err != nil {
-				return nil, fmt.Errorf("callformat: malformed result envelope: %w", err)
-			}
-		default:
-			// Submission could fail before call format processing so the result would be plain.
-			return nil, result.Failed
+		if err := unmarshalResultEnvelope(result, &envelope); err != nil {
+			return nil, err
 		}
-
-		// Open sealed envelope.
-		var (
-			pt  []byte
-			err error
-		)
-		if pt, err = mraeDeoxysii.Box.Open(nil, envelope.Nonce[:], envelope.Data, nil, m.pk, m.sk); err != nil {
-			return nil, fmt.Errorf("callformat: failed to open result envelope: %w", err)
-		}
-
-		var output types.CallResult
-		if err = cbor.Unmarshal(pt, &output); err != nil {
-			return nil, fmt.Errorf("callformat: malformed result: %w", err)
+		return openResult(envelope.Header, func(ad []byte) ([]byte, error) {
+			return mraeDeoxysii.Box.Open(nil, envelope.Nonce[:], envelope.Data, ad, m.pk, m.sk)
+		})
+	case *metaEncryptedX25519XChaCha20Poly1305:
+		var envelope types.ResultEnvelopeX25519XChaCha20Poly1305
+		if err := unmarshalResultEnvelope(result, &envelope); err != nil {
+			return nil, err
 		}
-		return &output, nil
+		return openResult(envelope.Header, func(ad []byte) ([]byte, error) {
+			return xchacha20Poly1305BoxOpen(envelope.Nonce[:], envelope.Data, ad, m.pk, m.sk)
+		})
 	default:
 		return nil, fmt.Errorf("callformat: unsupported call format: %T", m)
 	}
 }
+
+// unmarshalResultEnvelope unmarshals the result envelope of an encrypted call.
+func unmarshalResultEnvelope(result *types.CallResult, envelope interface{}) error {
+	var raw []byte
+	switch {
+	case result.IsUnknown():
+		raw = result.Unknown
+	case result.IsSuccess():
+		raw = result.Ok
+	default:
+		// Submission could fail before call format processing so the result would be plain.
+		return result.Failed
+	}
+	if err := cbor.Unmarshal(raw, envelope); err != nil {
+		return fmt.Errorf("callformat: malformed result envelope: %w", err)
+	}
+	return nil
+}
+
+// openResult opens a sealed result. The plaintext header (if any) is authenticated as additional
+// data.
+func openResult(header *types.ResultHeader, open func(ad []byte) ([]byte, error)) (*types.CallResult, error) {
+	var ad []byte
+	if header != nil {
+		ad = cbor.Marshal(header)
+	}
+
+	pt, err := open(ad)
+	if err != nil {
+		if header != nil && !header.Success {
+			return nil, fmt.Errorf("callformat: failed to open result envelope of failed call (module: %s code: %d): %w",
+				header.Module, header.Code, err)
+		}
+		return nil, fmt.Errorf("callformat: failed to open result envelope: %w", err)
+	}
+
+	var output types.CallResult
+	if err = cbor.Unmarshal(pt, &output); err != nil {
+		return nil, fmt.Errorf("callformat: malformed result: %w", err)
+	}
+	return &output, nil
+}
+
+// xchacha20Poly1305DeriveKey derives the symmetric key of the X25519-XChaCha20-Poly1305 box from
+// the given X25519 public and private keys.
+func xchacha20Poly1305DeriveKey(pk *x25519.PublicKey, sk *x25519.PrivateKey) ([]byte, error) {
+	pmk, err := x25519.X25519(sk[:], pk[:])
+	if err != nil {
+		return nil, fmt.Errorf("callformat: failed to derive shared secret: %w", err)
+	}
+
+	kdf := hmac.New(sha512.New512_256, []byte("MRAE_Box_XChaCha20-Poly1305"))
+	_, _ = kdf.Write(pmk)
+	return kdf.Sum(nil), nil
+}
+
+// xchacha20Poly1305BoxSeal seals the plaintext and additional data using the key derived from the
+// given X25519 key pair.
+func xchacha20Poly1305BoxSeal(nonce, plaintext, ad []byte, pk *x25519.PublicKey, sk *x25519.PrivateKey) ([]byte, error) {
+	key, err := xchacha20Poly1305DeriveKey(pk, sk)
+	if err != nil {
+		return nil, err
+	}
+	aead, err := chacha20poly1305.NewX(key)
+	if err != nil {
+		return nil, fmt.Errorf("callformat: failed to initialize XChaCha20-Poly1305: %w", err)
+	}
+	return aead.Seal(nil, nonce, plaintext, ad), nil
+}
+
+// xchacha20Poly1305BoxOpen opens a box sealed using the key derived from the given X25519 key
+// pair.
+func xchacha20Poly1305BoxOpen(nonce, ciphertext, ad []byte, pk *x25519.PublicKey, sk *x25519.PrivateKey) ([]byte, error) {
+	key, err := xchacha20Poly1305DeriveKey(pk, sk)
+	if err != nil {
+		return nil, err
+	}
+	aead, err := chacha20poly1305.NewX(key)
+	if err != nil {
+		return nil, fmt.Errorf("callformat: failed to initialize XChaCha20-Poly1305: %w", err)
+	}
+	return aead.Open(nil, nonce, ciphertext, ad)
+}
@@ -0,0 +1,107 @@
+package callformat
+
+import (
+	"crypto/rand"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/curve25519-voi/primitives/x25519"
+	"github.com/oasisprotocol/deoxysii"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	mraeDeoxysii "github.com/oasisprotocol/oasis-core/go/common/crypto/mrae/deoxysii"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+func TestEncryptedCallFormats(t *testing.T) {
+	require := require.New(t)
+
+	// Runtime call data key pair.
+	rtPk, rtSk, err := x25519.GenerateKey(rand.Reader)
+	require.NoError(err, "GenerateKey")
+	cfg := &EncodeConfig{
+		PublicKey: &types.SignedPublicKey{PublicKey: *rtPk},
+		Epoch:     5,
+	}
+
+	call := &types.Call{Method: "test.Method", Body: cbor.Marshal("args"), ReadOnly: true}
+	header := &types.ResultHeader{Success: true}
+	output := &types.CallResult{Ok: cbor.Marshal("result")}
+
+	for _, tc := range []struct {
+		format types.CallFormat
+		// open opens the encoded call body and returns the plain call and the caller's key.
+		open func(body []byte) ([]byte, *x25519.PublicKey)
+		// seal seals the result for the caller.
+		seal func(pt []byte, pk *x25519.PublicKey) []byte
+	}{
+		{
+			types.CallFormatEncryptedX25519DeoxysII,
+			func(body []byte) ([]byte, *x25519.PublicKey) {
+				var envelope types.CallEnvelopeX25519DeoxysII
+				require.NoError(cbor.Unmarshal(body, &envelope), "malformed call envelope")
+				require.EqualValues(5, envelope.Epoch)
+				pt, err := mraeDeoxysii.Box.Open(nil, envelope.Nonce[:], envelope.Data, nil, &envelope.Pk, rtSk)
+				require.NoError(err, "call should be sealed for the runtime key")
+				return pt, &envelope.Pk
+			},
+			func(pt []byte, pk *x25519.PublicKey) []byte {
+				var nonce [deoxysii.NonceSize]byte
+				return cbor.Marshal(&types.ResultEnvelopeX25519DeoxysII{
+					Nonce:  nonce,
+					Data:   mraeDeoxysii.Box.Seal(nil, nonce[:], pt, cbor.Marshal(header), pk, rtSk),
+					Header: header,
+				})
+			},
+		},
+		{
+			types.CallFormatEncryptedX25519XChaCha20Poly1305,
+			func(body []byte) ([]byte, *x25519.PublicKey) {
+				var envelope types.CallEnvelopeX25519XChaCha20Poly1305
+				require.NoError(cbor.Unmarshal(body, &envelope), "malformed call envelope")
+				require.EqualValues(5, envelope.Epoch)
+				pt, err := xchacha20Poly1305BoxOpen(envelope.Nonce[:], envelope.Data, nil, &envelope.Pk, rtSk)
+				require.NoError(err, "call should be sealed for the runtime key")
+				return pt, &envelope.Pk
+			},
+			func(pt []byte, pk *x25519.PublicKey) []byte {
+				var nonce [types.XChaCha20Poly1305NonceSize]byte
+				_, err := rand.Read(nonce[:])
+				require.NoError(err, "rand.Read")
+				data, err := xchacha20Poly1305BoxSeal(nonce[:], pt, cbor.Marshal(header), pk, rtSk)
+				require.NoError(err, "xchacha20Poly1305BoxSeal")
+				return cbor.Marshal(&types.ResultEnvelopeX25519XChaCha20Poly1305{
+					Nonce:  nonce,
+					Data:   data,
+					Header: header,
+				})
+			},
+		},
+	} {
+		encoded, meta, err := EncodeCall(call, tc.format, cfg)
+		require.NoError(err, "EncodeCall(%s)", tc.format)
+		require.EqualValues(tc.format, encoded.Format)
+		require.Empty(encoded.Method, "method should be hidden")
+		require.True(encoded.ReadOnly, "read-only flag should be preserved")
+
+		pt, pk := tc.open(encoded.Body)
+		var decoded types.Call
+		require.NoError(cbor.Unmarshal(pt, &decoded), "malformed call")
+		require.EqualValues(call.Method, decoded.Method)
+		require.EqualValues(call.Body, decoded.Body)
+
+		result, err := DecodeResult(&types.CallResult{Ok: tc.seal(cbor.Marshal(output), pk)}, meta)
+		require.NoError(err, "DecodeResult(%s)", tc.format)
+		require.EqualValues(output.Ok, result.Ok)
+
+		// Results sealed for another caller should be rejected.
+		otherPk, _, err := x25519.GenerateKey(rand.Reader)
+		require.NoError(err, "GenerateKey")
+		_, err = DecodeResult(&types.CallResult{Ok: tc.seal(cbor.Marshal(output), otherPk)}, meta)
+		require.Error(err, "DecodeResult(%s) should reject results sealed for another caller", tc.format)
+	}
+
+	_, _, err = EncodeCall(call, types.CallFormatEncryptedX25519XChaCha20Poly1305, nil)
+	require.Error(err, "EncodeCall should require the runtime call data public key")
+}
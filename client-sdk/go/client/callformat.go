@@ -24,7 +24,11 @@ type callDataPublicKeyQueryResponse struct {
 func (tb *TransactionBuilder) encodeCall(ctx context.Context, call *types.Call, cf types.CallFormat) (*types.Call, interface{}, error) {
 	var cfg callformat.EncodeConfig
 	switch cf {
-	case types.CallFormatEncryptedX25519DeoxysII:
+	case types.CallFormatEncryptedX25519DeoxysII, types.CallFormatEncryptedX25519XChaCha20Poly1305:
+		if tb.ccb != nil {
+			return tb.ccb.encodeCall(ctx, call, cf)
+		}
+
 		// Obtain current calldata X25519 public key.
 		var rsp callDataPublicKeyQueryResponse
 		if err := tb.rc.Query(ctx, RoundLatest, methodCallDataPublicKey, nil, &rsp); err != nil {
@@ -126,6 +126,11 @@ type CheckTxError struct {
 	Message string
 }
 
+// Error implements error.
+func (e *CheckTxError) Error() string {
+	return fmt.Sprintf("check tx failed (module: %s code: %d): %s", e.Module, e.Code, e.Message)
+}
+
 // SubmitTxRawMeta is the result of SubmitTxRawMeta call.
 type SubmitTxRawMeta struct {
 	TransactionMeta
@@ -377,37 +382,11 @@ func (rc *runtimeClient) GetEventsRaw(ctx context.Context, round uint64) ([]*typ
 
 // Implements RuntimeClient.
 func (rc *runtimeClient) GetEvents(ctx context.Context, round uint64, decoders []EventDecoder, includeUndecoded bool) ([]DecodedEvent, error) {
-	rawEvs, err := rc.cc.GetEvents(ctx, &coreClient.GetEventsRequest{
-		RuntimeID: rc.runtimeID,
-		Round:     round,
-	})
+	rawEvs, err := rc.GetEventsRaw(ctx, round)
 	if err != nil {
 		return nil, err
 	}
-
-	evs := make([]DecodedEvent, 0)
-OUTER:
-	for _, rawEv := range rawEvs {
-		var ev types.Event
-		if err := ev.UnmarshalRaw(rawEv.Key, rawEv.Value, &rawEv.TxHash); err != nil { //nolint: gosec
-			return nil, fmt.Errorf("failed to unmarshal event '%v': %w", rawEv, err)
-		}
-		for _, decoder := range decoders {
-			decoded, err := decoder.DecodeEvent(&ev)
-			if err != nil {
-				return nil, fmt.Errorf("failed to decode event: %w", err)
-			}
-			if decoded != nil {
-				evs = append(evs, decoded...)
-				continue OUTER
-			}
-		}
-		if includeUndecoded {
-			evs = append(evs, &ev)
-		}
-	}
-
-	return evs, nil
+	return decodeEvents(rawEvs, decoders, includeUndecoded)
 }
 
 // Implements RuntimeClient.
@@ -0,0 +1,123 @@
+package client
+
+import (
+	"context"
+	"fmt"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	"github.com/oasisprotocol/oasis-core/go/common/pubsub"
+	roothash "github.com/oasisprotocol/oasis-core/go/roothash/api"
+	"github.com/oasisprotocol/oasis-core/go/roothash/api/block"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// testRuntimeClient is a runtime client that serves canned responses. Methods that are not
+// overridden panic when called.
+type testRuntimeClient struct {
+	RuntimeClient
+
+	info      *types.RuntimeInfo
+	queries   map[types.MethodName]interface{}
+	submitted []*types.UnverifiedTransaction
+	result    *SubmitTxRawMeta
+
+	// submitErrs are the errors returned by subsequent SubmitTxRawMeta calls before returning
+	// the result.
+	submitErrs []error
+
+	// watches are the block channels returned by subsequent WatchBlocks calls.
+	watches []chan *roothash.AnnotatedBlock
+	// events are the events emitted in each round.
+	events map[uint64][]*types.Event
+	// txs are the transactions included in each round.
+	txs map[uint64][]*TransactionWithResults
+	// latestRound is the round of the latest block.
+	latestRound uint64
+}
+
+type testSubscription struct{}
+
+func (testSubscription) Close() {}
+
+func newTestRuntimeClient() *testRuntimeClient {
+	return &testRuntimeClient{
+		info: &types.RuntimeInfo{
+			ChainContext: &signature.RichContext{
+				RuntimeID:    common.Namespace{},
+				ChainContext: "test",
+				Base:         types.SignatureContextBase,
+			},
+		},
+		queries: make(map[types.MethodName]interface{}),
+		result: &SubmitTxRawMeta{
+			TransactionMeta: TransactionMeta{Round: 42},
+			Result:          types.CallResult{Ok: cbor.Marshal("ok")},
+		},
+		events: make(map[uint64][]*types.Event),
+		txs:    make(map[uint64][]*TransactionWithResults),
+	}
+}
+
+// watchBlocks queues a block subscription delivering the given rounds. The subscription fails
+// after delivering the rounds in case closed is true.
+func (rc *testRuntimeClient) watchBlocks(closed bool, rounds ...uint64) {
+	ch := make(chan *roothash.AnnotatedBlock, len(rounds))
+	for _, round := range rounds {
+		ch <- &roothash.AnnotatedBlock{
+			Block: &block.Block{Header: block.Header{Round: round}},
+		}
+	}
+	if closed {
+		close(ch)
+	}
+	rc.watches = append(rc.watches, ch)
+}
+
+func (rc *testRuntimeClient) GetInfo(context.Context) (*types.RuntimeInfo, error) {
+	return rc.info, nil
+}
+
+func (rc *testRuntimeClient) Query(_ context.Context, _ uint64, method types.MethodName, _, rsp interface{}) error {
+	result, ok := rc.queries[method]
+	if !ok {
+		return fmt.Errorf("unexpected query: %s", method)
+	}
+	return cbor.Unmarshal(cbor.Marshal(result), rsp)
+}
+
+func (rc *testRuntimeClient) SubmitTxRawMeta(_ context.Context, tx *types.UnverifiedTransaction) (*SubmitTxRawMeta, error) {
+	rc.submitted = append(rc.submitted, tx)
+	if len(rc.submitErrs) > 0 {
+		err := rc.submitErrs[0]
+		rc.submitErrs = rc.submitErrs[1:]
+		return nil, err
+	}
+	return rc.result, nil
+}
+
+func (rc *testRuntimeClient) WatchBlocks(context.Context) (<-chan *roothash.AnnotatedBlock, pubsub.ClosableSubscription, error) {
+	if len(rc.watches) == 0 {
+		return nil, nil, fmt.Errorf("no more block subscriptions")
+	}
+	ch := rc.watches[0]
+	rc.watches = rc.watches[1:]
+	return ch, testSubscription{}, nil
+}
+
+func (rc *testRuntimeClient) GetEventsRaw(_ context.Context, round uint64) ([]*types.Event, error) {
+	return rc.events[round], nil
+}
+
+func (rc *testRuntimeClient) GetBlock(_ context.Context, round uint64) (*block.Block, error) {
+	if round == RoundLatest {
+		round = rc.latestRound
+	}
+	return &block.Block{Header: block.Header{Round: round}}, nil
+}
+
+func (rc *testRuntimeClient) GetTransactionsWithResults(_ context.Context, round uint64) ([]*TransactionWithResults, error) {
+	return rc.txs[round], nil
+}
@@ -0,0 +1,358 @@
+package client
+
+import (
+	"bytes"
+	"context"
+	"fmt"
+	"sync"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/callformat"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+const (
+	// DefaultViewCallGasLimit is the default gas limit of confidential view calls that don't
+	// specify one.
+	DefaultViewCallGasLimit = 30_000_000
+	// DefaultViewCallRoundRange is the number of rounds for which a signed view call is valid.
+	DefaultViewCallRoundRange = 15
+
+	methodCallDataPublicKeyValidity = "core.CallDataPublicKeyValidity"
+	methodExecuteReadOnlyTx         = "core.ExecuteReadOnlyTx"
+)
+
+type executeReadOnlyTxQuery struct {
+	Tx []byte `json:"tx"`
+}
+
+type executeReadOnlyTxResponse struct {
+	Result types.CallResult `json:"result"`
+}
+
+type callDataPublicKeyValidityQueryResponse struct {
+	// PublicKey is the ephemeral X25519 runtime public key.
+	PublicKey types.SignedPublicKey `json:"public_key"`
+	// Epoch is the epoch of the ephemeral runtime key.
+	Epoch uint64 `json:"epoch"`
+	// RotateAfter is the last epoch in which the key is used for encrypting new calls.
+	RotateAfter uint64 `json:"rotate_after"`
+	// ValidUntil is the last epoch in which calls encrypted with the key are accepted.
+	ValidUntil uint64 `json:"valid_until"`
+}
+
+// EpochSource returns the current consensus layer epoch.
+type EpochSource func(ctx context.Context) (uint64, error)
+
+// CallDataPublicKeyVerifier verifies the key manager signature of a runtime call data public key
+// for the given epoch.
+type CallDataPublicKeyVerifier func(pk *types.SignedPublicKey, epoch uint64) error
+
+// PinnedCallDataPublicKey is a runtime call data public key pinned by a confidential call builder.
+type PinnedCallDataPublicKey struct {
+	// PublicKey is the signed runtime call data public key.
+	PublicKey types.SignedPublicKey
+	// Epoch is the epoch of the ephemeral runtime key.
+	Epoch uint64
+	// RotateAfter is the last epoch in which the key is used for encrypting new calls.
+	RotateAfter uint64
+	// ValidUntil is the last epoch in which calls encrypted with the key are accepted.
+	ValidUntil uint64
+}
+
+// ConfidentialCallBuilder produces encrypted calls using one of the encrypted call formats
+// (X25519-Deoxys-II by default) and decrypts their results.
+//
+// The runtime call data public key is fetched once, verified and pinned together with its key
+// manager signature. It is only replaced after an explicit refresh returns a key for a newer
+// epoch, a different key for an already pinned epoch is rejected. A fresh ephemeral key pair is
+// generated for every call so that compromise of a client key only affects a single call.
+type ConfidentialCallBuilder struct {
+	l sync.Mutex
+
+	rc          RuntimeClient
+	verifier    CallDataPublicKeyVerifier
+	epochSource EpochSource
+	callFormat  types.CallFormat
+
+	pinned *PinnedCallDataPublicKey
+}
+
+// NewConfidentialCallBuilder creates a new confidential call builder.
+//
+// The verifier is used to verify key manager signatures of fetched call data public keys. In case
+// it is nil, the node that the runtime client is connected to must be trusted.
+func NewConfidentialCallBuilder(rc RuntimeClient, verifier CallDataPublicKeyVerifier) *ConfidentialCallBuilder {
+	return &ConfidentialCallBuilder{
+		rc:         rc,
+		verifier:   verifier,
+		callFormat: types.CallFormatEncryptedX25519DeoxysII,
+	}
+}
+
+// SetCallFormat configures the encrypted call format used for encoding calls.
+func (b *ConfidentialCallBuilder) SetCallFormat(format types.CallFormat) error {
+	switch format {
+	case types.CallFormatEncryptedX25519DeoxysII, types.CallFormatEncryptedX25519XChaCha20Poly1305:
+	default:
+		return fmt.Errorf("callformat: call format %s is not an encrypted call format", format)
+	}
+
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	b.callFormat = format
+	return nil
+}
+
+// CallFormat returns the encrypted call format used for encoding calls.
+func (b *ConfidentialCallBuilder) CallFormat() types.CallFormat {
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	return b.callFormat
+}
+
+// SetEpochSource configures the source of the current epoch used to detect key rotation.
+//
+// When configured, the pinned call data public key is cached until the epoch after which it
+// should no longer be used for encrypting new calls and is then transparently refreshed.
+// Otherwise the key is only replaced by explicit refreshes.
+func (b *ConfidentialCallBuilder) SetEpochSource(src EpochSource) *ConfidentialCallBuilder {
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	b.epochSource = src
+	return b
+}
+
+// PinnedKey returns the currently pinned call data public key (if any).
+func (b *ConfidentialCallBuilder) PinnedKey() *PinnedCallDataPublicKey {
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	if b.pinned == nil {
+		return nil
+	}
+	pinned := *b.pinned
+	return &pinned
+}
+
+// Pin pins the given call data public key after verifying it.
+func (b *ConfidentialCallBuilder) Pin(key *PinnedCallDataPublicKey) error {
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	return b.pinLocked(key)
+}
+
+func (b *ConfidentialCallBuilder) pinLocked(key *PinnedCallDataPublicKey) error {
+	if b.pinned != nil {
+		switch {
+		case key.Epoch < b.pinned.Epoch:
+			return fmt.Errorf("callformat: call data public key for epoch %d is older than pinned key for epoch %d", key.Epoch, b.pinned.Epoch)
+		case key.Epoch == b.pinned.Epoch:
+			if !bytes.Equal(key.PublicKey.PublicKey[:], b.pinned.PublicKey.PublicKey[:]) {
+				return fmt.Errorf("callformat: call data public key does not match pinned key for epoch %d", key.Epoch)
+			}
+			return nil
+		default:
+		}
+	}
+
+	if b.verifier != nil {
+		if err := b.verifier(&key.PublicKey, key.Epoch); err != nil {
+			return fmt.Errorf("callformat: failed to verify call data public key: %w", err)
+		}
+	}
+
+	pinned := *key
+	b.pinned = &pinned
+	return nil
+}
+
+// Refresh fetches the current call data public key from the runtime and pins it in case it
+// belongs to a newer epoch than the currently pinned key.
+func (b *ConfidentialCallBuilder) Refresh(ctx context.Context) error {
+	var rsp callDataPublicKeyValidityQueryResponse
+	if err := b.rc.Query(ctx, RoundLatest, methodCallDataPublicKeyValidity, nil, &rsp); err != nil {
+		return fmt.Errorf("callformat: failed to query calldata X25519 public key: %w", err)
+	}
+
+	b.l.Lock()
+	defer b.l.Unlock()
+
+	return b.pinLocked(&PinnedCallDataPublicKey{
+		PublicKey:   rsp.PublicKey,
+		Epoch:       rsp.Epoch,
+		RotateAfter: rsp.RotateAfter,
+		ValidUntil:  rsp.ValidUntil,
+	})
+}
+
+// currentKey returns the pinned call data public key, fetching it first in case no key has been
+// pinned yet or the pinned key has been rotated.
+func (b *ConfidentialCallBuilder) currentKey(ctx context.Context) (*PinnedCallDataPublicKey, error) {
+	b.l.Lock()
+	epochSource := b.epochSource
+	b.l.Unlock()
+
+	pinned := b.PinnedKey()
+	stale := pinned == nil
+	if !stale && epochSource != nil {
+		epoch, err := epochSource(ctx)
+		if err != nil {
+			return nil, fmt.Errorf("callformat: failed to fetch current epoch: %w", err)
+		}
+		stale = epoch > pinned.RotateAfter
+	}
+	if !stale {
+		return pinned, nil
+	}
+
+	if err := b.Refresh(ctx); err != nil {
+		return nil, err
+	}
+	return b.PinnedKey(), nil
+}
+
+// EncodeCall encrypts the given call using the configured call format and a fresh ephemeral key
+// pair.
+//
+// In case no call data public key has been pinned yet or the pinned key has been rotated, it is
+// fetched first. Returns the encoded call and the metadata that needs to be passed to
+// DecodeResult.
+func (b *ConfidentialCallBuilder) EncodeCall(ctx context.Context, call *types.Call) (*types.Call, interface{}, error) {
+	return b.encodeCall(ctx, call, b.CallFormat())
+}
+
+func (b *ConfidentialCallBuilder) encodeCall(ctx context.Context, call *types.Call, format types.CallFormat) (*types.Call, interface{}, error) {
+	pinned, err := b.currentKey(ctx)
+	if err != nil {
+		return nil, nil, err
+	}
+
+	return callformat.EncodeCall(call, format, &callformat.EncodeConfig{
+		PublicKey: &pinned.PublicKey,
+		Epoch:     pinned.Epoch,
+	})
+}
+
+// DecodeResult decrypts the result of a call encoded by EncodeCall.
+func (b *ConfidentialCallBuilder) DecodeResult(result *types.CallResult, meta interface{}) (*types.CallResult, error) {
+	return callformat.DecodeResult(result, meta)
+}
+
+// RetryOnRotation runs the given confidential call and, in case it fails after the pinned call
+// data public key has been rotated, refreshes the key and retries the call once.
+func (b *ConfidentialCallBuilder) RetryOnRotation(ctx context.Context, call func() error) error {
+	pinned := b.PinnedKey()
+	err := call()
+	if err == nil {
+		return nil
+	}
+
+	// The call could have failed because the pinned key has been rotated, retry once with a newer
+	// key if there is one.
+	if pinned == nil || b.Refresh(ctx) != nil {
+		return err
+	}
+	if current := b.PinnedKey(); current.Epoch <= pinned.Epoch {
+		return err
+	}
+	return call()
+}
+
+// ConfidentialViewCall performs the transaction's call as a signed and encrypted read-only call
+// without submitting it and decodes the decrypted result into rsp.
+//
+// This works for calls of any module (e.g. EVM and WASM contract calls), which can therefore rely
+// on the caller's identity while neither the call nor its result are revealed. The call is signed
+// by the given signer using its current nonce, encrypted with the configured confidential call
+// builder (or a new one in case none is configured) and executed via the core.ExecuteReadOnlyTx
+// query. In case the call fails after the call data public key has been rotated, it is retried
+// once with the new key.
+func (tb *TransactionBuilder) ConfidentialViewCall(ctx context.Context, signer signature.Signer, rsp interface{}) error {
+	if tb.tx.Call.Format != types.CallFormatPlain || tb.callMeta != nil {
+		return fmt.Errorf("confidential view calls require a plain call")
+	}
+	spec, err := types.NewSignatureAddressSpecFromPublicKey(signer.Public())
+	if err != nil {
+		return fmt.Errorf("unsupported signer: %w", err)
+	}
+	ccb := tb.ccb
+	if ccb == nil {
+		ccb = NewConfidentialCallBuilder(tb.rc, nil)
+	}
+
+	return ccb.RetryOnRotation(ctx, func() error {
+		return tb.confidentialViewCall(ctx, ccb, spec, signer, rsp)
+	})
+}
+
+func (tb *TransactionBuilder) confidentialViewCall(ctx context.Context, ccb *ConfidentialCallBuilder, spec types.SignatureAddressSpec, signer signature.Signer, rsp interface{}) error {
+	blk, err := tb.rc.GetBlock(ctx, RoundLatest)
+	if err != nil {
+		return fmt.Errorf("failed to fetch latest block: %w", err)
+	}
+	round := blk.Header.Round
+
+	var nonce uint64
+	if err = tb.rc.Query(ctx, round, methodNonce, &nonceQuery{Address: types.NewAddress(spec)}, &nonce); err != nil {
+		return fmt.Errorf("failed to query nonce: %w", err)
+	}
+
+	// Sign a read-only copy of the transaction that is only valid for a limited number of rounds.
+	tx := *tb.tx
+	tx.AuthInfo.SignerInfo = nil
+	tx.AppendAuthSignature(spec, nonce)
+	notAfter := round + DefaultViewCallRoundRange
+	tx.AuthInfo.NotBefore = &round
+	tx.AuthInfo.NotAfter = &notAfter
+	if tx.AuthInfo.Fee.Gas == 0 {
+		tx.AuthInfo.Fee.Gas = DefaultViewCallGasLimit
+	}
+
+	call := tb.tx.Call
+	call.ReadOnly = true
+	encoded, meta, err := ccb.EncodeCall(ctx, &call)
+	if err != nil {
+		return err
+	}
+	tx.Call = *encoded
+
+	rtInfo, err := tb.rc.GetInfo(ctx)
+	if err != nil {
+		return fmt.Errorf("failed to retrieve runtime info: %w", err)
+	}
+	ts := tx.PrepareForSigning()
+	if err = ts.AppendSign(rtInfo.ChainContext, signer); err != nil {
+		return err
+	}
+
+	var out executeReadOnlyTxResponse
+	query := &executeReadOnlyTxQuery{Tx: cbor.Marshal(ts.UnverifiedTransaction())}
+	if err = tb.rc.Query(ctx, round, methodExecuteReadOnlyTx, query, &out); err != nil {
+		return err
+	}
+	result, err := ccb.DecodeResult(&out.Result, meta)
+	if err != nil {
+		return err
+	}
+
+	switch {
+	case result.IsUnknown():
+		return fmt.Errorf("got unknown result: %X", result.Unknown)
+	case result.IsSuccess():
+		if rsp != nil {
+			if err = cbor.Unmarshal(result.Ok, rsp); err != nil {
+				return fmt.Errorf("failed to unmarshal call result: %w", err)
+			}
+		}
+		return nil
+	default:
+		return result.Failed
+	}
+}
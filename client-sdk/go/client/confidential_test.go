@@ -0,0 +1,301 @@
+package client
+
+import (
+	"context"
+	"crypto/rand"
+	"fmt"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/curve25519-voi/primitives/x25519"
+	"github.com/oasisprotocol/deoxysii"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	mraeDeoxysii "github.com/oasisprotocol/oasis-core/go/common/crypto/mrae/deoxysii"
+
+	sdkTesting "github.com/oasisprotocol/oasis-sdk/client-sdk/go/testing"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// callDataKeyTestClient is a runtime client that only answers call data public key queries.
+type callDataKeyTestClient struct {
+	RuntimeClient
+
+	rsp     callDataPublicKeyValidityQueryResponse
+	queries int
+}
+
+func (c *callDataKeyTestClient) Query(_ context.Context, _ uint64, method types.MethodName, _, rsp interface{}) error {
+	if method != methodCallDataPublicKeyValidity {
+		return fmt.Errorf("unexpected query: %s", method)
+	}
+	c.queries++
+	*rsp.(*callDataPublicKeyValidityQueryResponse) = c.rsp
+	return nil
+}
+
+func (c *callDataKeyTestClient) setKey(epoch uint64, keyByte byte) {
+	c.rsp = callDataPublicKeyValidityQueryResponse{
+		Epoch:       epoch,
+		RotateAfter: epoch + 1,
+		ValidUntil:  epoch + 2,
+	}
+	c.rsp.PublicKey.PublicKey[0] = keyByte
+}
+
+func TestConfidentialCallBuilderPinning(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := &callDataKeyTestClient{}
+	rc.setKey(5, 0x01)
+
+	ccb := NewConfidentialCallBuilder(rc, nil)
+	require.Nil(ccb.PinnedKey(), "no key should be pinned initially")
+
+	// The first call should fetch and pin the key.
+	call := &types.Call{Method: "test.Method", Body: []byte{0xa0}}
+	encoded, meta, err := ccb.EncodeCall(ctx, call)
+	require.NoError(err, "EncodeCall")
+	require.NotNil(meta)
+	require.EqualValues(types.CallFormatEncryptedX25519DeoxysII, encoded.Format)
+	require.Empty(encoded.Method, "method should be hidden")
+	require.EqualValues(5, ccb.PinnedKey().Epoch)
+	require.EqualValues(6, ccb.PinnedKey().RotateAfter)
+	require.EqualValues(7, ccb.PinnedKey().ValidUntil)
+	require.Equal(1, rc.queries)
+
+	// Further calls should reuse the pinned key.
+	_, _, err = ccb.EncodeCall(ctx, call)
+	require.NoError(err, "EncodeCall")
+	require.Equal(1, rc.queries, "pinned key should be reused")
+
+	// A different key for the pinned epoch must be rejected.
+	rc.setKey(5, 0x02)
+	require.Error(ccb.Refresh(ctx), "different key for the pinned epoch should be rejected")
+	require.EqualValues(0x01, ccb.PinnedKey().PublicKey.PublicKey[0])
+
+	// A key for an older epoch must be rejected.
+	rc.setKey(4, 0x03)
+	require.Error(ccb.Refresh(ctx), "key for an older epoch should be rejected")
+	require.EqualValues(5, ccb.PinnedKey().Epoch)
+
+	// A key for a newer epoch should replace the pinned key.
+	rc.setKey(6, 0x04)
+	require.NoError(ccb.Refresh(ctx), "Refresh")
+	require.EqualValues(6, ccb.PinnedKey().Epoch)
+	require.EqualValues(0x04, ccb.PinnedKey().PublicKey.PublicKey[0])
+}
+
+func TestConfidentialCallBuilderVerifier(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := &callDataKeyTestClient{}
+	rc.setKey(5, 0x01)
+
+	var verified []uint64
+	ccb := NewConfidentialCallBuilder(rc, func(pk *types.SignedPublicKey, epoch uint64) error {
+		verified = append(verified, epoch)
+		if pk.PublicKey[0] == 0xff {
+			return fmt.Errorf("bad signature")
+		}
+		return nil
+	})
+
+	require.NoError(ccb.Refresh(ctx), "Refresh")
+	require.Equal([]uint64{5}, verified)
+
+	// Keys failing verification must not be pinned.
+	rc.setKey(6, 0xff)
+	require.Error(ccb.Refresh(ctx), "key failing verification should be rejected")
+	require.EqualValues(5, ccb.PinnedKey().Epoch)
+
+	// Refreshing to the already pinned key should not verify it again.
+	rc.setKey(5, 0x01)
+	require.NoError(ccb.Refresh(ctx), "Refresh")
+	require.Equal([]uint64{5, 6}, verified)
+}
+
+func TestConfidentialCallBuilderRotation(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	keyForEpoch := func(epoch uint64) *callDataPublicKeyValidityQueryResponse {
+		var rsp callDataPublicKeyValidityQueryResponse
+		rsp.PublicKey.PublicKey[0] = 0x40 + byte(epoch)
+		rsp.Epoch = epoch
+		rsp.RotateAfter = epoch + 1
+		rsp.ValidUntil = epoch + 2
+		return &rsp
+	}
+
+	rc := newTestRuntimeClient()
+	rc.queries[methodCallDataPublicKeyValidity] = keyForEpoch(1)
+
+	var epoch uint64 = 1
+	ccb := NewConfidentialCallBuilder(rc, nil).SetEpochSource(func(context.Context) (uint64, error) {
+		return epoch, nil
+	})
+	call := &types.Call{Method: "test.Method"}
+
+	_, _, err := ccb.EncodeCall(ctx, call)
+	require.NoError(err, "EncodeCall")
+	require.EqualValues(1, ccb.PinnedKey().Epoch)
+
+	// The pinned key should be cached until it is rotated.
+	rc.queries[methodCallDataPublicKeyValidity] = keyForEpoch(2)
+	epoch = 2
+	_, _, err = ccb.EncodeCall(ctx, call)
+	require.NoError(err, "EncodeCall")
+	require.EqualValues(1, ccb.PinnedKey().Epoch, "key should be cached until rotation")
+
+	// After rotation, a new key should be fetched transparently.
+	epoch = 3
+	_, _, err = ccb.EncodeCall(ctx, call)
+	require.NoError(err, "EncodeCall")
+	require.EqualValues(2, ccb.PinnedKey().Epoch, "rotated key should be refreshed")
+}
+
+// viewCallTestClient is a runtime client that executes read-only transactions by opening the
+// encrypted call with the runtime call data key and returning the sealed call body as the result.
+type viewCallTestClient struct {
+	*testRuntimeClient
+
+	// keys are the runtime call data private keys by epoch.
+	keys map[uint64]*x25519.PrivateKey
+	// minEpoch is the oldest epoch whose key is still accepted.
+	minEpoch uint64
+	// executed are the executed read-only transactions.
+	executed []*types.Transaction
+}
+
+func (c *viewCallTestClient) setKey(epoch uint64) {
+	pk, sk, err := x25519.GenerateKey(rand.Reader)
+	if err != nil {
+		panic(err)
+	}
+	c.keys[epoch] = sk
+	c.queries[methodCallDataPublicKeyValidity] = &callDataPublicKeyValidityQueryResponse{
+		PublicKey:   types.SignedPublicKey{PublicKey: *pk},
+		Epoch:       epoch,
+		RotateAfter: epoch + 1,
+		ValidUntil:  epoch + 2,
+	}
+}
+
+func (c *viewCallTestClient) Query(ctx context.Context, round uint64, method types.MethodName, args, rsp interface{}) error {
+	if method != methodExecuteReadOnlyTx {
+		return c.testRuntimeClient.Query(ctx, round, method, args, rsp)
+	}
+
+	var (
+		ut       types.UnverifiedTransaction
+		tx       types.Transaction
+		envelope types.CallEnvelopeX25519DeoxysII
+	)
+	if err := cbor.Unmarshal(args.(*executeReadOnlyTxQuery).Tx, &ut); err != nil {
+		return err
+	}
+	if err := cbor.Unmarshal(ut.Body, &tx); err != nil {
+		return err
+	}
+	if err := cbor.Unmarshal(tx.Call.Body, &envelope); err != nil {
+		return err
+	}
+	c.executed = append(c.executed, &tx)
+	if envelope.Epoch < c.minEpoch {
+		return fmt.Errorf("call data key for epoch %d has expired", envelope.Epoch)
+	}
+
+	sk := c.keys[envelope.Epoch]
+	pt, err := mraeDeoxysii.Box.Open(nil, envelope.Nonce[:], envelope.Data, nil, &envelope.Pk, sk)
+	if err != nil {
+		return err
+	}
+	var call types.Call
+	if err = cbor.Unmarshal(pt, &call); err != nil {
+		return err
+	}
+
+	var nonce [deoxysii.NonceSize]byte
+	result := cbor.Marshal(&types.CallResult{Ok: call.Body})
+	*rsp.(*executeReadOnlyTxResponse) = executeReadOnlyTxResponse{
+		Result: types.CallResult{Ok: cbor.Marshal(&types.ResultEnvelopeX25519DeoxysII{
+			Nonce: nonce,
+			Data:  mraeDeoxysii.Box.Seal(nil, nonce[:], result, nil, &envelope.Pk, sk),
+		})},
+	}
+	return nil
+}
+
+func TestConfidentialViewCall(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := &viewCallTestClient{
+		testRuntimeClient: newTestRuntimeClient(),
+		keys:              make(map[uint64]*x25519.PrivateKey),
+	}
+	rc.latestRound = 10
+	rc.queries[methodNonce] = uint64(7)
+	rc.setKey(1)
+
+	ccb := NewConfidentialCallBuilder(rc, nil)
+	tb := NewTransactionBuilder(rc, "test.View", "hello").SetConfidentialCallBuilder(ccb)
+
+	var rsp string
+	require.NoError(tb.ConfidentialViewCall(ctx, sdkTesting.Alice.Signer, &rsp), "ConfidentialViewCall")
+	require.Equal("hello", rsp, "decrypted result should be returned")
+
+	require.Len(rc.executed, 1)
+	tx := rc.executed[0]
+	require.EqualValues(types.CallFormatEncryptedX25519DeoxysII, tx.Call.Format)
+	require.Empty(tx.Call.Method, "method should be hidden")
+	require.True(tx.Call.ReadOnly, "view calls should be read-only")
+	require.Len(tx.AuthInfo.SignerInfo, 1)
+	require.EqualValues(sdkTesting.Alice.SigSpec, *tx.AuthInfo.SignerInfo[0].AddressSpec.Signature)
+	require.EqualValues(7, tx.AuthInfo.SignerInfo[0].Nonce)
+	require.EqualValues(10, *tx.AuthInfo.NotBefore)
+	require.EqualValues(10+DefaultViewCallRoundRange, *tx.AuthInfo.NotAfter)
+	require.EqualValues(DefaultViewCallGasLimit, tx.AuthInfo.Fee.Gas)
+
+	// The builder's transaction should be left untouched.
+	require.EqualValues(types.CallFormatPlain, tb.GetTransaction().Call.Format)
+	require.Empty(tb.GetTransaction().AuthInfo.SignerInfo)
+
+	// Calls failing after the key has been rotated should be retried with the new key.
+	rc.setKey(2)
+	rc.minEpoch = 2
+	rsp = ""
+	require.NoError(tb.ConfidentialViewCall(ctx, sdkTesting.Alice.Signer, &rsp), "ConfidentialViewCall")
+	require.Equal("hello", rsp)
+	require.Len(rc.executed, 3, "call should be retried once")
+	require.EqualValues(2, ccb.PinnedKey().Epoch)
+
+	// Failures without key rotation should not be retried.
+	rc.minEpoch = 3
+	require.Error(tb.ConfidentialViewCall(ctx, sdkTesting.Alice.Signer, &rsp), "ConfidentialViewCall should fail")
+	require.Len(rc.executed, 4, "call should not be retried without a newer key")
+}
+
+func TestConfidentialCallBuilderCallFormat(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := &callDataKeyTestClient{}
+	rc.setKey(5, 0x09)
+
+	ccb := NewConfidentialCallBuilder(rc, nil)
+	require.EqualValues(types.CallFormatEncryptedX25519DeoxysII, ccb.CallFormat())
+	require.Error(ccb.SetCallFormat(types.CallFormatPlain), "plain call format should be rejected")
+
+	require.NoError(ccb.SetCallFormat(types.CallFormatEncryptedX25519XChaCha20Poly1305), "SetCallFormat")
+	encoded, _, err := ccb.EncodeCall(ctx, &types.Call{Method: "test.Method"})
+	require.NoError(err, "EncodeCall")
+	require.EqualValues(types.CallFormatEncryptedX25519XChaCha20Poly1305, encoded.Format)
+
+	var envelope types.CallEnvelopeX25519XChaCha20Poly1305
+	require.NoError(cbor.Unmarshal(encoded.Body, &envelope), "call envelope should use the configured format")
+	require.EqualValues(5, envelope.Epoch)
+}
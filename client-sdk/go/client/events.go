@@ -0,0 +1,219 @@
+package client
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"time"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// DefaultEventSubscriptionRetryInterval is the default interval between attempts to recover a
+// failed event subscription.
+const DefaultEventSubscriptionRetryInterval = time.Second
+
+// EventFilter selects the events delivered by an event subscription.
+//
+// An event matches the filter when it matches all of the configured criteria. Empty criteria
+// match all events.
+type EventFilter struct {
+	// Module is the name of the module that emitted the event.
+	Module string
+	// Codes are the accepted event codes.
+	Codes []uint32
+	// Keys are the accepted event tag keys (see types.NewEventKey).
+	Keys []types.EventKey
+}
+
+// Matches checks whether the given event matches the filter.
+func (f *EventFilter) Matches(ev *types.Event) bool {
+	if f == nil {
+		return true
+	}
+	if f.Module != "" && ev.Module != f.Module {
+		return false
+	}
+	if len(f.Codes) > 0 {
+		var found bool
+		for _, code := range f.Codes {
+			if ev.Code == code {
+				found = true
+				break
+			}
+		}
+		if !found {
+			return false
+		}
+	}
+	if len(f.Keys) > 0 {
+		key := ev.Key()
+		var found bool
+		for _, k := range f.Keys {
+			if key.IsEqual(k) {
+				found = true
+				break
+			}
+		}
+		if !found {
+			return false
+		}
+	}
+	return true
+}
+
+// EventSubscriptionOptions configure an event subscription.
+type EventSubscriptionOptions struct {
+	// StartRound is the first round for which events are delivered. Zero means that delivery
+	// starts at the first block observed after subscribing.
+	StartRound uint64
+	// Filter selects the delivered events. Nil means that all events are delivered.
+	Filter *EventFilter
+	// Decoders are used to decode events into typed module events (e.g. accounts.NewV1(rc)).
+	Decoders []EventDecoder
+	// IncludeUndecoded specifies whether events that could not be decoded by any of the decoders
+	// are delivered as raw *types.Event.
+	IncludeUndecoded bool
+	// RetryInterval is the interval between attempts to recover a failed subscription. Zero means
+	// DefaultEventSubscriptionRetryInterval.
+	RetryInterval time.Duration
+}
+
+// SubscribeEvents follows new runtime blocks and delivers filtered and decoded events.
+//
+// Events are delivered for every round in order, also for rounds without any matching events, so
+// that indexers may persist the last processed round and later resume from the following round
+// via StartRound. In case the block subscription or fetching of events fails, the error is reported
+// on the returned error channel and the subscription is reestablished after the retry interval,
+// catching up on any rounds missed in the meantime. Errors that cannot be resolved by retrying
+// (e.g. events that fail to decode) are reported and terminate the subscription.
+//
+// Callers must receive from both channels. Both channels are closed once the subscription
+// terminates or the context is cancelled.
+func SubscribeEvents(ctx context.Context, rc RuntimeClient, opts *EventSubscriptionOptions) (<-chan *BlockEvents, <-chan error) {
+	if opts == nil {
+		opts = &EventSubscriptionOptions{}
+	}
+	retryInterval := opts.RetryInterval
+	if retryInterval == 0 {
+		retryInterval = DefaultEventSubscriptionRetryInterval
+	}
+
+	ch := make(chan *BlockEvents)
+	errCh := make(chan error)
+	go func() {
+		defer close(errCh)
+		defer close(ch)
+
+		next := opts.StartRound
+		haveNext := next != 0
+
+		// reportError reports the given error and returns false in case the context has been
+		// cancelled.
+		reportError := func(err error) bool {
+			select {
+			case errCh <- err:
+				return true
+			case <-ctx.Done():
+				return false
+			}
+		}
+
+		// followBlocks delivers events until the block subscription fails and returns false in
+		// case the subscription should not be retried.
+		followBlocks := func() bool {
+			blkCh, blkSub, err := rc.WatchBlocks(ctx)
+			if err != nil {
+				return reportError(fmt.Errorf("failed to subscribe to blocks: %w", err))
+			}
+			defer blkSub.Close()
+
+			for {
+				select {
+				case <-ctx.Done():
+					return false
+				case blk, ok := <-blkCh:
+					if !ok {
+						return reportError(errors.New("block subscription closed"))
+					}
+
+					round := blk.Block.Header.Round
+					if !haveNext {
+						next = round
+						haveNext = true
+					}
+					for ; next <= round; next++ {
+						rawEvs, err := rc.GetEventsRaw(ctx, next)
+						if err != nil {
+							return reportError(fmt.Errorf("failed to fetch events for round %d: %w", next, err))
+						}
+
+						// Decoding is deterministic, so decoding errors are not retried.
+						events, err := filterAndDecodeEvents(next, rawEvs, opts)
+						if err != nil {
+							reportError(err)
+							return false
+						}
+
+						select {
+						case ch <- events:
+						case <-ctx.Done():
+							return false
+						}
+					}
+				}
+			}
+		}
+
+		for followBlocks() {
+			select {
+			case <-ctx.Done():
+				return
+			case <-time.After(retryInterval):
+			}
+		}
+	}()
+
+	return ch, errCh
+}
+
+func filterAndDecodeEvents(round uint64, rawEvs []*types.Event, opts *EventSubscriptionOptions) (*BlockEvents, error) {
+	filtered := make([]*types.Event, 0, len(rawEvs))
+	for _, ev := range rawEvs {
+		if opts.Filter.Matches(ev) {
+			filtered = append(filtered, ev)
+		}
+	}
+
+	events, err := decodeEvents(filtered, opts.Decoders, opts.IncludeUndecoded)
+	if err != nil {
+		return nil, fmt.Errorf("round %d: %w", round, err)
+	}
+	return &BlockEvents{
+		Round:  round,
+		Events: events,
+	}, nil
+}
+
+// decodeEvents decodes the given events with the provided decoders. Each event is decoded by the
+// first decoder that recognizes it.
+func decodeEvents(rawEvs []*types.Event, decoders []EventDecoder, includeUndecoded bool) ([]DecodedEvent, error) {
+	evs := make([]DecodedEvent, 0)
+OUTER:
+	for _, ev := range rawEvs {
+		for _, decoder := range decoders {
+			decoded, err := decoder.DecodeEvent(ev)
+			if err != nil {
+				return nil, fmt.Errorf("failed to decode event: %w", err)
+			}
+			if decoded != nil {
+				evs = append(evs, decoded...)
+				continue OUTER
+			}
+		}
+		if includeUndecoded {
+			evs = append(evs, ev)
+		}
+	}
+	return evs, nil
+}
@@ -0,0 +1,116 @@
+package client
+
+import (
+	"context"
+	"fmt"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+type testEventDecoder struct{}
+
+func (testEventDecoder) DecodeEvent(ev *types.Event) ([]DecodedEvent, error) {
+	if ev.Code != 1 {
+		return nil, nil
+	}
+	return []DecodedEvent{string(ev.Value)}, nil
+}
+
+func TestEventFilter(t *testing.T) {
+	require := require.New(t)
+
+	ev := &types.Event{Module: "accounts", Code: 1}
+	require.True((*EventFilter)(nil).Matches(ev))
+	require.True((&EventFilter{}).Matches(ev))
+	require.True((&EventFilter{Module: "accounts", Codes: []uint32{2, 1}}).Matches(ev))
+	require.False((&EventFilter{Module: "evm"}).Matches(ev))
+	require.False((&EventFilter{Codes: []uint32{2}}).Matches(ev))
+	require.True((&EventFilter{Keys: []types.EventKey{types.NewEventKey("accounts", 1)}}).Matches(ev))
+	require.False((&EventFilter{Keys: []types.EventKey{types.NewEventKey("accounts", 2)}}).Matches(ev))
+}
+
+func TestSubscribeEvents(t *testing.T) {
+	require := require.New(t)
+
+	rc := newTestRuntimeClient()
+	for round := uint64(1); round <= 5; round++ {
+		rc.events[round] = []*types.Event{
+			{Module: "accounts", Code: 1, Value: []byte{byte('a' + round)}},
+			{Module: "accounts", Code: 2},
+			{Module: "core", Code: 1},
+		}
+	}
+	// The first subscription fails after round 3, round 4 is missed while reconnecting.
+	rc.watchBlocks(true, 3)
+	rc.watchBlocks(false, 5)
+
+	ctx, cancel := context.WithCancel(context.Background())
+	defer cancel()
+
+	ch, errCh := SubscribeEvents(ctx, rc, &EventSubscriptionOptions{
+		StartRound:       2,
+		Filter:           &EventFilter{Module: "accounts"},
+		Decoders:         []EventDecoder{testEventDecoder{}},
+		IncludeUndecoded: true,
+		RetryInterval:    time.Millisecond,
+	})
+	var errs int
+	for round := uint64(2); round <= 5; {
+		select {
+		case blk := <-ch:
+			require.EqualValues(round, blk.Round, "rounds should be delivered in order")
+			require.Len(blk.Events, 2)
+			require.EqualValues(string([]byte{byte('a' + round)}), blk.Events[0])
+			require.EqualValues(&types.Event{Module: "accounts", Code: 2}, blk.Events[1])
+			round++
+		case err := <-errCh:
+			require.Error(err)
+			errs++
+		case <-time.After(time.Second):
+			t.Fatalf("timed out waiting for round %d", round)
+		}
+	}
+	require.Equal(1, errs, "failed block subscription should be reported")
+}
+
+type failingEventDecoder struct{}
+
+func (failingEventDecoder) DecodeEvent(*types.Event) ([]DecodedEvent, error) {
+	return nil, fmt.Errorf("malformed event")
+}
+
+func TestSubscribeEventsDecodeError(t *testing.T) {
+	require := require.New(t)
+
+	rc := newTestRuntimeClient()
+	rc.events[1] = []*types.Event{{Module: "accounts", Code: 1}}
+	rc.watchBlocks(false, 1)
+
+	ctx, cancel := context.WithCancel(context.Background())
+	defer cancel()
+
+	ch, errCh := SubscribeEvents(ctx, rc, &EventSubscriptionOptions{
+		Decoders:      []EventDecoder{failingEventDecoder{}},
+		RetryInterval: time.Millisecond,
+	})
+	select {
+	case err := <-errCh:
+		require.ErrorContains(err, "malformed event")
+	case <-time.After(time.Second):
+		t.Fatalf("timed out waiting for the decoding error")
+	}
+
+	// Decoding errors should terminate the subscription instead of being retried.
+	select {
+	case _, ok := <-ch:
+		require.False(ok, "event channel should be closed")
+	case <-time.After(time.Second):
+		t.Fatalf("timed out waiting for the subscription to terminate")
+	}
+	_, ok := <-errCh
+	require.False(ok, "error channel should be closed")
+}
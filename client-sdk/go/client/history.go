@@ -0,0 +1,178 @@
+package client
+
+import (
+	"context"
+	"fmt"
+	"reflect"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	"github.com/oasisprotocol/oasis-core/go/common/crypto/hash"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// DefaultTransactionPageLimit is the default maximum number of transactions in a page.
+const DefaultTransactionPageLimit = 100
+
+// DefaultTransactionPageRoundLimit is the default maximum number of rounds scanned for a page.
+const DefaultTransactionPageRoundLimit = 1000
+
+// DecodedTransaction is a historic transaction with its envelope, call body and result decoded.
+type DecodedTransaction struct {
+	// Round is the round in which the transaction was executed.
+	Round uint64
+	// Index is the index of the transaction within the block.
+	Index int
+	// Hash is the hash of the encoded transaction.
+	Hash hash.Hash
+
+	// Raw is the transaction as included in the block.
+	Raw *types.UnverifiedTransaction
+	// Tx is the decoded transaction. It is nil in case the transaction is malformed. Note that
+	// signatures are not verified.
+	Tx *types.Transaction
+	// Body is the decoded call body. In case the body type of the method has been registered
+	// (e.g. by importing the corresponding module package), this is a pointer to the registered
+	// type. It is nil for malformed transactions, encrypted calls and unknown methods.
+	Body interface{}
+
+	// Result is the call result.
+	Result types.CallResult
+	// Events are the events emitted by the transaction.
+	Events []*types.Event
+}
+
+// DecodeResult decodes the successful call result into rsp, returning the failure otherwise.
+//
+// Results of encrypted calls cannot be decoded without the caller's keys.
+func (dt *DecodedTransaction) DecodeResult(rsp interface{}) error {
+	switch {
+	case dt.Result.IsUnknown():
+		return fmt.Errorf("got unknown result: %X", dt.Result.Unknown)
+	case dt.Result.IsSuccess():
+		if rsp != nil {
+			if err := cbor.Unmarshal(dt.Result.Ok, rsp); err != nil {
+				return fmt.Errorf("failed to unmarshal call result: %w", err)
+			}
+		}
+		return nil
+	default:
+		return dt.Result.Failed
+	}
+}
+
+// DecodeTransaction decodes the given transaction and its results.
+func DecodeTransaction(round uint64, index int, txr *TransactionWithResults) *DecodedTransaction {
+	dt := &DecodedTransaction{
+		Round:  round,
+		Index:  index,
+		Hash:   txr.Tx.Hash(),
+		Raw:    &txr.Tx,
+		Result: txr.Result,
+		Events: txr.Events,
+	}
+
+	var tx types.Transaction
+	if err := cbor.Unmarshal(txr.Tx.Body, &tx); err != nil {
+		return dt
+	}
+	dt.Tx = &tx
+
+	if tx.Call.Format != types.CallFormatPlain {
+		return dt
+	}
+	if bodyType := tx.Call.Method.BodyType(); bodyType != nil {
+		body := reflect.New(reflect.TypeOf(bodyType)).Interface()
+		if err := cbor.Unmarshal(tx.Call.Body, body); err == nil {
+			dt.Body = body
+		}
+	}
+	return dt
+}
+
+// TransactionCursor is the position of a transaction within the chain.
+type TransactionCursor struct {
+	// Round is the round of the block containing the transaction.
+	Round uint64
+	// Index is the index of the transaction within the block.
+	Index int
+}
+
+// TransactionQuery selects historic transactions.
+type TransactionQuery struct {
+	// StartRound is the first round to fetch transactions from.
+	StartRound uint64
+	// EndRound is the last round (inclusive) to fetch transactions from. RoundLatest refers to
+	// the latest round at the time of the query.
+	EndRound uint64
+	// Cursor is the position at which to continue fetching, as returned in TransactionPage. It
+	// takes precedence over StartRound.
+	Cursor *TransactionCursor
+	// Limit is the maximum number of transactions in the page. Zero means
+	// DefaultTransactionPageLimit.
+	Limit int
+	// RoundLimit is the maximum number of rounds scanned for the page, so that long runs of
+	// rounds without any transactions do not result in an unbounded number of requests. Zero
+	// means DefaultTransactionPageRoundLimit.
+	RoundLimit uint64
+}
+
+// TransactionPage is a page of historic transactions.
+type TransactionPage struct {
+	// Transactions are the decoded transactions in chain order.
+	Transactions []*DecodedTransaction
+	// Next is the cursor for fetching the next page. It is nil when there are no more
+	// transactions in the queried range. Note that a page may contain fewer transactions than
+	// the limit (or none at all) in case the round limit has been reached.
+	Next *TransactionCursor
+}
+
+// FetchTransactions fetches and decodes a page of transactions in the queried round range.
+func FetchTransactions(ctx context.Context, rc RuntimeClient, query *TransactionQuery) (*TransactionPage, error) {
+	limit := query.Limit
+	if limit <= 0 {
+		limit = DefaultTransactionPageLimit
+	}
+	roundLimit := query.RoundLimit
+	if roundLimit == 0 {
+		roundLimit = DefaultTransactionPageRoundLimit
+	}
+	endRound := query.EndRound
+	if endRound == RoundLatest {
+		blk, err := rc.GetBlock(ctx, RoundLatest)
+		if err != nil {
+			return nil, fmt.Errorf("failed to fetch latest block: %w", err)
+		}
+		endRound = blk.Header.Round
+	}
+	cursor := TransactionCursor{Round: query.StartRound}
+	if query.Cursor != nil {
+		cursor = *query.Cursor
+	}
+
+	var page TransactionPage
+	for round := cursor.Round; round <= endRound; round++ {
+		if round-cursor.Round == roundLimit {
+			page.Next = &TransactionCursor{Round: round}
+			return &page, nil
+		}
+
+		txs, err := rc.GetTransactionsWithResults(ctx, round)
+		if err != nil {
+			return nil, fmt.Errorf("failed to fetch transactions for round %d: %w", round, err)
+		}
+
+		index := 0
+		if round == cursor.Round {
+			index = cursor.Index
+		}
+		for ; index < len(txs); index++ {
+			if len(page.Transactions) == limit {
+				page.Next = &TransactionCursor{Round: round, Index: index}
+				return &page, nil
+			}
+			page.Transactions = append(page.Transactions, DecodeTransaction(round, index, txs[index]))
+		}
+	}
+	return &page, nil
+}
@@ -0,0 +1,97 @@
+package client
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+type testCallBody struct {
+	Value uint64 `json:"value"`
+}
+
+var methodTestCall = types.NewMethodName("test.HistoryCall", testCallBody{})
+
+func newTestTransactionWithResults(method types.MethodName, body interface{}, result types.CallResult) *TransactionWithResults {
+	tx := types.NewTransaction(nil, method, body)
+	return &TransactionWithResults{
+		Tx:     types.UnverifiedTransaction{Body: cbor.Marshal(tx)},
+		Result: result,
+	}
+}
+
+func TestDecodeTransaction(t *testing.T) {
+	require := require.New(t)
+
+	dt := DecodeTransaction(1, 0, newTestTransactionWithResults(methodTestCall, testCallBody{Value: 42}, types.CallResult{Ok: cbor.Marshal(uint64(7))}))
+	require.NotNil(dt.Tx)
+	require.EqualValues(methodTestCall, dt.Tx.Call.Method)
+	require.EqualValues(&testCallBody{Value: 42}, dt.Body, "registered body types should be decoded")
+	var rsp uint64
+	require.NoError(dt.DecodeResult(&rsp))
+	require.EqualValues(7, rsp)
+
+	dt = DecodeTransaction(1, 1, newTestTransactionWithResults("test.Unknown", nil, types.CallResult{Failed: &types.FailedCallResult{Module: "test", Code: 1}}))
+	require.NotNil(dt.Tx)
+	require.Nil(dt.Body, "bodies of unknown methods should not be decoded")
+	require.Error(dt.DecodeResult(nil))
+
+	dt = DecodeTransaction(1, 2, &TransactionWithResults{Tx: types.UnverifiedTransaction{Body: []byte("garbage")}})
+	require.Nil(dt.Tx, "malformed transactions should not be decoded")
+}
+
+func TestFetchTransactions(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := newTestRuntimeClient()
+	rc.latestRound = 4
+	var value uint64
+	for _, round := range []uint64{1, 2, 4} {
+		for i := 0; i < 2; i++ {
+			rc.txs[round] = append(rc.txs[round], newTestTransactionWithResults(methodTestCall, testCallBody{Value: value}, types.CallResult{}))
+			value++
+		}
+	}
+
+	var fetched []uint64
+	var pages int
+	query := &TransactionQuery{StartRound: 1, EndRound: RoundLatest, Limit: 3}
+	for {
+		page, err := FetchTransactions(ctx, rc, query)
+		require.NoError(err)
+		pages++
+		for _, tx := range page.Transactions {
+			fetched = append(fetched, tx.Body.(*testCallBody).Value)
+		}
+		if page.Next == nil {
+			break
+		}
+		query.Cursor = page.Next
+	}
+	require.EqualValues([]uint64{0, 1, 2, 3, 4, 5}, fetched, "all transactions should be fetched in order")
+	require.EqualValues(2, pages)
+
+	page, err := FetchTransactions(ctx, rc, &TransactionQuery{StartRound: 2, EndRound: 3})
+	require.NoError(err)
+	require.Len(page.Transactions, 2)
+	require.EqualValues(2, page.Transactions[1].Round)
+	require.EqualValues(1, page.Transactions[1].Index)
+	require.Nil(page.Next)
+
+	// Scanning should stop at the round limit, also for rounds without any transactions.
+	page, err = FetchTransactions(ctx, rc, &TransactionQuery{StartRound: 2, EndRound: RoundLatest, RoundLimit: 2})
+	require.NoError(err)
+	require.Len(page.Transactions, 2)
+	require.EqualValues(&TransactionCursor{Round: 4}, page.Next)
+
+	page, err = FetchTransactions(ctx, rc, &TransactionQuery{StartRound: 3, EndRound: RoundLatest, RoundLimit: 1})
+	require.NoError(err)
+	require.Empty(page.Transactions)
+	require.EqualValues(&TransactionCursor{Round: 4}, page.Next)
+}
@@ -0,0 +1,122 @@
+package client
+
+import (
+	"context"
+	"fmt"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// OfflineSigningParams are the parameters required to sign a transaction without access to the
+// network. All of them would otherwise be queried from a node.
+type OfflineSigningParams struct {
+	// RuntimeID is the identifier of the runtime the transaction is destined for.
+	RuntimeID common.Namespace
+	// ConsensusChainContext is the consensus layer chain domain separation context.
+	ConsensusChainContext string
+	// Nonce is the signer's account nonce. It is only used when the transaction does not specify
+	// any signers yet.
+	Nonce uint64
+	// Fee is the transaction fee. Nil means that the fee already configured in the transaction
+	// is used.
+	Fee *types.Fee
+}
+
+// ChainContext returns the chain domain separation context used for signing.
+func (p *OfflineSigningParams) ChainContext() signature.Context {
+	return &signature.RichContext{
+		RuntimeID:    p.RuntimeID,
+		ChainContext: p.ConsensusChainContext,
+		Base:         types.SignatureContextBase,
+	}
+}
+
+// SignOffline signs the transaction with the given signers without any network access and
+// returns the serialized signed transaction which can later be broadcast via Broadcast.
+//
+// In case the transaction does not specify any signers, exactly one signer must be given and it
+// is added with the configured nonce. Otherwise the signers must match the transaction's signer
+// information.
+func SignOffline(tx *types.Transaction, params *OfflineSigningParams, signers ...signature.Signer) ([]byte, error) {
+	if params.ConsensusChainContext == "" {
+		return nil, fmt.Errorf("missing consensus chain context")
+	}
+	if params.Fee != nil {
+		tx.AuthInfo.Fee = *params.Fee
+	}
+	if len(tx.AuthInfo.SignerInfo) == 0 {
+		if len(signers) != 1 {
+			return nil, fmt.Errorf("exactly one signer required for transactions without signer information")
+		}
+		spec, err := types.NewSignatureAddressSpecFromPublicKey(signers[0].Public())
+		if err != nil {
+			return nil, err
+		}
+		tx.AppendAuthSignature(spec, params.Nonce)
+	}
+	if err := tx.ValidateBasic(); err != nil {
+		return nil, fmt.Errorf("malformed transaction: %w", err)
+	}
+
+	chainCtx := params.ChainContext()
+	ts := tx.PrepareForSigning()
+	for _, signer := range signers {
+		if err := ts.AppendSign(chainCtx, signer); err != nil {
+			return nil, fmt.Errorf("failed to sign transaction: %w", err)
+		}
+	}
+	ut := ts.UnverifiedTransaction()
+	if _, err := ut.Verify(chainCtx); err != nil {
+		return nil, fmt.Errorf("signed transaction failed verification: %w", err)
+	}
+	return cbor.Marshal(ut), nil
+}
+
+// DecodeSignedTransaction decodes a signed transaction produced by SignOffline and verifies its
+// signatures against the given chain domain separation context.
+func DecodeSignedTransaction(chainCtx signature.Context, blob []byte) (*types.UnverifiedTransaction, *types.Transaction, error) {
+	var ut types.UnverifiedTransaction
+	if err := cbor.Unmarshal(blob, &ut); err != nil {
+		return nil, nil, fmt.Errorf("malformed signed transaction: %w", err)
+	}
+	tx, err := ut.Verify(chainCtx)
+	if err != nil {
+		return nil, nil, fmt.Errorf("failed to verify signed transaction: %w", err)
+	}
+	return &ut, tx, nil
+}
+
+// Broadcast submits a signed transaction produced by SignOffline and waits for its execution
+// results.
+//
+// The transaction is first verified against the runtime's chain domain separation context so
+// that transactions signed for a different network are rejected before submission.
+func Broadcast(ctx context.Context, rc RuntimeClient, blob []byte) (*SubmitTxRawMeta, error) {
+	rtInfo, err := rc.GetInfo(ctx)
+	if err != nil {
+		return nil, fmt.Errorf("failed to retrieve runtime info: %w", err)
+	}
+	ut, _, err := DecodeSignedTransaction(rtInfo.ChainContext, blob)
+	if err != nil {
+		return nil, err
+	}
+	return rc.SubmitTxRawMeta(ctx, ut)
+}
+
+// BroadcastNoWait submits a signed transaction produced by SignOffline but does not wait for its
+// execution.
+func BroadcastNoWait(ctx context.Context, rc RuntimeClient, blob []byte) error {
+	rtInfo, err := rc.GetInfo(ctx)
+	if err != nil {
+		return fmt.Errorf("failed to retrieve runtime info: %w", err)
+	}
+	ut, _, err := DecodeSignedTransaction(rtInfo.ChainContext, blob)
+	if err != nil {
+		return err
+	}
+	return rc.SubmitTxNoWait(ctx, ut)
+}
@@ -0,0 +1,55 @@
+package client
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/common/quantity"
+
+	sdkTesting "github.com/oasisprotocol/oasis-sdk/client-sdk/go/testing"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+func TestSignOfflineAndBroadcast(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	params := &OfflineSigningParams{
+		RuntimeID:             common.Namespace{},
+		ConsensusChainContext: "test",
+		Nonce:                 5,
+		Fee: &types.Fee{
+			Amount: types.NewBaseUnits(*quantity.NewFromUint64(100), types.NativeDenomination),
+			Gas:    1000,
+		},
+	}
+	blob, err := SignOffline(types.NewTransaction(nil, "hello.World", nil), params, sdkTesting.Alice.Signer)
+	require.NoError(err, "SignOffline")
+
+	_, tx, err := DecodeSignedTransaction(params.ChainContext(), blob)
+	require.NoError(err, "DecodeSignedTransaction")
+	require.EqualValues(5, tx.AuthInfo.SignerInfo[0].Nonce)
+	require.EqualValues(1000, tx.AuthInfo.Fee.Gas)
+
+	rc := newTestRuntimeClient()
+	meta, err := Broadcast(ctx, rc, blob)
+	require.NoError(err, "Broadcast")
+	require.EqualValues(42, meta.Round)
+	require.Len(rc.submitted, 1)
+
+	// Transactions signed for a different network should be rejected before submission.
+	params.ConsensusChainContext = "other"
+	blob, err = SignOffline(types.NewTransaction(nil, "hello.World", nil), params, sdkTesting.Alice.Signer)
+	require.NoError(err, "SignOffline")
+	_, err = Broadcast(ctx, rc, blob)
+	require.Error(err, "Broadcast should reject transactions for other networks")
+	require.Len(rc.submitted, 1)
+
+	_, err = SignOffline(types.NewTransaction(nil, "hello.World", nil), params, sdkTesting.Alice.Signer, sdkTesting.Bob.Signer)
+	require.Error(err, "SignOffline should require signer information for multiple signers")
+	_, err = Broadcast(ctx, rc, []byte("garbage"))
+	require.Error(err, "Broadcast should reject malformed transactions")
+}
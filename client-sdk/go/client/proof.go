@@ -0,0 +1,134 @@
+package client
+
+import (
+	"bytes"
+	"context"
+	"fmt"
+
+	"github.com/oasisprotocol/oasis-core/go/roothash/api/block"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs/node"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs/syncer"
+)
+
+// StateProof is a proof of the value (or absence) of a runtime state key in a given state root.
+type StateProof struct {
+	// Root is the state root the proof is relative to.
+	Root node.Root `json:"root"`
+	// Key is the full storage key.
+	Key []byte `json:"key"`
+	// Proof is the MKVS proof for the key.
+	Proof syncer.Proof `json:"proof"`
+}
+
+// ModuleStorageKey returns the full storage key of a key in the storage of the given module.
+//
+// Note that keys in confidential module stores are encrypted and cannot be derived this way.
+func ModuleStorageKey(module string, key []byte) []byte {
+	return append([]byte(module), key...)
+}
+
+// StateRoot returns the runtime state root committed to in the given block.
+func StateRoot(blk *block.Block) node.Root {
+	return node.Root{
+		Namespace: blk.Header.Namespace,
+		Version:   blk.Header.Round,
+		Type:      node.RootTypeState,
+		Hash:      blk.Header.StateRoot,
+	}
+}
+
+// StateProofProvider is a runtime client that can fetch state proofs.
+//
+// Runtime clients returned by New implement this interface.
+type StateProofProvider interface {
+	// GetStateProof fetches a proof of the value (or absence) of the given runtime state key at
+	// the given round.
+	//
+	// The node is not trusted, the returned proof must be verified against a trusted state root
+	// using VerifyStateProof before relying on it.
+	GetStateProof(ctx context.Context, round uint64, key []byte) (*StateProof, error)
+}
+
+// GetStateProof fetches a proof of the value (or absence) of the given runtime state key at the
+// given round using the given runtime client, which must implement StateProofProvider.
+func GetStateProof(ctx context.Context, rc RuntimeClient, round uint64, key []byte) (*StateProof, error) {
+	sp, ok := rc.(StateProofProvider)
+	if !ok {
+		return nil, fmt.Errorf("state proof: runtime client does not support state proofs")
+	}
+	return sp.GetStateProof(ctx, round, key)
+}
+
+// Implements StateProofProvider.
+func (rc *runtimeClient) GetStateProof(ctx context.Context, round uint64, key []byte) (*StateProof, error) {
+	blk, err := rc.GetBlock(ctx, round)
+	if err != nil {
+		return nil, fmt.Errorf("failed to fetch block: %w", err)
+	}
+	root := StateRoot(blk)
+
+	rsp, err := rc.cc.State().SyncGet(ctx, &syncer.GetRequest{
+		Tree: syncer.TreeID{
+			Root:     root,
+			Position: root.Hash,
+		},
+		Key: key,
+	})
+	if err != nil {
+		return nil, fmt.Errorf("failed to fetch state proof: %w", err)
+	}
+	return &StateProof{
+		Root:  root,
+		Key:   key,
+		Proof: rsp.Proof,
+	}, nil
+}
+
+// proofReadSyncer is a read syncer that always returns the same proof. Any proofs returned by a
+// read syncer are verified by the tree against the root before being used.
+type proofReadSyncer struct {
+	proof *syncer.Proof
+}
+
+func (rs *proofReadSyncer) SyncGet(context.Context, *syncer.GetRequest) (*syncer.ProofResponse, error) {
+	return &syncer.ProofResponse{Proof: *rs.proof}, nil
+}
+
+func (rs *proofReadSyncer) SyncGetPrefixes(context.Context, *syncer.GetPrefixesRequest) (*syncer.ProofResponse, error) {
+	return nil, fmt.Errorf("state proof: prefix queries not supported")
+}
+
+func (rs *proofReadSyncer) SyncIterate(context.Context, *syncer.IterateRequest) (*syncer.ProofResponse, error) {
+	return nil, fmt.Errorf("state proof: iteration not supported")
+}
+
+// VerifyStateProof verifies the given proof against the given trusted state root and returns
+// the proven value of the key. In case the proof shows that the key does not exist, nil is
+// returned.
+//
+// The state root must come from a trusted source (e.g. a block header verified by a light
+// client), the root included in the proof itself is not trusted.
+func VerifyStateProof(ctx context.Context, trustedRoot node.Root, proof *StateProof) ([]byte, error) {
+	if !proof.Root.Equal(&trustedRoot) {
+		return nil, fmt.Errorf("state proof: proof is for a different state root")
+	}
+
+	tree := mkvs.NewWithRoot(&proofReadSyncer{proof: &proof.Proof}, nil, trustedRoot)
+	defer tree.Close()
+
+	value, err := tree.Get(ctx, proof.Key)
+	if err != nil {
+		return nil, fmt.Errorf("state proof: verification failed: %w", err)
+	}
+	return value, nil
+}
+
+// VerifyModuleState is a convenience wrapper around VerifyStateProof that also checks that the
+// proof is for the given key in the storage of the given module.
+func VerifyModuleState(ctx context.Context, trustedRoot node.Root, module string, key []byte, proof *StateProof) ([]byte, error) {
+	if !bytes.Equal(proof.Key, ModuleStorageKey(module, key)) {
+		return nil, fmt.Errorf("state proof: proof is for a different key")
+	}
+	return VerifyStateProof(ctx, trustedRoot, proof)
+}
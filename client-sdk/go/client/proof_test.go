@@ -0,0 +1,138 @@
+package client
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/roothash/api/block"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs/node"
+	"github.com/oasisprotocol/oasis-core/go/storage/mkvs/syncer"
+)
+
+// newTestStateTree returns a committed in-memory state tree containing the given entries
+// together with its root.
+func newTestStateTree(t *testing.T, round uint64, entries map[string]string) (mkvs.Tree, node.Root) {
+	ctx := context.Background()
+
+	tree := mkvs.New(nil, nil, node.RootTypeState)
+	t.Cleanup(tree.Close)
+	for k, v := range entries {
+		require.NoError(t, tree.Insert(ctx, []byte(k), []byte(v)))
+	}
+	_, rootHash, err := tree.Commit(ctx, common.Namespace{}, round)
+	require.NoError(t, err)
+
+	return tree, node.Root{
+		Namespace: common.Namespace{},
+		Version:   round,
+		Type:      node.RootTypeState,
+		Hash:      rootHash,
+	}
+}
+
+func newTestStateProof(t *testing.T, tree mkvs.Tree, root node.Root, key []byte) *StateProof {
+	rsp, err := tree.SyncGet(context.Background(), &syncer.GetRequest{
+		Tree: syncer.TreeID{
+			Root:     root,
+			Position: root.Hash,
+		},
+		Key: key,
+	})
+	require.NoError(t, err)
+	return &StateProof{Root: root, Key: key, Proof: rsp.Proof}
+}
+
+func TestVerifyStateProof(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	tree, root := newTestStateTree(t, 5, map[string]string{
+		"accounts\x01foo": "bar",
+		"accounts\x01baz": "qux",
+		"core\x01params":  "params",
+	})
+
+	// Inclusion.
+	proof := newTestStateProof(t, tree, root, []byte("accounts\x01foo"))
+	value, err := VerifyStateProof(ctx, root, proof)
+	require.NoError(err)
+	require.EqualValues("bar", value)
+
+	// Exclusion.
+	proof = newTestStateProof(t, tree, root, []byte("accounts\x01missing"))
+	value, err = VerifyStateProof(ctx, root, proof)
+	require.NoError(err)
+	require.Nil(value, "missing keys should be proven absent")
+
+	// Proof for a different root.
+	proof = newTestStateProof(t, tree, root, []byte("accounts\x01foo"))
+	otherRoot := root
+	otherRoot.Version = 6
+	_, err = VerifyStateProof(ctx, otherRoot, proof)
+	require.Error(err, "proofs for a different root should be rejected")
+
+	// Proof claiming to be for the trusted root while being for a different tree.
+	otherTree, otherTreeRoot := newTestStateTree(t, 5, map[string]string{
+		"accounts\x01foo": "evil",
+	})
+	proof = newTestStateProof(t, otherTree, otherTreeRoot, []byte("accounts\x01foo"))
+	proof.Root = root
+	_, err = VerifyStateProof(ctx, root, proof)
+	require.Error(err, "proofs not matching the trusted root should be rejected")
+
+	// Tampered proof.
+	proof = newTestStateProof(t, tree, root, []byte("accounts\x01foo"))
+	require.NotEmpty(proof.Proof.Entries)
+	for i := len(proof.Proof.Entries) - 1; i >= 0; i-- {
+		if entry := proof.Proof.Entries[i]; len(entry) > 0 {
+			entry[len(entry)-1] ^= 0xff
+			break
+		}
+	}
+	_, err = VerifyStateProof(ctx, root, proof)
+	require.Error(err, "tampered proofs should be rejected")
+}
+
+func TestVerifyModuleState(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	key := ModuleStorageKey("accounts", []byte("\x01foo"))
+	require.EqualValues("accounts\x01foo", key)
+
+	tree, root := newTestStateTree(t, 1, map[string]string{string(key): "bar"})
+	proof := newTestStateProof(t, tree, root, key)
+
+	value, err := VerifyModuleState(ctx, root, "accounts", []byte("\x01foo"), proof)
+	require.NoError(err)
+	require.EqualValues("bar", value)
+
+	_, err = VerifyModuleState(ctx, root, "accounts", []byte("\x01bar"), proof)
+	require.Error(err, "proofs for a different key should be rejected")
+	_, err = VerifyModuleState(ctx, root, "core", []byte("\x01foo"), proof)
+	require.Error(err, "proofs for a different module should be rejected")
+}
+
+func TestStateRoot(t *testing.T) {
+	require := require.New(t)
+
+	var blk block.Block
+	blk.Header.Namespace = common.Namespace{1}
+	blk.Header.Round = 42
+	blk.Header.StateRoot.FromBytes([]byte("state"))
+
+	root := StateRoot(&blk)
+	require.EqualValues(blk.Header.Namespace, root.Namespace)
+	require.EqualValues(42, root.Version)
+	require.EqualValues(node.RootTypeState, root.Type)
+	require.EqualValues(blk.Header.StateRoot, root.Hash)
+}
+
+func TestGetStateProofUnsupported(t *testing.T) {
+	_, err := GetStateProof(context.Background(), newTestRuntimeClient(), 1, []byte("key"))
+	require.Error(t, err, "clients without state proof support should be rejected")
+}
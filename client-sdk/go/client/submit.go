@@ -0,0 +1,174 @@
+package client
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"time"
+
+	"github.com/oasisprotocol/oasis-core/go/common/crypto/hash"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+const (
+	// coreModuleName is the name of the core module which reports nonce errors.
+	coreModuleName = "core"
+	// coreErrInvalidNonce is the core module error code reported for invalid nonces.
+	coreErrInvalidNonce = 4
+)
+
+// ErrSubmitAttemptsExhausted is the error returned by SubmitTxWithRetry when the transaction could
+// not be submitted within the configured number of attempts.
+var ErrSubmitAttemptsExhausted = errors.New("transaction submission attempts exhausted")
+
+// SubmitStrategy configures how SubmitTxWithRetry submits transactions.
+type SubmitStrategy struct {
+	// MaxAttempts is the maximum number of submission attempts.
+	MaxAttempts int
+	// AttemptTimeout is the time to wait for the transaction to be executed in each attempt. Zero
+	// means that each attempt is only bounded by the parent context.
+	AttemptTimeout time.Duration
+
+	// InitialBackoff is the time to wait before the first resubmission.
+	InitialBackoff time.Duration
+	// MaxBackoff is the maximum time to wait between resubmissions.
+	MaxBackoff time.Duration
+	// BackoffMultiplier is the factor by which the backoff grows after each failed attempt.
+	BackoffMultiplier float64
+
+	// LookbackRounds is the number of most recent rounds searched for the transaction when
+	// checking whether a failed attempt has in fact been executed.
+	LookbackRounds uint64
+	// Retryable decides whether a submission error should be retried. Nil means that all errors
+	// are retried.
+	Retryable func(error) bool
+}
+
+// DefaultSubmitStrategy returns the default submission strategy.
+func DefaultSubmitStrategy() *SubmitStrategy {
+	return &SubmitStrategy{
+		MaxAttempts:       5,
+		AttemptTimeout:    time.Minute,
+		InitialBackoff:    time.Second,
+		MaxBackoff:        30 * time.Second,
+		BackoffMultiplier: 2,
+		LookbackRounds:    20,
+	}
+}
+
+func (s *SubmitStrategy) backoff(attempt int) time.Duration {
+	backoff := float64(s.InitialBackoff)
+	for i := 1; i < attempt; i++ {
+		backoff *= s.BackoffMultiplier
+		if s.MaxBackoff > 0 && backoff >= float64(s.MaxBackoff) {
+			return s.MaxBackoff
+		}
+	}
+	return time.Duration(backoff)
+}
+
+// SubmitTxWithRetry submits a signed transaction and waits for its execution results, handling
+// node timeouts and transient failures according to the given strategy.
+//
+// Since the same signed transaction (and therefore the same nonce) is resubmitted on every
+// attempt, it can be executed at most once. Whenever an attempt fails or the transaction is
+// rejected with an invalid nonce after a previous attempt, the most recent blocks are searched
+// for the transaction hash so that an already executed transaction is reported with its actual
+// results instead of as a failure.
+//
+// Failed transaction checks are not retried and are reported in the returned metadata, the same
+// as with SubmitTxRawMeta.
+func SubmitTxWithRetry(ctx context.Context, rc RuntimeClient, tx *types.UnverifiedTransaction, strategy *SubmitStrategy) (*SubmitTxRawMeta, error) {
+	if strategy == nil {
+		strategy = DefaultSubmitStrategy()
+	}
+	if strategy.MaxAttempts <= 0 {
+		return nil, fmt.Errorf("invalid submit strategy: at least one attempt required")
+	}
+	txHash := tx.Hash()
+
+	var lastErr error
+	for attempt := 1; attempt <= strategy.MaxAttempts; attempt++ {
+		if attempt > 1 {
+			select {
+			case <-time.After(strategy.backoff(attempt - 1)):
+			case <-ctx.Done():
+				return nil, ctx.Err()
+			}
+		}
+
+		meta, err := submitAttempt(ctx, rc, tx, strategy.AttemptTimeout)
+		switch {
+		case err == nil && meta.CheckTxError == nil:
+			return meta, nil
+		case err == nil:
+			// The transaction check failed. An invalid nonce after a previous attempt means that
+			// the transaction could have already been executed.
+			if attempt == 1 || meta.CheckTxError.Module != coreModuleName || meta.CheckTxError.Code != coreErrInvalidNonce {
+				return meta, nil
+			}
+			if executed, lookupErr := FindTransaction(ctx, rc, txHash, strategy.LookbackRounds); lookupErr == nil && executed != nil {
+				return executed, nil
+			}
+			return meta, nil
+		case ctx.Err() != nil:
+			return nil, ctx.Err()
+		}
+
+		// The attempt failed, but the transaction could still have been executed.
+		lastErr = err
+		if executed, lookupErr := FindTransaction(ctx, rc, txHash, strategy.LookbackRounds); lookupErr == nil && executed != nil {
+			return executed, nil
+		}
+		if strategy.Retryable != nil && !strategy.Retryable(err) {
+			return nil, err
+		}
+	}
+	return nil, fmt.Errorf("%w: %w", ErrSubmitAttemptsExhausted, lastErr)
+}
+
+func submitAttempt(ctx context.Context, rc RuntimeClient, tx *types.UnverifiedTransaction, timeout time.Duration) (*SubmitTxRawMeta, error) {
+	if timeout > 0 {
+		var cancel context.CancelFunc
+		ctx, cancel = context.WithTimeout(ctx, timeout)
+		defer cancel()
+	}
+	return rc.SubmitTxRawMeta(ctx, tx)
+}
+
+// FindTransaction searches the given number of most recent rounds for an executed transaction with
+// the given hash and returns its results. It returns nil in case the transaction is not found.
+func FindTransaction(ctx context.Context, rc RuntimeClient, txHash hash.Hash, lookbackRounds uint64) (*SubmitTxRawMeta, error) {
+	blk, err := rc.GetBlock(ctx, RoundLatest)
+	if err != nil {
+		return nil, fmt.Errorf("failed to fetch latest block: %w", err)
+	}
+	latest := blk.Header.Round
+
+	var start uint64
+	if latest > lookbackRounds {
+		start = latest - lookbackRounds
+	}
+	for round := latest; ; round-- {
+		txs, err := rc.GetTransactionsWithResults(ctx, round)
+		if err != nil {
+			return nil, fmt.Errorf("failed to fetch transactions for round %d: %w", round, err)
+		}
+		for index, txr := range txs {
+			if h := txr.Tx.Hash(); !h.Equal(&txHash) {
+				continue
+			}
+			return &SubmitTxRawMeta{
+				TransactionMeta: TransactionMeta{
+					Round:      round,
+					BatchOrder: uint32(index),
+				},
+				Result: txr.Result,
+			}, nil
+		}
+		if round == start {
+			return nil, nil
+		}
+	}
+}
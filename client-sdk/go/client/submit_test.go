@@ -0,0 +1,90 @@
+package client
+
+import (
+	"context"
+	"fmt"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	sdkTesting "github.com/oasisprotocol/oasis-sdk/client-sdk/go/testing"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+func TestSubmitTxWithRetry(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := newTestRuntimeClient()
+	tb := NewTransactionBuilder(rc, "test.Method", nil).
+		AppendAuthSignature(sdkTesting.Alice.SigSpec, 0)
+	require.NoError(tb.AppendSign(ctx, sdkTesting.Alice.Signer))
+	tx := tb.GetSignedTransaction()
+
+	strategy := &SubmitStrategy{
+		MaxAttempts:       3,
+		InitialBackoff:    time.Millisecond,
+		MaxBackoff:        2 * time.Millisecond,
+		BackoffMultiplier: 2,
+		LookbackRounds:    5,
+	}
+
+	// Transient failures should be retried with the same transaction.
+	rc.submitErrs = []error{fmt.Errorf("timeout"), fmt.Errorf("timeout")}
+	meta, err := SubmitTxWithRetry(ctx, rc, tx, strategy)
+	require.NoError(err, "SubmitTxWithRetry")
+	require.EqualValues(42, meta.Round)
+	require.Len(rc.submitted, 3)
+	require.Equal(tx.Hash(), rc.submitted[2].Hash(), "resubmissions should use the same transaction")
+
+	// Running out of attempts should be reported.
+	rc.submitted = nil
+	rc.submitErrs = []error{fmt.Errorf("timeout"), fmt.Errorf("timeout"), fmt.Errorf("timeout")}
+	_, err = SubmitTxWithRetry(ctx, rc, tx, strategy)
+	require.ErrorIs(err, ErrSubmitAttemptsExhausted)
+	require.Len(rc.submitted, 3)
+
+	// Non-retryable errors should be returned immediately.
+	rc.submitted = nil
+	rc.submitErrs = []error{fmt.Errorf("permanent")}
+	strategy.Retryable = func(error) bool { return false }
+	_, err = SubmitTxWithRetry(ctx, rc, tx, strategy)
+	require.EqualError(err, "permanent")
+	require.Len(rc.submitted, 1)
+	strategy.Retryable = nil
+
+	// A transaction executed despite a failed attempt should be detected.
+	rc.submitted = nil
+	rc.latestRound = 10
+	rc.txs[8] = []*TransactionWithResults{
+		{Tx: types.UnverifiedTransaction{Body: []byte("other")}},
+		{Tx: *tx, Result: types.CallResult{Ok: cbor.Marshal("executed")}},
+	}
+	rc.submitErrs = []error{fmt.Errorf("timeout")}
+	meta, err = SubmitTxWithRetry(ctx, rc, tx, strategy)
+	require.NoError(err, "SubmitTxWithRetry")
+	require.EqualValues(8, meta.Round)
+	require.EqualValues(1, meta.BatchOrder)
+	require.EqualValues(cbor.Marshal("executed"), meta.Result.Ok)
+	require.Len(rc.submitted, 1, "executed transactions should not be resubmitted")
+
+	// Transactions outside of the lookback window should not be considered.
+	found, err := FindTransaction(ctx, rc, tx.Hash(), 1)
+	require.NoError(err, "FindTransaction")
+	require.Nil(found)
+
+	// Failed checks should not be retried.
+	rc.submitted = nil
+	rc.result = &SubmitTxRawMeta{
+		TransactionMeta: TransactionMeta{
+			CheckTxError: &CheckTxError{Module: "core", Code: 5, Message: "insufficient balance to pay fees"},
+		},
+	}
+	meta, err = SubmitTxWithRetry(ctx, rc, tx, strategy)
+	require.NoError(err, "SubmitTxWithRetry")
+	require.NotNil(meta.CheckTxError)
+	require.Len(rc.submitted, 1)
+}
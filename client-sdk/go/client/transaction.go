@@ -3,27 +3,64 @@ package client
 import (
 	"context"
 	"fmt"
+	"math"
 
 	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	"github.com/oasisprotocol/oasis-core/go/common/quantity"
 
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
 )
 
+const (
+	methodNonce       = "accounts.Nonce"
+	methodEstimateGas = "core.EstimateGas"
+)
+
+type nonceQuery struct {
+	Address types.Address `json:"address"`
+}
+
+type estimateGasQuery struct {
+	Tx                *types.Transaction `json:"tx"`
+	PropagateFailures bool               `json:"propagate_failures,omitempty"`
+}
+
+// DefaultGasMultiplier is the default multiplier applied to estimated gas.
+//
+// Gas is estimated against the latest state, which may change before the transaction is executed,
+// so some headroom is left by default to avoid transactions running out of gas.
+const DefaultGasMultiplier = 1.2
+
 // TransactionBuilder is a helper for building and submitting transactions.
 type TransactionBuilder struct {
 	rc RuntimeClient
 	tx *types.Transaction
 	ts *types.TransactionSigner
 
+	ccb      *ConfidentialCallBuilder
 	callMeta interface{}
+
+	gasMultiplier float64
+	gasPrice      *types.BaseUnits
+}
+
+// TransactionOutcome is the outcome of a transaction submitted via SignAndSubmit.
+type TransactionOutcome struct {
+	TransactionMeta
+
+	// Nonce is the nonce used by the signer.
+	Nonce uint64
+	// Gas is the gas limit of the submitted transaction.
+	Gas uint64
 }
 
 // NewTransactionBuilder creates a new transaction builder.
 func NewTransactionBuilder(rc RuntimeClient, method types.MethodName, body interface{}) *TransactionBuilder {
 	return &TransactionBuilder{
-		rc: rc,
-		tx: types.NewTransaction(nil, method, body),
+		rc:            rc,
+		tx:            types.NewTransaction(nil, method, body),
+		gasMultiplier: DefaultGasMultiplier,
 	}
 }
 
@@ -39,6 +76,20 @@ func (tb *TransactionBuilder) SetFeeGas(gas uint64) *TransactionBuilder {
 	return tb
 }
 
+// SetGasMultiplier configures the multiplier applied to the estimated gas by EstimateGas. Values
+// above one leave headroom for state changes between estimation and execution.
+func (tb *TransactionBuilder) SetGasMultiplier(multiplier float64) *TransactionBuilder {
+	tb.gasMultiplier = multiplier
+	return tb
+}
+
+// SetGasPrice configures the gas price used by EstimateGas to derive the fee amount from the
+// estimated gas.
+func (tb *TransactionBuilder) SetGasPrice(price types.BaseUnits) *TransactionBuilder {
+	tb.gasPrice = &price
+	return tb
+}
+
 // SetFeeConsensusMessages configures the maximum number of consensus messages that can be emitted
 // by the transaction.
 func (tb *TransactionBuilder) SetFeeConsensusMessages(consensusMessages uint32) *TransactionBuilder {
@@ -46,6 +97,15 @@ func (tb *TransactionBuilder) SetFeeConsensusMessages(consensusMessages uint32)
 	return tb
 }
 
+// SetConfidentialCallBuilder configures the confidential call builder used for encrypting the
+// call when the call format is changed to an encrypted call format.
+//
+// Using a shared builder ensures that the pinned runtime call data public key is reused.
+func (tb *TransactionBuilder) SetConfidentialCallBuilder(ccb *ConfidentialCallBuilder) *TransactionBuilder {
+	tb.ccb = ccb
+	return tb
+}
+
 // SetCallFormat changes the transaction's call format.
 //
 // Depending on the call format this operation my require queries into the runtime in order to
@@ -116,7 +176,8 @@ func (tb *TransactionBuilder) GetSignedTransaction() *types.UnverifiedTransactio
 
 // AppendSign signs the transaction and appends the signature.
 //
-// The signer must be specified in the AuthInfo.
+// The signer must be specified in the AuthInfo. Any signature.Signer can be used, including the
+// keystore-backed and remote signers provided by the wallet package.
 func (tb *TransactionBuilder) AppendSign(ctx context.Context, signer signature.Signer) error {
 	if tb.ts == nil {
 		tb.ts = tb.tx.PrepareForSigning()
@@ -189,6 +250,28 @@ func (tb *TransactionBuilder) SubmitTxMeta(ctx context.Context, rsp interface{})
 	return &meta.TransactionMeta, err
 }
 
+// SubmitTxWithRetry submits a transaction to the runtime transaction scheduler and waits for
+// transaction execution results, retrying according to the given strategy. See
+// SubmitTxWithRetry for details.
+func (tb *TransactionBuilder) SubmitTxWithRetry(ctx context.Context, strategy *SubmitStrategy, rsp interface{}) (*TransactionMeta, error) {
+	if tb.ts == nil {
+		return nil, fmt.Errorf("unable to submit unsigned transaction")
+	}
+
+	meta, err := SubmitTxWithRetry(ctx, tb.rc, tb.ts.UnverifiedTransaction(), strategy)
+	if err != nil {
+		return nil, err
+	}
+
+	// Check if an error was encountered during transaction checks.
+	if meta.CheckTxError != nil {
+		return &meta.TransactionMeta, nil
+	}
+
+	err = tb.DecodeResult(&meta.Result, rsp)
+	return &meta.TransactionMeta, err
+}
+
 // SubmitTxNoWait submits a transaction to the runtime transaction scheduler but does not wait for
 // transaction execution.
 func (tb *TransactionBuilder) SubmitTxNoWait(ctx context.Context) error {
@@ -197,3 +280,87 @@ func (tb *TransactionBuilder) SubmitTxNoWait(ctx context.Context) error {
 	}
 	return tb.rc.SubmitTxNoWait(ctx, tb.ts.UnverifiedTransaction())
 }
+
+// ResolveNonce queries the current nonce of the account with the given address specification.
+func (tb *TransactionBuilder) ResolveNonce(ctx context.Context, spec types.SignatureAddressSpec) (uint64, error) {
+	var nonce uint64
+	if err := tb.rc.Query(ctx, RoundLatest, methodNonce, &nonceQuery{Address: types.NewAddress(spec)}, &nonce); err != nil {
+		return 0, fmt.Errorf("failed to query nonce: %w", err)
+	}
+	return nonce, nil
+}
+
+// EstimateGas estimates the gas required to execute the transaction by simulating it, applies the
+// configured gas multiplier and sets the resulting fee gas (and fee amount in case a gas price is
+// configured).
+//
+// The transaction signers must already be specified in the AuthInfo.
+func (tb *TransactionBuilder) EstimateGas(ctx context.Context) (uint64, error) {
+	var estimated uint64
+	if err := tb.rc.Query(ctx, RoundLatest, methodEstimateGas, &estimateGasQuery{Tx: tb.tx, PropagateFailures: true}, &estimated); err != nil {
+		return 0, fmt.Errorf("failed to estimate gas: %w", err)
+	}
+
+	gas := uint64(math.Ceil(float64(estimated) * tb.gasMultiplier))
+	tb.SetFeeGas(gas)
+
+	if tb.gasPrice != nil {
+		amount := tb.gasPrice.Amount.Clone()
+		if err := amount.Mul(quantity.NewFromUint64(gas)); err != nil {
+			return 0, fmt.Errorf("failed to compute fee amount: %w", err)
+		}
+		tb.SetFeeAmount(types.NewBaseUnits(*amount, tb.gasPrice.Denomination))
+	}
+	return gas, nil
+}
+
+// SignAndSubmit resolves the signer's nonce, estimates gas (unless the fee gas has been set
+// explicitly), signs the transaction, submits it and waits for transaction execution results.
+//
+// In case the signer information has already been appended to the AuthInfo, the nonce is not
+// resolved. When the call format has been changed via SetCallFormat, the encrypted call is signed
+// and the result is decrypted before being unmarshalled into rsp.
+//
+// The returned outcome is available even when the transaction failed. Failures during transaction
+// checks are reported as a *CheckTxError and failed calls as a types.FailedCallResult.
+func (tb *TransactionBuilder) SignAndSubmit(ctx context.Context, signer signature.Signer, rsp interface{}) (*TransactionOutcome, error) {
+	if tb.ts != nil {
+		return nil, fmt.Errorf("transaction has already been signed")
+	}
+
+	if len(tb.tx.AuthInfo.SignerInfo) == 0 {
+		spec, err := types.NewSignatureAddressSpecFromPublicKey(signer.Public())
+		if err != nil {
+			return nil, err
+		}
+		nonce, err := tb.ResolveNonce(ctx, spec)
+		if err != nil {
+			return nil, err
+		}
+		tb.AppendAuthSignature(spec, nonce)
+	}
+
+	if tb.tx.AuthInfo.Fee.Gas == 0 {
+		if _, err := tb.EstimateGas(ctx); err != nil {
+			return nil, err
+		}
+	}
+
+	if err := tb.AppendSign(ctx, signer); err != nil {
+		return nil, err
+	}
+
+	meta, err := tb.SubmitTxMeta(ctx, rsp)
+	if meta == nil {
+		return nil, err
+	}
+	outcome := &TransactionOutcome{
+		TransactionMeta: *meta,
+		Nonce:           tb.tx.AuthInfo.SignerInfo[0].Nonce,
+		Gas:             tb.tx.AuthInfo.Fee.Gas,
+	}
+	if err == nil && meta.CheckTxError != nil {
+		err = meta.CheckTxError
+	}
+	return outcome, err
+}
@@ -0,0 +1,64 @@
+package client
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common/quantity"
+
+	sdkTesting "github.com/oasisprotocol/oasis-sdk/client-sdk/go/testing"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+func TestTransactionBuilderSignAndSubmit(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	rc := newTestRuntimeClient()
+	rc.queries[methodNonce] = uint64(5)
+	rc.queries[methodEstimateGas] = uint64(1000)
+
+	var rsp string
+	outcome, err := NewTransactionBuilder(rc, "test.Method", nil).
+		SetGasMultiplier(1.5).
+		SetGasPrice(types.NewBaseUnits(*quantity.NewFromUint64(2), types.NativeDenomination)).
+		SignAndSubmit(ctx, sdkTesting.Alice.Signer, &rsp)
+	require.NoError(err)
+	require.EqualValues("ok", rsp)
+	require.EqualValues(42, outcome.Round)
+	require.EqualValues(5, outcome.Nonce)
+	require.EqualValues(1500, outcome.Gas)
+
+	require.Len(rc.submitted, 1)
+	tx, err := rc.submitted[0].Verify(rc.info.ChainContext)
+	require.NoError(err, "submitted transaction should be correctly signed")
+	require.EqualValues(5, tx.AuthInfo.SignerInfo[0].Nonce)
+	require.EqualValues(1500, tx.AuthInfo.Fee.Gas)
+	require.Zero(tx.AuthInfo.Fee.Amount.Amount.Cmp(quantity.NewFromUint64(3000)), "fee amount should be derived from gas price")
+
+	// Explicitly configured fee gas should not be estimated.
+	delete(rc.queries, methodEstimateGas)
+	outcome, err = NewTransactionBuilder(rc, "test.Method", nil).
+		SetFeeGas(123).
+		SignAndSubmit(ctx, sdkTesting.Alice.Signer, nil)
+	require.NoError(err)
+	require.EqualValues(123, outcome.Gas)
+
+	// Check failures should be reported as typed errors.
+	rc.queries[methodEstimateGas] = uint64(1000)
+	rc.result = &SubmitTxRawMeta{
+		TransactionMeta: TransactionMeta{
+			CheckTxError: &CheckTxError{Module: "core", Code: 4, Message: "out of gas"},
+		},
+	}
+	outcome, err = NewTransactionBuilder(rc, "test.Method", nil).
+		SignAndSubmit(ctx, sdkTesting.Alice.Signer, nil)
+	require.Error(err)
+	var checkErr *CheckTxError
+	require.ErrorAs(err, &checkErr)
+	require.EqualValues("core", checkErr.Module)
+	require.NotNil(outcome)
+	require.EqualValues(1200, outcome.Gas, "default gas multiplier should be applied")
+}
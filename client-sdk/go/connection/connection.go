@@ -65,7 +65,11 @@ func (c *connection) Runtime(pt *config.ParaTime) RuntimeClient {
 	if err := runtimeID.UnmarshalHex(pt.ID); err != nil {
 		panic(err)
 	}
-	cli := client.New(c.conn, runtimeID)
+	return NewRuntimeClient(client.New(c.conn, runtimeID))
+}
+
+// NewRuntimeClient augments the given runtime client with commonly used modules.
+func NewRuntimeClient(cli client.RuntimeClient) RuntimeClient {
 	return RuntimeClient{
 		RuntimeClient:     cli,
 		Core:              core.NewV1(cli),
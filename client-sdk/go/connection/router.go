@@ -0,0 +1,188 @@
+package connection
+
+import (
+	"context"
+	"fmt"
+	"sort"
+	"sync"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/client"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/config"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/modules/consensusaccounts"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// Router manages clients for multiple runtimes sharing a single node connection and routes
+// requests to them by runtime identifier.
+type Router struct {
+	conn Connection
+
+	mu       sync.RWMutex
+	runtimes map[common.Namespace]RuntimeClient
+}
+
+// NewRouter creates a new runtime router over the given node connection.
+func NewRouter(conn Connection) *Router {
+	return &Router{
+		conn:     conn,
+		runtimes: make(map[common.Namespace]RuntimeClient),
+	}
+}
+
+// Connection returns the underlying node connection.
+func (r *Router) Connection() Connection {
+	return r.conn
+}
+
+// Add registers the given ParaTime with the router and returns its client. Adding an already
+// registered ParaTime returns the existing client.
+func (r *Router) Add(pt *config.ParaTime) (RuntimeClient, error) {
+	var runtimeID common.Namespace
+	if err := runtimeID.UnmarshalHex(pt.ID); err != nil {
+		return RuntimeClient{}, fmt.Errorf("malformed runtime identifier: %w", err)
+	}
+
+	r.mu.Lock()
+	defer r.mu.Unlock()
+
+	if rt, ok := r.runtimes[runtimeID]; ok {
+		return rt, nil
+	}
+	rt := r.conn.Runtime(pt)
+	r.runtimes[runtimeID] = rt
+	return rt, nil
+}
+
+// Register registers an existing runtime client (e.g. one with custom middleware) under the given
+// runtime identifier, replacing any previously registered client.
+func (r *Router) Register(runtimeID common.Namespace, cli client.RuntimeClient) RuntimeClient {
+	rt := NewRuntimeClient(cli)
+
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.runtimes[runtimeID] = rt
+	return rt
+}
+
+// Runtime returns the client for the given runtime.
+func (r *Router) Runtime(runtimeID common.Namespace) (RuntimeClient, error) {
+	r.mu.RLock()
+	defer r.mu.RUnlock()
+
+	rt, ok := r.runtimes[runtimeID]
+	if !ok {
+		return RuntimeClient{}, fmt.Errorf("unknown runtime: %s", runtimeID)
+	}
+	return rt, nil
+}
+
+// Runtimes returns the identifiers of all registered runtimes in sorted order.
+func (r *Router) Runtimes() []common.Namespace {
+	r.mu.RLock()
+	defer r.mu.RUnlock()
+
+	ids := make([]common.Namespace, 0, len(r.runtimes))
+	for id := range r.runtimes {
+		ids = append(ids, id)
+	}
+	sort.Slice(ids, func(i, j int) bool {
+		return ids[i].String() < ids[j].String()
+	})
+	return ids
+}
+
+// Query performs a query on the given runtime.
+func (r *Router) Query(ctx context.Context, runtimeID common.Namespace, round uint64, method types.MethodName, args, rsp interface{}) error {
+	rt, err := r.Runtime(runtimeID)
+	if err != nil {
+		return err
+	}
+	return rt.Query(ctx, round, method, args, rsp)
+}
+
+// SubmitTx submits a transaction to the given runtime and waits for its execution results.
+func (r *Router) SubmitTx(ctx context.Context, runtimeID common.Namespace, tx *types.UnverifiedTransaction) (cbor.RawMessage, error) {
+	rt, err := r.Runtime(runtimeID)
+	if err != nil {
+		return nil, err
+	}
+	return rt.SubmitTx(ctx, tx)
+}
+
+// Deposit deposits tokens from the signer's consensus layer account into the given runtime and
+// waits until the deposit has been processed by the runtime.
+//
+// Deposits are performed by the runtime withdrawing from the signer's consensus layer account,
+// so the signer must have previously configured a sufficient consensus layer allowance for the
+// runtime. In case to is nil, the tokens are deposited into the signer's runtime account.
+//
+// The deposit event emitted once the consensus layer withdrawal has been processed is returned.
+// In case the withdrawal failed, the event is returned together with an error.
+func (r *Router) Deposit(ctx context.Context, runtimeID common.Namespace, signer signature.Signer, to *types.Address, amount types.BaseUnits) (*consensusaccounts.DepositEvent, error) {
+	rt, err := r.Runtime(runtimeID)
+	if err != nil {
+		return nil, err
+	}
+	spec, err := types.NewSignatureAddressSpecFromPublicKey(signer.Public())
+	if err != nil {
+		return nil, err
+	}
+
+	tb := rt.ConsensusAccounts.Deposit(to, amount).
+		SetFeeConsensusMessages(1)
+	outcome, err := tb.SignAndSubmit(ctx, signer, nil)
+	if err != nil {
+		return nil, fmt.Errorf("failed to submit deposit: %w", err)
+	}
+
+	return WaitForDeposit(ctx, rt, outcome.Round, types.NewAddress(spec), outcome.Nonce)
+}
+
+// WaitForDeposit waits for the deposit event identified by the depositor's address and the nonce
+// of the deposit transaction, starting at the given round.
+func WaitForDeposit(ctx context.Context, rt RuntimeClient, round uint64, from types.Address, nonce uint64) (*consensusaccounts.DepositEvent, error) {
+	ctx, cancel := context.WithCancel(ctx)
+	defer cancel()
+
+	evCh, errCh := client.SubscribeEvents(ctx, rt, &client.EventSubscriptionOptions{
+		StartRound: round,
+		Filter: &client.EventFilter{
+			Module: consensusaccounts.ModuleName,
+			Codes:  []uint32{consensusaccounts.DepositEventCode},
+		},
+		Decoders: []client.EventDecoder{rt.ConsensusAccounts},
+	})
+	var subErr error
+	for {
+		select {
+		case bev, ok := <-evCh:
+			if !ok {
+				if subErr != nil {
+					return nil, fmt.Errorf("failed to watch deposit events: %w", subErr)
+				}
+				return nil, ctx.Err()
+			}
+			for _, ev := range bev.Events {
+				de := ev.(*consensusaccounts.Event).Deposit
+				if de == nil || !de.From.Equal(from) || de.Nonce != nonce {
+					continue
+				}
+				if !de.IsSuccess() {
+					return de, fmt.Errorf("deposit failed in round %d: module: %s code: %d", bev.Round, de.Error.Module, de.Error.Code)
+				}
+				return de, nil
+			}
+		case err, ok := <-errCh:
+			if !ok {
+				errCh = nil
+				continue
+			}
+			// Remember the last error in case it terminates the subscription.
+			subErr = err
+		}
+	}
+}
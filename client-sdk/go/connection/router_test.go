@@ -0,0 +1,151 @@
+package connection
+
+import (
+	"context"
+	"fmt"
+	"testing"
+	"time"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	"github.com/oasisprotocol/oasis-core/go/common/pubsub"
+	"github.com/oasisprotocol/oasis-core/go/common/quantity"
+	roothash "github.com/oasisprotocol/oasis-core/go/roothash/api"
+	"github.com/oasisprotocol/oasis-core/go/roothash/api/block"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/client"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/modules/consensusaccounts"
+	sdkTesting "github.com/oasisprotocol/oasis-sdk/client-sdk/go/testing"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// testRuntimeClient is a runtime client that executes deposits and emits the corresponding
+// deposit events in the following round. Methods that are not overridden panic when called.
+type testRuntimeClient struct {
+	client.RuntimeClient
+
+	round     uint64
+	submitted []*types.UnverifiedTransaction
+	events    map[uint64][]*types.Event
+	blocks    chan *roothash.AnnotatedBlock
+}
+
+type testSubscription struct{}
+
+func (testSubscription) Close() {}
+
+func newTestRuntimeClient(round uint64) *testRuntimeClient {
+	return &testRuntimeClient{
+		round:  round,
+		events: make(map[uint64][]*types.Event),
+		blocks: make(chan *roothash.AnnotatedBlock, 16),
+	}
+}
+
+func (rc *testRuntimeClient) GetInfo(context.Context) (*types.RuntimeInfo, error) {
+	return &types.RuntimeInfo{
+		ChainContext: &signature.RichContext{
+			RuntimeID:    common.Namespace{},
+			ChainContext: "test",
+			Base:         types.SignatureContextBase,
+		},
+	}, nil
+}
+
+func (rc *testRuntimeClient) Query(_ context.Context, _ uint64, method types.MethodName, _, rsp interface{}) error {
+	switch method {
+	case "accounts.Nonce":
+		return cbor.Unmarshal(cbor.Marshal(uint64(7)), rsp)
+	case "core.EstimateGas":
+		return cbor.Unmarshal(cbor.Marshal(uint64(10_000)), rsp)
+	default:
+		return fmt.Errorf("unexpected query: %s", method)
+	}
+}
+
+func (rc *testRuntimeClient) SubmitTxRawMeta(_ context.Context, ut *types.UnverifiedTransaction) (*client.SubmitTxRawMeta, error) {
+	rc.submitted = append(rc.submitted, ut)
+
+	var tx types.Transaction
+	if err := cbor.Unmarshal(ut.Body, &tx); err != nil {
+		return nil, err
+	}
+	var body consensusaccounts.Deposit
+	if err := cbor.Unmarshal(tx.Call.Body, &body); err != nil {
+		return nil, err
+	}
+	from := types.NewAddress(*tx.AuthInfo.SignerInfo[0].AddressSpec.Signature)
+	to := from
+	if body.To != nil {
+		to = *body.To
+	}
+
+	// The deposit is processed in the round following the one that included the transaction.
+	rc.events[rc.round+1] = append(rc.events[rc.round+1], &types.Event{
+		Module: consensusaccounts.ModuleName,
+		Code:   consensusaccounts.DepositEventCode,
+		Value: cbor.Marshal([]*consensusaccounts.DepositEvent{
+			{From: from, Nonce: tx.AuthInfo.SignerInfo[0].Nonce - 1, To: to, Amount: body.Amount},
+			{From: from, Nonce: tx.AuthInfo.SignerInfo[0].Nonce, To: to, Amount: body.Amount},
+		}),
+	})
+	for _, round := range []uint64{rc.round, rc.round + 1} {
+		rc.blocks <- &roothash.AnnotatedBlock{
+			Block: &block.Block{Header: block.Header{Round: round}},
+		}
+	}
+
+	return &client.SubmitTxRawMeta{
+		TransactionMeta: client.TransactionMeta{Round: rc.round},
+		Result:          types.CallResult{Ok: cbor.Marshal(nil)},
+	}, nil
+}
+
+func (rc *testRuntimeClient) WatchBlocks(context.Context) (<-chan *roothash.AnnotatedBlock, pubsub.ClosableSubscription, error) {
+	return rc.blocks, testSubscription{}, nil
+}
+
+func (rc *testRuntimeClient) GetEventsRaw(_ context.Context, round uint64) ([]*types.Event, error) {
+	return rc.events[round], nil
+}
+
+func TestRouter(t *testing.T) {
+	require := require.New(t)
+
+	var id1, id2 common.Namespace
+	_ = id1.UnmarshalHex("8000000000000000000000000000000000000000000000000000000000000001")
+	_ = id2.UnmarshalHex("8000000000000000000000000000000000000000000000000000000000000002")
+
+	router := NewRouter(nil)
+	rc1 := newTestRuntimeClient(10)
+	rc2 := newTestRuntimeClient(20)
+	router.Register(id2, rc2)
+	router.Register(id1, rc1)
+	require.EqualValues([]common.Namespace{id1, id2}, router.Runtimes())
+
+	var unknown common.Namespace
+	_, err := router.Runtime(unknown)
+	require.Error(err, "unknown runtimes should be rejected")
+	err = router.Query(context.Background(), unknown, client.RoundLatest, "accounts.Nonce", nil, nil)
+	require.Error(err, "queries to unknown runtimes should be rejected")
+
+	var nonce uint64
+	err = router.Query(context.Background(), id1, client.RoundLatest, "accounts.Nonce", nil, &nonce)
+	require.NoError(err)
+	require.EqualValues(7, nonce)
+
+	ctx, cancel := context.WithTimeout(context.Background(), 5*time.Second)
+	defer cancel()
+
+	amount := types.NewBaseUnits(*quantity.NewFromUint64(1_000), types.NativeDenomination)
+	ev, err := router.Deposit(ctx, id2, sdkTesting.Alice.Signer, &sdkTesting.Bob.Address, amount)
+	require.NoError(err, "Deposit")
+	require.Len(rc2.submitted, 1, "deposit should be submitted to the routed runtime")
+	require.Empty(rc1.submitted)
+	require.EqualValues(7, ev.Nonce, "the event matching the deposit nonce should be returned")
+	require.True(ev.From.Equal(sdkTesting.Alice.Address))
+	require.True(ev.To.Equal(sdkTesting.Bob.Address))
+}
@@ -19,7 +19,9 @@ var (
 
 var (
 	// Callable methods.
-	methodTransfer = types.NewMethodName("accounts.Transfer", Transfer{})
+	methodTransfer     = types.NewMethodName("accounts.Transfer", Transfer{})
+	methodApprove      = types.NewMethodName("accounts.Approve", Approve{})
+	methodTransferFrom = types.NewMethodName("accounts.TransferFrom", TransferFrom{})
 
 	// Queries.
 	methodParameters       = types.NewMethodName("accounts.Parameters", nil)
@@ -27,6 +29,7 @@ var (
 	methodBalances         = types.NewMethodName("accounts.Balances", BalancesQuery{})
 	methodAddresses        = types.NewMethodName("accounts.Addresses", AddressesQuery{})
 	methodDenominationInfo = types.NewMethodName("accounts.DenominationInfo", DenominationInfoQuery{})
+	methodAllowances       = types.NewMethodName("accounts.Allowances", AllowancesQuery{})
 )
 
 // V1 is the v1 accounts module interface.
@@ -36,6 +39,12 @@ type V1 interface {
 	// Transfer generates an accounts.Transfer transaction.
 	Transfer(to types.Address, amount types.BaseUnits) *client.TransactionBuilder
 
+	// Approve generates an accounts.Approve transaction.
+	Approve(spender types.Address, amount types.BaseUnits) *client.TransactionBuilder
+
+	// TransferFrom generates an accounts.TransferFrom transaction.
+	TransferFrom(from types.Address, to types.Address, amount types.BaseUnits) *client.TransactionBuilder
+
 	// Parameters queries the accounts module parameters.
 	Parameters(ctx context.Context, round uint64) (*Parameters, error)
 
@@ -51,6 +60,9 @@ type V1 interface {
 	// DenominationInfo queries the information about a given denomination.
 	DenominationInfo(ctx context.Context, round uint64, denomination types.Denomination) (*DenominationInfo, error)
 
+	// Allowances queries the allowances given by the owner to the spender.
+	Allowances(ctx context.Context, round uint64, owner types.Address, spender types.Address) (*Allowances, error)
+
 	// GetEvents returns all account events emitted in a given block.
 	GetEvents(ctx context.Context, round uint64) ([]*Event, error)
 }
@@ -67,6 +79,23 @@ func (a *v1) Transfer(to types.Address, amount types.BaseUnits) *client.Transact
 	})
 }
 
+// Implements V1.
+func (a *v1) Approve(spender types.Address, amount types.BaseUnits) *client.TransactionBuilder {
+	return client.NewTransactionBuilder(a.rc, methodApprove, &Approve{
+		Spender: spender,
+		Amount:  amount,
+	})
+}
+
+// Implements V1.
+func (a *v1) TransferFrom(from types.Address, to types.Address, amount types.BaseUnits) *client.TransactionBuilder {
+	return client.NewTransactionBuilder(a.rc, methodTransferFrom, &TransferFrom{
+		From:   from,
+		To:     to,
+		Amount: amount,
+	})
+}
+
 // Implements V1.
 func (a *v1) Parameters(ctx context.Context, round uint64) (*Parameters, error) {
 	var params Parameters
@@ -100,11 +129,18 @@ func (a *v1) Balances(ctx context.Context, round uint64, address types.Address)
 // Implements V1.
 func (a *v1) Addresses(ctx context.Context, round uint64, denomination types.Denomination) (Addresses, error) {
 	var addresses Addresses
-	err := a.rc.Query(ctx, round, methodAddresses, &AddressesQuery{Denomination: denomination}, &addresses)
-	if err != nil {
-		return nil, err
+	query := AddressesQuery{Denomination: denomination}
+	for {
+		var page types.PageResponse[types.Address]
+		if err := a.rc.Query(ctx, round, methodAddresses, &query, &page); err != nil {
+			return nil, err
+		}
+		addresses = append(addresses, page.Items...)
+		if page.Next == nil {
+			return addresses, nil
+		}
+		query.Pagination.Cursor = page.Next
 	}
-	return addresses, nil
 }
 
 // Implements V1.
@@ -117,6 +153,16 @@ func (a *v1) DenominationInfo(ctx context.Context, round uint64, denomination ty
 	return &info, nil
 }
 
+// Implements V1.
+func (a *v1) Allowances(ctx context.Context, round uint64, owner types.Address, spender types.Address) (*Allowances, error) {
+	var allowances Allowances
+	err := a.rc.Query(ctx, round, methodAllowances, &AllowancesQuery{Owner: owner, Spender: spender}, &allowances)
+	if err != nil {
+		return nil, err
+	}
+	return &allowances, nil
+}
+
 // Implements V1.
 func (a *v1) GetEvents(ctx context.Context, round uint64) ([]*Event, error) {
 	rawEvs, err := a.rc.GetEventsRaw(ctx, round)
@@ -174,6 +220,14 @@ func DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {
 		for _, ev := range evs {
 			events = append(events, &Event{Mint: ev})
 		}
+	case ApprovalEventCode:
+		var evs []*ApprovalEvent
+		if err := cbor.Unmarshal(event.Value, &evs); err != nil {
+			return nil, fmt.Errorf("decode account approval event value: %w", err)
+		}
+		for _, ev := range evs {
+			events = append(events, &Event{Approval: ev})
+		}
 	default:
 		return nil, fmt.Errorf("invalid accounts event code: %v", event.Code)
 	}
@@ -189,3 +243,13 @@ func NewV1(rc client.RuntimeClient) V1 {
 func NewTransferTx(fee *types.Fee, body *Transfer) *types.Transaction {
 	return types.NewTransaction(fee, methodTransfer, body)
 }
+
+// NewApproveTx generates a new accounts.Approve transaction.
+func NewApproveTx(fee *types.Fee, body *Approve) *types.Transaction {
+	return types.NewTransaction(fee, methodApprove, body)
+}
+
+// NewTransferFromTx generates a new accounts.TransferFrom transaction.
+func NewTransferFromTx(fee *types.Fee, body *TransferFrom) *types.Transaction {
+	return types.NewTransaction(fee, methodTransferFrom, body)
+}
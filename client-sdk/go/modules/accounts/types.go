@@ -23,6 +23,33 @@ func (f *Transfer) PrettyType() (interface{}, error) {
 	return f, nil
 }
 
+// Approve is the body for the accounts.Approve call.
+type Approve struct {
+	// Spender is the address that is allowed to transfer funds from the caller's account.
+	Spender types.Address   `json:"spender"`
+	// Amount is the maximum amount that can be transferred. Zero revokes the allowance of the
+	// denomination.
+	Amount  types.BaseUnits `json:"amount"`
+}
+
+// TransferFrom is the body for the accounts.TransferFrom call.
+type TransferFrom struct {
+	// From is the address of the account that approved the caller to transfer its funds.
+	From   types.Address   `json:"from"`
+	To     types.Address   `json:"to"`
+	Amount types.BaseUnits `json:"amount"`
+}
+
+// PrettyPrint writes a pretty-printed representation of the transaction to the given writer.
+func (f *TransferFrom) PrettyPrint(ctx context.Context, prefix string, w io.Writer) {
+	types.PrettyPrintToAmount(ctx, prefix, w, &f.To, f.Amount)
+}
+
+// PrettyType returns a representation of the type that can be used for pretty printing.
+func (f *TransferFrom) PrettyType() (interface{}, error) {
+	return f, nil
+}
+
 // NonceQuery are the arguments for the accounts.Nonce query.
 type NonceQuery struct {
 	Address types.Address `json:"address"`
@@ -41,6 +68,7 @@ type AccountBalances struct {
 // AddressesQuery are the arguments for the accounts.Addresses query.
 type AddressesQuery struct {
 	Denomination types.Denomination `json:"denomination"`
+	Pagination   types.PageRequest  `json:"pagination,omitempty"`
 }
 
 // DenominationInfoQuery are the arguments for the accounts.DenominationInfo query.
@@ -54,12 +82,25 @@ type DenominationInfo struct {
 	Decimals uint8 `json:"decimals"`
 }
 
+// AllowancesQuery are the arguments for the accounts.Allowances query.
+type AllowancesQuery struct {
+	Owner   types.Address `json:"owner"`
+	Spender types.Address `json:"spender"`
+}
+
+// Allowances are the allowances given by an owner to a spender.
+type Allowances struct {
+	Allowances map[types.Denomination]types.Quantity `json:"allowances"`
+}
+
 // Addresses is the response of the accounts.Addresses query.
 type Addresses []types.Address
 
 // GasCosts are the accounts module gas costs.
 type GasCosts struct {
-	TxTransfer uint64 `json:"tx_transfer"`
+	TxTransfer     uint64 `json:"tx_transfer"`
+	TxApprove      uint64 `json:"tx_approve,omitempty"`
+	TxTransferFrom uint64 `json:"tx_transfer_from,omitempty"`
 }
 
 // Parameters are the parameters for the accounts module.
@@ -80,6 +121,8 @@ const (
 	BurnEventCode = 2
 	// MintEventCode is the event code for the mint event.
 	MintEventCode = 3
+	// ApprovalEventCode is the event code for the approval event.
+	ApprovalEventCode = 8
 )
 
 // TransferEvent is the transfer event.
@@ -101,9 +144,17 @@ type MintEvent struct {
 	Amount types.BaseUnits `json:"amount"`
 }
 
+// ApprovalEvent is the approval event.
+type ApprovalEvent struct {
+	Owner   types.Address   `json:"owner"`
+	Spender types.Address   `json:"spender"`
+	Amount  types.BaseUnits `json:"amount"`
+}
+
 // Event is an account event.
 type Event struct {
 	Transfer *TransferEvent
 	Burn     *BurnEvent
 	Mint     *MintEvent
+	Approval *ApprovalEvent
 }
@@ -55,10 +55,12 @@ type V1 interface {
 	// Delegation queries the given delegation metadata based on a (from, to) address pair.
 	Delegation(ctx context.Context, round uint64, query *DelegationQuery) (*DelegationInfo, error)
 
-	// Delegations queries all delegation metadata originating from a given account.
+	// Delegations queries all delegation metadata originating from a given account, fetching
+	// all result pages.
 	Delegations(ctx context.Context, round uint64, query *DelegationsQuery) ([]*ExtendedDelegationInfo, error)
 
-	// Undelegations queries all undelegation metadata to a given account.
+	// Undelegations queries all undelegation metadata to a given account, fetching all result
+	// pages.
 	Undelegations(ctx context.Context, round uint64, query *UndelegationsQuery) ([]*UndelegationInfo, error)
 
 	// GetEvents returns all consensus accounts events emitted in a given block.
@@ -144,21 +146,35 @@ func (a *v1) Delegation(ctx context.Context, round uint64, query *DelegationQuer
 // Implements V1.
 func (a *v1) Delegations(ctx context.Context, round uint64, query *DelegationsQuery) ([]*ExtendedDelegationInfo, error) {
 	var dis []*ExtendedDelegationInfo
-	err := a.rc.Query(ctx, round, methodDelegations, query, &dis)
-	if err != nil {
-		return nil, err
+	q := *query
+	for {
+		var page types.PageResponse[*ExtendedDelegationInfo]
+		if err := a.rc.Query(ctx, round, methodDelegations, &q, &page); err != nil {
+			return nil, err
+		}
+		dis = append(dis, page.Items...)
+		if page.Next == nil {
+			return dis, nil
+		}
+		q.Pagination.Cursor = page.Next
 	}
-	return dis, nil
 }
 
 // Implements V1.
 func (a *v1) Undelegations(ctx context.Context, round uint64, query *UndelegationsQuery) ([]*UndelegationInfo, error) {
 	var udis []*UndelegationInfo
-	err := a.rc.Query(ctx, round, methodUndelegations, query, &udis)
-	if err != nil {
-		return nil, err
+	q := *query
+	for {
+		var page types.PageResponse[*UndelegationInfo]
+		if err := a.rc.Query(ctx, round, methodUndelegations, &q, &page); err != nil {
+			return nil, err
+		}
+		udis = append(udis, page.Items...)
+		if page.Next == nil {
+			return udis, nil
+		}
+		q.Pagination.Cursor = page.Next
 	}
-	return udis, nil
 }
 
 // Implements V1.
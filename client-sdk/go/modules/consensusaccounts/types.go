@@ -98,12 +98,14 @@ type DelegationQuery struct {
 
 // DelegationsQuery are the arguments for consensus.Delegations method.
 type DelegationsQuery struct {
-	From types.Address `json:"from"`
+	From       types.Address     `json:"from"`
+	Pagination types.PageRequest `json:"pagination,omitempty"`
 }
 
 // UndelegationsQuery are the arguments for consensus.Undelegations method.
 type UndelegationsQuery struct {
-	To types.Address `json:"to"`
+	To         types.Address     `json:"to"`
+	Pagination types.PageRequest `json:"pagination,omitempty"`
 }
 
 // DelegationInfo is information about a delegation.
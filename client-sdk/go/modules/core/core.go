@@ -12,12 +12,18 @@ import (
 
 var (
 	// Queries.
-	methodParameters        = types.NewMethodName("core.Parameters", nil)
-	methodEstimateGas       = types.NewMethodName("core.EstimateGas", EstimateGasQuery{})
-	methodMinGasPrice       = types.NewMethodName("core.MinGasPrice", nil)
-	methodRuntimeInfo       = types.NewMethodName("core.RuntimeInfo", nil)
-	methodCallDataPublicKey = types.NewMethodName("core.CallDataPublicKey", nil)
-	methodExecuteReadOnlyTx = types.NewMethodName("core.ExecuteReadOnlyTx", ExecuteReadOnlyTxQuery{})
+	methodParameters                = types.NewMethodName("core.Parameters", nil)
+	methodEstimateGas               = types.NewMethodName("core.EstimateGas", EstimateGasQuery{})
+	methodMinGasPrice               = types.NewMethodName("core.MinGasPrice", nil)
+	methodRuntimeInfo               = types.NewMethodName("core.RuntimeInfo", nil)
+	methodErrorCatalog              = types.NewMethodName("core.ErrorCatalog", nil)
+	methodRuntimeMetadata           = types.NewMethodName("core.RuntimeMetadata", nil)
+	methodCallDataPublicKey         = types.NewMethodName("core.CallDataPublicKey", nil)
+	methodCallDataPublicKeyValidity = types.NewMethodName("core.CallDataPublicKeyValidity", nil)
+	methodExecuteReadOnlyTx         = types.NewMethodName("core.ExecuteReadOnlyTx", ExecuteReadOnlyTxQuery{})
+	methodReceipt                   = types.NewMethodName("core.Receipt", ReceiptQuery{})
+	methodIndexedEvents             = types.NewMethodName("core.IndexedEvents", IndexedEventsQuery{})
+	methodParameterHistory          = types.NewMethodName("core.ParameterHistory", ParameterHistoryQuery{})
 )
 
 // V1 is the v1 core module interface.
@@ -41,11 +47,31 @@ type V1 interface {
 	// RuntimeInfo returns basic info about the module and the containing runtime.
 	RuntimeInfo(ctx context.Context) (*RuntimeInfoResponse, error)
 
+	// ErrorCatalog returns descriptions of all errors that can be emitted by the runtime.
+	ErrorCatalog(ctx context.Context) ([]*ErrorInfo, error)
+
+	// RuntimeMetadata returns schema metadata (methods and events) of all modules of the runtime.
+	RuntimeMetadata(ctx context.Context) (*RuntimeMetadataResponse, error)
+
 	// CallDataPublicKey returns the runtime's call data public key.
 	CallDataPublicKey(ctx context.Context) (*CallDataPublicKeyResponse, error)
 
+	// CallDataPublicKeyValidity returns the runtime's call data public key together with the
+	// range of epochs during which it is valid.
+	CallDataPublicKeyValidity(ctx context.Context) (*CallDataPublicKeyValidityResponse, error)
+
 	// ExecuteReadOnlyTx executes a read only transaction.
 	ExecuteReadOnlyTx(ctx context.Context, round uint64, tx *types.UnverifiedTransaction) (*ExecuteReadOnlyTxResponse, error)
+
+	// Receipt returns the execution receipt of the transaction with the given index in the given
+	// round.
+	Receipt(ctx context.Context, round uint64, index uint32) (*Receipt, error)
+
+	// IndexedEvents returns a page of events recorded in a secondary event index.
+	IndexedEvents(ctx context.Context, round uint64, query *IndexedEventsQuery) (*IndexedEventsResponse, error)
+
+	// ParameterHistory returns the recorded parameter changes of a module, oldest first.
+	ParameterHistory(ctx context.Context, round uint64, query *ParameterHistoryQuery) ([]*ParametersChange, error)
 }
 
 type v1 struct {
@@ -141,6 +167,22 @@ func DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {
 		for _, ev := range evs {
 			events = append(events, &Event{GasUsed: ev})
 		}
+	case ParametersChangedEventCode:
+		var evs []*ParametersChangedEvent
+		if err := cbor.Unmarshal(event.Value, &evs); err != nil {
+			return nil, fmt.Errorf("decode core parameters changed event value: %w", err)
+		}
+		for _, ev := range evs {
+			events = append(events, &Event{ParametersChanged: ev})
+		}
+	case MessageResultDroppedEventCode:
+		var evs []*MessageResultDroppedEvent
+		if err := cbor.Unmarshal(event.Value, &evs); err != nil {
+			return nil, fmt.Errorf("decode core message result dropped event value: %w", err)
+		}
+		for _, ev := range evs {
+			events = append(events, &Event{MessageResultDropped: ev})
+		}
 	default:
 		return nil, fmt.Errorf("invalid core event code: %v", event.Code)
 	}
@@ -157,6 +199,26 @@ func (a *v1) RuntimeInfo(ctx context.Context) (*RuntimeInfoResponse, error) {
 	return &info, nil
 }
 
+// Implements V1.
+func (a *v1) ErrorCatalog(ctx context.Context) ([]*ErrorInfo, error) {
+	var errors []*ErrorInfo
+	err := a.rc.Query(ctx, client.RoundLatest, methodErrorCatalog, nil, &errors)
+	if err != nil {
+		return nil, err
+	}
+	return errors, nil
+}
+
+// Implements V1.
+func (a *v1) RuntimeMetadata(ctx context.Context) (*RuntimeMetadataResponse, error) {
+	var metadata RuntimeMetadataResponse
+	err := a.rc.Query(ctx, client.RoundLatest, methodRuntimeMetadata, nil, &metadata)
+	if err != nil {
+		return nil, err
+	}
+	return &metadata, nil
+}
+
 // Implements V1.
 func (a *v1) CallDataPublicKey(ctx context.Context) (*CallDataPublicKeyResponse, error) {
 	var cdpk CallDataPublicKeyResponse
@@ -167,6 +229,16 @@ func (a *v1) CallDataPublicKey(ctx context.Context) (*CallDataPublicKeyResponse,
 	return &cdpk, nil
 }
 
+// Implements V1.
+func (a *v1) CallDataPublicKeyValidity(ctx context.Context) (*CallDataPublicKeyValidityResponse, error) {
+	var cdpk CallDataPublicKeyValidityResponse
+	err := a.rc.Query(ctx, client.RoundLatest, methodCallDataPublicKeyValidity, nil, &cdpk)
+	if err != nil {
+		return nil, err
+	}
+	return &cdpk, nil
+}
+
 // Implements V1.
 func (a *v1) ExecuteReadOnlyTx(ctx context.Context, round uint64, tx *types.UnverifiedTransaction) (*ExecuteReadOnlyTxResponse, error) {
 	var rsp ExecuteReadOnlyTxResponse
@@ -177,6 +249,36 @@ func (a *v1) ExecuteReadOnlyTx(ctx context.Context, round uint64, tx *types.Unve
 	return &rsp, nil
 }
 
+// Implements V1.
+func (a *v1) Receipt(ctx context.Context, round uint64, index uint32) (*Receipt, error) {
+	var receipt Receipt
+	err := a.rc.Query(ctx, client.RoundLatest, methodReceipt, ReceiptQuery{Round: round, Index: index}, &receipt)
+	if err != nil {
+		return nil, err
+	}
+	return &receipt, nil
+}
+
+// Implements V1.
+func (a *v1) IndexedEvents(ctx context.Context, round uint64, query *IndexedEventsQuery) (*IndexedEventsResponse, error) {
+	var rsp IndexedEventsResponse
+	err := a.rc.Query(ctx, round, methodIndexedEvents, query, &rsp)
+	if err != nil {
+		return nil, err
+	}
+	return &rsp, nil
+}
+
+// Implements V1.
+func (a *v1) ParameterHistory(ctx context.Context, round uint64, query *ParameterHistoryQuery) ([]*ParametersChange, error) {
+	var history []*ParametersChange
+	err := a.rc.Query(ctx, round, methodParameterHistory, query, &history)
+	if err != nil {
+		return nil, err
+	}
+	return history, nil
+}
+
 // NewV1 generates a V1 client helper for the core module.
 func NewV1(rc client.RuntimeClient) V1 {
 	return &v1{rc: rc}
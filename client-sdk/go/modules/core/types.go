@@ -2,6 +2,7 @@ package core
 
 import (
 	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+	"github.com/oasisprotocol/oasis-core/go/common/crypto/hash"
 	"github.com/oasisprotocol/oasis-core/go/common/quantity"
 	"github.com/oasisprotocol/oasis-core/go/common/version"
 
@@ -47,6 +48,10 @@ const ModuleName = "core"
 const (
 	// GasUsedEventCode is the event code for the gas used event.
 	GasUsedEventCode = 1
+	// ParametersChangedEventCode is the event code for the parameters changed event.
+	ParametersChangedEventCode = 3
+	// MessageResultDroppedEventCode is the event code for the message result dropped event.
+	MessageResultDroppedEventCode = 4
 )
 
 // GasUsedEvent is a gas used event.
@@ -54,9 +59,35 @@ type GasUsedEvent struct {
 	Amount uint64 `json:"amount"`
 }
 
+// ParameterChange is a change of a single top-level module parameter.
+type ParameterChange struct {
+	// Key is the name of the parameter.
+	Key string `json:"key"`
+	// Old is the CBOR-encoded value before the change, if the parameter was set.
+	Old cbor.RawMessage `json:"old,omitempty"`
+	// New is the CBOR-encoded value after the change, if the parameter is set.
+	New cbor.RawMessage `json:"new,omitempty"`
+}
+
+// ParametersChangedEvent is a module parameters changed event.
+type ParametersChangedEvent struct {
+	Module  string            `json:"module"`
+	Changes []ParameterChange `json:"changes"`
+}
+
+// MessageResultDroppedEvent is emitted for each result of a consensus message that has been
+// dropped without being handled.
+type MessageResultDroppedEvent struct {
+	HookName string `json:"hook_name"`
+	Round    uint64 `json:"round"`
+	Index    uint32 `json:"index"`
+}
+
 // Event is a core module event.
 type Event struct {
-	GasUsed *GasUsedEvent
+	GasUsed              *GasUsedEvent
+	ParametersChanged    *ParametersChangedEvent
+	MessageResultDropped *MessageResultDroppedEvent
 }
 
 // RuntimeInfoResponse is the response of the core.RuntimeInfo query
@@ -67,6 +98,10 @@ type RuntimeInfoResponse struct {
 	StateVersion uint32 `json:"state_version"`
 	// Modules are the SDK modules that comprise this runtime.
 	Modules map[string]ModuleInfo `json:"modules"`
+	// Confidential indicates whether the runtime is confidential.
+	Confidential bool `json:"confidential,omitempty"`
+	// TransactionVersions are the supported transaction format versions.
+	TransactionVersions []uint16 `json:"transaction_versions,omitempty"`
 }
 
 // ModuleInfo is the information about a single module within the runtime.
@@ -75,8 +110,135 @@ type ModuleInfo struct {
 	Version uint32 `json:"version"`
 	// Params are the initial parameters of the module.
 	Params cbor.RawMessage `json:"params"`
+	// ParamsDigest is the hash of the CBOR-encoded module parameters.
+	ParamsDigest hash.Hash `json:"params_digest"`
 	// Methods are the RPC methods exposed by the module.
 	Methods []MethodHandlerInfo `json:"methods"`
+	// Features are the enabled module features, keyed by feature name.
+	Features map[string]cbor.RawMessage `json:"features,omitempty"`
+}
+
+// ErrorInfo is the description of an error that can be emitted by a module.
+type ErrorInfo struct {
+	// Module is the name of the module that emits the error.
+	Module string `json:"module"`
+	// Code is the error code, unique within the module.
+	Code uint32 `json:"code"`
+	// Name is the name of the error.
+	Name string `json:"name"`
+	// Message is the human-readable message template.
+	Message string `json:"message"`
+}
+
+// RuntimeMetadataResponse is the response of the core.RuntimeMetadata query.
+type RuntimeMetadataResponse struct {
+	RuntimeVersion *version.Version `json:"runtime_version"`
+	// Modules are the schema metadata of the SDK modules that comprise this runtime.
+	Modules map[string]ModuleMetadata `json:"modules"`
+}
+
+// ModuleMetadata is the schema metadata of a single module within the runtime.
+type ModuleMetadata struct {
+	// Version is the version of the module.
+	Version uint32 `json:"version"`
+	// Methods are the RPC methods exposed by the module.
+	Methods []MethodMetadata `json:"methods"`
+	// Events are the events that can be emitted by the module.
+	Events []EventInfo `json:"events"`
+	// Types are the schemas of the types referenced by methods and events, keyed by type name.
+	Types map[string]TypeSchema `json:"types,omitempty"`
+}
+
+// MethodMetadata describes a single RPC together with its argument and result types.
+type MethodMetadata struct {
+	// Name is the name of the RPC.
+	Name string `json:"name"`
+	// Kind is the kind of the RPC.
+	Kind methodHandlerKind `json:"kind"`
+	// Args is the name of the argument type in the module's type schemas.
+	Args string `json:"args"`
+	// Result is the name of the result type in the module's type schemas.
+	Result string `json:"result"`
+	// IntroducedIn is the module version in which the method was introduced, if any.
+	IntroducedIn *uint32 `json:"introduced_in,omitempty"`
+	// DeprecatedSince is the module version since which the method is deprecated, if any.
+	DeprecatedSince *uint32 `json:"deprecated_since,omitempty"`
+}
+
+// EventInfo is the description of an event that can be emitted by a module.
+type EventInfo struct {
+	// Module is the name of the module that emits the event.
+	Module string `json:"module"`
+	// Code is the event code, unique within the module.
+	Code uint32 `json:"code"`
+	// Name is the name of the event.
+	Name string `json:"name"`
+	// Fields are the fields of the event in declaration order.
+	Fields []EventFieldInfo `json:"fields,omitempty"`
+}
+
+// EventFieldInfo is the description of an event field.
+type EventFieldInfo struct {
+	// Name is the name of the field, empty for unnamed fields.
+	Name string `json:"name,omitempty"`
+	// Type is the name of the field's type in the module's type schemas.
+	Type string `json:"ty"`
+}
+
+// TypeKind is the kind of a type schema.
+type TypeKind string
+
+// These constants represent the kinds of type schemas.
+const (
+	TypeKindUnit     TypeKind = "unit"
+	TypeKindBool     TypeKind = "bool"
+	TypeKindUint     TypeKind = "uint"
+	TypeKindInt      TypeKind = "int"
+	TypeKindString   TypeKind = "string"
+	TypeKindBytes    TypeKind = "bytes"
+	TypeKindArray    TypeKind = "array"
+	TypeKindOptional TypeKind = "optional"
+	TypeKindMap      TypeKind = "map"
+	TypeKindTuple    TypeKind = "tuple"
+	TypeKindStruct   TypeKind = "struct"
+	TypeKindEnum     TypeKind = "enum"
+	TypeKindOpaque   TypeKind = "opaque"
+)
+
+// TypeSchema is the schema of a type referenced by a module.
+type TypeSchema struct {
+	// Kind is the kind of the type.
+	Kind TypeKind `json:"kind"`
+	// Size is the size in bits of integers, or the fixed length of byte strings and arrays.
+	Size uint64 `json:"size,omitempty"`
+	// Items are the names of the element types of arrays, optionals, maps and tuples.
+	Items []string `json:"items,omitempty"`
+	// Fields are the fields of structs in declaration order.
+	Fields []FieldSchema `json:"fields,omitempty"`
+	// Variants are the variants of enums in declaration order.
+	Variants []VariantSchema `json:"variants,omitempty"`
+	// Untagged indicates whether enum variants are encoded without the variant name.
+	Untagged bool `json:"untagged,omitempty"`
+}
+
+// FieldSchema is the schema of a struct field.
+type FieldSchema struct {
+	// Name is the key under which the field is encoded.
+	Name string `json:"name"`
+	// Type is the name of the field's type.
+	Type string `json:"ty"`
+	// Optional indicates whether the field is omitted from the encoding when empty.
+	Optional bool `json:"optional,omitempty"`
+}
+
+// VariantSchema is the schema of an enum variant.
+type VariantSchema struct {
+	// Name is the key under which the variant is encoded, empty for embedded enums.
+	Name string `json:"name"`
+	// Discriminant is the integer under which the unit variant is encoded, if any.
+	Discriminant *uint64 `json:"discriminant,omitempty"`
+	// Type is the name of the variant's inner type, if any.
+	Type *string `json:"ty,omitempty"`
 }
 
 // MethodHandlerInfo describes a single RPC.
@@ -102,6 +264,23 @@ type CallDataPublicKeyResponse struct {
 	PublicKey types.SignedPublicKey `json:"public_key"`
 	// Epoch is the epoch of the ephemeral runtime key.
 	Epoch uint64 `json:"epoch,omitempty"`
+	// Formats are the encrypted call formats supported by the runtime, in order of preference.
+	Formats []types.CallFormat `json:"formats,omitempty"`
+}
+
+// CallDataPublicKeyValidityResponse is the response of the core.CallDataPublicKeyValidity query.
+type CallDataPublicKeyValidityResponse struct {
+	// PublicKey is the signed runtime call data public key.
+	PublicKey types.SignedPublicKey `json:"public_key"`
+	// Epoch is the epoch of the ephemeral runtime key, which is also the first epoch in which it
+	// is used.
+	Epoch uint64 `json:"epoch"`
+	// RotateAfter is the last epoch in which the key is used for encrypting new calls.
+	RotateAfter uint64 `json:"rotate_after"`
+	// ValidUntil is the last epoch in which calls encrypted with the key are accepted.
+	ValidUntil uint64 `json:"valid_until"`
+	// Formats are the encrypted call formats supported by the runtime, in order of preference.
+	Formats []types.CallFormat `json:"formats,omitempty"`
 }
 
 // ExecuteReadOnlyTxQuery is the body of the core.ExecuteReadOnlyTx query.
@@ -113,3 +292,78 @@ type ExecuteReadOnlyTxQuery struct {
 type ExecuteReadOnlyTxResponse struct {
 	Result types.CallResult `json:"result"`
 }
+
+// ReceiptQuery is the body of the core.Receipt query.
+type ReceiptQuery struct {
+	Round uint64 `json:"round"`
+	Index uint32 `json:"index"`
+}
+
+// Receipt is the execution receipt of a transaction.
+type Receipt struct {
+	// Success is true if the transaction succeeded.
+	Success bool `json:"success"`
+	// Module is the module of the error in case the transaction failed.
+	Module string `json:"module,omitempty"`
+	// Code is the error code in case the transaction failed.
+	Code uint32 `json:"code,omitempty"`
+	// GasUsed is the amount of gas used by the transaction.
+	GasUsed uint64 `json:"gas_used"`
+	// EventsRoot is the Merkle root of the events emitted by the transaction.
+	EventsRoot hash.Hash `json:"events_root"`
+	// MessagesRoot is the Merkle root of the messages emitted by the transaction.
+	MessagesRoot hash.Hash `json:"messages_root"`
+}
+
+// PageRequest is a request for a page of query results.
+type PageRequest = types.PageRequest
+
+// IndexedEventsQuery is the body of the core.IndexedEvents query.
+type IndexedEventsQuery struct {
+	// Module is the name of the module that emitted the events.
+	Module string `json:"module"`
+	// Code is the code of the events.
+	Code uint32 `json:"code"`
+	// Field is the name of the indexed event field.
+	Field string `json:"field"`
+	// Value is the CBOR-encoded value of the indexed event field to look up.
+	Value cbor.RawMessage `json:"value"`
+	// Pagination selects the page of results to return.
+	Pagination PageRequest `json:"pagination,omitempty"`
+}
+
+// IndexedEvent is an event recorded in a secondary event index.
+type IndexedEvent struct {
+	// Round is the round in which the event was emitted.
+	Round uint64 `json:"round"`
+	// TxIndex is the index of the transaction that emitted the event within the round.
+	TxIndex uint32 `json:"tx_index"`
+	// Value is the CBOR-encoded value of the event.
+	Value cbor.RawMessage `json:"value"`
+}
+
+// IndexedEventsResponse is the response of the core.IndexedEvents query.
+type IndexedEventsResponse struct {
+	// Items are the indexed events in the page.
+	Items []IndexedEvent `json:"items"`
+	// Next is the cursor for fetching the next page, if any.
+	Next []byte `json:"next,omitempty"`
+}
+
+// ParameterHistoryQuery is the body of the core.ParameterHistory query.
+type ParameterHistoryQuery struct {
+	// Module is the name of the module.
+	Module string `json:"module"`
+	// FromRound is the first round to include.
+	FromRound uint64 `json:"from_round,omitempty"`
+	// ToRound is the last round to include. Zero means that no upper bound is applied.
+	ToRound uint64 `json:"to_round,omitempty"`
+}
+
+// ParametersChange is a recorded change of module parameters.
+type ParametersChange struct {
+	// Round is the round in which the parameters changed.
+	Round uint64 `json:"round"`
+	// Changes are the changed parameters.
+	Changes []ParameterChange `json:"changes"`
+}
@@ -0,0 +1,174 @@
+package evm
+
+import (
+	"context"
+	"fmt"
+	"math/big"
+
+	"github.com/ethereum/go-ethereum/accounts/abi"
+	"github.com/ethereum/go-ethereum/common/math"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/client"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+const (
+	// DefaultViewCallGasLimit is the default gas limit of confidential view calls.
+	DefaultViewCallGasLimit = 30_000_000
+	// DefaultLeashBlockRange is the default number of blocks for which a signed view call is
+	// valid.
+	DefaultLeashBlockRange = 15
+
+	methodAccountsNonce = "accounts.Nonce"
+)
+
+type accountsNonceQuery struct {
+	Address types.Address `json:"address"`
+}
+
+// ConfidentialViewCaller performs signed and encrypted view calls against confidential EVM
+// contracts using Ethereum-signed call data, so that no runtime signer is needed.
+//
+// Calls are signed by the caller so that contracts can rely on msg.sender and both the call data
+// and the results are encrypted with the runtime call data public key, using the call format
+// configured on the confidential call builder. The key is cached by the confidential call builder
+// and in case a call fails after the key has been rotated, the key is re-fetched and the call is
+// retried once. Calls of other modules can use client.TransactionBuilder.ConfidentialViewCall.
+type ConfidentialViewCaller struct {
+	rc      client.RuntimeClient
+	ccb     *client.ConfidentialCallBuilder
+	signer  RSVSigner
+	caller  []byte
+	chainID uint64
+
+	gasLimit   uint64
+	blockRange uint64
+}
+
+// NewConfidentialViewCaller creates a new confidential view caller for the given Ethereum caller
+// address. The signer must produce signatures for the caller address.
+func NewConfidentialViewCaller(rc client.RuntimeClient, ccb *client.ConfidentialCallBuilder, chainID uint64, caller []byte, signer RSVSigner) *ConfidentialViewCaller {
+	return &ConfidentialViewCaller{
+		rc:         rc,
+		ccb:        ccb,
+		signer:     signer,
+		caller:     caller,
+		chainID:    chainID,
+		gasLimit:   DefaultViewCallGasLimit,
+		blockRange: DefaultLeashBlockRange,
+	}
+}
+
+// SetGasLimit sets the gas limit of view calls.
+func (c *ConfidentialViewCaller) SetGasLimit(gasLimit uint64) *ConfidentialViewCaller {
+	c.gasLimit = gasLimit
+	return c
+}
+
+// SetBlockRange sets the number of blocks for which signed view calls are valid.
+func (c *ConfidentialViewCaller) SetBlockRange(blockRange uint64) *ConfidentialViewCaller {
+	c.blockRange = blockRange
+	return c
+}
+
+// Call performs a signed and encrypted view call of the given contract and returns the
+// decrypted EVM return data.
+func (c *ConfidentialViewCaller) Call(ctx context.Context, address []byte, value *big.Int, data []byte) ([]byte, error) {
+	var result []byte
+	err := c.ccb.RetryOnRotation(ctx, func() (err error) {
+		result, err = c.call(ctx, address, value, data)
+		return err
+	})
+	if err != nil {
+		return nil, err
+	}
+	return result, nil
+}
+
+func (c *ConfidentialViewCaller) call(ctx context.Context, address []byte, value *big.Int, data []byte) ([]byte, error) {
+	blk, err := c.rc.GetBlock(ctx, client.RoundLatest)
+	if err != nil {
+		return nil, fmt.Errorf("failed to fetch latest block: %w", err)
+	}
+	round := blk.Header.Round
+
+	var nonce uint64
+	if err = c.rc.Query(ctx, round, methodAccountsNonce, &accountsNonceQuery{Address: types.NewAddressFromEth(c.caller)}, &nonce); err != nil {
+		return nil, fmt.Errorf("failed to query caller nonce: %w", err)
+	}
+	blockHash := blk.Header.EncodedHash()
+	leash := Leash{
+		Nonce:       nonce,
+		BlockNumber: round,
+		BlockHash:   blockHash[:],
+		BlockRange:  c.blockRange,
+	}
+
+	if value == nil {
+		value = big.NewInt(0)
+	}
+	pack, err := NewSignedCallDataPack(c.signer, c.chainID, c.caller, address, c.gasLimit, nil, value, data, leash)
+	if err != nil {
+		return nil, err
+	}
+	encoded, meta, err := c.ccb.EncodeCall(ctx, &pack.Data)
+	if err != nil {
+		return nil, err
+	}
+	pack.Data = *encoded
+
+	raw, err := NewV1(c.rc).SimulateCall(
+		ctx,
+		round,
+		math.U256Bytes(big.NewInt(0)),
+		c.gasLimit,
+		c.caller,
+		address,
+		math.U256Bytes(new(big.Int).Set(value)),
+		cbor.Marshal(pack),
+	)
+	if err != nil {
+		return nil, err
+	}
+
+	var encResult types.CallResult
+	if err = cbor.Unmarshal(raw, &encResult); err != nil {
+		return nil, fmt.Errorf("failed to unmarshal encrypted call result: %w", err)
+	}
+	result, err := c.ccb.DecodeResult(&encResult, meta)
+	if err != nil {
+		return nil, err
+	}
+	switch {
+	case result.IsUnknown():
+		return nil, fmt.Errorf("got unknown result")
+	case result.IsSuccess():
+		var output []byte
+		if err = cbor.Unmarshal(result.Ok, &output); err != nil {
+			return nil, fmt.Errorf("failed to unmarshal call result: %w", err)
+		}
+		return output, nil
+	default:
+		return nil, result.Failed
+	}
+}
+
+// CallMethod performs a signed and encrypted view call of the given contract method and returns
+// the decrypted results unpacked according to the contract ABI.
+func (c *ConfidentialViewCaller) CallMethod(ctx context.Context, address []byte, contractABI *abi.ABI, method string, args ...interface{}) ([]interface{}, error) {
+	data, err := contractABI.Pack(method, args...)
+	if err != nil {
+		return nil, fmt.Errorf("failed to pack call data: %w", err)
+	}
+	output, err := c.Call(ctx, address, nil, data)
+	if err != nil {
+		return nil, err
+	}
+	results, err := contractABI.Unpack(method, output)
+	if err != nil {
+		return nil, fmt.Errorf("failed to unpack results: %w", err)
+	}
+	return results, nil
+}
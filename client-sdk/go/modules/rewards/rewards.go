@@ -2,24 +2,53 @@ package rewards
 
 import (
 	"context"
+	"fmt"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
 
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/client"
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
 )
 
-// Queries.
-var methodParameters = types.NewMethodName("rewards.Parameters", nil)
+var (
+	// Callable methods.
+	methodRegisterInfraction = types.NewMethodName("rewards.RegisterInfraction", Infraction{})
+
+	// Queries.
+	methodParameters       = types.NewMethodName("rewards.Parameters", nil)
+	methodEmissionSchedule = types.NewMethodName("rewards.EmissionSchedule", EmissionScheduleQuery{})
+	methodPendingPenalties = types.NewMethodName("rewards.PendingPenalties", PendingPenaltiesQuery{})
+)
 
 // V1 is the v1 rewards module interface.
 type V1 interface {
+	client.EventDecoder
+
+	// RegisterInfraction generates a rewards.RegisterInfraction transaction.
+	RegisterInfraction(infraction *Infraction) *client.TransactionBuilder
+
 	// Parameters queries the rewards module parameters.
 	Parameters(ctx context.Context, round uint64) (*Parameters, error)
+
+	// EmissionSchedule queries the projected per-entity reward emission for upcoming epochs.
+	EmissionSchedule(ctx context.Context, round uint64, query *EmissionScheduleQuery) (*EmissionSchedule, error)
+
+	// PendingPenalties queries the penalties pending application at reward distribution time.
+	PendingPenalties(ctx context.Context, round uint64, query *PendingPenaltiesQuery) ([]*PendingPenalty, error)
+
+	// GetEvents returns all rewards events emitted in a given block.
+	GetEvents(ctx context.Context, round uint64) ([]*Event, error)
 }
 
 type v1 struct {
 	rc client.RuntimeClient
 }
 
+// Implements V1.
+func (a *v1) RegisterInfraction(infraction *Infraction) *client.TransactionBuilder {
+	return client.NewTransactionBuilder(a.rc, methodRegisterInfraction, infraction)
+}
+
 // Implements V1.
 func (a *v1) Parameters(ctx context.Context, round uint64) (*Parameters, error) {
 	var params Parameters
@@ -30,6 +59,91 @@ func (a *v1) Parameters(ctx context.Context, round uint64) (*Parameters, error)
 	return &params, nil
 }
 
+// Implements V1.
+func (a *v1) EmissionSchedule(ctx context.Context, round uint64, query *EmissionScheduleQuery) (*EmissionSchedule, error) {
+	var schedule EmissionSchedule
+	err := a.rc.Query(ctx, round, methodEmissionSchedule, query, &schedule)
+	if err != nil {
+		return nil, err
+	}
+	return &schedule, nil
+}
+
+// Implements V1.
+func (a *v1) PendingPenalties(ctx context.Context, round uint64, query *PendingPenaltiesQuery) ([]*PendingPenalty, error) {
+	var penalties []*PendingPenalty
+	var q PendingPenaltiesQuery
+	if query != nil {
+		q = *query
+	}
+	for {
+		var page types.PageResponse[*PendingPenalty]
+		if err := a.rc.Query(ctx, round, methodPendingPenalties, &q, &page); err != nil {
+			return nil, err
+		}
+		penalties = append(penalties, page.Items...)
+		if page.Next == nil {
+			return penalties, nil
+		}
+		q.Pagination.Cursor = page.Next
+	}
+}
+
+// Implements V1.
+func (a *v1) GetEvents(ctx context.Context, round uint64) ([]*Event, error) {
+	rawEvs, err := a.rc.GetEventsRaw(ctx, round)
+	if err != nil {
+		return nil, err
+	}
+
+	evs := make([]*Event, 0)
+	for _, rawEv := range rawEvs {
+		ev, err := a.DecodeEvent(rawEv)
+		if err != nil {
+			return nil, err
+		}
+		for _, e := range ev {
+			evs = append(evs, e.(*Event))
+		}
+	}
+
+	return evs, nil
+}
+
+// Implements client.EventDecoder.
+func (a *v1) DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {
+	return DecodeEvent(event)
+}
+
+// DecodeEvent decodes a rewards event.
+func DecodeEvent(event *types.Event) ([]client.DecodedEvent, error) {
+	if event.Module != ModuleName {
+		return nil, nil
+	}
+	var events []client.DecodedEvent
+	switch event.Code {
+	case InfractionRegisteredEventCode:
+		var evs []*InfractionRegisteredEvent
+		if err := cbor.Unmarshal(event.Value, &evs); err != nil {
+			return nil, fmt.Errorf("decode rewards infraction registered event value: %w", err)
+		}
+		for _, ev := range evs {
+			events = append(events, &Event{InfractionRegistered: ev})
+		}
+	case RewardPenalizedEventCode:
+		var evs []*RewardPenalizedEvent
+		if err := cbor.Unmarshal(event.Value, &evs); err != nil {
+			return nil, fmt.Errorf("decode rewards reward penalized event value: %w", err)
+		}
+		for _, ev := range evs {
+			events = append(events, &Event{RewardPenalized: ev})
+		}
+	default:
+		return nil, fmt.Errorf("invalid rewards event code: %v", event.Code)
+	}
+	return events, nil
+}
+
 // NewV1 generates a V1 client helper for the rewards module.
 func NewV1(rc client.RuntimeClient) V1 {
 	return &v1{rc: rc}
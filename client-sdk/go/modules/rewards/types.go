@@ -6,13 +6,45 @@ import (
 	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
 )
 
+// ModuleName is the rewards module name.
+const ModuleName = "rewards"
+
 // RewardPoolAddress is the address of the reward pool.
-var RewardPoolAddress = types.NewAddressForModule("rewards", []byte("reward-pool"))
+var RewardPoolAddress = types.NewAddressForModule(ModuleName, []byte("reward-pool"))
+
+// HalvingCurve halves the reward amount every Interval epochs.
+type HalvingCurve struct {
+	Interval beacon.EpochTime `json:"interval"`
+}
+
+// LinearDecayCurve linearly decreases the reward amount towards EndAmount which is reached at
+// the end of the step.
+type LinearDecayCurve struct {
+	EndAmount types.Quantity `json:"end_amount"`
+}
+
+// ExponentialDecayCurve multiplies the reward amount by Numerator / Denominator every epoch.
+type ExponentialDecayCurve struct {
+	Numerator   uint64 `json:"numerator"`
+	Denominator uint64 `json:"denominator"`
+}
+
+// RewardCurve is the curve describing how the reward amount changes over the epochs of a reward
+// step. Exactly one of the fields must be set.
+type RewardCurve struct {
+	Halving          *HalvingCurve          `json:"halving,omitempty"`
+	LinearDecay      *LinearDecayCurve      `json:"linear_decay,omitempty"`
+	ExponentialDecay *ExponentialDecayCurve `json:"exponential_decay,omitempty"`
+}
 
 // RewardStep is one of the time periods in the reward schedule.
 type RewardStep struct {
 	Until  beacon.EpochTime `json:"until"`
 	Amount types.BaseUnits  `json:"amount"`
+
+	// Curve is the curve applied to the amount over the epochs of the step. Nil means that the
+	// amount is constant.
+	Curve *RewardCurve `json:"curve,omitempty"`
 }
 
 // RewardSchedule is a reward schedule.
@@ -26,4 +58,125 @@ type Parameters struct {
 
 	ParticipationThresholdNumerator   uint64 `json:"participation_threshold_numerator"`
 	ParticipationThresholdDenominator uint64 `json:"participation_threshold_denominator"`
+
+	// MinParticipationRounds is the minimum number of rounds in an epoch in which an entity must
+	// have participated in order to be eligible for rewards for that epoch.
+	MinParticipationRounds uint64 `json:"min_participation_rounds,omitempty"`
+
+	// Delegation are the delegation-aware reward distribution parameters. Nil means that all
+	// rewards are paid to the entity address.
+	Delegation *DelegationParameters `json:"delegation,omitempty"`
+
+	// Penalties are the reward penalty parameters.
+	Penalties PenaltyParameters `json:"penalties,omitempty"`
+}
+
+// CommissionRateDenominator is the denominator of reward commission rates.
+const CommissionRateDenominator = 100_000
+
+// DelegationParameters are the parameters of delegation-aware reward distribution.
+//
+// Rewards of entities that have delegations in the consensus accounts module are split between
+// the entity, which retains its commission, and its delegators in proportion to their shares.
+type DelegationParameters struct {
+	// DefaultCommissionRate is the commission rate retained by entities, in units of
+	// 1/CommissionRateDenominator.
+	DefaultCommissionRate uint64 `json:"default_commission_rate"`
+	// CommissionRates are per-entity commission rates overriding the default commission rate.
+	CommissionRates map[types.Address]uint64 `json:"commission_rates,omitempty"`
+}
+
+// PenaltyRateDenominator is the denominator of penalty rates.
+const PenaltyRateDenominator = 100_000
+
+// PenaltyParameters are the parameters of reward penalties.
+type PenaltyParameters struct {
+	// Reporters are the addresses allowed to register infractions via transactions.
+	Reporters []types.Address `json:"reporters,omitempty"`
+	// TxRegisterInfraction is the gas cost of the register infraction transaction.
+	TxRegisterInfraction uint64 `json:"tx_register_infraction,omitempty"`
+}
+
+// Infraction is an infraction registered against an address, reducing its reward for the given
+// epoch.
+type Infraction struct {
+	Address types.Address    `json:"address"`
+	Epoch   beacon.EpochTime `json:"epoch"`
+	// Rate is the fraction of the reward that is withheld, in units of 1/PenaltyRateDenominator.
+	Rate uint64 `json:"rate"`
+	// Reason is an optional description of the infraction.
+	Reason string `json:"reason,omitempty"`
+}
+
+// Penalty is the penalty accumulated from all infractions registered against an address for an
+// epoch.
+type Penalty struct {
+	// Rate is the fraction of the reward that is withheld, in units of 1/PenaltyRateDenominator.
+	Rate uint64 `json:"rate"`
+	// Infractions is the number of registered infractions.
+	Infractions uint64 `json:"infractions"`
+}
+
+// PendingPenalty is a penalty pending application at reward distribution time.
+type PendingPenalty struct {
+	Address types.Address    `json:"address"`
+	Epoch   beacon.EpochTime `json:"epoch"`
+	Penalty Penalty          `json:"penalty"`
+}
+
+// PendingPenaltiesQuery is the pending penalties query.
+type PendingPenaltiesQuery struct {
+	// Address restricts the results to penalties of the given address.
+	Address *types.Address `json:"address,omitempty"`
+	// Pagination selects the page of results to return.
+	Pagination types.PageRequest `json:"pagination,omitempty"`
+}
+
+// EmissionScheduleQuery is the emission schedule query.
+type EmissionScheduleQuery struct {
+	// From is the first epoch to project the emission for. Nil means the current epoch.
+	From *beacon.EpochTime `json:"from,omitempty"`
+	// Limit is the maximum number of epochs to project. Zero means the maximum allowed number.
+	Limit uint64 `json:"limit,omitempty"`
+}
+
+// EpochEmission is the per-entity reward emitted in a given epoch.
+type EpochEmission struct {
+	Epoch  beacon.EpochTime `json:"epoch"`
+	Amount types.BaseUnits  `json:"amount"`
+}
+
+// EmissionSchedule is the projected emission schedule.
+type EmissionSchedule struct {
+	// Epochs are the per-entity rewards for the projected epochs. Epochs after the end of the
+	// reward schedule are omitted.
+	Epochs []EpochEmission `json:"epochs"`
+}
+
+const (
+	// InfractionRegisteredEventCode is the event code for the infraction registered event.
+	InfractionRegisteredEventCode = 1
+	// RewardPenalizedEventCode is the event code for the reward penalized event.
+	RewardPenalizedEventCode = 2
+)
+
+// InfractionRegisteredEvent is an infraction registered event.
+type InfractionRegisteredEvent struct {
+	Address types.Address    `json:"address"`
+	Epoch   beacon.EpochTime `json:"epoch"`
+	Rate    uint64           `json:"rate"`
+	Reason  string           `json:"reason,omitempty"`
+}
+
+// RewardPenalizedEvent is a reward penalized event.
+type RewardPenalizedEvent struct {
+	Address  types.Address    `json:"address"`
+	Epoch    beacon.EpochTime `json:"epoch"`
+	Withheld types.BaseUnits  `json:"withheld"`
+}
+
+// Event is a rewards module event.
+type Event struct {
+	InfractionRegistered *InfractionRegisteredEvent
+	RewardPenalized      *RewardPenalizedEvent
 }
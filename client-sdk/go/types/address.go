@@ -2,6 +2,7 @@ package types
 
 import (
 	"encoding"
+	"fmt"
 
 	"golang.org/x/crypto/sha3"
 
@@ -90,6 +91,22 @@ func NewSignatureAddressSpecSr25519(pk sr25519.PublicKey) SignatureAddressSpec {
 	return SignatureAddressSpec{Sr25519: &pk}
 }
 
+// NewSignatureAddressSpecFromPublicKey creates a new address specification for the given public key.
+//
+// Secp256k1 public keys use the Ethereum-compatible address derivation.
+func NewSignatureAddressSpecFromPublicKey(pk sdkSignature.PublicKey) (SignatureAddressSpec, error) {
+	switch pk := pk.(type) {
+	case ed25519.PublicKey:
+		return NewSignatureAddressSpecEd25519(pk), nil
+	case secp256k1.PublicKey:
+		return NewSignatureAddressSpecSecp256k1Eth(pk), nil
+	case sr25519.PublicKey:
+		return NewSignatureAddressSpecSr25519(pk), nil
+	default:
+		return SignatureAddressSpec{}, fmt.Errorf("unsupported public key type: %T", pk)
+	}
+}
+
 // Address is the account address.
 type Address address.Address
 
@@ -29,6 +29,23 @@ func TestAddressSecp256k1Eth(t *testing.T) {
 	require.EqualValues("oasis1qzd7akz24n6fxfhdhtk977s5857h3c6gf5583mcg", addr.String())
 }
 
+func TestNewSignatureAddressSpecFromPublicKey(t *testing.T) {
+	require := require.New(t)
+
+	edPk := ed25519.NewPublicKey("utrdHlX///////////////////////////////////8=")
+	spec, err := NewSignatureAddressSpecFromPublicKey(edPk)
+	require.NoError(err)
+	require.EqualValues(NewSignatureAddressSpecEd25519(edPk), spec)
+
+	secpPk := secp256k1.NewPublicKey("Arra3R5V////////////////////////////////////")
+	spec, err = NewSignatureAddressSpecFromPublicKey(secpPk)
+	require.NoError(err)
+	require.EqualValues(NewSignatureAddressSpecSecp256k1Eth(secpPk), spec)
+
+	_, err = NewSignatureAddressSpecFromPublicKey(nil)
+	require.Error(err)
+}
+
 func TestAddressMultisig(t *testing.T) {
 	require := require.New(t)
 
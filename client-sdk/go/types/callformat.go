@@ -17,10 +17,51 @@ type CallEnvelopeX25519DeoxysII struct {
 	Data []byte `json:"data"`
 }
 
+// ResultHeader is the plaintext header of an encrypted call result.
+//
+// It is authenticated as the additional data of the sealed result box.
+type ResultHeader struct {
+	// Success is whether the call was successful.
+	Success bool `json:"success"`
+	// Module is the module name of the failed call.
+	Module string `json:"module,omitempty"`
+	// Code is the error code of the failed call.
+	Code uint32 `json:"code,omitempty"`
+}
+
 // ResultEnvelopeX25519DeoxysII is a result envelope when using the EncryptedX25519DeoxysII format.
 type ResultEnvelopeX25519DeoxysII struct {
 	// Nonce.
 	Nonce [deoxysii.NonceSize]byte `json:"nonce"`
 	// Data is the encrypted result data.
 	Data []byte `json:"data"`
+	// Header is the authenticated plaintext result header.
+	Header *ResultHeader `json:"header,omitempty"`
+}
+
+// XChaCha20Poly1305NonceSize is the size of the XChaCha20-Poly1305 nonce in bytes.
+const XChaCha20Poly1305NonceSize = 24
+
+// CallEnvelopeX25519XChaCha20Poly1305 is a call envelope when using the
+// EncryptedX25519XChaCha20Poly1305 format.
+type CallEnvelopeX25519XChaCha20Poly1305 struct {
+	// Pk is the caller's ephemeral public key used for X25519.
+	Pk x25519.PublicKey `json:"pk"`
+	// Nonce. Since XChaCha20-Poly1305 is not nonce-misuse resistant, it must be random.
+	Nonce [XChaCha20Poly1305NonceSize]byte `json:"nonce"`
+	// Epoch is the epoch of the ephemeral runtime key.
+	Epoch uint64 `json:"epoch,omitempty"`
+	// Data is the encrypted call data.
+	Data []byte `json:"data"`
+}
+
+// ResultEnvelopeX25519XChaCha20Poly1305 is a result envelope when using the
+// EncryptedX25519XChaCha20Poly1305 format.
+type ResultEnvelopeX25519XChaCha20Poly1305 struct {
+	// Nonce.
+	Nonce [XChaCha20Poly1305NonceSize]byte `json:"nonce"`
+	// Data is the encrypted result data.
+	Data []byte `json:"data"`
+	// Header is the authenticated plaintext result header.
+	Header *ResultHeader `json:"header,omitempty"`
 }
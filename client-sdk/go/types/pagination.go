@@ -0,0 +1,18 @@
+package types
+
+// PageRequest is a request for a page of query results.
+type PageRequest struct {
+	// Cursor is the opaque cursor returned in a previous response. In case it is not specified,
+	// the first page is returned.
+	Cursor []byte `json:"cursor,omitempty"`
+	// Limit is the maximum number of items to return.
+	Limit uint16 `json:"limit,omitempty"`
+}
+
+// PageResponse is a page of query results.
+type PageResponse[T any] struct {
+	// Items are the items in the page.
+	Items []T `json:"items"`
+	// Next is the cursor for fetching the next page. Not present in case this is the last page.
+	Next []byte `json:"next,omitempty"`
+}
@@ -345,6 +345,9 @@ const (
 	// CallFormatEncryptedX25519DeoxysII is the encrypted call format using X25519 for key exchange
 	// and Deoxys-II for symmetric encryption.
 	CallFormatEncryptedX25519DeoxysII = CallFormat(1)
+	// CallFormatEncryptedX25519XChaCha20Poly1305 is the encrypted call format using X25519 for key
+	// exchange and XChaCha20-Poly1305 for symmetric encryption.
+	CallFormatEncryptedX25519XChaCha20Poly1305 = CallFormat(2)
 )
 
 // String returns a string representation of the call format.
@@ -354,6 +357,8 @@ func (cf CallFormat) String() string {
 		return "plain"
 	case CallFormatEncryptedX25519DeoxysII:
 		return "encrypted/x25519-deoxysii"
+	case CallFormatEncryptedX25519XChaCha20Poly1305:
+		return "encrypted/x25519-xchacha20poly1305"
 	default:
 		return "[unknown]"
 	}
@@ -373,6 +378,8 @@ type AuthInfo struct {
 	Fee        Fee          `json:"fee"`
 	NotBefore  *uint64      `json:"not_before,omitempty"`
 	NotAfter   *uint64      `json:"not_after,omitempty"`
+	After      []hash.Hash  `json:"after,omitempty"`
+	Conflicts  []Address    `json:"conflicts,omitempty"`
 }
 
 // Fee contains the transaction fee information.
@@ -0,0 +1,126 @@
+package types
+
+import (
+	"encoding/binary"
+	"encoding/json"
+	"os"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common/cbor"
+)
+
+// testVectorsEnv is the environment variable holding the path to the wire format test vectors
+// generated by the Rust runtime SDK (see `gen-test-vectors` in runtime-sdk).
+const testVectorsEnv = "OASIS_SDK_TEST_VECTORS"
+
+type testVector struct {
+	Kind     string          `json:"kind"`
+	Name     string          `json:"name"`
+	Encoded  []byte          `json:"encoded"`
+	Expected json.RawMessage `json:"expected"`
+}
+
+type addressVectorExpected struct {
+	Bech32     string `json:"bech32"`
+	Sigspec    []byte `json:"sigspec,omitempty"`
+	Module     string `json:"module,omitempty"`
+	ModuleKind []byte `json:"module_kind,omitempty"`
+}
+
+type callVectorExpected struct {
+	Format   CallFormat `json:"format"`
+	Method   MethodName `json:"method"`
+	ReadOnly bool       `json:"read_only"`
+}
+
+type transactionVectorExpected struct {
+	Method MethodName `json:"method"`
+	Signer string     `json:"signer"`
+	Nonce  uint64     `json:"nonce"`
+	Gas    uint64     `json:"gas"`
+}
+
+type eventVectorExpected struct {
+	Module string `json:"module"`
+	Code   uint32 `json:"code"`
+	Key    []byte `json:"key"`
+}
+
+func TestWireVectors(t *testing.T) {
+	path := os.Getenv(testVectorsEnv)
+	if path == "" {
+		t.Skipf("%s not set", testVectorsEnv)
+	}
+
+	data, err := os.ReadFile(path)
+	require.NoError(t, err, "ReadFile")
+	var vectors []testVector
+	require.NoError(t, json.Unmarshal(data, &vectors), "json.Unmarshal")
+	require.NotEmpty(t, vectors, "test vectors should not be empty")
+
+	for _, v := range vectors {
+		v := v
+		t.Run(v.Kind+"/"+v.Name, func(t *testing.T) {
+			require := require.New(t)
+
+			switch v.Kind {
+			case "address":
+				var expected addressVectorExpected
+				require.NoError(json.Unmarshal(v.Expected, &expected))
+
+				var addr Address
+				require.NoError(cbor.Unmarshal(v.Encoded, &addr))
+				require.Equal(v.Encoded, cbor.Marshal(addr), "encoding should be canonical")
+				require.EqualValues(expected.Bech32, addr.String())
+
+				if expected.Sigspec != nil {
+					var spec SignatureAddressSpec
+					require.NoError(cbor.Unmarshal(expected.Sigspec, &spec))
+					require.True(addr.Equal(NewAddress(spec)), "address should be derived from sigspec")
+				}
+				if expected.Module != "" {
+					require.True(addr.Equal(NewAddressForModule(expected.Module, expected.ModuleKind)), "address should be derived from module")
+				}
+			case "call":
+				var expected callVectorExpected
+				require.NoError(json.Unmarshal(v.Expected, &expected))
+
+				var call Call
+				require.NoError(cbor.Unmarshal(v.Encoded, &call))
+				require.Equal(v.Encoded, cbor.Marshal(call), "encoding should be canonical")
+				require.EqualValues(expected.Format, call.Format)
+				require.EqualValues(expected.Method, call.Method)
+				require.EqualValues(expected.ReadOnly, call.ReadOnly)
+			case "transaction":
+				var expected transactionVectorExpected
+				require.NoError(json.Unmarshal(v.Expected, &expected))
+
+				var tx Transaction
+				require.NoError(cbor.Unmarshal(v.Encoded, &tx))
+				require.Equal(v.Encoded, cbor.Marshal(tx), "encoding should be canonical")
+				require.NotEmpty(tx.AuthInfo.SignerInfo)
+				signer, err := tx.AuthInfo.SignerInfo[0].AddressSpec.Address()
+				require.NoError(err)
+				require.EqualValues(expected.Method, tx.Call.Method)
+				require.EqualValues(expected.Signer, signer.String())
+				require.EqualValues(expected.Nonce, tx.AuthInfo.SignerInfo[0].Nonce)
+				require.EqualValues(expected.Gas, tx.AuthInfo.Fee.Gas)
+			case "event":
+				var expected eventVectorExpected
+				require.NoError(json.Unmarshal(v.Expected, &expected))
+
+				var value interface{}
+				require.NoError(cbor.Unmarshal(v.Encoded, &value))
+				require.Equal(v.Encoded, cbor.Marshal(value), "encoding should be canonical")
+
+				code := make([]byte, 4)
+				binary.BigEndian.PutUint32(code, expected.Code)
+				require.Equal(expected.Key, append([]byte(expected.Module), code...))
+			default:
+				t.Fatalf("unknown test vector kind: %s", v.Kind)
+			}
+		})
+	}
+}
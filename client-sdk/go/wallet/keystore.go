@@ -0,0 +1,252 @@
+package wallet
+
+import (
+	"crypto/rand"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+
+	"github.com/oasisprotocol/deoxysii"
+	"golang.org/x/crypto/argon2"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+)
+
+const (
+	// keyFileVersion is the current version of the encrypted key file format.
+	keyFileVersion = 1
+	// keyFileExtension is the file name extension of encrypted key files.
+	keyFileExtension = ".key.json"
+
+	saltSize = 32
+)
+
+// ErrKeyNotFound is the error returned when a key does not exist in the keystore.
+var ErrKeyNotFound = errors.New("wallet: key not found")
+
+// ErrInvalidPassphrase is the error returned when decryption of a key fails.
+var ErrInvalidPassphrase = errors.New("wallet: invalid passphrase or corrupted key")
+
+// KDFParams are the Argon2id key derivation parameters.
+type KDFParams struct {
+	Salt    []byte `json:"salt"`
+	Time    uint32 `json:"time"`
+	Memory  uint32 `json:"memory"`
+	Threads uint8  `json:"threads"`
+}
+
+// DefaultKDFParams returns the default key derivation parameters with a fresh random salt.
+func DefaultKDFParams() (*KDFParams, error) {
+	salt := make([]byte, saltSize)
+	if _, err := rand.Read(salt); err != nil {
+		return nil, fmt.Errorf("wallet: failed to generate salt: %w", err)
+	}
+	return &KDFParams{
+		Salt:    salt,
+		Time:    3,
+		Memory:  64 * 1024,
+		Threads: 4,
+	}, nil
+}
+
+func (p *KDFParams) deriveKey(passphrase string) []byte {
+	return argon2.IDKey([]byte(passphrase), p.Salt, p.Time, p.Memory, p.Threads, deoxysii.KeySize)
+}
+
+// EncryptedKey is a secret key encrypted with a passphrase.
+//
+// The secret key is encrypted using Deoxys-II with a key derived from the passphrase via
+// Argon2id. The algorithm and public key are authenticated as additional data.
+type EncryptedKey struct {
+	Version    uint16    `json:"version"`
+	Algorithm  Algorithm `json:"algorithm"`
+	PublicKey  string    `json:"public_key"`
+	KDF        KDFParams `json:"kdf"`
+	Nonce      []byte    `json:"nonce"`
+	Ciphertext []byte    `json:"ciphertext"`
+}
+
+func (ek *EncryptedKey) additionalData() []byte {
+	return []byte(fmt.Sprintf("oasis-sdk/wallet: v%d %s %s", ek.Version, ek.Algorithm, ek.PublicKey))
+}
+
+// EncryptKey encrypts the given secret key with a passphrase. In case kdf is nil, the default
+// key derivation parameters are used.
+func EncryptKey(alg Algorithm, secret []byte, passphrase string, kdf *KDFParams) (*EncryptedKey, error) {
+	signer, err := NewSigner(alg, secret)
+	if err != nil {
+		return nil, err
+	}
+	defer signer.Reset()
+
+	if kdf == nil {
+		if kdf, err = DefaultKDFParams(); err != nil {
+			return nil, err
+		}
+	}
+
+	ek := &EncryptedKey{
+		Version:   keyFileVersion,
+		Algorithm: alg,
+		PublicKey: signer.Public().String(),
+		KDF:       *kdf,
+		Nonce:     make([]byte, deoxysii.NonceSize),
+	}
+	if _, err = rand.Read(ek.Nonce); err != nil {
+		return nil, fmt.Errorf("wallet: failed to generate nonce: %w", err)
+	}
+
+	aead, err := deoxysii.New(ek.KDF.deriveKey(passphrase))
+	if err != nil {
+		return nil, err
+	}
+	ek.Ciphertext = aead.Seal(nil, ek.Nonce, secret, ek.additionalData())
+	return ek, nil
+}
+
+// Decrypt decrypts the secret key and returns an in-memory signer for it.
+func (ek *EncryptedKey) Decrypt(passphrase string) (signature.Signer, error) {
+	if ek.Version != keyFileVersion {
+		return nil, fmt.Errorf("wallet: unsupported key version: %d", ek.Version)
+	}
+	if len(ek.Nonce) != deoxysii.NonceSize {
+		return nil, fmt.Errorf("wallet: malformed nonce")
+	}
+
+	aead, err := deoxysii.New(ek.KDF.deriveKey(passphrase))
+	if err != nil {
+		return nil, err
+	}
+	secret, err := aead.Open(nil, ek.Nonce, ek.Ciphertext, ek.additionalData())
+	if err != nil {
+		return nil, ErrInvalidPassphrase
+	}
+	defer func() {
+		for i := range secret {
+			secret[i] = 0
+		}
+	}()
+
+	signer, err := NewSigner(ek.Algorithm, secret)
+	if err != nil {
+		return nil, err
+	}
+	if signer.Public().String() != ek.PublicKey {
+		signer.Reset()
+		return nil, fmt.Errorf("wallet: public key mismatch")
+	}
+	return signer, nil
+}
+
+// FileKeystore is a keystore that stores each key in a separate encrypted file in a directory.
+type FileKeystore struct {
+	dir string
+}
+
+// NewFileKeystore creates a new file keystore in the given directory, creating it if needed.
+func NewFileKeystore(dir string) (*FileKeystore, error) {
+	if err := os.MkdirAll(dir, 0o700); err != nil {
+		return nil, fmt.Errorf("wallet: failed to create keystore directory: %w", err)
+	}
+	return &FileKeystore{dir: dir}, nil
+}
+
+func (ks *FileKeystore) path(name string) (string, error) {
+	if name == "" || strings.ContainsAny(name, `/\`) || name == "." || name == ".." {
+		return "", fmt.Errorf("wallet: invalid key name: %q", name)
+	}
+	return filepath.Join(ks.dir, name+keyFileExtension), nil
+}
+
+// Import encrypts the given secret key with a passphrase and stores it under the given name.
+//
+// It fails in case a key with the same name already exists.
+func (ks *FileKeystore) Import(name string, alg Algorithm, secret []byte, passphrase string, kdf *KDFParams) error {
+	path, err := ks.path(name)
+	if err != nil {
+		return err
+	}
+	ek, err := EncryptKey(alg, secret, passphrase, kdf)
+	if err != nil {
+		return err
+	}
+	data, err := json.MarshalIndent(ek, "", "  ")
+	if err != nil {
+		return err
+	}
+
+	f, err := os.OpenFile(path, os.O_WRONLY|os.O_CREATE|os.O_EXCL, 0o600)
+	if err != nil {
+		return fmt.Errorf("wallet: failed to create key file: %w", err)
+	}
+	if _, err = f.Write(data); err != nil {
+		f.Close()
+		return fmt.Errorf("wallet: failed to write key file: %w", err)
+	}
+	return f.Close()
+}
+
+// Get returns the encrypted key stored under the given name.
+func (ks *FileKeystore) Get(name string) (*EncryptedKey, error) {
+	path, err := ks.path(name)
+	if err != nil {
+		return nil, err
+	}
+	data, err := os.ReadFile(path)
+	switch {
+	case errors.Is(err, os.ErrNotExist):
+		return nil, ErrKeyNotFound
+	case err != nil:
+		return nil, fmt.Errorf("wallet: failed to read key file: %w", err)
+	}
+
+	var ek EncryptedKey
+	if err = json.Unmarshal(data, &ek); err != nil {
+		return nil, fmt.Errorf("wallet: malformed key file: %w", err)
+	}
+	return &ek, nil
+}
+
+// Signer decrypts the key stored under the given name and returns a signer for it.
+func (ks *FileKeystore) Signer(name, passphrase string) (signature.Signer, error) {
+	ek, err := ks.Get(name)
+	if err != nil {
+		return nil, err
+	}
+	return ek.Decrypt(passphrase)
+}
+
+// List returns the sorted names of all keys in the keystore.
+func (ks *FileKeystore) List() ([]string, error) {
+	entries, err := os.ReadDir(ks.dir)
+	if err != nil {
+		return nil, fmt.Errorf("wallet: failed to read keystore directory: %w", err)
+	}
+
+	var names []string
+	for _, entry := range entries {
+		if entry.IsDir() || !strings.HasSuffix(entry.Name(), keyFileExtension) {
+			continue
+		}
+		names = append(names, strings.TrimSuffix(entry.Name(), keyFileExtension))
+	}
+	sort.Strings(names)
+	return names, nil
+}
+
+// Remove removes the key stored under the given name.
+func (ks *FileKeystore) Remove(name string) error {
+	path, err := ks.path(name)
+	if err != nil {
+		return err
+	}
+	err = os.Remove(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return ErrKeyNotFound
+	}
+	return err
+}
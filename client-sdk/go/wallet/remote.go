@@ -0,0 +1,79 @@
+package wallet
+
+import (
+	"context"
+	"fmt"
+	"time"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+)
+
+// DefaultRemoteSignTimeout is the default timeout for remote signing requests.
+const DefaultRemoteSignTimeout = 30 * time.Second
+
+// RemoteBackend is an external signing service, e.g. an HSM or a custody system.
+type RemoteBackend interface {
+	// ContextSign requests a signature over the message using the given derived domain separation
+	// context (see signature.Context.Derive), following the rules of the key's algorithm.
+	ContextSign(ctx context.Context, context, message []byte) ([]byte, error)
+}
+
+// RemoteBackendFunc is a function implementing RemoteBackend.
+type RemoteBackendFunc func(ctx context.Context, context, message []byte) ([]byte, error)
+
+// ContextSign implements RemoteBackend.
+func (fn RemoteBackendFunc) ContextSign(ctx context.Context, context, message []byte) ([]byte, error) {
+	return fn(ctx, context, message)
+}
+
+type remoteSigner struct {
+	pk      signature.PublicKey
+	backend RemoteBackend
+	timeout time.Duration
+}
+
+func (s *remoteSigner) Public() signature.PublicKey {
+	return s.pk
+}
+
+func (s *remoteSigner) ContextSign(sigCtx signature.Context, message []byte) ([]byte, error) {
+	ctx, cancel := context.WithTimeout(context.Background(), s.timeout)
+	defer cancel()
+
+	rawCtx := sigCtx.Derive()
+	sig, err := s.backend.ContextSign(ctx, rawCtx, message)
+	if err != nil {
+		return nil, fmt.Errorf("wallet: remote signing failed: %w", err)
+	}
+	// Do not trust the backend to produce valid signatures for the expected key.
+	if !s.pk.Verify(rawCtx, message, sig) {
+		return nil, fmt.Errorf("wallet: remote signer returned an invalid signature")
+	}
+	return sig, nil
+}
+
+func (s *remoteSigner) Sign([]byte) ([]byte, error) {
+	return nil, fmt.Errorf("wallet: signing without context not supported by remote signers")
+}
+
+func (s *remoteSigner) String() string {
+	return "remote signer: " + s.pk.String()
+}
+
+func (s *remoteSigner) Reset() {
+	// Nothing to do, no sensitive state is kept locally.
+}
+
+// NewRemoteSigner creates a new signer that delegates signing for the given public key to an
+// external backend. Returned signatures are verified before being used. In case timeout is zero,
+// DefaultRemoteSignTimeout is used.
+func NewRemoteSigner(pk signature.PublicKey, backend RemoteBackend, timeout time.Duration) signature.Signer {
+	if timeout == 0 {
+		timeout = DefaultRemoteSignTimeout
+	}
+	return &remoteSigner{
+		pk:      pk,
+		backend: backend,
+		timeout: timeout,
+	}
+}
@@ -0,0 +1,62 @@
+// Package wallet contains pluggable transaction signers.
+//
+// All signers implement signature.Signer so that they can be used anywhere a signer is accepted,
+// including client.TransactionBuilder. Available signers are:
+//
+//   - in-memory signers created from raw secret keys (see NewSigner),
+//   - signers backed by encrypted key files (see FileKeystore),
+//   - signers delegating to an external signing service such as an HSM or a custody system (see
+//     NewRemoteSigner).
+package wallet
+
+import (
+	"fmt"
+
+	"github.com/oasisprotocol/curve25519-voi/primitives/ed25519"
+	memorySigner "github.com/oasisprotocol/oasis-core/go/common/crypto/signature/signers/memory"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	sdkEd25519 "github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature/ed25519"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature/secp256k1"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature/sr25519"
+)
+
+// Algorithm is a signature algorithm.
+type Algorithm string
+
+const (
+	// AlgorithmEd25519 is the Ed25519 signature algorithm.
+	AlgorithmEd25519 = Algorithm("ed25519")
+	// AlgorithmSecp256k1 is the Secp256k1 signature algorithm.
+	AlgorithmSecp256k1 = Algorithm("secp256k1")
+	// AlgorithmSr25519 is the Sr25519 signature algorithm.
+	AlgorithmSr25519 = Algorithm("sr25519")
+)
+
+// NewSigner creates a new in-memory signer for the given algorithm and secret key.
+//
+// Ed25519 secret keys can either be 32-byte seeds or 64-byte private keys.
+func NewSigner(alg Algorithm, secret []byte) (signature.Signer, error) {
+	switch alg {
+	case AlgorithmEd25519:
+		var sk ed25519.PrivateKey
+		switch len(secret) {
+		case ed25519.SeedSize:
+			sk = ed25519.NewKeyFromSeed(secret)
+		case ed25519.PrivateKeySize:
+			sk = append(ed25519.PrivateKey{}, secret...)
+		default:
+			return nil, fmt.Errorf("wallet: malformed ed25519 secret key")
+		}
+		return sdkEd25519.WrapSigner(memorySigner.NewFromRuntime(sk)), nil
+	case AlgorithmSecp256k1:
+		if len(secret) != 32 {
+			return nil, fmt.Errorf("wallet: malformed secp256k1 secret key")
+		}
+		return secp256k1.NewSigner(secret), nil
+	case AlgorithmSr25519:
+		return sr25519.NewSigner(secret)
+	default:
+		return nil, fmt.Errorf("wallet: unsupported algorithm: %s", alg)
+	}
+}
@@ -0,0 +1,111 @@
+package wallet
+
+import (
+	"bytes"
+	"context"
+	"fmt"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+
+	"github.com/oasisprotocol/oasis-core/go/common"
+
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/crypto/signature"
+	"github.com/oasisprotocol/oasis-sdk/client-sdk/go/types"
+)
+
+// testKDFParams are cheap key derivation parameters for use in tests.
+var testKDFParams = &KDFParams{
+	Salt:    []byte("oasis-sdk/wallet: test salt"),
+	Time:    1,
+	Memory:  64,
+	Threads: 1,
+}
+
+func TestFileKeystore(t *testing.T) {
+	require := require.New(t)
+
+	ks, err := NewFileKeystore(t.TempDir())
+	require.NoError(err)
+
+	secrets := map[Algorithm][]byte{
+		AlgorithmEd25519:   bytes.Repeat([]byte{0x01}, 32),
+		AlgorithmSecp256k1: bytes.Repeat([]byte{0x02}, 32),
+	}
+	for alg, secret := range secrets {
+		expected, err := NewSigner(alg, secret)
+		require.NoError(err)
+
+		name := string(alg)
+		err = ks.Import(name, alg, secret, "passphrase", testKDFParams)
+		require.NoError(err, "Import")
+		err = ks.Import(name, alg, secret, "passphrase", testKDFParams)
+		require.Error(err, "Import should fail for existing keys")
+
+		signer, err := ks.Signer(name, "passphrase")
+		require.NoError(err, "Signer")
+		require.True(expected.Public().Equal(signer.Public()), "decrypted key should match")
+
+		_, err = ks.Signer(name, "wrong")
+		require.ErrorIs(err, ErrInvalidPassphrase)
+	}
+
+	names, err := ks.List()
+	require.NoError(err)
+	require.EqualValues([]string{"ed25519", "secp256k1"}, names)
+
+	_, err = ks.Signer("missing", "passphrase")
+	require.ErrorIs(err, ErrKeyNotFound)
+	err = ks.Import("../escape", AlgorithmEd25519, secrets[AlgorithmEd25519], "passphrase", testKDFParams)
+	require.Error(err, "Import should reject invalid names")
+
+	require.NoError(ks.Remove("ed25519"))
+	require.ErrorIs(ks.Remove("ed25519"), ErrKeyNotFound)
+
+	// Tampering with the authenticated public key should be detected.
+	ek, err := ks.Get("secp256k1")
+	require.NoError(err)
+	ek.PublicKey = "tampered"
+	_, err = ek.Decrypt("passphrase")
+	require.ErrorIs(err, ErrInvalidPassphrase)
+}
+
+func TestRemoteSigner(t *testing.T) {
+	require := require.New(t)
+
+	local, err := NewSigner(AlgorithmEd25519, bytes.Repeat([]byte{0x03}, 32))
+	require.NoError(err)
+	other, err := NewSigner(AlgorithmEd25519, bytes.Repeat([]byte{0x04}, 32))
+	require.NoError(err)
+
+	backend := local
+	remote := NewRemoteSigner(local.Public(), RemoteBackendFunc(func(_ context.Context, context, message []byte) ([]byte, error) {
+		if backend == nil {
+			return nil, fmt.Errorf("backend unavailable")
+		}
+		return backend.ContextSign(signature.RawContext(context), message)
+	}), 0)
+
+	chainCtx := &signature.RichContext{
+		RuntimeID:    common.Namespace{},
+		ChainContext: "test",
+		Base:         types.SignatureContextBase,
+	}
+	spec, err := types.NewSignatureAddressSpecFromPublicKey(remote.Public())
+	require.NoError(err)
+	tx := types.NewTransaction(nil, "hello.World", nil)
+	tx.AppendAuthSignature(spec, 0)
+
+	ts := tx.PrepareForSigning()
+	require.NoError(ts.AppendSign(chainCtx, remote), "AppendSign with remote signer")
+	_, err = ts.UnverifiedTransaction().Verify(chainCtx)
+	require.NoError(err, "remotely signed transaction should verify")
+
+	backend = other
+	_, err = remote.ContextSign(chainCtx, []byte("message"))
+	require.Error(err, "signatures by the wrong key should be rejected")
+
+	backend = nil
+	_, err = remote.ContextSign(chainCtx, []byte("message"))
+	require.Error(err, "backend failures should be propagated")
+}
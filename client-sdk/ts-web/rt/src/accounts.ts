@@ -47,7 +47,9 @@ export class Wrapper extends wrapper.Base {
     }
 
     queryAddresses() {
-        return this.query<types.AccountsAddressesQuery, Uint8Array[]>(METHOD_ADDRESSES);
+        return this.query<types.AccountsAddressesQuery, types.PageResponse<Uint8Array>>(
+            METHOD_ADDRESSES,
+        );
     }
 
     queryDenominationInfo() {
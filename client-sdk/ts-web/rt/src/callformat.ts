@@ -116,11 +116,14 @@ export async function decodeResult(
                     const envelop = oasis.misc.fromCBOR(
                         result.unknown,
                     ) as types.ResultEnvelopeX25519DeoxysII;
-                    const zeroBuffer = new Uint8Array(0);
+                    // The plaintext header (if any) is authenticated as additional data.
+                    const additionalData = envelop?.header
+                        ? oasis.misc.toCBOR(envelop.header)
+                        : new Uint8Array(0);
                     const pt = mraeDeoxysii.boxOpen(
                         envelop?.nonce,
                         envelop?.data,
-                        zeroBuffer,
+                        additionalData,
                         meta.pk,
                         meta.sk,
                     );
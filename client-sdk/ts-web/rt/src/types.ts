@@ -58,6 +58,32 @@ export interface CallerAddress {
     eth_address?: Uint8Array;
 }
 
+/**
+ * A request for a page of query results.
+ */
+export interface PageRequest {
+    /**
+     * Opaque cursor returned in a previous response. In case it is not specified, the first page
+     * is returned.
+     */
+    cursor?: Uint8Array;
+    /**
+     * Maximum number of items to return.
+     */
+    limit?: number;
+}
+
+/**
+ * A page of query results.
+ */
+export interface PageResponse<T> {
+    items: T[];
+    /**
+     * Cursor for fetching the next page. Not present in case this is the last page.
+     */
+    next?: Uint8Array;
+}
+
 // The below is imported from oasis-core (Rust), but it's never used from the oasis-node side.
 // So I'm putting this here in the runtime package.
 /**
@@ -114,6 +140,7 @@ export interface AccountsNonceQuery {
  */
 export interface AccountsAddressesQuery {
     denomination: Uint8Array;
+    pagination?: PageRequest;
 }
 
 /**
@@ -472,9 +499,20 @@ export interface CallEnvelopeX25519DeoxysII {
 /**
  * A result envelope when using the CALLFORMAT_ENCRYPTED_X25519DEOXYSII format.
  */
+/**
+ * Plaintext header of an encrypted call result. It is authenticated as the additional data of the
+ * sealed result box.
+ */
+export interface ResultHeader {
+    success: boolean;
+    module?: string;
+    code?: number;
+}
+
 export interface ResultEnvelopeX25519DeoxysII {
     nonce: Uint8Array;
     data: Uint8Array;
+    header?: ResultHeader;
 }
 
 export interface ContractsPolicy {
@@ -0,0 +1,96 @@
+import * as oasis from '@oasisprotocol/client';
+import * as fs from 'fs';
+
+import * as oasisRT from './../src';
+
+/**
+ * Wire format test vectors generated by the Rust runtime SDK (see `gen-test-vectors` in
+ * runtime-sdk). The path to the vectors is given by the `OASIS_SDK_TEST_VECTORS` environment
+ * variable and the tests are skipped when it is not set.
+ */
+interface TestVector {
+    kind: string;
+    name: string;
+    encoded: string;
+    expected: any;
+}
+
+const vectorsPath = process.env.OASIS_SDK_TEST_VECTORS;
+const vectors: TestVector[] = vectorsPath
+    ? JSON.parse(fs.readFileSync(vectorsPath, 'utf8'))
+    : [];
+
+function roundtrip(v: TestVector): any {
+    const encoded = oasis.misc.fromBase64(v.encoded);
+    const decoded = oasis.misc.fromCBOR(encoded);
+    expect(oasis.misc.toHex(oasis.misc.toCBOR(decoded))).toEqual(oasis.misc.toHex(encoded));
+    return decoded;
+}
+
+function expectNumber(actual: oasis.types.longnum | undefined, expected: number) {
+    // JSON numbers beyond the safe integer range lose precision, skip those.
+    if (Number.isSafeInteger(expected)) {
+        expect(BigInt(actual ?? 0)).toEqual(BigInt(expected));
+    }
+}
+
+(vectorsPath ? describe : describe.skip)('vectors', () => {
+    it('Should have test vectors', () => {
+        expect(vectors.length).toBeGreaterThan(0);
+    });
+
+    for (const v of vectors) {
+        it(`Should round-trip ${v.kind} ${v.name}`, async () => {
+            switch (v.kind) {
+                case 'address': {
+                    const address: Uint8Array = roundtrip(v);
+                    expect(oasisRT.address.toBech32(address)).toEqual(v.expected.bech32);
+                    if (v.expected.sigspec) {
+                        const sigspec = oasis.misc.fromCBOR(
+                            oasis.misc.fromBase64(v.expected.sigspec),
+                        ) as oasisRT.types.SignatureAddressSpec;
+                        if (sigspec.ed25519 || sigspec.secp256k1eth) {
+                            const derived = await oasisRT.address.fromSigspec(sigspec);
+                            expect(oasisRT.address.toBech32(derived)).toEqual(v.expected.bech32);
+                        }
+                    }
+                    break;
+                }
+                case 'call': {
+                    const call: oasisRT.types.Call = roundtrip(v);
+                    expect(call.format ?? oasisRT.transaction.CALLFORMAT_PLAIN).toEqual(
+                        v.expected.format,
+                    );
+                    expect(call.method ?? '').toEqual(v.expected.method);
+                    expect(call.ro ?? false).toEqual(v.expected.read_only);
+                    break;
+                }
+                case 'transaction': {
+                    const tx: oasisRT.types.Transaction = roundtrip(v);
+                    expect(tx.call.method ?? '').toEqual(v.expected.method);
+                    const sigspec = tx.ai.si[0].address_spec.signature;
+                    if (sigspec?.ed25519 || sigspec?.secp256k1eth) {
+                        const signer = await oasisRT.address.fromSigspec(sigspec);
+                        expect(oasisRT.address.toBech32(signer)).toEqual(v.expected.signer);
+                    }
+                    expectNumber(tx.ai.si[0].nonce, v.expected.nonce);
+                    expectNumber(tx.ai.fee.gas, v.expected.gas);
+                    break;
+                }
+                case 'event': {
+                    roundtrip(v);
+                    const code = new Uint8Array(4);
+                    new DataView(code.buffer).setUint32(0, v.expected.code, false);
+                    expect(
+                        oasis.misc.toHex(
+                            oasis.misc.concat(oasis.misc.fromString(v.expected.module), code),
+                        ),
+                    ).toEqual(oasis.misc.toHex(oasis.misc.fromBase64(v.expected.key)));
+                    break;
+                }
+                default:
+                    throw new Error(`unknown test vector kind: ${v.kind}`);
+            }
+        });
+    }
+});
@@ -12,6 +12,14 @@ pub enum QueryRequest {
     /// Accounts queries.
     #[cbor(rename = "accounts")]
     Accounts(AccountsQuery),
+
+    /// Oracle queries.
+    #[cbor(rename = "oracle")]
+    Oracle(OracleQuery),
+
+    /// ROFL queries.
+    #[cbor(rename = "rofl")]
+    Rofl(RoflQuery),
 }
 
 /// A query response.
@@ -37,6 +45,14 @@ pub enum QueryResponse {
     /// Accounts queries.
     #[cbor(rename = "accounts")]
     Accounts(AccountsResponse),
+
+    /// Oracle queries.
+    #[cbor(rename = "oracle")]
+    Oracle(OracleResponse),
+
+    /// ROFL queries.
+    #[cbor(rename = "rofl")]
+    Rofl(RoflResponse),
 }
 
 /// Accounts API queries.
@@ -70,3 +86,62 @@ impl From<AccountsResponse> for QueryResponse {
         Self::Accounts(q)
     }
 }
+
+/// Oracle API queries.
+#[non_exhaustive]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+pub enum OracleQuery {
+    /// Query the latest aggregated value of a data feed.
+    #[cbor(rename = "value")]
+    Value { feed: String },
+}
+
+impl From<OracleQuery> for QueryRequest {
+    fn from(q: OracleQuery) -> Self {
+        Self::Oracle(q)
+    }
+}
+
+/// Oracle API responses.
+#[non_exhaustive]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+pub enum OracleResponse {
+    /// The latest aggregated value of a data feed and the runtime round it was aggregated in.
+    Value { value: u128, round: u64 },
+}
+
+impl From<OracleResponse> for QueryResponse {
+    fn from(q: OracleResponse) -> Self {
+        Self::Oracle(q)
+    }
+}
+
+/// ROFL API queries.
+#[non_exhaustive]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+pub enum RoflQuery {
+    /// Query whether the current transaction has been signed by an authorized instance of the
+    /// given ROFL application.
+    #[cbor(rename = "is_authorized_origin")]
+    IsAuthorizedOrigin { app: Vec<u8> },
+}
+
+impl From<RoflQuery> for QueryRequest {
+    fn from(q: RoflQuery) -> Self {
+        Self::Rofl(q)
+    }
+}
+
+/// ROFL API responses.
+#[non_exhaustive]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+pub enum RoflResponse {
+    /// Whether the current transaction has been signed by an authorized application instance.
+    IsAuthorizedOrigin { authorized: bool },
+}
+
+impl From<RoflResponse> for QueryResponse {
+    fn from(q: RoflResponse) -> Self {
+        Self::Rofl(q)
+    }
+}
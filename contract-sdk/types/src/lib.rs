@@ -76,6 +76,9 @@ pub enum CallFormat {
     Plain = 0,
     /// Encrypted call data using X25519 for key exchange and Deoxys-II for symmetric encryption.
     EncryptedX25519DeoxysII = 1,
+    /// Encrypted call data using X25519 for key exchange and XChaCha20-Poly1305 for symmetric
+    /// encryption.
+    EncryptedX25519XChaCha20Poly1305 = 2,
 }
 
 impl CallFormat {
@@ -98,6 +101,9 @@ impl From<oasis_runtime_sdk::types::transaction::CallFormat> for CallFormat {
         match a {
             RuntimeCallFormat::Plain => Self::Plain,
             RuntimeCallFormat::EncryptedX25519DeoxysII => Self::EncryptedX25519DeoxysII,
+            RuntimeCallFormat::EncryptedX25519XChaCha20Poly1305 => {
+                Self::EncryptedX25519XChaCha20Poly1305
+            }
         }
     }
 }
@@ -42,11 +42,15 @@ impl sdk::Runtime for Runtime {
                     min_gas_price: BTreeMap::from([(Denomination::NATIVE, 0)]),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             // Accounts module.
             modules::accounts::Genesis {
                 parameters: modules::accounts::Parameters {
-                    gas_costs: modules::accounts::GasCosts { tx_transfer: 100 },
+                    gas_costs: modules::accounts::GasCosts {
+                        tx_transfer: 100,
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 balances: BTreeMap::from([
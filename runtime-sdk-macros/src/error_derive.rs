@@ -1,7 +1,7 @@
 use darling::{util::Flag, FromDeriveInput, FromField, FromVariant};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, quote_spanned};
-use syn::{DeriveInput, Ident, Index, Member, Path};
+use syn::{parse::ParseStream, DeriveInput, Ident, Index, LitStr, Member, Path};
 
 use crate::generators::{self as gen, CodedVariant};
 
@@ -28,10 +28,12 @@ struct Error {
 }
 
 #[derive(FromVariant)]
-#[darling(attributes(sdk_error))]
+#[darling(attributes(sdk_error), forward_attrs(error))]
 struct ErrorVariant {
     ident: Ident,
 
+    attrs: Vec<syn::Attribute>,
+
     fields: darling::ast::Fields<ErrorField>,
 
     /// The explicit ID of the error code. Overrides any autonumber set on the error enum.
@@ -45,6 +47,24 @@ struct ErrorVariant {
     abort: Flag,
 }
 
+impl ErrorVariant {
+    /// The message format string from the `#[error("...")]` attribute, if any.
+    fn message(&self) -> String {
+        self.attrs
+            .iter()
+            .find_map(|attr| {
+                attr.parse_args_with(|input: ParseStream<'_>| {
+                    let message: LitStr = input.parse()?;
+                    // Ignore any format arguments.
+                    input.parse::<TokenStream>()?;
+                    Ok(message.value())
+                })
+                .ok()
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl CodedVariant for ErrorVariant {
     const FIELD_NAME: &'static str = "code";
 
@@ -77,7 +97,7 @@ pub fn derive_error(input: DeriveInput) -> TokenStream {
         .module_name
         .unwrap_or_else(|| syn::parse_quote!(MODULE_NAME));
 
-    let (module_name_body, code_body, abort_body) = convert_variants(
+    let (module_name_body, code_body, abort_body, catalog_body) = convert_variants(
         &format_ident!("self"),
         module_name,
         &error.data.as_ref().take_enum().unwrap(),
@@ -103,6 +123,10 @@ pub fn derive_error(input: DeriveInput) -> TokenStream {
             fn into_abort(self) -> Result<__sdk::dispatcher::Error, Self> {
                 #abort_body
             }
+
+            fn catalog() -> Vec<__sdk::error::ErrorInfo> {
+                #catalog_body
+            }
         }
 
         #[automatically_derived]
@@ -120,14 +144,21 @@ fn convert_variants(
     variants: &[&ErrorVariant],
     autonumber: bool,
     abort_self: bool,
-) -> (TokenStream, TokenStream, TokenStream) {
+) -> (TokenStream, TokenStream, TokenStream, TokenStream) {
     if variants.is_empty() {
-        return (quote!(#module_name), quote!(0), quote!(Err(#enum_binding)));
+        return (
+            quote!(#module_name),
+            quote!(0),
+            quote!(Err(#enum_binding)),
+            quote!(Vec::new()),
+        );
     }
 
     let mut next_autonumber = 0u32;
     let mut reserved_numbers = std::collections::BTreeSet::new();
 
+    let mut catalog_entries = Vec::new();
+
     let (module_name_matches, (code_matches, abort_matches)): (Vec<_>, (Vec<_>, Vec<_>)) = variants
         .iter()
         .map(|variant| {
@@ -243,6 +274,17 @@ fn convert_variants(
                     }
                 };
 
+                let name = variant_ident.to_string();
+                let message = variant.message();
+                catalog_entries.push(quote! {
+                    __sdk::error::ErrorInfo {
+                        module: #module_name.to_string(),
+                        code: #code,
+                        name: #name.to_string(),
+                        message: #message.to_string(),
+                    }
+                });
+
                 let abort = if variant.abort.is_present() {
                     quote!{
                         Self::#variant_ident(err) => Ok(err),
@@ -290,6 +332,9 @@ fn convert_variants(
             }
         },
         abort_body,
+        quote! {
+            vec![#(#catalog_entries),*]
+        },
     )
 }
 
@@ -329,6 +374,40 @@ mod tests {
                             Self::ErrorAbort(err) => Ok(err),
                         }
                     }
+                    fn catalog() -> Vec<__sdk::error::ErrorInfo> {
+                        vec![
+                            __sdk::error::ErrorInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 0u32,
+                                name: "Error0".to_string(),
+                                message: "".to_string(),
+                            },
+                            __sdk::error::ErrorInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 2u32,
+                                name: "Error2".to_string(),
+                                message: "".to_string(),
+                            },
+                            __sdk::error::ErrorInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 1u32,
+                                name: "Error1".to_string(),
+                                message: "".to_string(),
+                            },
+                            __sdk::error::ErrorInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 3u32,
+                                name: "Error3".to_string(),
+                                message: "".to_string(),
+                            },
+                            __sdk::error::ErrorInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 4u32,
+                                name: "ErrorAbort".to_string(),
+                                message: "".to_string(),
+                            },
+                        ]
+                    }
                 }
                 #[automatically_derived]
                 impl From<Error> for __sdk::error::RuntimeError {
@@ -376,6 +455,9 @@ mod tests {
                     fn into_abort(self) -> Result<__sdk::dispatcher::Error, Self> {
                         Err(self)
                     }
+                    fn catalog() -> Vec<__sdk::error::ErrorInfo> {
+                        Vec::new()
+                    }
                 }
                 #[automatically_derived]
                 impl From<Error> for __sdk::error::RuntimeError {
@@ -421,6 +503,9 @@ mod tests {
                             }
                         }
                     }
+                    fn catalog() -> Vec<__sdk::error::ErrorInfo> {
+                        vec![]
+                    }
                 }
                 #[automatically_derived]
                 impl From<Error> for __sdk::error::RuntimeError {
@@ -467,6 +552,22 @@ mod tests {
                     fn into_abort(self) -> Result<__sdk::dispatcher::Error, Self> {
                         Ok(self)
                     }
+                    fn catalog() -> Vec<__sdk::error::ErrorInfo> {
+                        vec![
+                            __sdk::error::ErrorInfo {
+                                module: THE_MODULE_NAME.to_string(),
+                                code: 1u32,
+                                name: "Foo".to_string(),
+                                message: "the foo".to_string(),
+                            },
+                            __sdk::error::ErrorInfo {
+                                module: THE_MODULE_NAME.to_string(),
+                                code: 2u32,
+                                name: "Bar".to_string(),
+                                message: "".to_string(),
+                            },
+                        ]
+                    }
                 }
                 #[automatically_derived]
                 impl From<Error> for __sdk::error::RuntimeError {
@@ -481,6 +582,7 @@ mod tests {
             #[derive(Error)]
             #[sdk_error(module_name = "THE_MODULE_NAME", abort_self)]
             pub enum Error {
+                #[error("the foo")]
                 #[sdk_error(code = 1)]
                 Foo,
                 #[sdk_error(code = 2)]
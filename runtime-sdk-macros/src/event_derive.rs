@@ -1,4 +1,4 @@
-use darling::{util::Flag, FromDeriveInput, FromVariant};
+use darling::{util::Flag, FromDeriveInput, FromField, FromVariant};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{DeriveInput, Ident};
@@ -27,11 +27,20 @@ struct Event {
 struct EventVariant {
     ident: Ident,
 
+    fields: darling::ast::Fields<EventField>,
+
     /// The explicit ID of the event code. Overrides any autonumber set on the event enum.
     #[darling(rename = "code")]
     code: Option<u32>,
 }
 
+#[derive(FromField)]
+struct EventField {
+    ident: Option<Ident>,
+
+    ty: syn::Type,
+}
+
 impl CodedVariant for EventVariant {
     const FIELD_NAME: &'static str = "code";
 
@@ -55,14 +64,46 @@ pub fn derive_event(input: DeriveInput) -> TokenStream {
         .module_name
         .unwrap_or_else(|| syn::parse_quote!(MODULE_NAME));
 
+    let variants = event.data.as_ref().take_enum().unwrap();
     let code_converter = gen::enum_code_converter(
         &format_ident!("self"),
-        &event.data.as_ref().take_enum().unwrap(),
+        &variants,
         event.autonumber.is_present(),
     );
 
     let sdk_crate = gen::sdk_crate_path();
 
+    // Invalid codes are reported by the code converter, so such variants are just skipped here.
+    let catalog_entries = variants
+        .iter()
+        .zip(gen::assign_codes(&variants, event.autonumber.is_present()))
+        .filter_map(|(variant, code)| {
+            let code = code.ok()?;
+            let name = variant.ident.to_string();
+            let fields = variant.fields.iter().map(|field| {
+                let name = field
+                    .ident
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                let ty = gen::register_schema(&sdk_crate, &field.ty);
+                quote! {
+                    #sdk_crate::event::EventFieldInfo {
+                        name: #name.to_string(),
+                        ty: #ty,
+                    }
+                }
+            });
+            Some(quote! {
+                #sdk_crate::event::EventInfo {
+                    module: #module_name.to_string(),
+                    code: #code,
+                    name: #name.to_string(),
+                    fields: vec![#(#fields),*],
+                }
+            })
+        });
+
     gen::wrap_in_const(quote! {
         impl #sdk_crate::event::Event for #event_ty_ident {
             fn module_name() -> &'static str {
@@ -72,6 +113,15 @@ pub fn derive_event(input: DeriveInput) -> TokenStream {
             fn code(&self) -> u32 {
                 #code_converter
             }
+
+            fn catalog(
+                types: &mut #sdk_crate::schema::SchemaRegistry,
+            ) -> Vec<#sdk_crate::event::EventInfo> {
+                #[allow(unused_imports)]
+                use #sdk_crate::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                vec![#(#catalog_entries),*]
+            }
         }
     })
 }
@@ -94,6 +144,53 @@ mod tests {
                             Self::Event3 { .. } => 3u32,
                         }
                     }
+                    fn catalog(
+                        types: &mut ::oasis_runtime_sdk::schema::SchemaRegistry,
+                    ) -> Vec<::oasis_runtime_sdk::event::EventInfo> {
+                        #[allow(unused_imports)]
+                        use ::oasis_runtime_sdk::schema::__private::{
+                            ViaFallback as _, ViaSchema as _,
+                        };
+
+                        vec![
+                            ::oasis_runtime_sdk::event::EventInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 0u32,
+                                name: "Event0".to_string(),
+                                fields: vec![],
+                            },
+                            ::oasis_runtime_sdk::event::EventInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 2u32,
+                                name: "Event2".to_string(),
+                                fields: vec![::oasis_runtime_sdk::event::EventFieldInfo {
+                                    name: "payload".to_string(),
+                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<
+                                        Vec<u8>,
+                                    >::new())
+                                        .register(types),
+                                }],
+                            },
+                            ::oasis_runtime_sdk::event::EventInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 1u32,
+                                name: "Event1".to_string(),
+                                fields: vec![::oasis_runtime_sdk::event::EventFieldInfo {
+                                    name: "".to_string(),
+                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<
+                                        String,
+                                    >::new())
+                                        .register(types),
+                                }],
+                            },
+                            ::oasis_runtime_sdk::event::EventInfo {
+                                module: MODULE_NAME.to_string(),
+                                code: 3u32,
+                                name: "Event3".to_string(),
+                                fields: vec![],
+                            },
+                        ]
+                    }
                 }
             };
         );
@@ -128,6 +225,16 @@ mod tests {
                     fn code(&self) -> u32 {
                         0
                     }
+                    fn catalog(
+                        types: &mut ::oasis_runtime_sdk::schema::SchemaRegistry,
+                    ) -> Vec<::oasis_runtime_sdk::event::EventInfo> {
+                        #[allow(unused_imports)]
+                        use ::oasis_runtime_sdk::schema::__private::{
+                            ViaFallback as _, ViaSchema as _,
+                        };
+
+                        vec![]
+                    }
                 }
             };
         );
@@ -29,6 +29,37 @@ pub fn sdk_crate_path() -> syn::Path {
     }
 }
 
+/// Generates an expression registering the schema of the given type with the `types` registry in
+/// scope and evaluating to the registered name. Types that do not implement `Schema` are
+/// registered as opaque, which requires `schema::__private::{ViaSchema, ViaFallback}` in scope.
+pub fn register_schema(sdk_crate: &syn::Path, ty: &syn::Type) -> TokenStream {
+    quote!((&#sdk_crate::schema::__private::SchemaProbe::<#ty>::new()).register(types))
+}
+
+/// Renders a type as written in the source in a compact form (e.g. `Vec<u8>` or
+/// `types::Transfer`), only keeping whitespace that separates identifiers.
+pub fn type_name(ty: &syn::Type) -> String {
+    let tokens = quote!(#ty).to_string();
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut name = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_whitespace() {
+            name.push(c);
+            continue;
+        }
+        let keep = matches!(
+            (name.chars().last(), chars.peek()),
+            (Some(prev), Some(&next)) if is_ident_char(prev) && is_ident_char(next)
+        );
+        if keep {
+            name.push(' ');
+        }
+    }
+    name
+}
+
 pub trait CodedVariant {
     /// The field in the helper attribute that yields the value provided by `code`.
     /// For instance, in `#[sdk_event(code = 0)]`, the `FIELD_NAME` would be `code`.
@@ -41,32 +72,33 @@ pub trait CodedVariant {
     fn code(&self) -> Option<u32>;
 }
 
-/// Returns a `match` expression that encodes an enum's variants as integral codes.
-pub fn enum_code_converter<V: CodedVariant>(
-    enum_binding: &Ident,
+/// Reason why a code could not be assigned to a variant.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodeError {
+    /// The explicitly specified code is already used by another variant.
+    AlreadyUsed(u32),
+    /// The variant has no explicit code and autonumbering is disabled.
+    Missing,
+}
+
+/// Assigns integral codes to an enum's variants, in variant order.
+///
+/// Variants without an explicit code are autonumbered sequentially (if enabled), skipping any
+/// codes that are already reserved.
+pub fn assign_codes<V: CodedVariant>(
     variants: &[&V],
     autonumber: bool,
-) -> TokenStream {
-    if variants.is_empty() {
-        return quote!(0); // Early return with default if there are no variants.
-    }
-
+) -> Vec<Result<u32, CodeError>> {
     let mut next_autonumber = 0u32;
     let mut reserved_numbers = std::collections::BTreeSet::new();
-    let match_arms = variants.iter().map(|variant| {
-        let variant_ident = variant.ident();
-        let code = match variant.code() {
+    variants
+        .iter()
+        .map(|variant| match variant.code() {
             Some(code) => {
-                if reserved_numbers.contains(&code) {
-                    variant_ident
-                        .span()
-                        .unwrap()
-                        .error(format!("code {code} already used"))
-                        .emit();
-                    return quote!({});
+                if !reserved_numbers.insert(code) {
+                    return Err(CodeError::AlreadyUsed(code));
                 }
-                reserved_numbers.insert(code);
-                code
+                Ok(code)
             }
             None if autonumber => {
                 let mut reserved_successors = reserved_numbers.range(next_autonumber..);
@@ -76,9 +108,37 @@ pub fn enum_code_converter<V: CodedVariant>(
                 let code = next_autonumber;
                 reserved_numbers.insert(code);
                 next_autonumber += 1;
-                code
+                Ok(code)
+            }
+            None => Err(CodeError::Missing),
+        })
+        .collect()
+}
+
+/// Returns a `match` expression that encodes an enum's variants as integral codes.
+pub fn enum_code_converter<V: CodedVariant>(
+    enum_binding: &Ident,
+    variants: &[&V],
+    autonumber: bool,
+) -> TokenStream {
+    if variants.is_empty() {
+        return quote!(0); // Early return with default if there are no variants.
+    }
+
+    let codes = assign_codes(variants, autonumber);
+    let match_arms = variants.iter().zip(codes).map(|(variant, code)| {
+        let variant_ident = variant.ident();
+        let code = match code {
+            Ok(code) => code,
+            Err(CodeError::AlreadyUsed(code)) => {
+                variant_ident
+                    .span()
+                    .unwrap()
+                    .error(format!("code {code} already used"))
+                    .emit();
+                return quote!({});
             }
-            None => {
+            Err(CodeError::Missing) => {
                 variant_ident
                     .span()
                     .unwrap()
@@ -119,4 +179,22 @@ mod tests {
         let actual: syn::Expr = syn::parse2(converter).unwrap();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn format_type_names() {
+        let cases: Vec<(syn::Type, &str)> = vec![
+            (syn::parse_quote!(u64), "u64"),
+            (syn::parse_quote!(Vec<u8>), "Vec<u8>"),
+            (syn::parse_quote!(types::Transfer), "types::Transfer"),
+            (
+                syn::parse_quote!(BTreeMap<String, types::StateStats>),
+                "BTreeMap<String,types::StateStats>",
+            ),
+            (syn::parse_quote!(&'static str), "&'static str"),
+            (syn::parse_quote!(()), "()"),
+        ];
+        for (ty, expected) in cases {
+            assert_eq!(type_name(&ty), expected);
+        }
+    }
 }
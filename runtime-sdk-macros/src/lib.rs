@@ -7,6 +7,7 @@ mod error_derive;
 mod event_derive;
 mod generators;
 mod module_derive;
+mod schema_derive;
 #[cfg(test)]
 mod test_utils;
 mod version_from_cargo;
@@ -43,10 +44,19 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
     error_derive::derive_error(input).into()
 }
 
+/// Derives the `Schema` trait on a struct or enum, following its `cbor` attributes.
+#[proc_macro_derive(Schema, attributes(cbor))]
+pub fn schema_derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    schema_derive::derive_schema(input).into()
+}
+
 /// Derives traits from a non-trait `impl` block (rather than from a `struct`).
 ///
 /// Only the `Module` trait is supported. In other words, given an `impl MyModule` block, the macro
-/// derives implementations needed for implementing a module.
+/// derives implementations needed for implementing a module. The `NAME`, `VERSION` and
+/// `DEPENDENCIES` constants, the `Error`, `Event` and `Parameters` types and the `features`
+/// function are used to implement the `Module` trait.
 /// See also the `#[handler]` and `#[migration]` attributes.
 #[proc_macro_attribute]
 pub fn sdk_derive(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -77,12 +87,27 @@ pub fn sdk_derive(args: TokenStream, input: TokenStream) -> TokenStream {
 ///  - `#[handler(message_result = "my_module.MyMR")]`: Marks a function that handles
 ///        the "my_module.MyMR" message result and can be passed to
 ///        oasis_runtime_sdk::module::dispatch_message_result.
+///        The message event argument can be either a `MessageEvent` or a
+///        `TypedMessageEvent<T>` in which case the result is decoded as `T`.
 ///
 /// Query handler can also contain the `expensive` tag. Example:
 /// `#[handler(query = "my_module.MyQuery", expensive)]`.
 /// Queries tagged `expensive` can be enabled/disabled are disabled by default to avoid
 /// excessive costs to the node operator. This can be overridden in the node config.
 ///
+/// Query handler can also contain the `authenticated` tag. Example:
+/// `#[handler(query = "my_module.MyQuery", authenticated)]`.
+/// On confidential runtimes, queries tagged `authenticated` must be wrapped in a signed query
+/// envelope and the handler can obtain the authenticated caller via
+/// `oasis_runtime_sdk::callformat::query_caller`.
+///
+/// Call and query handlers can also contain the `introduced_in` and `deprecated_since` tags,
+/// specifying module versions. Example:
+/// `#[handler(call = "my_module.MyCall", introduced_in = 2, deprecated_since = 3)]`.
+/// Methods are rejected with `core::Error::InvalidMethod` while the module state version recorded
+/// in state (see `Module::state_version`) is lower than `introduced_in`. Deprecated methods remain callable; both versions are reported in the
+/// `core.RuntimeMetadata` query.
+///
 /// NOTE: This attribute is parsed by the `#[sdk_derive(...)]` macro, which cannot
 /// interpret the attribute name semantically. Use `#[handler]`, not
 /// `#[oasis_runtime_sdk_macros::handler]` or other paths/aliases.
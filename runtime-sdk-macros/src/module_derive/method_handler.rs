@@ -2,7 +2,7 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::parse_quote;
 
-use crate::emit_compile_error;
+use crate::{emit_compile_error, generators as gen};
 
 /// Deriver for the `MethodHandler` trait.
 pub struct DeriveMethodHandler {
@@ -28,10 +28,13 @@ impl super::Deriver for DeriveMethodHandler {
             return Some(item);
         };
 
+        let (args_ty, result_ty) = handler_types(&method.sig);
         self.handlers.push(ParsedImplItem {
             handler: Some(HandlerInfo {
                 attrs,
                 ident: method.sig.ident.clone(),
+                args_ty,
+                result_ty,
             }),
             item,
         });
@@ -107,16 +110,25 @@ impl super::Deriver for DeriveMethodHandler {
                 .map(|h| {
                     (h.attrs.rpc_name.clone(), {
                         let ident = &h.ident;
+                        let introduced_check = introduced_check(h);
 
                         if h.attrs.is_internal {
                             quote! {
                                 |ctx, body| {
+                                    #introduced_check
                                     if !ctx.is_internal() {
                                         return Err(sdk::modules::core::Error::Forbidden.into());
                                     }
                                     Self::#ident(ctx, body)
                                 }
                             }
+                        } else if h.attrs.introduced_in.is_some() {
+                            quote! {
+                                |ctx, body| {
+                                    #introduced_check
+                                    Self::#ident(ctx, body)
+                                }
+                            }
                         } else {
                             quote! { Self::#ident }
                         }
@@ -153,7 +165,28 @@ impl super::Deriver for DeriveMethodHandler {
         };
 
         let dispatch_query_impl = {
-            let (handler_names, handler_idents) = filter_by_kind(handlers, HandlerKind::Query);
+            let (handler_names, handler_fns): (Vec<_>, Vec<_>) = handlers
+                .iter()
+                .filter_map(|h| h.handler.as_ref())
+                .filter(|h| h.attrs.kind == HandlerKind::Query)
+                .map(|h| {
+                    (h.attrs.rpc_name.clone(), {
+                        let ident = &h.ident;
+                        let introduced_check = introduced_check(h);
+
+                        if h.attrs.introduced_in.is_some() {
+                            quote! {
+                                |ctx, args| {
+                                    #introduced_check
+                                    Self::#ident(ctx, args)
+                                }
+                            }
+                        } else {
+                            quote! { Self::#ident }
+                        }
+                    })
+                })
+                .unzip();
 
             if handler_names.is_empty() {
                 quote! {
@@ -177,7 +210,7 @@ impl super::Deriver for DeriveMethodHandler {
                     ) -> DispatchResult<cbor::Value, Result<cbor::Value, sdk::error::RuntimeError>> {
                         match method {
                             #(
-                              #handler_names => module::dispatch_query(ctx, args, Self::#handler_idents),
+                              #handler_names => module::dispatch_query(ctx, args, #handler_fns),
                             )*
                             q if q == format!("{}.Parameters", Self::NAME) => module::dispatch_query(ctx, args, Self::query_parameters),
                             _ => DispatchResult::Unhandled(args),
@@ -203,9 +236,13 @@ impl super::Deriver for DeriveMethodHandler {
                         match handler_name {
                             #(
                               #handler_names => {
+                                  let event = match sdk::types::message::FromMessageEvent::from_message_event(result.event) {
+                                      Ok(event) => event,
+                                      Err(event) => return DispatchResult::Unhandled(MessageResult { event, context: result.context }),
+                                  };
                                   Self::#handler_idents(
                                       ctx,
-                                      result.event,
+                                      event,
                                       cbor::from_value(result.context).expect("invalid message handler context"),
                                   );
                                   DispatchResult::Handled(())
@@ -242,6 +279,52 @@ impl super::Deriver for DeriveMethodHandler {
             }
         };
 
+        let method_metadata_impl = {
+            let handlers: Vec<_> = handlers
+                .iter()
+                .filter_map(|h| h.handler.as_ref())
+                .filter(|h| h.attrs.kind != HandlerKind::Prefetch)
+                .collect();
+            let handler_names: Vec<_> = handlers.iter().map(|h| &h.attrs.rpc_name).collect();
+            let handler_kinds: Vec<_> = handlers
+                .iter()
+                .map(|h| h.attrs.kind.as_sdk_ident())
+                .collect();
+            let args_tys: Vec<_> = handlers.iter().map(|h| &h.args_ty).collect();
+            let result_tys: Vec<_> = handlers.iter().map(|h| &h.result_ty).collect();
+            let introduced_ins: Vec<_> = handlers
+                .iter()
+                .map(|h| optional_version(&h.attrs.introduced_in))
+                .collect();
+            let deprecated_sinces: Vec<_> = handlers
+                .iter()
+                .map(|h| optional_version(&h.attrs.deprecated_since))
+                .collect();
+            if handler_names.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    fn method_metadata(
+                        types: &mut sdk::schema::SchemaRegistry,
+                    ) -> Vec<core_types::MethodMetadata> {
+                        #[allow(unused_imports)]
+                        use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                        vec![ #(
+                            core_types::MethodMetadata {
+                                kind: #handler_kinds,
+                                name: #handler_names.to_string(),
+                                args: #args_tys,
+                                result: #result_tys,
+                                introduced_in: #introduced_ins,
+                                deprecated_since: #deprecated_sinces,
+                            },
+                        )* ]
+                    }
+                }
+            }
+        };
+
         let expensive_queries_impl = {
             let handler_names: Vec<syn::Expr> = handlers
                 .iter()
@@ -278,6 +361,24 @@ impl super::Deriver for DeriveMethodHandler {
             }
         };
 
+        let authenticated_queries_impl = {
+            let handler_names: Vec<syn::Expr> = handlers
+                .iter()
+                .filter_map(|h| h.handler.as_ref())
+                .filter(|h| h.attrs.kind == HandlerKind::Query && h.attrs.is_authenticated)
+                .map(|h| h.attrs.rpc_name.clone())
+                .collect();
+            if handler_names.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    fn is_authenticated_query(method: &str) -> bool {
+                        [ #( #handler_names, )* ].contains(&method)
+                    }
+                }
+            }
+        };
+
         let allowed_interactive_calls_impl = {
             let handler_names: Vec<syn::Expr> = handlers
                 .iter()
@@ -304,8 +405,10 @@ impl super::Deriver for DeriveMethodHandler {
                 #dispatch_query_impl
                 #dispatch_message_result_impl
                 #supported_methods_impl
+                #method_metadata_impl
                 #expensive_queries_impl
                 #allowed_private_km_queries_impl
+                #authenticated_queries_impl
                 #allowed_interactive_calls_impl
             }
 
@@ -319,6 +422,27 @@ impl super::Deriver for DeriveMethodHandler {
     }
 }
 
+/// Generates a check rejecting calls to the handler while the module state version is lower than
+/// the version the handler was introduced in.
+fn introduced_check(handler: &HandlerInfo) -> TokenStream {
+    match &handler.attrs.introduced_in {
+        Some(version) => quote! {
+            if <Self as module::Module>::state_version() < #version {
+                return Err(sdk::modules::core::Error::InvalidMethod(method.to_owned()).into());
+            }
+        },
+        None => quote! {},
+    }
+}
+
+/// Generates an `Option<u32>` expression for an optional version.
+fn optional_version(version: &Option<syn::LitInt>) -> TokenStream {
+    match version {
+        Some(version) => quote! { Some(#version) },
+        None => quote! { None },
+    }
+}
+
 /// An item (in the `syn` sense, i.e. a fn, type, comment, etc) in an `impl` block,
 /// plus parsed data about its #[handler] attribute, if any.
 #[derive(Clone)]
@@ -332,6 +456,88 @@ struct HandlerInfo {
     attrs: MethodHandlerAttr,
     /// Name of the handler function.
     ident: syn::Ident,
+    /// Expression registering the schema of the handler's argument type.
+    args_ty: TokenStream,
+    /// Expression registering the schema of the handler's result type, without the error.
+    result_ty: TokenStream,
+}
+
+/// Returns expressions registering the schemas of the argument and result types of a handler
+/// function, each evaluating to the name of the registered type.
+///
+/// The argument is the last function argument (the first one being the context) and the result
+/// is the success type in case the function returns a `Result`.
+fn handler_types(sig: &syn::Signature) -> (TokenStream, TokenStream) {
+    let unit: syn::Type = parse_quote!(());
+    let args_ty = sig
+        .inputs
+        .iter()
+        .rev()
+        .find_map(|arg| match arg {
+            syn::FnArg::Typed(arg) => Some(arg.ty.as_ref()),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .unwrap_or(&unit);
+
+    let result_ty = match &sig.output {
+        syn::ReturnType::Default => &unit,
+        syn::ReturnType::Type(_, ty) => {
+            let ok_ty = match ty.as_ref() {
+                syn::Type::Path(path) => path
+                    .path
+                    .segments
+                    .last()
+                    .filter(|segment| segment.ident == "Result")
+                    .and_then(|segment| match &segment.arguments {
+                        syn::PathArguments::AngleBracketed(args) => args.args.first(),
+                        _ => None,
+                    })
+                    .and_then(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    }),
+                _ => None,
+            };
+            ok_ty.unwrap_or(ty.as_ref())
+        }
+    };
+
+    (
+        schema_ref(args_ty, &sig.generics),
+        schema_ref(result_ty, &sig.generics),
+    )
+}
+
+/// Returns an expression registering the schema of the given type and evaluating to its name.
+///
+/// Types that do not implement `Schema` are registered as opaque. So are types referring to the
+/// handler's own generic parameters, as those cannot be named outside of the handler.
+fn schema_ref(ty: &syn::Type, generics: &syn::Generics) -> TokenStream {
+    fn mentions_generics(tokens: TokenStream, params: &[String]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => {
+                ident == "impl" || params.contains(&ident.to_string())
+            }
+            proc_macro2::TokenTree::Group(group) => mentions_generics(group.stream(), params),
+            _ => false,
+        })
+    }
+
+    let params: Vec<_> = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Type(param) => param.ident.to_string(),
+            syn::GenericParam::Lifetime(param) => param.lifetime.ident.to_string(),
+            syn::GenericParam::Const(param) => param.ident.to_string(),
+        })
+        .collect();
+    if mentions_generics(quote!(#ty), &params) {
+        let name = gen::type_name(ty);
+        quote!(types.opaque(#name))
+    } else {
+        gen::register_schema(&parse_quote!(sdk), ty)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -367,10 +573,19 @@ struct MethodHandlerAttr {
     /// Whether this handler is tagged as allowing access to private key manager state. Only applies
     /// to query handlers.
     allow_private_km: bool,
+    /// Whether this handler is tagged as requiring caller authentication. Only applies to query
+    /// handlers.
+    is_authenticated: bool,
     /// Whether this handler is tagged as allowing interactive calls. Only applies to call handlers.
     allow_interactive: bool,
     /// Whether this handler is tagged as internal.
     is_internal: bool,
+    /// Module version in which the handler was introduced. Only applies to call and query
+    /// handlers.
+    introduced_in: Option<syn::LitInt>,
+    /// Module version since which the handler is deprecated. Only applies to call and query
+    /// handlers.
+    deprecated_since: Option<syn::LitInt>,
 }
 impl syn::parse::Parse for MethodHandlerAttr {
     fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
@@ -388,8 +603,11 @@ impl syn::parse::Parse for MethodHandlerAttr {
         // Parse optional comma-separated tags.
         let mut is_expensive = false;
         let mut allow_private_km = false;
+        let mut is_authenticated = false;
         let mut allow_interactive = false;
         let mut is_internal = false;
+        let mut introduced_in: Option<syn::LitInt> = None;
+        let mut deprecated_since: Option<syn::LitInt> = None;
         while input.peek(syn::token::Comma) {
             let _: syn::token::Comma = input.parse()?;
             let tag: syn::Ident = input.parse()?;
@@ -410,6 +628,14 @@ impl syn::parse::Parse for MethodHandlerAttr {
                     ));
                 }
                 allow_private_km = true;
+            } else if tag == "authenticated" {
+                if kind != HandlerKind::Query {
+                    return Err(syn::Error::new(
+                        tag.span(),
+                        "`authenticated` tag is only allowed on `query` handlers",
+                    ));
+                }
+                is_authenticated = true;
             } else if tag == "allow_interactive" {
                 if kind != HandlerKind::Call {
                     return Err(syn::Error::new(
@@ -426,10 +652,25 @@ impl syn::parse::Parse for MethodHandlerAttr {
                     ));
                 }
                 is_internal = true;
+            } else if tag == "introduced_in" || tag == "deprecated_since" {
+                if kind != HandlerKind::Call && kind != HandlerKind::Query {
+                    return Err(syn::Error::new(
+                        tag.span(),
+                        format!("`{tag}` tag is only allowed on `call` and `query` handlers"),
+                    ));
+                }
+                let _: syn::token::Eq = input.parse()?;
+                let version: syn::LitInt = input.parse()?;
+                version.base10_parse::<u32>()?;
+                if tag == "introduced_in" {
+                    introduced_in = Some(version);
+                } else {
+                    deprecated_since = Some(version);
+                }
             } else {
                 return Err(syn::Error::new(
                     tag.span(),
-                    "invalid handler tag; supported: `expensive`, `allow_private_km`, `allow_interactive`, `internal`",
+                    "invalid handler tag; supported: `expensive`, `allow_private_km`, `authenticated`, `allow_interactive`, `internal`, `introduced_in`, `deprecated_since`",
                 ));
             }
         }
@@ -437,13 +678,24 @@ impl syn::parse::Parse for MethodHandlerAttr {
         if !input.is_empty() {
             return Err(syn::Error::new(input.span(), "unexpected extra tokens"));
         }
+        if let (Some(introduced_in), Some(deprecated_since)) = (&introduced_in, &deprecated_since) {
+            if deprecated_since.base10_parse::<u32>()? < introduced_in.base10_parse::<u32>()? {
+                return Err(syn::Error::new(
+                    deprecated_since.span(),
+                    "`deprecated_since` must not be lower than `introduced_in`",
+                ));
+            }
+        }
         Ok(Self {
             kind,
             rpc_name,
             is_expensive,
             allow_private_km,
+            is_authenticated,
             allow_interactive,
             is_internal,
+            introduced_in,
+            deprecated_since,
         })
     }
 }
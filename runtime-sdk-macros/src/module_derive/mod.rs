@@ -238,6 +238,39 @@ mod tests {
                                     },
                                 ]
                             }
+                            fn method_metadata(
+                                types: &mut sdk::schema::SchemaRegistry,
+                            ) -> Vec<core_types::MethodMetadata> {
+                                #[allow(unused_imports)]
+                                use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                                vec![
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Call,
+                                        name: "my_module.MyCall".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<Bar2>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<Baz2>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Call,
+                                        name: "my_module.MyOtherCall".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<Bar3>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<Baz3>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Call,
+                                        name: "my_module.MyInternalCall".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<Bar4>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<Baz4>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                ]
+                            }
                         }
                         #[automatically_derived]
                         impl<C: Cfg> MyModule<C> {
@@ -312,6 +345,39 @@ mod tests {
                                     },
                                 ]
                             }
+                            fn method_metadata(
+                                types: &mut sdk::schema::SchemaRegistry,
+                            ) -> Vec<core_types::MethodMetadata> {
+                                #[allow(unused_imports)]
+                                use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                                vec![
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Query,
+                                        name: RPC_NAME_OF_MY_QUERY.to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Query,
+                                        name: "module.OtherQuery".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Query,
+                                        name: "module.ConfidentialQuery".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                        introduced_in: None,
+                                        deprecated_since: None,
+                                    },
+                                ]
+                            }
                             fn is_expensive_query(method: &str) -> bool {
                                 ["module.OtherQuery", "module.ConfidentialQuery"].contains(&method)
                             }
@@ -341,6 +407,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn generate_method_handler_impl_versions() {
+        let input = syn::parse_quote!(
+            impl<C: Cfg> MyModule<C> {
+                #[handler(call = "my_module.MyCall", introduced_in = 2)]
+                fn my_call(foo: Bar) -> Baz {}
+                #[handler(query = "my_module.MyQuery", introduced_in = 2, deprecated_since = 3)]
+                fn my_query<Q: Context>(ctx: &mut Q, args: Vec<Q::Arg>) -> Result<u64, Error> {}
+            }
+        );
+
+        expect_module_impl(
+            input,
+            USES.with(|uses| {
+                syn::parse_quote!(
+                    const _: () = {
+                        #uses
+                        #[automatically_derived]
+                        impl<C: Cfg> sdk::module::MethodHandler for MyModule<C> {
+                            fn dispatch_call<C: TxContext>(
+                                ctx: &mut C,
+                                method: &str,
+                                body: cbor::Value,
+                            ) -> DispatchResult<cbor::Value, CallResult> {
+                                match method {
+                                    "my_module.MyCall" => module::dispatch_call(ctx, body, |ctx, body| {
+                                        if <Self as module::Module>::state_version() < 2 {
+                                            return Err(sdk::modules::core::Error::InvalidMethod(method.to_owned()).into());
+                                        }
+                                        Self::my_call(ctx, body)
+                                    }),
+                                    _ => DispatchResult::Unhandled(body),
+                                }
+                            }
+                            fn dispatch_query<C: Context>(
+                                ctx: &mut C,
+                                method: &str,
+                                args: cbor::Value,
+                            ) -> DispatchResult<cbor::Value, Result<cbor::Value, sdk::error::RuntimeError>>
+                            {
+                                match method {
+                                    "my_module.MyQuery" => module::dispatch_query(ctx, args, |ctx, args| {
+                                        if <Self as module::Module>::state_version() < 2 {
+                                            return Err(sdk::modules::core::Error::InvalidMethod(method.to_owned()).into());
+                                        }
+                                        Self::my_query(ctx, args)
+                                    }),
+                                    q if q == format!("{}.Parameters", Self::NAME) => {
+                                        module::dispatch_query(ctx, args, Self::query_parameters)
+                                    }
+                                    _ => DispatchResult::Unhandled(args),
+                                }
+                            }
+                            fn supported_methods() -> Vec<core_types::MethodHandlerInfo> {
+                                vec![
+                                    core_types::MethodHandlerInfo {
+                                        kind: core_types::MethodHandlerKind::Call,
+                                        name: "my_module.MyCall".to_string(),
+                                    },
+                                    core_types::MethodHandlerInfo {
+                                        kind: core_types::MethodHandlerKind::Query,
+                                        name: "my_module.MyQuery".to_string(),
+                                    },
+                                ]
+                            }
+                            fn method_metadata(
+                                types: &mut sdk::schema::SchemaRegistry,
+                            ) -> Vec<core_types::MethodMetadata> {
+                                #[allow(unused_imports)]
+                                use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                                vec![
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Call,
+                                        name: "my_module.MyCall".to_string(),
+                                        args: (&sdk::schema::__private::SchemaProbe::<Bar>::new()).register(types),
+                                        result: (&sdk::schema::__private::SchemaProbe::<Baz>::new()).register(types),
+                                        introduced_in: Some(2),
+                                        deprecated_since: None,
+                                    },
+                                    core_types::MethodMetadata {
+                                        kind: core_types::MethodHandlerKind::Query,
+                                        name: "my_module.MyQuery".to_string(),
+                                        args: types.opaque("Vec<Q::Arg>"),
+                                        result: (&sdk::schema::__private::SchemaProbe::<u64>::new()).register(types),
+                                        introduced_in: Some(2),
+                                        deprecated_since: Some(3),
+                                    },
+                                ]
+                            }
+                        }
+                        #[automatically_derived]
+                        impl<C: Cfg> MyModule<C> {
+                            fn query_parameters<C: Context>(
+                                _ctx: &mut C,
+                                _args: (),
+                            ) -> Result<<Self as module::Module>::Parameters, <Self as module::Module>::Error>
+                            {
+                                Ok(Self::params())
+                            }
+                            #[handler(call = "my_module.MyCall", introduced_in = 2)]
+                            fn my_call(foo: Bar) -> Baz {}
+                            #[handler(query = "my_module.MyQuery", introduced_in = 2, deprecated_since = 3)]
+                            fn my_query<Q: Context>(ctx: &mut Q, args: Vec<Q::Arg>) -> Result<u64, Error> {}
+                        }
+                    };
+                )
+            }),
+        );
+    }
+
     #[test]
     fn generate_method_handler_impl_method_calls() {
         let input = syn::parse_quote!(
@@ -378,6 +555,21 @@ mod tests {
                                     name: "my_module.MyMC".to_string(),
                                 }]
                             }
+                            fn method_metadata(
+                                types: &mut sdk::schema::SchemaRegistry,
+                            ) -> Vec<core_types::MethodMetadata> {
+                                #[allow(unused_imports)]
+                                use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                                vec![core_types::MethodMetadata {
+                                    kind: core_types::MethodHandlerKind::Query,
+                                    name: "my_module.MyMC".to_string(),
+                                    args: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                    result: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                    introduced_in: None,
+                                    deprecated_since: None,
+                                }]
+                            }
                         }
                         #[automatically_derived]
                         impl<C: Cfg> MyModule<C> {
@@ -393,6 +585,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn generate_module_impl_dependencies() {
+        let input = syn::parse_quote!(
+            impl<C: Cfg> MyModule<C> {
+                const NAME: &'static str = MODULE_NAME;
+                const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+            }
+        );
+
+        expect_module_impl(
+            input,
+            USES.with(|uses| {
+                syn::parse_quote!(
+                    const _: () = {
+                        #uses
+                        #[automatically_derived]
+                        impl<C: Cfg> sdk::module::Module for MyModule<C> {
+                            const NAME: &'static str = MODULE_NAME;
+                            const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+                        }
+                        #[automatically_derived]
+                        impl<C: Cfg> sdk::module::MethodHandler for MyModule<C> {
+                            fn dispatch_query<C: Context>(
+                                ctx: &mut C,
+                                method: &str,
+                                args: cbor::Value,
+                            ) -> DispatchResult<cbor::Value, Result<cbor::Value, sdk::error::RuntimeError>>
+                            {
+                                match method {
+                                    q if q == format!("{}.Parameters", Self::NAME) => {
+                                        module::dispatch_query(ctx, args, Self::query_parameters)
+                                    }
+                                    _ => DispatchResult::Unhandled(args),
+                                }
+                            }
+                        }
+                        #[automatically_derived]
+                        impl<C: Cfg> MyModule<C> {
+                            fn query_parameters<C: Context>(_ctx: &mut C, _args: ()) -> Result<<Self as module::Module>::Parameters, <Self as module::Module>::Error> {
+                                Ok(Self::params())
+                            }
+                        }
+                    };
+                )
+            }),
+        );
+    }
+
+    #[test]
+    fn generate_module_impl_features() {
+        let input = syn::parse_quote!(
+            impl<C: Cfg> MyModule<C> {
+                const NAME: &'static str = MODULE_NAME;
+
+                fn features() -> BTreeMap<String, cbor::Value> {
+                    BTreeMap::new()
+                }
+            }
+        );
+
+        expect_module_impl(
+            input,
+            USES.with(|uses| {
+                syn::parse_quote!(
+                    const _: () = {
+                        #uses
+                        #[automatically_derived]
+                        impl<C: Cfg> sdk::module::Module for MyModule<C> {
+                            const NAME: &'static str = MODULE_NAME;
+                            fn features() -> BTreeMap<String, cbor::Value> {
+                                BTreeMap::new()
+                            }
+                        }
+                        #[automatically_derived]
+                        impl<C: Cfg> sdk::module::MethodHandler for MyModule<C> {
+                            fn dispatch_query<C: Context>(
+                                ctx: &mut C,
+                                method: &str,
+                                args: cbor::Value,
+                            ) -> DispatchResult<cbor::Value, Result<cbor::Value, sdk::error::RuntimeError>>
+                            {
+                                match method {
+                                    q if q == format!("{}.Parameters", Self::NAME) => {
+                                        module::dispatch_query(ctx, args, Self::query_parameters)
+                                    }
+                                    _ => DispatchResult::Unhandled(args),
+                                }
+                            }
+                        }
+                        #[automatically_derived]
+                        impl<C: Cfg> MyModule<C> {
+                            fn query_parameters<C: Context>(_ctx: &mut C, _args: ()) -> Result<<Self as module::Module>::Parameters, <Self as module::Module>::Error> {
+                                Ok(Self::params())
+                            }
+                        }
+                    };
+                )
+            }),
+        );
+    }
+
     #[test]
     fn generate_module_impl() {
         let input = syn::parse_quote!(
@@ -501,6 +794,21 @@ mod tests {
                                     name: "my_module.MyMC".to_string(),
                                 }]
                             }
+                            fn method_metadata(
+                                types: &mut sdk::schema::SchemaRegistry,
+                            ) -> Vec<core_types::MethodMetadata> {
+                                #[allow(unused_imports)]
+                                use sdk::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                                vec![core_types::MethodMetadata {
+                                    kind: core_types::MethodHandlerKind::Query,
+                                    name: "my_module.MyMC".to_string(),
+                                    args: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                    result: (&sdk::schema::__private::SchemaProbe::<()>::new()).register(types),
+                                    introduced_in: None,
+                                    deprecated_since: None,
+                                }]
+                            }
                         }
                         #[automatically_derived]
                         impl<C: Cfg> MyModule<C> {
@@ -552,6 +860,18 @@ mod tests {
         super::derive_module(input);
     }
 
+    #[test]
+    #[should_panic(expected = "only allowed on `query` handlers")]
+    fn generate_method_handler_malformed_authenticated_nonquery() {
+        let input: syn::ItemImpl = syn::parse_quote!(
+            impl<C: Cfg> MyModule<C> {
+                #[handler(call = "foo", authenticated)]
+                fn my_method_call() -> () {}
+            }
+        );
+        super::derive_module(input);
+    }
+
     #[test]
     #[should_panic(expected = "only allowed on `call` handlers")]
     fn generate_method_handler_malformed_internal_noncall() {
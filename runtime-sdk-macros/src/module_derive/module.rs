@@ -27,13 +27,17 @@ impl super::Deriver for DeriveModule {
             }
             syn::ImplItem::Const(ref cnst) => {
                 match cnst.ident.to_string().as_str() {
-                    "NAME" | "VERSION" => {
+                    "NAME" | "VERSION" | "DEPENDENCIES" => {
                         self.module_cfg.push(item);
                         None // Take the item.
                     }
                     _ => Some(item), // Return the item.
                 }
             }
+            syn::ImplItem::Fn(ref f) if f.sig.ident == "features" => {
+                self.module_cfg.push(item);
+                None // Take the item.
+            }
             _ => Some(item), // Return the item.
         }
     }
@@ -0,0 +1,483 @@
+use darling::{util::Flag, FromDeriveInput, FromField, FromMeta, FromVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Ident};
+
+use crate::generators as gen;
+
+// The `cbor` attributes mirror those of `cbor::Encode` so that schemas describe the same
+// encoding. Attributes that do not affect the shape of the encoding are accepted but ignored.
+// Fields of types that do not implement `Schema` are described as opaque.
+
+#[derive(FromDeriveInput)]
+#[darling(supports(any), attributes(cbor))]
+#[allow(dead_code)]
+struct Schema {
+    ident: Ident,
+
+    generics: syn::Generics,
+
+    data: darling::ast::Data<SchemaVariant, SchemaField>,
+
+    transparent: Flag,
+
+    untagged: Flag,
+
+    tag: Option<Key>,
+
+    as_array: Flag,
+
+    no_default: Flag,
+
+    with_default: Flag,
+
+    allow_unknown: Flag,
+}
+
+#[derive(FromVariant)]
+#[darling(attributes(cbor))]
+#[allow(dead_code)]
+struct SchemaVariant {
+    ident: Ident,
+
+    discriminant: Option<syn::Expr>,
+
+    fields: darling::ast::Fields<SchemaField>,
+
+    rename: Option<Key>,
+
+    as_array: Flag,
+
+    as_struct: Flag,
+
+    skip: Flag,
+
+    embed: Flag,
+
+    allow_unknown: Flag,
+
+    missing: Flag,
+}
+
+#[derive(FromField)]
+#[darling(attributes(cbor))]
+#[allow(dead_code)]
+struct SchemaField {
+    ident: Option<Ident>,
+
+    ty: syn::Type,
+
+    rename: Option<Key>,
+
+    optional: Flag,
+
+    skip: Flag,
+
+    skip_serializing_if: Option<syn::Path>,
+
+    serialize_with: Option<syn::Path>,
+
+    deserialize_with: Option<syn::Path>,
+}
+
+/// A CBOR map key, as given in `#[cbor(rename = ...)]`.
+struct Key(String);
+
+impl FromMeta for Key {
+    fn from_value(value: &syn::Lit) -> darling::Result<Self> {
+        match value {
+            syn::Lit::Str(s) => Ok(Self(s.value())),
+            syn::Lit::Int(i) => Ok(Self(i.base10_digits().to_string())),
+            _ => Err(darling::Error::unexpected_lit_type(value).with_span(value)),
+        }
+    }
+}
+
+pub fn derive_schema(input: DeriveInput) -> TokenStream {
+    let schema = match Schema::from_derive_input(&input) {
+        Ok(schema) => schema,
+        Err(e) => return e.write_errors(),
+    };
+
+    let sdk_crate = gen::sdk_crate_path();
+    let ident = &schema.ident;
+
+    let mut generics = schema.generics.clone();
+    let type_params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(#sdk_crate::schema::Schema));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let ident_str = ident.to_string();
+    let name = if type_params.is_empty() {
+        quote!(concat!(module_path!(), "::", #ident_str).to_string())
+    } else {
+        quote! {
+            format!(
+                "{}::{}<{}>",
+                module_path!(),
+                #ident_str,
+                [#(<#type_params as #sdk_crate::schema::Schema>::schema_name()),*].join(", "),
+            )
+        }
+    };
+
+    let body = match &schema.data {
+        darling::ast::Data::Struct(fields) if schema.transparent.is_present() => {
+            match fields.iter().find(|field| !field.skip.is_present()) {
+                Some(field) => {
+                    let ty = &field.ty;
+                    quote!(<#ty as #sdk_crate::schema::Schema>::schema(types))
+                }
+                None => quote!(#sdk_crate::schema::TypeSchema::default()),
+            }
+        }
+        darling::ast::Data::Struct(fields) => {
+            struct_schema(&sdk_crate, fields, schema.as_array.is_present(), false)
+        }
+        darling::ast::Data::Enum(_) if schema.tag.is_some() => {
+            // Internally tagged enums are not described.
+            quote!(#sdk_crate::schema::TypeSchema::default())
+        }
+        darling::ast::Data::Enum(variants) => {
+            enum_schema(&sdk_crate, variants, schema.untagged.is_present())
+        }
+    };
+
+    gen::wrap_in_const(quote! {
+        impl #impl_generics #sdk_crate::schema::Schema for #ident #ty_generics #where_clause {
+            fn schema_name() -> String {
+                #name
+            }
+
+            fn schema(types: &mut #sdk_crate::schema::SchemaRegistry) -> #sdk_crate::schema::TypeSchema {
+                #[allow(unused_imports)]
+                use #sdk_crate::schema::__private::{ViaFallback as _, ViaSchema as _};
+
+                #body
+            }
+        }
+    })
+}
+
+/// Generates an expression evaluating to the schema of a struct (or struct-like enum variant)
+/// with the given fields.
+fn struct_schema(
+    sdk_crate: &syn::Path,
+    fields: &darling::ast::Fields<SchemaField>,
+    as_array: bool,
+    unit_as_struct: bool,
+) -> TokenStream {
+    let fields: Vec<_> = fields
+        .iter()
+        .filter(|field| !field.skip.is_present())
+        .collect();
+    let is_named = fields.iter().any(|field| field.ident.is_some());
+
+    if fields.is_empty() && !unit_as_struct {
+        quote!(#sdk_crate::schema::TypeSchema::new(#sdk_crate::schema::TypeKind::Unit))
+    } else if as_array || !is_named && !fields.is_empty() {
+        let items = fields
+            .iter()
+            .map(|field| gen::register_schema(sdk_crate, &field.ty));
+        quote! {
+            #sdk_crate::schema::TypeSchema::with_items(
+                #sdk_crate::schema::TypeKind::Tuple,
+                vec![#(#items),*],
+            )
+        }
+    } else {
+        let fields = fields.iter().map(|field| {
+            let name = field
+                .rename
+                .as_ref()
+                .map(|key| key.0.clone())
+                .or_else(|| field.ident.as_ref().map(ToString::to_string))
+                .unwrap_or_default();
+            let ty = gen::register_schema(sdk_crate, &field.ty);
+            let optional = field.optional.is_present() || field.skip_serializing_if.is_some();
+            quote! {
+                #sdk_crate::schema::FieldSchema {
+                    name: #name.to_string(),
+                    ty: #ty,
+                    optional: #optional,
+                }
+            }
+        });
+        quote! {
+            #sdk_crate::schema::TypeSchema {
+                kind: #sdk_crate::schema::TypeKind::Struct,
+                fields: vec![#(#fields),*],
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Generates an expression evaluating to the schema of an enum with the given variants. Payloads
+/// of variants with more than one field are registered as `<enum>::<variant>`.
+fn enum_schema(sdk_crate: &syn::Path, variants: &[SchemaVariant], untagged: bool) -> TokenStream {
+    let variants = variants
+        .iter()
+        .filter(|variant| !variant.skip.is_present())
+        .map(|variant| {
+            let variant_str = variant.ident.to_string();
+            let name = if variant.embed.is_present() {
+                String::new()
+            } else {
+                variant
+                    .rename
+                    .as_ref()
+                    .map(|key| key.0.clone())
+                    .unwrap_or_else(|| variant_str.clone())
+            };
+            let is_unit = variant.fields.is_unit() && !variant.as_struct.is_present();
+            let discriminant = match &variant.discriminant {
+                Some(syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(value),
+                    ..
+                })) if is_unit => value
+                    .base10_parse::<u64>()
+                    .map(|value| quote!(Some(#value)))
+                    .unwrap_or_else(|e| e.to_compile_error()),
+                _ => quote!(None),
+            };
+            let ty = if is_unit {
+                quote!(None)
+            } else if variant.fields.is_newtype() && !variant.as_array.is_present() {
+                let ty = gen::register_schema(sdk_crate, &variant.fields.fields[0].ty);
+                quote!(Some(#ty))
+            } else {
+                let schema = struct_schema(
+                    sdk_crate,
+                    &variant.fields,
+                    variant.as_array.is_present(),
+                    variant.as_struct.is_present(),
+                );
+                quote! {{
+                    let schema = #schema;
+                    Some(types.define(format!("{}::{}", Self::schema_name(), #variant_str), schema))
+                }}
+            };
+            quote! {
+                #sdk_crate::schema::VariantSchema {
+                    name: #name.to_string(),
+                    discriminant: #discriminant,
+                    ty: #ty,
+                }
+            }
+        });
+
+    quote! {
+        #sdk_crate::schema::TypeSchema {
+            kind: #sdk_crate::schema::TypeKind::Enum,
+            variants: vec![#(#variants),*],
+            untagged: #untagged,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // The expected code is delimited by braces so that it is not reformatted, as rustfmt would
+    // add trailing commas that are not present in the generated code.
+
+    #[test]
+    fn generate_schema_impl_struct() {
+        let expected: syn::Stmt = syn::parse_quote! {
+            const _: () = {
+                impl ::oasis_runtime_sdk::schema::Schema for Transfer {
+                    fn schema_name() -> String {
+                        concat!(module_path!(), "::", "Transfer").to_string()
+                    }
+                    fn schema(
+                        types: &mut ::oasis_runtime_sdk::schema::SchemaRegistry,
+                    ) -> ::oasis_runtime_sdk::schema::TypeSchema {
+                        #[allow(unused_imports)]
+                        use ::oasis_runtime_sdk::schema::__private::{
+                            ViaFallback as _, ViaSchema as _,
+                        };
+
+                        ::oasis_runtime_sdk::schema::TypeSchema {
+                            kind: ::oasis_runtime_sdk::schema::TypeKind::Struct,
+                            fields: vec![
+                                ::oasis_runtime_sdk::schema::FieldSchema {
+                                    name: "to".to_string(),
+                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<Address>::new())
+                                        .register(types),
+                                    optional: false,
+                                },
+                                ::oasis_runtime_sdk::schema::FieldSchema {
+                                    name: "amt".to_string(),
+                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<BaseUnits>::new())
+                                        .register(types),
+                                    optional: true,
+                                }
+                            ],
+                            ..Default::default()
+                        }
+                    }
+                }
+            };
+        };
+
+        let input: syn::DeriveInput = syn::parse_quote!(
+            #[derive(Schema)]
+            #[cbor(no_default)]
+            struct Transfer {
+                to: Address,
+                #[cbor(rename = "amt", optional)]
+                amount: BaseUnits,
+                #[cbor(skip)]
+                cached: u64,
+            }
+        );
+        let schema_derivation = super::derive_schema(input);
+        let actual: syn::Stmt = syn::parse2(schema_derivation).unwrap();
+
+        crate::assert_empty_diff!(actual, expected);
+    }
+
+    #[test]
+    fn generate_schema_impl_generic_tuple() {
+        let expected: syn::Stmt = syn::parse_quote! {
+            const _: () = {
+                impl<T: ::oasis_runtime_sdk::schema::Schema> ::oasis_runtime_sdk::schema::Schema
+                    for Pair<T>
+                {
+                    fn schema_name() -> String {
+                        format!(
+                            "{}::{}<{}>",
+                            module_path!(),
+                            "Pair",
+                            [<T as ::oasis_runtime_sdk::schema::Schema>::schema_name()].join(", "),
+                        )
+                    }
+                    fn schema(
+                        types: &mut ::oasis_runtime_sdk::schema::SchemaRegistry,
+                    ) -> ::oasis_runtime_sdk::schema::TypeSchema {
+                        #[allow(unused_imports)]
+                        use ::oasis_runtime_sdk::schema::__private::{
+                            ViaFallback as _, ViaSchema as _,
+                        };
+
+                        ::oasis_runtime_sdk::schema::TypeSchema::with_items(
+                            ::oasis_runtime_sdk::schema::TypeKind::Tuple,
+                            vec![
+                                (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<T>::new())
+                                    .register(types),
+                                (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<u64>::new())
+                                    .register(types)
+                            ],
+                        )
+                    }
+                }
+            };
+        };
+
+        let input: syn::DeriveInput = syn::parse_quote!(
+            #[derive(Schema)]
+            struct Pair<T>(T, u64);
+        );
+        let schema_derivation = super::derive_schema(input);
+        let actual: syn::Stmt = syn::parse2(schema_derivation).unwrap();
+
+        crate::assert_empty_diff!(actual, expected);
+    }
+
+    #[test]
+    fn generate_schema_impl_enum() {
+        let expected: syn::Stmt = syn::parse_quote! {
+            const _: () = {
+                impl ::oasis_runtime_sdk::schema::Schema for Format {
+                    fn schema_name() -> String {
+                        concat!(module_path!(), "::", "Format").to_string()
+                    }
+                    fn schema(
+                        types: &mut ::oasis_runtime_sdk::schema::SchemaRegistry,
+                    ) -> ::oasis_runtime_sdk::schema::TypeSchema {
+                        #[allow(unused_imports)]
+                        use ::oasis_runtime_sdk::schema::__private::{
+                            ViaFallback as _, ViaSchema as _,
+                        };
+
+                        ::oasis_runtime_sdk::schema::TypeSchema {
+                            kind: ::oasis_runtime_sdk::schema::TypeKind::Enum,
+                            variants: vec![
+                                ::oasis_runtime_sdk::schema::VariantSchema {
+                                    name: "Plain".to_string(),
+                                    discriminant: Some(0u64),
+                                    ty: None,
+                                },
+                                ::oasis_runtime_sdk::schema::VariantSchema {
+                                    name: "wrapped".to_string(),
+                                    discriminant: None,
+                                    ty: Some(
+                                        (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<Vec<u8>>::new())
+                                            .register(types)
+                                    ),
+                                },
+                                ::oasis_runtime_sdk::schema::VariantSchema {
+                                    name: "Pair".to_string(),
+                                    discriminant: None,
+                                    ty: {
+                                        let schema = ::oasis_runtime_sdk::schema::TypeSchema {
+                                            kind: ::oasis_runtime_sdk::schema::TypeKind::Struct,
+                                            fields: vec![
+                                                ::oasis_runtime_sdk::schema::FieldSchema {
+                                                    name: "a".to_string(),
+                                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<u8>::new())
+                                                        .register(types),
+                                                    optional: false,
+                                                },
+                                                ::oasis_runtime_sdk::schema::FieldSchema {
+                                                    name: "b".to_string(),
+                                                    ty: (&::oasis_runtime_sdk::schema::__private::SchemaProbe::<u8>::new())
+                                                        .register(types),
+                                                    optional: false,
+                                                }
+                                            ],
+                                            ..Default::default()
+                                        };
+                                        Some(types.define(
+                                            format!("{}::{}", Self::schema_name(), "Pair"),
+                                            schema
+                                        ))
+                                    },
+                                }
+                            ],
+                            untagged: false,
+                            ..Default::default()
+                        }
+                    }
+                }
+            };
+        };
+
+        let input: syn::DeriveInput = syn::parse_quote!(
+            #[derive(Schema)]
+            enum Format {
+                Plain = 0,
+                #[cbor(rename = "wrapped")]
+                Wrapped(Vec<u8>),
+                Pair {
+                    a: u8,
+                    b: u8,
+                },
+                #[cbor(skip)]
+                Internal,
+            }
+        );
+        let schema_derivation = super::derive_schema(input);
+        let actual: syn::Stmt = syn::parse2(schema_derivation).unwrap();
+
+        crate::assert_empty_diff!(actual, expected);
+    }
+}
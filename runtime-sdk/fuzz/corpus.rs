@@ -0,0 +1,24 @@
+use std::{fs, path};
+
+use oasis_runtime_sdk::testing::fuzz::{corpus_calls, corpus_envelopes, corpus_txs};
+
+fn main() {
+    let targets = vec![
+        ("fuzz-decode-tx", corpus_envelopes()),
+        ("fuzz-decode-call", corpus_calls()),
+        ("fuzz-dispatch-tx", corpus_txs()),
+        ("fuzz-check-tx", corpus_envelopes()),
+    ];
+
+    for (target, inputs) in targets {
+        let output_dir = path::Path::new("hfuzz_workspace")
+            .join(target)
+            .join("input");
+        fs::create_dir_all(&output_dir).expect("failed to create output directory");
+
+        for (idx, input) in inputs.into_iter().enumerate() {
+            fs::write(output_dir.join(format!("{idx}")), input)
+                .expect("failed to write input file");
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use honggfuzz::fuzz;
+
+use oasis_runtime_sdk::testing::fuzz::{decode_call, FuzzRuntime};
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            decode_call::<FuzzRuntime>(data);
+        });
+    }
+}
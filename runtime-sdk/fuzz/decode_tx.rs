@@ -0,0 +1,11 @@
+use honggfuzz::fuzz;
+
+use oasis_runtime_sdk::testing::fuzz::{decode_tx, FuzzRuntime};
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            decode_tx::<FuzzRuntime>(data);
+        });
+    }
+}
@@ -0,0 +1,11 @@
+use honggfuzz::fuzz;
+
+use oasis_runtime_sdk::testing::fuzz::{dispatch_tx, FuzzRuntime};
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            dispatch_tx::<FuzzRuntime>(data);
+        });
+    }
+}
@@ -0,0 +1,97 @@
+//! Gas cost regression benchmarks for core SDK modules.
+//!
+//! Usage: `gas-benchmark [OUTPUT]`. The report is written to stdout when no output is given.
+use std::{collections::BTreeMap, env, fs};
+
+use oasis_runtime_sdk::{
+    module,
+    modules::{self, accounts},
+    testing::{gas::Bench, keys, scenario::Tx},
+    types::{
+        address::Address,
+        token::{self, Denomination},
+    },
+    Runtime, Version,
+};
+
+/// Number of transactions executed for each workload.
+const ITERATIONS: u64 = 100;
+
+struct Config;
+
+impl modules::core::Config for Config {
+    const EMIT_GAS_USED_EVENTS: bool = true;
+}
+
+struct BenchRuntime;
+
+impl Runtime for BenchRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+
+    type Core = modules::core::Module<Config>;
+
+    type Modules = (modules::core::Module<Config>, accounts::Module);
+
+    fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+        (
+            modules::core::Genesis {
+                parameters: modules::core::Parameters {
+                    max_batch_gas: 10_000_000,
+                    max_tx_size: 32 * 1024,
+                    max_tx_signers: 8,
+                    max_multisig_signers: 8,
+                    gas_costs: modules::core::GasCosts {
+                        tx_byte: 1,
+                        auth_signature: 1_000,
+                        auth_multisig_signer: 1_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                parameters: accounts::Parameters {
+                    gas_costs: accounts::GasCosts {
+                        tx_transfer: 1_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                balances: BTreeMap::from([(
+                    keys::alice::address(),
+                    BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                )]),
+                total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+fn transfer(to: Address) -> Tx {
+    Tx::new(
+        keys::alice::sigspec(),
+        "accounts.Transfer",
+        accounts::types::Transfer {
+            to,
+            amount: token::BaseUnits::new(1, Denomination::NATIVE),
+        },
+    )
+}
+
+fn main() {
+    let mut bench = Bench::<BenchRuntime>::new(ITERATIONS);
+
+    // Transfers to an existing account and to new accounts.
+    bench.measure("accounts.Transfer", |_| transfer(keys::bob::address()));
+    bench.measure("accounts.Transfer/new_account", |iteration| {
+        transfer(Address::from_module("bench", &iteration.to_string()))
+    });
+
+    let report = bench.finish().to_string();
+    match env::args().nth(1) {
+        Some(output) => fs::write(output, report).expect("failed to write report"),
+        None => print!("{report}"),
+    }
+}
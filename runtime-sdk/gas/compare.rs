@@ -0,0 +1,57 @@
+//! Compare a gas benchmark report against a baseline.
+//!
+//! Usage: `gas-compare BASELINE CURRENT [--gas-threshold PERCENT] [--time-threshold PERCENT]`.
+//! Exits with a non-zero status in case any workload drifted beyond the thresholds.
+use std::{env, fs, process};
+
+use oasis_runtime_sdk::testing::gas::{compare, Report, Thresholds};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: gas-compare BASELINE CURRENT [--gas-threshold PERCENT] [--time-threshold PERCENT]"
+    );
+    process::exit(2);
+}
+
+fn read_report(path: &str) -> Report {
+    let data = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("failed to read report {path}: {err}");
+        process::exit(2);
+    });
+    data.parse().unwrap_or_else(|err| {
+        eprintln!("failed to parse report {path}: {err}");
+        process::exit(2);
+    })
+}
+
+fn main() {
+    let mut paths = vec![];
+    let mut thresholds = Thresholds::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut percent = || -> u64 {
+            args.next()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| usage())
+        };
+        match arg.as_str() {
+            "--gas-threshold" => thresholds.gas_percent = percent(),
+            "--time-threshold" => thresholds.time_percent = Some(percent()),
+            _ if arg.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    let (baseline, current) = match &paths[..] {
+        [baseline, current] => (read_report(baseline), read_report(current)),
+        _ => usage(),
+    };
+
+    let comparison = compare(&baseline, &current, thresholds);
+    for drift in &comparison.drifts {
+        println!("{drift}");
+    }
+    if !comparison.is_ok() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,150 @@
+//! Gas cost regression benchmarks for the contracts module.
+//!
+//! Usage: `gas-benchmark-contracts [OUTPUT]`. The report is written to stdout when no output is
+//! given. Requires the hello test contract to be built.
+use std::{collections::BTreeMap, env, fs, io::Write};
+
+use oasis_runtime_sdk::{
+    module,
+    modules::{self, accounts},
+    testing::{gas::Bench, keys, scenario::Tx},
+    types::{token::Denomination, transaction},
+    Runtime, Version,
+};
+use oasis_runtime_sdk_contracts::{types, Config, Genesis, Module as Contracts};
+
+/// Hello contract code.
+static HELLO_CONTRACT_CODE: &[u8] = include_bytes!(
+    "../../../../tests/contracts/hello/target/wasm32-unknown-unknown/release/hello.wasm"
+);
+
+/// Number of transactions executed for each workload.
+const ITERATIONS: u64 = 20;
+
+struct CoreConfig;
+
+impl modules::core::Config for CoreConfig {
+    const EMIT_GAS_USED_EVENTS: bool = true;
+}
+
+struct ContractsConfig;
+
+impl Config for ContractsConfig {
+    type Accounts = accounts::Module;
+}
+
+struct BenchRuntime;
+
+impl Runtime for BenchRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+
+    type Core = modules::core::Module<CoreConfig>;
+
+    type Modules = (
+        modules::core::Module<CoreConfig>,
+        accounts::Module,
+        Contracts<ContractsConfig>,
+    );
+
+    fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+        (
+            modules::core::Genesis {
+                parameters: modules::core::Parameters {
+                    max_batch_gas: 1_000_000_000,
+                    max_tx_size: 512 * 1024,
+                    max_tx_signers: 8,
+                    max_multisig_signers: 8,
+                    gas_costs: modules::core::GasCosts {
+                        tx_byte: 1,
+                        auth_signature: 1_000,
+                        auth_multisig_signer: 1_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                balances: BTreeMap::from([(
+                    keys::alice::address(),
+                    BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                )]),
+                total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                ..Default::default()
+            },
+            Genesis {
+                parameters: Default::default(),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+fn upload_hello() -> Tx {
+    // Compress contract code.
+    let mut code = Vec::with_capacity(HELLO_CONTRACT_CODE.len() << 3);
+    let mut encoder = snap::write::FrameEncoder::new(&mut code);
+    encoder.write_all(HELLO_CONTRACT_CODE).unwrap();
+    drop(encoder); // Make sure data is flushed.
+
+    Tx::new(
+        keys::alice::sigspec(),
+        "contracts.Upload",
+        types::Upload {
+            abi: types::ABI::OasisV1,
+            instantiate_policy: types::Policy::Everyone,
+            code,
+        },
+    )
+    .with_fee(transaction::Fee {
+        amount: Default::default(),
+        gas: 160_000_000,
+        consensus_messages: 0,
+    })
+}
+
+fn main() {
+    let mut bench = Bench::<BenchRuntime>::new(ITERATIONS);
+
+    bench.measure("contracts.Upload/hello", |_| upload_hello());
+
+    // Upload the code that is shared by all instantiation workloads.
+    let mut result = bench
+        .scenario()
+        .block()
+        .tx(upload_hello())
+        .run()
+        .expect("block should execute");
+    let code_id = match result.txs.remove(0).result {
+        module::CallResult::Ok(result) => {
+            cbor::from_value::<types::UploadResult>(result)
+                .expect("upload result should decode")
+                .id
+        }
+        result => panic!("contract upload failed: {result:?}"),
+    };
+
+    bench.measure("contracts.Instantiate/hello", |_| {
+        Tx::new(
+            keys::alice::sigspec(),
+            "contracts.Instantiate",
+            types::Instantiate {
+                code_id,
+                upgrades_policy: types::Policy::Address(keys::alice::address()),
+                // Needs to conform to contract API.
+                data: cbor::to_vec(cbor::cbor_map! {
+                    "instantiate" => cbor::cbor_map! {
+                        "initial_counter" => cbor::cbor_int!(33)
+                    }
+                }),
+                tokens: vec![],
+            },
+        )
+    });
+
+    let report = bench.finish().to_string();
+    match env::args().nth(1) {
+        Some(output) => fs::write(output, report).expect("failed to write report"),
+        None => print!("{report}"),
+    }
+}
@@ -2,7 +2,7 @@
 use oasis_contract_sdk_types::{message::Reply, ExecutionOk};
 use oasis_runtime_sdk::{
     context::Context,
-    types::{address::Address, token, transaction::CallFormat},
+    types::{address::Address, app_id::AppId, token, transaction::CallFormat},
 };
 
 use super::{types, Error, Parameters};
@@ -110,6 +110,8 @@ pub struct ExecutionContext<'ctx, C: Context> {
     pub read_only: bool,
     /// Call format.
     pub call_format: CallFormat,
+    /// ROFL application whose authorized instance originated the transaction, if any.
+    pub rofl_origin: Option<AppId>,
 
     /// Whether the execution has aborted with an error that should be propagated instead of just
     /// using the generic "execution failed" error.
@@ -127,6 +129,7 @@ impl<'ctx, C: Context> ExecutionContext<'ctx, C> {
         caller_address: Address,
         read_only: bool,
         call_format: CallFormat,
+        rofl_origin: Option<AppId>,
         tx_context: &'ctx mut C,
     ) -> Self {
         Self {
@@ -138,6 +141,7 @@ impl<'ctx, C: Context> ExecutionContext<'ctx, C> {
             caller_address,
             read_only,
             call_format,
+            rofl_origin,
             aborted: None,
         }
     }
@@ -1,9 +1,17 @@
 //! Environment query imports.
 use oasis_contract_sdk_types::{
-    env::{AccountsQuery, AccountsResponse, QueryRequest, QueryResponse},
+    env::{
+        AccountsQuery, AccountsResponse, OracleQuery, OracleResponse, QueryRequest, QueryResponse,
+        RoflQuery, RoflResponse,
+    },
     InstanceId,
 };
-use oasis_runtime_sdk::{context::Context, modules::accounts::API as _};
+use oasis_runtime_sdk::{
+    context::Context,
+    error::Error as _,
+    modules::{accounts::API as _, oracle, oracle::API as _},
+    types::app_id::AppId,
+};
 
 use super::{memory::Region, OasisV1};
 use crate::{
@@ -42,7 +50,7 @@ impl<Cfg: Config> OasisV1<Cfg> {
                 )??;
 
                 // Dispatch query.
-                let result = dispatch_query::<Cfg, C>(ec.tx_context, request);
+                let result = dispatch_query::<Cfg, C>(ec.tx_context, ec.rofl_origin, request);
 
                 // Create new region by calling `allocate`.
                 //
@@ -102,18 +110,28 @@ impl<Cfg: Config> OasisV1<Cfg> {
 }
 
 /// Perform environment query dispatch.
-fn dispatch_query<Cfg: Config, C: Context>(ctx: &mut C, query: QueryRequest) -> QueryResponse {
+fn dispatch_query<Cfg: Config, C: Context>(
+    ctx: &mut C,
+    rofl_origin: Option<AppId>,
+    query: QueryRequest,
+) -> QueryResponse {
     match query {
         // Information about the current runtime block.
         QueryRequest::BlockInfo => QueryResponse::BlockInfo {
-            round: ctx.runtime_header().round,
+            round: ctx.time().round(),
             epoch: ctx.epoch(),
-            timestamp: ctx.runtime_header().timestamp,
+            timestamp: ctx.time().timestamp(),
         },
 
         // Accounts API queries.
         QueryRequest::Accounts(query) => dispatch_accounts_query::<Cfg, C>(ctx, query),
 
+        // Oracle API queries.
+        QueryRequest::Oracle(query) => dispatch_oracle_query(ctx, query),
+
+        // ROFL API queries.
+        QueryRequest::Rofl(query) => dispatch_rofl_query(rofl_origin, query),
+
         _ => QueryResponse::Error {
             module: "".to_string(),
             code: 1,
@@ -145,3 +163,43 @@ fn dispatch_accounts_query<Cfg: Config, C: Context>(
         },
     }
 }
+
+/// Perform oracle API query dispatch.
+fn dispatch_oracle_query<C: Context>(ctx: &mut C, query: OracleQuery) -> QueryResponse {
+    match query {
+        OracleQuery::Value { feed } => match oracle::Module::get_value(ctx, &feed) {
+            Ok(observation) => OracleResponse::Value {
+                value: observation.value,
+                round: observation.aggregated_at,
+            }
+            .into(),
+            Err(err) => QueryResponse::Error {
+                module: err.module_name().to_string(),
+                code: err.code(),
+                message: err.to_string(),
+            },
+        },
+
+        _ => QueryResponse::Error {
+            module: "".to_string(),
+            code: 1,
+            message: "query not supported".to_string(),
+        },
+    }
+}
+
+/// Perform ROFL API query dispatch.
+fn dispatch_rofl_query(rofl_origin: Option<AppId>, query: RoflQuery) -> QueryResponse {
+    match query {
+        RoflQuery::IsAuthorizedOrigin { app } => RoflResponse::IsAuthorizedOrigin {
+            authorized: rofl_origin.is_some() && rofl_origin == AppId::from_bytes(&app).ok(),
+        }
+        .into(),
+
+        _ => QueryResponse::Error {
+            module: "".to_string(),
+            code: 1,
+            message: "query not supported".to_string(),
+        },
+    }
+}
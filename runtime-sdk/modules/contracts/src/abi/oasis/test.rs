@@ -323,6 +323,7 @@ fn run_contract_with_defaults(
             max_batch_gas: gas_limit,
             ..Default::default()
         },
+        ..Default::default()
     });
 
     let mut tx = mock::transaction();
@@ -374,6 +375,7 @@ fn run_contract_with_defaults(
             Default::default(),
             ctx.is_read_only(),
             ctx.tx_call_format(),
+            None,
             &mut ctx,
         );
         wasm::instantiate::<ContractsConfig, _>(&mut exec_ctx, &contract, &call).inner?;
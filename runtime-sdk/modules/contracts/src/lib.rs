@@ -6,7 +6,7 @@
 #[cfg(test)]
 extern crate alloc;
 
-use std::{convert::TryInto, io::Read};
+use std::{collections::BTreeMap, convert::TryInto, io::Read};
 
 use thiserror::Error;
 
@@ -15,10 +15,14 @@ use oasis_runtime_sdk::{
     self as sdk,
     context::{Context, TxContext},
     core::common::crypto::hash::Hash,
-    handler, migration, module,
+    export, handler, migration, module,
     module::Module as _,
     modules,
-    modules::{accounts::API as _, core::API as _},
+    modules::{
+        accounts::API as _,
+        core::API as _,
+        rofl::{self, API as _},
+    },
     runtime::Runtime,
     sdk_derive, storage,
     storage::{CurrentStore, Store},
@@ -311,6 +315,10 @@ impl module::Parameters for Parameters {
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Genesis {
     pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
 }
 
 /// Local configuration that can be provided by the node operator.
@@ -349,6 +357,18 @@ pub mod state {
 
     /// Uploaded code.
     pub const CODE: &[u8] = &[0xFF];
+
+    /// Prefixes of module state exported as raw state.
+    ///
+    /// Instance state may be confidential so it can only be exported as raw state.
+    pub const RAW: &[&[u8]] = &[
+        NEXT_CODE_IDENTIFIER,
+        NEXT_INSTANCE_IDENTIFIER,
+        CODE_INFO,
+        INSTANCE_INFO,
+        INSTANCE_STATE,
+        CODE,
+    ];
 }
 
 /// Module configuration.
@@ -422,13 +442,43 @@ impl<Cfg: Config> Module<Cfg> {
 #[sdk_derive(Module)]
 impl<Cfg: Config> Module<Cfg> {
     const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
     type Error = Error;
     type Event = Event;
     type Parameters = Parameters;
     type Genesis = Genesis;
 
+    fn features() -> BTreeMap<String, cbor::Value> {
+        let params = Self::params();
+        BTreeMap::from([
+            (
+                "max_code_size".to_string(),
+                cbor::to_value(params.max_code_size),
+            ),
+            (
+                "max_memory_pages".to_string(),
+                cbor::to_value(params.max_memory_pages),
+            ),
+            (
+                "max_subcall_depth".to_string(),
+                cbor::to_value(params.max_subcall_depth),
+            ),
+            (
+                "max_subcall_count".to_string(),
+                cbor::to_value(params.max_subcall_count),
+            ),
+            (
+                "max_result_size_bytes".to_string(),
+                cbor::to_value(params.max_result_size_bytes),
+            ),
+        ])
+    }
+
     #[migration(init)]
     fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
         // Set genesis parameters.
         Self::set_params(genesis.parameters);
     }
@@ -588,6 +638,7 @@ impl<Cfg: Config> Module<Cfg> {
             ctx.tx_caller_address(),
             ctx.is_read_only(),
             ctx.tx_call_format(),
+            rofl::Module::authorized_origin(ctx),
             ctx,
         );
         let result = wasm::instantiate::<Cfg, C>(&mut exec_ctx, &contract, &body);
@@ -636,6 +687,7 @@ impl<Cfg: Config> Module<Cfg> {
             ctx.tx_caller_address(),
             ctx.is_read_only(),
             ctx.tx_call_format(),
+            rofl::Module::authorized_origin(ctx),
             ctx,
         );
         let result = wasm::call::<Cfg, C>(&mut exec_ctx, &contract, &body);
@@ -709,6 +761,7 @@ impl<Cfg: Config> Module<Cfg> {
             ctx.tx_caller_address(),
             ctx.is_read_only(),
             ctx.tx_call_format(),
+            rofl::Module::authorized_origin(ctx),
             ctx,
         );
         // Pre-upgrade invocation must succeed for the upgrade to proceed.
@@ -735,6 +788,7 @@ impl<Cfg: Config> Module<Cfg> {
             ctx.tx_caller_address(),
             ctx.is_read_only(),
             ctx.tx_call_format(),
+            rofl::Module::authorized_origin(ctx),
             ctx,
         );
 
@@ -860,6 +914,7 @@ impl<Cfg: Config> Module<Cfg> {
             Default::default(), // No caller for queries.
             true,
             CallFormat::Plain,
+            None,
             ctx,
         );
         let result = wasm::query::<Cfg, C>(&mut exec_ctx, &contract, &args).inner?; // No need to handle gas.
@@ -871,3 +926,12 @@ impl<Cfg: Config> Module<Cfg> {
 impl<Cfg: Config> module::TransactionHandler for Module<Cfg> {}
 impl<Cfg: Config> module::BlockHandler for Module<Cfg> {}
 impl<Cfg: Config> module::InvariantHandler for Module<Cfg> {}
+
+impl<Cfg: Config> module::StateExporter for Module<Cfg> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
@@ -9,7 +9,10 @@ use oasis_contract_sdk_types::{
 use oasis_runtime_sdk::{
     context::TxContext,
     event::etag_for_event,
-    modules::core::API as _,
+    modules::{
+        core::API as _,
+        rofl::{self, API as _},
+    },
     runtime::Runtime,
     subcall::{self, SubcallInfo},
     types::transaction::CallerAddress,
@@ -140,6 +143,7 @@ fn process_subcalls<Cfg: Config, C: TxContext>(
                         body,
                         max_depth: params.max_subcall_depth,
                         max_gas,
+                        read_only: false,
                     },
                     subcall::AllowAllValidator,
                 )?;
@@ -177,6 +181,7 @@ fn process_subcalls<Cfg: Config, C: TxContext>(
                             ctx.tx_caller_address(),
                             orig_read_only,
                             orig_call_format,
+                            rofl::Module::authorized_origin(ctx),
                             ctx,
                         );
                         let reply_result =
@@ -50,7 +50,7 @@ where
     } else {
         None
     };
-    let round = ctx.runtime_header().round;
+    let round = ctx.time().round();
     let confidential_key: Option<StateKey> = if let StoreKind::Confidential = store_kind {
         let kmgr_client = ctx.key_manager().ok_or(Error::Unsupported)?;
         let kid = keymanager::get_key_pair_id([
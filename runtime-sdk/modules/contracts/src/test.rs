@@ -137,6 +137,7 @@ fn test_hello_contract_call() {
             max_batch_gas: 1_000_000_000,
             ..Default::default()
         },
+        ..Default::default()
     });
 
     Accounts::init(accounts::Genesis {
@@ -160,6 +161,7 @@ fn test_hello_contract_call() {
 
     Contracts::init(Genesis {
         parameters: Default::default(),
+        ..Default::default()
     });
 
     let instance_id =
@@ -693,6 +695,7 @@ impl Runtime for ContractRuntime {
                     min_gas_price: BTreeMap::from([(Denomination::NATIVE, 1)]),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             accounts::Genesis {
                 balances: {
@@ -714,6 +717,7 @@ impl Runtime for ContractRuntime {
             },
             Genesis {
                 parameters: Default::default(),
+                ..Default::default()
             },
         )
     }
@@ -1261,3 +1265,21 @@ fn test_hello_contract_change_upgrade_policy_fail() {
         assert_eq!(&result.to_string(), "forbidden by policy");
     });
 }
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<ContractRuntime>(context::Mode::ExecuteTx, true);
+
+    ContractRuntime::migrate(&mut ctx);
+
+    deploy_hello_contract(&mut ctx, vec![]);
+
+    let genesis = mock::assert_export_round_trip::<Contracts>();
+    for prefix in crate::state::RAW {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "code, instances and instance state should be exported"
+        );
+    }
+}
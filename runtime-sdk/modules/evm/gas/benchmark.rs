@@ -0,0 +1,155 @@
+//! Gas cost regression benchmarks for the EVM module.
+//!
+//! Usage: `gas-benchmark-evm [OUTPUT]`. The report is written to stdout when no output is given.
+use std::{collections::BTreeMap, env, fs};
+
+use ethabi::Token;
+
+use oasis_runtime_sdk::{
+    module,
+    modules::{self, accounts},
+    testing::{gas::Bench, keys, scenario::Tx},
+    types::{token::Denomination, transaction},
+    Runtime, Version,
+};
+use oasis_runtime_sdk_evm::{
+    mock::load_contract_bytecode,
+    types::{self, H160},
+    Config, Genesis, Module as EVMModule,
+};
+
+/// Test contract code.
+static ERC20_CONTRACT_CODE_HEX: &str =
+    include_str!("../../../../tests/e2e/contracts/evm_erc20_test_compiled.hex");
+
+/// Number of transactions executed for each workload.
+const ITERATIONS: u64 = 100;
+
+struct CoreConfig;
+
+impl modules::core::Config for CoreConfig {
+    const EMIT_GAS_USED_EVENTS: bool = true;
+}
+
+struct EVMConfig;
+
+impl Config for EVMConfig {
+    type Accounts = accounts::Module;
+
+    type AdditionalPrecompileSet = ();
+
+    const CHAIN_ID: u64 = 0xa515;
+
+    const TOKEN_DENOMINATION: Denomination = Denomination::NATIVE;
+}
+
+struct BenchRuntime;
+
+impl Runtime for BenchRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+
+    type Core = modules::core::Module<CoreConfig>;
+
+    type Modules = (
+        modules::core::Module<CoreConfig>,
+        accounts::Module,
+        EVMModule<EVMConfig>,
+    );
+
+    fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+        (
+            modules::core::Genesis {
+                parameters: modules::core::Parameters {
+                    max_batch_gas: 30_000_000,
+                    max_tx_size: 128 * 1024,
+                    max_tx_signers: 8,
+                    max_multisig_signers: 8,
+                    gas_costs: modules::core::GasCosts {
+                        tx_byte: 1,
+                        auth_signature: 1_000,
+                        auth_multisig_signer: 1_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                balances: BTreeMap::from([(
+                    keys::dave::address(),
+                    BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                )]),
+                total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000_000_000)]),
+                ..Default::default()
+            },
+            Genesis {
+                parameters: Default::default(),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+fn create_erc20() -> Tx {
+    Tx::new(
+        keys::dave::sigspec(),
+        "evm.Create",
+        types::Create {
+            value: 0.into(),
+            init_code: load_contract_bytecode(ERC20_CONTRACT_CODE_HEX),
+        },
+    )
+    .with_fee(transaction::Fee {
+        amount: Default::default(),
+        gas: 3_000_000,
+        consensus_messages: 0,
+    })
+}
+
+fn main() {
+    let mut bench = Bench::<BenchRuntime>::new(ITERATIONS);
+
+    bench.measure("evm.Create/erc20", |_| create_erc20());
+
+    // Deploy a contract that is shared by all call workloads.
+    let mut result = bench
+        .scenario()
+        .block()
+        .tx(create_erc20())
+        .run()
+        .expect("block should execute");
+    let erc20 = match result.txs.remove(0).result {
+        module::CallResult::Ok(address) => H160::from_slice(
+            &cbor::from_value::<Vec<u8>>(address).expect("contract address should decode"),
+        ),
+        result => panic!("contract creation failed: {result:?}"),
+    };
+
+    bench.measure("evm.Call/erc20_transfer", |iteration| {
+        let mut data = ethabi::short_signature(
+            "transfer",
+            &[ethabi::ParamType::Address, ethabi::ParamType::Uint(256)],
+        )
+        .to_vec();
+        data.extend(ethabi::encode(&[
+            Token::Address(ethabi::Address::from_low_u64_be(iteration + 1)),
+            Token::Uint(1.into()),
+        ]));
+
+        Tx::new(
+            keys::dave::sigspec(),
+            "evm.Call",
+            types::Call {
+                address: erc20,
+                value: 0.into(),
+                data,
+            },
+        )
+    });
+
+    let report = bench.finish().to_string();
+    match env::args().nth(1) {
+        Some(output) => fs::write(output, report).expect("failed to write report"),
+        None => print!("{report}"),
+    }
+}
@@ -18,10 +18,11 @@ use oasis_runtime_sdk::{
     modules::{
         accounts::API as _,
         core::{self, API as _},
+        rofl::{self, API as _},
     },
     storage::CurrentStore,
     subcall,
-    types::token,
+    types::{app_id::AppId, token},
     Runtime,
 };
 
@@ -77,6 +78,9 @@ pub(crate) trait EVMBackendExt {
         info: subcall::SubcallInfo,
         validator: V,
     ) -> Result<subcall::SubcallResult, core::Error>;
+
+    /// ROFL application whose instance originated the current transaction, if any.
+    fn rofl_origin(&self) -> Option<AppId>;
 }
 
 impl<T: EVMBackendExt> EVMBackendExt for &T {
@@ -84,6 +88,10 @@ impl<T: EVMBackendExt> EVMBackendExt for &T {
         (*self).random_bytes(num_bytes, pers)
     }
 
+    fn rofl_origin(&self) -> Option<AppId> {
+        (*self).rofl_origin()
+    }
+
     fn subcall<V: subcall::Validator + 'static>(
         &self,
         info: subcall::SubcallInfo,
@@ -122,6 +130,11 @@ impl<'ctx, C: TxContext, Cfg: Config> EVMBackendExt for OasisBackend<'ctx, C, Cf
 
         Ok(result)
     }
+
+    fn rofl_origin(&self) -> Option<AppId> {
+        let ctx = self.ctx.borrow();
+        rofl::Module::authorized_origin(&**ctx)
+    }
 }
 
 /// Oasis-specific substate implementation for the EVM stack executor.
@@ -327,7 +340,7 @@ impl<'ctx, 'backend, 'config, C: TxContext, Cfg: Config> Backend
     }
 
     fn block_number(&self) -> U256 {
-        self.backend.ctx.borrow().runtime_header().round.into()
+        self.backend.ctx.borrow().time().round().into()
     }
 
     fn block_coinbase(&self) -> H160 {
@@ -336,7 +349,7 @@ impl<'ctx, 'backend, 'config, C: TxContext, Cfg: Config> Backend
     }
 
     fn block_timestamp(&self) -> U256 {
-        self.backend.ctx.borrow().runtime_header().timestamp.into()
+        self.backend.ctx.borrow().time().timestamp().into()
     }
 
     fn block_difficulty(&self) -> U256 {
@@ -559,7 +572,8 @@ impl<'ctx, 'backend, 'config, C: TxContext, Cfg: Config> StackState<'config>
         let amount = transfer.value.as_u128();
         let amount = token::BaseUnits::new(amount, Cfg::TOKEN_DENOMINATION);
 
-        Cfg::Accounts::transfer_silent(from, to, &amount).map_err(|_| ExitError::OutOfFund)
+        let mut ctx = self.backend.ctx.borrow_mut();
+        Cfg::Accounts::transfer_silent(*ctx, from, to, &amount).map_err(|_| ExitError::OutOfFund)
     }
 
     fn reset_balance(&mut self, _address: H160) {
@@ -15,6 +15,7 @@ pub fn from_sigspec(spec: &SignatureAddressSpec) -> Result<H160, Error> {
 pub fn from_tx_auth_info(ai: &AuthInfo) -> Result<H160, Error> {
     match &ai.signer_info[0].address_spec {
         AddressSpec::Signature(spec) => from_sigspec(spec),
+        AddressSpec::Rofl(spec) => Ok(H160::from_slice(&spec.eth_address())),
         AddressSpec::Internal(CallerAddress::EthAddress(address)) => Ok(address.into()),
         _ => Err(Error::InvalidSignerType),
     }
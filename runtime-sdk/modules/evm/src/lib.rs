@@ -12,6 +12,8 @@ mod signed_call;
 pub mod state;
 pub mod types;
 
+use std::collections::BTreeMap;
+
 use evm::{
     executor::stack::{StackExecutor, StackSubstateMetadata},
     Config as EVMConfig,
@@ -22,7 +24,7 @@ use thiserror::Error;
 use oasis_runtime_sdk::{
     callformat,
     context::{BatchContext, Context, TransactionWithMeta, TxContext},
-    handler, migration,
+    export, handler, migration,
     module::{self, Module as _},
     modules::{
         self,
@@ -30,8 +32,8 @@ use oasis_runtime_sdk::{
         core::{Error as CoreError, API as _},
     },
     runtime::Runtime,
-    sdk_derive,
-    storage::CurrentStore,
+    sdk_derive, simulation_cache,
+    storage::{ConfidentialStorePolicy, CurrentStore},
     types::{
         address::{self, Address},
         token, transaction,
@@ -68,6 +70,10 @@ pub trait Config: 'static {
     /// Whether to use confidential storage by default, and transaction data encryption.
     const CONFIDENTIAL: bool = false;
 
+    /// Side channel hardening policy for confidential contract storage. Only used in case
+    /// `CONFIDENTIAL` is set.
+    const CONFIDENTIAL_STORAGE_POLICY: ConfidentialStorePolicy = ConfidentialStorePolicy::none();
+
     /// Whether to refund unused transaction fee.
     const REFUND_UNUSED_FEE: bool = true;
 
@@ -237,6 +243,10 @@ impl module::Parameters for Parameters {
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Genesis {
     pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
 }
 
 /// Local configuration that can be provided by the node operator.
@@ -653,17 +663,50 @@ impl<Cfg: Config> Module<Cfg> {
     }
 }
 
+/// Name of the hard fork that the given EVM configuration corresponds to.
+fn hard_fork(config: &EVMConfig) -> &'static str {
+    if config.has_push0 {
+        "shanghai"
+    } else if config.has_base_fee {
+        "london"
+    } else if config.increase_state_access_gas {
+        "berlin"
+    } else if config.has_chain_id {
+        "istanbul"
+    } else {
+        "frontier"
+    }
+}
+
 #[sdk_derive(Module)]
 impl<Cfg: Config> Module<Cfg> {
     const NAME: &'static str = MODULE_NAME;
     const VERSION: u32 = 2;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
     type Error = Error;
     type Event = Event;
     type Parameters = Parameters;
     type Genesis = Genesis;
 
+    fn features() -> BTreeMap<String, cbor::Value> {
+        BTreeMap::from([
+            ("chain_id".to_string(), cbor::to_value(Cfg::CHAIN_ID)),
+            (
+                "confidential".to_string(),
+                cbor::to_value(Cfg::CONFIDENTIAL),
+            ),
+            (
+                "hard_fork".to_string(),
+                cbor::to_value(hard_fork(Cfg::evm_config(false))),
+            ),
+        ])
+    }
+
     #[migration(init)]
     fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
         // Set genesis parameters.
         Self::set_params(genesis.parameters);
     }
@@ -709,7 +752,53 @@ impl<Cfg: Config> Module<Cfg> {
                 cfg.query_simulate_call_max_gas,
             ));
         }
-        Self::simulate_call(ctx, body)
+
+        // Reuse the result of simulating the same call against the same state (if any). The key
+        // is derived from the equivalent `evm.Call` so that results of simulating the call via
+        // `core.EstimateGas` are reused as well. In confidential runtimes the query may be a signed
+        // query which is processed differently from an `evm.Call` so the whole query is used.
+        let call = if Cfg::CONFIDENTIAL {
+            transaction::Call {
+                format: transaction::CallFormat::Plain,
+                method: "evm.SimulateCall".to_owned(),
+                body: cbor::to_value(body.clone()),
+                ..Default::default()
+            }
+        } else {
+            transaction::Call {
+                format: transaction::CallFormat::Plain,
+                method: "evm.Call".to_owned(),
+                body: cbor::to_value(types::Call {
+                    address: body.address,
+                    value: body.value,
+                    data: body.data.clone(),
+                }),
+                ..Default::default()
+            }
+        };
+        let key = simulation_cache::Key::for_call(
+            ctx,
+            Cfg::map_address(body.caller.into()),
+            &call,
+            body.gas_limit,
+        );
+        let simulation = simulation_cache::get_or_simulate(
+            ctx,
+            key,
+            // Only successful results can be returned as failures are propagated as errors.
+            |cached| matches!(cached.result, Ok(cbor::Value::ByteString(_))),
+            |ctx| {
+                Ok::<_, Error>(simulation_cache::SimulationResult {
+                    result: Ok(cbor::Value::ByteString(Self::simulate_call(ctx, body)?)),
+                    gas_used: 0,
+                    tx_size: None,
+                })
+            },
+        )?;
+        match simulation.result {
+            Ok(cbor::Value::ByteString(result)) => Ok(result),
+            _ => unreachable!("only successful simulations are cached"),
+        }
     }
 }
 
@@ -733,7 +822,7 @@ impl<Cfg: Config> module::BlockHandler for Module<Cfg> {
     fn end_block<C: Context>(ctx: &mut C) {
         CurrentStore::with(|store| {
             // Update the list of historic block hashes.
-            let block_number = ctx.runtime_header().round;
+            let block_number = ctx.time().round();
             let block_hash = ctx.runtime_header().encoded_hash();
             let mut block_hashes = state::block_hashes(store);
 
@@ -749,3 +838,12 @@ impl<Cfg: Config> module::BlockHandler for Module<Cfg> {
 }
 
 impl<Cfg: Config> module::InvariantHandler for Module<Cfg> {}
+
+impl<Cfg: Config> module::StateExporter for Module<Cfg> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use ethabi::{ParamType, Token};
+use evm::{
+    executor::stack::{PrecompileFailure, PrecompileHandle, PrecompileOutput},
+    ExitError, ExitSucceed,
+};
+use primitive_types::{H160, H256, U256};
+use sha3::Digest as _;
+
+use crate::{backend::EVMBackendExt, Config};
+use oasis_runtime_sdk::{
+    module::CallResult,
+    modules::accounts::{self, API as _},
+    subcall,
+    types::{address::Address, token, transaction::CallerAddress},
+};
+
+use super::{record_linear_cost, PrecompileResult};
+
+const ALLOWANCE_BASE_COST: u64 = 2_100;
+const CALL_BASE_COST: u64 = 100;
+const WORD_COST: u64 = 10;
+/// Cost of emitting a log with three topics and a single word of data.
+const LOG_COST: u64 = 375 + 3 * 375 + 8 * 32;
+
+/// Bridge between the allowances of the accounts module and EVM contracts.
+///
+/// The precompile implements the following interface where accounts are given either as 20-byte
+/// Ethereum addresses or as 21-byte runtime addresses and amounts are in the native EVM token
+/// denomination:
+///
+/// ```solidity
+/// function allowance(bytes owner, bytes spender) external view returns (uint256);
+/// function approve(bytes spender, uint256 amount) external returns (bool);
+/// function transferFrom(bytes from, bytes to, uint256 amount) external returns (bool);
+/// ```
+///
+/// The caller is the owner for `approve` and the spender for `transferFrom`. Successful calls emit
+/// ERC-20-compatible `Approval` and `Transfer` logs from the precompile address. Their address
+/// topics contain the Ethereum address in case the account was given as one and the runtime
+/// address otherwise.
+pub(super) fn call_allowance_bridge<Cfg: Config, B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+) -> PrecompileResult {
+    let input = handle.input();
+    if input.len() < 4 {
+        return Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other("missing function selector".into()),
+        });
+    }
+    let selector: [u8; 4] = input[..4].try_into().unwrap();
+    let args = input[4..].to_vec();
+
+    if selector == ethabi::short_signature("allowance", &[ParamType::Bytes, ParamType::Bytes]) {
+        call_allowance::<Cfg>(handle, &args)
+    } else if selector
+        == ethabi::short_signature("approve", &[ParamType::Bytes, ParamType::Uint(256)])
+    {
+        call_approve::<Cfg, B>(handle, backend, &args)
+    } else if selector
+        == ethabi::short_signature(
+            "transferFrom",
+            &[ParamType::Bytes, ParamType::Bytes, ParamType::Uint(256)],
+        )
+    {
+        call_transfer_from::<Cfg, B>(handle, backend, &args)
+    } else {
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other("unknown function selector".into()),
+        })
+    }
+}
+
+/// An account given to the precompile.
+struct Account {
+    address: Address,
+    topic: H256,
+}
+
+impl Account {
+    fn parse<Cfg: Config>(raw: Vec<u8>) -> Result<Self, PrecompileFailure> {
+        let address = match raw.len() {
+            20 => Cfg::map_address(H160::from_slice(&raw)),
+            Address::SIZE => Address::from_bytes(&raw).map_err(|_| PrecompileFailure::Error {
+                exit_status: ExitError::Other("address is malformed".into()),
+            })?,
+            _ => {
+                return Err(PrecompileFailure::Error {
+                    exit_status: ExitError::Other("address is malformed".into()),
+                })
+            }
+        };
+        let mut topic = H256::zero();
+        topic.as_bytes_mut()[32 - raw.len()..].copy_from_slice(&raw);
+        Ok(Self { address, topic })
+    }
+
+    fn from_eth<Cfg: Config>(address: H160) -> Self {
+        Self {
+            address: Cfg::map_address(address),
+            topic: address.into(),
+        }
+    }
+}
+
+fn decode(types: &[ParamType], args: &[u8]) -> Result<Vec<Token>, PrecompileFailure> {
+    ethabi::decode(types, args).map_err(|e| PrecompileFailure::Error {
+        exit_status: ExitError::Other(e.to_string().into()),
+    })
+}
+
+fn decode_amount(token: Token) -> Result<u128, PrecompileFailure> {
+    let amount = token.into_uint().unwrap();
+    if amount > U256::from(u128::MAX) {
+        return Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other("amount is too large".into()),
+        });
+    }
+    Ok(amount.as_u128())
+}
+
+/// Ensure that the precompile is called using a regular, state-changing call (and not a
+/// delegatecall) so the caller is actually the address of the calling contract.
+fn ensure_state_changing_call(handle: &impl PrecompileHandle) -> Result<(), PrecompileFailure> {
+    if handle.context().address != handle.code_address() || handle.is_static() {
+        return Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other("invalid call".into()),
+        });
+    }
+    Ok(())
+}
+
+fn call_allowance<Cfg: Config>(
+    handle: &mut impl PrecompileHandle,
+    args: &[u8],
+) -> PrecompileResult {
+    record_linear_cost(handle, args.len() as u64, ALLOWANCE_BASE_COST, WORD_COST)?;
+
+    let mut call_args = decode(&[ParamType::Bytes, ParamType::Bytes], args)?;
+    let spender = Account::parse::<Cfg>(call_args.pop().unwrap().into_bytes().unwrap())?;
+    let owner = Account::parse::<Cfg>(call_args.pop().unwrap().into_bytes().unwrap())?;
+
+    let allowance =
+        Cfg::Accounts::get_allowance(owner.address, spender.address, Cfg::TOKEN_DENOMINATION)
+            .map_err(|_| PrecompileFailure::Error {
+                exit_status: ExitError::Other("allowance query failed".into()),
+            })?;
+
+    Ok(PrecompileOutput {
+        exit_status: ExitSucceed::Returned,
+        output: ethabi::encode(&[Token::Uint(allowance.into())]),
+    })
+}
+
+fn call_approve<Cfg: Config, B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+    args: &[u8],
+) -> PrecompileResult {
+    record_linear_cost(handle, args.len() as u64, CALL_BASE_COST, WORD_COST)?;
+    ensure_state_changing_call(handle)?;
+
+    let mut call_args = decode(&[ParamType::Bytes, ParamType::Uint(256)], args)?;
+    let amount = decode_amount(call_args.pop().unwrap())?;
+    let spender = Account::parse::<Cfg>(call_args.pop().unwrap().into_bytes().unwrap())?;
+    let owner = Account::from_eth::<Cfg>(handle.context().caller);
+
+    dispatch_subcall(
+        handle,
+        backend,
+        "accounts.Approve",
+        cbor::to_value(accounts::types::Approve {
+            spender: spender.address,
+            amount: token::BaseUnits::new(amount, Cfg::TOKEN_DENOMINATION),
+        }),
+    )?;
+
+    emit_log(
+        handle,
+        "Approval(address,address,uint256)",
+        &owner,
+        &spender,
+        amount,
+    )
+}
+
+fn call_transfer_from<Cfg: Config, B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+    args: &[u8],
+) -> PrecompileResult {
+    record_linear_cost(handle, args.len() as u64, CALL_BASE_COST, WORD_COST)?;
+    ensure_state_changing_call(handle)?;
+
+    let mut call_args = decode(
+        &[ParamType::Bytes, ParamType::Bytes, ParamType::Uint(256)],
+        args,
+    )?;
+    let amount = decode_amount(call_args.pop().unwrap())?;
+    let to = Account::parse::<Cfg>(call_args.pop().unwrap().into_bytes().unwrap())?;
+    let from = Account::parse::<Cfg>(call_args.pop().unwrap().into_bytes().unwrap())?;
+
+    dispatch_subcall(
+        handle,
+        backend,
+        "accounts.TransferFrom",
+        cbor::to_value(accounts::types::TransferFrom {
+            from: from.address,
+            to: to.address,
+            amount: token::BaseUnits::new(amount, Cfg::TOKEN_DENOMINATION),
+        }),
+    )?;
+
+    emit_log(
+        handle,
+        "Transfer(address,address,uint256)",
+        &from,
+        &to,
+        amount,
+    )
+}
+
+/// Dispatch a call to the accounts module on behalf of the calling contract.
+fn dispatch_subcall<B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+    method: &str,
+    body: cbor::Value,
+) -> Result<(), PrecompileFailure> {
+    let result = backend
+        .subcall(
+            subcall::SubcallInfo {
+                caller: CallerAddress::EthAddress(handle.context().caller.into()),
+                method: method.to_string(),
+                body,
+                max_depth: 8,
+                max_gas: handle.remaining_gas(),
+                read_only: false,
+            },
+            subcall::AllowAllValidator,
+        )
+        .map_err(|_| PrecompileFailure::Error {
+            exit_status: ExitError::Other("subcall failed".into()),
+        })?;
+
+    // Charge gas (this shouldn't fail given that we set the limit appropriately).
+    handle.record_cost(result.gas_used)?;
+
+    match result.call_result {
+        CallResult::Ok(_) => Ok(()),
+        CallResult::Failed { message, .. } => Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other(message.into()),
+        }),
+        CallResult::Aborted(_) => Err(PrecompileFailure::Error {
+            exit_status: ExitError::Other("subcall failed".into()),
+        }),
+    }
+}
+
+/// Emit an ERC-20-compatible log and return `true` to the caller.
+fn emit_log(
+    handle: &mut impl PrecompileHandle,
+    signature: &str,
+    from: &Account,
+    to: &Account,
+    amount: u128,
+) -> PrecompileResult {
+    handle.record_cost(LOG_COST)?;
+    let address = handle.code_address();
+    handle.log(
+        address,
+        vec![
+            H256::from_slice(&sha3::Keccak256::digest(signature.as_bytes())),
+            from.topic,
+            to.topic,
+        ],
+        ethabi::encode(&[Token::Uint(amount.into())]),
+    )?;
+
+    Ok(PrecompileOutput {
+        exit_status: ExitSucceed::Returned,
+        output: ethabi::encode(&[Token::Bool(true)]),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ethabi::{ParamType, Token};
+    use sha3::Digest as _;
+
+    use oasis_runtime_sdk::{
+        modules::accounts::{self, API as _},
+        subcall,
+        testing::{keys, mock::Mock},
+        types::{
+            token::{BaseUnits, Denomination},
+            transaction::CallerAddress,
+        },
+    };
+
+    use super::super::testing::*;
+
+    const ADDRESS: H160 = H160([
+        0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x06,
+    ]);
+
+    fn topic(raw: &[u8]) -> H256 {
+        let mut topic = H256::zero();
+        topic.as_bytes_mut()[32 - raw.len()..].copy_from_slice(raw);
+        topic
+    }
+
+    fn event_topic(signature: &str) -> H256 {
+        H256::from_slice(&sha3::Keccak256::digest(signature.as_bytes()))
+    }
+
+    fn input(name: &str, types: &[ParamType], tokens: &[Token]) -> Vec<u8> {
+        [
+            ethabi::short_signature(name, types).to_vec(),
+            ethabi::encode(tokens),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_allowance() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx();
+        accounts::Module::approve(
+            &mut ctx,
+            keys::alice::address(),
+            keys::dave::address(),
+            &BaseUnits::new(1_000, Denomination::NATIVE),
+        )
+        .unwrap();
+
+        let output = call_contract(
+            ADDRESS,
+            &input(
+                "allowance",
+                &[ParamType::Bytes, ParamType::Bytes],
+                &[
+                    Token::Bytes(keys::alice::address().as_ref().to_vec()),
+                    Token::Bytes(keys::dave::pk_secp256k1().to_eth_address()),
+                ],
+            ),
+            10_000,
+        )
+        .unwrap()
+        .expect("allowance query should succeed")
+        .output;
+        assert_eq!(output, ethabi::encode(&[Token::Uint(1_000.into())]));
+    }
+
+    #[test]
+    fn test_approve() {
+        let backend = MockBackend::default();
+        let spender = keys::dave::pk_secp256k1().to_eth_address();
+
+        let (result, logs) = call_contract_with_logs(
+            &backend,
+            ADDRESS,
+            &input(
+                "approve",
+                &[ParamType::Bytes, ParamType::Uint(256)],
+                &[Token::Bytes(spender.clone()), Token::Uint(500.into())],
+            ),
+            100_000,
+        )
+        .unwrap();
+        let output = result.expect("approve should succeed").output;
+        assert_eq!(output, ethabi::encode(&[Token::Bool(true)]));
+
+        let subcalls = backend.subcalls();
+        assert_eq!(subcalls.len(), 1);
+        assert_eq!(subcalls[0].method, "accounts.Approve");
+        assert!(matches!(subcalls[0].caller, CallerAddress::EthAddress(_)));
+        let body: accounts::types::Approve = cbor::from_value(subcalls[0].body.clone()).unwrap();
+        assert_eq!(body.spender, keys::dave::address());
+        assert_eq!(body.amount, BaseUnits::new(500, Denomination::NATIVE));
+
+        assert_eq!(logs.len(), 1, "approval should be logged");
+        assert_eq!(logs[0].address, ADDRESS);
+        assert_eq!(
+            logs[0].topics,
+            vec![
+                event_topic("Approval(address,address,uint256)"),
+                H256::zero(),
+                topic(&spender),
+            ]
+        );
+        assert_eq!(logs[0].data, ethabi::encode(&[Token::Uint(500.into())]));
+    }
+
+    #[test]
+    fn test_transfer_from() {
+        let backend = MockBackend::default();
+        let from = keys::alice::address().as_ref().to_vec();
+        let to = keys::dave::pk_secp256k1().to_eth_address();
+
+        let (result, logs) = call_contract_with_logs(
+            &backend,
+            ADDRESS,
+            &input(
+                "transferFrom",
+                &[ParamType::Bytes, ParamType::Bytes, ParamType::Uint(256)],
+                &[
+                    Token::Bytes(from.clone()),
+                    Token::Bytes(to.clone()),
+                    Token::Uint(300.into()),
+                ],
+            ),
+            100_000,
+        )
+        .unwrap();
+        let output = result.expect("transfer should succeed").output;
+        assert_eq!(output, ethabi::encode(&[Token::Bool(true)]));
+
+        let subcalls = backend.subcalls();
+        assert_eq!(subcalls.len(), 1);
+        assert_eq!(subcalls[0].method, "accounts.TransferFrom");
+        let body: accounts::types::TransferFrom =
+            cbor::from_value(subcalls[0].body.clone()).unwrap();
+        assert_eq!(body.from, keys::alice::address());
+        assert_eq!(body.to, keys::dave::address());
+        assert_eq!(body.amount, BaseUnits::new(300, Denomination::NATIVE));
+
+        // Runtime addresses should be used as topics when accounts are given as such.
+        assert_eq!(logs.len(), 1, "transfer should be logged");
+        assert_eq!(logs[0].address, ADDRESS);
+        assert_eq!(
+            logs[0].topics,
+            vec![
+                event_topic("Transfer(address,address,uint256)"),
+                topic(&from),
+                topic(&to),
+            ]
+        );
+        assert_eq!(logs[0].data, ethabi::encode(&[Token::Uint(300.into())]));
+    }
+
+    #[test]
+    fn test_transfer_from_failed() {
+        let backend = MockBackend::default().with_subcall_failure(subcall::SubcallFailure {
+            module: "accounts".to_owned(),
+            code: 2,
+            message: "insufficient balance".to_owned(),
+        });
+
+        let (result, logs) = call_contract_with_logs(
+            &backend,
+            ADDRESS,
+            &input(
+                "transferFrom",
+                &[ParamType::Bytes, ParamType::Bytes, ParamType::Uint(256)],
+                &[
+                    Token::Bytes(keys::alice::address().as_ref().to_vec()),
+                    Token::Bytes(keys::dave::address().as_ref().to_vec()),
+                    Token::Uint(300.into()),
+                ],
+            ),
+            100_000,
+        )
+        .unwrap();
+        assert!(result.is_err(), "failed transfers should be rejected");
+        assert!(logs.is_empty(), "failed transfers should not be logged");
+
+        // Amounts not representable in the runtime should be rejected without a subcall.
+        let backend = MockBackend::default();
+        let (result, _) = call_contract_with_logs(
+            &backend,
+            ADDRESS,
+            &input(
+                "transferFrom",
+                &[ParamType::Bytes, ParamType::Bytes, ParamType::Uint(256)],
+                &[
+                    Token::Bytes(keys::alice::address().as_ref().to_vec()),
+                    Token::Bytes(keys::dave::address().as_ref().to_vec()),
+                    Token::Uint(ethabi::Uint::MAX),
+                ],
+            ),
+            100_000,
+        )
+        .unwrap();
+        assert!(result.is_err(), "too large amounts should be rejected");
+        assert!(backend.subcalls().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let ret = call_contract(ADDRESS, &[0x01, 0x02], 10_000).unwrap();
+        assert!(ret.is_err(), "missing selector should be rejected");
+
+        let ret = call_contract(ADDRESS, &[0x01, 0x02, 0x03, 0x04], 10_000).unwrap();
+        assert!(ret.is_err(), "unknown selector should be rejected");
+
+        let ret = call_contract(
+            ADDRESS,
+            &input(
+                "allowance",
+                &[ParamType::Bytes, ParamType::Bytes],
+                &[
+                    Token::Bytes(vec![0x01; 5]),
+                    Token::Bytes(keys::dave::address().as_ref().to_vec()),
+                ],
+            ),
+            10_000,
+        )
+        .unwrap();
+        assert!(ret.is_err(), "malformed addresses should be rejected");
+    }
+}
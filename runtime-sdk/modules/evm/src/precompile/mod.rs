@@ -12,8 +12,11 @@ use primitive_types::H160;
 
 use crate::{backend::EVMBackendExt, Config};
 
+mod allowance;
 mod confidential;
 mod gas;
+mod names;
+mod rofl;
 mod sha2;
 mod standard;
 mod subcall;
@@ -134,6 +137,10 @@ impl<Cfg: Config, B: EVMBackendExt> PrecompileSet for Precompiles<'_, Cfg, B> {
             (1, 1, 2) => sha2::call_sha512(handle),
             (1, 1, 3) => subcall::call_subcall(handle, self.backend),
             (1, 1, 4) => sha2::call_sha384(handle),
+            (1, 1, 5) => names::call_resolve(handle, self.backend),
+            (1, 1, 6) => allowance::call_allowance_bridge::<Cfg, B>(handle, self.backend),
+            (1, 1, 7) => rofl::call_is_authorized_origin(handle, self.backend),
+            (1, 1, 8) => names::call_reverse_resolve(handle, self.backend),
             _ => return Cfg::additional_precompiles().and_then(|pc| pc.execute(handle)),
         })
     }
@@ -150,7 +157,7 @@ impl<Cfg: Config, B: EVMBackendExt> PrecompileSet for Precompiles<'_, Cfg, B> {
                 // Oasis-specific, confidential.
                 (1, 0, 1..=10, true) |
                 // Oasis-specific, general.
-                (1, 1, 1..=4, _)
+                (1, 1, 1..=8, _)
             )
         {
             IsPrecompileResult::Answer {
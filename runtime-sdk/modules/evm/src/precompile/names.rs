@@ -0,0 +1,289 @@
+use ethabi::{ParamType, Token};
+use evm::{
+    executor::stack::{PrecompileFailure, PrecompileHandle, PrecompileOutput},
+    ExitError, ExitSucceed,
+};
+use primitive_types::H160;
+
+use crate::backend::EVMBackendExt;
+use oasis_runtime_sdk::{
+    module::CallResult,
+    modules::names::types,
+    subcall,
+    types::{address::Address, transaction::CallerAddress},
+};
+
+use super::{record_linear_cost, PrecompileResult};
+
+const RESOLVE_BASE_COST: u64 = 100;
+const RESOLVE_WORD_COST: u64 = 10;
+
+/// Resolve a name registered with the names module.
+///
+/// Returns whether the name resolves, the Ethereum-compatible address that it resolves to (or the
+/// zero address if none) and the raw runtime address that it resolves to.
+pub(super) fn call_resolve<B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+) -> PrecompileResult {
+    record_linear_cost(
+        handle,
+        handle.input().len() as u64,
+        RESOLVE_BASE_COST,
+        RESOLVE_WORD_COST,
+    )?;
+
+    let mut call_args = ethabi::decode(&[ParamType::String], handle.input()).map_err(|e| {
+        PrecompileFailure::Error {
+            exit_status: ExitError::Other(e.to_string().into()),
+        }
+    })?;
+    let name = call_args.pop().unwrap().into_string().unwrap();
+
+    let result = backend
+        .subcall(
+            subcall::SubcallInfo {
+                caller: CallerAddress::EthAddress(handle.context().caller.into()),
+                method: "names.Resolve".to_string(),
+                body: cbor::to_value(types::NameQuery { name }),
+                max_depth: 8,
+                max_gas: handle.remaining_gas(),
+                read_only: true,
+            },
+            subcall::AllowAllValidator,
+        )
+        .map_err(|_| PrecompileFailure::Error {
+            exit_status: ExitError::Other("resolve failed".into()),
+        })?;
+
+    // Charge gas (this shouldn't fail given that we set the limit appropriately).
+    handle.record_cost(result.gas_used)?;
+
+    let record: Option<types::NameRecord> = match result.call_result {
+        CallResult::Ok(value) => {
+            Some(
+                cbor::from_value(value).map_err(|_| PrecompileFailure::Error {
+                    exit_status: ExitError::Other("record is malformed".into()),
+                })?,
+            )
+        }
+        // Names that are not registered or have expired do not resolve.
+        CallResult::Failed { .. } => None,
+        CallResult::Aborted(_) => {
+            return Err(PrecompileFailure::Error {
+                exit_status: ExitError::Other("resolve failed".into()),
+            });
+        }
+    };
+
+    let output = match record {
+        Some(record) => ethabi::encode(&[
+            Token::Bool(true),
+            Token::Address(record.eth_address.map(H160::from).unwrap_or_default()),
+            Token::Bytes(record.address.as_ref().to_vec()),
+        ]),
+        None => ethabi::encode(&[
+            Token::Bool(false),
+            Token::Address(H160::zero()),
+            Token::Bytes(Vec::new()),
+        ]),
+    };
+
+    Ok(PrecompileOutput {
+        exit_status: ExitSucceed::Returned,
+        output,
+    })
+}
+
+/// Resolve the reverse record of an Ethereum-compatible address registered with the names module.
+///
+/// Returns whether the address has a valid reverse record and the name that it resolves to (or an
+/// empty string if none).
+pub(super) fn call_reverse_resolve<B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+) -> PrecompileResult {
+    record_linear_cost(
+        handle,
+        handle.input().len() as u64,
+        RESOLVE_BASE_COST,
+        RESOLVE_WORD_COST,
+    )?;
+
+    let mut call_args = ethabi::decode(&[ParamType::Address], handle.input()).map_err(|e| {
+        PrecompileFailure::Error {
+            exit_status: ExitError::Other(e.to_string().into()),
+        }
+    })?;
+    let address = call_args.pop().unwrap().into_address().unwrap();
+
+    let result = backend
+        .subcall(
+            subcall::SubcallInfo {
+                caller: CallerAddress::EthAddress(handle.context().caller.into()),
+                method: "names.ReverseResolve".to_string(),
+                body: cbor::to_value(types::ReverseQuery {
+                    address: Address::from_eth(address.as_ref()),
+                }),
+                max_depth: 8,
+                max_gas: handle.remaining_gas(),
+                read_only: true,
+            },
+            subcall::AllowAllValidator,
+        )
+        .map_err(|_| PrecompileFailure::Error {
+            exit_status: ExitError::Other("reverse resolve failed".into()),
+        })?;
+
+    // Charge gas (this shouldn't fail given that we set the limit appropriately).
+    handle.record_cost(result.gas_used)?;
+
+    let name: Option<String> = match result.call_result {
+        CallResult::Ok(value) => {
+            Some(
+                cbor::from_value(value).map_err(|_| PrecompileFailure::Error {
+                    exit_status: ExitError::Other("name is malformed".into()),
+                })?,
+            )
+        }
+        // Addresses without a valid reverse record do not resolve.
+        CallResult::Failed { .. } => None,
+        CallResult::Aborted(_) => {
+            return Err(PrecompileFailure::Error {
+                exit_status: ExitError::Other("reverse resolve failed".into()),
+            });
+        }
+    };
+
+    let output = match name {
+        Some(name) => ethabi::encode(&[Token::Bool(true), Token::String(name)]),
+        None => ethabi::encode(&[Token::Bool(false), Token::String(String::new())]),
+    };
+
+    Ok(PrecompileOutput {
+        exit_status: ExitSucceed::Returned,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ethabi::{ParamType, Token};
+
+    use oasis_runtime_sdk::{
+        module::CallResult, modules::names::types, testing::keys, types::address::Address,
+    };
+
+    use super::super::testing::*;
+
+    const RESOLVE_ADDRESS: H160 = H160([
+        0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x05,
+    ]);
+    const REVERSE_RESOLVE_ADDRESS: H160 = H160([
+        0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x08,
+    ]);
+
+    /// Ethereum-compatible address of the account that the registered name resolves to.
+    const ETH_ADDRESS: [u8; 20] = [0x42; 20];
+
+    /// Record of the single registered name, "alice", that also has a reverse record.
+    fn record() -> types::NameRecord {
+        types::NameRecord {
+            owner: keys::alice::address(),
+            address: Address::from_eth(&ETH_ADDRESS),
+            eth_address: Some(ETH_ADDRESS),
+            expiration: 1_000,
+        }
+    }
+
+    /// A backend resolving the single registered name.
+    fn backend() -> MockBackend {
+        MockBackend::default().with_subcall_handler(|info| {
+            let found = match info.method.as_str() {
+                "names.Resolve" => {
+                    let query: types::NameQuery = cbor::from_value(info.body.clone()).unwrap();
+                    (query.name == "alice").then(|| cbor::to_value(record()))
+                }
+                "names.ReverseResolve" => {
+                    let query: types::ReverseQuery = cbor::from_value(info.body.clone()).unwrap();
+                    (query.address == record().address).then(|| cbor::to_value("alice".to_owned()))
+                }
+                _ => unimplemented!(),
+            };
+            match found {
+                Some(value) => CallResult::Ok(value),
+                None => CallResult::Failed {
+                    module: "names".to_owned(),
+                    code: 2,
+                    message: "unknown name".to_owned(),
+                },
+            }
+        })
+    }
+
+    #[test]
+    fn test_resolve_malformed_input() {
+        let ret = call_contract(RESOLVE_ADDRESS, &[0x01, 0x02], 10_000).unwrap();
+        assert!(ret.is_err(), "malformed input should be rejected");
+
+        let ret = call_contract(REVERSE_RESOLVE_ADDRESS, &[0x01, 0x02], 10_000).unwrap();
+        assert!(ret.is_err(), "malformed input should be rejected");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolve = |name: &str| {
+            let input = ethabi::encode(&[Token::String(name.to_owned())]);
+            let output = call_contract_with_backend(&backend(), RESOLVE_ADDRESS, &input, 10_000)
+                .unwrap()
+                .expect("resolve should succeed")
+                .output;
+            ethabi::decode(
+                &[ParamType::Bool, ParamType::Address, ParamType::Bytes],
+                &output,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            resolve("alice"),
+            vec![
+                Token::Bool(true),
+                Token::Address(H160(ETH_ADDRESS)),
+                Token::Bytes(record().address.as_ref().to_vec()),
+            ]
+        );
+        assert_eq!(
+            resolve("bob"),
+            vec![
+                Token::Bool(false),
+                Token::Address(H160::zero()),
+                Token::Bytes(Vec::new()),
+            ],
+            "unknown names should not resolve"
+        );
+    }
+
+    #[test]
+    fn test_reverse_resolve() {
+        let reverse_resolve = |address: H160| {
+            let input = ethabi::encode(&[Token::Address(address)]);
+            let output =
+                call_contract_with_backend(&backend(), REVERSE_RESOLVE_ADDRESS, &input, 10_000)
+                    .unwrap()
+                    .expect("reverse resolve should succeed")
+                    .output;
+            ethabi::decode(&[ParamType::Bool, ParamType::String], &output).unwrap()
+        };
+
+        assert_eq!(
+            reverse_resolve(H160(ETH_ADDRESS)),
+            vec![Token::Bool(true), Token::String("alice".to_owned())]
+        );
+        assert_eq!(
+            reverse_resolve(H160([0x43; 20])),
+            vec![Token::Bool(false), Token::String(String::new())],
+            "addresses without reverse records should not resolve"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use ethabi::{ParamType, Token};
+use evm::{
+    executor::stack::{PrecompileFailure, PrecompileHandle, PrecompileOutput},
+    ExitError, ExitSucceed,
+};
+
+use crate::backend::EVMBackendExt;
+use oasis_runtime_sdk::types::app_id::AppId;
+
+use super::{record_linear_cost, PrecompileResult};
+
+const IS_AUTHORIZED_ORIGIN_BASE_COST: u64 = 100;
+
+/// Check whether the current transaction has been signed by an authorized instance of the given
+/// ROFL application.
+///
+/// The input is the 21-byte application identifier and the output is a boolean.
+pub(super) fn call_is_authorized_origin<B: EVMBackendExt>(
+    handle: &mut impl PrecompileHandle,
+    backend: &B,
+) -> PrecompileResult {
+    record_linear_cost(handle, 0, IS_AUTHORIZED_ORIGIN_BASE_COST, 0)?;
+
+    let mut call_args = ethabi::decode(&[ParamType::FixedBytes(AppId::SIZE)], handle.input())
+        .map_err(|e| PrecompileFailure::Error {
+            exit_status: ExitError::Other(e.to_string().into()),
+        })?;
+    let app = call_args.pop().unwrap().into_fixed_bytes().unwrap();
+    let app = AppId::from_bytes(&app).map_err(|_| PrecompileFailure::Error {
+        exit_status: ExitError::Other("invalid app identifier".into()),
+    })?;
+
+    let authorized = backend.rofl_origin() == Some(app);
+
+    Ok(PrecompileOutput {
+        exit_status: ExitSucceed::Returned,
+        output: ethabi::encode(&[Token::Bool(authorized)]),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ethabi::{ParamType, Token};
+
+    use oasis_runtime_sdk::{testing::keys, types::app_id::AppId};
+
+    use super::super::testing::*;
+
+    const ADDRESS: H160 = H160([
+        0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x07,
+    ]);
+
+    fn is_authorized_origin(backend: &MockBackend, app: AppId) -> bool {
+        let input = ethabi::encode(&[Token::FixedBytes(app.as_ref().to_vec())]);
+        let output = call_contract_with_backend(backend, ADDRESS, &input, 10_000)
+            .unwrap()
+            .expect("call should succeed")
+            .output;
+        ethabi::decode(&[ParamType::Bool], &output)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .into_bool()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_is_authorized_origin_malformed_input() {
+        let ret = call_contract(ADDRESS, &[0x01, 0x02], 10_000).unwrap();
+        assert!(ret.is_err(), "malformed input should be rejected");
+    }
+
+    #[test]
+    fn test_is_authorized_origin() {
+        let app = AppId::from_creator_nonce(&keys::alice::address(), 0);
+        let other = AppId::from_creator_nonce(&keys::alice::address(), 1);
+
+        let ret = call_contract(
+            ADDRESS,
+            &ethabi::encode(&[Token::FixedBytes(app.as_ref().to_vec())]),
+            10_000,
+        )
+        .unwrap()
+        .expect("call should succeed");
+        assert_eq!(
+            ethabi::decode(&[ParamType::Bool], &ret.output).unwrap(),
+            vec![Token::Bool(false)],
+            "transactions not signed by ROFL apps should not be authorized"
+        );
+
+        let backend = MockBackend::default().with_rofl_origin(app);
+        assert!(is_authorized_origin(&backend, app));
+        assert!(
+            !is_authorized_origin(&backend, other),
+            "other apps should not be authorized"
+        );
+    }
+}
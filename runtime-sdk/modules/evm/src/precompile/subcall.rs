@@ -81,6 +81,7 @@ pub(super) fn call_subcall<B: EVMBackendExt>(
                 body,
                 max_depth: 8,
                 max_gas,
+                read_only: false,
             },
             ForbidReentrancy,
         )
@@ -99,11 +100,22 @@ pub(super) fn call_subcall<B: EVMBackendExt>(
                 Token::Bytes(cbor::to_vec(value)), // response
             ]),
         }),
-        CallResult::Failed { code, module, .. } => Ok(PrecompileOutput {
+        CallResult::Failed {
+            code,
+            module,
+            message,
+        } => Ok(PrecompileOutput {
             exit_status: ExitSucceed::Returned,
             output: ethabi::encode(&[
-                Token::Uint(code.into()),    // status_code
-                Token::Bytes(module.into()), // response
+                Token::Uint(code.into()),            // status_code
+                Token::Bytes(module.clone().into()), // response
+                // Structured revert data (CBOR). Appended after the response so that existing
+                // callers decoding only `(uint64, bytes)` are not affected.
+                Token::Bytes(cbor::to_vec(subcall::SubcallFailure {
+                    module,
+                    code,
+                    message,
+                })),
             ]),
         }),
         CallResult::Aborted(_) => {
@@ -123,6 +135,7 @@ mod test {
         context,
         module::{self, Module as _},
         modules::accounts,
+        subcall,
         testing::{
             keys,
             mock::{CallOptions, Mock},
@@ -137,7 +150,10 @@ mod test {
     use crate::{
         self as evm,
         mock::{decode_reverted, EvmSigner},
-        precompile::testing::{init_and_deploy_contract, TestConfig, TestRuntime},
+        precompile::testing::{
+            call_contract_with_backend, init_and_deploy_contract, MockBackend, TestConfig,
+            TestRuntime, H160,
+        },
         Config as _,
     };
 
@@ -255,6 +271,48 @@ mod test {
         assert_eq!(events[0].amount, 25742);
     }
 
+    #[test]
+    fn test_subcall_failure_output() {
+        let failure = subcall::SubcallFailure {
+            module: "accounts".to_owned(),
+            code: 2,
+            message: "insufficient balance".to_owned(),
+        };
+        let input = ethabi::encode(&[
+            Token::Bytes("accounts.Transfer".into()),
+            Token::Bytes(cbor::to_vec(cbor::Value::Simple(
+                cbor::SimpleValue::NullValue,
+            ))),
+        ]);
+        let output = call_contract_with_backend(
+            &MockBackend::default().with_subcall_failure(failure.clone()),
+            H160([
+                0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x03,
+            ]),
+            &input,
+            10_000,
+        )
+        .unwrap()
+        .expect("failed subcall should be returned to the caller")
+        .output;
+
+        // Callers decoding only the first two outputs should not be affected.
+        let mut outputs = ethabi::decode(&[ParamType::Uint(64), ParamType::Bytes], &output)
+            .expect("status code and module should be decodable");
+        assert_eq!(outputs.pop().unwrap().into_bytes().unwrap(), b"accounts");
+        assert_eq!(outputs.pop().unwrap().into_uint().unwrap(), 2.into());
+
+        // The third output should contain the structured failure.
+        let mut outputs = ethabi::decode(
+            &[ParamType::Uint(64), ParamType::Bytes, ParamType::Bytes],
+            &output,
+        )
+        .expect("structured failure should be decodable");
+        let decoded: subcall::SubcallFailure =
+            cbor::from_slice(&outputs.pop().unwrap().into_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, failure);
+    }
+
     #[test]
     fn test_require_regular_call() {
         let mut mock = Mock::default();
@@ -363,6 +421,7 @@ mod test {
         accounts::Module::set_params(accounts::Parameters {
             gas_costs: accounts::GasCosts {
                 tx_transfer: 100_000,
+                ..Default::default()
             },
             ..Default::default()
         });
@@ -1,4 +1,5 @@
 use evm::{
+    backend::Log,
     executor::stack::{PrecompileHandle, PrecompileSet},
     Context, ExitError, ExitReason, Transfer,
 };
@@ -9,7 +10,10 @@ use oasis_runtime_sdk::{
     modules::{accounts, accounts::Module, core, core::Error},
     subcall,
     testing::keys,
-    types::token::{self, Denomination},
+    types::{
+        app_id::AppId,
+        token::{self, Denomination},
+    },
     BatchContext, Runtime, Version,
 };
 
@@ -19,7 +23,7 @@ use crate::{
 };
 
 use super::{PrecompileResult, Precompiles};
-use std::collections::BTreeMap;
+use std::{cell::RefCell, collections::BTreeMap};
 
 pub(crate) struct TestConfig;
 
@@ -35,7 +39,53 @@ impl crate::Config for TestConfig {
     const CONFIDENTIAL: bool = true;
 }
 
-struct MockBackend;
+/// Amount of gas used by each subcall made through a [`MockBackend`].
+const MOCK_SUBCALL_GAS_USED: u64 = 10;
+
+/// Handler producing the results of subcalls made through a [`MockBackend`].
+type SubcallHandler = Box<dyn Fn(&subcall::SubcallInfo) -> module::CallResult>;
+
+/// A configurable backend for precompile tests. All subcalls are recorded and, unless configured
+/// otherwise, succeed with an empty result.
+#[derive(Default)]
+pub(crate) struct MockBackend {
+    rofl_origin: Option<AppId>,
+    subcall_handler: Option<SubcallHandler>,
+    subcalls: RefCell<Vec<subcall::SubcallInfo>>,
+}
+
+#[cfg(test)]
+impl MockBackend {
+    /// Make transactions originate from the given ROFL application.
+    pub(crate) fn with_rofl_origin(mut self, app: AppId) -> Self {
+        self.rofl_origin = Some(app);
+        self
+    }
+
+    /// Complete subcalls with the results produced by the given handler.
+    pub(crate) fn with_subcall_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&subcall::SubcallInfo) -> module::CallResult + 'static,
+    {
+        self.subcall_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Fail all subcalls with the given failure.
+    pub(crate) fn with_subcall_failure(self, failure: subcall::SubcallFailure) -> Self {
+        self.with_subcall_handler(move |_| module::CallResult::Failed {
+            module: failure.module.clone(),
+            code: failure.code,
+            message: failure.message.clone(),
+        })
+    }
+
+    /// Subcalls made through the backend so far.
+    pub(crate) fn subcalls(&self) -> Vec<subcall::SubcallInfo> {
+        self.subcalls.borrow().clone()
+    }
+}
+
 impl crate::backend::EVMBackendExt for MockBackend {
     fn random_bytes(&self, num_bytes: u64, pers: &[u8]) -> Vec<u8> {
         pers.iter()
@@ -46,10 +96,25 @@ impl crate::backend::EVMBackendExt for MockBackend {
 
     fn subcall<V: subcall::Validator + 'static>(
         &self,
-        _info: subcall::SubcallInfo,
+        info: subcall::SubcallInfo,
         _validator: V,
     ) -> Result<subcall::SubcallResult, Error> {
-        unimplemented!()
+        let call_result = match &self.subcall_handler {
+            Some(handler) => handler(&info),
+            None => module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+        };
+        self.subcalls.borrow_mut().push(info);
+
+        Ok(subcall::SubcallResult {
+            state: Default::default(),
+            call_result,
+            gas_used: MOCK_SUBCALL_GAS_USED,
+            gas_refund: 0,
+        })
+    }
+
+    fn rofl_origin(&self) -> Option<AppId> {
+        self.rofl_origin
     }
 }
 
@@ -60,6 +125,7 @@ struct MockPrecompileHandle<'a> {
     gas_limit: u64,
     gas_cost: u64,
     gas_used: u64,
+    logs: Vec<Log>,
 }
 
 impl<'a> PrecompileHandle for MockPrecompileHandle<'a> {
@@ -89,7 +155,12 @@ impl<'a> PrecompileHandle for MockPrecompileHandle<'a> {
         self.gas_limit.saturating_sub(self.gas_cost)
     }
 
-    fn log(&mut self, _address: H160, _topics: Vec<H256>, _data: Vec<u8>) -> Result<(), ExitError> {
+    fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        self.logs.push(Log {
+            address,
+            topics,
+            data,
+        });
         Ok(())
     }
 
@@ -136,6 +207,46 @@ pub fn call_contract(address: H160, input: &[u8], gas_limit: u64) -> Option<Prec
     call_contract_with_gas_report(address, input, gas_limit).map(|(result, _)| result)
 }
 
+/// Call the precompile at the given address as the precompile itself using the given backend.
+#[cfg(test)]
+pub(crate) fn call_contract_with_backend<B: crate::backend::EVMBackendExt>(
+    backend: &B,
+    address: H160,
+    input: &[u8],
+    gas_limit: u64,
+) -> Option<PrecompileResult> {
+    call_contract_with_logs(backend, address, input, gas_limit).map(|(result, _)| result)
+}
+
+/// Call the precompile at the given address as the precompile itself using the given backend and
+/// return the logs emitted by the call.
+#[cfg(test)]
+pub(crate) fn call_contract_with_logs<B: crate::backend::EVMBackendExt>(
+    backend: &B,
+    address: H160,
+    input: &[u8],
+    gas_limit: u64,
+) -> Option<(PrecompileResult, Vec<Log>)> {
+    let context: Context = Context {
+        address,
+        caller: Default::default(),
+        apparent_value: From::from(0),
+    };
+    let precompiles: Precompiles<'_, TestConfig, B> = Precompiles::new(backend);
+    let mut handle = MockPrecompileHandle {
+        address,
+        input,
+        context: &context,
+        gas_limit,
+        gas_cost: 0,
+        gas_used: 0,
+        logs: Vec::new(),
+    };
+    precompiles
+        .execute(&mut handle)
+        .map(|result| (result, handle.logs))
+}
+
 #[doc(hidden)]
 pub fn call_contract_with_gas_report(
     address: H160,
@@ -147,7 +258,8 @@ pub fn call_contract_with_gas_report(
         caller: Default::default(),
         apparent_value: From::from(0),
     };
-    let precompiles: Precompiles<'_, TestConfig, MockBackend> = Precompiles::new(&MockBackend);
+    let backend = MockBackend::default();
+    let precompiles: Precompiles<'_, TestConfig, MockBackend> = Precompiles::new(&backend);
     let mut handle = MockPrecompileHandle {
         address,
         input,
@@ -155,6 +267,7 @@ pub fn call_contract_with_gas_report(
         gas_limit,
         gas_cost: 0,
         gas_used: 0,
+        logs: Vec::new(),
     };
     precompiles
         .execute(&mut handle)
@@ -222,7 +335,12 @@ impl Runtime for TestRuntime {
                     gas_costs: Default::default(),
                     min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 0)]),
                     dynamic_min_gas_price: Default::default(),
+                    subcall: Default::default(),
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
             accounts::Genesis {
                 balances: BTreeMap::from([(
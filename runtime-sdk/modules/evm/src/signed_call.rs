@@ -42,7 +42,7 @@ pub(crate) fn verify<C: Context, Cfg: Config>(
     }
 
     // Next, verify the leash.
-    let current_block = ctx.runtime_header().round;
+    let current_block = ctx.time().round();
     let sdk_address = Cfg::map_address(query.caller.into());
     let nonce = Cfg::Accounts::get_nonce(sdk_address).unwrap();
     if nonce > leash.nonce {
@@ -1,6 +1,9 @@
 use oasis_runtime_sdk::{
     context::Context,
-    storage::{ConfidentialStore, CurrentStore, HashedStore, PrefixStore, Store, TypedStore},
+    storage::{
+        ConfidentialStore, ConfidentialStorePolicy, CurrentStore, HashedStore, PrefixStore, Store,
+        TypedStore,
+    },
 };
 
 use crate::{types::H160, Config};
@@ -15,6 +18,12 @@ pub const BLOCK_HASHES: &[u8] = &[0x03];
 /// Prefix for Ethereum account storage in our confidential storage (maps H160||H256 -> H256).
 pub const CONFIDENTIAL_STORAGES: &[u8] = &[0x04];
 
+/// Prefixes of module state exported as raw state.
+///
+/// Account storage is keyed by hashed (and in case of confidential storage, encrypted) keys so it
+/// can only be exported as raw state.
+pub const RAW: &[&[u8]] = &[CODES, STORAGES, BLOCK_HASHES, CONFIDENTIAL_STORAGES];
+
 /// Confidential store key pair ID domain separation context base.
 pub const CONFIDENTIAL_STORE_KEY_PAIR_ID_CONTEXT_BASE: &[u8] = b"oasis-runtime-sdk/evm: state";
 const CONTEXT_KEY_CONFIDENTIAL_STORE_INSTANCE_COUNT: &str = "evm.ConfidentialStoreCounter";
@@ -31,7 +40,7 @@ where
     F: FnOnce(&mut TypedStore<&mut dyn Store>) -> R,
 {
     if Cfg::CONFIDENTIAL {
-        with_confidential_storage(ctx, address, f)
+        with_confidential_storage(ctx, address, Cfg::CONFIDENTIAL_STORAGE_POLICY, f)
     } else {
         with_public_storage(address, f)
     }
@@ -51,7 +60,12 @@ where
 }
 
 /// Run closure with the confidential store of the provided contract address.
-pub fn with_confidential_storage<'a, C, F, R>(ctx: &'a mut C, address: &'a H160, f: F) -> R
+pub fn with_confidential_storage<'a, C, F, R>(
+    ctx: &'a mut C,
+    address: &'a H160,
+    policy: ConfidentialStorePolicy,
+    f: F,
+) -> R
 where
     C: Context,
     F: FnOnce(&mut TypedStore<&mut dyn Store>) -> R,
@@ -69,7 +83,7 @@ where
     let confidential_key = keypair.state_key;
 
     // These values are used to derive the confidential store nonce:
-    let round = ctx.runtime_header().round;
+    let round = ctx.time().round();
     let instance_count: usize = {
         // One Context is used per tx batch, so the instance count will monotonically increase.
         let cnt = *ctx
@@ -91,7 +105,8 @@ where
                 instance_count.to_le_bytes().as_slice(),
                 &[mode as u8],
             ],
-        );
+        )
+        .with_policy(policy);
         let mut store = TypedStore::new(&mut confidential_storages as &mut dyn Store);
         f(&mut store)
     })
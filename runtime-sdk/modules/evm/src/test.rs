@@ -162,6 +162,7 @@ fn do_test_evm_calls<C: Config>(force_plain: bool) {
             max_batch_gas: 10_000_000,
             ..Default::default()
         },
+        ..Default::default()
     });
 
     Accounts::init(accounts::Genesis {
@@ -185,6 +186,7 @@ fn do_test_evm_calls<C: Config>(force_plain: bool) {
 
     EVMModule::<C>::init(Genesis {
         parameters: Default::default(),
+        ..Default::default()
     });
 
     let erc20 = load_erc20();
@@ -277,6 +279,19 @@ fn do_test_evm_calls<C: Config>(force_plain: bool) {
     assert_eq!(erc20_name[64..68], vec![0x54, 0x65, 0x73, 0x74]); // "Test".
 }
 
+#[test]
+fn test_features() {
+    use module::Module as _;
+
+    let features = EVMModule::<EVMConfig>::features();
+    assert_eq!(features["chain_id"], cbor::to_value(0xa515u64));
+    assert_eq!(features["confidential"], cbor::to_value(false));
+    assert_eq!(features["hard_fork"], cbor::to_value("shanghai"));
+
+    let features = EVMModule::<ConfidentialEVMConfig>::features();
+    assert_eq!(features["confidential"], cbor::to_value(true));
+}
+
 #[test]
 fn test_evm_calls() {
     do_test_evm_calls::<EVMConfig>(false);
@@ -305,6 +320,7 @@ fn test_c10l_evm_balance_transfer() {
             max_batch_gas: 10_000_000,
             ..Default::default()
         },
+        ..Default::default()
     });
 
     Accounts::init(accounts::Genesis {
@@ -318,6 +334,7 @@ fn test_c10l_evm_balance_transfer() {
 
     EVMModule::<ConfidentialEVMConfig>::init(Genesis {
         parameters: Default::default(),
+        ..Default::default()
     });
 
     let recipient = ethabi::Address::repeat_byte(42);
@@ -415,6 +432,7 @@ impl<C: Config> Runtime for EVMRuntime<C> {
                     min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 0)]),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             accounts::Genesis {
                 balances: {
@@ -436,6 +454,7 @@ impl<C: Config> Runtime for EVMRuntime<C> {
             },
             Genesis {
                 parameters: Default::default(),
+                ..Default::default()
             },
         )
     }
@@ -1094,3 +1113,32 @@ fn test_return_value_limits() {
     assert_eq!(result[64], 0xFF, "result should be correct");
     assert_eq!(result[1023], 0x42, "result should be correct");
 }
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx =
+        mock.create_ctx_for_runtime::<EVMRuntime<EVMConfig>>(context::Mode::ExecuteTx, true);
+    let mut signer = EvmSigner::new(0, keys::dave::sigspec());
+
+    EVMRuntime::<EVMConfig>::migrate(&mut ctx);
+
+    // Create a contract, which stores its code and initial storage.
+    let dispatch_result = signer.call(
+        &mut ctx,
+        "evm.Create",
+        types::Create {
+            value: 0.into(),
+            init_code: load_contract_bytecode(TEST_CONTRACT_CODE_HEX),
+        },
+    );
+    assert!(dispatch_result.result.is_success(), "create should succeed");
+
+    let genesis = mock::assert_export_round_trip::<EVMModule<EVMConfig>>();
+    for prefix in [crate::state::CODES, crate::state::STORAGES] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "contract code and storage should be exported"
+        );
+    }
+}
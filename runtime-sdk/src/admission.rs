@@ -0,0 +1,159 @@
+//! Node-local transaction admission policies.
+//!
+//! Admission policies are only consulted while checking transactions for inclusion in the local
+//! transaction pool and before any signatures are verified, so they can cheaply reject unwanted
+//! transactions. They have no effect on consensus and different nodes may use different policies.
+use crate::{context::Context, modules};
+
+/// Unique module name.
+const MODULE_NAME: &str = "admission";
+
+/// Information about a transaction that is available before its signatures are verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxAdmissionInfo<'a> {
+    /// Size of the raw transaction envelope (in bytes).
+    pub size: usize,
+    /// Called method. This is `None` in case the method cannot be determined without fully
+    /// decoding the transaction (e.g., for module-controlled decoding schemes).
+    pub method: Option<&'a str>,
+    /// Number of authentication proofs (signers) in the envelope.
+    pub signer_count: usize,
+}
+
+/// A policy that decides whether a transaction should be admitted into the local transaction
+/// pool.
+pub trait TxAdmissionPolicy {
+    /// Decide whether the transaction should be admitted. Returning an error rejects the
+    /// transaction.
+    fn admit<C: Context>(
+        ctx: &mut C,
+        info: &TxAdmissionInfo<'_>,
+    ) -> Result<(), modules::core::Error>;
+}
+
+/// Policy that admits all transactions.
+pub struct AdmitAll;
+
+impl TxAdmissionPolicy for AdmitAll {
+    fn admit<C: Context>(
+        _ctx: &mut C,
+        _info: &TxAdmissionInfo<'_>,
+    ) -> Result<(), modules::core::Error> {
+        Ok(())
+    }
+}
+
+/// Admission policy configuration that can be provided by the node operator.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct LocalConfig {
+    /// Maximum size of the raw transaction envelope (in bytes). The special value of 0 means
+    /// that only the runtime-wide limit applies.
+    #[cbor(optional)]
+    pub max_tx_size: u64,
+
+    /// Maximum number of signers. The special value of 0 means that only the runtime-wide limit
+    /// applies.
+    #[cbor(optional)]
+    pub max_signers: u64,
+
+    /// Methods that are not admitted.
+    #[cbor(optional)]
+    pub denied_methods: Vec<String>,
+}
+
+impl LocalConfig {
+    /// Whether a transaction with the given information is admitted by this configuration.
+    pub fn admits(&self, info: &TxAdmissionInfo<'_>) -> bool {
+        if self.max_tx_size > 0 && info.size as u64 > self.max_tx_size {
+            return false;
+        }
+        if self.max_signers > 0 && info.signer_count as u64 > self.max_signers {
+            return false;
+        }
+        if let Some(method) = info.method {
+            if self.denied_methods.iter().any(|m| m == method) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Policy configured via the node-local configuration under the `admission` key. In case no
+/// configuration is provided, all transactions are admitted.
+pub struct LocalConfigPolicy;
+
+impl TxAdmissionPolicy for LocalConfigPolicy {
+    fn admit<C: Context>(
+        ctx: &mut C,
+        info: &TxAdmissionInfo<'_>,
+    ) -> Result<(), modules::core::Error> {
+        let cfg: LocalConfig = match ctx.local_config(MODULE_NAME) {
+            Some(cfg) => cfg,
+            None => return Ok(()),
+        };
+        if !cfg.admits(info) {
+            return Err(modules::core::Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::testing::{configmap, mock};
+
+    #[test]
+    fn test_local_config_policy() {
+        let info = TxAdmissionInfo {
+            size: 100,
+            method: Some("accounts.Transfer"),
+            signer_count: 2,
+        };
+
+        let mut mock = mock::Mock::default();
+        let mut ctx = mock.create_check_ctx();
+        LocalConfigPolicy::admit(&mut ctx, &info).expect("no configuration should admit all");
+
+        let mut mock = mock::Mock::with_local_config(configmap! {
+            "admission" => configmap! {
+                "max_tx_size" => 100,
+                "max_signers" => 2,
+                "denied_methods" => vec!["consensus.Withdraw"],
+            },
+        });
+        let mut ctx = mock.create_check_ctx();
+        LocalConfigPolicy::admit(&mut ctx, &info)
+            .expect("transaction within limits should be admitted");
+
+        let result = LocalConfigPolicy::admit(
+            &mut ctx,
+            &TxAdmissionInfo {
+                size: 101,
+                ..info.clone()
+            },
+        );
+        assert!(matches!(result, Err(modules::core::Error::Forbidden)));
+
+        let result = LocalConfigPolicy::admit(
+            &mut ctx,
+            &TxAdmissionInfo {
+                signer_count: 3,
+                ..info.clone()
+            },
+        );
+        assert!(matches!(result, Err(modules::core::Error::Forbidden)));
+
+        let result = LocalConfigPolicy::admit(
+            &mut ctx,
+            &TxAdmissionInfo {
+                method: Some("consensus.Withdraw"),
+                ..info
+            },
+        );
+        assert!(matches!(result, Err(modules::core::Error::Forbidden)));
+    }
+}
@@ -8,12 +8,17 @@ use rand_core::{OsRng, RngCore};
 
 use crate::{
     context::Context,
-    core::common::crypto::{mrae::deoxysii, x25519},
-    crypto::signature::context::get_chain_context_for,
+    core::common::crypto::{hash::Hash, mrae::deoxysii, x25519},
+    crypto::{mrae::xchacha20poly1305, signature::context::get_chain_context_for},
     keymanager, module,
-    modules::core::Error,
+    modules::{
+        accounts::API as _,
+        core::{Error, API as _},
+    },
+    runtime::Runtime,
     types::{
         self,
+        address::Address,
         transaction::{Call, CallFormat, CallResult},
     },
 };
@@ -22,7 +27,19 @@ use crate::{
 ///
 /// This is half the current window as enforced by the key manager as negative results are not
 /// cached and randomized queries could open the scheme to a potential DoS attack.
-const MAX_EPHEMERAL_KEY_AGE: beacon::EpochTime = 5;
+pub const MAX_EPHEMERAL_KEY_AGE: beacon::EpochTime = 5;
+
+/// Maximum number of rounds for which a signed query can remain valid.
+pub const MAX_SIGNED_QUERY_VALIDITY: u64 = 100;
+
+/// Context key used to store the authenticated caller of the current query.
+const CONTEXT_KEY_QUERY_CALLER: &str = "callformat.QueryCaller";
+
+/// Encrypted call formats supported by the runtime, in order of preference.
+pub const ENCRYPTED_CALL_FORMATS: &[CallFormat] = &[
+    CallFormat::EncryptedX25519DeoxysII,
+    CallFormat::EncryptedX25519XChaCha20Poly1305,
+];
 
 /// Additional metadata required by the result encoding function.
 pub enum Metadata {
@@ -35,6 +52,16 @@ pub enum Metadata {
         /// Transaction index within the batch.
         index: usize,
     },
+    EncryptedX25519XChaCha20Poly1305 {
+        /// Caller's ephemeral public key used for X25519.
+        pk: x25519::PublicKey,
+        /// Secret key.
+        sk: x25519::PrivateKey,
+        /// Transaction index within the batch.
+        index: usize,
+        /// Hash of the call envelope, used to derive unique result nonces.
+        envelope_hash: Hash,
+    },
 }
 
 impl std::fmt::Debug for Metadata {
@@ -46,6 +73,78 @@ impl std::fmt::Debug for Metadata {
                 .field("pk", pk)
                 .field("index", index)
                 .finish_non_exhaustive(),
+            Self::EncryptedX25519XChaCha20Poly1305 { pk, index, .. } => f
+                .debug_struct("Metadata::EncryptedX25519XChaCha20Poly1305")
+                .field("pk", pk)
+                .field("index", index)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Call envelope of one of the encrypted call formats.
+enum CallEnvelope {
+    X25519DeoxysII(types::callformat::CallEnvelopeX25519DeoxysII),
+    X25519XChaCha20Poly1305(types::callformat::CallEnvelopeX25519XChaCha20Poly1305),
+}
+
+impl CallEnvelope {
+    fn decode(format: CallFormat, body: cbor::Value) -> Result<Self, Error> {
+        match format {
+            CallFormat::Plain => Err(Error::InvalidCallFormat(anyhow!("plain call format"))),
+            CallFormat::EncryptedX25519DeoxysII => {
+                cbor::from_value(body).map(Self::X25519DeoxysII).ok()
+            }
+            CallFormat::EncryptedX25519XChaCha20Poly1305 => cbor::from_value(body)
+                .map(Self::X25519XChaCha20Poly1305)
+                .ok(),
+        }
+        .ok_or_else(|| Error::InvalidCallFormat(anyhow!("bad call envelope")))
+    }
+
+    fn pk(&self) -> x25519::PublicKey {
+        match self {
+            Self::X25519DeoxysII(envelope) => envelope.pk,
+            Self::X25519XChaCha20Poly1305(envelope) => envelope.pk,
+        }
+    }
+
+    fn epoch(&self) -> beacon::EpochTime {
+        match self {
+            Self::X25519DeoxysII(envelope) => envelope.epoch,
+            Self::X25519XChaCha20Poly1305(envelope) => envelope.epoch,
+        }
+    }
+
+    fn open(&self, sk: &x25519::PrivateKey) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::X25519DeoxysII(envelope) => deoxysii::box_open(
+                &envelope.nonce,
+                envelope.data.clone(),
+                vec![],
+                &envelope.pk.0,
+                &sk.0,
+            ),
+            Self::X25519XChaCha20Poly1305(envelope) => xchacha20poly1305::box_open(
+                &envelope.nonce,
+                envelope.data.clone(),
+                vec![],
+                &envelope.pk.0,
+                &sk.0,
+            ),
+        }
+    }
+
+    fn into_metadata(self, sk: x25519::PrivateKey, index: usize) -> Metadata {
+        let pk = self.pk();
+        match self {
+            Self::X25519DeoxysII(_) => Metadata::EncryptedX25519DeoxysII { pk, sk, index },
+            Self::X25519XChaCha20Poly1305(envelope) => Metadata::EncryptedX25519XChaCha20Poly1305 {
+                pk,
+                sk,
+                index,
+                envelope_hash: Hash::digest_bytes(&cbor::to_vec(envelope)),
+            },
         }
     }
 }
@@ -62,7 +161,8 @@ fn verify_epoch<C: Context>(ctx: &C, epoch: beacon::EpochTime) -> Result<(), Err
     if epoch > ctx.epoch() {
         return Err(Error::InvalidCallFormat(anyhow!("epoch in the future")));
     }
-    if epoch < ctx.epoch().saturating_sub(MAX_EPHEMERAL_KEY_AGE) {
+    let params = <C::Runtime as Runtime>::Core::ephemeral_key_parameters();
+    if ctx.epoch() > params.key_valid_until(epoch) {
         return Err(Error::InvalidCallFormat(anyhow!(
             "epoch too far in the past"
         )));
@@ -97,25 +197,28 @@ pub fn decode_call_ex<C: Context>(
         // In case of the plain-text data format, we simply pass on the call unchanged.
         CallFormat::Plain => Ok(Some((call, Metadata::Empty))),
 
-        // Encrypted data format using X25519 key exchange and Deoxys-II symmetric encryption.
-        CallFormat::EncryptedX25519DeoxysII => {
+        // Encrypted data formats using X25519 key exchange and symmetric encryption.
+        CallFormat::EncryptedX25519DeoxysII | CallFormat::EncryptedX25519XChaCha20Poly1305 => {
             // Method must be empty.
             if !call.method.is_empty() {
                 return Err(Error::InvalidCallFormat(anyhow!("non-empty method")));
             }
-            // Body needs to follow the specified envelope.
-            let envelope: types::callformat::CallEnvelopeX25519DeoxysII =
-                cbor::from_value(call.body)
-                    .map_err(|_| Error::InvalidCallFormat(anyhow!("bad call envelope")))?;
-            let pk = envelope.pk;
+            // Body needs to follow the envelope of the specified format.
+            let envelope = CallEnvelope::decode(call.format, call.body)?;
 
             // Make sure a key manager is available in this runtime.
             let key_manager = ctx
                 .key_manager()
                 .ok_or_else(|| Error::InvalidCallFormat(anyhow!("confidential txs unavailable")))?;
 
+            // Validate the key epoch as this does not require decryption.
+            if envelope.epoch() > 0 {
+                verify_epoch(ctx, envelope.epoch())?;
+            }
+
             // If we are only doing checks, this is the most that we can do as in this case we may
-            // be unable to access the key manager.
+            // be unable to access the key manager. Deferring also ensures that no plaintext can
+            // reach check results, emitted events or metrics.
             if !assume_km_reachable && (ctx.is_check_only() || ctx.is_simulation()) {
                 return Ok(None);
             }
@@ -131,25 +234,20 @@ pub fn decode_call_ex<C: Context>(
                     })?;
                 let sk = keypair.input_keypair.sk;
                 // Derive shared secret via X25519 and open the sealed box.
-                deoxysii::box_open(
-                    &envelope.nonce,
-                    envelope.data.clone(),
-                    vec![],
-                    &envelope.pk.0,
-                    &sk.0,
-                )
-                .map(|data| (data, sk))
+                envelope.open(&sk).map(|data| (data, sk))
             };
 
             // Get transaction key pair from the key manager. Note that only the `input_keypair`
             // portion is used.
-            let (data, sk) = if envelope.epoch > 0 {
-                verify_epoch(ctx, envelope.epoch)?;
-                decrypt(envelope.epoch)
+            let (data, sk) = if envelope.epoch() > 0 {
+                decrypt(envelope.epoch())
             } else {
-                // In case of failure, also try with previous epoch key in case the epoch
-                // transition just occurred.
-                decrypt(ctx.epoch()).or_else(|_| decrypt(ctx.epoch() - 1))
+                // In case of failure, also try with previous key in case the key rotation just
+                // occurred.
+                let params = <C::Runtime as Runtime>::Core::ephemeral_key_parameters();
+                let key_epoch = params.key_epoch(ctx.epoch());
+                decrypt(key_epoch)
+                    .or_else(|_| decrypt(key_epoch.saturating_sub(params.rotation_interval())))
             }
             .map_err(Error::InvalidCallFormat)?;
 
@@ -163,10 +261,7 @@ pub fn decode_call_ex<C: Context>(
                 return Err(Error::InvalidCallFormat(anyhow!("read-only flag mismatch")));
             }
 
-            Ok(Some((
-                call,
-                Metadata::EncryptedX25519DeoxysII { pk, sk, index },
-            )))
+            Ok(Some((call, envelope.into_metadata(sk, index))))
         }
     }
 }
@@ -175,41 +270,66 @@ pub fn decode_call_ex<C: Context>(
 /// Encodes a call such that it can be decoded by `decode_call[_ex]`.
 pub fn encode_call<C: Context>(
     ctx: &C,
-    mut call: Call,
+    call: Call,
     client_keypair: &(x25519_dalek::PublicKey, x25519_dalek::StaticSecret),
 ) -> Result<Call, Error> {
     match call.format {
         // In case of the plain-text data format, we simply pass on the call unchanged.
         CallFormat::Plain => Ok(call),
 
-        // Encrypted data format using X25519 key exchange and Deoxys-II symmetric encryption.
-        CallFormat::EncryptedX25519DeoxysII => {
+        // Encrypted data formats using X25519 key exchange and symmetric encryption.
+        CallFormat::EncryptedX25519DeoxysII | CallFormat::EncryptedX25519XChaCha20Poly1305 => {
             let key_manager = ctx.key_manager().ok_or_else(|| {
                 Error::InvalidCallFormat(anyhow!("confidential transactions not available"))
             })?;
-            let epoch = ctx.epoch();
+            let epoch =
+                <C::Runtime as Runtime>::Core::ephemeral_key_parameters().key_epoch(ctx.epoch());
             let runtime_keypair = key_manager
                 .get_or_create_ephemeral_keys(get_key_pair_id(epoch), epoch)
                 .map_err(|err| Error::Abort(err.into()))?;
             let runtime_pk = runtime_keypair.input_keypair.pk;
-            let nonce = [0u8; deoxysii::NONCE_SIZE];
+
+            let format = call.format;
+            let plaintext = cbor::to_vec(call);
+            let body = match format {
+                CallFormat::EncryptedX25519DeoxysII => {
+                    let nonce = [0u8; deoxysii::NONCE_SIZE];
+                    cbor::to_value(types::callformat::CallEnvelopeX25519DeoxysII {
+                        pk: client_keypair.0.into(),
+                        nonce,
+                        epoch,
+                        data: deoxysii::box_seal(
+                            &nonce,
+                            plaintext,
+                            vec![],
+                            &runtime_pk.0,
+                            &client_keypair.1,
+                        )
+                        .unwrap(),
+                    })
+                }
+                _ => {
+                    let mut nonce = [0u8; xchacha20poly1305::NONCE_SIZE];
+                    OsRng.fill_bytes(&mut nonce);
+                    cbor::to_value(types::callformat::CallEnvelopeX25519XChaCha20Poly1305 {
+                        pk: client_keypair.0.into(),
+                        nonce,
+                        epoch,
+                        data: xchacha20poly1305::box_seal(
+                            &nonce,
+                            plaintext,
+                            vec![],
+                            &runtime_pk.0,
+                            &client_keypair.1,
+                        )
+                        .unwrap(),
+                    })
+                }
+            };
 
             Ok(Call {
-                format: call.format,
-                method: std::mem::take(&mut call.method),
-                body: cbor::to_value(types::callformat::CallEnvelopeX25519DeoxysII {
-                    pk: client_keypair.0.into(),
-                    nonce,
-                    epoch,
-                    data: deoxysii::box_seal(
-                        &nonce,
-                        cbor::to_vec(call),
-                        vec![],
-                        &runtime_pk.0,
-                        &client_keypair.1,
-                    )
-                    .unwrap(),
-                }),
+                format,
+                body,
                 ..Default::default()
             })
         }
@@ -238,26 +358,40 @@ pub fn encode_result_ex<C: Context>(
         // In case of the plain-text data format, we simply pass on the data unchanged.
         Metadata::Empty => result.into(),
 
-        // Encrypted data format using X25519 key exchange and Deoxys-II symmetric encryption.
-        Metadata::EncryptedX25519DeoxysII { pk, sk, index } => {
+        // Encrypted data formats using X25519 key exchange and symmetric encryption.
+        metadata => {
             // Serialize result.
             let result: CallResult = result.into();
 
             if expose_failure {
                 if result.is_success() {
-                    return CallResult::Ok(encrypt_result_x25519_deoxysii(
-                        ctx, result, pk, sk, index,
-                    ));
+                    return CallResult::Ok(encrypt_result(ctx, result, metadata));
                 }
 
                 return result;
             }
 
-            CallResult::Unknown(encrypt_result_x25519_deoxysii(ctx, result, pk, sk, index))
+            CallResult::Unknown(encrypt_result(ctx, result, metadata))
         }
     }
 }
 
+/// Encrypt a call result using the encryption scheme of the given call format metadata.
+fn encrypt_result<C: Context>(ctx: &C, result: CallResult, metadata: Metadata) -> cbor::Value {
+    match metadata {
+        Metadata::Empty => unreachable!("plain call results are never encrypted"),
+        Metadata::EncryptedX25519DeoxysII { pk, sk, index } => {
+            encrypt_result_x25519_deoxysii(ctx, result, pk, sk, index)
+        }
+        Metadata::EncryptedX25519XChaCha20Poly1305 {
+            pk,
+            sk,
+            index,
+            envelope_hash,
+        } => encrypt_result_x25519_xchacha20poly1305(ctx, result, pk, sk, index, envelope_hash),
+    }
+}
+
 /// Encrypt a call result using the X25519-Deoxys-II encryption scheme.
 pub fn encrypt_result_x25519_deoxysii<C: Context>(
     ctx: &C,
@@ -268,9 +402,7 @@ pub fn encrypt_result_x25519_deoxysii<C: Context>(
 ) -> cbor::Value {
     // Generate nonce for the output as Round (8 bytes) || Index (4 bytes) || 00 00 00.
     let mut nonce = Vec::with_capacity(deoxysii::NONCE_SIZE);
-    nonce
-        .write_u64::<BigEndian>(ctx.runtime_header().round)
-        .unwrap();
+    nonce.write_u64::<BigEndian>(ctx.time().round()).unwrap();
     nonce
         .write_u32::<BigEndian>(index.try_into().unwrap())
         .unwrap();
@@ -280,11 +412,54 @@ pub fn encrypt_result_x25519_deoxysii<C: Context>(
         OsRng.fill_bytes(&mut nonce[deoxysii::NONCE_SIZE - 3..]);
     }
     let nonce = nonce.try_into().unwrap();
+    let header = types::callformat::ResultHeader::new(&result);
+    let result = cbor::to_vec(result);
+    let data = deoxysii::box_seal(&nonce, result, header.additional_data(), &pk.0, &sk.0).unwrap();
+
+    // Return an envelope.
+    cbor::to_value(types::callformat::ResultEnvelopeX25519DeoxysII {
+        nonce,
+        data,
+        header: Some(header),
+    })
+}
+
+/// Encrypt a call result using the X25519-XChaCha20-Poly1305 encryption scheme.
+pub fn encrypt_result_x25519_xchacha20poly1305<C: Context>(
+    ctx: &C,
+    result: types::transaction::CallResult,
+    pk: x25519::PublicKey,
+    sk: x25519::PrivateKey,
+    index: usize,
+    envelope_hash: Hash,
+) -> cbor::Value {
+    // As XChaCha20-Poly1305 is not nonce-misuse resistant, the nonce for the output is derived
+    // as H(Round || Index || Envelope Hash) so that it is unique for each call under the same key.
+    // Nonces are randomized for all simulations (including private queries) as these may be
+    // repeated in the same round with different results.
+    let mut nonce = [0u8; xchacha20poly1305::NONCE_SIZE];
+    if ctx.is_simulation() {
+        OsRng.fill_bytes(&mut nonce);
+    } else {
+        let index: u32 = index.try_into().unwrap();
+        let h = Hash::digest_bytes_list(&[
+            &ctx.time().round().to_be_bytes(),
+            &index.to_be_bytes(),
+            envelope_hash.as_ref(),
+        ]);
+        nonce.copy_from_slice(h.truncated(xchacha20poly1305::NONCE_SIZE));
+    }
+    let header = types::callformat::ResultHeader::new(&result);
     let result = cbor::to_vec(result);
-    let data = deoxysii::box_seal(&nonce, result, vec![], &pk.0, &sk.0).unwrap();
+    let data = xchacha20poly1305::box_seal(&nonce, result, header.additional_data(), &pk.0, &sk.0)
+        .unwrap();
 
     // Return an envelope.
-    cbor::to_value(types::callformat::ResultEnvelopeX25519DeoxysII { nonce, data })
+    cbor::to_value(types::callformat::ResultEnvelopeX25519XChaCha20Poly1305 {
+        nonce,
+        data,
+        header: Some(header),
+    })
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -311,36 +486,339 @@ pub fn decode_result<C: Context>(
             })
         }
     };
-    match format {
+
+    // Get the runtime pubkey from the KM. A real client would simply use the session key that has
+    // already been derived.
+    let key_manager = ctx
+        .key_manager()
+        .ok_or_else(|| Error::InvalidCallFormat(anyhow!("confidential txs unavailable")))?;
+    let epoch = <C::Runtime as Runtime>::Core::ephemeral_key_parameters().key_epoch(ctx.epoch());
+    let keypair = key_manager
+        .get_or_create_ephemeral_keys(get_key_pair_id(epoch), epoch)
+        .map_err(|err| Error::Abort(err.into()))?;
+    let runtime_pk = keypair.input_keypair.pk;
+
+    let bad_envelope = |_| Error::InvalidCallFormat(anyhow!("bad result envelope"));
+    let additional_data = |header: &Option<types::callformat::ResultHeader>| {
+        header
+            .as_ref()
+            .map(|header| header.additional_data())
+            .unwrap_or_default()
+    };
+    let (data, header) = match format {
         CallFormat::Plain => unreachable!("checked above"),
         CallFormat::EncryptedX25519DeoxysII => {
             let envelope: types::callformat::ResultEnvelopeX25519DeoxysII =
-                cbor::from_value(envelope_value)
-                    .map_err(|_| Error::InvalidCallFormat(anyhow!("bad result envelope")))?;
-
-            // Get the runtime pubkey from the KM. A real client would simply use the
-            // session key that has already been derived.
-            let key_manager = ctx
-                .key_manager()
-                .ok_or_else(|| Error::InvalidCallFormat(anyhow!("confidential txs unavailable")))?;
-            let keypair = key_manager
-                .get_or_create_ephemeral_keys(get_key_pair_id(ctx.epoch()), ctx.epoch())
-                .map_err(|err| Error::Abort(err.into()))?;
-            let runtime_pk = keypair.input_keypair.pk;
-
+                cbor::from_value(envelope_value).map_err(bad_envelope)?;
             let data = deoxysii::box_open(
                 &envelope.nonce,
                 envelope.data,
-                vec![],
+                additional_data(&envelope.header),
                 &runtime_pk.0,
                 &client_keypair.1,
-            )
-            .map_err(Error::InvalidCallFormat)?;
-            let call_result: CallResult = cbor::from_slice(&data)
-                .map_err(|_| Error::InvalidCallFormat(anyhow!("malformed call")))?;
-            Ok(call_result
-                .into_call_result()
-                .expect("CallResult was Unknown"))
+            );
+            (data, envelope.header)
+        }
+        CallFormat::EncryptedX25519XChaCha20Poly1305 => {
+            let envelope: types::callformat::ResultEnvelopeX25519XChaCha20Poly1305 =
+                cbor::from_value(envelope_value).map_err(bad_envelope)?;
+            let data = xchacha20poly1305::box_open(
+                &envelope.nonce,
+                envelope.data,
+                additional_data(&envelope.header),
+                &runtime_pk.0,
+                &client_keypair.1,
+            );
+            (data, envelope.header)
+        }
+    };
+    let data = data.map_err(Error::InvalidCallFormat)?;
+    let call_result: CallResult =
+        cbor::from_slice(&data).map_err(|_| Error::InvalidCallFormat(anyhow!("malformed call")))?;
+
+    // Make sure the plaintext header matches the actual result.
+    if let Some(header) = header {
+        if header != types::callformat::ResultHeader::new(&call_result) {
+            return Err(Error::InvalidCallFormat(anyhow!("result header mismatch")));
         }
     }
+
+    Ok(call_result
+        .into_call_result()
+        .expect("CallResult was Unknown"))
+}
+
+/// Decode the arguments of a query that requires caller authentication.
+///
+/// On confidential runtimes the arguments must be a `SignedQueryEnvelope` for the given method.
+/// On non-confidential runtimes signed queries are accepted but not required. In case the
+/// signature is valid, the signer's address is made available via `query_caller`.
+pub fn decode_authenticated_query<C: Context>(
+    ctx: &mut C,
+    method: &str,
+    args: cbor::Value,
+) -> Result<cbor::Value, Error> {
+    let envelope: types::callformat::SignedQueryEnvelope = match cbor::from_value(args.clone()) {
+        Ok(envelope) => envelope,
+        Err(_) if !ctx.is_confidential() => return Ok(args),
+        Err(_) => return Err(Error::InvalidSignedQuery("missing signed query envelope")),
+    };
+
+    let body: types::callformat::SignedQueryBody = cbor::from_slice(&envelope.body)
+        .map_err(|_| Error::InvalidSignedQuery("malformed body"))?;
+    if body.method != method {
+        return Err(Error::InvalidSignedQuery("method mismatch"));
+    }
+
+    // First verify the signature since it's cheap compared to accessing state.
+    let signature_context =
+        get_chain_context_for(types::callformat::SIGNED_QUERY_SIGNATURE_CONTEXT_BASE);
+    body.auth
+        .signer
+        .public_key()
+        .verify(&signature_context, &envelope.body, &envelope.signature)
+        .map_err(|_| Error::InvalidSignedQuery("signature verification failed"))?;
+
+    // Next, verify the expiration and the nonce.
+    let round = ctx.time().round();
+    if body.auth.expiration < round {
+        return Err(Error::InvalidSignedQuery("expired"));
+    }
+    if body.auth.expiration > round.saturating_add(MAX_SIGNED_QUERY_VALIDITY) {
+        return Err(Error::InvalidSignedQuery(
+            "expiration too far in the future",
+        ));
+    }
+    let caller = Address::from_sigspec(&body.auth.signer);
+    let nonce = crate::modules::accounts::Module::get_nonce(caller)
+        .map_err(|_| Error::InvalidSignedQuery("failed to fetch nonce"))?;
+    if body.auth.nonce < nonce {
+        return Err(Error::InvalidSignedQuery("stale nonce"));
+    }
+
+    ctx.value::<Address>(CONTEXT_KEY_QUERY_CALLER).set(caller);
+
+    Ok(body.args)
+}
+
+/// Authenticated caller of the current query.
+///
+/// Only available within handlers of queries tagged as `authenticated` when the query was signed.
+pub fn query_caller<C: Context>(ctx: &mut C) -> Option<Address> {
+    ctx.value::<Address>(CONTEXT_KEY_QUERY_CALLER)
+        .get()
+        .copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Mode, core::common::crypto::mrae::deoxysii, testing::mock};
+
+    #[test]
+    fn test_encrypted_call_formats() {
+        let mut mock = mock::Mock::default();
+        let ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::ExecuteTx, true);
+        let client_keypair = deoxysii::generate_key_pair();
+
+        for &format in ENCRYPTED_CALL_FORMATS {
+            let call = Call {
+                format,
+                method: "test.Method".into(),
+                body: cbor::Value::from(b"hello".to_vec()),
+                ..Default::default()
+            };
+            let encoded = encode_call(&ctx, call, &client_keypair).unwrap();
+            assert!(encoded.method.is_empty(), "method should be hidden");
+
+            let (decoded, metadata) = decode_call(&ctx, encoded.clone(), 0)
+                .unwrap()
+                .expect("call should be decoded");
+            assert_eq!(decoded.method, "test.Method");
+            assert_eq!(decoded.body, cbor::Value::from(b"hello".to_vec()));
+
+            let result = encode_result(
+                &ctx,
+                module::CallResult::Ok(cbor::Value::from(42u64)),
+                metadata,
+            );
+            assert!(matches!(result, CallResult::Unknown(_)));
+            let result = decode_result(&ctx, format, result, &client_keypair).unwrap();
+            assert!(matches!(result, module::CallResult::Ok(v) if v == cbor::Value::from(42u64)));
+
+            // Envelopes must not be accepted under a different encrypted call format.
+            for &other in ENCRYPTED_CALL_FORMATS.iter().filter(|&&f| f != format) {
+                let mismatched = Call {
+                    format: other,
+                    ..encoded.clone()
+                };
+                assert!(decode_call(&ctx, mismatched, 0).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_xchacha20poly1305_result_nonces() {
+        let mut mock = mock::Mock::default();
+        let ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::ExecuteTx, true);
+        let client_keypair = deoxysii::generate_key_pair();
+
+        let result_nonce = |method: &str| {
+            let call = Call {
+                format: CallFormat::EncryptedX25519XChaCha20Poly1305,
+                method: method.into(),
+                ..Default::default()
+            };
+            let encoded = encode_call(&ctx, call, &client_keypair).unwrap();
+            let (_, metadata) = decode_call(&ctx, encoded, 0).unwrap().unwrap();
+            let result = encode_result(
+                &ctx,
+                module::CallResult::Ok(cbor::Value::from(42u64)),
+                metadata,
+            );
+            let envelope: types::callformat::ResultEnvelopeX25519XChaCha20Poly1305 = match result {
+                CallResult::Unknown(envelope) => cbor::from_value(envelope).unwrap(),
+                _ => panic!("results should be encrypted"),
+            };
+            envelope.nonce
+        };
+
+        // Calls by the same client at the same round and index must not reuse result nonces.
+        assert_ne!(result_nonce("test.Method"), result_nonce("test.Method"));
+    }
+
+    #[test]
+    fn test_result_header() {
+        let mut mock = mock::Mock::default();
+        let ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::ExecuteTx, true);
+        let client_keypair = deoxysii::generate_key_pair();
+        let call = Call {
+            format: CallFormat::EncryptedX25519DeoxysII,
+            method: "test.Method".into(),
+            ..Default::default()
+        };
+        let encoded = encode_call(&ctx, call, &client_keypair).unwrap();
+        let encrypt_failure = || {
+            let (_, metadata) = decode_call(&ctx, encoded.clone(), 0).unwrap().unwrap();
+            let result = encode_result(
+                &ctx,
+                module::CallResult::Failed {
+                    module: "test".to_owned(),
+                    code: 7,
+                    message: "secret reason".to_owned(),
+                },
+                metadata,
+            );
+            match result {
+                CallResult::Unknown(envelope) => envelope,
+                _ => panic!("failures should be encrypted"),
+            }
+        };
+
+        // The outcome should be visible without decrypting the result.
+        let envelope: types::callformat::ResultEnvelopeX25519DeoxysII =
+            cbor::from_value(encrypt_failure()).unwrap();
+        assert_eq!(
+            envelope.header,
+            Some(types::callformat::ResultHeader {
+                success: false,
+                module: "test".to_owned(),
+                code: 7,
+            })
+        );
+        let result = decode_result(
+            &ctx,
+            CallFormat::EncryptedX25519DeoxysII,
+            CallResult::Unknown(cbor::to_value(envelope.clone())),
+            &client_keypair,
+        )
+        .unwrap();
+        assert!(
+            matches!(result, module::CallResult::Failed { message, .. } if message == "secret reason")
+        );
+
+        // Tampering with the header should make decryption fail.
+        let tampered = types::callformat::ResultEnvelopeX25519DeoxysII {
+            header: Some(types::callformat::ResultHeader {
+                success: true,
+                ..Default::default()
+            }),
+            ..envelope
+        };
+        decode_result(
+            &ctx,
+            CallFormat::EncryptedX25519DeoxysII,
+            CallResult::Unknown(cbor::to_value(tampered)),
+            &client_keypair,
+        )
+        .expect_err("tampered header should be rejected");
+    }
+
+    #[test]
+    fn test_signed_query() {
+        use crate::{
+            crypto::signature::{MemorySigner, PublicKey, SignatureType},
+            modules::accounts::{Module as Accounts, API as _},
+            types::{address::SignatureAddressSpec, callformat::*},
+        };
+
+        let mut mock = mock::Mock::default();
+        mock.runtime_header.round = 10;
+        let mut ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::CheckTx, true);
+
+        let signer = MemorySigner::new_test(SignatureType::Ed25519_Oasis, "signed query");
+        let sigspec = match signer.public_key() {
+            PublicKey::Ed25519(pk) => SignatureAddressSpec::Ed25519(pk),
+            _ => unreachable!(),
+        };
+        let caller = Address::from_sigspec(&sigspec);
+        Accounts::set_nonce(caller, 5);
+
+        let sign = |method: &str, nonce: u64, expiration: u64| {
+            let body = cbor::to_vec(SignedQueryBody {
+                method: method.to_owned(),
+                args: cbor::Value::from(42u64),
+                auth: QueryAuthInfo {
+                    signer: sigspec.clone(),
+                    nonce,
+                    expiration,
+                },
+            });
+            let signature = signer
+                .sign(
+                    &get_chain_context_for(SIGNED_QUERY_SIGNATURE_CONTEXT_BASE),
+                    &body,
+                )
+                .unwrap();
+            cbor::to_value(SignedQueryEnvelope { body, signature })
+        };
+
+        let args = decode_authenticated_query(&mut ctx, "test.Query", sign("test.Query", 5, 20))
+            .expect("signed query should be accepted");
+        assert_eq!(args, cbor::Value::from(42u64));
+        assert_eq!(query_caller(&mut ctx), Some(caller));
+
+        for (args, reason) in [
+            (cbor::Value::from(42u64), "missing signed query envelope"),
+            (sign("test.Other", 5, 20), "method mismatch"),
+            (sign("test.Query", 4, 20), "stale nonce"),
+            (sign("test.Query", 5, 9), "expired"),
+            (
+                sign("test.Query", 5, 1000),
+                "expiration too far in the future",
+            ),
+        ] {
+            assert!(matches!(
+                decode_authenticated_query(&mut ctx, "test.Query", args),
+                Err(Error::InvalidSignedQuery(r)) if r == reason
+            ));
+        }
+
+        // Non-confidential runtimes also accept unsigned queries.
+        let mut ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::CheckTx, false);
+        let args =
+            decode_authenticated_query(&mut ctx, "test.Query", cbor::Value::from(42u64)).unwrap();
+        assert_eq!(args, cbor::Value::from(42u64));
+        assert_eq!(query_caller(&mut ctx), None);
+    }
 }
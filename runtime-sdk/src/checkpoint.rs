@@ -0,0 +1,230 @@
+//! Transaction checkpointing for interactive debugging.
+//!
+//! When a debugger is installed, the dispatcher pauses at every module call boundary (before and
+//! after dispatching each call, including subcalls), hands the debugger a checkpoint containing
+//! the state changes that are pending in the current storage transaction and resumes once the
+//! debugger returns.
+//!
+//! This module is only available with the `debug-checkpoints` feature. It is intended for local
+//! single-node debugging and must never be enabled on nodes participating in consensus.
+use std::{
+    fmt::{self, Write as _},
+    sync::{mpsc, Arc, Mutex, RwLock},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{context::Mode, storage::CurrentStore};
+
+/// Module call boundary at which execution was paused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// Before the call is dispatched to the handling module.
+    BeforeCall,
+    /// After the call has been handled and all after call hooks have run.
+    AfterCall {
+        /// Whether the call was successful.
+        success: bool,
+    },
+}
+
+/// A paused point in transaction execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Execution mode.
+    pub mode: Mode,
+    /// Index of the transaction within the batch.
+    pub tx_index: usize,
+    /// Called method.
+    pub method: String,
+    /// Boundary at which execution was paused.
+    pub boundary: Boundary,
+    /// State changes pending in the current storage transaction, ordered by key. Removals are
+    /// represented by a `None` value.
+    pub delta: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl Checkpoint {
+    /// Render the pending state delta in a human-readable form, one key per line.
+    pub fn dump_delta(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in &self.delta {
+            let _ = match value {
+                Some(value) => writeln!(out, "+ {} = {}", hex::encode(key), hex::encode(value)),
+                None => writeln!(out, "- {}", hex::encode(key)),
+            };
+        }
+        out
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let boundary = match self.boundary {
+            Boundary::BeforeCall => "before",
+            Boundary::AfterCall { success: true } => "after (ok)",
+            Boundary::AfterCall { success: false } => "after (failed)",
+        };
+        writeln!(
+            f,
+            "[{}] tx {} {} {}: {} pending update(s)",
+            self.mode,
+            self.tx_index,
+            boundary,
+            self.method,
+            self.delta.len()
+        )?;
+        write!(f, "{}", self.dump_delta())
+    }
+}
+
+/// A debugger that receives checkpoints.
+pub trait Debugger: Send + Sync {
+    /// Handle a checkpoint. Execution is paused until this method returns.
+    fn checkpoint(&self, checkpoint: Checkpoint);
+}
+
+static DEBUGGER: Lazy<RwLock<Option<Arc<dyn Debugger>>>> = Lazy::new(Default::default);
+
+/// Install the node-wide debugger, replacing any previously installed debugger. Passing `None`
+/// disables checkpointing.
+pub fn set_debugger(debugger: Option<Arc<dyn Debugger>>) {
+    *DEBUGGER.write().unwrap() = debugger;
+}
+
+/// Pause execution at the given boundary in case a debugger is installed.
+pub(crate) fn pause(mode: Mode, tx_index: usize, method: &str, boundary: Boundary) {
+    // Do not hold the lock while paused so the debugger can be replaced (e.g. detached).
+    let debugger = match DEBUGGER.read().unwrap().as_ref() {
+        Some(debugger) => debugger.clone(),
+        None => return,
+    };
+
+    debugger.checkpoint(Checkpoint {
+        mode,
+        tx_index,
+        method: method.to_owned(),
+        boundary,
+        delta: CurrentStore::pending_updates(),
+    });
+}
+
+/// Debugger that hands checkpoints over to a separate `Session` (e.g. running in another thread)
+/// and keeps execution paused until the session resumes it.
+pub struct SteppingDebugger {
+    checkpoints: Mutex<mpsc::Sender<(Checkpoint, mpsc::Sender<()>)>>,
+}
+
+impl SteppingDebugger {
+    /// Create a new stepping debugger and its session.
+    pub fn new() -> (Self, Session) {
+        let (tx, rx) = mpsc::channel();
+        (
+            Self {
+                checkpoints: Mutex::new(tx),
+            },
+            Session { checkpoints: rx },
+        )
+    }
+}
+
+impl Debugger for SteppingDebugger {
+    fn checkpoint(&self, checkpoint: Checkpoint) {
+        let (resume_tx, resume_rx) = mpsc::channel();
+        if self
+            .checkpoints
+            .lock()
+            .unwrap()
+            .send((checkpoint, resume_tx))
+            .is_err()
+        {
+            // Session has been closed, do not pause.
+            return;
+        }
+        // Wait until resumed (or until the paused checkpoint is dropped).
+        let _ = resume_rx.recv();
+    }
+}
+
+/// A debugging session that receives checkpoints from a `SteppingDebugger`.
+pub struct Session {
+    checkpoints: mpsc::Receiver<(Checkpoint, mpsc::Sender<()>)>,
+}
+
+impl Session {
+    /// Wait for execution to pause at the next checkpoint. Returns `None` in case the debugger has
+    /// been dropped.
+    pub fn next_checkpoint(&self) -> Option<Paused> {
+        self.checkpoints
+            .recv()
+            .ok()
+            .map(|(checkpoint, resume)| Paused { checkpoint, resume })
+    }
+}
+
+/// Execution paused at a checkpoint. Dropping it resumes execution.
+pub struct Paused {
+    /// Checkpoint at which execution is paused.
+    pub checkpoint: Checkpoint,
+    resume: mpsc::Sender<()>,
+}
+
+impl Paused {
+    /// Resume execution.
+    pub fn resume(self) {
+        let _ = self.resume.send(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::{storage::current::TransactionResult, testing::mock};
+
+    #[test]
+    fn test_stepping_debugger() {
+        let (debugger, session) = SteppingDebugger::new();
+        let debugger = Arc::new(debugger);
+
+        let handle = thread::spawn(move || {
+            let _mock = mock::Mock::default(); // Initializes the current store.
+
+            CurrentStore::with_transaction(|| {
+                let checkpoint = |boundary| Checkpoint {
+                    mode: Mode::ExecuteTx,
+                    tx_index: 0,
+                    method: "test.Method".to_owned(),
+                    boundary,
+                    delta: CurrentStore::pending_updates(),
+                };
+
+                debugger.checkpoint(checkpoint(Boundary::BeforeCall));
+                CurrentStore::with(|store| store.insert(b"foo", b"bar"));
+                debugger.checkpoint(checkpoint(Boundary::AfterCall { success: true }));
+
+                TransactionResult::Rollback(())
+            });
+        });
+
+        let paused = session.next_checkpoint().expect("execution should pause");
+        assert_eq!(paused.checkpoint.boundary, Boundary::BeforeCall);
+        assert!(paused.checkpoint.delta.is_empty());
+        paused.resume();
+
+        let paused = session.next_checkpoint().expect("execution should pause");
+        assert_eq!(
+            paused.checkpoint.boundary,
+            Boundary::AfterCall { success: true }
+        );
+        assert_eq!(paused.checkpoint.dump_delta(), "+ 666f6f = 626172\n");
+        paused.resume();
+
+        handle.join().unwrap();
+        assert!(
+            session.next_checkpoint().is_none(),
+            "debugger should be dropped"
+        );
+    }
+}
@@ -1,10 +1,12 @@
 //! Execution context.
 use std::{
     any::Any,
+    cell::Cell,
     collections::btree_map::{BTreeMap, Entry},
     fmt,
     marker::PhantomData,
     ops::{Deref, DerefMut},
+    rc::Rc,
 };
 
 use slog::{self, o};
@@ -18,12 +20,13 @@ use oasis_core_runtime::{
 
 use crate::{
     crypto::random::{LeafRng, RootRng},
-    event::{Event, EventTag, EventTags},
+    event::{Event, EventAttribution, EventTag, EventTags},
     history,
     keymanager::KeyManager,
     module::MethodHandler as _,
-    modules::core::Error,
+    modules::{self, core::Error},
     runtime,
+    time::{BlockTime, TimeSource},
     types::{address::Address, message::MessageEventHookInvocation, transaction},
 };
 
@@ -243,8 +246,13 @@ pub trait Context {
     /// Historical state.
     fn history(&self) -> &dyn history::HistoryHost;
 
+    /// Deterministic time source.
+    fn time(&self) -> &dyn TimeSource;
+
     /// Current epoch.
-    fn epoch(&self) -> consensus::beacon::EpochTime;
+    fn epoch(&self) -> consensus::beacon::EpochTime {
+        self.time().epoch()
+    }
 
     /// Emits an event by transforming it into a tag and emitting a tag.
     fn emit_event<E: Event>(&mut self, event: E);
@@ -349,8 +357,8 @@ impl<'a, 'b, C: Context> Context for std::cell::RefMut<'a, &'b mut C> {
         self.deref().history()
     }
 
-    fn epoch(&self) -> consensus::beacon::EpochTime {
-        self.deref().epoch()
+    fn time(&self) -> &dyn TimeSource {
+        self.deref().time()
     }
 
     fn emit_event<E: Event>(&mut self, event: E) {
@@ -542,6 +550,45 @@ impl<'a, 'b, C: TxContext> TxContext for std::cell::RefMut<'a, &'b mut C> {
     }
 }
 
+/// Position of a call within a transaction, used for event attribution.
+#[derive(Clone, Debug)]
+struct CallPosition {
+    /// Index of the transaction in the block.
+    tx_index: Option<u32>,
+    /// Index of the call within the transaction.
+    call_index: u32,
+    /// Depth of the call within the transaction.
+    call_depth: u16,
+    /// Name of the module whose method is being called.
+    module: String,
+    /// Name of the module that performed this call in case it is a nested call.
+    caller_module: Option<String>,
+    /// Number of nested calls performed so far, shared by all calls within the transaction.
+    calls: Rc<Cell<u32>>,
+}
+
+impl CallPosition {
+    fn attribution(&self) -> EventAttribution {
+        EventAttribution {
+            tx_index: self.tx_index,
+            call_index: self.call_index,
+            call_depth: self.call_depth,
+            caller_module: self.caller_module.clone(),
+        }
+    }
+}
+
+/// Attribute the given event tag in case event attribution is enabled for the runtime.
+fn attribute_etag<R: runtime::Runtime>(
+    etag: EventTag,
+    position: Option<&CallPosition>,
+) -> EventTag {
+    if !<<R::Core as modules::core::API>::Config as modules::core::Config>::EMIT_EVENT_ATTRIBUTION {
+        return etag;
+    }
+    etag.attributed(position.map(CallPosition::attribution).unwrap_or_default())
+}
+
 /// Dispatch context for the whole batch.
 pub struct RuntimeBatchContext<'a, R: runtime::Runtime> {
     mode: Mode,
@@ -553,11 +600,13 @@ pub struct RuntimeBatchContext<'a, R: runtime::Runtime> {
     // TODO: linked consensus layer block
     consensus_state: &'a consensus::state::ConsensusState,
     history: &'a dyn history::HistoryHost,
-    epoch: consensus::beacon::EpochTime,
+    time: Rc<dyn TimeSource>,
     logger: slog::Logger,
 
     /// Whether this context is part of an existing transaction (e.g. a subcall).
     internal: bool,
+    /// Position of the parent call in case this context is part of an existing transaction.
+    call_position: Option<CallPosition>,
 
     /// Block emitted event tags. Events are aggregated by tag key, the value
     /// is a list of all emitted event values.
@@ -599,11 +648,12 @@ impl<'a, R: runtime::Runtime> RuntimeBatchContext<'a, R> {
             runtime_round_results,
             consensus_state,
             history,
-            epoch,
+            time: Rc::new(BlockTime::new(runtime_header, epoch)),
             key_manager,
             logger: get_logger("runtime-sdk")
                 .new(o!("ctx" => "dispatch", "mode" => Into::<&'static str>::into(&mode))),
             internal: false,
+            call_position: None,
             block_etags: EventTags::new(),
             max_messages,
             messages: Vec::new(),
@@ -613,6 +663,14 @@ impl<'a, R: runtime::Runtime> RuntimeBatchContext<'a, R> {
         }
     }
 
+    /// Use the given time source instead of the one derived from the runtime block header.
+    ///
+    /// This is mainly useful in tests which need to control the passage of time.
+    pub fn with_time_source(mut self, time: Rc<dyn TimeSource>) -> Self {
+        self.time = time;
+        self
+    }
+
     /// Executes a function in a child context in pre-schedule mode.
     ///
     /// The context collects its own messages and starts with an empty set of context values.
@@ -641,9 +699,10 @@ impl<'a, R: runtime::Runtime> RuntimeBatchContext<'a, R> {
             runtime_round_results: self.runtime_round_results,
             consensus_state: self.consensus_state,
             history: self.history,
-            epoch: self.epoch,
+            time: self.time.clone(),
             logger: self.logger.clone(),
             internal: self.internal,
+            call_position: self.call_position.clone(),
             block_etags: EventTags::new(),
             max_messages: self.remaining_messages(),
             messages: Vec::new(),
@@ -690,17 +749,16 @@ impl<'a, R: runtime::Runtime> Context for RuntimeBatchContext<'a, R> {
         self.history
     }
 
-    fn epoch(&self) -> consensus::beacon::EpochTime {
-        self.epoch
+    fn time(&self) -> &dyn TimeSource {
+        self.time.as_ref()
     }
 
     fn emit_event<E: Event>(&mut self, event: E) {
-        let etag = event.into_event_tag();
-        let tag = self.block_etags.entry(etag.key).or_default();
-        tag.push(etag.value);
+        self.emit_etag(event.into_event_tag());
     }
 
     fn emit_etag(&mut self, etag: EventTag) {
+        let etag = attribute_etag::<R>(etag, self.call_position.as_ref());
         let tag = self.block_etags.entry(etag.key).or_default();
         tag.push(etag.value);
     }
@@ -755,11 +813,12 @@ impl<'a, R: runtime::Runtime> Context for RuntimeBatchContext<'a, R> {
             runtime_round_results: self.runtime_round_results,
             consensus_state: self.consensus_state,
             history: self.history,
-            epoch: self.epoch,
+            time: self.time.clone(),
             logger: self
                 .logger
                 .new(o!("ctx" => "dispatch", "mode" => Into::<&'static str>::into(&mode))),
             internal: self.internal,
+            call_position: self.call_position.clone(),
             block_etags: EventTags::new(),
             max_messages: match mode {
                 Mode::SimulateTx => self.max_messages,
@@ -786,6 +845,40 @@ impl<'a, R: runtime::Runtime> BatchContext for RuntimeBatchContext<'a, R> {
         // Update RNG state to include entering this transaction context.
         self.rng.append_tx(tm.tx_hash);
 
+        let module = tm
+            .tx
+            .call
+            .method
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let call_position = match &self.call_position {
+            // A nested call within an existing transaction.
+            Some(parent) => {
+                let call_index = parent.calls.get().saturating_add(1);
+                parent.calls.set(call_index);
+
+                CallPosition {
+                    tx_index: parent.tx_index,
+                    call_index,
+                    call_depth: parent.call_depth.saturating_add(1),
+                    module,
+                    caller_module: Some(parent.module.clone()),
+                    calls: parent.calls.clone(),
+                }
+            }
+            // A top-level call.
+            None => CallPosition {
+                tx_index: tm.tx_index.try_into().ok(),
+                call_index: 0,
+                call_depth: 0,
+                module,
+                caller_module: None,
+                calls: Rc::new(Cell::new(0)),
+            },
+        };
+
         let tx_ctx = RuntimeTxContext {
             mode: self.mode,
             host_info: self.host_info,
@@ -794,7 +887,7 @@ impl<'a, R: runtime::Runtime> BatchContext for RuntimeBatchContext<'a, R> {
             runtime_round_results: self.runtime_round_results,
             consensus_state: self.consensus_state,
             history: self.history,
-            epoch: self.epoch,
+            time: self.time.clone(),
             logger: self
                 .logger
                 .new(o!("ctx" => "transaction", "mode" => Into::<&'static str>::into(&self.mode))),
@@ -804,6 +897,7 @@ impl<'a, R: runtime::Runtime> BatchContext for RuntimeBatchContext<'a, R> {
             tx_call_format: tm.tx.call.format,
             read_only: tm.tx.call.read_only,
             internal: self.internal,
+            call_position,
             etags: BTreeMap::new(),
             etags_unconditional: BTreeMap::new(),
             max_messages: self.remaining_messages(),
@@ -840,7 +934,7 @@ pub struct RuntimeTxContext<'round, 'store, R: runtime::Runtime> {
     runtime_round_results: &'round roothash::RoundResults,
     consensus_state: &'round consensus::state::ConsensusState,
     history: &'round dyn history::HistoryHost,
-    epoch: consensus::beacon::EpochTime,
+    time: Rc<dyn TimeSource>,
     // TODO: linked consensus layer block
     logger: slog::Logger,
 
@@ -856,6 +950,8 @@ pub struct RuntimeTxContext<'round, 'store, R: runtime::Runtime> {
     read_only: bool,
     /// Whether this context is part of an existing transaction (e.g. a subcall).
     internal: bool,
+    /// Position of the call within the transaction.
+    call_position: CallPosition,
 
     /// Emitted event tags. Events are aggregated by tag key, the value
     /// is a list of all emitted event values.
@@ -915,17 +1011,16 @@ impl<'round, 'store, R: runtime::Runtime> Context for RuntimeTxContext<'round, '
         self.history
     }
 
-    fn epoch(&self) -> consensus::beacon::EpochTime {
-        self.epoch
+    fn time(&self) -> &dyn TimeSource {
+        self.time.as_ref()
     }
 
     fn emit_event<E: Event>(&mut self, event: E) {
-        let etag = event.into_event_tag();
-        let tag = self.etags.entry(etag.key).or_default();
-        tag.push(etag.value);
+        self.emit_etag(event.into_event_tag());
     }
 
     fn emit_etag(&mut self, etag: EventTag) {
+        let etag = attribute_etag::<R>(etag, Some(&self.call_position));
         let tag = self.etags.entry(etag.key).or_default();
         tag.push(etag.value);
     }
@@ -986,11 +1081,12 @@ impl<'round, 'store, R: runtime::Runtime> Context for RuntimeTxContext<'round, '
             runtime_round_results: self.runtime_round_results,
             consensus_state: self.consensus_state,
             history: self.history,
-            epoch: self.epoch,
+            time: self.time.clone(),
             logger: self
                 .logger
                 .new(o!("ctx" => "dispatch", "mode" => Into::<&'static str>::into(&mode))),
             internal: self.internal,
+            call_position: Some(self.call_position.clone()),
             block_etags: EventTags::new(),
             max_messages: match mode {
                 Mode::SimulateTx => self.max_messages,
@@ -1046,20 +1142,28 @@ impl<R: runtime::Runtime> TxContext for RuntimeTxContext<'_, '_, R> {
     fn emit_message(
         &mut self,
         msg: roothash::Message,
-        hook: MessageEventHookInvocation,
+        mut hook: MessageEventHookInvocation,
     ) -> Result<(), Error> {
         // Check against maximum number of messages that can be emitted per round.
         if self.messages.len() >= self.max_messages as usize {
             return Err(Error::OutOfMessageSlots);
         }
 
+        // Record which nested call emitted the message so its result can be attributed.
+        if <<R::Core as modules::core::API>::Config as modules::core::Config>::EMIT_EVENT_ATTRIBUTION
+            && self.call_position.call_depth > 0
+            && hook.origin.is_none()
+        {
+            hook.origin = Some(self.call_position.attribution());
+        }
+
         self.messages.push((msg, hook));
 
         Ok(())
     }
 
     fn emit_unconditional_event<E: Event>(&mut self, event: E) {
-        let etag = event.into_event_tag();
+        let etag = attribute_etag::<R>(event.into_event_tag(), Some(&self.call_position));
         let tag = self.etags_unconditional.entry(etag.key).or_default();
         tag.push(etag.value);
     }
@@ -1163,10 +1267,16 @@ impl<'a, V: Any + Default> ContextValue<'a, V> {
 #[cfg(test)]
 #[allow(clippy::many_single_char_names)]
 mod test {
-    use oasis_core_runtime::{common::versioned::Versioned, consensus::staking};
+    use oasis_core_runtime::{
+        common::{version::Version, versioned::Versioned},
+        consensus::staking,
+    };
 
     use super::*;
-    use crate::testing::{mock, mock::Mock};
+    use crate::{
+        module,
+        testing::{mock, mock::Mock},
+    };
 
     #[test]
     fn test_value() {
@@ -1481,4 +1591,121 @@ mod test {
             },
         );
     }
+
+    struct AttributionConfig;
+
+    impl modules::core::Config for AttributionConfig {
+        const EMIT_EVENT_ATTRIBUTION: bool = true;
+    }
+
+    struct AttributionRuntime;
+
+    impl runtime::Runtime for AttributionRuntime {
+        const VERSION: Version = Version::new(0, 0, 0);
+
+        type Core = modules::core::Module<AttributionConfig>;
+
+        type Modules = modules::core::Module<AttributionConfig>;
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_attribution() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<AttributionRuntime>(Mode::ExecuteTx, false);
+
+        let decode = |state: &State| -> Vec<crate::event::AttributedEvent> {
+            state
+                .events
+                .values()
+                .flatten()
+                .map(|value| cbor::from_value(value.clone()).unwrap())
+                .collect()
+        };
+
+        let mut origins = Vec::new();
+        let state = ctx.with_tx(
+            TransactionWithMeta {
+                tx: mock::transaction(),
+                tx_size: 0,
+                tx_index: 3,
+                tx_hash: Default::default(),
+            },
+            |mut tx_ctx, _call| {
+                tx_ctx.emit_event(modules::core::Event::GasUsed { amount: 1 });
+
+                for _ in 0..2 {
+                    let state = tx_ctx.with_child(Mode::ExecuteTx, |mut child_ctx| {
+                        let mut tx = mock::transaction();
+                        tx.call.method = "sub.Call".to_owned();
+
+                        child_ctx.with_tx(
+                            TransactionWithMeta::internal(tx),
+                            |mut sub_ctx, _call| {
+                                sub_ctx.emit_event(modules::core::Event::GasUsed { amount: 2 });
+                                sub_ctx
+                                    .emit_message(
+                                        roothash::Message::Staking(Versioned::new(
+                                            0,
+                                            roothash::StakingMessage::Transfer(
+                                                staking::Transfer::default(),
+                                            ),
+                                        )),
+                                        MessageEventHookInvocation::new("test".to_string(), ""),
+                                    )
+                                    .expect("emitting a message should work");
+                                sub_ctx.commit()
+                            },
+                        )
+                    });
+                    tx_ctx.emit_etags(state.events);
+                    origins.extend(state.messages.into_iter().map(|(_, hook)| hook.origin));
+                }
+
+                tx_ctx.commit()
+            },
+        );
+
+        let attributions: Vec<_> = decode(&state)
+            .into_iter()
+            .map(|ev| ev.attribution)
+            .collect();
+        let nested = |call_index| EventAttribution {
+            tx_index: Some(3),
+            call_index,
+            call_depth: 1,
+            caller_module: Some("mock".to_string()),
+        };
+        assert_eq!(
+            attributions,
+            vec![
+                EventAttribution {
+                    tx_index: Some(3),
+                    call_index: 0,
+                    call_depth: 0,
+                    caller_module: None,
+                },
+                nested(1),
+                nested(2),
+            ],
+            "events should be attributed to the emitting call"
+        );
+        assert_eq!(
+            origins,
+            vec![Some(nested(1)), Some(nested(2))],
+            "messages should be attributed to the emitting call"
+        );
+
+        // Events emitted outside of transactions have no transaction index.
+        ctx.emit_event(modules::core::Event::GasUsed { amount: 3 });
+        let state = ctx.commit();
+        assert_eq!(
+            decode(&state)[0].attribution,
+            EventAttribution::default(),
+            "block events should not be attributed to a transaction"
+        );
+    }
 }
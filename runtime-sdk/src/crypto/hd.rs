@@ -0,0 +1,295 @@
+//! Hierarchical deterministic key derivation.
+//!
+//! Implements SLIP-10 for Ed25519 (as used by the Oasis ADR-8 path scheme) and BIP-32 for
+//! Secp256k1 so that keys can be derived consistently with hardware and software wallets.
+use hmac::{Hmac, Mac as _};
+use k256::elliptic_curve::{sec1::ToEncodedPoint as _, Field as _, PrimeField as _};
+use sha2::Sha512;
+use thiserror::Error;
+
+use crate::crypto::signature::{MemorySigner, SignatureType};
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Offset of hardened child indices.
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// BIP-44 purpose.
+pub const BIP44_PURPOSE: u32 = 44;
+/// SLIP-44 coin type registered for Oasis.
+pub const ADR8_COIN_TYPE: u32 = 474;
+/// SLIP-44 coin type registered for Ethereum.
+pub const ETH_COIN_TYPE: u32 = 60;
+
+const ED25519_CURVE: &[u8] = b"ed25519 seed";
+const SECP256K1_CURVE: &[u8] = b"Bitcoin seed";
+
+/// Error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("malformed derivation path")]
+    MalformedPath,
+    #[error("non-hardened derivation not supported for this curve")]
+    NonHardenedDerivation,
+    #[error("invalid derived key")]
+    InvalidKey,
+}
+
+/// A derivation path consisting of child indices. Hardened indices include the `HARDENED`
+/// offset.
+pub type Path = Vec<u32>;
+
+/// Parse a derivation path in the conventional form (e.g. `m/44'/474'/0'`). Both `'` and `h` can
+/// be used to denote hardened indices.
+pub fn parse_path(path: &str) -> Result<Path, Error> {
+    let mut components = path.split('/');
+    if components.next() != Some("m") {
+        return Err(Error::MalformedPath);
+    }
+    components
+        .map(|component| {
+            let (index, hardened) = match component
+                .strip_suffix('\'')
+                .or_else(|| component.strip_suffix('h'))
+            {
+                Some(index) => (index, true),
+                None => (component, false),
+            };
+            let index: u32 = index.parse().map_err(|_| Error::MalformedPath)?;
+            if index >= HARDENED {
+                return Err(Error::MalformedPath);
+            }
+            Ok(if hardened { index | HARDENED } else { index })
+        })
+        .collect()
+}
+
+/// ADR-8 derivation path (`m/44'/474'/index'`) for the given account index.
+pub fn adr8_path(index: u32) -> Path {
+    vec![
+        BIP44_PURPOSE | HARDENED,
+        ADR8_COIN_TYPE | HARDENED,
+        index | HARDENED,
+    ]
+}
+
+/// Ethereum-compatible derivation path (`m/44'/60'/0'/0/index`) for the given account index.
+pub fn eth_path(index: u32) -> Path {
+    vec![
+        BIP44_PURPOSE | HARDENED,
+        ETH_COIN_TYPE | HARDENED,
+        HARDENED,
+        0,
+        index,
+    ]
+}
+
+/// An extended private key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedKey {
+    /// Private key.
+    pub key: [u8; 32],
+    /// Chain code.
+    pub chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    fn from_hmac(hmac_key: &[u8], parts: &[&[u8]]) -> Self {
+        let mut mac = HmacSha512::new_from_slice(hmac_key).expect("Hmac::new_from_slice");
+        for part in parts {
+            mac.update(part);
+        }
+        let out = mac.finalize().into_bytes();
+
+        let mut key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        key.copy_from_slice(&out[..32]);
+        chain_code.copy_from_slice(&out[32..]);
+        Self { key, chain_code }
+    }
+}
+
+/// Derive an Ed25519 extended key from the given seed along the given path using SLIP-10.
+///
+/// Ed25519 only supports hardened derivation.
+pub fn derive_ed25519(seed: &[u8], path: &[u32]) -> Result<ExtendedKey, Error> {
+    let mut xkey = ExtendedKey::from_hmac(ED25519_CURVE, &[seed]);
+    for index in path {
+        if index & HARDENED == 0 {
+            return Err(Error::NonHardenedDerivation);
+        }
+        xkey = ExtendedKey::from_hmac(
+            &xkey.chain_code,
+            &[&[0u8][..], &xkey.key, &index.to_be_bytes()],
+        );
+    }
+    Ok(xkey)
+}
+
+/// Derive a Secp256k1 extended key from the given seed along the given path using BIP-32.
+pub fn derive_secp256k1(seed: &[u8], path: &[u32]) -> Result<ExtendedKey, Error> {
+    let mut xkey = ExtendedKey::from_hmac(SECP256K1_CURVE, &[seed]);
+    let mut key = parse_secp256k1_scalar(&xkey.key)?;
+    for index in path {
+        let child = if index & HARDENED != 0 {
+            ExtendedKey::from_hmac(
+                &xkey.chain_code,
+                &[&[0u8][..], &xkey.key, &index.to_be_bytes()],
+            )
+        } else {
+            let public_key = k256::SecretKey::from_slice(&xkey.key)
+                .map_err(|_| Error::InvalidKey)?
+                .public_key()
+                .to_encoded_point(true);
+            ExtendedKey::from_hmac(
+                &xkey.chain_code,
+                &[public_key.as_bytes(), &index.to_be_bytes()],
+            )
+        };
+
+        // In the (astronomically unlikely) case of an invalid child key, BIP-32 says to proceed
+        // with the next index but as paths are fixed by wallets we report an error instead.
+        key += parse_secp256k1_scalar(&child.key)?;
+        if bool::from(key.is_zero()) {
+            return Err(Error::InvalidKey);
+        }
+        xkey = ExtendedKey {
+            key: key.to_repr().into(),
+            chain_code: child.chain_code,
+        };
+    }
+    Ok(xkey)
+}
+
+fn parse_secp256k1_scalar(bytes: &[u8; 32]) -> Result<k256::Scalar, Error> {
+    let scalar: k256::Scalar =
+        Option::from(k256::Scalar::from_repr((*bytes).into())).ok_or(Error::InvalidKey)?;
+    if bool::from(scalar.is_zero()) {
+        return Err(Error::InvalidKey);
+    }
+    Ok(scalar)
+}
+
+/// Derive a memory signer of the given signature type from the given seed along the given path.
+pub fn derive_signer(
+    sig_type: SignatureType,
+    seed: &[u8],
+    path: &[u32],
+) -> Result<MemorySigner, Error> {
+    let xkey = if sig_type.is_ed25519_variant() {
+        derive_ed25519(seed, path)?
+    } else if sig_type.is_secp256k1_variant() {
+        derive_secp256k1(seed, path)?
+    } else {
+        return Err(Error::InvalidKey);
+    };
+    MemorySigner::new_from_seed(sig_type, &xkey.key).map_err(|_| Error::InvalidKey)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn xkey(key: &str, chain_code: &str) -> ExtendedKey {
+        ExtendedKey {
+            key: hex::decode(key).unwrap().try_into().unwrap(),
+            chain_code: hex::decode(chain_code).unwrap().try_into().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(parse_path("m").unwrap(), Path::new());
+        assert_eq!(parse_path("m/44'/474'/0'").unwrap(), adr8_path(0));
+        assert_eq!(parse_path("m/44h/60h/0h/0/5").unwrap(), eth_path(5));
+
+        for path in ["", "44'/474'", "m/", "m/x'", "m/2147483648", "m/1''"] {
+            assert!(
+                parse_path(path).is_err(),
+                "path {path:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_slip10_ed25519_vectors() {
+        // Test vector 1 from SLIP-10.
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let vectors = [
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+            ),
+            (
+                "m/0'/1'",
+                "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+            ),
+        ];
+        for (path, key, chain_code) in vectors {
+            let path = parse_path(path).unwrap();
+            assert_eq!(
+                derive_ed25519(&seed, &path).unwrap(),
+                xkey(key, chain_code),
+                "path {path:?}"
+            );
+        }
+
+        assert!(matches!(
+            derive_ed25519(&seed, &parse_path("m/0'/1").unwrap()),
+            Err(Error::NonHardenedDerivation)
+        ));
+    }
+
+    #[test]
+    fn test_bip32_secp256k1_vectors() {
+        // Test vector 1 from BIP-32.
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let vectors = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            ),
+        ];
+        for (path, key, chain_code) in vectors {
+            let path = parse_path(path).unwrap();
+            assert_eq!(
+                derive_secp256k1(&seed, &path).unwrap(),
+                xkey(key, chain_code),
+                "path {path:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_derive_signer() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+
+        let signer = derive_signer(SignatureType::Ed25519_Oasis, &seed, &adr8_path(0)).unwrap();
+        let expected = derive_ed25519(&seed, &adr8_path(0)).unwrap();
+        assert_eq!(signer.to_bytes(), expected.key.to_vec());
+
+        let signer = derive_signer(SignatureType::Secp256k1_Oasis, &seed, &eth_path(0)).unwrap();
+        let expected = derive_secp256k1(&seed, &eth_path(0)).unwrap();
+        assert_eq!(signer.to_bytes(), expected.key.to_vec());
+
+        assert!(derive_signer(SignatureType::Sr25519, &seed, &adr8_path(0)).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 //! Cryptography.
 
+pub mod hd;
+pub mod mrae;
 pub mod multisig;
 pub mod random;
 pub mod signature;
+pub mod webauthn;
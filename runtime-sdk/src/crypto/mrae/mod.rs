@@ -0,0 +1,4 @@
+//! Misuse-resistant authenticated encryption box constructions.
+//!
+//! The Deoxys-II box is provided by Oasis Core; boxes in this module follow the same interface.
+pub mod xchacha20poly1305;
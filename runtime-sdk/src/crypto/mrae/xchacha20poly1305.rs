@@ -0,0 +1,101 @@
+//! X25519 box using XChaCha20-Poly1305 for symmetric encryption.
+//!
+//! Unlike Deoxys-II, XChaCha20-Poly1305 is not nonce-misuse resistant. The extended nonce is large
+//! enough for random nonces to be used safely.
+use anyhow::{anyhow, Result};
+use chacha20poly1305::{
+    aead::{Aead as _, KeyInit as _, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hmac::{Hmac, Mac as _};
+use sha2::Sha512_256;
+
+/// Size of the symmetric key in bytes.
+pub const KEY_SIZE: usize = 32;
+/// Size of the nonce in bytes.
+pub const NONCE_SIZE: usize = 24;
+/// Size of the authentication tag in bytes.
+pub const TAG_SIZE: usize = 16;
+
+/// Derive a symmetric key for use with the box primitives from the provided X25519 public and
+/// private keys.
+pub fn derive_symmetric_key(
+    public_key: &x25519_dalek::PublicKey,
+    private_key: &x25519_dalek::StaticSecret,
+) -> [u8; KEY_SIZE] {
+    let pmk = private_key.diffie_hellman(public_key);
+
+    let mut kdf = Hmac::<Sha512_256>::new_from_slice(b"MRAE_Box_XChaCha20-Poly1305")
+        .expect("Hmac::new_from_slice");
+    kdf.update(pmk.as_bytes());
+
+    let mut key = [0u8; KEY_SIZE];
+    key.copy_from_slice(&kdf.finalize().into_bytes());
+    key
+}
+
+/// Seal a message and additional data using the key derived from the given X25519 key pair.
+pub fn box_seal(
+    nonce: &[u8; NONCE_SIZE],
+    message: Vec<u8>,
+    additional_data: Vec<u8>,
+    peers_public_key: &x25519_dalek::PublicKey,
+    private_key: &x25519_dalek::StaticSecret,
+) -> Result<Vec<u8>> {
+    let key = derive_symmetric_key(peers_public_key, private_key);
+    XChaCha20Poly1305::new(&key.into())
+        .encrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: &message,
+                aad: &additional_data,
+            },
+        )
+        .map_err(|_| anyhow!("xchacha20poly1305: encryption failed"))
+}
+
+/// Open a sealed box using the key derived from the given X25519 key pair.
+pub fn box_open(
+    nonce: &[u8; NONCE_SIZE],
+    ciphertext: Vec<u8>,
+    additional_data: Vec<u8>,
+    peers_public_key: &x25519_dalek::PublicKey,
+    private_key: &x25519_dalek::StaticSecret,
+) -> Result<Vec<u8>> {
+    let key = derive_symmetric_key(peers_public_key, private_key);
+    XChaCha20Poly1305::new(&key.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &additional_data,
+            },
+        )
+        .map_err(|_| anyhow!("xchacha20poly1305: decryption failed"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_box_seal_open() {
+        let alice = x25519_dalek::StaticSecret::from([1u8; 32]);
+        let bob = x25519_dalek::StaticSecret::from([2u8; 32]);
+        let alice_pk = x25519_dalek::PublicKey::from(&alice);
+        let bob_pk = x25519_dalek::PublicKey::from(&bob);
+        let nonce = [1u8; NONCE_SIZE];
+
+        let sealed =
+            box_seal(&nonce, b"message".to_vec(), b"ad".to_vec(), &bob_pk, &alice).unwrap();
+        assert_eq!(sealed.len(), b"message".len() + TAG_SIZE);
+
+        let opened = box_open(&nonce, sealed.clone(), b"ad".to_vec(), &alice_pk, &bob).unwrap();
+        assert_eq!(opened, b"message");
+
+        box_open(&nonce, sealed.clone(), vec![], &alice_pk, &bob)
+            .expect_err("different additional data should fail");
+        box_open(&[2u8; NONCE_SIZE], sealed, b"ad".to_vec(), &alice_pk, &bob)
+            .expect_err("different nonce should fail");
+    }
+}
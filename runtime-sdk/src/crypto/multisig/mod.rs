@@ -35,6 +35,11 @@ pub type SignatureSetOwned = Vec<Option<Signature>>;
 /// A multisig configuration.
 /// A set of signers with total "weight" greater than or equal to a "threshold" can authenticate
 /// for the configuration.
+///
+/// A k-of-n configuration is the special case where all n signers have a weight of 1 and the
+/// threshold is k. Since the derived address (see `Address::from_multisig`) commits to the whole
+/// configuration, including all weights and the threshold, changing any of them results in a
+/// different address.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Config {
     /// The signers.
@@ -0,0 +1,294 @@
+//! BLS12-381 signatures.
+//!
+//! Uses the minimal public key size variant (public keys in G1, signatures in G2) of the message
+//! augmentation scheme where the signer's public key is prepended to every signed message. This
+//! prevents rogue key attacks and allows aggregation of signatures over arbitrary (including
+//! equal) messages without requiring proofs of possession.
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    multi_miller_loop, G1Affine, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use sha2::{Digest, Sha512, Sha512_256};
+
+use crate::crypto::signature::{Error, Signature};
+
+/// Domain separation tag for hashing messages to G2.
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+
+/// Domain separation context for deriving secret keys from seeds.
+const SEED_CONTEXT: &[u8] = b"oasis-runtime-sdk/bls12381: seed";
+
+/// Size of a compressed public key.
+const PUBLIC_KEY_SIZE: usize = 48;
+/// Size of a compressed signature.
+const SIGNATURE_SIZE: usize = 96;
+
+/// A BLS12-381 public key (in compressed form).
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(transparent, no_default)]
+pub struct PublicKey(Vec<u8>);
+
+impl PublicKey {
+    /// Return a byte representation of this public key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Construct a public key from a slice of bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        // Ensure the bytes represents a valid public key.
+        PublicKey::decompress_public_key(bytes)?;
+        Ok(PublicKey(bytes.to_vec()))
+    }
+
+    /// Verify a signature.
+    pub fn verify(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        aggregate_verify(context, &[(self, message)], signature)
+    }
+
+    /// Verify signature without using any domain separation scheme (the message is still
+    /// augmented with the public key).
+    pub fn verify_raw(&self, message: &[u8], signature: &Signature) -> Result<(), Error> {
+        let public_key = PublicKey::decompress_public_key(&self.0)?;
+        let signature = decompress_signature(signature)?;
+        verify_pairing(&signature, &[(public_key, self.hash_to_curve(message))])
+    }
+
+    fn hash_to_curve(&self, message: &[u8]) -> G2Affine {
+        let augmented = [self.0.as_slice(), message].concat();
+        <G2Projective as HashToCurve<ExpandMsgXmd<sha2_0_9::Sha256>>>::hash_to_curve(augmented, DST)
+            .into()
+    }
+
+    fn decompress_public_key(bytes: &[u8]) -> Result<G1Affine, Error> {
+        let bytes: &[u8; PUBLIC_KEY_SIZE] =
+            bytes.try_into().map_err(|_| Error::MalformedPublicKey)?;
+        let public_key: G1Affine =
+            Option::from(G1Affine::from_compressed(bytes)).ok_or(Error::MalformedPublicKey)?;
+        if bool::from(public_key.is_identity()) {
+            return Err(Error::MalformedPublicKey);
+        }
+        Ok(public_key)
+    }
+}
+
+impl From<&'static str> for PublicKey {
+    fn from(s: &'static str) -> PublicKey {
+        PublicKey::from_bytes(&base64::decode(s).unwrap()).unwrap()
+    }
+}
+
+/// Hash the context and message into the message that is actually signed.
+fn prehash(context: &[u8], message: &[u8]) -> Vec<u8> {
+    Sha512_256::new()
+        .chain_update(context)
+        .chain_update(message)
+        .finalize()
+        .to_vec()
+}
+
+fn decompress_signature(signature: &Signature) -> Result<G2Affine, Error> {
+    let bytes: &[u8; SIGNATURE_SIZE] = signature
+        .as_ref()
+        .try_into()
+        .map_err(|_| Error::MalformedSignature)?;
+    Option::from(G2Affine::from_compressed(bytes)).ok_or(Error::MalformedSignature)
+}
+
+/// Check that `e(g1, signature) == prod(e(pk_i, h_i))`.
+fn verify_pairing(signature: &G2Affine, terms: &[(G1Affine, G2Affine)]) -> Result<(), Error> {
+    let neg_generator = -G1Affine::generator();
+    let prepared: Vec<_> = std::iter::once((neg_generator, G2Prepared::from(*signature)))
+        .chain(terms.iter().map(|(pk, h)| (*pk, G2Prepared::from(*h))))
+        .collect();
+    let terms: Vec<_> = prepared.iter().map(|(pk, h)| (pk, h)).collect();
+
+    if multi_miller_loop(&terms).final_exponentiation() != Gt::identity() {
+        return Err(Error::VerificationFailed);
+    }
+    Ok(())
+}
+
+/// Aggregate the given signatures into a single signature.
+///
+/// The aggregate signature can be verified against all the signed messages using
+/// `aggregate_verify`.
+pub fn aggregate_signatures(signatures: &[Signature]) -> Result<Signature, Error> {
+    if signatures.is_empty() {
+        return Err(Error::InvalidArgument);
+    }
+
+    let mut aggregate = G2Projective::identity();
+    for signature in signatures {
+        aggregate += decompress_signature(signature)?;
+    }
+    Ok(G2Affine::from(aggregate).to_compressed().to_vec().into())
+}
+
+/// Verify an aggregate signature over the given (public key, message) pairs where all messages
+/// have been signed under the same context.
+///
+/// Messages need not be distinct as each message is augmented with the signer's public key.
+pub fn aggregate_verify(
+    context: &[u8],
+    signed: &[(&PublicKey, &[u8])],
+    signature: &Signature,
+) -> Result<(), Error> {
+    if signed.is_empty() {
+        return Err(Error::InvalidArgument);
+    }
+
+    let signature = decompress_signature(signature)?;
+    let terms = signed
+        .iter()
+        .map(|(pk, message)| {
+            Ok((
+                PublicKey::decompress_public_key(&pk.0)?,
+                pk.hash_to_curve(&prehash(context, message)),
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    verify_pairing(&signature, &terms)
+}
+
+/// A memory-backed signer for BLS12-381.
+pub struct MemorySigner {
+    sk: Scalar,
+}
+
+impl MemorySigner {
+    fn public_key_inner(&self) -> PublicKey {
+        PublicKey(
+            G1Affine::from(G1Affine::generator() * self.sk)
+                .to_compressed()
+                .to_vec(),
+        )
+    }
+
+    fn sign_augmented(&self, message: &[u8]) -> Signature {
+        let h = self.public_key_inner().hash_to_curve(message);
+        G2Affine::from(h * self.sk).to_compressed().to_vec().into()
+    }
+}
+
+impl super::Signer for MemorySigner {
+    fn new_from_seed(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < 32 {
+            return Err(Error::InvalidArgument);
+        }
+        let digest = Sha512::new()
+            .chain_update(SEED_CONTEXT)
+            .chain_update(seed)
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&digest);
+        let sk = Scalar::from_bytes_wide(&wide);
+        if sk == Scalar::from(0u64) {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Self { sk })
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes: &[u8; 32] = bytes.try_into().map_err(|_| Error::MalformedPrivateKey)?;
+        let sk: Scalar =
+            Option::from(Scalar::from_bytes(bytes)).ok_or(Error::MalformedPrivateKey)?;
+        if sk == Scalar::from(0u64) {
+            return Err(Error::MalformedPrivateKey);
+        }
+        Ok(Self { sk })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.sk.to_bytes().to_vec()
+    }
+
+    fn public_key(&self) -> super::PublicKey {
+        super::PublicKey::Bls12381(self.public_key_inner())
+    }
+
+    fn sign(&self, context: &[u8], message: &[u8]) -> Result<Signature, Error> {
+        Ok(self.sign_augmented(&prehash(context, message)))
+    }
+
+    fn sign_raw(&self, message: &[u8]) -> Result<Signature, Error> {
+        Ok(self.sign_augmented(message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::Signer as _, *};
+
+    #[test]
+    fn test_sign_verify() {
+        let ctx = b"oasis-core/test: context";
+        let message = b"this is a message";
+
+        let signer = MemorySigner::new_from_seed(&Sha512_256::digest("bls12381 test")).unwrap();
+        let pk = match signer.public_key() {
+            super::super::PublicKey::Bls12381(pk) => pk,
+            _ => panic!("public key should be a BLS12-381 public key"),
+        };
+        assert_eq!(pk.as_bytes().len(), PUBLIC_KEY_SIZE);
+        PublicKey::from_bytes(pk.as_bytes()).expect("public key should round-trip");
+
+        let signature = signer.sign(ctx, message).unwrap();
+        assert_eq!(signature.as_ref().len(), SIGNATURE_SIZE);
+        pk.verify(ctx, message, &signature)
+            .expect("signature should verify");
+        pk.verify(b"oasis-core/test: wrong context", message, &signature)
+            .expect_err("signature under a different context should fail verification");
+        pk.verify(ctx, b"this isn't a message", &signature)
+            .expect_err("signature over a different message should fail verification");
+        pk.verify_raw(message, &signature)
+            .expect_err("context-separated signature should not verify as raw");
+
+        let raw = signer.sign_raw(message).unwrap();
+        pk.verify_raw(message, &raw)
+            .expect("raw signature should verify");
+
+        let restored = MemorySigner::from_bytes(&signer.to_bytes()).unwrap();
+        assert_eq!(restored.public_key(), signer.public_key());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let ctx = b"oasis-core/test: context";
+        let signers: Vec<_> = ["alice", "bob", "charlie"]
+            .iter()
+            .map(|name| MemorySigner::new_from_seed(&Sha512_256::digest(name)).unwrap())
+            .collect();
+        let pks: Vec<_> = signers.iter().map(|s| s.public_key_inner()).collect();
+        // The same message signed by multiple signers must also aggregate.
+        let messages: [&[u8]; 3] = [b"message one", b"message two", b"message two"];
+
+        let signatures: Vec<_> = signers
+            .iter()
+            .zip(messages)
+            .map(|(signer, message)| signer.sign(ctx, message).unwrap())
+            .collect();
+        let aggregate = aggregate_signatures(&signatures).unwrap();
+
+        let signed: Vec<_> = pks.iter().zip(messages).collect();
+        aggregate_verify(ctx, &signed, &aggregate).expect("aggregate signature should verify");
+
+        // Any mismatch should fail verification.
+        aggregate_verify(ctx, &signed[..2], &aggregate)
+            .expect_err("aggregate with a missing signer should fail verification");
+        let mut swapped = signed.clone();
+        swapped[0].1 = b"message two";
+        aggregate_verify(ctx, &swapped, &aggregate)
+            .expect_err("aggregate over different messages should fail verification");
+        aggregate_verify(b"oasis-core/test: wrong context", &signed, &aggregate)
+            .expect_err("aggregate under a different context should fail verification");
+
+        aggregate_signatures(&[]).expect_err("empty aggregate should be rejected");
+        aggregate_verify(ctx, &[], &aggregate).expect_err("empty aggregate should be rejected");
+    }
+}
@@ -0,0 +1,144 @@
+//! Dilithium (post-quantum) signatures.
+//!
+//! Uses the Dilithium3 parameter set. Support is experimental and only available with the
+//! `experimental-dilithium` feature; the encoding and address derivation may change as the
+//! scheme is standardized.
+use crystals_dilithium::dilithium3;
+use sha2::{Digest as _, Sha512_256};
+
+use crate::crypto::signature::{Error, Signature};
+
+/// Domain separation context for deriving key pairs from seeds.
+const SEED_CONTEXT: &[u8] = b"oasis-runtime-sdk/dilithium3: seed";
+
+/// A Dilithium3 public key.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(transparent, no_default)]
+pub struct PublicKey(Vec<u8>);
+
+impl PublicKey {
+    /// Return a byte representation of this public key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Construct a public key from a slice of bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != dilithium3::PUBLICKEYBYTES {
+            return Err(Error::MalformedPublicKey);
+        }
+        Ok(PublicKey(bytes.to_vec()))
+    }
+
+    /// Verify a signature.
+    pub fn verify(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.verify_raw(&prehash(context, message), signature)
+    }
+
+    /// Verify signature without using any domain separation scheme.
+    pub fn verify_raw(&self, message: &[u8], signature: &Signature) -> Result<(), Error> {
+        if signature.as_ref().len() != dilithium3::SIGNBYTES {
+            return Err(Error::MalformedSignature);
+        }
+        let public_key = dilithium3::PublicKey::from_bytes(&self.0);
+        if !public_key.verify(message, signature.as_ref()) {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Hash the context and message into the message that is actually signed.
+fn prehash(context: &[u8], message: &[u8]) -> Vec<u8> {
+    Sha512_256::new()
+        .chain_update(context)
+        .chain_update(message)
+        .finalize()
+        .to_vec()
+}
+
+/// A memory-backed signer for Dilithium3.
+pub struct MemorySigner {
+    keypair: dilithium3::Keypair,
+}
+
+impl super::Signer for MemorySigner {
+    fn new_from_seed(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < 32 {
+            return Err(Error::InvalidArgument);
+        }
+        let seed = Sha512_256::new()
+            .chain_update(SEED_CONTEXT)
+            .chain_update(seed)
+            .finalize();
+        Ok(Self {
+            keypair: dilithium3::Keypair::generate(Some(seed.as_slice())),
+        })
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != dilithium3::KEYPAIRBYTES {
+            return Err(Error::MalformedPrivateKey);
+        }
+        Ok(Self {
+            keypair: dilithium3::Keypair::from_bytes(bytes),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.keypair.to_bytes().to_vec()
+    }
+
+    fn public_key(&self) -> super::PublicKey {
+        super::PublicKey::Dilithium3(PublicKey(self.keypair.public.bytes.to_vec()))
+    }
+
+    fn sign(&self, context: &[u8], message: &[u8]) -> Result<Signature, Error> {
+        self.sign_raw(&prehash(context, message))
+    }
+
+    fn sign_raw(&self, message: &[u8]) -> Result<Signature, Error> {
+        Ok(self.keypair.sign(message).to_vec().into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::Signer as _, *};
+
+    #[test]
+    fn test_sign_verify() {
+        let ctx = b"oasis-core/test: context";
+        let message = b"this is a message";
+
+        let signer = MemorySigner::new_from_seed(&Sha512_256::digest("dilithium test")).unwrap();
+        let pk = match signer.public_key() {
+            super::super::PublicKey::Dilithium3(pk) => pk,
+            _ => panic!("public key should be a Dilithium3 public key"),
+        };
+        PublicKey::from_bytes(pk.as_bytes()).expect("public key should round-trip");
+        PublicKey::from_bytes(&pk.as_bytes()[1..]).expect_err("truncated key should be rejected");
+
+        let signature = signer.sign(ctx, message).unwrap();
+        assert_eq!(signature.as_ref().len(), dilithium3::SIGNBYTES);
+        pk.verify(ctx, message, &signature)
+            .expect("signature should verify");
+        pk.verify(b"oasis-core/test: wrong context", message, &signature)
+            .expect_err("signature under a different context should fail verification");
+        pk.verify(ctx, b"this isn't a message", &signature)
+            .expect_err("signature over a different message should fail verification");
+        pk.verify_raw(message, &signature)
+            .expect_err("context-separated signature should not verify as raw");
+
+        // Key derivation from seeds must be deterministic.
+        let same = MemorySigner::new_from_seed(&Sha512_256::digest("dilithium test")).unwrap();
+        assert_eq!(same.public_key(), signer.public_key());
+        let restored = MemorySigner::from_bytes(&signer.to_bytes()).unwrap();
+        assert_eq!(restored.public_key(), signer.public_key());
+    }
+}
@@ -116,6 +116,119 @@ impl From<PublicKey> for CorePublicKey {
     }
 }
 
+/// An Ed25519 signature over a context and message, prepared for batch verification.
+#[derive(Clone, Debug)]
+pub struct BatchEntry {
+    public_key: PublicKey,
+    digest: [u8; 32],
+    signature: Signature,
+}
+
+impl BatchEntry {
+    /// Prepare the given signature over the context and message for batch verification.
+    pub fn new(
+        public_key: &PublicKey,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature,
+    ) -> Self {
+        let mut digest = Sha512_256::new();
+        digest.update(context);
+        digest.update(message);
+
+        Self {
+            public_key: public_key.clone(),
+            digest: digest.finalize().into(),
+            signature: signature.clone(),
+        }
+    }
+
+    /// Verify this signature individually.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.public_key.verify_raw(&self.digest, &self.signature)
+    }
+
+    /// Whether the result of batch verification is guaranteed to match the result of verifying
+    /// this signature individually.
+    ///
+    /// Individual verification is strict (cofactorless) while batch verification is cofactored,
+    /// so the two only agree when both the public key and the signature's R component are
+    /// canonically encoded points in the prime-order subgroup.
+    fn is_batchable(&self) -> bool {
+        let is_strict_point = |bytes: &[u8]| {
+            let compressed = CompressedEdwardsY::from_slice(bytes);
+            match compressed.decompress() {
+                Some(point) => {
+                    point.compress() == compressed
+                        && !point.is_small_order()
+                        && point.is_torsion_free()
+                }
+                None => false,
+            }
+        };
+
+        self.signature.0.len() == CoreSignature::len()
+            && is_strict_point(&self.signature.0[..32])
+            && is_strict_point(self.public_key.as_bytes())
+    }
+}
+
+/// Verify a batch of signatures at once.
+///
+/// The result is always the same as if each of the signatures was verified individually. In case
+/// verification fails, the entries are verified individually so the error is the one returned by
+/// the first offending signature. Callers that need to identify all offending signatures should
+/// verify the entries individually.
+pub fn verify_batch<'a>(entries: impl IntoIterator<Item = &'a BatchEntry>) -> Result<(), Error> {
+    let entries: Vec<_> = entries.into_iter().collect();
+    if batch_equation_holds(&entries) {
+        return Ok(());
+    }
+    entries.iter().try_for_each(|entry| entry.verify())
+}
+
+/// Check the batch verification equation for the given entries. Entries which cannot be batch
+/// verified with strict semantics are verified individually.
+///
+/// Returns `false` in case any of the signatures may be invalid.
+fn batch_equation_holds(entries: &[&BatchEntry]) -> bool {
+    let mut batch = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry.is_batchable() {
+            batch.push(entry);
+        } else if entry.verify().is_err() {
+            return false;
+        }
+    }
+
+    match batch.as_slice() {
+        [] => return true,
+        // Avoid batch verification overhead for a single signature.
+        [entry] => return entry.verify().is_ok(),
+        _ => {}
+    }
+
+    let mut digests = Vec::with_capacity(batch.len());
+    let mut signatures = Vec::with_capacity(batch.len());
+    let mut public_keys = Vec::with_capacity(batch.len());
+    for entry in batch {
+        let signature: ed25519_dalek::Signature = match entry.signature.as_ref().try_into() {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+        let public_key: ed25519_dalek::VerifyingKey = match entry.public_key.as_bytes().try_into() {
+            Ok(public_key) => public_key,
+            Err(_) => return false,
+        };
+
+        digests.push(entry.digest.as_slice());
+        signatures.push(signature);
+        public_keys.push(public_key);
+    }
+
+    ed25519_dalek::verify_batch(&digests, &signatures, &public_keys).is_ok()
+}
+
 /// A memory-backed signer for Ed25519.
 pub struct MemorySigner {
     key: Key,
@@ -299,4 +412,129 @@ mod test {
             "esk_signer pk != esk_pubic_key"
         );
     }
+
+    #[test]
+    fn test_verify_batch() {
+        let context = b"oasis-core/test: context";
+        let signers: Vec<_> = (0..4u8)
+            .map(|i| MemorySigner::new_from_seed(&[i; 32]).unwrap())
+            .collect();
+        let entry = |signer: &MemorySigner, message: &[u8], signature: &Signature| {
+            let pk = match signer.public_key() {
+                super::super::PublicKey::Ed25519(pk) => pk,
+                _ => unreachable!(),
+            };
+            BatchEntry::new(&pk, context, message, signature)
+        };
+
+        let mut entries: Vec<_> = signers
+            .iter()
+            .map(|signer| {
+                let message = signer.public_key().as_bytes().to_vec();
+                entry(signer, &message, &signer.sign(context, &message).unwrap())
+            })
+            .collect();
+        verify_batch(&entries).expect("batch should verify");
+        verify_batch(&entries[..1]).expect("single entry should verify");
+        verify_batch(&[]).expect("empty batch should verify");
+
+        // Replace one of the signatures with a signature over a different message.
+        let signature = signers[2].sign(context, b"other message").unwrap();
+        entries[2] = entry(&signers[2], signers[2].public_key().as_bytes(), &signature);
+        verify_batch(&entries).expect_err("batch with an invalid signature should fail");
+
+        let invalid: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.verify().is_err())
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![2],
+            "individual verification should identify the culprit"
+        );
+    }
+
+    #[test]
+    fn test_verify_batch_strict() {
+        use curve25519_dalek::{
+            constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION},
+            scalar::Scalar,
+            traits::IsIdentity,
+        };
+
+        let context = b"oasis-core/test: context";
+        let signer = MemorySigner::new_from_seed(&[1; 32]).unwrap();
+        let valid = {
+            let pk = match signer.public_key() {
+                super::super::PublicKey::Ed25519(pk) => pk,
+                _ => unreachable!(),
+            };
+            BatchEntry::new(
+                &pk,
+                context,
+                b"message",
+                &signer.sign(context, b"message").unwrap(),
+            )
+        };
+
+        // Construct a signature under a mixed-order public key which only satisfies the cofactored
+        // verification equation.
+        let a = Scalar::from_bytes_mod_order([7; 32]);
+        let r = Scalar::from_bytes_mod_order([9; 32]);
+        let public_key = (a * ED25519_BASEPOINT_POINT + EIGHT_TORSION[1]).compress();
+        let pk = PublicKey::from_bytes(public_key.as_bytes()).unwrap();
+        let big_r = (r * ED25519_BASEPOINT_POINT).compress();
+        let invalid = (0u8..)
+            .find_map(|i| {
+                let message = [i];
+                let digest = Sha512_256::new()
+                    .chain_update(context)
+                    .chain_update(message)
+                    .finalize();
+                let mut hram = [0u8; 64];
+                hram.copy_from_slice(
+                    &Sha512::new()
+                        .chain_update(big_r.as_bytes())
+                        .chain_update(public_key.as_bytes())
+                        .chain_update(digest)
+                        .finalize(),
+                );
+                let k = Scalar::from_bytes_mod_order_wide(&hram);
+                if (k * EIGHT_TORSION[1]).is_identity() {
+                    // The torsion component cancels out, try a different message.
+                    return None;
+                }
+                let s = r + k * a;
+                let signature: Signature = [big_r.as_bytes().as_slice(), s.as_bytes().as_slice()]
+                    .concat()
+                    .into();
+                Some(BatchEntry::new(&pk, context, &message, &signature))
+            })
+            .unwrap();
+
+        invalid
+            .verify()
+            .expect_err("strict verification should reject the signature");
+        let cofactored = ed25519_dalek::verify_batch(
+            &[valid.digest.as_slice(), invalid.digest.as_slice()],
+            &[
+                valid.signature.as_ref().try_into().unwrap(),
+                invalid.signature.as_ref().try_into().unwrap(),
+            ],
+            &[
+                valid.public_key.as_bytes().try_into().unwrap(),
+                invalid.public_key.as_bytes().try_into().unwrap(),
+            ],
+        );
+        assert!(
+            cofactored.is_ok(),
+            "cofactored batch verification should accept the signature"
+        );
+        verify_batch([&valid, &invalid])
+            .expect_err("batch verification should agree with strict verification");
+        verify_batch([&invalid]).expect_err("single entry should fail");
+        verify_batch([&valid]).expect("valid entry should verify");
+    }
 }
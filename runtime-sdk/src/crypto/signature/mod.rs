@@ -4,10 +4,14 @@ use std::convert::TryFrom;
 use digest::{typenum::Unsigned as _, Digest as _};
 use thiserror::Error;
 
+pub mod bls12381;
 pub mod context;
 mod digests;
+#[cfg(feature = "experimental-dilithium")]
+pub mod dilithium;
 pub mod ed25519;
 pub mod secp256k1;
+pub mod secp256k1schnorr;
 pub mod secp256r1;
 pub mod secp384r1;
 pub mod sr25519;
@@ -34,6 +38,13 @@ pub enum SignatureType {
     Secp256r1_PrehashedSha256,
     #[cbor(rename = "secp384r1_prehashed_sha384")]
     Secp384r1_PrehashedSha384,
+    #[cbor(rename = "bls12381")]
+    Bls12381,
+    #[cbor(rename = "secp256k1_bip340")]
+    Secp256k1_Bip340,
+    #[cfg(feature = "experimental-dilithium")]
+    #[cbor(rename = "dilithium3")]
+    Dilithium3,
 }
 
 impl SignatureType {
@@ -48,6 +59,10 @@ impl SignatureType {
             Self::Sr25519 => 6,
             Self::Secp256r1_PrehashedSha256 => 7,
             Self::Secp384r1_PrehashedSha384 => 8,
+            Self::Bls12381 => 9,
+            Self::Secp256k1_Bip340 => 10,
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3 => 11,
         }
     }
 
@@ -85,6 +100,19 @@ impl SignatureType {
     pub fn is_secp384r1_variant(&self) -> bool {
         matches!(self, Self::Secp384r1_PrehashedSha384)
     }
+
+    pub fn is_bls12381_variant(&self) -> bool {
+        matches!(self, Self::Bls12381)
+    }
+
+    pub fn is_secp256k1_bip340_variant(&self) -> bool {
+        matches!(self, Self::Secp256k1_Bip340)
+    }
+
+    #[cfg(feature = "experimental-dilithium")]
+    pub fn is_dilithium_variant(&self) -> bool {
+        matches!(self, Self::Dilithium3)
+    }
 }
 
 impl TryFrom<u8> for SignatureType {
@@ -101,6 +129,10 @@ impl TryFrom<u8> for SignatureType {
             6 => Ok(Self::Sr25519),
             7 => Ok(Self::Secp256r1_PrehashedSha256),
             8 => Ok(Self::Secp384r1_PrehashedSha384),
+            9 => Ok(Self::Bls12381),
+            10 => Ok(Self::Secp256k1_Bip340),
+            #[cfg(feature = "experimental-dilithium")]
+            11 => Ok(Self::Dilithium3),
             _ => Err(Error::InvalidArgument),
         }
     }
@@ -123,6 +155,16 @@ pub enum PublicKey {
 
     #[cbor(rename = "sr25519")]
     Sr25519(sr25519::PublicKey),
+
+    #[cbor(rename = "bls12381")]
+    Bls12381(bls12381::PublicKey),
+
+    #[cbor(rename = "secp256k1schnorr")]
+    Secp256k1Schnorr(secp256k1schnorr::PublicKey),
+
+    #[cfg(feature = "experimental-dilithium")]
+    #[cbor(rename = "dilithium3")]
+    Dilithium3(dilithium::PublicKey),
 }
 
 /// Error.
@@ -153,6 +195,10 @@ impl PublicKey {
             PublicKey::Secp256r1(pk) => pk.as_bytes(),
             PublicKey::Secp384r1(pk) => pk.as_bytes(),
             PublicKey::Sr25519(pk) => pk.as_bytes(),
+            PublicKey::Bls12381(pk) => pk.as_bytes(),
+            PublicKey::Secp256k1Schnorr(pk) => pk.as_bytes(),
+            #[cfg(feature = "experimental-dilithium")]
+            PublicKey::Dilithium3(pk) => pk.as_bytes(),
         }
     }
 
@@ -176,6 +222,14 @@ impl PublicKey {
                 Ok(Self::Secp384r1(secp384r1::PublicKey::from_bytes(bytes)?))
             }
             SignatureType::Sr25519 => Ok(Self::Sr25519(sr25519::PublicKey::from_bytes(bytes)?)),
+            SignatureType::Bls12381 => Ok(Self::Bls12381(bls12381::PublicKey::from_bytes(bytes)?)),
+            SignatureType::Secp256k1_Bip340 => Ok(Self::Secp256k1Schnorr(
+                secp256k1schnorr::PublicKey::from_bytes(bytes)?,
+            )),
+            #[cfg(feature = "experimental-dilithium")]
+            SignatureType::Dilithium3 => {
+                Ok(Self::Dilithium3(dilithium::PublicKey::from_bytes(bytes)?))
+            }
         }
     }
 
@@ -192,6 +246,10 @@ impl PublicKey {
             PublicKey::Secp256r1(pk) => pk.verify(context, message, signature),
             PublicKey::Secp384r1(pk) => pk.verify(context, message, signature),
             PublicKey::Sr25519(pk) => pk.verify(context, message, signature),
+            PublicKey::Bls12381(pk) => pk.verify(context, message, signature),
+            PublicKey::Secp256k1Schnorr(pk) => pk.verify(context, message, signature),
+            #[cfg(feature = "experimental-dilithium")]
+            PublicKey::Dilithium3(pk) => pk.verify(context, message, signature),
         }
     }
 
@@ -204,6 +262,10 @@ impl PublicKey {
             PublicKey::Secp256r1(pk) => pk.verify_raw(message, signature),
             PublicKey::Secp384r1(pk) => pk.verify_raw(message, signature),
             PublicKey::Sr25519(_) => Err(Error::InvalidArgument),
+            PublicKey::Bls12381(pk) => pk.verify_raw(message, signature),
+            PublicKey::Secp256k1Schnorr(pk) => pk.verify_raw(message, signature),
+            #[cfg(feature = "experimental-dilithium")]
+            PublicKey::Dilithium3(pk) => pk.verify_raw(message, signature),
         }
     }
 
@@ -285,6 +347,19 @@ impl PublicKey {
                 _ => Err(Error::InvalidArgument),
             },
             Self::Sr25519(_) => Err(Error::InvalidArgument),
+            Self::Bls12381(pk) => match signature_type {
+                SignatureType::Bls12381 => pk.verify(context_or_hash, message, signature),
+                _ => Err(Error::InvalidArgument),
+            },
+            Self::Secp256k1Schnorr(pk) => match signature_type {
+                SignatureType::Secp256k1_Bip340 => pk.verify(context_or_hash, message, signature),
+                _ => Err(Error::InvalidArgument),
+            },
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(pk) => match signature_type {
+                SignatureType::Dilithium3 => pk.verify(context_or_hash, message, signature),
+                _ => Err(Error::InvalidArgument),
+            },
         }
     }
 
@@ -295,15 +370,35 @@ impl PublicKey {
         public_keys: &[PublicKey],
         signatures: &[Signature],
     ) -> Result<(), Error> {
+        let entries = Self::prepare_batch_multisig(context, message, public_keys, signatures)?;
+        ed25519::verify_batch(&entries)
+    }
+
+    /// Prepare a batch of signatures of the same message for deferred verification.
+    ///
+    /// Signatures that do not support batch verification are verified immediately while Ed25519
+    /// signatures are returned so that they can be verified together with other batches using
+    /// `ed25519::verify_batch`.
+    pub fn prepare_batch_multisig(
+        context: &[u8],
+        message: &[u8],
+        public_keys: &[PublicKey],
+        signatures: &[Signature],
+    ) -> Result<Vec<ed25519::BatchEntry>, Error> {
         if public_keys.len() != signatures.len() {
             return Err(Error::InvalidArgument);
         }
 
-        // TODO: Use actual batch verification.
+        let mut entries = vec![];
         for (pk, sig) in public_keys.iter().zip(signatures.iter()) {
-            pk.verify(context, message, sig)?;
+            match pk {
+                PublicKey::Ed25519(pk) => {
+                    entries.push(ed25519::BatchEntry::new(pk, context, message, sig))
+                }
+                _ => pk.verify(context, message, sig)?,
+            }
         }
-        Ok(())
+        Ok(entries)
     }
 }
 
@@ -362,11 +457,21 @@ pub enum MemorySigner {
     Secp256k1(secp256k1::MemorySigner),
     Secp256r1(secp256r1::MemorySigner),
     Secp384r1(secp384r1::MemorySigner),
+    Bls12381(bls12381::MemorySigner),
+    Secp256k1Schnorr(secp256k1schnorr::MemorySigner),
+    #[cfg(feature = "experimental-dilithium")]
+    Dilithium3(dilithium::MemorySigner),
 }
 
 impl MemorySigner {
     /// Create a new memory signer from a seed.
     pub fn new_from_seed(sig_type: SignatureType, seed: &[u8]) -> Result<Self, Error> {
+        #[cfg(feature = "experimental-dilithium")]
+        if sig_type.is_dilithium_variant() {
+            return Ok(Self::Dilithium3(dilithium::MemorySigner::new_from_seed(
+                seed,
+            )?));
+        }
         if sig_type.is_ed25519_variant() {
             Ok(Self::Ed25519(ed25519::MemorySigner::new_from_seed(seed)?))
         } else if sig_type.is_secp256k1_variant() {
@@ -381,6 +486,12 @@ impl MemorySigner {
             Ok(Self::Secp384r1(secp384r1::MemorySigner::new_from_seed(
                 seed,
             )?))
+        } else if sig_type.is_bls12381_variant() {
+            Ok(Self::Bls12381(bls12381::MemorySigner::new_from_seed(seed)?))
+        } else if sig_type.is_secp256k1_bip340_variant() {
+            Ok(Self::Secp256k1Schnorr(
+                secp256k1schnorr::MemorySigner::new_from_seed(seed)?,
+            ))
         } else {
             Err(Error::InvalidArgument)
         }
@@ -397,6 +508,12 @@ impl MemorySigner {
 
     /// Reconstruct the signer from its byte representation.
     pub fn from_bytes(sig_type: SignatureType, bytes: &[u8]) -> Result<Self, Error> {
+        #[cfg(feature = "experimental-dilithium")]
+        if sig_type.is_dilithium_variant() {
+            return Ok(Self::Dilithium3(dilithium::MemorySigner::from_bytes(
+                bytes,
+            )?));
+        }
         if sig_type.is_ed25519_variant() {
             Ok(Self::Ed25519(ed25519::MemorySigner::from_bytes(bytes)?))
         } else if sig_type.is_secp256k1_variant() {
@@ -405,6 +522,12 @@ impl MemorySigner {
             Ok(Self::Secp256r1(secp256r1::MemorySigner::from_bytes(bytes)?))
         } else if sig_type.is_secp384r1_variant() {
             Ok(Self::Secp384r1(secp384r1::MemorySigner::from_bytes(bytes)?))
+        } else if sig_type.is_bls12381_variant() {
+            Ok(Self::Bls12381(bls12381::MemorySigner::from_bytes(bytes)?))
+        } else if sig_type.is_secp256k1_bip340_variant() {
+            Ok(Self::Secp256k1Schnorr(
+                secp256k1schnorr::MemorySigner::from_bytes(bytes)?,
+            ))
         } else {
             Err(Error::InvalidArgument)
         }
@@ -417,6 +540,10 @@ impl MemorySigner {
             Self::Secp256k1(signer) => signer.to_bytes(),
             Self::Secp256r1(signer) => signer.to_bytes(),
             Self::Secp384r1(signer) => signer.to_bytes(),
+            Self::Bls12381(signer) => signer.to_bytes(),
+            Self::Secp256k1Schnorr(signer) => signer.to_bytes(),
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(signer) => signer.to_bytes(),
         }
     }
 
@@ -427,6 +554,10 @@ impl MemorySigner {
             Self::Secp256k1(signer) => signer.public_key(),
             Self::Secp256r1(signer) => signer.public_key(),
             Self::Secp384r1(signer) => signer.public_key(),
+            Self::Bls12381(signer) => signer.public_key(),
+            Self::Secp256k1Schnorr(signer) => signer.public_key(),
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(signer) => signer.public_key(),
         }
     }
 
@@ -437,6 +568,10 @@ impl MemorySigner {
             Self::Secp256k1(signer) => signer.sign(context, message),
             Self::Secp256r1(signer) => signer.sign(context, message),
             Self::Secp384r1(signer) => signer.sign(context, message),
+            Self::Bls12381(signer) => signer.sign(context, message),
+            Self::Secp256k1Schnorr(signer) => signer.sign(context, message),
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(signer) => signer.sign(context, message),
         }
     }
 
@@ -447,6 +582,10 @@ impl MemorySigner {
             Self::Secp256k1(signer) => signer.sign_raw(message),
             Self::Secp256r1(signer) => signer.sign_raw(message),
             Self::Secp384r1(signer) => signer.sign_raw(message),
+            Self::Bls12381(signer) => signer.sign_raw(message),
+            Self::Secp256k1Schnorr(signer) => signer.sign_raw(message),
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(signer) => signer.sign_raw(message),
         }
     }
 
@@ -526,6 +665,19 @@ impl MemorySigner {
                 }
                 _ => Err(Error::InvalidArgument),
             },
+            Self::Bls12381(signer) => match signature_type {
+                SignatureType::Bls12381 => signer.sign(context_or_hash, message),
+                _ => Err(Error::InvalidArgument),
+            },
+            Self::Secp256k1Schnorr(signer) => match signature_type {
+                SignatureType::Secp256k1_Bip340 => signer.sign(context_or_hash, message),
+                _ => Err(Error::InvalidArgument),
+            },
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(signer) => match signature_type {
+                SignatureType::Dilithium3 => signer.sign(context_or_hash, message),
+                _ => Err(Error::InvalidArgument),
+            },
         }
     }
 }
@@ -556,6 +708,8 @@ mod test {
             SignatureType::Ed25519_Oasis,
             SignatureType::Ed25519_Pure,
             SignatureType::Secp256k1_Oasis,
+            SignatureType::Bls12381,
+            SignatureType::Secp256k1_Bip340,
         ] {
             let signer = MemorySigner::new_test(sig_type, "memory signer test");
             let pk = signer.public_key();
@@ -570,6 +724,8 @@ mod test {
                 .expect_err("signature should fail verification");
             if matches!(sig_type, SignatureType::Ed25519_Oasis)
                 || matches!(sig_type, SignatureType::Secp256k1_Oasis)
+                || matches!(sig_type, SignatureType::Bls12381)
+                || matches!(sig_type, SignatureType::Secp256k1_Bip340)
             {
                 pk.verify_by_type(sig_type, corrupt_ctx, message, &signature)
                     .expect_err("signature should fail verification");
@@ -0,0 +1,145 @@
+//! Secp256k1 Schnorr (BIP-340) signatures.
+//!
+//! Public keys are 32-byte x-only keys as used by Taproot. Since aggregated keys (e.g. produced
+//! by MuSig2) are indistinguishable from regular keys, they can be used as signers directly.
+use digest::Digest as _;
+use k256::{
+    schnorr::{
+        self,
+        signature::hazmat::{PrehashSigner as _, PrehashVerifier as _},
+    },
+    sha2::Sha512_256,
+};
+
+use crate::crypto::signature::{Error, Signature};
+
+/// A Secp256k1 Schnorr (x-only) public key.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(transparent, no_default)]
+pub struct PublicKey(Vec<u8>);
+
+impl PublicKey {
+    /// Return a byte representation of this public key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Construct a public key from a slice of bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        // Ensure the bytes represents a valid public key.
+        PublicKey::decode_public_key(bytes)?;
+        Ok(PublicKey(bytes.to_vec()))
+    }
+
+    /// Verify a signature.
+    pub fn verify(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        let digest = Sha512_256::new()
+            .chain_update(context)
+            .chain_update(message)
+            .finalize();
+        self.verify_raw(&digest, signature)
+    }
+
+    /// Verify signature of a 32-byte message without using any domain separation scheme.
+    pub fn verify_raw(&self, message: &[u8], signature: &Signature) -> Result<(), Error> {
+        if message.len() != 32 {
+            return Err(Error::InvalidArgument);
+        }
+        let public_key = PublicKey::decode_public_key(&self.0)?;
+        let signature = schnorr::Signature::try_from(signature.as_ref())
+            .map_err(|_| Error::MalformedSignature)?;
+        public_key
+            .verify_prehash(message, &signature)
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Result<schnorr::VerifyingKey, Error> {
+        schnorr::VerifyingKey::from_bytes(bytes).map_err(|_| Error::MalformedPublicKey)
+    }
+}
+
+impl From<&'static str> for PublicKey {
+    fn from(s: &'static str) -> PublicKey {
+        PublicKey::from_bytes(&base64::decode(s).unwrap()).unwrap()
+    }
+}
+
+/// A memory-backed signer for Secp256k1 Schnorr signatures.
+pub struct MemorySigner {
+    sk: schnorr::SigningKey,
+}
+
+impl super::Signer for MemorySigner {
+    fn new_from_seed(seed: &[u8]) -> Result<Self, Error> {
+        let sk = schnorr::SigningKey::from_bytes(seed).map_err(|_| Error::InvalidArgument)?;
+        Ok(Self { sk })
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            sk: schnorr::SigningKey::from_bytes(bytes).map_err(|_| Error::MalformedPrivateKey)?,
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.sk.to_bytes().to_vec()
+    }
+
+    fn public_key(&self) -> super::PublicKey {
+        super::PublicKey::Secp256k1Schnorr(PublicKey(self.sk.verifying_key().to_bytes().to_vec()))
+    }
+
+    fn sign(&self, context: &[u8], message: &[u8]) -> Result<Signature, Error> {
+        let digest = Sha512_256::new()
+            .chain_update(context)
+            .chain_update(message)
+            .finalize();
+        self.sign_raw(&digest)
+    }
+
+    fn sign_raw(&self, message: &[u8]) -> Result<Signature, Error> {
+        if message.len() != 32 {
+            return Err(Error::InvalidArgument);
+        }
+        // Use all-zero auxiliary randomness so that signatures are deterministic.
+        let signature: schnorr::Signature = self
+            .sk
+            .sign_prehash(message)
+            .map_err(|_| Error::SigningError)?;
+        Ok(signature.to_bytes().to_vec().into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{super::Signer as _, *};
+
+    #[test]
+    fn test_bip340_vector() {
+        // Test vector 0 from BIP-340.
+        let sk = hex::decode("0000000000000000000000000000000000000000000000000000000000000003")
+            .unwrap();
+        let pk = hex::decode("f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9")
+            .unwrap();
+        let message = [0u8; 32];
+        let expected = hex::decode("e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0").unwrap();
+
+        let signer = MemorySigner::from_bytes(&sk).unwrap();
+        assert_eq!(signer.public_key().as_bytes(), pk.as_slice());
+        let signature = signer.sign_raw(&message).unwrap();
+        assert_eq!(signature.as_ref(), expected.as_slice());
+
+        let pk = PublicKey::from_bytes(&pk).unwrap();
+        pk.verify_raw(&message, &signature)
+            .expect("signature should verify");
+        pk.verify_raw(&[1u8; 32], &signature)
+            .expect_err("signature over a different message should fail verification");
+        pk.verify_raw(b"short", &signature)
+            .expect_err("non 32-byte messages should be rejected");
+    }
+}
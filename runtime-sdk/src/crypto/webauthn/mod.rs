@@ -0,0 +1,112 @@
+//! WebAuthn (passkey) authentication.
+//!
+//! A WebAuthn assertion is a P-256 signature over the authenticator data followed by the SHA-256
+//! hash of the client data JSON where the client data contains the challenge that was presented
+//! to the authenticator. For transaction authentication the challenge is derived from the
+//! transaction body and the chain signature context (see `challenge`).
+use sha2::{Digest as _, Sha256};
+use thiserror::Error;
+
+use oasis_core_runtime::common::crypto::hash::Hash;
+
+use crate::crypto::signature::{secp256r1, Signature};
+
+#[cfg(test)]
+mod test;
+
+/// Offset of the flags byte in the authenticator data.
+const FLAGS_OFFSET: usize = 32;
+/// Minimum size of the authenticator data (RP ID hash, flags and signature counter).
+const MIN_AUTHENTICATOR_DATA_SIZE: usize = 37;
+/// User present flag.
+const FLAG_USER_PRESENT: u8 = 0x01;
+/// Client data type of assertions.
+const CLIENT_DATA_TYPE_GET: &str = "webauthn.get";
+
+/// Error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("malformed authenticator data")]
+    MalformedAuthenticatorData,
+    #[error("relying party mismatch")]
+    RelyingPartyMismatch,
+    #[error("user not present")]
+    UserNotPresent,
+    #[error("malformed client data")]
+    MalformedClientData,
+    #[error("challenge mismatch")]
+    ChallengeMismatch,
+    #[error("signature verification failed")]
+    VerificationFailed,
+}
+
+/// A WebAuthn credential configuration.
+///
+/// The derived address (see `Address::from_webauthn`) commits to both the relying party
+/// identifier and the credential public key.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct Config {
+    /// Relying party identifier (e.g. `example.com`) that the credential is scoped to.
+    pub rp_id: String,
+    /// Credential public key.
+    pub public_key: secp256r1::PublicKey,
+}
+
+/// A WebAuthn assertion produced by an authenticator.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Assertion {
+    /// Raw authenticator data.
+    pub authenticator_data: Vec<u8>,
+    /// Raw client data JSON.
+    pub client_data_json: Vec<u8>,
+    /// DER-encoded P-256 signature.
+    pub signature: Signature,
+}
+
+/// Derive the challenge that must be signed in order to authenticate the given transaction body
+/// under the given signature context.
+pub fn challenge(context: &[u8], body: &[u8]) -> Hash {
+    Hash::digest_bytes_list(&[context, body])
+}
+
+impl Config {
+    /// Verify that the assertion has been produced by this credential over the given challenge.
+    pub fn verify(&self, challenge: &Hash, assertion: &Assertion) -> Result<(), Error> {
+        // Verify the authenticator data is for the configured relying party and that the user
+        // was present.
+        let authenticator_data = &assertion.authenticator_data;
+        if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_SIZE {
+            return Err(Error::MalformedAuthenticatorData);
+        }
+        if authenticator_data[..FLAGS_OFFSET] != Sha256::digest(self.rp_id.as_bytes())[..] {
+            return Err(Error::RelyingPartyMismatch);
+        }
+        if authenticator_data[FLAGS_OFFSET] & FLAG_USER_PRESENT == 0 {
+            return Err(Error::UserNotPresent);
+        }
+
+        // Verify the client data is an assertion over the challenge.
+        let client_data: serde_json::Value = serde_json::from_slice(&assertion.client_data_json)
+            .map_err(|_| Error::MalformedClientData)?;
+        if client_data.get("type").and_then(|v| v.as_str()) != Some(CLIENT_DATA_TYPE_GET) {
+            return Err(Error::MalformedClientData);
+        }
+        let expected_challenge = base64::encode_config(challenge.as_ref(), base64::URL_SAFE_NO_PAD);
+        match client_data.get("challenge").and_then(|v| v.as_str()) {
+            Some(challenge) if challenge == expected_challenge => {}
+            Some(_) => return Err(Error::ChallengeMismatch),
+            None => return Err(Error::MalformedClientData),
+        }
+
+        // Verify the signature over the authenticator data and client data hash.
+        let message = [
+            authenticator_data.as_slice(),
+            &Sha256::digest(&assertion.client_data_json),
+        ]
+        .concat();
+        self.public_key
+            .verify_raw(&message, &assertion.signature)
+            .map_err(|_| Error::VerificationFailed)
+    }
+}
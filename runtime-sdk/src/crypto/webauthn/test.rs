@@ -0,0 +1,148 @@
+use sha2::{Digest as _, Sha256};
+
+use super::*;
+use crate::crypto::signature::{self, MemorySigner, SignatureType};
+
+fn assertion(signer: &MemorySigner, rp_id: &str, flags: u8, client_data_json: String) -> Assertion {
+    let mut authenticator_data = Sha256::digest(rp_id.as_bytes()).to_vec();
+    authenticator_data.push(flags);
+    authenticator_data.extend_from_slice(&[0, 0, 0, 1]); // Signature counter.
+    let client_data_json = client_data_json.into_bytes();
+
+    let message = [
+        authenticator_data.as_slice(),
+        &Sha256::digest(&client_data_json),
+    ]
+    .concat();
+    Assertion {
+        signature: signer.sign_raw(&message).unwrap(),
+        authenticator_data,
+        client_data_json,
+    }
+}
+
+fn client_data(typ: &str, challenge: &Hash) -> String {
+    format!(
+        r#"{{"type":"{}","challenge":"{}","origin":"https://example.com","crossOrigin":false}}"#,
+        typ,
+        base64::encode_config(challenge.as_ref(), base64::URL_SAFE_NO_PAD)
+    )
+}
+
+#[test]
+fn test_verify() {
+    let signer = MemorySigner::new_test(SignatureType::Secp256r1_PrehashedSha256, "webauthn");
+    let public_key = match signer.public_key() {
+        signature::PublicKey::Secp256r1(pk) => pk,
+        _ => panic!("public key should be a P-256 public key"),
+    };
+    let config = Config {
+        rp_id: "example.com".to_owned(),
+        public_key,
+    };
+    let tx_challenge = challenge(b"test context", b"transaction body");
+    let other_challenge = challenge(b"test context", b"other transaction body");
+
+    let valid = assertion(
+        &signer,
+        "example.com",
+        FLAG_USER_PRESENT,
+        client_data("webauthn.get", &tx_challenge),
+    );
+    config
+        .verify(&tx_challenge, &valid)
+        .expect("valid assertion should verify");
+
+    let result = config.verify(&other_challenge, &valid);
+    assert!(matches!(result, Err(Error::ChallengeMismatch)));
+
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "evil.com",
+            FLAG_USER_PRESENT,
+            client_data("webauthn.get", &tx_challenge),
+        ),
+    );
+    assert!(matches!(result, Err(Error::RelyingPartyMismatch)));
+
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "example.com",
+            0,
+            client_data("webauthn.get", &tx_challenge),
+        ),
+    );
+    assert!(matches!(result, Err(Error::UserNotPresent)));
+
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "example.com",
+            FLAG_USER_PRESENT,
+            client_data("webauthn.create", &tx_challenge),
+        ),
+    );
+    assert!(matches!(result, Err(Error::MalformedClientData)));
+
+    // Client data members should be matched structurally, not by their serialization.
+    let encoded_challenge = base64::encode_config(tx_challenge.as_ref(), base64::URL_SAFE_NO_PAD);
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "example.com",
+            FLAG_USER_PRESENT,
+            format!(
+                r#"{{ "type": "webauthn.get", "challenge": "{}" }}"#,
+                encoded_challenge
+            ),
+        ),
+    );
+    assert!(
+        result.is_ok(),
+        "whitespace in client data should be accepted"
+    );
+
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "example.com",
+            FLAG_USER_PRESENT,
+            format!(
+                r#"{{"type":"webauthn.get","challenge":"other","extra":{{"challenge":"{}"}}}}"#,
+                encoded_challenge
+            ),
+        ),
+    );
+    assert!(matches!(result, Err(Error::ChallengeMismatch)));
+
+    let result = config.verify(
+        &tx_challenge,
+        &assertion(
+            &signer,
+            "example.com",
+            FLAG_USER_PRESENT,
+            format!(
+                r#"["type","webauthn.get","challenge","{}"]"#,
+                encoded_challenge
+            ),
+        ),
+    );
+    assert!(matches!(result, Err(Error::MalformedClientData)));
+
+    let mut tampered = valid.clone();
+    tampered.client_data_json = client_data("webauthn.get", &other_challenge).into_bytes();
+    let result = config.verify(&other_challenge, &tampered);
+    assert!(matches!(result, Err(Error::VerificationFailed)));
+
+    let mut truncated = valid;
+    truncated.authenticator_data.truncate(FLAGS_OFFSET);
+    let result = config.verify(&tx_challenge, &truncated);
+    assert!(matches!(result, Err(Error::MalformedAuthenticatorData)));
+}
@@ -1,13 +1,16 @@
 //! Transaction dispatcher.
 use std::{
+    borrow::Cow,
+    cell::RefCell,
     collections::{BTreeMap, BTreeSet},
     convert::TryInto,
     marker::PhantomData,
     sync::{atomic::AtomicBool, Arc},
+    time::Instant,
 };
 
 use anyhow::anyhow;
-use slog::error;
+use slog::{error, warn};
 use thiserror::Error;
 
 use oasis_core_runtime::{
@@ -25,27 +28,46 @@ use oasis_core_runtime::{
     types::{CheckTxMetadata, CheckTxResult},
 };
 
+#[cfg(feature = "debug-checkpoints")]
+use crate::checkpoint;
 use crate::{
-    callformat,
+    admission, callformat,
     context::{BatchContext, Context, Mode, RuntimeBatchContext, TransactionWithMeta, TxContext},
-    crypto::random::RootRng,
-    error::{Error as _, RuntimeError},
-    event::IntoTags,
+    crypto::{
+        random::RootRng,
+        signature::{self, ed25519},
+    },
+    error::{Error as _, ErrorCatalog, RuntimeError},
+    event::{EventTags, IntoTags},
     keymanager::{KeyManagerClient, KeyManagerError},
-    module::{self, BlockHandler, MethodHandler, TransactionHandler},
+    metrics,
+    module::{self, BlockHandler, MethodHandler, ModuleInfoHandler as _, TransactionHandler},
     modules,
     modules::core::API as _,
     runtime::Runtime,
-    schedule_control::ScheduleControlHost,
+    schedule_control::{self, ScheduleControlHost, ScheduledTransaction},
     sender::SenderMeta,
+    simulation_cache,
     storage::{self, current::TransactionResult, CurrentStore, Prefix},
-    types,
+    subcall, trace, types,
     types::transaction::{AuthProof, Transaction},
 };
 
 /// Unique module name.
 const MODULE_NAME: &str = "dispatcher";
 
+/// Context key for the amount of gas used by block workers in the current block.
+const CONTEXT_KEY_BLOCK_WORKER_GAS_USED: &str = "dispatcher.BlockWorkerGasUsed";
+/// Context key for the hashes of transactions successfully executed in the current block.
+const CONTEXT_KEY_EXECUTED_TXS: &str = "dispatcher.ExecutedTxs";
+/// Context key for the unmet dependency of the transaction being dispatched.
+const CONTEXT_KEY_UNMET_DEPENDENCY: &str = "dispatcher.UnmetDependency";
+
+/// Number of rounds worth of unhandled message results (at the maximum number of messages per
+/// round) retained for retrying in subsequent rounds. In case the limit is exceeded, the oldest
+/// results are dropped.
+const PENDING_MESSAGE_RESULT_ROUNDS: usize = 8;
+
 /// Error emitted by the dispatch process. Note that this indicates an error in the dispatch
 /// process itself and should not be used for any transaction-related errors.
 #[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
@@ -72,6 +94,18 @@ pub enum Error {
     BatchOutOfGas,
 }
 
+/// Number of events in the given event tags.
+fn count_events(events: &EventTags) -> u64 {
+    events.values().map(|events| events.len() as u64).sum()
+}
+
+/// Whether the given call result is a failure due to exceeding the block resource limits.
+fn exceeds_block_resources(result: &module::CallResult) -> bool {
+    let err = modules::core::Error::BlockResourcesExhausted;
+    matches!(result, module::CallResult::Failed { module, code, .. }
+        if module == err.module_name() && *code == err.code())
+}
+
 /// Result of dispatching a transaction.
 #[derive(Debug)]
 pub struct DispatchResult {
@@ -101,6 +135,45 @@ impl DispatchResult {
             call_format_metadata,
         }
     }
+
+    /// Build the execution receipt of the dispatched transaction.
+    fn receipt(
+        &self,
+        gas_used: u64,
+        messages: &[(
+            roothash::Message,
+            types::message::MessageEventHookInvocation,
+        )],
+    ) -> modules::core::types::Receipt {
+        let (success, module, code) = match &self.result {
+            module::CallResult::Failed { module, code, .. } => (false, module.clone(), *code),
+            _ => (true, String::new(), 0),
+        };
+        let events: Vec<_> = self
+            .tags
+            .iter()
+            .map(|tag| {
+                Hash::digest_bytes_list(&[
+                    &(tag.key.len() as u32).to_be_bytes(),
+                    &tag.key,
+                    &tag.value,
+                ])
+            })
+            .collect();
+        let messages: Vec<_> = messages
+            .iter()
+            .map(|(msg, _)| Hash::digest_bytes(&cbor::to_vec(msg.clone())))
+            .collect();
+
+        modules::core::types::Receipt {
+            success,
+            module,
+            code,
+            gas_used,
+            events_root: modules::core::types::merkle_root(&events),
+            messages_root: modules::core::types::merkle_root(&messages),
+        }
+    }
 }
 
 impl From<module::CallResult> for DispatchResult {
@@ -144,6 +217,11 @@ impl<R: Runtime> Dispatcher<R> {
         consensus_verifier: Arc<dyn Verifier>,
         schedule_control_host: Arc<dyn ScheduleControlHost>,
     ) -> Self {
+        // Make sure that the error codes of all modules are unique.
+        if let Err(err) = ErrorCatalog::new(R::Modules::error_catalog()) {
+            panic!("inconsistent error catalog: {err}");
+        }
+
         Self {
             host_info,
             key_manager,
@@ -158,27 +236,118 @@ impl<R: Runtime> Dispatcher<R> {
         ctx: &mut C,
         tx: &[u8],
     ) -> Result<types::transaction::Transaction, modules::core::Error> {
+        let (tx, entries) = Self::decode_tx_deferred(ctx, tx)?;
+        Self::verify_deferred_signatures(&entries)?;
+        Ok(tx)
+    }
+
+    /// Decode a batch of runtime transactions.
+    ///
+    /// Ed25519 signatures of all transactions are batch verified at once. In case batch
+    /// verification fails, each transaction is verified individually to identify the offending
+    /// ones, so the results are the same as if each transaction was decoded using `decode_tx`.
+    pub fn decode_txs<'a, C: Context>(
+        ctx: &mut C,
+        txs: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Vec<Result<types::transaction::Transaction, modules::core::Error>> {
+        let mut results = vec![];
+        let mut pending = vec![];
+        for tx in txs {
+            match Self::decode_tx_deferred(ctx, tx) {
+                Ok((tx, entries)) => {
+                    pending.push((results.len(), entries));
+                    results.push(Ok(tx));
+                }
+                Err(err) => results.push(Err(err)),
+            }
+        }
+
+        if ed25519::verify_batch(pending.iter().flat_map(|(_, entries)| entries)).is_err() {
+            for (index, entries) in pending {
+                let result = entries.iter().try_for_each(ed25519::BatchEntry::verify);
+                if let Err(err) = Self::map_signature_error(result) {
+                    results[index] = Err(err);
+                }
+            }
+        }
+
+        results
+    }
+
+    fn verify_deferred_signatures(
+        entries: &[ed25519::BatchEntry],
+    ) -> Result<(), modules::core::Error> {
+        Self::map_signature_error(ed25519::verify_batch(entries))
+    }
+
+    fn map_signature_error(
+        result: Result<(), signature::Error>,
+    ) -> Result<(), modules::core::Error> {
+        result.map_err(|e| {
+            modules::core::Error::MalformedTransaction(
+                types::transaction::Error::MalformedTransaction(e.into()).into(),
+            )
+        })
+    }
+
+    /// Decode a runtime transaction, returning any Ed25519 signatures that still need to be
+    /// verified.
+    fn decode_tx_deferred<C: Context>(
+        ctx: &mut C,
+        tx: &[u8],
+    ) -> Result<(types::transaction::Transaction, Vec<ed25519::BatchEntry>), modules::core::Error>
+    {
         // Perform any checks before decoding.
         R::Modules::approve_raw_tx(ctx, tx)?;
 
+        // Decompress the envelope if needed, bounding its size by the maximum transaction size.
+        let max_tx_size = R::Core::max_tx_size().try_into().unwrap();
+        let tx = types::transaction::decode_envelope(tx, max_tx_size).map_err(|e| match e {
+            types::transaction::Error::OversizedTransaction => {
+                modules::core::Error::OversizedTransaction
+            }
+            e => modules::core::Error::MalformedTransaction(e.into()),
+        })?;
+        if let Cow::Owned(ref envelope) = tx {
+            // Any checks must also pass for the decompressed envelope.
+            R::Modules::approve_raw_tx(ctx, envelope)?;
+        }
+
         // Deserialize transaction.
-        let utx: types::transaction::UnverifiedTransaction = cbor::from_slice(tx)
+        let utx: types::transaction::UnverifiedTransaction = cbor::from_slice(&tx)
             .map_err(|e| modules::core::Error::MalformedTransaction(e.into()))?;
 
         // Perform any checks before signature verification.
         R::Modules::approve_unverified_tx(ctx, &utx)?;
 
+        // Consult the admission policy when checking transactions for the local pool.
+        if ctx.mode() == Mode::CheckTx {
+            let decoded = match utx.1.as_slice() {
+                [AuthProof::Module(_)] => None,
+                _ => cbor::from_slice::<Transaction>(&utx.0).ok(),
+            };
+            R::admit_tx(
+                ctx,
+                &admission::TxAdmissionInfo {
+                    size: tx.len(),
+                    method: decoded.as_ref().map(|tx| tx.call.method.as_str()),
+                    signer_count: utx.1.len(),
+                },
+            )?;
+        }
+
         match utx.1.as_slice() {
             [AuthProof::Module(scheme)] => {
-                R::Modules::decode_tx(ctx, scheme, &utx.0)?.ok_or_else(|| {
+                let tx = R::Modules::decode_tx(ctx, scheme, &utx.0)?.ok_or_else(|| {
                     modules::core::Error::MalformedTransaction(anyhow!(
                         "module-controlled transaction decoding scheme {} not supported",
                         scheme
                     ))
-                })
+                })?;
+                Ok((tx, vec![]))
             }
             _ => utx
-                .verify()
+                .verify_deferred()
                 .map_err(|e| modules::core::Error::MalformedTransaction(e.into())),
         }
     }
@@ -192,26 +361,39 @@ impl<R: Runtime> Dispatcher<R> {
         opts: &DispatchOptions<'_>,
     ) -> (module::CallResult, callformat::Metadata) {
         let read_only = call.read_only;
+        #[cfg(feature = "debug-checkpoints")]
+        let method = call.method.clone();
+        #[cfg(feature = "debug-checkpoints")]
+        checkpoint::pause(
+            ctx.mode(),
+            ctx.tx_index(),
+            &method,
+            checkpoint::Boundary::BeforeCall,
+        );
 
         // Dispatch the call.
         let (result, metadata) = Self::_dispatch_tx_call(ctx, call, opts);
 
         // Unconditionally call after handle call hook.
         let result = match R::Modules::after_handle_call(ctx, result) {
-            Ok(result) => result,
-            Err(e) => {
-                // If the call failed, return the error.
-                return (e.into_call_result(), metadata);
+            // Make sure that a read-only call did not result in any modifications.
+            Ok(_) if read_only && CurrentStore::has_pending_updates() => {
+                modules::core::Error::ReadOnlyTransaction.into_call_result()
             }
+            Ok(result) => result,
+            // If the call failed, return the error.
+            Err(e) => e.into_call_result(),
         };
 
-        // Make sure that a read-only call did not result in any modifications.
-        if read_only && CurrentStore::has_pending_updates() {
-            return (
-                modules::core::Error::ReadOnlyTransaction.into_call_result(),
-                metadata,
-            );
-        }
+        #[cfg(feature = "debug-checkpoints")]
+        checkpoint::pause(
+            ctx.mode(),
+            ctx.tx_index(),
+            &method,
+            checkpoint::Boundary::AfterCall {
+                success: result.is_success(),
+            },
+        );
 
         (result, metadata)
     }
@@ -225,6 +407,15 @@ impl<R: Runtime> Dispatcher<R> {
             return (e.into_call_result(), callformat::Metadata::Empty);
         }
 
+        // Fail transactions with unmet dependencies only after the before call hooks so that
+        // they are still charged for their inclusion.
+        if let Some(dependency) = ctx.tx_value::<Hash>(CONTEXT_KEY_UNMET_DEPENDENCY).take() {
+            return (
+                modules::core::Error::UnmetDependency(dependency).into_call_result(),
+                callformat::Metadata::Empty,
+            );
+        }
+
         // Decode call based on specified call format.
         let (call, call_format_metadata) = match callformat::decode_call(ctx, call, ctx.tx_index())
         {
@@ -247,7 +438,15 @@ impl<R: Runtime> Dispatcher<R> {
                 );
             }
         }
+        if let Err(e) = R::Modules::before_authorized_call_dispatch(ctx, &call) {
+            return (e.into_call_result(), call_format_metadata);
+        }
 
+        // Do not record methods of encrypted calls as they are confidential.
+        let _span = trace::dispatch_call(match call_format_metadata {
+            callformat::Metadata::Empty => &call.method,
+            _ => "",
+        });
         let result = match R::Modules::dispatch_call(ctx, &call.method, call.body) {
             module::DispatchResult::Handled(result) => result,
             module::DispatchResult::Unhandled(_) => {
@@ -258,23 +457,99 @@ impl<R: Runtime> Dispatcher<R> {
         (result, call_format_metadata)
     }
 
+    /// Simulate the given transaction against the current state. Any storage changes made by the
+    /// transaction are discarded.
+    ///
+    /// Results are cached in the node-local simulation cache so simulating the same call against
+    /// the same state multiple times (e.g. when estimating gas and later simulating the call) only
+    /// executes it once.
+    pub fn simulate_tx<C: Context>(
+        ctx: &mut C,
+        tx: types::transaction::Transaction,
+        tx_size: u32,
+    ) -> Result<simulation_cache::SimulationResult, Error> {
+        let caller = tx
+            .auth_info
+            .signer_info
+            .first()
+            .map(|si| si.address_spec.address())
+            .unwrap_or_default();
+        let key = simulation_cache::Key::for_call(ctx, caller, &tx.call, tx.auth_info.fee.gas);
+
+        simulation_cache::get_or_simulate(
+            ctx,
+            key,
+            // Gas usage depends on the transaction size and is only known for full transactions.
+            |cached| cached.tx_size == Some(tx_size),
+            |ctx| {
+                CurrentStore::with_transaction(|| {
+                    let result = ctx.with_simulation(|mut sim_ctx| {
+                        sim_ctx.with_tx(
+                            TransactionWithMeta {
+                                tx,
+                                tx_size,
+                                tx_index: 0,
+                                tx_hash: Default::default(),
+                            },
+                            |mut tx_ctx, call| {
+                                let (result, _) =
+                                    Self::dispatch_tx_call(&mut tx_ctx, call, &Default::default());
+                                let gas_used = R::Core::used_batch_gas(&mut tx_ctx);
+                                let result = match result {
+                                    module::CallResult::Ok(value) => Ok(value),
+                                    module::CallResult::Aborted(err) => return Err(err),
+                                    failed => Err(failed.try_into().unwrap()), // Guaranteed to be a Failed CallResult.
+                                };
+
+                                Ok(simulation_cache::SimulationResult {
+                                    result,
+                                    gas_used,
+                                    tx_size: Some(tx_size),
+                                })
+                            },
+                        )
+                    });
+
+                    TransactionResult::Rollback(result) // Always rollback storage changes.
+                })
+            },
+        )
+    }
+
     /// Dispatch a runtime transaction in the given context with the provided options.
     pub fn dispatch_tx_opts<C: BatchContext>(
         ctx: &mut C,
         tx: types::transaction::Transaction,
         opts: &DispatchOptions<'_>,
     ) -> Result<DispatchResult, Error> {
+        let span = trace::dispatch_tx(&tx);
+
         // Run pre-processing hooks.
         if !opts.skip_authentication {
+            let _span = trace::module_hook("authenticate_tx");
             if let Err(err) = R::Modules::authenticate_tx(ctx, &tx) {
+                span.record_success(false);
                 return Ok(err.into_call_result().into());
             }
         }
+
+        // Transactions can only be executed after all of their dependencies. This is checked
+        // after authentication so that transactions failing the check still pay the fee.
+        let unmet_dependency = if ctx.mode() == Mode::ExecuteTx {
+            Self::unmet_dependency(ctx, &tx)
+        } else {
+            None
+        };
         let tx_auth_info = tx.auth_info.clone();
         let is_read_only = tx.call.read_only;
 
+        // Collect metrics for executed transactions in case a metrics sink is installed.
+        let metrics = (ctx.mode() == Mode::ExecuteTx && metrics::is_enabled())
+            .then(|| (tx.call.method.clone(), Instant::now()));
+        let mut gas_used = 0;
+
         let (result, messages) = CurrentStore::with_transaction(|| {
-            ctx.with_tx(
+            let (mut result, mut messages, deferred, mut events, commit) = ctx.with_tx(
                 TransactionWithMeta {
                     tx,
                     tx_size: opts.tx_size,
@@ -282,16 +557,23 @@ impl<R: Runtime> Dispatcher<R> {
                     tx_hash: opts.tx_hash,
                 },
                 |mut ctx, call| {
+                    if let Some(dependency) = unmet_dependency {
+                        ctx.tx_value(CONTEXT_KEY_UNMET_DEPENDENCY).set(dependency);
+                    }
                     let (result, call_format_metadata) =
                         Self::dispatch_tx_call(&mut ctx, call, opts);
+                    gas_used = R::Core::used_tx_gas(&mut ctx);
                     if !result.is_success() || is_read_only {
                         // Retrieve unconditional events by doing an explicit rollback.
                         let etags = ctx.rollback();
 
-                        return TransactionResult::Rollback((
+                        return (
                             DispatchResult::new(result, etags.into_tags(), call_format_metadata),
                             Vec::new(),
-                        ));
+                            Vec::new(),
+                            0,
+                            false,
+                        );
                     }
 
                     // Load priority.
@@ -300,10 +582,17 @@ impl<R: Runtime> Dispatcher<R> {
                     let sender_metadata = R::Core::take_sender_meta(&mut ctx);
 
                     if ctx.is_check_only() {
-                        // Rollback state during checks.
-                        ctx.rollback();
+                        // Rollback state during checks. When scheduling, emitted events and
+                        // messages are still collected to check the block resource limits.
+                        let (messages, events) = if ctx.is_pre_schedule() {
+                            let state = ctx.commit();
+                            (state.messages, count_events(&state.events))
+                        } else {
+                            ctx.rollback();
+                            (Vec::new(), 0)
+                        };
 
-                        TransactionResult::Rollback((
+                        (
                             DispatchResult {
                                 result,
                                 tags: Vec::new(),
@@ -311,12 +600,17 @@ impl<R: Runtime> Dispatcher<R> {
                                 sender_metadata,
                                 call_format_metadata,
                             },
+                            messages,
                             Vec::new(),
-                        ))
+                            events,
+                            false,
+                        )
                     } else {
                         // Commit store and return emitted tags and messages.
+                        let deferred = subcall::take_deferred(&mut ctx);
                         let state = ctx.commit();
-                        TransactionResult::Commit((
+                        let events = count_events(&state.events);
+                        (
                             DispatchResult {
                                 result,
                                 tags: state.events.into_tags(),
@@ -325,20 +619,92 @@ impl<R: Runtime> Dispatcher<R> {
                                 call_format_metadata,
                             },
                             state.messages,
-                        ))
+                            deferred,
+                            events,
+                            true,
+                        )
                     }
                 },
-            )
-        });
+            );
+
+            // Perform any subcalls deferred by the transaction now that it has been committed.
+            if !deferred.is_empty() {
+                let max_messages = ctx
+                    .remaining_messages()
+                    .saturating_sub(messages.len() as u32);
+                let state = subcall::run_deferred(ctx, deferred, max_messages);
+                events = events.saturating_add(count_events(&state.events));
+                result.tags.extend(state.events.into_tags());
+                messages.extend(state.messages);
+            }
+
+            // Make sure the transaction, including any deferred subcalls, does not exceed any
+            // block resource limits. This is also checked before scheduling the transaction so
+            // that transactions exceeding the limits are deferred to a later block.
+            if commit || (ctx.is_pre_schedule() && result.result.is_success()) {
+                let usage = modules::core::BlockResources {
+                    storage_write_bytes: CurrentStore::pending_update_bytes(),
+                    messages: messages.len() as u32,
+                    events,
+                };
+                if let Err(err) = R::Core::use_block_resources(ctx, usage) {
+                    return TransactionResult::Rollback((
+                        DispatchResult::new(
+                            err.into_call_result(),
+                            Vec::new(),
+                            result.call_format_metadata,
+                        ),
+                        Vec::new(),
+                    ));
+                }
+            }
 
+            if !commit {
+                return TransactionResult::Rollback((result, Vec::new()));
+            }
+            TransactionResult::Commit((result, messages))
+        });
         // Run after dispatch hooks.
-        R::Modules::after_dispatch_tx(ctx, &tx_auth_info, &result.result);
+        {
+            let _span = trace::module_hook("after_dispatch_tx");
+            R::Modules::after_dispatch_tx(ctx, &tx_auth_info, &result.result);
+        }
+
+        span.record_gas_used(gas_used);
+        span.record_success(result.result.is_success());
+
+        if let Some((method, start)) = metrics {
+            metrics::record_tx(&metrics::TxMetrics {
+                method: &method,
+                gas_used,
+                duration: start.elapsed(),
+                success: result.result.is_success(),
+            });
+        }
 
         // Propagate batch aborts.
         if let module::CallResult::Aborted(err) = result.result {
             return Err(err);
         }
 
+        if ctx.mode() == Mode::ExecuteTx {
+            // Remember successfully executed transactions for dependency checks.
+            if result.result.is_success() {
+                ctx.value::<BTreeSet<Hash>>(CONTEXT_KEY_EXECUTED_TXS)
+                    .or_default()
+                    .insert(opts.tx_hash);
+            }
+
+            // Commit the execution receipt of the transaction if enabled.
+            if <<R::Core as modules::core::API>::Config as modules::core::Config>::STORE_RECEIPTS {
+                let receipt = result.receipt(gas_used, &messages);
+                R::Core::store_receipt(ctx, opts.tx_index as u32, receipt);
+            }
+
+            // Update any secondary event indices.
+            R::Core::index_events(ctx, opts.tx_index as u32, &result.tags);
+        }
+
         // Forward any emitted messages if we are not in check tx context.
         if !ctx.is_check_only() {
             ctx.emit_messages(messages)
@@ -444,6 +810,20 @@ impl<R: Runtime> Dispatcher<R> {
         })
     }
 
+    /// Returns the first dependency of the given transaction that has not been successfully
+    /// executed earlier in the current block, if any.
+    fn unmet_dependency<C: BatchContext>(ctx: &mut C, tx: &Transaction) -> Option<Hash> {
+        if tx.auth_info.after.is_empty() {
+            return None;
+        }
+        let executed = ctx.value::<BTreeSet<Hash>>(CONTEXT_KEY_EXECUTED_TXS).get();
+        tx.auth_info
+            .after
+            .iter()
+            .find(|dependency| !executed.map_or(false, |executed| executed.contains(*dependency)))
+            .copied()
+    }
+
     /// Prefetch prefixes for the given transaction.
     pub fn prefetch_tx(
         prefixes: &mut BTreeSet<Prefix>,
@@ -455,10 +835,95 @@ impl<R: Runtime> Dispatcher<R> {
         }
     }
 
-    fn handle_last_round_messages<C: Context>(ctx: &mut C) -> Result<(), modules::core::Error> {
+    /// Run all block workers registered for the given phase in order of their priority.
+    ///
+    /// Each worker runs atomically with a gas budget that is limited by both the worker's own
+    /// limit and the remaining block-wide worker gas limit. Storage accesses made by a worker are
+    /// charged against its budget in addition to any gas the worker uses explicitly. Changes made
+    /// by failed workers and by workers exceeding their budget are rolled back.
+    pub(crate) fn run_block_workers<C: BatchContext>(ctx: &mut C, phase: module::BlockPhase) {
+        let mut workers: Vec<_> = R::Modules::block_workers()
+            .into_iter()
+            .filter(|worker| worker.phase == phase)
+            .collect();
+        workers.sort_by_key(|worker| (worker.priority, worker.name));
+
+        let max_gas = match R::Core::block_limits().max_block_worker_gas {
+            0 => u64::MAX,
+            max_gas => max_gas,
+        };
+        for worker in workers {
+            let used = *ctx
+                .value::<u64>(CONTEXT_KEY_BLOCK_WORKER_GAS_USED)
+                .or_default();
+            let mut limit = max_gas.saturating_sub(used);
+            if worker.max_gas > 0 {
+                limit = limit.min(worker.max_gas);
+            }
+            let mut meter = module::BlockGasMeter::new(limit);
+            let span = trace::block_worker(worker.name);
+
+            let state = ctx.with_child(ctx.mode(), |mut ctx| {
+                CurrentStore::with_transaction(|| {
+                    CurrentStore::start_access_tracking();
+                    let result = R::Modules::run_block_worker(&mut ctx, worker.name, &mut meter);
+                    let stats = CurrentStore::stop_access_tracking();
+
+                    // Charge for storage accesses so that the budget is enforced even for workers
+                    // that do not meter themselves. The whole budget is used up by workers that
+                    // exceed it.
+                    let result = match result {
+                        module::DispatchResult::Handled(Ok(())) => module::DispatchResult::Handled(
+                            R::Core::storage_access_gas(&stats)
+                                .and_then(|gas| meter.use_gas(gas))
+                                .map_err(|err| {
+                                    meter.exhaust();
+                                    err
+                                }),
+                        ),
+                        result => result,
+                    };
+
+                    match result {
+                        module::DispatchResult::Handled(Ok(())) => {
+                            TransactionResult::Commit(Some(ctx.commit()))
+                        }
+                        module::DispatchResult::Handled(Err(err)) => {
+                            error!(ctx.get_logger("dispatcher"), "block worker failed";
+                                "worker" => worker.name,
+                                "err" => ?err,
+                            );
+                            TransactionResult::Rollback(None)
+                        }
+                        module::DispatchResult::Unhandled(()) => {
+                            error!(ctx.get_logger("dispatcher"), "block worker not handled";
+                                "worker" => worker.name,
+                            );
+                            TransactionResult::Rollback(None)
+                        }
+                    }
+                })
+            });
+
+            *ctx.value::<u64>(CONTEXT_KEY_BLOCK_WORKER_GAS_USED)
+                .or_default() = used.saturating_add(meter.used());
+            span.record_gas_used(meter.used());
+
+            if let Some(state) = state {
+                ctx.emit_etags(state.events);
+                ctx.emit_messages(state.messages)
+                    .expect("child context has already enforced the limits");
+            }
+        }
+    }
+
+    pub(crate) fn handle_last_round_messages<C: Context>(
+        ctx: &mut C,
+    ) -> Result<(), modules::core::Error> {
         let message_events = ctx.runtime_round_results().messages.clone();
+        let round = ctx.time().round();
 
-        let mut handlers = CurrentStore::with(|store| {
+        let (mut handlers, pending) = CurrentStore::with(|store| {
             let store = storage::TypedStore::new(storage::PrefixStore::new(
                 store,
                 &modules::core::MODULE_NAME,
@@ -466,25 +931,38 @@ impl<R: Runtime> Dispatcher<R> {
             let handlers: BTreeMap<u32, types::message::MessageEventHookInvocation> = store
                 .get(modules::core::state::MESSAGE_HANDLERS)
                 .unwrap_or_default();
+            let pending: Vec<types::message::PendingMessageResult> = store
+                .get(modules::core::state::PENDING_MESSAGE_RESULTS)
+                .unwrap_or_default();
 
-            handlers
+            (handlers, pending)
         });
+        let has_pending = !pending.is_empty();
+
+        // Retry any results that could not be handled in previous rounds first, so that results
+        // are processed in the order in which they were received.
+        let mut unhandled = Vec::new();
+        for result in pending {
+            if let Some(result) = Self::dispatch_message_result(ctx, result) {
+                unhandled.push(result);
+            }
+        }
 
         for event in message_events {
-            let handler = handlers
+            let hook = handlers
                 .remove(&event.index)
                 .ok_or(modules::core::Error::MessageHandlerMissing(event.index))?;
-            let hook_name = handler.hook_name.clone();
 
-            R::Modules::dispatch_message_result(
+            if let Some(result) = Self::dispatch_message_result(
                 ctx,
-                &hook_name,
-                types::message::MessageResult {
-                    event,
-                    context: handler.payload,
-                },
-            )
-            .ok_or(modules::core::Error::InvalidMethod(hook_name))?;
+                types::message::PendingMessageResult { round, event, hook },
+            ) {
+                warn!(ctx.get_logger("dispatcher"), "message result not handled, will retry";
+                    "hook_name" => &result.hook.hook_name,
+                    "index" => result.event.index,
+                );
+                unhandled.push(result);
+            }
         }
 
         if !handlers.is_empty() {
@@ -492,10 +970,123 @@ impl<R: Runtime> Dispatcher<R> {
             return Err(modules::core::Error::MessageHandlerNotInvoked);
         }
 
+        // Results for hooks that no module handles can never be handled, so drop them instead of
+        // retrying forever.
+        let mut dropped = Vec::new();
+        if !unhandled.is_empty() {
+            let known = Self::message_result_handlers();
+            let (retained, unknown): (Vec<_>, Vec<_>) = unhandled
+                .into_iter()
+                .partition(|result| known.contains(&result.hook.hook_name));
+            unhandled = retained;
+            for result in unknown {
+                error!(ctx.get_logger("dispatcher"), "dropping message result for unknown handler";
+                    "hook_name" => &result.hook.hook_name,
+                    "round" => result.round,
+                    "index" => result.event.index,
+                );
+                dropped.push(result);
+            }
+        }
+        // Bound the number of retained results, dropping the oldest ones first.
+        let max_pending = Self::max_pending_message_results(ctx);
+        if unhandled.len() > max_pending {
+            let excess = unhandled.len() - max_pending;
+            for result in unhandled.drain(..excess) {
+                error!(ctx.get_logger("dispatcher"), "dropping unhandled message result";
+                    "hook_name" => &result.hook.hook_name,
+                    "round" => result.round,
+                    "index" => result.event.index,
+                );
+                dropped.push(result);
+            }
+        }
+        for result in dropped {
+            ctx.emit_event(modules::core::Event::MessageResultDropped {
+                hook_name: result.hook.hook_name,
+                round: result.round,
+                index: result.event.index,
+            });
+        }
+
+        // Persist unhandled results so they survive until a handler is able to process them.
+        if has_pending || !unhandled.is_empty() {
+            CurrentStore::with(|store| {
+                let mut store = storage::TypedStore::new(storage::PrefixStore::new(
+                    store,
+                    &modules::core::MODULE_NAME,
+                ));
+                if unhandled.is_empty() {
+                    store.remove(modules::core::state::PENDING_MESSAGE_RESULTS);
+                } else {
+                    store.insert(modules::core::state::PENDING_MESSAGE_RESULTS, unhandled);
+                }
+            });
+        }
+
         Ok(())
     }
 
-    fn save_emitted_message_handlers(handlers: Vec<types::message::MessageEventHookInvocation>) {
+    /// Maximum number of unhandled message results retained for retrying in subsequent rounds.
+    ///
+    /// Must be called before any messages are emitted in the current round.
+    fn max_pending_message_results<C: Context>(ctx: &C) -> usize {
+        (ctx.remaining_messages() as usize)
+            .max(1)
+            .saturating_mul(PENDING_MESSAGE_RESULT_ROUNDS)
+    }
+
+    /// Names of all message result handlers exposed by the runtime's modules.
+    fn message_result_handlers() -> BTreeSet<String> {
+        R::Modules::module_metadata()
+            .into_values()
+            .flat_map(|metadata| metadata.methods)
+            .filter(|method| {
+                matches!(
+                    method.kind,
+                    modules::core::types::MethodHandlerKind::MessageResult
+                )
+            })
+            .map(|method| method.name)
+            .collect()
+    }
+
+    /// Dispatch the given message result to its handler, returning it back in case it was not
+    /// handled.
+    fn dispatch_message_result<C: Context>(
+        ctx: &mut C,
+        result: types::message::PendingMessageResult,
+    ) -> Option<types::message::PendingMessageResult> {
+        let types::message::PendingMessageResult { round, event, hook } = result;
+        let hook_name = hook.hook_name;
+        let origin = hook.origin;
+
+        match R::Modules::dispatch_message_result(
+            ctx,
+            &hook_name,
+            types::message::MessageResult {
+                event,
+                context: hook.payload,
+            },
+        ) {
+            module::DispatchResult::Handled(()) => None,
+            module::DispatchResult::Unhandled(result) => {
+                Some(types::message::PendingMessageResult {
+                    round,
+                    event: result.event,
+                    hook: types::message::MessageEventHookInvocation {
+                        hook_name,
+                        payload: result.context,
+                        origin,
+                    },
+                })
+            }
+        }
+    }
+
+    pub(crate) fn save_emitted_message_handlers(
+        handlers: Vec<types::message::MessageEventHookInvocation>,
+    ) {
         let message_handlers: BTreeMap<u32, types::message::MessageEventHookInvocation> = handlers
             .into_iter()
             .enumerate()
@@ -517,28 +1108,46 @@ impl<R: Runtime> Dispatcher<R> {
         method: &str,
         args: Vec<u8>,
     ) -> Result<Vec<u8>, RuntimeError> {
+        let _span = trace::dispatch_query(method);
         let args = cbor::from_slice(&args)
             .map_err(|err| modules::core::Error::InvalidArgument(err.into()))?;
 
-        CurrentStore::with_transaction(|| {
-            // Catch any panics that occur during query dispatch.
-            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                // Perform state migrations if required.
-                R::migrate(ctx);
-
-                if !R::is_allowed_query(method) || !ctx.is_allowed_query::<R>(method) {
-                    return Err(modules::core::Error::Forbidden.into());
-                }
-
-                R::Modules::dispatch_query(ctx, method, args)
-                    .ok_or_else(|| modules::core::Error::InvalidMethod(method.into()))?
-            }));
+        // Enforce the node-local resource limits of the query.
+        let limits = ctx
+            .local_config::<modules::core::LocalConfig>(modules::core::MODULE_NAME)
+            .map(|cfg| cfg.query_limits.limits_for(method))
+            .unwrap_or_default();
+
+        modules::core::query_limits::with_limits(limits, || {
+            CurrentStore::with_transaction(|| {
+                // Catch any panics that occur during query dispatch.
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    // Perform state migrations if required.
+                    R::migrate(ctx);
+
+                    if !R::is_allowed_query(method) || !ctx.is_allowed_query::<R>(method) {
+                        return Err(modules::core::Error::Forbidden.into());
+                    }
 
-            // Always rollback any changes to storage. Note that this is usually a no-op because
-            // Oasis Core would rollback any storage changes related to queries, but this makes it
-            // explicit to ensure this remains the case regardless of upstream changes.
-            TransactionResult::Rollback(result)
+                    // Authenticate the caller of queries that require it.
+                    let args = if R::Modules::is_authenticated_query(method) {
+                        callformat::decode_authenticated_query(ctx, method, args)?
+                    } else {
+                        args
+                    };
+
+                    R::Modules::dispatch_query(ctx, method, args)
+                        .ok_or_else(|| modules::core::Error::InvalidMethod(method.into()))?
+                }));
+
+                // Always rollback any changes to storage. Note that this is usually a no-op
+                // because Oasis Core would rollback any storage changes related to queries, but
+                // this makes it explicit to ensure this remains the case regardless of upstream
+                // changes.
+                TransactionResult::Rollback(result)
+            })
         })
+        .map_err(modules::core::Error::QueryResourcesExhausted)?
         .map_err(|err| -> RuntimeError { Error::QueryAborted(format!("{err:?}")).into() })?
         .map(cbor::to_vec)
     }
@@ -560,7 +1169,14 @@ impl<R: Runtime> Dispatcher<R> {
         let history = self.consensus_verifier.clone();
         let rng = RootRng::new();
 
-        let root = storage::MKVSStore::new(&mut rt_ctx.runtime_state);
+        // Track all changes made to state in case state size statistics should be maintained.
+        let track_state_stats =
+            <<R::Core as modules::core::API>::Config as modules::core::Config>::TRACK_STATE_STATS;
+        let state_changes = RefCell::new(storage::StateChanges::default());
+        let root = storage::ChangeTrackingStore::new(
+            storage::MKVSStore::new(&mut rt_ctx.runtime_state),
+            track_state_stats.then_some(&state_changes),
+        );
         let mut ctx = RuntimeBatchContext::<'_, R>::new(
             Mode::ExecuteTx,
             &self.host_info,
@@ -574,6 +1190,7 @@ impl<R: Runtime> Dispatcher<R> {
             rt_ctx.max_messages,
         );
 
+        let _span = trace::execute_batch(ctx.time().round());
         CurrentStore::enter(root, || {
             // Perform state migrations if required.
             R::migrate(&mut ctx);
@@ -581,13 +1198,32 @@ impl<R: Runtime> Dispatcher<R> {
             // Handle last round message results.
             Self::handle_last_round_messages(&mut ctx)?;
 
-            // Run begin block hooks.
-            R::Modules::begin_block(&mut ctx);
+            // Run begin block hooks and workers.
+            {
+                let _span = trace::module_hook("begin_block");
+                R::Modules::begin_block(&mut ctx);
+            }
+            Self::run_block_workers(&mut ctx, module::BlockPhase::Begin);
 
             let results = f(&mut ctx)?;
 
-            // Run end block hooks.
-            R::Modules::end_block(&mut ctx);
+            // Run end block workers and hooks.
+            Self::run_block_workers(&mut ctx, module::BlockPhase::End);
+            {
+                let _span = trace::module_hook("end_block");
+                R::Modules::end_block(&mut ctx);
+            }
+
+            // Emit events for any parameter changes made in this block.
+            R::Core::record_parameter_changes(&mut ctx, R::Modules::module_params());
+
+            // Update state size statistics with all changes made in this block.
+            if track_state_stats {
+                R::Core::update_state_stats(&state_changes.take());
+                if metrics::is_enabled() {
+                    metrics::record_state_stats(&R::Core::state_stats());
+                }
+            }
 
             // Commit the context and retrieve the emitted messages.
             let state = ctx.commit();
@@ -620,18 +1256,22 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
 
                 let mut txs = Vec::with_capacity(batch.len());
                 let mut prefixes: BTreeSet<Prefix> = BTreeSet::new();
-                for tx in batch.iter() {
-                    let tx_size = tx.len().try_into().map_err(|_| {
-                        Error::MalformedTransactionInBatch(anyhow!("transaction too large"))
-                    })?;
+                let decoded = Self::decode_txs(ctx, batch.iter().map(|tx| tx.as_slice()));
+                for (tx, decoded) in batch.iter().zip(decoded) {
+                    let tx_size =
+                        types::transaction::envelope_size(tx)
+                            .try_into()
+                            .map_err(|_| {
+                                Error::MalformedTransactionInBatch(anyhow!("transaction too large"))
+                            })?;
                     let tx_hash = Hash::digest_bytes(tx);
                     // It is an error to include a malformed transaction in a batch. So instead of only
                     // reporting a failed execution result, we fail the whole batch. This will make the compute
                     // node vote for failure and the round will fail.
                     //
                     // Correct proposers should only include transactions which have passed check_tx.
-                    let tx = Self::decode_tx(ctx, tx)
-                        .map_err(|err| Error::MalformedTransactionInBatch(err.into()))?;
+                    let tx =
+                        decoded.map_err(|err| Error::MalformedTransactionInBatch(err.into()))?;
                     txs.push((tx_size, tx_hash, tx.clone()));
 
                     if prefetch_enabled {
@@ -679,28 +1319,50 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
                     let last_batch_len = batch.len();
                     let last_batch_tx_hash = batch.last().map(|raw_tx| Hash::digest_bytes(raw_tx));
 
-                    for raw_tx in batch.drain(..) {
+                    // Decode transactions and determine the order in which they are scheduled.
+                    let mut txs = Vec::with_capacity(batch.len());
+                    let raw_txs: Vec<_> = batch.drain(..).collect();
+                    let decoded = Self::decode_txs(ctx, raw_txs.iter().map(|tx| tx.as_slice()));
+                    for (raw_tx, decoded) in raw_txs.into_iter().zip(decoded) {
+                        let tx_hash = Hash::digest_bytes(&raw_tx);
+                        match decoded {
+                            Ok(tx) => txs.push(ScheduledTransaction {
+                                gas_price: R::Core::native_gas_price(&tx.auth_info.fee),
+                                raw: raw_tx,
+                                hash: tx_hash,
+                                tx,
+                            }),
+                            Err(_) => {
+                                // Transaction is malformed, make sure it gets removed from the
+                                // queue and don't include it in a block.
+                                tx_reject_hashes.push(tx_hash);
+                            }
+                        }
+                    }
+                    R::prioritize_transactions(&mut txs);
+                    schedule_control::order_dependencies(&mut txs);
+
+                    for ScheduledTransaction {
+                        raw: raw_tx,
+                        hash: tx_hash,
+                        tx,
+                        ..
+                    } in txs
+                    {
                         // If we don't have enough gas for processing even the cheapest transaction
                         // we are done. Same if we reached the runtime-imposed maximum tx count.
+                        // Same if any of the other block resource limits has been reached.
                         let remaining_gas = R::Core::remaining_batch_gas(ctx);
                         if remaining_gas < cfg.min_remaining_gas
                             || new_batch.len() >= cfg.max_tx_count
+                            || R::Core::block_resources_exhausted(ctx)
                         {
                             break 'batch;
                         }
 
-                        // Decode transaction.
-                        let tx_hash = Hash::digest_bytes(&raw_tx);
-                        let tx = match Self::decode_tx(ctx, &raw_tx) {
-                            Ok(tx) => tx,
-                            Err(_) => {
-                                // Transaction is malformed, make sure it gets removed from the
-                                // queue and don't include it in a block.
-                                tx_reject_hashes.push(tx_hash);
-                                continue;
-                            }
-                        };
-                        let tx_size = raw_tx.len().try_into().unwrap();
+                        let tx_size = types::transaction::envelope_size(&raw_tx)
+                            .try_into()
+                            .unwrap();
 
                         // If we don't have enough gas remaining to process this transaction, just
                         // skip it.
@@ -711,12 +1373,18 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
                         if tx.auth_info.fee.consensus_messages > ctx.remaining_messages() {
                             continue;
                         }
+                        // Same if any of its dependencies has not been executed yet, the
+                        // transaction may still become executable in a later batch.
+                        if Self::unmet_dependency(ctx, &tx).is_some() {
+                            continue;
+                        }
 
                         // Determine the current transaction index.
                         let tx_index = new_batch.len();
 
                         // First run the transaction in check tx mode in a separate subcontext. If
                         // that fails, skip and (sometimes) reject transaction.
+                        let used_resources = R::Core::used_block_resources(ctx);
                         let skip = CurrentStore::with_transaction(|| {
                             let result = ctx.with_pre_schedule(|mut ctx| -> Result<_, Error> {
                                 // First authenticate the transaction to get any nonce related errors.
@@ -729,6 +1397,14 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
                                         // Skip and reject the transaction.
                                     }
                                     Ok(_) => {
+                                        // Account for the block resources used so far so that
+                                        // the checks can determine whether the transaction fits.
+                                        if R::Core::use_block_resources(&mut ctx, used_resources)
+                                            .is_err()
+                                        {
+                                            return Ok(true);
+                                        }
+
                                         // Run additional checks on the transaction.
                                         let check_result = Self::dispatch_tx_opts(
                                             &mut ctx,
@@ -745,6 +1421,11 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
                                             // Checks successful, execute transaction as usual.
                                             return Ok(false);
                                         }
+                                        if exceeds_block_resources(&check_result.result) {
+                                            // Only skip transaction as it may fit into a later
+                                            // block.
+                                            return Ok(true);
+                                        }
                                     }
                                 }
 
@@ -828,11 +1509,14 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
             // Prefetch.
             let mut txs: Vec<Result<_, RuntimeError>> = Vec::with_capacity(batch.len());
             let mut prefixes: BTreeSet<Prefix> = BTreeSet::new();
-            for tx in batch.iter() {
-                let tx_size = tx.len().try_into().map_err(|_| {
-                    Error::MalformedTransactionInBatch(anyhow!("transaction too large"))
-                })?;
-                let res = match Self::decode_tx(&mut ctx, tx) {
+            let decoded = Self::decode_txs(&mut ctx, batch.iter().map(|tx| tx.as_slice()));
+            for (tx, decoded) in batch.iter().zip(decoded) {
+                let tx_size = types::transaction::envelope_size(tx)
+                    .try_into()
+                    .map_err(|_| {
+                        Error::MalformedTransactionInBatch(anyhow!("transaction too large"))
+                    })?;
+                let res = match decoded {
                     Ok(tx) => {
                         if prefetch_enabled {
                             Self::prefetch_tx(&mut prefixes, tx.clone()).map(|_| (tx_size, tx))
@@ -907,7 +1591,6 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
         // Prepare dispatch context.
         let history = self.consensus_verifier.clone();
 
-        let root = storage::MKVSStore::new(&mut rt_ctx.runtime_state);
         let mut ctx = RuntimeBatchContext::<'_, R>::new(
             Mode::CheckTx,
             &self.host_info,
@@ -921,6 +1604,11 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
             rt_ctx.max_messages,
         );
 
+        // Account for storage reads so that the node-local resource limits can be enforced.
+        let root = modules::core::query_limits::LimitedStore::new(storage::MKVSStore::new(
+            &mut rt_ctx.runtime_state,
+        ));
+
         CurrentStore::enter(root, || Self::dispatch_query(&mut ctx, method, args))
     }
 }
@@ -929,6 +1617,7 @@ impl<R: Runtime + Send + Sync> transaction::dispatcher::Dispatcher for Dispatche
 mod test {
     use super::*;
     use crate::{
+        core::common::crypto::mrae::deoxysii,
         handler,
         module::Module,
         modules::core,
@@ -985,6 +1674,18 @@ mod test {
             Err(AlphabetError::Core(core::Error::Abort(Error::Aborted)))
         }
 
+        #[handler(message_result = "alphabet.Typed")]
+        fn message_result_typed<C: Context>(
+            _ctx: &mut C,
+            me: types::message::TypedMessageEvent<u64>,
+            context: u64,
+        ) {
+            let value = me.result.unwrap_or_default() + context;
+            CurrentStore::with(|store| {
+                store.insert(b"message_result", &cbor::to_vec(value));
+            });
+        }
+
         #[handler(query = "alphabet.Alpha")]
         fn alpha<C: Context>(_ctx: &mut C, _args: ()) -> Result<(), AlphabetError> {
             Ok(())
@@ -995,9 +1696,60 @@ mod test {
             // Nothing actually expensive here. We're just pretending for testing purposes.
             Ok(())
         }
+
+        #[handler(query = "alphabet.Spin")]
+        fn spin<C: Context>(ctx: &mut C, _args: ()) -> Result<(), AlphabetError> {
+            // Use gas forever without ever accessing storage.
+            loop {
+                <C::Runtime as Runtime>::Core::use_batch_gas(ctx, 0)
+                    .map_err(AlphabetError::Core)?;
+            }
+        }
     }
 
-    impl module::BlockHandler for AlphabetModule {}
+    impl module::BlockHandler for AlphabetModule {
+        fn block_workers() -> Vec<module::BlockWorker> {
+            let worker = |name, phase, priority, max_gas| module::BlockWorker {
+                name,
+                phase,
+                priority,
+                max_gas,
+            };
+            vec![
+                worker("alphabet.Last", module::BlockPhase::End, 1, 0),
+                worker("alphabet.Greedy", module::BlockPhase::End, 0, 15),
+                worker("alphabet.First", module::BlockPhase::End, 0, 0),
+                worker("alphabet.Begin", module::BlockPhase::Begin, 10, 0),
+            ]
+        }
+
+        fn run_block_worker<C: Context>(
+            _ctx: &mut C,
+            name: &str,
+            meter: &mut module::BlockGasMeter,
+        ) -> module::DispatchResult<(), Result<(), core::Error>> {
+            if !name.starts_with("alphabet.") {
+                return module::DispatchResult::Unhandled(());
+            }
+
+            // Record the order in which workers run.
+            CurrentStore::with(|store| {
+                let mut order: Vec<String> = store
+                    .get(b"workers")
+                    .map(|raw| cbor::from_slice(&raw).unwrap())
+                    .unwrap_or_default();
+                order.push(name.to_owned());
+                store.insert(b"workers", &cbor::to_vec(order));
+            });
+
+            let result = match name {
+                // Uses more gas than its limit allows.
+                "alphabet.Greedy" => meter.use_gas(10).and_then(|_| meter.use_gas(10)),
+                _ => meter.use_gas(10),
+            };
+            module::DispatchResult::Handled(result)
+        }
+    }
     impl module::TransactionHandler for AlphabetModule {}
     impl module::InvariantHandler for AlphabetModule {}
 
@@ -1020,11 +1772,25 @@ mod test {
                             tx_byte: 0,
                             auth_signature: 0,
                             auth_multisig_signer: 0,
+                            auth_signature_bls12381: 0,
+                            auth_signature_secp256k1schnorr: 0,
+                            auth_signature_dilithium3: 0,
                             callformat_x25519_deoxysii: 0,
+                            callformat_x25519_xchacha20poly1305: 0,
+                            tx_memo_byte: 0,
+                            storage_remove_refund: 0,
+                            storage_restore_refund: 0,
+                            storage_cold_access: 0,
+                            storage_warm_access: 0,
                         },
                         min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 0)]),
                         dynamic_min_gas_price: Default::default(),
+                        subcall: Default::default(),
+                        block_limits: Default::default(),
+                        ephemeral_keys: Default::default(),
+                        storage_deposit: Default::default(),
                     },
+                    ..Default::default()
                 },
                 (),
             )
@@ -1081,6 +1847,56 @@ mod test {
         .expect("alphabet.Omega is an expensive query and expensive queries are allowed");
     }
 
+    #[test]
+    fn test_query_limits() {
+        let local_config = configmap! {
+            "core" => configmap! {
+                "query_limits" => configmap! {
+                    "limits" => configmap! {
+                        "max_storage_reads" => 1_000,
+                    },
+                    "overrides" => configmap! {
+                        "core.RuntimeInfo" => configmap! {
+                            "max_storage_reads" => 1,
+                        },
+                        "alphabet.Spin" => configmap! {
+                            "max_execution_time_ms" => 10,
+                        },
+                    },
+                },
+            },
+        };
+        let mut mock = Mock::with_local_config(local_config);
+        let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::CheckTx, false);
+
+        let mut store = crate::testing::mock::empty_store();
+        CurrentStore::enter(&mut store, || AlphabetRuntime::migrate(&mut ctx));
+        // Storage reads are accounted for by the root store, as set up for node queries.
+        let mut query = |method: &str| {
+            CurrentStore::enter(core::query_limits::LimitedStore::new(&mut store), || {
+                Dispatcher::<AlphabetRuntime>::dispatch_query(
+                    &mut ctx,
+                    method,
+                    cbor::to_vec(().into_cbor_value()),
+                )
+            })
+        };
+
+        query("alphabet.Alpha").expect("query within its limits should succeed");
+
+        // Runtime info includes the parameters of all modules which requires multiple reads.
+        let err = query("core.RuntimeInfo").expect_err("query exceeding its limits should fail");
+        assert_eq!(err.module, "core");
+        assert_eq!(err.code, 35);
+        assert_eq!(err.message, "query resources exhausted: storage reads");
+
+        // Queries that never access storage should be stopped when using gas.
+        let err = query("alphabet.Spin").expect_err("query exceeding its limits should fail");
+        assert_eq!(err.module, "core");
+        assert_eq!(err.code, 35);
+        assert_eq!(err.message, "query resources exhausted: execution time");
+    }
+
     #[test]
     fn test_dispatch_read_only_call() {
         let mut mock = Mock::default();
@@ -1170,4 +1986,358 @@ mod test {
             Dispatcher::<AlphabetRuntime>::dispatch_tx(&mut ctx, 1024, tx.clone(), 0);
         assert!(matches!(dispatch_result, Err(Error::Aborted)));
     }
+
+    #[test]
+    fn test_dispatch_unmet_dependency() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+
+        AlphabetRuntime::migrate(&mut ctx);
+
+        let first = transaction::Transaction {
+            version: 1,
+            call: transaction::Call {
+                format: transaction::CallFormat::Plain,
+                method: "alphabet.ReadOnly".to_owned(),
+                ..Default::default()
+            },
+            auth_info: transaction::AuthInfo {
+                signer_info: vec![transaction::SignerInfo::new_sigspec(
+                    keys::alice::sigspec(),
+                    0,
+                )],
+                fee: transaction::Fee {
+                    amount: token::BaseUnits::new(0, token::Denomination::NATIVE),
+                    gas: 1000,
+                    consensus_messages: 0,
+                },
+                ..Default::default()
+            },
+        };
+        let first_hash = Hash::digest_bytes(b"first");
+        let mut second = first.clone();
+        second.auth_info.after = vec![first_hash];
+
+        let opts = |tx_index, tx_hash| DispatchOptions {
+            tx_size: 1024,
+            tx_index,
+            tx_hash,
+            ..Default::default()
+        };
+        let second_hash = Hash::digest_bytes(b"second");
+
+        // Dispatch the dependent transaction first.
+        let dispatch_result = Dispatcher::<AlphabetRuntime>::dispatch_tx_opts(
+            &mut ctx,
+            second.clone(),
+            &opts(0, second_hash),
+        )
+        .expect("dispatch should work");
+        match dispatch_result.result {
+            module::CallResult::Failed { module, code, .. } => {
+                assert_eq!(&module, "core");
+                assert_eq!(code, 36);
+            }
+            _ => panic!("transaction with unmet dependencies should fail"),
+        }
+        assert!(
+            !dispatch_result.tags.is_empty(),
+            "transaction with unmet dependencies should still go through the call hooks"
+        );
+
+        // Dispatch the dependency and then the dependent transaction again.
+        let dispatch_result =
+            Dispatcher::<AlphabetRuntime>::dispatch_tx_opts(&mut ctx, first, &opts(1, first_hash))
+                .expect("dispatch should work");
+        assert!(dispatch_result.result.is_success());
+        let dispatch_result = Dispatcher::<AlphabetRuntime>::dispatch_tx_opts(
+            &mut ctx,
+            second,
+            &opts(2, second_hash),
+        )
+        .expect("dispatch should work");
+        assert!(
+            dispatch_result.result.is_success(),
+            "transaction with met dependencies should succeed"
+        );
+    }
+
+    #[test]
+    fn test_handle_message_results() {
+        use types::message::{MessageEvent, MessageEventHookInvocation, PendingMessageResult};
+
+        let pending_results = || -> Vec<PendingMessageResult> {
+            CurrentStore::with(|store| {
+                let store =
+                    storage::TypedStore::new(storage::PrefixStore::new(store, &core::MODULE_NAME));
+                store
+                    .get(core::state::PENDING_MESSAGE_RESULTS)
+                    .unwrap_or_default()
+            })
+        };
+        let event = |index, result| MessageEvent {
+            module: "staking".to_owned(),
+            code: 0,
+            index,
+            result: Some(result),
+        };
+
+        let mut mock = Mock::default();
+        mock.runtime_header.round = 5;
+        mock.runtime_round_results.messages = vec![
+            event(0, cbor::to_value(40u64)),
+            event(1, cbor::to_value("not a number")),
+            event(2, cbor::to_value(40u64)),
+        ];
+        {
+            let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+            AlphabetRuntime::migrate(&mut ctx);
+
+            Dispatcher::<AlphabetRuntime>::save_emitted_message_handlers(vec![
+                MessageEventHookInvocation::new("alphabet.Typed".to_owned(), 2u64),
+                MessageEventHookInvocation::new("alphabet.Typed".to_owned(), 2u64),
+                MessageEventHookInvocation::new("alphabet.Unknown".to_owned(), 0u64),
+            ]);
+            Dispatcher::<AlphabetRuntime>::handle_last_round_messages(&mut ctx)
+                .expect("message results should be handled");
+
+            let tags = ctx.commit().events.into_tags();
+            assert_eq!(tags.len(), 1, "dropped results should emit an event");
+            assert_eq!(tags[0].key, b"core\x00\x00\x00\x04"); // core.MessageResultDropped (code = 4) event
+        }
+
+        let value: u64 = CurrentStore::with(|store| {
+            cbor::from_slice(&store.get(b"message_result").unwrap()).unwrap()
+        });
+        assert_eq!(
+            value, 42,
+            "handler should be invoked with the decoded result"
+        );
+
+        let pending = pending_results();
+        assert_eq!(
+            pending.iter().map(|p| p.event.index).collect::<Vec<_>>(),
+            vec![1],
+            "undecodable results should be persisted and unknown ones dropped"
+        );
+        assert!(pending.iter().all(|p| p.round == 5));
+
+        // Unhandled results should be retained in subsequent rounds.
+        mock.runtime_header.round = 6;
+        mock.runtime_round_results.messages = vec![];
+        {
+            let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+
+            Dispatcher::<AlphabetRuntime>::save_emitted_message_handlers(vec![]);
+            Dispatcher::<AlphabetRuntime>::handle_last_round_messages(&mut ctx)
+                .expect("message results should be handled");
+        }
+
+        let pending = pending_results();
+        assert_eq!(pending.len(), 1);
+        assert!(pending.iter().all(|p| p.round == 5));
+
+        // The number of retained results should be bounded, dropping the oldest ones.
+        let max_pending = PENDING_MESSAGE_RESULT_ROUNDS * mock.max_messages as usize;
+        mock.runtime_header.round = 7;
+        mock.runtime_round_results.messages = (0..max_pending as u32)
+            .map(|index| event(index, cbor::to_value("not a number")))
+            .collect();
+        {
+            let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+            assert_eq!(
+                Dispatcher::<AlphabetRuntime>::max_pending_message_results(&ctx),
+                max_pending
+            );
+
+            Dispatcher::<AlphabetRuntime>::save_emitted_message_handlers(
+                (0..max_pending)
+                    .map(|_| MessageEventHookInvocation::new("alphabet.Typed".to_owned(), 2u64))
+                    .collect(),
+            );
+            Dispatcher::<AlphabetRuntime>::handle_last_round_messages(&mut ctx)
+                .expect("message results should be handled");
+
+            let tags = ctx.commit().events.into_tags();
+            assert_eq!(tags.len(), 1, "dropped results should emit an event");
+            assert_eq!(tags[0].key, b"core\x00\x00\x00\x04"); // core.MessageResultDropped (code = 4) event
+        }
+
+        let pending = pending_results();
+        assert_eq!(pending.len(), max_pending);
+        assert!(
+            pending.iter().all(|p| p.round == 7),
+            "oldest results should be dropped first"
+        );
+    }
+
+    #[test]
+    fn test_run_block_workers() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+        AlphabetRuntime::migrate(&mut ctx);
+
+        let order = || -> Vec<String> {
+            CurrentStore::with(|store| {
+                store
+                    .get(b"workers")
+                    .map(|raw| cbor::from_slice(&raw).unwrap())
+                    .unwrap_or_default()
+            })
+        };
+
+        Dispatcher::<AlphabetRuntime>::run_block_workers(&mut ctx, module::BlockPhase::Begin);
+        assert_eq!(order(), vec!["alphabet.Begin"]);
+
+        Dispatcher::<AlphabetRuntime>::run_block_workers(&mut ctx, module::BlockPhase::End);
+        assert_eq!(
+            order(),
+            vec!["alphabet.Begin", "alphabet.First", "alphabet.Last"],
+            "workers should run in order and failed workers should be rolled back"
+        );
+        assert_eq!(
+            *ctx.value::<u64>(CONTEXT_KEY_BLOCK_WORKER_GAS_USED)
+                .or_default(),
+            40,
+            "gas used by all workers should be accounted for"
+        );
+    }
+
+    #[test]
+    fn test_run_block_workers_storage_gas() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::ExecuteTx, false);
+        AlphabetRuntime::migrate(&mut ctx);
+        Core::set_params(core::Parameters {
+            gas_costs: core::GasCosts {
+                storage_cold_access: 10,
+                ..Default::default()
+            },
+            block_limits: core::BlockLimits {
+                max_block_worker_gas: 15,
+                ..Default::default()
+            },
+            ..Core::params()
+        });
+
+        // The worker only meters 10 gas itself but also accesses storage.
+        Dispatcher::<AlphabetRuntime>::run_block_workers(&mut ctx, module::BlockPhase::Begin);
+        assert!(
+            CurrentStore::with(|store| store.get(b"workers")).is_none(),
+            "worker exceeding its budget due to storage accesses should be rolled back"
+        );
+        assert_eq!(
+            *ctx.value::<u64>(CONTEXT_KEY_BLOCK_WORKER_GAS_USED)
+                .or_default(),
+            15,
+            "worker exceeding its budget should use up the whole budget"
+        );
+    }
+
+    #[test]
+    fn test_check_encrypted_call() {
+        struct RecordingSink(std::sync::Mutex<Vec<String>>);
+
+        impl metrics::MetricsSink for RecordingSink {
+            fn record_tx(&self, metrics: &metrics::TxMetrics<'_>) {
+                self.0.lock().unwrap().push(metrics.method.to_owned());
+            }
+        }
+
+        // The sink can only be installed once, so this is the only test that installs it.
+        let sink = Arc::new(RecordingSink(Default::default()));
+        assert!(
+            metrics::set_sink(sink.clone()).is_ok(),
+            "metrics sink should not be installed yet"
+        );
+
+        let mut mock = Mock::default();
+        mock.epoch = 10;
+        let mut ctx = mock.create_ctx_for_runtime::<AlphabetRuntime>(Mode::CheckTx, true);
+
+        AlphabetRuntime::migrate(&mut ctx);
+
+        // Plaintext that must never become visible during checks.
+        let secret = b"super secret plaintext".to_vec();
+        let contains_secret = |data: &[u8]| data.windows(secret.len()).any(|w| w == secret);
+
+        let client_keypair = deoxysii::generate_key_pair();
+        let call = callformat::encode_call(
+            &ctx,
+            transaction::Call {
+                format: transaction::CallFormat::EncryptedX25519DeoxysII,
+                method: "alphabet.Secret".to_owned(),
+                body: cbor::Value::from(secret.clone()),
+                ..Default::default()
+            },
+            &client_keypair,
+        )
+        .unwrap();
+        let tx = |call| transaction::Transaction {
+            version: 1,
+            call,
+            auth_info: transaction::AuthInfo {
+                signer_info: vec![transaction::SignerInfo::new_sigspec(
+                    keys::alice::sigspec(),
+                    0,
+                )],
+                fee: transaction::Fee {
+                    amount: token::BaseUnits::new(0, token::Denomination::NATIVE),
+                    gas: 1000,
+                    consensus_messages: 0,
+                },
+                ..Default::default()
+            },
+        };
+
+        // Decryption should be deferred during checks.
+        let dispatch_result =
+            Dispatcher::<AlphabetRuntime>::dispatch_tx(&mut ctx, 1024, tx(call.clone()), 0)
+                .expect("encrypted call check should work");
+        assert!(matches!(
+            dispatch_result.result,
+            module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue))
+        ));
+        assert!(matches!(
+            dispatch_result.call_format_metadata,
+            callformat::Metadata::Empty
+        ));
+        for tag in &dispatch_result.tags {
+            assert!(!contains_secret(&tag.key) && !contains_secret(&tag.value));
+        }
+
+        // Envelopes using expired keys should be rejected without decryption.
+        let mut envelope: types::callformat::CallEnvelopeX25519DeoxysII =
+            cbor::from_value(call.body.clone()).unwrap();
+        envelope.epoch = 1;
+        let stale = transaction::Call {
+            body: cbor::to_value(envelope),
+            ..call
+        };
+        let dispatch_result =
+            Dispatcher::<AlphabetRuntime>::dispatch_tx(&mut ctx, 1024, tx(stale), 0)
+                .expect("encrypted call check should work");
+        match dispatch_result.result {
+            module::CallResult::Failed {
+                module, message, ..
+            } => {
+                assert_eq!(&module, "core");
+                assert!(message.contains("epoch too far in the past"));
+            }
+            _ => panic!("call using an expired key should fail"),
+        }
+        for tag in &dispatch_result.tags {
+            assert!(!contains_secret(&tag.key) && !contains_secret(&tag.value));
+        }
+
+        assert!(
+            !sink
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|method| method == "alphabet.Secret"),
+            "no metrics should be recorded for checked encrypted calls"
+        );
+    }
 }
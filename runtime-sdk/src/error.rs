@@ -1,4 +1,6 @@
 //! Error types for runtimes.
+use std::collections::BTreeMap;
+
 pub use oasis_core_runtime::types::Error as RuntimeError;
 
 use crate::{dispatcher, module::CallResult};
@@ -56,6 +58,15 @@ pub trait Error: std::error::Error {
     {
         Err(self)
     }
+
+    /// Descriptions of all errors that can be emitted by this error type. Errors that are
+    /// transparently forwarded from other error types are not included.
+    fn catalog() -> Vec<ErrorInfo>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
 }
 
 impl Error for std::convert::Infallible {
@@ -68,6 +79,78 @@ impl Error for std::convert::Infallible {
     }
 }
 
+/// Description of an error that can be emitted by a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub struct ErrorInfo {
+    /// Name of the module that emits the error.
+    pub module: String,
+    /// Error code, unique within the module.
+    pub code: u32,
+    /// Name of the error.
+    pub name: String,
+    /// Human-readable message template. Placeholders (e.g. `{0}`) refer to error details that are
+    /// only known when the error is emitted.
+    pub message: String,
+}
+
+/// Error code collision between two error descriptions.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("error code {code} of module '{module}' used by both '{first}' and '{second}'")]
+pub struct CollisionError {
+    pub module: String,
+    pub code: u32,
+    pub first: String,
+    pub second: String,
+}
+
+/// Registry of all errors that can be emitted by a runtime, keyed by module name and error code.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorCatalog {
+    errors: BTreeMap<(String, u32), ErrorInfo>,
+}
+
+impl ErrorCatalog {
+    /// Build a catalog from the given error descriptions.
+    ///
+    /// Registering the same description multiple times is allowed, while registering a different
+    /// error under an already used code results in a collision error.
+    pub fn new(errors: Vec<ErrorInfo>) -> Result<Self, CollisionError> {
+        let mut catalog = Self::default();
+        for info in errors {
+            catalog.register(info)?;
+        }
+        Ok(catalog)
+    }
+
+    /// Register the given error description.
+    pub fn register(&mut self, info: ErrorInfo) -> Result<(), CollisionError> {
+        let key = (info.module.clone(), info.code);
+        match self.errors.get(&key) {
+            Some(existing) if existing != &info => Err(CollisionError {
+                module: info.module,
+                code: info.code,
+                first: existing.name.clone(),
+                second: info.name,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                self.errors.insert(key, info);
+                Ok(())
+            }
+        }
+    }
+
+    /// Look up the description of the given error.
+    pub fn get(&self, module: &str, code: u32) -> Option<&ErrorInfo> {
+        self.errors.get(&(module.to_owned(), code))
+    }
+
+    /// All registered error descriptions, ordered by module name and error code.
+    pub fn errors(&self) -> Vec<ErrorInfo> {
+        self.errors.values().cloned().collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -189,4 +272,58 @@ mod test {
             _ => panic!("expected failed result, got: {:?}", result),
         }
     }
+
+    #[test]
+    fn test_error_catalog() {
+        assert_eq!(
+            ChildError::catalog(),
+            vec![
+                ErrorInfo {
+                    module: "test1".to_owned(),
+                    code: 1,
+                    name: "Error1".to_owned(),
+                    message: "first error".to_owned(),
+                },
+                ErrorInfo {
+                    module: "test1".to_owned(),
+                    code: 2,
+                    name: "Error2".to_owned(),
+                    message: "second error".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            ParentError::catalog().len(),
+            1,
+            "transparent errors should not be included"
+        );
+        assert!(ParentParentError::catalog().is_empty());
+
+        let catalog = ErrorCatalog::new(
+            [
+                ChildError::catalog(),
+                ChildError::catalog(),
+                ParentError::catalog(),
+            ]
+            .concat(),
+        )
+        .expect("duplicate registrations should be allowed");
+        assert_eq!(catalog.errors().len(), 3);
+        assert_eq!(catalog.get("test1", 2).unwrap().name, "Error2");
+        assert!(catalog.get("test2", 2).is_none());
+
+        let mut colliding = ChildError::catalog().remove(0);
+        colliding.name = "Other".to_owned();
+        let err = ErrorCatalog::new([ChildError::catalog(), vec![colliding]].concat())
+            .expect_err("colliding registrations should be rejected");
+        assert_eq!(
+            err,
+            CollisionError {
+                module: "test1".to_owned(),
+                code: 1,
+                first: "Error1".to_owned(),
+                second: "Other".to_owned(),
+            }
+        );
+    }
 }
@@ -1,7 +1,16 @@
 //! Event types for runtimes.
 use std::collections::BTreeMap;
 
-use oasis_core_runtime::transaction::tags::{Tag, Tags};
+use oasis_core_runtime::{
+    common::crypto::{mrae::deoxysii, x25519},
+    transaction::tags::{Tag, Tags},
+};
+use rand_core::RngCore;
+
+use crate::{context::Context, modules::core::Error, schema::SchemaRegistry};
+
+/// Personalization of the RNG used for generating ephemeral keys of encrypted events.
+const ENCRYPTED_EVENT_RNG_PERS: &[u8] = b"oasis-runtime-sdk/event: encrypted";
 
 /// An event emitted by the runtime.
 ///
@@ -31,6 +40,12 @@ pub trait Event: Sized + cbor::Encode {
     /// Code uniquely identifying the event.
     fn code(&self) -> u32;
 
+    /// Descriptions of all events of this type, registering the schemas of event fields. Derived
+    /// implementations list every variant.
+    fn catalog(_types: &mut SchemaRegistry) -> Vec<EventInfo> {
+        Vec::new()
+    }
+
     /// Converts an event into an event tag.
     ///
     /// # Key
@@ -58,6 +73,32 @@ impl Event for () {
     }
 }
 
+/// Description of an event that can be emitted by a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EventInfo {
+    /// Name of the module that emits the event.
+    pub module: String,
+    /// Event code, unique within the module.
+    pub code: u32,
+    /// Name of the event.
+    pub name: String,
+    /// Fields of the event in declaration order. Fields of tuple variants are unnamed.
+    #[cbor(optional)]
+    pub fields: Vec<EventFieldInfo>,
+}
+
+/// Description of an event field.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EventFieldInfo {
+    /// Name of the field, empty for unnamed fields.
+    #[cbor(optional)]
+    pub name: String,
+    /// Name of the field's type in the module's type schemas.
+    pub ty: String,
+}
+
 /// Generate an EventTag corresponding to the passed event triple.
 pub fn etag_for_event(module_name: &str, code: u32, value: cbor::Value) -> EventTag {
     EventTag {
@@ -75,6 +116,118 @@ pub struct EventTag {
     pub value: cbor::Value,
 }
 
+impl EventTag {
+    /// Wrap the event value together with the given attribution.
+    pub fn attributed(self, attribution: EventAttribution) -> Self {
+        Self {
+            key: self.key,
+            value: cbor::to_value(AttributedEvent {
+                attribution,
+                event: self.value,
+            }),
+        }
+    }
+}
+
+/// Attribution of an emitted event to the transaction and call that emitted it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub struct EventAttribution {
+    /// Index of the transaction within the block. Not set for events emitted outside of
+    /// transactions (e.g. in block handlers).
+    #[cbor(optional)]
+    pub tx_index: Option<u32>,
+    /// Index of the call within the transaction. Calls are numbered in order of invocation, the
+    /// top-level call has index 0.
+    #[cbor(optional)]
+    pub call_index: u32,
+    /// Depth of the call within the transaction, the top-level call has depth 0.
+    #[cbor(optional)]
+    pub call_depth: u16,
+    /// Name of the module that performed the nested call (e.g. via `subcall::call`). Not set for
+    /// top-level calls.
+    #[cbor(optional)]
+    pub caller_module: Option<String>,
+}
+
+/// An event value together with its attribution.
+///
+/// Event values are only wrapped in case the runtime enables event attribution via the
+/// `EMIT_EVENT_ATTRIBUTION` core module configuration.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct AttributedEvent {
+    pub attribution: EventAttribution,
+    pub event: cbor::Value,
+}
+
+/// Plaintext of an encrypted event.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct EncryptedEventPayload {
+    /// Name of the module that emitted the event.
+    pub module: String,
+    /// Code of the event.
+    pub code: u32,
+    /// CBOR-serialized event value.
+    pub value: cbor::Value,
+}
+
+/// An event encrypted to a specific recipient using the X25519-Deoxys-II scheme.
+///
+/// The payload is sealed using a fresh ephemeral key pair so that only the holder of the
+/// recipient's private key is able to decrypt it.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct EncryptedEvent {
+    /// Ephemeral public key used for X25519.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+    pub pk: x25519::PublicKey,
+    /// Nonce.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+    pub nonce: [u8; deoxysii::NONCE_SIZE],
+    /// Encrypted CBOR-serialized `EncryptedEventPayload`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+    pub data: Vec<u8>,
+}
+
+impl EncryptedEvent {
+    /// Encrypt the given event to the given recipient public key.
+    ///
+    /// This requires the context RNG and is therefore only available in confidential runtimes.
+    pub fn seal<C: Context, E: Event>(
+        ctx: &mut C,
+        recipient: &x25519::PublicKey,
+        event: E,
+    ) -> Result<Self, Error> {
+        let mut rng = ctx.rng(ENCRYPTED_EVENT_RNG_PERS)?;
+        let sk = x25519_dalek::StaticSecret::random_from_rng(&mut rng);
+        let pk = x25519_dalek::PublicKey::from(&sk);
+        let mut nonce = [0u8; deoxysii::NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let payload = EncryptedEventPayload {
+            module: E::module_name().to_string(),
+            code: event.code(),
+            value: cbor::to_value(event),
+        };
+        let data = deoxysii::box_seal(&nonce, cbor::to_vec(payload), vec![], &recipient.0, &sk)
+            .map_err(Error::InvalidArgument)?;
+
+        Ok(Self {
+            pk: pk.into(),
+            nonce,
+            data,
+        })
+    }
+
+    /// Decrypt the event using the recipient's private key.
+    pub fn open(&self, sk: &x25519::PrivateKey) -> anyhow::Result<EncryptedEventPayload> {
+        let data = deoxysii::box_open(&self.nonce, self.data.clone(), vec![], &self.pk.0, &sk.0)?;
+        Ok(cbor::from_slice(&data)?)
+    }
+}
+
 /// Event tags with values accumulated by key.
 pub type EventTags = BTreeMap<Vec<u8>, Vec<cbor::Value>>;
 
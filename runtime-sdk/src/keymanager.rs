@@ -1,7 +1,8 @@
 //! Keymanager interface.
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use tiny_keccak::{Hasher, TupleHash};
+use zeroize::Zeroize;
 
 use oasis_core_keymanager::client::{KeyManagerClient as CoreKeyManagerClient, RemoteClient};
 pub use oasis_core_keymanager::{
@@ -10,7 +11,7 @@ pub use oasis_core_keymanager::{
     policy::TrustedPolicySigners,
 };
 use oasis_core_runtime::{
-    common::namespace::Namespace,
+    common::{crypto::x25519, namespace::Namespace},
     consensus::{beacon::EpochTime, verifier::Verifier},
     future::block_on,
     identity::Identity,
@@ -18,6 +19,8 @@ use oasis_core_runtime::{
     RpcDispatcher,
 };
 
+use crate::context::Context;
+
 /// Key manager interface. This is a runtime context-resident convenience
 /// wrapper to the keymanager configured for the runtime.
 pub(crate) struct KeyManagerClient {
@@ -313,3 +316,161 @@ where
 
     KeyPairId(key_pair_id)
 }
+
+/// Entity key pair ID domain separation context.
+pub const ENTITY_KEY_PAIR_ID_CONTEXT: &[u8] = b"oasis-runtime-sdk/keymanager: entity key pair id";
+
+/// Context key used for caching entity keys.
+const CONTEXT_KEY_ENTITY_KEYS: &str = "keymanager.EntityKeys";
+
+/// Derive a `KeyPairId` for keys of the given entity (e.g. a contract) owned by the given module.
+///
+/// Bumping the generation yields an unrelated set of keys which can be used for key rotation.
+pub fn get_entity_key_pair_id(module: &str, entity: &[u8], generation: u64) -> KeyPairId {
+    get_key_pair_id([
+        ENTITY_KEY_PAIR_ID_CONTEXT,
+        module.as_bytes(),
+        entity,
+        &generation.to_be_bytes(),
+    ])
+}
+
+/// Keys derived for a specific entity. Secret key material is zeroized on drop.
+pub struct EntityKeys {
+    state_key: StateKey,
+    public_key: x25519::PublicKey,
+    private_key: x25519::PrivateKey,
+}
+
+impl EntityKeys {
+    /// Symmetric key (e.g. for encrypting entity state).
+    pub fn symmetric_key(&self) -> &[u8; 32] {
+        &self.state_key.0
+    }
+
+    /// X25519 public key.
+    pub fn public_key(&self) -> &x25519::PublicKey {
+        &self.public_key
+    }
+
+    /// X25519 private key.
+    pub fn private_key(&self) -> &x25519::PrivateKey {
+        &self.private_key
+    }
+}
+
+impl From<KeyPair> for EntityKeys {
+    fn from(kp: KeyPair) -> Self {
+        Self {
+            state_key: kp.state_key.clone(),
+            public_key: kp.input_keypair.pk,
+            private_key: kp.input_keypair.sk.clone(),
+        }
+    }
+}
+
+impl Drop for EntityKeys {
+    fn drop(&mut self) {
+        // The X25519 private key is zeroized by its own drop implementation.
+        self.state_key.0.zeroize();
+    }
+}
+
+/// Extension of the key manager interface with per-entity key derivation.
+pub trait EntityKeyManager: KeyManager {
+    /// Get or create keys of the given entity (e.g. a contract) owned by the given module.
+    ///
+    /// The keys are stable for a given (module, entity, generation) triple.
+    fn get_or_create_entity_keys(
+        &self,
+        module: &str,
+        entity: &[u8],
+        generation: u64,
+    ) -> Result<EntityKeys, KeyManagerError> {
+        self.get_or_create_keys(get_entity_key_pair_id(module, entity, generation))
+            .map(Into::into)
+    }
+}
+
+impl<K: KeyManager + ?Sized> EntityKeyManager for K {}
+
+/// Get or create keys of the given entity owned by the given module using the key manager of the
+/// given context.
+///
+/// Derived keys are cached in the context so that repeated use within the same context does not
+/// result in additional key manager requests.
+pub fn get_or_create_entity_keys<C: Context>(
+    ctx: &mut C,
+    module: &str,
+    entity: &[u8],
+    generation: u64,
+) -> Result<Arc<EntityKeys>, KeyManagerError> {
+    let key_pair_id = get_entity_key_pair_id(module, entity, generation);
+    if let Some(keys) = ctx
+        .value::<HashMap<KeyPairId, Arc<EntityKeys>>>(CONTEXT_KEY_ENTITY_KEYS)
+        .get()
+        .and_then(|cache| cache.get(&key_pair_id))
+    {
+        return Ok(keys.clone());
+    }
+
+    let keys: Arc<EntityKeys> = Arc::new(
+        ctx.key_manager()
+            .ok_or(KeyManagerError::NotInitialized)?
+            .get_or_create_keys(key_pair_id)?
+            .into(),
+    );
+    ctx.value::<HashMap<KeyPairId, Arc<EntityKeys>>>(CONTEXT_KEY_ENTITY_KEYS)
+        .or_default()
+        .insert(key_pair_id, keys.clone());
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Mode, testing::mock};
+
+    #[test]
+    fn test_entity_keys() {
+        let id = get_entity_key_pair_id("test", b"entity", 0);
+        assert_eq!(id, get_entity_key_pair_id("test", b"entity", 0));
+        assert_ne!(id, get_entity_key_pair_id("test", b"entity", 1));
+        assert_ne!(id, get_entity_key_pair_id("test", b"other", 0));
+        assert_ne!(id, get_entity_key_pair_id("other", b"entity", 0));
+
+        let mut mock = mock::Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(Mode::ExecuteTx, true);
+
+        let keys = get_or_create_entity_keys(&mut ctx, "test", b"entity", 0).unwrap();
+        let cached = get_or_create_entity_keys(&mut ctx, "test", b"entity", 0).unwrap();
+        assert!(Arc::ptr_eq(&keys, &cached), "keys should be cached");
+
+        let direct = ctx
+            .key_manager()
+            .unwrap()
+            .get_or_create_entity_keys("test", b"entity", 0)
+            .unwrap();
+        assert_eq!(direct.symmetric_key(), keys.symmetric_key());
+        assert_eq!(
+            direct.public_key().0.as_bytes(),
+            keys.public_key().0.as_bytes()
+        );
+
+        let rotated = get_or_create_entity_keys(&mut ctx, "test", b"entity", 1).unwrap();
+        assert_ne!(
+            rotated.public_key().0.as_bytes(),
+            keys.public_key().0.as_bytes(),
+            "different generations should use different keys"
+        );
+
+        // Entity keys require a key manager.
+        drop(ctx);
+        let mut ctx = mock.create_ctx();
+        assert!(matches!(
+            get_or_create_entity_keys(&mut ctx, "test", b"entity", 0),
+            Err(KeyManagerError::NotInitialized)
+        ));
+    }
+}
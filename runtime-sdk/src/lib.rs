@@ -1,24 +1,33 @@
 //! Oasis runtime SDK.
-#![feature(test)]
+#![feature(associated_type_defaults, test)]
 #![deny(rust_2018_idioms, unreachable_pub)]
 
+pub mod admission;
 pub mod callformat;
+#[cfg(feature = "debug-checkpoints")]
+pub mod checkpoint;
 pub mod config;
 pub mod context;
 pub mod crypto;
 pub mod dispatcher;
 pub mod error;
 pub mod event;
+pub mod export;
 pub mod history;
 pub mod keymanager;
+pub mod metrics;
 pub mod module;
 pub mod modules;
 pub mod runtime;
 pub mod schedule_control;
+pub mod schema;
 pub mod sender;
+pub mod simulation_cache;
 pub mod storage;
 pub mod subcall;
 pub mod testing;
+pub mod time;
+pub mod trace;
 pub mod types;
 
 pub use crate::{
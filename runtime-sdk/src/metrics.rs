@@ -0,0 +1,489 @@
+//! Node-local transaction execution metrics.
+//!
+//! Metrics are only collected when a sink has been installed via `set_sink`. They are never
+//! consulted during execution so they have no effect on consensus.
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use once_cell::sync::OnceCell;
+
+use crate::modules::core::{rate_limit, types::StateStats};
+
+/// Metrics recorded for a single executed transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxMetrics<'a> {
+    /// Called method.
+    pub method: &'a str,
+    /// Amount of gas used by the transaction.
+    pub gas_used: u64,
+    /// Time it took to dispatch the transaction.
+    pub duration: Duration,
+    /// Whether the transaction call was successful.
+    pub success: bool,
+}
+
+/// A sink that receives transaction metrics.
+pub trait MetricsSink: Send + Sync {
+    /// Record metrics for an executed transaction.
+    fn record_tx(&self, metrics: &TxMetrics<'_>);
+
+    /// Record per-module state size statistics at the end of an executed block.
+    fn record_state_stats(&self, _stats: &BTreeMap<String, StateStats>) {
+        // Default implementation does nothing.
+    }
+
+    /// Record the current metrics of the transaction check rate limiter.
+    fn record_rate_limit(&self, _metrics: &rate_limit::Metrics) {
+        // Default implementation does nothing.
+    }
+}
+
+static SINK: OnceCell<Arc<dyn MetricsSink>> = OnceCell::new();
+
+/// Install the node-wide metrics sink. The sink can only be installed once; in case a sink has
+/// already been installed, the given sink is returned as an error.
+pub fn set_sink(sink: Arc<dyn MetricsSink>) -> Result<(), Arc<dyn MetricsSink>> {
+    SINK.set(sink)
+}
+
+/// Whether a node-wide metrics sink has been installed.
+pub fn is_enabled() -> bool {
+    SINK.get().is_some()
+}
+
+/// Record metrics for an executed transaction in the node-wide sink (if any).
+pub fn record_tx(metrics: &TxMetrics<'_>) {
+    if let Some(sink) = SINK.get() {
+        sink.record_tx(metrics);
+    }
+}
+
+/// Record per-module state size statistics in the node-wide sink (if any).
+pub fn record_state_stats(stats: &BTreeMap<String, StateStats>) {
+    if let Some(sink) = SINK.get() {
+        sink.record_state_stats(stats);
+    }
+}
+
+/// Record the current metrics of the transaction check rate limiter in the node-wide sink (if
+/// any).
+pub fn record_rate_limit(metrics: &rate_limit::Metrics) {
+    if let Some(sink) = SINK.get() {
+        sink.record_rate_limit(metrics);
+    }
+}
+
+/// Default upper bounds of the gas usage histogram buckets.
+pub const DEFAULT_GAS_BUCKETS: &[f64] = &[1_000.0, 10_000.0, 100_000.0, 1_000_000.0, 10_000_000.0];
+
+/// Default upper bounds of the dispatch latency histogram buckets (in seconds).
+pub const DEFAULT_LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Default maximum number of distinct method labels.
+pub const DEFAULT_MAX_METHODS: usize = 256;
+
+/// Method label used for all methods once the maximum number of distinct methods is reached.
+pub const OTHER_METHOD_LABEL: &str = "other";
+
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    /// Cumulative counts for each bucket.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, bounds: &[f64], value: f64) {
+        self.buckets.resize(bounds.len(), 0);
+        for (bucket, bound) in self.buckets.iter_mut().zip(bounds) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, method: &str, bounds: &[f64]) {
+        for (bucket, bound) in self.buckets.iter().zip(bounds) {
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{method=\"{method}\",le=\"{bound}\"}} {bucket}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{method=\"{method}\",le=\"+Inf\"}} {}",
+            self.count
+        );
+        let _ = writeln!(out, "{name}_sum{{method=\"{method}\"}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{method=\"{method}\"}} {}", self.count);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct MethodMetrics {
+    txs: u64,
+    failed: u64,
+    gas_used: Histogram,
+    duration: Histogram,
+}
+
+/// Metrics sink that aggregates metrics per method and renders them in the Prometheus text
+/// exposition format.
+///
+/// Method names are chosen by transaction senders, so only a limited number of distinct methods
+/// is tracked. Metrics of any further methods are aggregated under the `other` method label.
+///
+/// Serving the rendered metrics (e.g. over HTTP) is left to the node operator.
+pub struct PrometheusSink {
+    gas_buckets: Vec<f64>,
+    latency_buckets: Vec<f64>,
+    max_methods: usize,
+    methods: Mutex<BTreeMap<String, MethodMetrics>>,
+    state_stats: Mutex<BTreeMap<String, StateStats>>,
+    rate_limit: Mutex<Option<rate_limit::Metrics>>,
+}
+
+impl PrometheusSink {
+    /// Create a new sink using the default histogram buckets.
+    pub fn new() -> Self {
+        Self::with_buckets(
+            DEFAULT_GAS_BUCKETS.to_vec(),
+            DEFAULT_LATENCY_BUCKETS.to_vec(),
+        )
+    }
+
+    /// Create a new sink using the given upper bounds for the gas usage and latency (in seconds)
+    /// histogram buckets.
+    pub fn with_buckets(gas_buckets: Vec<f64>, latency_buckets: Vec<f64>) -> Self {
+        Self {
+            gas_buckets,
+            latency_buckets,
+            max_methods: DEFAULT_MAX_METHODS,
+            methods: Mutex::new(BTreeMap::new()),
+            state_stats: Mutex::new(BTreeMap::new()),
+            rate_limit: Mutex::new(None),
+        }
+    }
+
+    /// Set the maximum number of distinct method labels.
+    pub fn with_max_methods(mut self, max_methods: usize) -> Self {
+        self.max_methods = max_methods;
+        self
+    }
+
+    /// Render all collected metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let methods = self.methods.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP oasis_runtime_txs_total Number of executed transactions."
+        );
+        let _ = writeln!(out, "# TYPE oasis_runtime_txs_total counter");
+        for (method, metrics) in methods.iter() {
+            let _ = writeln!(
+                out,
+                "oasis_runtime_txs_total{{method=\"{}\"}} {}",
+                escape_label(method),
+                metrics.txs
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP oasis_runtime_txs_failed_total Number of executed transactions that failed."
+        );
+        let _ = writeln!(out, "# TYPE oasis_runtime_txs_failed_total counter");
+        for (method, metrics) in methods.iter() {
+            let _ = writeln!(
+                out,
+                "oasis_runtime_txs_failed_total{{method=\"{}\"}} {}",
+                escape_label(method),
+                metrics.failed
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP oasis_runtime_tx_gas_used Gas used by executed transactions."
+        );
+        let _ = writeln!(out, "# TYPE oasis_runtime_tx_gas_used histogram");
+        for (method, metrics) in methods.iter() {
+            metrics.gas_used.render(
+                &mut out,
+                "oasis_runtime_tx_gas_used",
+                &escape_label(method),
+                &self.gas_buckets,
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP oasis_runtime_tx_duration_seconds Time spent dispatching executed transactions."
+        );
+        let _ = writeln!(out, "# TYPE oasis_runtime_tx_duration_seconds histogram");
+        for (method, metrics) in methods.iter() {
+            metrics.duration.render(
+                &mut out,
+                "oasis_runtime_tx_duration_seconds",
+                &escape_label(method),
+                &self.latency_buckets,
+            );
+        }
+
+        let state_stats = self.state_stats.lock().unwrap();
+        if !state_stats.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP oasis_runtime_state_keys Number of keys in module state."
+            );
+            let _ = writeln!(out, "# TYPE oasis_runtime_state_keys gauge");
+            for (module, stats) in state_stats.iter() {
+                let _ = writeln!(
+                    out,
+                    "oasis_runtime_state_keys{{module=\"{}\"}} {}",
+                    escape_label(module),
+                    stats.keys
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "# HELP oasis_runtime_state_bytes Number of bytes used by module state."
+            );
+            let _ = writeln!(out, "# TYPE oasis_runtime_state_bytes gauge");
+            for (module, stats) in state_stats.iter() {
+                let _ = writeln!(
+                    out,
+                    "oasis_runtime_state_bytes{{module=\"{}\"}} {}",
+                    escape_label(module),
+                    stats.bytes
+                );
+            }
+        }
+
+        if let Some(rate_limit) = *self.rate_limit.lock().unwrap() {
+            for (name, help, value) in [
+                (
+                    "oasis_runtime_rate_limit_allowed_total",
+                    "Number of transaction checks allowed by the rate limiter.",
+                    rate_limit.allowed,
+                ),
+                (
+                    "oasis_runtime_rate_limit_limited_total",
+                    "Number of transaction checks rejected by the rate limiter.",
+                    rate_limit.limited,
+                ),
+                (
+                    "oasis_runtime_rate_limit_rechecked_total",
+                    "Number of transaction re-checks not charged by the rate limiter.",
+                    rate_limit.rechecked,
+                ),
+            ] {
+                let _ = writeln!(out, "# HELP {name} {help}");
+                let _ = writeln!(out, "# TYPE {name} counter");
+                let _ = writeln!(out, "{name} {value}");
+            }
+
+            let _ = writeln!(
+                out,
+                "# HELP oasis_runtime_rate_limit_tracked_accounts Number of accounts tracked by the rate limiter."
+            );
+            let _ = writeln!(
+                out,
+                "# TYPE oasis_runtime_rate_limit_tracked_accounts gauge"
+            );
+            let _ = writeln!(
+                out,
+                "oasis_runtime_rate_limit_tracked_accounts {}",
+                rate_limit.tracked_accounts
+            );
+        }
+
+        out
+    }
+}
+
+impl Default for PrometheusSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn record_tx(&self, metrics: &TxMetrics<'_>) {
+        let mut methods = self.methods.lock().unwrap();
+        // Bound the number of distinct labels, reserving one for the aggregate of other methods.
+        let method = if methods.contains_key(metrics.method)
+            || methods.len() < self.max_methods.saturating_sub(1)
+        {
+            metrics.method
+        } else {
+            OTHER_METHOD_LABEL
+        };
+        let entry = methods.entry(method.to_owned()).or_default();
+        entry.txs += 1;
+        if !metrics.success {
+            entry.failed += 1;
+        }
+        entry
+            .gas_used
+            .observe(&self.gas_buckets, metrics.gas_used as f64);
+        entry
+            .duration
+            .observe(&self.latency_buckets, metrics.duration.as_secs_f64());
+    }
+
+    fn record_state_stats(&self, stats: &BTreeMap<String, StateStats>) {
+        *self.state_stats.lock().unwrap() = stats.clone();
+    }
+
+    fn record_rate_limit(&self, metrics: &rate_limit::Metrics) {
+        *self.rate_limit.lock().unwrap() = Some(*metrics);
+    }
+}
+
+/// Escape a label value according to the Prometheus text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_sink() {
+        let sink = PrometheusSink::with_buckets(vec![100.0, 1_000.0], vec![0.5]);
+        sink.record_tx(&TxMetrics {
+            method: "accounts.Transfer",
+            gas_used: 100,
+            duration: Duration::from_millis(100),
+            success: true,
+        });
+        sink.record_tx(&TxMetrics {
+            method: "accounts.Transfer",
+            gas_used: 500,
+            duration: Duration::from_secs(1),
+            success: false,
+        });
+
+        assert_eq!(
+            sink.render(),
+            r#"# HELP oasis_runtime_txs_total Number of executed transactions.
+# TYPE oasis_runtime_txs_total counter
+oasis_runtime_txs_total{method="accounts.Transfer"} 2
+# HELP oasis_runtime_txs_failed_total Number of executed transactions that failed.
+# TYPE oasis_runtime_txs_failed_total counter
+oasis_runtime_txs_failed_total{method="accounts.Transfer"} 1
+# HELP oasis_runtime_tx_gas_used Gas used by executed transactions.
+# TYPE oasis_runtime_tx_gas_used histogram
+oasis_runtime_tx_gas_used_bucket{method="accounts.Transfer",le="100"} 1
+oasis_runtime_tx_gas_used_bucket{method="accounts.Transfer",le="1000"} 2
+oasis_runtime_tx_gas_used_bucket{method="accounts.Transfer",le="+Inf"} 2
+oasis_runtime_tx_gas_used_sum{method="accounts.Transfer"} 600
+oasis_runtime_tx_gas_used_count{method="accounts.Transfer"} 2
+# HELP oasis_runtime_tx_duration_seconds Time spent dispatching executed transactions.
+# TYPE oasis_runtime_tx_duration_seconds histogram
+oasis_runtime_tx_duration_seconds_bucket{method="accounts.Transfer",le="0.5"} 1
+oasis_runtime_tx_duration_seconds_bucket{method="accounts.Transfer",le="+Inf"} 2
+oasis_runtime_tx_duration_seconds_sum{method="accounts.Transfer"} 1.1
+oasis_runtime_tx_duration_seconds_count{method="accounts.Transfer"} 2
+"#
+        );
+    }
+
+    #[test]
+    fn test_prometheus_sink_state_stats() {
+        let sink = PrometheusSink::new();
+        sink.record_state_stats(&BTreeMap::from([
+            ("accounts".to_owned(), StateStats { keys: 2, bytes: 80 }),
+            ("core".to_owned(), StateStats { keys: 1, bytes: 10 }),
+        ]));
+
+        assert_eq!(
+            sink.render(),
+            r#"# HELP oasis_runtime_txs_total Number of executed transactions.
+# TYPE oasis_runtime_txs_total counter
+# HELP oasis_runtime_txs_failed_total Number of executed transactions that failed.
+# TYPE oasis_runtime_txs_failed_total counter
+# HELP oasis_runtime_tx_gas_used Gas used by executed transactions.
+# TYPE oasis_runtime_tx_gas_used histogram
+# HELP oasis_runtime_tx_duration_seconds Time spent dispatching executed transactions.
+# TYPE oasis_runtime_tx_duration_seconds histogram
+# HELP oasis_runtime_state_keys Number of keys in module state.
+# TYPE oasis_runtime_state_keys gauge
+oasis_runtime_state_keys{module="accounts"} 2
+oasis_runtime_state_keys{module="core"} 1
+# HELP oasis_runtime_state_bytes Number of bytes used by module state.
+# TYPE oasis_runtime_state_bytes gauge
+oasis_runtime_state_bytes{module="accounts"} 80
+oasis_runtime_state_bytes{module="core"} 10
+"#
+        );
+    }
+
+    #[test]
+    fn test_prometheus_sink_rate_limit() {
+        let sink = PrometheusSink::new();
+        sink.record_rate_limit(&rate_limit::Metrics {
+            allowed: 5,
+            limited: 2,
+            rechecked: 3,
+            tracked_accounts: 4,
+        });
+
+        assert!(sink.render().ends_with(
+            r#"# HELP oasis_runtime_rate_limit_allowed_total Number of transaction checks allowed by the rate limiter.
+# TYPE oasis_runtime_rate_limit_allowed_total counter
+oasis_runtime_rate_limit_allowed_total 5
+# HELP oasis_runtime_rate_limit_limited_total Number of transaction checks rejected by the rate limiter.
+# TYPE oasis_runtime_rate_limit_limited_total counter
+oasis_runtime_rate_limit_limited_total 2
+# HELP oasis_runtime_rate_limit_rechecked_total Number of transaction re-checks not charged by the rate limiter.
+# TYPE oasis_runtime_rate_limit_rechecked_total counter
+oasis_runtime_rate_limit_rechecked_total 3
+# HELP oasis_runtime_rate_limit_tracked_accounts Number of accounts tracked by the rate limiter.
+# TYPE oasis_runtime_rate_limit_tracked_accounts gauge
+oasis_runtime_rate_limit_tracked_accounts 4
+"#
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_sink_max_methods() {
+        let sink = PrometheusSink::new().with_max_methods(3);
+        for method in ["a.A", "b.B", "c.C", "d.D", "a.A"] {
+            sink.record_tx(&TxMetrics {
+                method,
+                gas_used: 100,
+                duration: Duration::from_millis(1),
+                success: true,
+            });
+        }
+
+        let methods = sink.methods.lock().unwrap();
+        assert_eq!(
+            methods.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["a.A", "b.B", "other"]
+        );
+        assert_eq!(methods["a.A"].txs, 2);
+        assert_eq!(methods["other"].txs, 2);
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
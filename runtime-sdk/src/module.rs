@@ -9,14 +9,18 @@ use impl_trait_for_tuples::impl_for_tuples;
 
 use crate::{
     context::{Context, TxContext},
+    core::common::crypto::hash::Hash,
     dispatcher, error,
     error::Error as _,
     event, modules,
-    modules::core::types::{MethodHandlerInfo, ModuleInfo},
+    modules::core::types::{MethodHandlerInfo, MethodMetadata, ModuleInfo, ModuleMetadata},
+    schema::SchemaRegistry,
     storage,
     storage::{CurrentStore, Prefix},
     types::{
+        address::Address,
         message::MessageResult,
+        token,
         transaction::{self, AuthInfo, Call, Transaction, UnverifiedTransaction},
     },
 };
@@ -209,6 +213,13 @@ pub trait MethodHandler {
         vec![]
     }
 
+    /// Lists all RPC methods exposed by this module together with the names of their argument
+    /// and result types, registering the schemas of those types. Like `supported_methods`, the
+    /// result is informational only.
+    fn method_metadata(_types: &mut SchemaRegistry) -> Vec<MethodMetadata> {
+        vec![]
+    }
+
     /// Checks whether the given query method is tagged as expensive.
     fn is_expensive_query(_method: &str) -> bool {
         false
@@ -219,6 +230,12 @@ pub trait MethodHandler {
         false
     }
 
+    /// Checks whether the given query requires caller authentication via a signed query envelope
+    /// on confidential runtimes.
+    fn is_authenticated_query(_method: &str) -> bool {
+        false
+    }
+
     /// Checks whether the given call is allowed to be called interactively via read-only
     /// transactions.
     fn is_allowed_interactive_call(_method: &str) -> bool {
@@ -311,6 +328,15 @@ impl MethodHandler for Tuple {
         false
     }
 
+    fn is_authenticated_query(method: &str) -> bool {
+        for_tuples!( #(
+            if Tuple::is_authenticated_query(method) {
+                return true;
+            }
+        )* );
+        false
+    }
+
     fn is_allowed_interactive_call(method: &str) -> bool {
         for_tuples!( #(
             if Tuple::is_allowed_interactive_call(method) {
@@ -379,6 +405,19 @@ pub trait TransactionHandler {
         Ok(())
     }
 
+    /// Perform any action after the call has been decoded and authorized, right before it is
+    /// dispatched to its handler, within the transaction context.
+    ///
+    /// In contrast to `before_handle_call`, the call has already been decoded according to its
+    /// call format so the method is always available.
+    fn before_authorized_call_dispatch<C: TxContext>(
+        _ctx: &mut C,
+        _call: &Call,
+    ) -> Result<(), modules::core::Error> {
+        // Default implementation doesn't do anything.
+        Ok(())
+    }
+
     /// Perform any action after call, within the transaction context.
     ///
     /// If an error is returned the transaction call fails and updates are rolled back.
@@ -396,10 +435,16 @@ pub trait TransactionHandler {
     }
 }
 
+#[allow(clippy::type_complexity)]
 #[impl_for_tuples(30)]
+#[tuple_types_custom_trait_bound(TransactionHandler + ModuleInfoHandler)]
 impl TransactionHandler for Tuple {
     fn approve_raw_tx<C: Context>(ctx: &mut C, tx: &[u8]) -> Result<(), modules::core::Error> {
-        for_tuples!( #( Tuple::approve_raw_tx(ctx, tx)?; )* );
+        let hooks: &[fn(&mut C, &[u8]) -> Result<(), modules::core::Error>] =
+            &[for_tuples!( #( Tuple::approve_raw_tx ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, tx)?;
+        }
         Ok(())
     }
 
@@ -407,7 +452,11 @@ impl TransactionHandler for Tuple {
         ctx: &mut C,
         utx: &UnverifiedTransaction,
     ) -> Result<(), modules::core::Error> {
-        for_tuples!( #( Tuple::approve_unverified_tx(ctx, utx)?; )* );
+        let hooks: &[fn(&mut C, &UnverifiedTransaction) -> Result<(), modules::core::Error>] =
+            &[for_tuples!( #( Tuple::approve_unverified_tx ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, utx)?;
+        }
         Ok(())
     }
 
@@ -416,12 +465,18 @@ impl TransactionHandler for Tuple {
         scheme: &str,
         body: &[u8],
     ) -> Result<Option<Transaction>, modules::core::Error> {
-        for_tuples!( #(
-            let decoded = Tuple::decode_tx(ctx, scheme, body)?;
-            if (decoded.is_some()) {
+        let hooks: &[fn(
+            &mut C,
+            &str,
+            &[u8],
+        ) -> Result<Option<Transaction>, modules::core::Error>] =
+            &[for_tuples!( #( Tuple::decode_tx ),* )];
+        for index in Self::module_order() {
+            let decoded = hooks[index](ctx, scheme, body)?;
+            if decoded.is_some() {
                 return Ok(decoded);
             }
-        )* );
+        }
         Ok(None)
     }
 
@@ -429,7 +484,11 @@ impl TransactionHandler for Tuple {
         ctx: &mut C,
         tx: &Transaction,
     ) -> Result<(), modules::core::Error> {
-        for_tuples!( #( Tuple::authenticate_tx(ctx, tx)?; )* );
+        let hooks: &[fn(&mut C, &Transaction) -> Result<(), modules::core::Error>] =
+            &[for_tuples!( #( Tuple::authenticate_tx ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, tx)?;
+        }
         Ok(())
     }
 
@@ -437,7 +496,23 @@ impl TransactionHandler for Tuple {
         ctx: &mut C,
         call: &Call,
     ) -> Result<(), modules::core::Error> {
-        for_tuples!( #( Tuple::before_handle_call(ctx, call)?; )* );
+        let hooks: &[fn(&mut C, &Call) -> Result<(), modules::core::Error>] =
+            &[for_tuples!( #( Tuple::before_handle_call ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, call)?;
+        }
+        Ok(())
+    }
+
+    fn before_authorized_call_dispatch<C: TxContext>(
+        ctx: &mut C,
+        call: &Call,
+    ) -> Result<(), modules::core::Error> {
+        let hooks: &[fn(&mut C, &Call) -> Result<(), modules::core::Error>] =
+            &[for_tuples!( #( Tuple::before_authorized_call_dispatch ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, call)?;
+        }
         Ok(())
     }
 
@@ -445,17 +520,66 @@ impl TransactionHandler for Tuple {
         ctx: &mut C,
         mut result: CallResult,
     ) -> Result<CallResult, modules::core::Error> {
-        for_tuples!( #(
-            result = Tuple::after_handle_call(ctx, result)?;
-        )* );
+        let hooks: &[fn(&mut C, CallResult) -> Result<CallResult, modules::core::Error>] =
+            &[for_tuples!( #( Tuple::after_handle_call ),* )];
+        for index in Self::module_order() {
+            result = hooks[index](ctx, result)?;
+        }
         Ok(result)
     }
 
     fn after_dispatch_tx<C: Context>(ctx: &mut C, tx_auth_info: &AuthInfo, result: &CallResult) {
-        for_tuples!( #( Tuple::after_dispatch_tx(ctx, tx_auth_info, result); )* );
+        let hooks: &[fn(&mut C, &AuthInfo, &CallResult)] =
+            &[for_tuples!( #( Tuple::after_dispatch_tx ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx, tx_auth_info, result);
+        }
+    }
+}
+
+/// Fee grant handler.
+///
+/// Used by the accounts module to pay transaction fees from allowances that other accounts have
+/// granted to the fee payer. The default implementation doesn't support fee grants.
+pub trait FeeGrantHandler {
+    /// Return the granter whose allowance pays the fee of the given transaction on behalf of the
+    /// payer, if any. Only called for transactions with a non-zero fee.
+    fn fee_granter<C: Context>(
+        _ctx: &mut C,
+        tx: &Transaction,
+        _payer: Address,
+    ) -> Result<Option<Address>, modules::core::Error> {
+        // Default implementation doesn't support fee grants.
+        if tx.auth_info.fee_granter.is_some() {
+            return Err(modules::core::Error::MalformedTransaction(anyhow::anyhow!(
+                "fee grants are not supported"
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Consume the given fee from the allowance given by the granter to the payer.
+    fn use_fee_grant<C: Context>(
+        _ctx: &mut C,
+        _granter: Address,
+        _payer: Address,
+        _fee: &token::BaseUnits,
+    ) -> Result<(), modules::core::Error> {
+        Ok(())
+    }
+
+    /// Return a refunded part of the fee to the allowance given by the granter to the payer.
+    fn refund_fee_grant<C: Context>(
+        _ctx: &mut C,
+        _granter: Address,
+        _payer: Address,
+        _refund: &token::BaseUnits,
+    ) {
     }
 }
 
+impl FeeGrantHandler for () {}
+
 /// Migration handler.
 pub trait MigrationHandler {
     /// Genesis state type.
@@ -479,6 +603,7 @@ pub trait MigrationHandler {
 
 #[allow(clippy::type_complexity)]
 #[impl_for_tuples(30)]
+#[tuple_types_custom_trait_bound(MigrationHandler + ModuleInfoHandler)]
 impl MigrationHandler for Tuple {
     for_tuples!( type Genesis = ( #( Tuple::Genesis ),* ); );
 
@@ -487,9 +612,104 @@ impl MigrationHandler for Tuple {
         meta: &mut modules::core::types::Metadata,
         genesis: Self::Genesis,
     ) -> bool {
-        [for_tuples!( #( Tuple::init_or_migrate(ctx, meta, genesis.Tuple) ),* )]
-            .iter()
-            .any(|x| *x)
+        let migrations: &mut [Option<
+            Box<dyn FnOnce(&mut C, &mut modules::core::types::Metadata) -> bool + '_>,
+        >] = &mut [for_tuples!( #(
+            Some(Box::new(move |ctx: &mut C, meta: &mut modules::core::types::Metadata| {
+                Tuple::init_or_migrate(ctx, meta, genesis.Tuple)
+            }))
+        ),* )];
+
+        let mut changed = false;
+        for index in Self::module_order() {
+            let migrate = migrations[index].take().unwrap();
+            changed |= migrate(ctx, meta);
+        }
+        changed
+    }
+}
+
+/// State export handler.
+pub trait StateExporter: MigrationHandler {
+    /// Export the current module state in the genesis format, such that initializing a new runtime
+    /// from the exported genesis results in equivalent state.
+    ///
+    /// The export must be deterministic for a given state.
+    fn export_state() -> Self::Genesis;
+}
+
+#[impl_for_tuples(30)]
+#[tuple_types_custom_trait_bound(StateExporter + ModuleInfoHandler)]
+impl StateExporter for Tuple {
+    #[allow(clippy::unused_unit)]
+    fn export_state() -> Self::Genesis {
+        for_tuples!( ( #( Tuple::export_state() ),* ) )
+    }
+}
+
+/// Phase of block processing in which a block worker runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockPhase {
+    /// After the `begin_block` hooks and before any transactions have been executed.
+    Begin,
+    /// After all transactions have been executed and before the `end_block` hooks.
+    End,
+}
+
+/// Periodic work performed by a module in each block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockWorker {
+    /// Unique worker name (e.g., `rewards.Payout`).
+    pub name: &'static str,
+    /// Block phase in which the worker runs.
+    pub phase: BlockPhase,
+    /// Ordering priority. Workers with a lower priority run first and ties are broken by name.
+    pub priority: i32,
+    /// Maximum amount of gas that the worker can use in a single block. The special value of 0
+    /// means that only the block-wide worker gas limit applies.
+    pub max_gas: u64,
+}
+
+/// Gas meter used by block workers.
+#[derive(Clone, Debug)]
+pub struct BlockGasMeter {
+    limit: u64,
+    used: u64,
+}
+
+impl BlockGasMeter {
+    /// Create a new gas meter with the given limit.
+    pub fn new(limit: u64) -> Self {
+        Self { limit, used: 0 }
+    }
+
+    /// Attempt to use gas. If the gas specified would cause the limit to be exceeded, fails with
+    /// Error::OutOfGas and gas usage is not increased.
+    pub fn use_gas(&mut self, gas: u64) -> Result<(), modules::core::Error> {
+        let used = self
+            .used
+            .checked_add(gas)
+            .ok_or(modules::core::Error::GasOverflow)?;
+        if used > self.limit {
+            return Err(modules::core::Error::OutOfGas(self.limit, used));
+        }
+        self.used = used;
+        Ok(())
+    }
+
+    /// Amount of gas used so far.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    /// Use up all remaining gas.
+    pub fn exhaust(&mut self) {
+        self.used = self.limit;
+    }
+
+    /// Amount of gas remaining.
+    pub fn remaining(&self) -> u64 {
+        self.limit - self.used
     }
 }
 
@@ -506,16 +726,67 @@ pub trait BlockHandler {
     fn end_block<C: Context>(_ctx: &mut C) {
         // Default implementation doesn't do anything.
     }
+
+    /// Block workers provided by the module.
+    ///
+    /// Unlike the `begin_block` and `end_block` hooks, workers run in a well-defined order and
+    /// are metered so they should be preferred for heavy periodic work. Work that does not fit
+    /// into the gas budget should be resumed in a later block.
+    fn block_workers() -> Vec<BlockWorker> {
+        // Default implementation doesn't provide any workers.
+        Vec::new()
+    }
+
+    /// Run the block worker with the given name, using the given gas meter.
+    ///
+    /// If an error is returned, any changes made by the worker are rolled back.
+    fn run_block_worker<C: Context>(
+        _ctx: &mut C,
+        _name: &str,
+        _meter: &mut BlockGasMeter,
+    ) -> DispatchResult<(), Result<(), modules::core::Error>> {
+        // Default implementation doesn't provide any workers.
+        DispatchResult::Unhandled(())
+    }
 }
 
 #[impl_for_tuples(30)]
+#[tuple_types_custom_trait_bound(BlockHandler + ModuleInfoHandler)]
 impl BlockHandler for Tuple {
     fn begin_block<C: Context>(ctx: &mut C) {
-        for_tuples!( #( Tuple::begin_block(ctx); )* );
+        let hooks: &[fn(&mut C)] = &[for_tuples!( #( Tuple::begin_block ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx);
+        }
     }
 
     fn end_block<C: Context>(ctx: &mut C) {
-        for_tuples!( #( Tuple::end_block(ctx); )* );
+        let hooks: &[fn(&mut C)] = &[for_tuples!( #( Tuple::end_block ),* )];
+        for index in Self::module_order() {
+            hooks[index](ctx);
+        }
+    }
+
+    fn block_workers() -> Vec<BlockWorker> {
+        let mut workers = Vec::new();
+        for_tuples!( #( workers.extend(Tuple::block_workers()); )* );
+        workers
+    }
+
+    fn run_block_worker<C: Context>(
+        ctx: &mut C,
+        name: &str,
+        meter: &mut BlockGasMeter,
+    ) -> DispatchResult<(), Result<(), modules::core::Error>> {
+        // Return on first handler that can run the worker.
+        for_tuples!( #(
+            match Tuple::run_block_worker(ctx, name, meter) {
+                DispatchResult::Handled(result) => return DispatchResult::Handled(result),
+                DispatchResult::Unhandled(()) => {}
+            }
+        )* );
+
+        DispatchResult::Unhandled(())
     }
 }
 
@@ -537,25 +808,106 @@ impl InvariantHandler for Tuple {
     }
 }
 
+/// Signing preview handler.
+pub trait PreviewHandler {
+    /// Render the body of a call to the given method as signing preview fields. Returns `None` if
+    /// the method is not handled or the body cannot be decoded.
+    fn preview_call(_method: &str, _body: &cbor::Value) -> Option<Vec<transaction::PreviewField>> {
+        // Default implementation doesn't render anything.
+        None
+    }
+}
+
+#[impl_for_tuples(30)]
+impl PreviewHandler for Tuple {
+    /// Render the call body using the first module in the tuple that handles the method.
+    fn preview_call(method: &str, body: &cbor::Value) -> Option<Vec<transaction::PreviewField>> {
+        for_tuples!( #(
+            if let Some(fields) = Tuple::preview_call(method, body) {
+                return Some(fields);
+            }
+        )* );
+        None
+    }
+}
+
 /// Info handler.
 pub trait ModuleInfoHandler {
     /// Reports info about the module (or modules, if `Self` is a tuple).
     fn module_info<C: Context>(_ctx: &mut C) -> BTreeMap<String, ModuleInfo>;
+
+    /// Reports descriptions of all errors that can be emitted by the module (or modules, if `Self`
+    /// is a tuple).
+    fn error_catalog() -> Vec<error::ErrorInfo>;
+
+    /// Reports schema metadata (methods, events and the schemas of the types they refer to) for
+    /// the module (or modules, if `Self` is a tuple).
+    fn module_metadata() -> BTreeMap<String, ModuleMetadata>;
+
+    /// Reports the declared dependencies of the module (or modules, if `Self` is a tuple) in
+    /// declaration order.
+    fn module_dependencies() -> Vec<(&'static str, &'static [&'static str])>;
+
+    /// Reports the order, derived from the declared dependencies, in which the modules (if `Self`
+    /// is a tuple) are initialized and their hooks invoked, as indices into the tuple.
+    fn module_order() -> Vec<usize>;
+
+    /// Reports the current parameters of the module (or modules, if `Self` is a tuple).
+    fn module_params() -> BTreeMap<String, cbor::Value>;
 }
 
 impl<M: Module + MethodHandler> ModuleInfoHandler for M {
     fn module_info<C: Context>(_ctx: &mut C) -> BTreeMap<String, ModuleInfo> {
+        let params = Self::params().into_cbor_value();
+        let params_digest = Hash::digest_bytes(&cbor::to_vec(params.clone()));
+
         let mut info = BTreeMap::new();
         info.insert(
             Self::NAME.to_string(),
             ModuleInfo {
                 version: Self::VERSION,
-                params: Self::params().into_cbor_value(),
+                params,
+                params_digest,
                 methods: Self::supported_methods(),
+                features: Self::features(),
             },
         );
         info
     }
+
+    fn error_catalog() -> Vec<error::ErrorInfo> {
+        <Self::Error as error::Error>::catalog()
+    }
+
+    fn module_metadata() -> BTreeMap<String, ModuleMetadata> {
+        let mut types = SchemaRegistry::default();
+        let methods = Self::method_metadata(&mut types);
+        let events = <Self::Event as event::Event>::catalog(&mut types);
+
+        let mut metadata = BTreeMap::new();
+        metadata.insert(
+            Self::NAME.to_string(),
+            ModuleMetadata {
+                version: Self::VERSION,
+                methods,
+                events,
+                types: types.into_types(),
+            },
+        );
+        metadata
+    }
+
+    fn module_dependencies() -> Vec<(&'static str, &'static [&'static str])> {
+        vec![(Self::NAME, Self::DEPENDENCIES)]
+    }
+
+    fn module_order() -> Vec<usize> {
+        vec![0]
+    }
+
+    fn module_params() -> BTreeMap<String, cbor::Value> {
+        BTreeMap::from([(Self::NAME.to_string(), Self::params().into_cbor_value())])
+    }
 }
 
 #[impl_for_tuples(30)]
@@ -568,6 +920,158 @@ impl ModuleInfoHandler for Tuple {
         )* );
         merged
     }
+
+    #[allow(clippy::let_and_return)]
+    fn error_catalog() -> Vec<error::ErrorInfo> {
+        let mut merged = Vec::new();
+        for_tuples!( #(
+            merged.extend(Tuple::error_catalog());
+        )* );
+        merged
+    }
+
+    #[allow(clippy::let_and_return)]
+    fn module_metadata() -> BTreeMap<String, ModuleMetadata> {
+        let mut merged = BTreeMap::new();
+        for_tuples!( #(
+            merged.extend(Tuple::module_metadata());
+        )* );
+        merged
+    }
+
+    #[allow(clippy::let_and_return)]
+    fn module_dependencies() -> Vec<(&'static str, &'static [&'static str])> {
+        let mut merged = Vec::new();
+        for_tuples!( #(
+            merged.extend(Tuple::module_dependencies());
+        )* );
+        merged
+    }
+
+    fn module_order() -> Vec<usize> {
+        order_elements(&[for_tuples!( #( Tuple::module_dependencies() ),* )])
+    }
+
+    #[allow(clippy::let_and_return)]
+    fn module_params() -> BTreeMap<String, cbor::Value> {
+        let mut merged = BTreeMap::new();
+        for_tuples!( #(
+            merged.extend(Tuple::module_params());
+        )* );
+        merged
+    }
+}
+
+/// Error resulting from module dependency validation.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum DependencyError {
+    #[error("module '{0}' is declared more than once")]
+    Duplicate(String),
+
+    #[error("module '{module}' depends on missing module '{dependency}'")]
+    Missing { module: String, dependency: String },
+
+    #[error("module dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Derive the module order from the given declared dependencies.
+///
+/// Each module is placed after all of its dependencies. When multiple modules could be placed
+/// next, the one declared first is chosen so the result is deterministic and equal to the
+/// declaration order whenever that order already satisfies all dependencies.
+pub fn resolve_order<'a>(
+    modules: &[(&'a str, &[&'a str])],
+) -> Result<Vec<&'a str>, DependencyError> {
+    let mut declared = BTreeSet::new();
+    for (name, _) in modules {
+        if !declared.insert(*name) {
+            return Err(DependencyError::Duplicate(name.to_string()));
+        }
+    }
+    for (name, dependencies) in modules {
+        if let Some(dependency) = dependencies.iter().find(|dep| !declared.contains(*dep)) {
+            return Err(DependencyError::Missing {
+                module: name.to_string(),
+                dependency: dependency.to_string(),
+            });
+        }
+    }
+
+    let mut order = Vec::with_capacity(modules.len());
+    let mut placed = BTreeSet::new();
+    while order.len() < modules.len() {
+        let next = modules.iter().find(|(name, dependencies)| {
+            !placed.contains(name) && dependencies.iter().all(|dep| placed.contains(dep))
+        });
+        match next {
+            Some((name, _)) => {
+                placed.insert(*name);
+                order.push(*name);
+            }
+            None => {
+                // Every remaining module has a remaining dependency, so following the first
+                // remaining dependency must eventually revisit a module.
+                let remaining = |name: &&str| !placed.contains(name);
+                let dependencies: BTreeMap<_, _> = modules.iter().cloned().collect();
+                let mut path: Vec<&str> = Vec::new();
+                let mut current = modules
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .find(remaining)
+                    .unwrap();
+                while !path.contains(&current) {
+                    path.push(current);
+                    current = dependencies[current]
+                        .iter()
+                        .copied()
+                        .find(remaining)
+                        .unwrap();
+                }
+                let start = path.iter().position(|name| *name == current).unwrap();
+                let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+                cycle.push(current.to_string());
+                return Err(DependencyError::Cycle(cycle));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Derive the order of the elements of a module tuple, given the declared dependencies of the
+/// modules contained in each element.
+///
+/// # Panics
+///
+/// This function panics if the dependencies cannot be satisfied.
+fn order_elements(elements: &[Vec<(&'static str, &'static [&'static str])>]) -> Vec<usize> {
+    let modules: Vec<_> = elements.iter().flatten().copied().collect();
+    if let Err(err) = resolve_order(&modules) {
+        panic!("invalid module configuration: {err}");
+    }
+
+    // Elements are usually single modules, in which case this is the same as the module order.
+    // Nested tuples are placed once all dependencies of their modules outside of the tuple are.
+    let mut order = Vec::with_capacity(elements.len());
+    let mut placed = BTreeSet::new();
+    while order.len() < elements.len() {
+        let next = (0..elements.len()).find(|index| {
+            let element = &elements[*index];
+            !order.contains(index)
+                && element.iter().all(|(_, dependencies)| {
+                    dependencies.iter().all(|dep| {
+                        placed.contains(dep) || element.iter().any(|(name, _)| name == dep)
+                    })
+                })
+        });
+        let index = match next {
+            Some(index) => index,
+            None => panic!("invalid module configuration: nested module tuples cannot be ordered"),
+        };
+        placed.extend(elements[index].iter().map(|(name, _)| *name));
+        order.push(index);
+    }
+    order
 }
 
 /// A runtime module.
@@ -578,6 +1082,12 @@ pub trait Module {
     /// Module version.
     const VERSION: u32 = 1;
 
+    /// Names of the modules whose APIs this module requires.
+    ///
+    /// The runtime refuses to start unless all dependencies are present and acyclic. The module is
+    /// initialized and its hooks are invoked after those of its dependencies.
+    const DEPENDENCIES: &'static [&'static str] = &[];
+
     /// Module error type.
     type Error: error::Error + 'static;
 
@@ -596,14 +1106,52 @@ pub trait Module {
         })
     }
 
+    /// Return the module's state version as recorded in state.
+    ///
+    /// This is lower than `VERSION` until the module's state has been initialized or migrated.
+    fn state_version() -> u32 {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &modules::core::MODULE_NAME);
+            let store = storage::TypedStore::new(store);
+            let metadata: modules::core::types::Metadata = store
+                .get(modules::core::state::METADATA)
+                .unwrap_or_default();
+            metadata
+                .versions
+                .get(Self::NAME)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
     /// Set the module's parameters.
+    ///
+    /// The replaced parameters are remembered until the end of the block so that the core module
+    /// can record the changes.
     fn set_params(params: Self::Parameters) {
+        let previous: Option<cbor::Value> = CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &Self::NAME);
+            let store = storage::TypedStore::new(store);
+            store.get(Self::Parameters::STORE_KEY)
+        });
+        if let Some(previous) = previous {
+            modules::core::snapshot_parameters(Self::NAME, previous);
+        }
+
         CurrentStore::with(|store| {
             let store = storage::PrefixStore::new(store, &Self::NAME);
             let mut store = storage::TypedStore::new(store);
             store.insert(Self::Parameters::STORE_KEY, params);
         });
     }
+
+    /// Return the module's enabled features, keyed by feature name.
+    ///
+    /// Features are reported to clients by the `core.RuntimeInfo` query so that they can adapt
+    /// to the capabilities of a particular deployment.
+    fn features() -> BTreeMap<String, cbor::Value> {
+        BTreeMap::new()
+    }
 }
 
 /// Parameters for a runtime module.
@@ -623,3 +1171,99 @@ pub trait Parameters: Debug + Default + cbor::Encode + cbor::Decode {
 impl Parameters for () {
     type Error = std::convert::Infallible;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_order() {
+        let modules: &[(&str, &[&str])] = &[
+            ("core", &[]),
+            ("bridge", &["accounts"]),
+            ("accounts", &[]),
+            ("consensus_accounts", &["accounts", "consensus"]),
+            ("consensus", &[]),
+        ];
+        assert_eq!(
+            resolve_order(modules).unwrap(),
+            vec![
+                "core",
+                "accounts",
+                "bridge",
+                "consensus",
+                "consensus_accounts"
+            ]
+        );
+
+        // Orders satisfying all dependencies are preserved.
+        let modules: &[(&str, &[&str])] =
+            &[("accounts", &[]), ("core", &[]), ("bridge", &["accounts"])];
+        assert_eq!(
+            resolve_order(modules).unwrap(),
+            vec!["accounts", "core", "bridge"]
+        );
+    }
+
+    #[test]
+    fn test_order_elements() {
+        let elements = [
+            vec![("core", &[] as &[&str])],
+            vec![("bridge", &["accounts"] as &[&str])],
+            vec![("accounts", &[] as &[&str])],
+        ];
+        assert_eq!(order_elements(&elements), vec![0, 2, 1]);
+
+        // Dependencies within nested tuples are resolved by the nested tuple.
+        let elements = [
+            vec![
+                ("bridge", &["accounts"] as &[&str]),
+                ("accounts", &[] as &[&str]),
+            ],
+            vec![("core", &[] as &[&str])],
+        ];
+        assert_eq!(order_elements(&elements), vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "module 'bridge' depends on missing module 'accounts'")]
+    fn test_order_elements_missing() {
+        order_elements(&[vec![("bridge", &["accounts"] as &[&str])]]);
+    }
+
+    #[test]
+    fn test_resolve_order_errors() {
+        let modules: &[(&str, &[&str])] = &[("accounts", &[]), ("accounts", &[])];
+        assert_eq!(
+            resolve_order(modules),
+            Err(DependencyError::Duplicate("accounts".to_string()))
+        );
+
+        let modules: &[(&str, &[&str])] = &[("core", &[]), ("bridge", &["accounts"])];
+        assert_eq!(
+            resolve_order(modules),
+            Err(DependencyError::Missing {
+                module: "bridge".to_string(),
+                dependency: "accounts".to_string(),
+            })
+        );
+
+        let modules: &[(&str, &[&str])] = &[
+            ("core", &[]),
+            ("a", &["c"]),
+            ("b", &["a"]),
+            ("c", &["b", "core"]),
+        ];
+        let err = resolve_order(modules).unwrap_err();
+        assert_eq!(
+            err,
+            DependencyError::Cycle(vec![
+                "a".to_string(),
+                "c".to_string(),
+                "b".to_string(),
+                "a".to_string()
+            ])
+        );
+        assert_eq!(err.to_string(), "module dependency cycle: a -> c -> b -> a");
+    }
+}
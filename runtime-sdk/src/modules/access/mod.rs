@@ -0,0 +1,457 @@
+//! Access module providing role-based access control for runtime methods.
+//!
+//! Methods can be restricted so that only callers holding one of the required roles can call them.
+//! Roles are granted to addresses and, together with the per-method required roles, are kept in
+//! module state so that they can be updated at runtime by the configured admins, holders of the
+//! admin role or other modules (e.g. governance) via the module API.
+//!
+//! Checks are performed after the call has been decoded, for transactions as well as subcalls.
+//! The method roles table is cached for the duration of a block to keep the checks cheap.
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    core::common::crypto::hash::Hash,
+    handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{address::Address, transaction::Call},
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "access";
+
+/// Role whose holders can manage roles and method roles.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Maximum length of a role name.
+const MAX_ROLE_LENGTH: usize = 64;
+
+/// Context key for the cached method roles table.
+const CONTEXT_KEY_METHOD_ROLES: &str = "access.MethodRoles";
+
+/// Errors emitted by the access module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 2)]
+    Forbidden,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the access module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    RoleGranted { address: Address, role: String },
+
+    #[sdk_event(code = 2)]
+    RoleRevoked { address: Address, role: String },
+
+    #[sdk_event(code = 3)]
+    MethodRolesChanged { method: String, roles: Vec<String> },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_grant_role: u64,
+    pub tx_revoke_role: u64,
+    pub tx_set_method_roles: u64,
+}
+
+/// Parameters for the access module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Addresses allowed to manage roles and method roles in addition to holders of the admin
+    /// role.
+    #[cbor(optional)]
+    pub admins: Vec<Address>,
+}
+
+impl module::Parameters for Parameters {
+    type Error = std::convert::Infallible;
+}
+
+/// Genesis state for the access module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Roles granted to addresses.
+    #[cbor(optional)]
+    pub grants: BTreeMap<Address, Vec<String>>,
+    /// Roles required to call methods.
+    #[cbor(optional)]
+    pub methods: BTreeMap<String, Vec<String>>,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of addresses to roles granted to them.
+    pub const GRANTS: &[u8] = &[0x01];
+    /// Table of methods and the roles required to call them.
+    pub const METHOD_ROLES: &[u8] = &[0x02];
+    /// Hash of the method roles table, used to validate cached copies of the table.
+    pub const METHOD_ROLES_HASH: &[u8] = &[0x03];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Roles granted to the given address.
+    fn get_roles(address: Address) -> Vec<String>;
+
+    /// Whether the given address holds the given role.
+    fn has_role(address: Address, role: &str) -> bool;
+
+    /// Grant a role to the given address.
+    fn grant_role(address: Address, role: &str) -> Result<(), Error>;
+
+    /// Revoke a role from the given address.
+    fn revoke_role(address: Address, role: &str);
+
+    /// Set the roles required to call the given method. An empty set of roles removes any
+    /// restrictions from the method.
+    fn set_method_roles(method: &str, roles: Vec<String>) -> Result<(), Error>;
+
+    /// Roles required to call the given method, any of which is sufficient. Roles required for
+    /// the method itself take precedence over the roles required for its module.
+    fn required_roles<C: Context>(ctx: &mut C, method: &str) -> Vec<String>;
+
+    /// Whether the given address is allowed to call the given method.
+    fn is_authorized<C: Context>(ctx: &mut C, address: Address, method: &str) -> bool {
+        let required = Self::required_roles(ctx, method);
+        if required.is_empty() {
+            return true;
+        }
+        let roles = Self::get_roles(address);
+        required.iter().any(|role| roles.contains(role))
+    }
+}
+
+/// Block-scoped cache of the method roles table.
+struct MethodRolesCache {
+    hash: Hash,
+    methods: BTreeMap<String, Vec<String>>,
+}
+
+pub struct Module;
+
+impl Module {
+    fn get_method_roles_table() -> BTreeMap<String, Vec<String>> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::METHOD_ROLES).unwrap_or_default()
+        })
+    }
+
+    fn get_method_roles_hash() -> Option<Hash> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::METHOD_ROLES_HASH)
+        })
+    }
+
+    fn set_method_roles_table(methods: BTreeMap<String, Vec<String>>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            if methods.is_empty() {
+                tstore.remove(state::METHOD_ROLES);
+                tstore.remove(state::METHOD_ROLES_HASH);
+                return;
+            }
+            tstore.insert(
+                state::METHOD_ROLES_HASH,
+                Hash::digest_bytes(&cbor::to_vec(methods.clone())),
+            );
+            tstore.insert(state::METHOD_ROLES, methods);
+        });
+    }
+
+    fn set_roles(address: Address, roles: Vec<String>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut grants =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::GRANTS));
+            if roles.is_empty() {
+                grants.remove(address);
+            } else {
+                grants.insert(address, roles);
+            }
+        });
+    }
+
+    fn get_grants() -> BTreeMap<Address, Vec<String>> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let grants = storage::TypedStore::new(storage::PrefixStore::new(store, &state::GRANTS));
+            grants.iter().collect()
+        })
+    }
+
+    fn ensure_admin<C: TxContext>(ctx: &C) -> Result<(), Error> {
+        let caller = ctx.tx_caller_address();
+        if !Self::params().admins.contains(&caller) && !Self::has_role(caller, ADMIN_ROLE) {
+            return Err(Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+/// Whether the given string is a valid role name.
+fn is_valid_role(role: &str) -> bool {
+    !role.is_empty() && role.len() <= MAX_ROLE_LENGTH
+}
+
+impl API for Module {
+    fn get_roles(address: Address) -> Vec<String> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let grants = storage::TypedStore::new(storage::PrefixStore::new(store, &state::GRANTS));
+            grants.get(address).unwrap_or_default()
+        })
+    }
+
+    fn has_role(address: Address, role: &str) -> bool {
+        Self::get_roles(address).iter().any(|r| r == role)
+    }
+
+    fn grant_role(address: Address, role: &str) -> Result<(), Error> {
+        if !is_valid_role(role) {
+            return Err(Error::InvalidArgument);
+        }
+        let mut roles = Self::get_roles(address);
+        if let Err(index) = roles.binary_search_by(|r| r.as_str().cmp(role)) {
+            roles.insert(index, role.to_owned());
+            Self::set_roles(address, roles);
+        }
+        Ok(())
+    }
+
+    fn revoke_role(address: Address, role: &str) {
+        let mut roles = Self::get_roles(address);
+        if let Ok(index) = roles.binary_search_by(|r| r.as_str().cmp(role)) {
+            roles.remove(index);
+            Self::set_roles(address, roles);
+        }
+    }
+
+    fn set_method_roles(method: &str, mut roles: Vec<String>) -> Result<(), Error> {
+        if method.is_empty() || !roles.iter().all(|role| is_valid_role(role)) {
+            return Err(Error::InvalidArgument);
+        }
+        roles.sort();
+        roles.dedup();
+
+        let mut methods = Self::get_method_roles_table();
+        if roles.is_empty() {
+            methods.remove(method);
+        } else {
+            methods.insert(method.to_owned(), roles);
+        }
+        Self::set_method_roles_table(methods);
+        Ok(())
+    }
+
+    fn required_roles<C: Context>(ctx: &mut C, method: &str) -> Vec<String> {
+        // Avoid any lookups in case no methods are restricted.
+        let hash = match Self::get_method_roles_hash() {
+            Some(hash) => hash,
+            None => return vec![],
+        };
+
+        // The cache is keyed by the hash of the table so it stays valid across any rollbacks.
+        if !matches!(
+            ctx.value::<MethodRolesCache>(CONTEXT_KEY_METHOD_ROLES).get(),
+            Some(cache) if cache.hash == hash
+        ) {
+            ctx.value(CONTEXT_KEY_METHOD_ROLES).set(MethodRolesCache {
+                hash,
+                methods: Self::get_method_roles_table(),
+            });
+        }
+        let cache = ctx
+            .value::<MethodRolesCache>(CONTEXT_KEY_METHOD_ROLES)
+            .get()
+            .expect("method roles cache should be populated");
+
+        if let Some(roles) = cache.methods.get(method) {
+            return roles.clone();
+        }
+        let module = method.split('.').next().unwrap_or_default();
+        cache
+            .methods
+            .get(&format!("{module}.*"))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[sdk_derive(Module)]
+impl Module {
+    const NAME: &'static str = MODULE_NAME;
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        for (address, roles) in genesis.grants {
+            for role in roles {
+                Self::grant_role(address, &role).expect("invalid genesis role grant");
+            }
+        }
+        for (method, roles) in genesis.methods {
+            Self::set_method_roles(&method, roles).expect("invalid genesis method roles");
+        }
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Grant a role to an address. Only admins can grant roles.
+    #[handler(call = "access.GrantRole")]
+    fn tx_grant_role<C: TxContext>(ctx: &mut C, body: types::GrantRole) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_grant_role)?;
+
+        Self::ensure_admin(ctx)?;
+        if !is_valid_role(&body.role) {
+            return Err(Error::InvalidArgument);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::grant_role(body.address, &body.role)?;
+
+        ctx.emit_event(Event::RoleGranted {
+            address: body.address,
+            role: body.role,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a role from an address. Only admins can revoke roles.
+    #[handler(call = "access.RevokeRole")]
+    fn tx_revoke_role<C: TxContext>(ctx: &mut C, body: types::RevokeRole) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_revoke_role)?;
+
+        Self::ensure_admin(ctx)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::revoke_role(body.address, &body.role);
+
+        ctx.emit_event(Event::RoleRevoked {
+            address: body.address,
+            role: body.role,
+        });
+
+        Ok(())
+    }
+
+    /// Set the roles required to call a method. Only admins can change method roles.
+    #[handler(call = "access.SetMethodRoles")]
+    fn tx_set_method_roles<C: TxContext>(
+        ctx: &mut C,
+        body: types::SetMethodRoles,
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_set_method_roles)?;
+
+        Self::ensure_admin(ctx)?;
+        if body.method.is_empty() || !body.roles.iter().all(|role| is_valid_role(role)) {
+            return Err(Error::InvalidArgument);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::set_method_roles(&body.method, body.roles.clone())?;
+
+        ctx.emit_event(Event::MethodRolesChanged {
+            method: body.method,
+            roles: body.roles,
+        });
+
+        Ok(())
+    }
+
+    #[handler(query = "access.Roles")]
+    fn query_roles<C: Context>(
+        _ctx: &mut C,
+        args: types::RolesQuery,
+    ) -> Result<Vec<String>, Error> {
+        Ok(Self::get_roles(args.address))
+    }
+
+    #[handler(query = "access.MethodRoles")]
+    fn query_method_roles<C: Context>(
+        ctx: &mut C,
+        args: types::MethodRolesQuery,
+    ) -> Result<Vec<String>, Error> {
+        Ok(Self::required_roles(ctx, &args.method))
+    }
+}
+
+impl module::TransactionHandler for Module {
+    fn before_authorized_call_dispatch<C: TxContext>(
+        ctx: &mut C,
+        call: &Call,
+    ) -> Result<(), modules::core::Error> {
+        let caller = ctx.tx_caller_address();
+        if !Self::is_authorized(ctx, caller, &call.method) {
+            return Err(modules::core::Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+impl module::BlockHandler for Module {}
+
+impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            grants: Self::get_grants(),
+            methods: Self::get_method_roles_table(),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Tests for the access module.
+use std::collections::BTreeMap;
+
+use crate::{
+    context::BatchContext,
+    module::TransactionHandler,
+    modules::core,
+    storage::{current::TransactionResult, CurrentStore},
+    testing::{keys, mock},
+    types::transaction,
+};
+
+use super::{types, Error, Genesis, Module as Access, Parameters, ADMIN_ROLE, API as _};
+
+fn init() {
+    Access::init(Genesis {
+        parameters: Parameters {
+            gas_costs: Default::default(),
+            admins: vec![keys::alice::address()],
+        },
+        grants: BTreeMap::from([(keys::bob::address(), vec!["minter".to_owned()])]),
+        methods: BTreeMap::from([("token.Mint".to_owned(), vec!["minter".to_owned()])]),
+    });
+}
+
+/// Check whether the given signer is allowed to call the given method.
+fn authorize(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    method: &str,
+) -> Result<(), core::Error> {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.call.method = method.to_owned();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        Access::before_authorized_call_dispatch(&mut tx_ctx, &call)
+    })
+}
+
+#[test]
+fn test_method_roles() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    authorize(&mut mock, &bob, "token.Mint").expect("role holder should be authorized");
+    let result = authorize(&mut mock, &alice, "token.Mint");
+    assert!(matches!(result, Err(core::Error::Forbidden)));
+    authorize(&mut mock, &alice, "token.Transfer").expect("unrestricted methods should be allowed");
+
+    // Module wildcards apply to all methods of the module without their own roles.
+    Access::set_method_roles("token.*", vec!["operator".to_owned()]).unwrap();
+    let result = authorize(&mut mock, &alice, "token.Transfer");
+    assert!(matches!(result, Err(core::Error::Forbidden)));
+    authorize(&mut mock, &bob, "token.Mint").expect("method roles should take precedence");
+
+    Access::grant_role(keys::alice::address(), "operator").unwrap();
+    authorize(&mut mock, &alice, "token.Transfer").expect("role holder should be authorized");
+
+    // Removing the roles lifts the restrictions.
+    Access::set_method_roles("token.*", vec![]).unwrap();
+    Access::set_method_roles("token.Mint", vec![]).unwrap();
+    let charlie = keys::charlie::signer_info();
+    authorize(&mut mock, &charlie, "token.Mint").expect("unrestricted methods should be allowed");
+}
+
+#[test]
+fn test_cache() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    let mut ctx = mock.create_ctx();
+    assert_eq!(
+        Access::required_roles(&mut ctx, "token.Mint"),
+        vec!["minter"]
+    );
+
+    // Changes of the method roles are picked up within the same block.
+    Access::set_method_roles("token.Mint", vec!["other".to_owned(), "minter".to_owned()]).unwrap();
+    assert_eq!(
+        Access::required_roles(&mut ctx, "token.Mint"),
+        vec!["minter", "other"]
+    );
+
+    // Including when the change is rolled back.
+    CurrentStore::with_transaction(|| {
+        Access::set_method_roles("token.Mint", vec![]).unwrap();
+        assert!(Access::required_roles(&mut ctx, "token.Mint").is_empty());
+        TransactionResult::Rollback(())
+    });
+    assert_eq!(
+        Access::required_roles(&mut ctx, "token.Mint"),
+        vec!["minter", "other"]
+    );
+}
+
+#[test]
+fn test_admin_calls() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    let grant = |mock: &mut mock::Mock, who: &transaction::SignerInfo, role: &str| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Access::tx_grant_role(
+                &mut tx_ctx,
+                types::GrantRole {
+                    address: keys::charlie::address(),
+                    role: role.to_owned(),
+                },
+            )
+        })
+    };
+
+    let result = grant(&mut mock, &bob, "minter");
+    assert!(matches!(result, Err(Error::Forbidden)));
+    let result = grant(&mut mock, &alice, "");
+    assert!(matches!(result, Err(Error::InvalidArgument)));
+    grant(&mut mock, &alice, "minter").expect("admins should be able to grant roles");
+    assert!(Access::has_role(keys::charlie::address(), "minter"));
+
+    // Holders of the admin role can manage roles as well.
+    Access::grant_role(keys::bob::address(), ADMIN_ROLE).unwrap();
+    grant(&mut mock, &bob, "operator").expect("admin role holders should be able to grant roles");
+    assert_eq!(
+        Access::get_roles(keys::charlie::address()),
+        vec!["minter", "operator"]
+    );
+
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![alice];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Access::tx_revoke_role(
+            &mut tx_ctx,
+            types::RevokeRole {
+                address: keys::charlie::address(),
+                role: "minter".to_owned(),
+            },
+        )
+    })
+    .expect("admins should be able to revoke roles");
+    assert_eq!(
+        Access::get_roles(keys::charlie::address()),
+        vec!["operator"]
+    );
+}
+
+#[test]
+fn test_export_state() {
+    let _mock = mock::Mock::default();
+    init();
+    Access::grant_role(keys::charlie::address(), "operator").unwrap();
+    Access::set_method_roles("token.Burn", vec!["operator".to_owned()]).unwrap();
+
+    let genesis = mock::assert_export_round_trip::<Access>();
+    assert_eq!(genesis.parameters.admins, vec![keys::alice::address()]);
+    assert_eq!(genesis.grants.len(), 2);
+    assert_eq!(genesis.methods.len(), 2);
+}
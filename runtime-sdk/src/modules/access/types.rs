@@ -0,0 +1,46 @@
+//! Access module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::address::Address;
+
+/// Grant role call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct GrantRole {
+    /// Address of the account to grant the role to.
+    pub address: Address,
+    /// Role to grant.
+    pub role: String,
+}
+
+/// Revoke role call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct RevokeRole {
+    /// Address of the account to revoke the role from.
+    pub address: Address,
+    /// Role to revoke.
+    pub role: String,
+}
+
+/// Set method roles call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct SetMethodRoles {
+    /// Method name or a module wildcard of the form `<module>.*`.
+    pub method: String,
+    /// Roles allowed to call the method. The caller needs to hold at least one of the roles. If
+    /// empty, the method can be called by anyone.
+    pub roles: Vec<String>,
+}
+
+/// Roles query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct RolesQuery {
+    /// Address of the account.
+    pub address: Address,
+}
+
+/// Method roles query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct MethodRolesQuery {
+    /// Method name or a module wildcard of the form `<module>.*`.
+    pub method: String,
+}
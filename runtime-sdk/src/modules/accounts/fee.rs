@@ -20,10 +20,8 @@ pub struct FeeManager {
 pub struct TransactionFee {
     /// Transaction fee payer address.
     payer: Address,
-    /// Denomination of the transaction fee.
-    denomination: token::Denomination,
     /// Amount charged before transaction execution.
-    charged: u128,
+    charged: token::BaseUnits,
     /// Amount that should be refunded after transaction execution.
     refunded: u128,
 }
@@ -31,12 +29,12 @@ pub struct TransactionFee {
 impl TransactionFee {
     /// Denomination of the transaction fee.
     pub fn denomination(&self) -> token::Denomination {
-        self.denomination.clone()
+        self.charged.denomination().clone()
     }
 
     /// Transaction fee amount.
     pub fn amount(&self) -> u128 {
-        self.charged.saturating_sub(self.refunded)
+        self.charged.amount().saturating_sub(self.refunded)
     }
 
     /// Transaction fee payer address.
@@ -63,20 +61,19 @@ impl FeeManager {
     pub fn record_fee(&mut self, payer: Address, amount: &token::BaseUnits) {
         let tx_fee = self.tx_fee.get_or_insert_with(|| TransactionFee {
             payer,
-            denomination: amount.denomination().clone(),
+            charged: token::BaseUnits::new(0, amount.denomination().clone()),
             ..Default::default()
         });
 
         assert!(payer == tx_fee.payer, "transaction fee payer cannot change");
-        assert!(
-            amount.denomination() == &tx_fee.denomination,
-            "transaction fee denomination cannot change"
-        );
 
-        tx_fee.charged = tx_fee
-            .charged
-            .checked_add(amount.amount())
-            .expect("should never overflow");
+        tx_fee.charged = match tx_fee.charged.checked_add(amount) {
+            Ok(charged) => charged,
+            Err(token::Error::DenominationMismatch(..)) => {
+                panic!("transaction fee denomination cannot change")
+            }
+            Err(_) => panic!("should never overflow"),
+        };
     }
 
     /// Record that a portion of the previously charged transaction fee should be refunded.
@@ -86,7 +83,10 @@ impl FeeManager {
         }
 
         let tx_fee = self.tx_fee.as_mut().unwrap();
-        tx_fee.refunded = std::cmp::min(tx_fee.refunded.saturating_add(amount), tx_fee.charged);
+        tx_fee.refunded = std::cmp::min(
+            tx_fee.refunded.saturating_add(amount),
+            tx_fee.charged.amount(),
+        );
     }
 
     /// Commit the currently open transaction fee by moving the final recorded amount into the fees
@@ -98,10 +98,7 @@ impl FeeManager {
     pub fn commit_tx(&mut self) -> FeeUpdates {
         let tx_fee = self.tx_fee.take().unwrap_or_default();
         if tx_fee.amount() > 0 {
-            let block_fees = self
-                .block_fees
-                .entry(tx_fee.denomination.clone())
-                .or_default();
+            let block_fees = self.block_fees.entry(tx_fee.denomination()).or_default();
 
             // Add to per-block accumulator.
             *block_fees = block_fees
@@ -111,7 +108,7 @@ impl FeeManager {
 
         FeeUpdates {
             payer: tx_fee.payer,
-            refund: token::BaseUnits::new(tx_fee.refunded, tx_fee.denomination),
+            refund: token::BaseUnits::new(tx_fee.refunded, tx_fee.denomination()),
         }
     }
 
@@ -11,9 +11,10 @@ use thiserror::Error;
 
 use crate::{
     context::{Context, TxContext},
-    core::common::quantity::Quantity,
+    core::common::{crypto::x25519, quantity::Quantity},
+    event::{self, EncryptedEvent},
     handler, migration, module,
-    module::{Module as _, Parameters as _},
+    module::{FeeGrantHandler as _, Module as _, Parameters as _},
     modules,
     modules::core::{Error as CoreError, API as _},
     runtime::Runtime,
@@ -23,8 +24,9 @@ use crate::{
     storage::{CurrentStore, Prefix},
     types::{
         address::{Address, SignatureAddressSpec},
+        pagination::{paginate, PageResponse},
         token,
-        transaction::{AuthInfo, Transaction},
+        transaction::{AddressSpec, AuthInfo, PreviewField, SessionKeySpec, Transaction},
     },
 };
 
@@ -59,6 +61,10 @@ pub enum Error {
     #[sdk_error(code = 4)]
     NotFound,
 
+    #[error("insufficient allowance")]
+    #[sdk_error(code = 5)]
+    InsufficientAllowance,
+
     #[error("core: {0}")]
     #[sdk_error(transparent)]
     Core(#[from] modules::core::Error),
@@ -66,7 +72,9 @@ pub enum Error {
 
 /// Events emitted by the accounts module.
 #[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(untagged)]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Event {
     #[sdk_event(code = 1)]
     Transfer {
@@ -86,12 +94,56 @@ pub enum Event {
         owner: Address,
         amount: token::BaseUnits,
     },
+
+    #[sdk_event(code = 4)]
+    SessionKeyAuthorized {
+        account: Address,
+        key: SignatureAddressSpec,
+        expiration: u64,
+    },
+
+    #[sdk_event(code = 5)]
+    SessionKeyRevoked {
+        account: Address,
+        key: SignatureAddressSpec,
+    },
+
+    #[sdk_event(code = 6)]
+    EncryptionKeySet {
+        account: Address,
+        #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_encoded"))]
+        key: Option<x25519::PublicKey>,
+    },
+
+    #[sdk_event(code = 7)]
+    Encrypted {
+        recipient: Address,
+        event: EncryptedEvent,
+    },
+
+    #[sdk_event(code = 8)]
+    Approval {
+        owner: Address,
+        spender: Address,
+        amount: token::BaseUnits,
+    },
 }
 
 /// Gas costs.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct GasCosts {
     pub tx_transfer: u64,
+
+    #[cbor(optional)]
+    pub tx_authorize_session_key: u64,
+    #[cbor(optional)]
+    pub tx_revoke_session_key: u64,
+    #[cbor(optional)]
+    pub tx_set_encryption_key: u64,
+    #[cbor(optional)]
+    pub tx_approve: u64,
+    #[cbor(optional)]
+    pub tx_transfer_from: u64,
 }
 
 /// Parameters for the accounts module.
@@ -105,6 +157,11 @@ pub struct Parameters {
 
     #[cbor(optional)]
     pub denomination_infos: BTreeMap<token::Denomination, types::DenominationInfo>,
+
+    /// Maximum number of rounds for which a session key can be authorized. Zero disables session
+    /// keys.
+    #[cbor(optional)]
+    pub max_session_key_duration: u64,
 }
 
 /// Errors emitted during rewards parameter validation.
@@ -136,6 +193,15 @@ pub struct Genesis {
     pub accounts: BTreeMap<Address, types::Account>,
     pub balances: BTreeMap<Address, BTreeMap<token::Denomination, u128>>,
     pub total_supplies: BTreeMap<token::Denomination, u128>,
+    /// Session keys authorized by accounts, keyed by account and session key address.
+    #[cbor(optional)]
+    pub session_keys: BTreeMap<Address, BTreeMap<Address, types::SessionKeyInfo>>,
+    /// Encryption public keys registered by accounts.
+    #[cbor(optional)]
+    pub encryption_keys: BTreeMap<Address, x25519::PublicKey>,
+    /// Allowances given by owners to spenders.
+    #[cbor(optional)]
+    pub allowances: BTreeMap<Address, BTreeMap<Address, types::Allowances>>,
 }
 
 /// Interface that can be called from other modules.
@@ -149,7 +215,12 @@ pub trait API {
     ) -> Result<(), Error>;
 
     /// Transfer an amount from one account to the other without emitting an event.
-    fn transfer_silent(from: Address, to: Address, amount: &token::BaseUnits) -> Result<(), Error>;
+    fn transfer_silent<C: Context>(
+        ctx: &mut C,
+        from: Address,
+        to: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error>;
 
     /// Mint new tokens, increasing the total supply.
     fn mint<C: Context>(ctx: &mut C, to: Address, amount: &token::BaseUnits) -> Result<(), Error>;
@@ -237,6 +308,59 @@ pub trait API {
         ctx: &mut C,
         tx_auth_info: &AuthInfo,
     ) -> Result<(), modules::core::Error>;
+
+    /// Fetch the encryption public key registered by the given account.
+    fn get_encryption_key(address: Address) -> Result<Option<x25519::PublicKey>, Error>;
+
+    /// Emit an event encrypted to the encryption key registered by the given recipient account.
+    ///
+    /// Returns `false` without emitting anything in case the recipient has not registered an
+    /// encryption key. Encrypting events requires a confidential runtime.
+    fn emit_encrypted_event<C: Context, E: event::Event>(
+        ctx: &mut C,
+        recipient: Address,
+        event: E,
+    ) -> Result<bool, Error>;
+
+    /// Set the amount of the given denomination that the spender is allowed to transfer from the
+    /// owner's account, replacing any previous allowance.
+    fn approve<C: Context>(
+        ctx: &mut C,
+        owner: Address,
+        spender: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error>;
+
+    /// Fetch the amount of the given denomination that the spender is allowed to transfer from
+    /// the owner's account.
+    fn get_allowance(
+        owner: Address,
+        spender: Address,
+        denomination: token::Denomination,
+    ) -> Result<u128, Error>;
+
+    /// Fetch all allowances given by the owner to the spender.
+    fn get_allowances(owner: Address, spender: Address) -> Result<types::Allowances, Error>;
+
+    /// Transfer an amount from the owner's account on behalf of the spender, decreasing the
+    /// spender's allowance accordingly.
+    fn transfer_from<C: Context>(
+        ctx: &mut C,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error>;
+
+    /// Account an amount spent by the given account against the spending cap of the session key
+    /// that authorized the current transaction, if any. Debits of runtime balances and allowance
+    /// increases are accounted for automatically, so this is only needed for funds spent
+    /// elsewhere (e.g. from the account's consensus layer account).
+    fn use_session_spending<C: Context>(
+        ctx: &mut C,
+        from: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), modules::core::Error>;
 }
 
 /// State schema constants.
@@ -247,6 +371,12 @@ pub mod state {
     pub const BALANCES: &[u8] = &[0x02];
     /// Map of total supplies (per denomination).
     pub const TOTAL_SUPPLY: &[u8] = &[0x03];
+    /// Map of account addresses to map of session key addresses to session key information.
+    pub const SESSION_KEYS: &[u8] = &[0x04];
+    /// Map of account addresses to registered encryption public keys.
+    pub const ENCRYPTION_KEYS: &[u8] = &[0x05];
+    /// Map of owner addresses to map of spender addresses to allowances (per denomination).
+    pub const ALLOWANCES: &[u8] = &[0x06];
 }
 
 pub struct Module;
@@ -283,7 +413,126 @@ impl std::convert::TryFrom<&[u8]> for AddressWithDenomination {
     }
 }
 
+/// This is needed to properly iterate over the SESSION_KEYS and ALLOWANCES maps.
+struct AddressPair(Address, Address);
+
+#[derive(Error, Debug)]
+enum APError {
+    #[error("malformed address")]
+    MalformedAddress,
+}
+
+impl std::convert::TryFrom<&[u8]> for AddressPair {
+    type Error = APError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let a =
+            Address::try_from(&bytes[..Address::SIZE]).map_err(|_| APError::MalformedAddress)?;
+        let b =
+            Address::try_from(&bytes[Address::SIZE..]).map_err(|_| APError::MalformedAddress)?;
+        Ok(AddressPair(a, b))
+    }
+}
+
 impl Module {
+    /// Fetch information about a session key authorized by the given account.
+    fn get_session_key(
+        account: Address,
+        key: &SignatureAddressSpec,
+    ) -> Option<types::SessionKeyInfo> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let session_keys = storage::PrefixStore::new(store, &state::SESSION_KEYS);
+            let keys = storage::TypedStore::new(storage::PrefixStore::new(session_keys, &account));
+            keys.get(Address::from_sigspec(key))
+        })
+    }
+
+    /// Store information about a session key authorized by the given account.
+    fn set_session_key(account: Address, key: &SignatureAddressSpec, info: types::SessionKeyInfo) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let session_keys = storage::PrefixStore::new(store, &state::SESSION_KEYS);
+            let mut keys =
+                storage::TypedStore::new(storage::PrefixStore::new(session_keys, &account));
+            keys.insert(Address::from_sigspec(key), info);
+        })
+    }
+
+    /// Store the allowances given by the owner to the spender.
+    fn set_allowances(owner: Address, spender: Address, allowances: types::Allowances) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances_store = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let mut spenders =
+                storage::TypedStore::new(storage::PrefixStore::new(allowances_store, &owner));
+            if allowances.allowances.is_empty() {
+                spenders.remove(spender);
+            } else {
+                spenders.insert(spender, allowances);
+            }
+        })
+    }
+
+    /// Store (or clear) the encryption public key registered by the given account.
+    fn set_encryption_key(address: Address, key: Option<x25519::PublicKey>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut keys =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::ENCRYPTION_KEYS));
+            match key {
+                Some(key) => keys.insert(address, key),
+                None => keys.remove(address),
+            }
+        });
+    }
+
+    /// Check that the session key that signed the transaction (if any) is authorized to do so and
+    /// return the updated session key information.
+    fn check_session_key<C: Context>(
+        ctx: &C,
+        tx: &Transaction,
+    ) -> Result<Option<(SessionKeySpec, types::SessionKeyInfo)>, modules::core::Error> {
+        let unauthorized =
+            |reason: &str| modules::core::Error::SessionKeyNotAuthorized(reason.to_string());
+
+        // Session keys may only be used by the caller.
+        if tx
+            .auth_info
+            .signers()
+            .skip(1)
+            .any(|si| matches!(si.address_spec, AddressSpec::SessionKey(_)))
+        {
+            return Err(unauthorized("session keys may only be used by the caller"));
+        }
+        let si = &tx.auth_info.signer_info[0];
+        let spec = match &si.address_spec {
+            AddressSpec::SessionKey(spec) => spec,
+            _ => return Ok(None),
+        };
+
+        let mut session = Self::get_session_key(spec.account, &spec.key)
+            .ok_or_else(|| unauthorized("unknown session key"))?;
+        if session.revoked || ctx.time().round() > session.expiration {
+            return Err(unauthorized("session key expired or revoked"));
+        }
+        if !session.methods.contains(&tx.call.method) {
+            return Err(unauthorized("method not allowed"));
+        }
+        if !session.is_nonce_usable(si.nonce) {
+            return Err(modules::core::Error::InvalidNonce);
+        }
+
+        // Account for any fees paid by the authorizing account. Any other amounts debited from the
+        // account while the transaction executes are accounted for as they are spent.
+        if tx.auth_info.fee_payer.is_none() && !session.try_spend(&tx.auth_info.fee.amount) {
+            return Err(unauthorized("spending cap exceeded"));
+        }
+
+        session.use_nonce(si.nonce);
+        Ok(Some((spec.clone(), session)))
+    }
+
     /// Add given amount of tokens to the specified account's balance.
     fn add_amount(addr: Address, amount: &token::BaseUnits) -> Result<(), Error> {
         if amount.amount() == 0 {
@@ -294,32 +543,37 @@ impl Module {
             let store = storage::PrefixStore::new(store, &MODULE_NAME);
             let balances = storage::PrefixStore::new(store, &state::BALANCES);
             let mut account = storage::TypedStore::new(storage::PrefixStore::new(balances, &addr));
-            let mut value: u128 = account.get(amount.denomination()).unwrap_or_default();
+            let value: u128 = account.get(amount.denomination()).unwrap_or_default();
 
-            value = value
-                .checked_add(amount.amount())
-                .ok_or(Error::InvalidArgument)?;
-            account.insert(amount.denomination(), value);
+            let value = token::BaseUnits::new(value, amount.denomination().clone())
+                .checked_add(amount)
+                .map_err(|_| Error::InvalidArgument)?;
+            account.insert(amount.denomination(), value.amount());
             Ok(())
         })
     }
 
     /// Subtract given amount of tokens from the specified account's balance.
-    fn sub_amount(addr: Address, amount: &token::BaseUnits) -> Result<(), Error> {
+    fn sub_amount<C: Context>(
+        ctx: &mut C,
+        addr: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error> {
         if amount.amount() == 0 {
             return Ok(());
         }
+        Self::use_session_spending(ctx, addr, amount)?;
 
         CurrentStore::with(|store| {
             let store = storage::PrefixStore::new(store, &MODULE_NAME);
             let balances = storage::PrefixStore::new(store, &state::BALANCES);
             let mut account = storage::TypedStore::new(storage::PrefixStore::new(balances, &addr));
-            let mut value: u128 = account.get(amount.denomination()).unwrap_or_default();
+            let value: u128 = account.get(amount.denomination()).unwrap_or_default();
 
-            value = value
-                .checked_sub(amount.amount())
-                .ok_or(Error::InsufficientBalance)?;
-            account.insert(amount.denomination(), value);
+            let value = token::BaseUnits::new(value, amount.denomination().clone())
+                .checked_sub(amount)
+                .map_err(|_| Error::InsufficientBalance)?;
+            account.insert(amount.denomination(), value.amount());
             Ok(())
         })
     }
@@ -334,14 +588,14 @@ impl Module {
             let store = storage::PrefixStore::new(store, &MODULE_NAME);
             let mut total_supplies =
                 storage::TypedStore::new(storage::PrefixStore::new(store, &state::TOTAL_SUPPLY));
-            let mut total_supply: u128 = total_supplies
+            let total_supply: u128 = total_supplies
                 .get(amount.denomination())
                 .unwrap_or_default();
 
-            total_supply = total_supply
-                .checked_add(amount.amount())
-                .ok_or(Error::InvalidArgument)?;
-            total_supplies.insert(amount.denomination(), total_supply);
+            let total_supply = token::BaseUnits::new(total_supply, amount.denomination().clone())
+                .checked_add(amount)
+                .map_err(|_| Error::InvalidArgument)?;
+            total_supplies.insert(amount.denomination(), total_supply.amount());
             Ok(())
         })
     }
@@ -356,14 +610,14 @@ impl Module {
             let store = storage::PrefixStore::new(store, &MODULE_NAME);
             let mut total_supplies =
                 storage::TypedStore::new(storage::PrefixStore::new(store, &state::TOTAL_SUPPLY));
-            let mut total_supply: u128 = total_supplies
+            let total_supply: u128 = total_supplies
                 .get(amount.denomination())
                 .unwrap_or_default();
 
-            total_supply = total_supply
-                .checked_sub(amount.amount())
-                .ok_or(Error::InsufficientBalance)?;
-            total_supplies.insert(amount.denomination(), total_supply);
+            let total_supply = token::BaseUnits::new(total_supply, amount.denomination().clone())
+                .checked_sub(amount)
+                .map_err(|_| Error::InsufficientBalance)?;
+            total_supplies.insert(amount.denomination(), total_supply.amount());
             Ok(())
         })
     }
@@ -406,6 +660,12 @@ impl Module {
 const CONTEXT_KEY_TX_FEE_REFUND_UNUSED: &str = "accounts.TxRefundUnusedFee";
 /// Context key for the per block fee manager.
 const CONTEXT_KEY_FEE_MANAGER: &str = "accounts.FeeManager";
+/// Context key for the fee granter of the transaction being checked.
+const CONTEXT_KEY_FEE_GRANTER: &str = "accounts.FeeGranter";
+/// Context key for the fee grant used by the transaction being executed.
+const CONTEXT_KEY_FEE_GRANT_USED: &str = "accounts.FeeGrantUsed";
+/// Context key for the session key that authorized the transaction being executed.
+const CONTEXT_KEY_ACTIVE_SESSION_KEY: &str = "accounts.ActiveSessionKey";
 
 impl API for Module {
     fn transfer<C: Context>(
@@ -418,7 +678,7 @@ impl API for Module {
             return Ok(());
         }
 
-        Self::transfer_silent(from, to, amount)?;
+        Self::transfer_silent(ctx, from, to, amount)?;
 
         // Emit a transfer event.
         ctx.emit_event(Event::Transfer {
@@ -430,9 +690,14 @@ impl API for Module {
         Ok(())
     }
 
-    fn transfer_silent(from: Address, to: Address, amount: &token::BaseUnits) -> Result<(), Error> {
+    fn transfer_silent<C: Context>(
+        ctx: &mut C,
+        from: Address,
+        to: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error> {
         // Subtract from source account.
-        Self::sub_amount(from, amount)?;
+        Self::sub_amount(ctx, from, amount)?;
         // Add to destination account.
         Self::add_amount(to, amount)?;
 
@@ -469,7 +734,7 @@ impl API for Module {
         }
 
         // Remove from target account.
-        Self::sub_amount(from, amount)?;
+        Self::sub_amount(ctx, from, amount)?;
 
         // Decrease total supply.
         Self::dec_total_supply(amount)
@@ -602,7 +867,8 @@ impl API for Module {
             return Ok(());
         }
 
-        Self::sub_amount(from, amount).map_err(|_| modules::core::Error::InsufficientFeeBalance)?;
+        Self::sub_amount(ctx, from, amount)
+            .map_err(|_| modules::core::Error::InsufficientFeeBalance)?;
 
         ctx.value::<fee::FeeManager>(CONTEXT_KEY_FEE_MANAGER)
             .or_default()
@@ -645,11 +911,23 @@ impl API for Module {
             let accounts =
                 storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::ACCOUNTS));
             let mut sender = None;
+            // The fee payer only authorizes fee payment for a transaction which is already
+            // protected against replays by the nonces of the other signers, so its nonce is not
+            // sequenced. This allows a single fee payer to sponsor concurrent transactions.
             for si in auth_info.signer_info.iter() {
+                // Session keys are protected against replays by their own nonce window so
+                // transactions signed by them are not sequenced with the account's transactions.
+                if let AddressSpec::SessionKey(_) = si.address_spec {
+                    if sender.is_none() {
+                        sender = Some(SenderMeta::default());
+                    }
+                    continue;
+                }
+
                 let address = si.address_spec.address();
                 let account: types::Account = accounts.get(address).unwrap_or_default();
 
-                // First signer pays for the fees and is considered the sender.
+                // First signer is considered the sender.
                 if sender.is_none() {
                     sender = Some(SenderMeta {
                         address,
@@ -697,12 +975,11 @@ impl API for Module {
 
         // Configure the sender.
         let sender = sender.expect("at least one signer is always present");
-        let sender_address = sender.address;
         if is_check_only {
             <C::Runtime as Runtime>::Core::set_sender_meta(ctx, sender);
         }
 
-        Ok(sender_address)
+        Ok(auth_info.signer_info[0].address_spec.address())
     }
 
     fn update_signer_nonces<C: Context>(
@@ -714,7 +991,13 @@ impl API for Module {
             let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
             let mut accounts =
                 storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::ACCOUNTS));
+            // The fee payer's nonce is not sequenced (see `check_signer_nonces`).
             for si in auth_info.signer_info.iter() {
+                // Session key nonces are tracked by the session key window.
+                if let AddressSpec::SessionKey(_) = si.address_spec {
+                    continue;
+                }
+
                 let address = si.address_spec.address();
                 let mut account: types::Account = accounts.get(address).unwrap_or_default();
 
@@ -728,6 +1011,151 @@ impl API for Module {
             Ok(())
         })
     }
+
+    fn get_encryption_key(address: Address) -> Result<Option<x25519::PublicKey>, Error> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let keys =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::ENCRYPTION_KEYS));
+            Ok(keys.get(address))
+        })
+    }
+
+    fn approve<C: Context>(
+        ctx: &mut C,
+        owner: Address,
+        spender: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error> {
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        let mut allowances = Self::get_allowances(owner, spender)?;
+
+        // Increasing an allowance lets the spender move the owner's funds so it counts as spent.
+        let previous = allowances
+            .allowances
+            .get(amount.denomination())
+            .copied()
+            .unwrap_or_default();
+        if let Ok(increase) = amount.checked_sub(&token::BaseUnits::new(
+            previous,
+            amount.denomination().clone(),
+        )) {
+            Self::use_session_spending(ctx, owner, &increase)?;
+        }
+
+        if amount.amount() == 0 {
+            allowances.allowances.remove(amount.denomination());
+        } else {
+            allowances
+                .allowances
+                .insert(amount.denomination().clone(), amount.amount());
+        }
+        Self::set_allowances(owner, spender, allowances);
+
+        ctx.emit_event(Event::Approval {
+            owner,
+            spender,
+            amount: amount.clone(),
+        });
+
+        Ok(())
+    }
+
+    fn get_allowance(
+        owner: Address,
+        spender: Address,
+        denomination: token::Denomination,
+    ) -> Result<u128, Error> {
+        let allowances = Self::get_allowances(owner, spender)?;
+        Ok(allowances
+            .allowances
+            .get(&denomination)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn get_allowances(owner: Address, spender: Address) -> Result<types::Allowances, Error> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let spenders = storage::TypedStore::new(storage::PrefixStore::new(allowances, &owner));
+            Ok(spenders.get(spender).unwrap_or_default())
+        })
+    }
+
+    fn transfer_from<C: Context>(
+        ctx: &mut C,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), Error> {
+        if ctx.is_check_only() || amount.amount() == 0 {
+            return Ok(());
+        }
+
+        // Decrease the allowance of the spender.
+        let mut allowances = Self::get_allowances(from, spender)?;
+        let allowance = allowances
+            .allowances
+            .get(amount.denomination())
+            .copied()
+            .unwrap_or_default();
+        let remaining = token::BaseUnits::new(allowance, amount.denomination().clone())
+            .checked_sub(amount)
+            .map_err(|_| Error::InsufficientAllowance)?;
+        if remaining.amount() == 0 {
+            allowances.allowances.remove(amount.denomination());
+        } else {
+            allowances
+                .allowances
+                .insert(amount.denomination().clone(), remaining.amount());
+        }
+        Self::set_allowances(from, spender, allowances);
+
+        Self::transfer(ctx, from, to, amount)
+    }
+
+    fn use_session_spending<C: Context>(
+        ctx: &mut C,
+        from: Address,
+        amount: &token::BaseUnits,
+    ) -> Result<(), modules::core::Error> {
+        let spec = match ctx
+            .value::<SessionKeySpec>(CONTEXT_KEY_ACTIVE_SESSION_KEY)
+            .get()
+        {
+            Some(spec) if spec.account == from => spec.clone(),
+            _ => return Ok(()),
+        };
+
+        let mut session = Self::get_session_key(spec.account, &spec.key).unwrap_or_default();
+        if !session.try_spend(amount) {
+            return Err(modules::core::Error::SessionKeyNotAuthorized(
+                "spending cap exceeded".to_string(),
+            ));
+        }
+        Self::set_session_key(spec.account, &spec.key, session);
+        Ok(())
+    }
+
+    fn emit_encrypted_event<C: Context, E: event::Event>(
+        ctx: &mut C,
+        recipient: Address,
+        event: E,
+    ) -> Result<bool, Error> {
+        let key = match Self::get_encryption_key(recipient)? {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        let event = EncryptedEvent::seal(ctx, &key, event)?;
+        ctx.emit_event(Event::Encrypted { recipient, event });
+
+        Ok(true)
+    }
 }
 
 #[sdk_derive(Module)]
@@ -785,6 +1213,37 @@ impl Module {
             for (denomination, total_supply) in computed_total_supply.iter() {
                 panic!("missing expected total supply: {total_supply} {denomination}",);
             }
+
+            // Create session keys.
+            let mut session_keys = storage::PrefixStore::new(&mut store, &state::SESSION_KEYS);
+            for (address, keys) in genesis.session_keys {
+                let mut account = storage::TypedStore::new(storage::PrefixStore::new(
+                    &mut session_keys,
+                    &address,
+                ));
+                for (key, info) in keys {
+                    account.insert(key, info);
+                }
+            }
+
+            // Register encryption keys.
+            let mut encryption_keys = storage::TypedStore::new(storage::PrefixStore::new(
+                &mut store,
+                &state::ENCRYPTION_KEYS,
+            ));
+            for (address, key) in genesis.encryption_keys {
+                encryption_keys.insert(address, key);
+            }
+
+            // Create allowances.
+            let mut allowances = storage::PrefixStore::new(&mut store, &state::ALLOWANCES);
+            for (owner, spenders) in genesis.allowances {
+                let mut owner =
+                    storage::TypedStore::new(storage::PrefixStore::new(&mut allowances, &owner));
+                for (spender, allowance) in spenders {
+                    owner.insert(spender, allowance);
+                }
+            }
         });
 
         // Validate genesis parameters.
@@ -840,6 +1299,134 @@ impl Module {
         Ok(())
     }
 
+    #[handler(call = "accounts.AuthorizeSessionKey")]
+    fn tx_authorize_session_key<C: TxContext>(
+        ctx: &mut C,
+        body: types::AuthorizeSessionKey,
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_authorize_session_key)?;
+
+        // Session keys cannot be used to manage session keys.
+        if params.max_session_key_duration == 0
+            || matches!(
+                ctx.tx_auth_info().signer_info[0].address_spec,
+                AddressSpec::SessionKey(_)
+            )
+        {
+            return Err(Error::Forbidden);
+        }
+
+        let round = ctx.time().round();
+        if body.methods.is_empty()
+            || body.expiration < round
+            || body.expiration - round > params.max_session_key_duration
+        {
+            return Err(Error::InvalidArgument);
+        }
+
+        // Keep the replay protection window of any previous session using the same key.
+        let account = ctx.tx_caller_address();
+        let previous = Self::get_session_key(account, &body.key).unwrap_or_default();
+        Self::set_session_key(
+            account,
+            &body.key,
+            types::SessionKeyInfo {
+                methods: body.methods,
+                spending_cap: body.spending_cap,
+                spent: 0,
+                expiration: body.expiration,
+                revoked: false,
+                nonce_base: previous.nonce_base,
+                nonce_bitmap: previous.nonce_bitmap,
+            },
+        );
+
+        ctx.emit_event(Event::SessionKeyAuthorized {
+            account,
+            key: body.key,
+            expiration: body.expiration,
+        });
+
+        Ok(())
+    }
+
+    #[handler(call = "accounts.RevokeSessionKey")]
+    fn tx_revoke_session_key<C: TxContext>(
+        ctx: &mut C,
+        body: types::RevokeSessionKey,
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_revoke_session_key)?;
+
+        // Session keys cannot be used to manage session keys.
+        if matches!(
+            ctx.tx_auth_info().signer_info[0].address_spec,
+            AddressSpec::SessionKey(_)
+        ) {
+            return Err(Error::Forbidden);
+        }
+
+        let account = ctx.tx_caller_address();
+        let mut session = Self::get_session_key(account, &body.key).ok_or(Error::NotFound)?;
+        session.revoked = true;
+        Self::set_session_key(account, &body.key, session);
+
+        ctx.emit_event(Event::SessionKeyRevoked {
+            account,
+            key: body.key,
+        });
+
+        Ok(())
+    }
+
+    #[handler(call = "accounts.SetEncryptionKey")]
+    fn tx_set_encryption_key<C: TxContext>(
+        ctx: &mut C,
+        body: types::SetEncryptionKey,
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_set_encryption_key)?;
+
+        let account = ctx.tx_caller_address();
+        Self::set_encryption_key(account, body.key.clone());
+
+        ctx.emit_event(Event::EncryptionKeySet {
+            account,
+            key: body.key,
+        });
+
+        Ok(())
+    }
+
+    #[handler(call = "accounts.Approve")]
+    fn tx_approve<C: TxContext>(ctx: &mut C, body: types::Approve) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_approve)?;
+
+        Self::approve(ctx, ctx.tx_caller_address(), body.spender, &body.amount)
+    }
+
+    #[handler(call = "accounts.TransferFrom")]
+    fn tx_transfer_from<C: TxContext>(ctx: &mut C, body: types::TransferFrom) -> Result<(), Error> {
+        let params = Self::params();
+
+        // Reject transfers when they are disabled.
+        if params.transfers_disabled {
+            return Err(Error::Forbidden);
+        }
+
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_transfer_from)?;
+
+        Self::transfer_from(
+            ctx,
+            ctx.tx_caller_address(),
+            body.from,
+            body.to,
+            &body.amount,
+        )
+    }
+
     #[handler(query = "accounts.Nonce")]
     fn query_nonce<C: Context>(_ctx: &mut C, args: types::NonceQuery) -> Result<u64, Error> {
         Self::get_nonce(args.address)
@@ -849,8 +1436,30 @@ impl Module {
     fn query_addresses<C: Context>(
         _ctx: &mut C,
         args: types::AddressesQuery,
-    ) -> Result<Vec<Address>, Error> {
-        Self::get_addresses(args.denomination)
+    ) -> Result<PageResponse<Address>, Error> {
+        let start = args
+            .pagination
+            .start::<Address>()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let balances = storage::StoreMap::<_, Vec<u8>, u128>::new(storage::PrefixStore::new(
+                store,
+                &state::BALANCES,
+            ));
+            // Balance keys are (address, denomination) so the cursor is just the address.
+            let start = start.map(|address| address.as_ref().to_vec());
+            let addresses =
+                balances
+                    .iter_from(&start.unwrap_or_default())
+                    .filter_map(|(key, _)| {
+                        let AddressWithDenomination(address, denomination) =
+                            key.as_slice().try_into().expect("corrupted storage key");
+                        (denomination == args.denomination).then_some((address, address))
+                    });
+            Ok(paginate(addresses, &args.pagination))
+        })
     }
 
     #[handler(query = "accounts.Balances")]
@@ -868,6 +1477,22 @@ impl Module {
     ) -> Result<types::DenominationInfo, Error> {
         Self::get_denomination_info(&args.denomination)
     }
+
+    #[handler(query = "accounts.EncryptionKey")]
+    fn query_encryption_key<C: Context>(
+        _ctx: &mut C,
+        args: types::EncryptionKeyQuery,
+    ) -> Result<Option<x25519::PublicKey>, Error> {
+        Self::get_encryption_key(args.address)
+    }
+
+    #[handler(query = "accounts.Allowances")]
+    fn query_allowances<C: Context>(
+        _ctx: &mut C,
+        args: types::AllowancesQuery,
+    ) -> Result<types::Allowances, Error> {
+        Self::get_allowances(args.owner, args.spender)
+    }
 }
 
 impl module::TransactionHandler for Module {
@@ -875,8 +1500,12 @@ impl module::TransactionHandler for Module {
         ctx: &mut C,
         tx: &Transaction,
     ) -> Result<(), modules::core::Error> {
+        // Clear any session key left over from a previous transaction.
+        ctx.value::<SessionKeySpec>(CONTEXT_KEY_ACTIVE_SESSION_KEY)
+            .take();
+
         // Check whether the transaction is currently valid.
-        let round = ctx.runtime_header().round;
+        let round = ctx.time().round();
         if let Some(not_before) = tx.auth_info.not_before {
             if round < not_before {
                 // Too early.
@@ -891,10 +1520,28 @@ impl module::TransactionHandler for Module {
         }
 
         // Check nonces.
-        let payer = Self::check_signer_nonces(ctx, &tx.auth_info)?;
+        let signer = Self::check_signer_nonces(ctx, &tx.auth_info)?;
+        // Check session key authorization.
+        let session_key = Self::check_session_key(ctx, tx)?;
+        // Fees are paid by the fee payer when one is given.
+        let signer = tx.auth_info.fee_payer_address().unwrap_or(signer);
+
+        // Fees are paid from the allowance of the fee granter when one is given.
+        let granter = if tx.auth_info.fee.amount.amount().is_zero() {
+            None
+        } else {
+            <C::Runtime as Runtime>::FeeGrants::fee_granter(ctx, tx, signer)?
+        };
+        if ctx.is_check_only() {
+            // Remember the granter so that the allowance can be updated after checks pass.
+            ctx.value(CONTEXT_KEY_FEE_GRANTER).set(granter);
+        }
 
         // Charge the specified amount of fees.
+        let mut grant = None;
         if !tx.auth_info.fee.amount.amount().is_zero() {
+            let payer = granter.unwrap_or(signer);
+
             if ctx.is_check_only() {
                 // Do not update balances during transaction checks. In case of checks, only do it
                 // after all the other checks have already passed as otherwise retrying the
@@ -904,9 +1551,21 @@ impl module::TransactionHandler for Module {
             } else {
                 // Actually perform the move.
                 Self::charge_tx_fee(ctx, payer, &tx.auth_info.fee.amount)?;
+
+                if let Some(granter) = granter {
+                    <C::Runtime as Runtime>::FeeGrants::use_fee_grant(
+                        ctx,
+                        granter,
+                        signer,
+                        &tx.auth_info.fee.amount,
+                    )?;
+                    grant = Some((granter, signer));
+                }
             }
 
-            let gas_price = tx.auth_info.fee.gas_price();
+            // Use the native-equivalent gas price so that fees paid in converted denominations are
+            // prioritized the same way as fees paid in the native denomination.
+            let gas_price = <C::Runtime as Runtime>::Core::native_gas_price(&tx.auth_info.fee);
             // Set transaction priority.
             <C::Runtime as Runtime>::Core::set_priority(
                 ctx,
@@ -918,7 +1577,16 @@ impl module::TransactionHandler for Module {
         // all the other checks have already passed as otherwise retrying the transaction will not
         // be possible.
         if !ctx.is_check_only() {
+            // Remember the used allowance so that any refunded fees can be returned to it.
+            ctx.value(CONTEXT_KEY_FEE_GRANT_USED).set(grant);
+
             Self::update_signer_nonces(ctx, &tx.auth_info)?;
+
+            if let Some((spec, session)) = session_key {
+                Self::set_session_key(spec.account, &spec.key, session);
+                // Account for any further spending while the transaction executes.
+                ctx.value(CONTEXT_KEY_ACTIVE_SESSION_KEY).set(spec);
+            }
         }
 
         Ok(())
@@ -964,6 +1632,10 @@ impl module::TransactionHandler for Module {
         tx_auth_info: &AuthInfo,
         result: &module::CallResult,
     ) {
+        // The session key only applies to the transaction that it authorized.
+        ctx.value::<SessionKeySpec>(CONTEXT_KEY_ACTIVE_SESSION_KEY)
+            .take();
+
         // Move transaction fees into the per-block fee accumulator.
         let mgr = ctx
             .value::<fee::FeeManager>(CONTEXT_KEY_FEE_MANAGER)
@@ -971,6 +1643,24 @@ impl module::TransactionHandler for Module {
         let fee_updates = mgr.commit_tx();
         // Refund any fees. This needs to happen after tx dispatch to ensure state is updated.
         Self::add_amount(fee_updates.payer, &fee_updates.refund).unwrap();
+        // Return refunded fees to the allowance they were paid from (if any).
+        let grant: Option<(Address, Address)> = ctx
+            .value(CONTEXT_KEY_FEE_GRANT_USED)
+            .take()
+            .unwrap_or_default();
+        if let Some((granter, payer)) = grant {
+            <C::Runtime as Runtime>::FeeGrants::refund_fee_grant(
+                ctx,
+                granter,
+                payer,
+                &fee_updates.refund,
+            );
+        }
+        // Fee granter is only recorded during transaction checks.
+        let granter: Option<Address> = ctx
+            .value(CONTEXT_KEY_FEE_GRANTER)
+            .take()
+            .unwrap_or_default();
 
         if !ctx.is_check_only() {
             // Do nothing further outside transaction checks.
@@ -982,15 +1672,36 @@ impl module::TransactionHandler for Module {
         }
 
         // Update payer balance.
-        let payer = Self::check_signer_nonces(ctx, tx_auth_info).unwrap(); // Already checked.
+        let signer = Self::check_signer_nonces(ctx, tx_auth_info).unwrap(); // Already checked.
+        let signer = tx_auth_info.fee_payer_address().unwrap_or(signer);
         let amount = &tx_auth_info.fee.amount;
-        Self::sub_amount(payer, amount).unwrap(); // Already checked.
+        Self::sub_amount(ctx, granter.unwrap_or(signer), amount).unwrap(); // Already checked.
+        if let Some(granter) = granter {
+            // Already checked.
+            <C::Runtime as Runtime>::FeeGrants::use_fee_grant(ctx, granter, signer, amount)
+                .unwrap();
+        }
 
         // Update nonces.
         Self::update_signer_nonces(ctx, tx_auth_info).unwrap();
     }
 }
 
+impl module::PreviewHandler for Module {
+    fn preview_call(method: &str, body: &cbor::Value) -> Option<Vec<PreviewField>> {
+        match method {
+            "accounts.Transfer" => {
+                let body: types::Transfer = cbor::from_value(body.clone()).ok()?;
+                Some(vec![
+                    PreviewField::new("To", body.to),
+                    PreviewField::new("Amount", body.amount),
+                ])
+            }
+            _ => None,
+        }
+    }
+}
+
 impl module::BlockHandler for Module {
     fn end_block<C: Context>(ctx: &mut C) {
         // Determine the fees that are available for disbursement from the last block.
@@ -1001,6 +1712,7 @@ impl module::BlockHandler for Module {
         // Drain previous fees from the fee accumulator.
         for (denom, remainder) in &previous_fees {
             Self::sub_amount(
+                ctx,
                 *ADDRESS_FEE_ACCUMULATOR,
                 &token::BaseUnits::new(*remainder, denom.clone()),
             )
@@ -1137,12 +1849,64 @@ impl module::InvariantHandler for Module {
         // There should be no remaining denominations in the computed supplies,
         // because that would mean that accounts have denominations that don't
         // appear in the total supplies table, which would obviously be wrong.
-        if computed_ts.is_empty() {
-            Ok(())
-        } else {
-            Err(CoreError::InvariantViolation(
+        if !computed_ts.is_empty() {
+            return Err(CoreError::InvariantViolation(
                 "encountered denomination that isn't present in total supplies table".to_string(),
-            ))
+            ));
+        }
+
+        // Escrowed storage deposits should match the tracked deposits.
+        modules::core::storage_deposit::check_invariants::<Self>()
+    }
+}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        let (accounts, session_keys, encryption_keys, allowances) = CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let accounts: BTreeMap<Address, types::Account> =
+                storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::ACCOUNTS))
+                    .iter()
+                    .collect();
+
+            let mut session_keys: BTreeMap<Address, BTreeMap<Address, types::SessionKeyInfo>> =
+                BTreeMap::new();
+            let keys = storage::TypedStore::new(storage::PrefixStore::new(
+                &mut store,
+                &state::SESSION_KEYS,
+            ));
+            for (AddressPair(address, key), info) in keys.iter() {
+                session_keys.entry(address).or_default().insert(key, info);
+            }
+
+            let encryption_keys: BTreeMap<Address, x25519::PublicKey> = storage::TypedStore::new(
+                storage::PrefixStore::new(&mut store, &state::ENCRYPTION_KEYS),
+            )
+            .iter()
+            .collect();
+
+            let mut allowances: BTreeMap<Address, BTreeMap<Address, types::Allowances>> =
+                BTreeMap::new();
+            let spenders =
+                storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::ALLOWANCES));
+            for (AddressPair(owner, spender), allowance) in spenders.iter() {
+                allowances
+                    .entry(owner)
+                    .or_default()
+                    .insert(spender, allowance);
+            }
+
+            (accounts, session_keys, encryption_keys, allowances)
+        });
+
+        Genesis {
+            parameters: Self::params(),
+            accounts,
+            balances: Self::get_all_balances().expect("balances should be readable"),
+            total_supplies: Self::get_total_supplies().expect("total supplies should be readable"),
+            session_keys,
+            encryption_keys,
+            allowances,
         }
     }
 }
@@ -8,6 +8,8 @@ use anyhow::anyhow;
 
 use crate::{
     context::{self, BatchContext, Context, TxContext},
+    core::common::crypto::x25519,
+    event::IntoTags as _,
     handler,
     module::{self, BlockHandler, InvariantHandler, MethodHandler, Module, TransactionHandler},
     modules::{
@@ -18,6 +20,7 @@ use crate::{
     testing::{keys, mock},
     types::{
         address::Address,
+        pagination::PageRequest,
         token::{BaseUnits, Denomination},
         transaction,
     },
@@ -51,6 +54,7 @@ impl Runtime for TestRuntime {
                     min_gas_price: BTreeMap::from([(Denomination::NATIVE, 0)]),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             Genesis {
                 balances: BTreeMap::from([(
@@ -59,7 +63,10 @@ impl Runtime for TestRuntime {
                 )]),
                 total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000_000)]),
                 parameters: Parameters {
-                    gas_costs: GasCosts { tx_transfer: 1_000 },
+                    gas_costs: GasCosts {
+                        tx_transfer: 1_000,
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 ..Default::default()
@@ -526,6 +533,295 @@ fn test_authenticate_tx() {
     assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
 }
 
+#[test]
+fn test_authenticate_tx_fee_payer() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+
+    // Bob has no funds, but Alice sponsors the transaction fee.
+    let tx = transaction::Transaction {
+        version: 1,
+        call: transaction::Call {
+            format: transaction::CallFormat::Plain,
+            method: "accounts.Transfer".to_owned(),
+            body: cbor::to_value(Transfer {
+                to: keys::charlie::address(),
+                amount: BaseUnits::new(0, Denomination::NATIVE),
+            }),
+            ..Default::default()
+        },
+        auth_info: transaction::AuthInfo {
+            signer_info: vec![transaction::SignerInfo::new_sigspec(
+                keys::bob::sigspec(),
+                0,
+            )],
+            fee: transaction::Fee {
+                amount: BaseUnits::new(1_000, Denomination::NATIVE),
+                gas: 1000,
+                consensus_messages: 0,
+            },
+            fee_payer: Some(transaction::SignerInfo::new_sigspec(
+                keys::alice::sigspec(),
+                0,
+            )),
+            ..Default::default()
+        },
+    };
+
+    Accounts::authenticate_tx(&mut ctx, &tx).expect("transaction authentication should succeed");
+    let bals = Accounts::get_balances(keys::alice::address()).expect("get_balances should succeed");
+    assert_eq!(
+        bals.balances[&Denomination::NATIVE],
+        999_000,
+        "fees should be subtracted from the fee payer"
+    );
+    let bals = Accounts::get_balances(keys::bob::address()).expect("get_balances should succeed");
+    assert!(bals.balances.is_empty(), "caller should not pay any fees");
+
+    // Only the caller's nonce should be incremented.
+    let nonce = Accounts::get_nonce(keys::bob::address()).expect("get_nonce should succeed");
+    assert_eq!(nonce, 1, "caller nonce should be incremented");
+    let nonce = Accounts::get_nonce(keys::alice::address()).expect("get_nonce should succeed");
+    assert_eq!(nonce, 0, "fee payer nonce should not be incremented");
+
+    // The fee payer should be able to sponsor another transaction with the same nonce.
+    let mut tx = tx;
+    tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::charlie::sigspec(),
+        0,
+    )];
+    Accounts::authenticate_tx(&mut ctx, &tx).expect("transaction authentication should succeed");
+    let bals = Accounts::get_balances(keys::alice::address()).expect("get_balances should succeed");
+    assert_eq!(
+        bals.balances[&Denomination::NATIVE],
+        998_000,
+        "fees should be subtracted from the fee payer"
+    );
+    let nonce = Accounts::get_nonce(keys::charlie::address()).expect("get_nonce should succeed");
+    assert_eq!(nonce, 1, "caller nonce should be incremented");
+
+    // Replaying the transaction should fail due to the caller's nonce.
+    let result = Accounts::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(core::Error::InvalidNonce)));
+}
+
+#[test]
+fn test_session_key_nonce_window() {
+    let mut session = SessionKeyInfo::default();
+    assert!(session.is_nonce_usable(0));
+    session.use_nonce(0);
+    assert!(!session.is_nonce_usable(0), "used nonce should be rejected");
+
+    // Nonces may be used out of order within the window.
+    session.use_nonce(10);
+    assert!(session.is_nonce_usable(5));
+    assert!(
+        !session.is_nonce_usable(10),
+        "used nonce should be rejected"
+    );
+
+    // Using a nonce above the window advances it.
+    session.use_nonce(100);
+    assert_eq!(session.nonce_base, 100 - SESSION_KEY_NONCE_WINDOW + 1);
+    assert!(
+        !session.is_nonce_usable(5),
+        "nonce below window should be rejected"
+    );
+    assert!(
+        !session.is_nonce_usable(100),
+        "used nonce should be rejected"
+    );
+    assert!(session.is_nonce_usable(99));
+    assert!(session.is_nonce_usable(1_000));
+}
+
+#[test]
+fn test_authenticate_tx_session_key() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+    Accounts::set_params(Parameters {
+        max_session_key_duration: 100,
+        ..Accounts::params()
+    });
+
+    // Alice authorizes Bob's key as a session key.
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::alice::sigspec(),
+        0,
+    )];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Accounts::tx_authorize_session_key(
+            &mut tx_ctx,
+            AuthorizeSessionKey {
+                key: keys::bob::sigspec(),
+                methods: vec!["accounts.Transfer".to_owned()],
+                spending_cap: BaseUnits::new(1_500, Denomination::NATIVE),
+                expiration: 10,
+            },
+        )
+        .expect("authorizing a session key should succeed");
+    });
+
+    let session_tx = |method: &str, amount: u128, nonce: u64| transaction::Transaction {
+        version: 1,
+        call: transaction::Call {
+            format: transaction::CallFormat::Plain,
+            method: method.to_owned(),
+            body: cbor::to_value(Transfer {
+                to: keys::charlie::address(),
+                amount: BaseUnits::new(amount, Denomination::NATIVE),
+            }),
+            ..Default::default()
+        },
+        auth_info: transaction::AuthInfo {
+            signer_info: vec![transaction::SignerInfo {
+                address_spec: transaction::AddressSpec::SessionKey(transaction::SessionKeySpec {
+                    account: keys::alice::address(),
+                    key: keys::bob::sigspec(),
+                }),
+                nonce,
+            }],
+            fee: transaction::Fee {
+                amount: BaseUnits::new(100, Denomination::NATIVE),
+                gas: 1000,
+                consensus_messages: 0,
+            },
+            ..Default::default()
+        },
+    };
+
+    let tx = session_tx("accounts.Transfer", 1_000, 5);
+    Accounts::authenticate_tx(&mut ctx, &tx).expect("transaction authentication should succeed");
+    let bals = Accounts::get_balances(keys::alice::address()).expect("get_balances should succeed");
+    assert_eq!(
+        bals.balances[&Denomination::NATIVE],
+        999_900,
+        "fees should be subtracted from the authorizing account"
+    );
+    let nonce = Accounts::get_nonce(keys::alice::address()).expect("get_nonce should succeed");
+    assert_eq!(nonce, 0, "account nonce should not be incremented");
+    let session = Accounts::get_session_key(keys::alice::address(), &keys::bob::sigspec())
+        .expect("session key should exist");
+    assert_eq!(session.spent, 100, "fee should count towards the cap");
+
+    // Any amounts debited from the authorizing account should count towards the cap.
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, _call| {
+        Accounts::transfer(
+            &mut tx_ctx,
+            keys::alice::address(),
+            keys::charlie::address(),
+            &BaseUnits::new(1_000, Denomination::NATIVE),
+        )
+        .expect("transfer should succeed");
+    });
+    Accounts::transfer(
+        &mut ctx,
+        keys::charlie::address(),
+        keys::bob::address(),
+        &BaseUnits::new(500, Denomination::NATIVE),
+    )
+    .expect("transfer from another account should succeed");
+    let session = Accounts::get_session_key(keys::alice::address(), &keys::bob::sigspec())
+        .expect("session key should exist");
+    assert_eq!(
+        session.spent, 1_100,
+        "only spending by the authorizing account should count towards the cap"
+    );
+
+    // Increasing allowances should count towards the cap.
+    Accounts::approve(
+        &mut ctx,
+        keys::alice::address(),
+        keys::dave::address(),
+        &BaseUnits::new(300, Denomination::NATIVE),
+    )
+    .expect("approve should succeed");
+    let session = Accounts::get_session_key(keys::alice::address(), &keys::bob::sigspec())
+        .expect("session key should exist");
+    assert_eq!(
+        session.spent, 1_400,
+        "allowance should count towards the cap"
+    );
+
+    // Spending over the cap should be rejected.
+    let result = Accounts::transfer(
+        &mut ctx,
+        keys::alice::address(),
+        keys::charlie::address(),
+        &BaseUnits::new(101, Denomination::NATIVE),
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Core(CoreError::SessionKeyNotAuthorized(_)))
+    ));
+    let result = Accounts::approve(
+        &mut ctx,
+        keys::alice::address(),
+        keys::dave::address(),
+        &BaseUnits::new(401, Denomination::NATIVE),
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Core(CoreError::SessionKeyNotAuthorized(_)))
+    ));
+    let bals = Accounts::get_balances(keys::alice::address()).expect("get_balances should succeed");
+    assert_eq!(bals.balances[&Denomination::NATIVE], 998_900);
+
+    // The session key should no longer apply once the transaction has been dispatched.
+    Accounts::after_dispatch_tx(
+        &mut ctx,
+        &tx.auth_info,
+        &module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+    );
+    Accounts::transfer(
+        &mut ctx,
+        keys::alice::address(),
+        keys::charlie::address(),
+        &BaseUnits::new(1_000, Denomination::NATIVE),
+    )
+    .expect("transfer should succeed");
+
+    // Replaying the same nonce should fail.
+    let result = Accounts::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(CoreError::InvalidNonce)));
+
+    // Methods that have not been allowed should be rejected.
+    let tx = session_tx("accounts.Other", 0, 6);
+    let result = Accounts::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(CoreError::SessionKeyNotAuthorized(_))));
+
+    // Fees over the cap should be rejected.
+    let tx = session_tx("accounts.Transfer", 0, 6);
+    Accounts::authenticate_tx(&mut ctx, &tx).expect("transaction authentication should succeed");
+    let tx = session_tx("accounts.Transfer", 0, 7);
+    let result = Accounts::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(CoreError::SessionKeyNotAuthorized(_))));
+
+    // Revoked session keys should be rejected.
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::alice::sigspec(),
+        0,
+    )];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Accounts::tx_revoke_session_key(
+            &mut tx_ctx,
+            RevokeSessionKey {
+                key: keys::bob::sigspec(),
+            },
+        )
+        .expect("revoking a session key should succeed");
+    });
+    let tx = session_tx("accounts.Transfer", 0, 8);
+    let result = Accounts::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(CoreError::SessionKeyNotAuthorized(_))));
+}
+
 #[test]
 fn test_tx_transfer() {
     let mut mock = mock::Mock::default();
@@ -727,10 +1023,11 @@ fn test_query_addresses() {
         &mut ctx,
         AddressesQuery {
             denomination: dn.clone(),
+            ..Default::default()
         },
     )
     .expect("query accounts should succeed");
-    assert_eq!(accs.len(), 0, "there should be no accounts initially");
+    assert_eq!(accs.items.len(), 0, "there should be no accounts initially");
 
     let gen = Genesis {
         balances: {
@@ -762,23 +1059,69 @@ fn test_query_addresses() {
     Accounts::init(gen);
 
     ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
-        let accs = Accounts::query_addresses(&mut tx_ctx, AddressesQuery { denomination: d1 })
-            .expect("query accounts should succeed");
-        assert_eq!(accs.len(), 2, "there should be two addresses");
+        let query = AddressesQuery {
+            denomination: d1.clone(),
+            ..Default::default()
+        };
+        let accs =
+            Accounts::query_addresses(&mut tx_ctx, query).expect("query accounts should succeed");
+        assert_eq!(accs.items.len(), 2, "there should be two addresses");
         assert_eq!(
-            accs,
+            accs.items,
             Vec::from_iter([keys::bob::address(), keys::alice::address()]),
             "addresses should be correct"
         );
+        assert_eq!(accs.next, None, "there should be no further pages");
 
-        let accs = Accounts::query_addresses(&mut tx_ctx, AddressesQuery { denomination: dn })
-            .expect("query accounts should succeed");
-        assert_eq!(accs.len(), 1, "there should be one address");
+        let query = AddressesQuery {
+            denomination: dn.clone(),
+            ..Default::default()
+        };
+        let accs =
+            Accounts::query_addresses(&mut tx_ctx, query).expect("query accounts should succeed");
+        assert_eq!(accs.items.len(), 1, "there should be one address");
         assert_eq!(
-            accs,
+            accs.items,
             Vec::from_iter([keys::alice::address()]),
             "addresses should be correct"
         );
+
+        // Addresses can be fetched one page at a time.
+        let query = AddressesQuery {
+            denomination: d1.clone(),
+            pagination: PageRequest {
+                cursor: None,
+                limit: 1,
+            },
+        };
+        let accs =
+            Accounts::query_addresses(&mut tx_ctx, query).expect("query accounts should succeed");
+        assert_eq!(accs.items, vec![keys::bob::address()]);
+        assert!(accs.next.is_some(), "there should be another page");
+
+        let query = AddressesQuery {
+            denomination: d1,
+            pagination: PageRequest {
+                cursor: accs.next,
+                limit: 1,
+            },
+        };
+        let accs =
+            Accounts::query_addresses(&mut tx_ctx, query).expect("query accounts should succeed");
+        assert_eq!(accs.items, vec![keys::alice::address()]);
+        assert_eq!(accs.next, None, "there should be no further pages");
+
+        let query = AddressesQuery {
+            denomination: dn,
+            pagination: PageRequest {
+                cursor: Some(vec![0xff]),
+                limit: 1,
+            },
+        };
+        assert!(matches!(
+            Accounts::query_addresses(&mut tx_ctx, query),
+            Err(Error::InvalidArgument)
+        ));
     });
 }
 
@@ -1260,6 +1603,7 @@ fn test_transaction_expiry() {
             },
             not_before: Some(10),
             not_after: Some(42),
+            ..Default::default()
         },
     };
 
@@ -1470,3 +1814,222 @@ fn test_pool_addresses() {
         "oasis1qp3r8hgsnphajmfzfuaa8fhjag7e0yt35cjxq0u4"
     );
 }
+
+#[test]
+fn test_encrypted_events() {
+    let mut mock = mock::Mock::default();
+
+    let sk = x25519_dalek::StaticSecret::from([1u8; 32]);
+    let pk = x25519::PublicKey(x25519_dalek::PublicKey::from(&sk));
+    let sk = x25519::PrivateKey(sk);
+    let event = super::Event::Mint {
+        owner: keys::bob::address(),
+        amount: BaseUnits::new(1_000, Denomination::NATIVE),
+    };
+
+    // Encrypted events require a confidential runtime.
+    {
+        let mut ctx = mock.create_ctx();
+        Accounts::set_encryption_key(keys::bob::address(), Some(pk));
+        Accounts::emit_encrypted_event(&mut ctx, keys::bob::address(), ())
+            .expect_err("encrypting events should fail in non-confidential runtimes");
+    }
+
+    let mut ctx = mock.create_ctx_for_runtime::<mock::EmptyRuntime>(context::Mode::ExecuteTx, true);
+    let key = Accounts::get_encryption_key(keys::bob::address())
+        .unwrap()
+        .expect("encryption key should be registered");
+    assert_eq!(key.0.as_bytes(), pk.0.as_bytes());
+
+    // Nothing should be emitted for recipients without an encryption key.
+    let emitted = Accounts::emit_encrypted_event(&mut ctx, keys::alice::address(), ())
+        .expect("emitting encrypted events should succeed");
+    assert!(
+        !emitted,
+        "nothing should be emitted without an encryption key"
+    );
+
+    let emitted = Accounts::emit_encrypted_event(&mut ctx, keys::bob::address(), event)
+        .expect("emitting encrypted events should succeed");
+    assert!(emitted, "event should be emitted");
+
+    let tags = ctx.commit().events.into_tags();
+    assert_eq!(tags.len(), 1, "one event should have been emitted");
+    assert_eq!(tags[0].key, b"accounts\x00\x00\x00\x07");
+
+    #[derive(Debug, cbor::Decode)]
+    #[cbor(no_default)]
+    struct EncryptedEvent {
+        recipient: Address,
+        event: crate::event::EncryptedEvent,
+    }
+
+    let events: Vec<EncryptedEvent> = cbor::from_slice(&tags[0].value).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].recipient, keys::bob::address());
+
+    let payload = events[0]
+        .event
+        .open(&sk)
+        .expect("recipient should be able to decrypt the event");
+    assert_eq!(payload.module, "accounts");
+    assert_eq!(payload.code, 3);
+    assert_eq!(
+        payload.value,
+        cbor::to_value(super::Event::Mint {
+            owner: keys::bob::address(),
+            amount: BaseUnits::new(1_000, Denomination::NATIVE),
+        })
+    );
+
+    let other = x25519::PrivateKey(x25519_dalek::StaticSecret::from([2u8; 32]));
+    events[0]
+        .event
+        .open(&other)
+        .expect_err("other keys should not be able to decrypt the event");
+
+    // Clearing the key should stop delivery.
+    Accounts::set_encryption_key(keys::bob::address(), None);
+    assert!(Accounts::get_encryption_key(keys::bob::address())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_allowances() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    let amount = |amount: u128| BaseUnits::new(amount, Denomination::NATIVE);
+
+    Accounts::set_balance(keys::alice::address(), &amount(1_000));
+    Accounts::approve(
+        &mut ctx,
+        keys::alice::address(),
+        keys::bob::address(),
+        &amount(300),
+    )
+    .expect("approval should succeed");
+    assert_eq!(
+        Accounts::get_allowance(
+            keys::alice::address(),
+            keys::bob::address(),
+            Denomination::NATIVE
+        )
+        .unwrap(),
+        300
+    );
+
+    // Transfers on behalf of the owner decrease the allowance.
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::bob::sigspec(),
+        0,
+    )];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Accounts::tx_transfer_from(
+            &mut tx_ctx,
+            TransferFrom {
+                from: keys::alice::address(),
+                to: keys::charlie::address(),
+                amount: amount(200),
+            },
+        )
+        .expect("transfer from should succeed");
+
+        let tags = tx_ctx.commit().events.into_tags();
+        assert_eq!(tags.len(), 1, "transfer event should be emitted");
+        assert_eq!(tags[0].key, b"accounts\x00\x00\x00\x01");
+    });
+    assert_eq!(
+        Accounts::get_balance(keys::charlie::address(), Denomination::NATIVE).unwrap(),
+        200
+    );
+    assert_eq!(
+        Accounts::get_balance(keys::alice::address(), Denomination::NATIVE).unwrap(),
+        800
+    );
+    assert_eq!(
+        Accounts::get_allowances(keys::alice::address(), keys::bob::address()).unwrap(),
+        Allowances {
+            allowances: BTreeMap::from([(Denomination::NATIVE, 100)]),
+        }
+    );
+
+    // Transfers exceeding the allowance are rejected.
+    let result = Accounts::transfer_from(
+        &mut ctx,
+        keys::bob::address(),
+        keys::alice::address(),
+        keys::charlie::address(),
+        &amount(200),
+    );
+    assert!(matches!(result, Err(Error::InsufficientAllowance)));
+    let result = Accounts::transfer_from(
+        &mut ctx,
+        keys::charlie::address(),
+        keys::alice::address(),
+        keys::charlie::address(),
+        &amount(1),
+    );
+    assert!(matches!(result, Err(Error::InsufficientAllowance)));
+
+    // Approving a zero amount revokes the allowance.
+    Accounts::approve(
+        &mut ctx,
+        keys::alice::address(),
+        keys::bob::address(),
+        &amount(0),
+    )
+    .expect("approval should succeed");
+    assert_eq!(
+        Accounts::get_allowances(keys::alice::address(), keys::bob::address()).unwrap(),
+        Allowances::default()
+    );
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init_accounts(&mut ctx);
+
+    Accounts::set_nonce(keys::alice::address(), 5);
+    Accounts::set_session_key(
+        keys::alice::address(),
+        &keys::bob::sigspec(),
+        SessionKeyInfo {
+            methods: vec!["accounts.Transfer".to_owned()],
+            spending_cap: BaseUnits::new(1_000, Denomination::NATIVE),
+            expiration: 10,
+            ..Default::default()
+        },
+    );
+    let pk = x25519::PublicKey(x25519_dalek::PublicKey::from(
+        &x25519_dalek::StaticSecret::from([1u8; 32]),
+    ));
+    Accounts::set_encryption_key(keys::bob::address(), Some(pk));
+    Accounts::approve(
+        &mut ctx,
+        keys::alice::address(),
+        keys::bob::address(),
+        &BaseUnits::new(300, Denomination::NATIVE),
+    )
+    .expect("approval should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Accounts>();
+    assert_eq!(genesis.accounts[&keys::alice::address()].nonce, 5);
+    assert_eq!(genesis.balances.len(), 1);
+    assert_eq!(genesis.total_supplies[&Denomination::NATIVE], 1_000_000);
+    assert_eq!(
+        genesis.session_keys[&keys::alice::address()][&Address::from_sigspec(&keys::bob::sigspec())]
+            .spending_cap,
+        BaseUnits::new(1_000, Denomination::NATIVE)
+    );
+    assert!(genesis.encryption_keys.contains_key(&keys::bob::address()));
+    assert_eq!(
+        genesis.allowances[&keys::alice::address()][&keys::bob::address()],
+        Allowances {
+            allowances: BTreeMap::from([(Denomination::NATIVE, 300)]),
+        }
+    );
+}
@@ -1,55 +1,253 @@
 //! Account module types.
 use std::collections::BTreeMap;
 
-use crate::types::{address::Address, token};
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::{
+    core::common::crypto::x25519,
+    types::{
+        address::{Address, SignatureAddressSpec},
+        pagination::PageRequest,
+        token,
+    },
+};
+
+/// Size of the session key nonce replay protection window.
+pub const SESSION_KEY_NONCE_WINDOW: u64 = 64;
 
 /// Transfer call.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Transfer {
     pub to: Address,
     pub amount: token::BaseUnits,
 }
 
 /// Account metadata.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Account {
     #[cbor(optional)]
     pub nonce: u64,
 }
 
 /// Arguments for the Nonce query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NonceQuery {
     pub address: Address,
 }
 
 /// Arguments for the Addresses query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AddressesQuery {
     pub denomination: token::Denomination,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
 }
 
 /// Arguments for the Balances query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BalancesQuery {
     pub address: Address,
 }
 
 /// Balances in an account.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AccountBalances {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::u128_map"))]
     pub balances: BTreeMap<token::Denomination, u128>,
 }
 
 /// Arguments for the DenominationInfo query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DenominationInfoQuery {
     pub denomination: token::Denomination,
 }
 
 /// Information about a denomination.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DenominationInfo {
     /// Number of decimals that the denomination is using.
     pub decimals: u8,
 }
+
+impl DenominationInfo {
+    /// Render the given amount as a decimal number using the denomination's decimals.
+    pub fn format_amount(&self, amount: &token::BaseUnits) -> String {
+        amount.to_decimal_string(self.decimals)
+    }
+
+    /// Parse a decimal number into an amount using the denomination's decimals.
+    pub fn parse_amount(
+        &self,
+        value: &str,
+        denomination: token::Denomination,
+    ) -> Result<token::BaseUnits, token::Error> {
+        token::BaseUnits::from_decimal_str(value, self.decimals, denomination)
+    }
+}
+
+/// Authorize session key call.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct AuthorizeSessionKey {
+    /// Session key to authorize.
+    pub key: SignatureAddressSpec,
+    /// Methods that the session key may call.
+    pub methods: Vec<String>,
+    /// Maximum amount that transactions signed by the session key may spend on fees and
+    /// transfers over the lifetime of the session.
+    pub spending_cap: token::BaseUnits,
+    /// Last round in which the session key is valid.
+    pub expiration: u64,
+}
+
+/// Revoke session key call.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct RevokeSessionKey {
+    /// Session key to revoke.
+    pub key: SignatureAddressSpec,
+}
+
+/// Set encryption key call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SetEncryptionKey {
+    /// X25519 public key that events addressed to the caller should be encrypted to. If not set,
+    /// any previously registered key is cleared.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_encoded"))]
+    pub key: Option<x25519::PublicKey>,
+}
+
+/// Approve call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Approve {
+    /// Address that is allowed to transfer funds from the caller's account.
+    pub spender: Address,
+    /// Maximum amount that can be transferred. Zero revokes the allowance of the denomination.
+    pub amount: token::BaseUnits,
+}
+
+/// Transfer from call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransferFrom {
+    /// Address of the account that approved the caller to transfer its funds.
+    pub from: Address,
+    pub to: Address,
+    pub amount: token::BaseUnits,
+}
+
+/// Arguments for the Allowances query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AllowancesQuery {
+    pub owner: Address,
+    pub spender: Address,
+}
+
+/// Allowances given by an owner to a spender.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Allowances {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::u128_map"))]
+    pub allowances: BTreeMap<token::Denomination, u128>,
+}
+
+/// Arguments for the EncryptionKey query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EncryptionKeyQuery {
+    pub address: Address,
+}
+
+/// Arguments for the SessionKey query.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct SessionKeyQuery {
+    pub account: Address,
+    pub key: SignatureAddressSpec,
+}
+
+/// Information about an authorized session key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SessionKeyInfo {
+    /// Methods that the session key may call.
+    pub methods: Vec<String>,
+    /// Maximum amount that may be spent over the lifetime of the session.
+    pub spending_cap: token::BaseUnits,
+    /// Amount already spent.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::u128_string"))]
+    pub spent: u128,
+    /// Last round in which the session key is valid.
+    pub expiration: u64,
+    /// Whether the session key has been revoked.
+    #[cbor(optional)]
+    pub revoked: bool,
+    /// Lowest nonce of the replay protection window.
+    #[cbor(optional)]
+    pub nonce_base: u64,
+    /// Bitmap of nonces in the replay protection window that have already been used.
+    #[cbor(optional)]
+    pub nonce_bitmap: u64,
+}
+
+impl SessionKeyInfo {
+    /// Whether the given nonce has not been used yet and is not below the replay protection
+    /// window. Nonces above the window are accepted and advance the window when used.
+    pub fn is_nonce_usable(&self, nonce: u64) -> bool {
+        match nonce.checked_sub(self.nonce_base) {
+            None => false,
+            Some(offset) if offset < SESSION_KEY_NONCE_WINDOW => {
+                self.nonce_bitmap & (1 << offset) == 0
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Mark the given nonce as used, advancing the replay protection window if needed.
+    pub fn use_nonce(&mut self, nonce: u64) {
+        let top = self.nonce_base + SESSION_KEY_NONCE_WINDOW - 1;
+        if nonce > top {
+            let shift = nonce - top;
+            self.nonce_bitmap = if shift >= SESSION_KEY_NONCE_WINDOW {
+                0
+            } else {
+                self.nonce_bitmap >> shift
+            };
+            self.nonce_base += shift;
+        }
+        self.nonce_bitmap |= 1 << (nonce - self.nonce_base);
+    }
+    /// Account the given amount against the spending cap. Returns `false` without updating the
+    /// spent amount in case the cap would be exceeded or the denomination does not match.
+    pub fn try_spend(&mut self, amount: &token::BaseUnits) -> bool {
+        if amount.amount() == 0 {
+            return true;
+        }
+        let spent = token::BaseUnits::new(self.spent, self.spending_cap.denomination().clone())
+            .checked_add(amount);
+        match spent {
+            Ok(spent) if spent <= self.spending_cap => {
+                self.spent = spent.amount();
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,520 @@
+//! Airdrop module for distributing tokens via Merkle-proof claims.
+//!
+//! Instead of transferring tokens to each recipient, a distributor funds a distribution and
+//! publishes the root of a Merkle tree of `(address, amount)` entitlements. Entitled accounts then
+//! claim their amounts by providing a proof of inclusion in the tree. Once the distribution
+//! expires, entitlements can no longer be claimed, the record of claimed entitlements is pruned
+//! and the distributor can claw back any unclaimed amount.
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    core::common::crypto::hash::Hash,
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{address::Address, token},
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "airdrop";
+
+/// Maximum number of claims of expired distributions pruned in each block.
+const MAX_PRUNED_CLAIMS_PER_BLOCK: usize = 1_000;
+
+/// Errors emitted by the airdrop module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("unknown distribution")]
+    #[sdk_error(code = 2)]
+    UnknownDistribution,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 3)]
+    Forbidden,
+
+    #[error("insufficient balance")]
+    #[sdk_error(code = 4)]
+    InsufficientBalance,
+
+    #[error("invalid proof")]
+    #[sdk_error(code = 5)]
+    InvalidProof,
+
+    #[error("already claimed")]
+    #[sdk_error(code = 6)]
+    AlreadyClaimed,
+
+    #[error("distribution expired")]
+    #[sdk_error(code = 7)]
+    Expired,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the airdrop module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    DistributionCreated {
+        id: u64,
+        distributor: Address,
+        amount: token::BaseUnits,
+    },
+
+    #[sdk_event(code = 2)]
+    Claimed {
+        id: u64,
+        address: Address,
+        amount: token::BaseUnits,
+    },
+
+    #[sdk_event(code = 3)]
+    ClawedBack { id: u64, amount: token::BaseUnits },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_create: u64,
+    pub tx_claim: u64,
+    /// Cost of verifying each proof element of a claim.
+    pub tx_claim_proof_node: u64,
+    pub tx_clawback: u64,
+}
+
+/// Parameters for the airdrop module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Maximum number of elements in a claim proof.
+    #[cbor(optional)]
+    pub max_proof_length: u16,
+}
+
+/// Errors emitted during airdrop parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("maximum proof length should be non-zero")]
+    InvalidMaxProofLength,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        if self.max_proof_length == 0 {
+            return Err(ParameterValidationError::InvalidMaxProofLength);
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the airdrop module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of distribution identifiers to distributions.
+    pub const DISTRIBUTIONS: &[u8] = &[0x01];
+    /// Identifier of the next distribution.
+    pub const NEXT_ID: &[u8] = &[0x02];
+    /// Set of claimed entitlements by distribution identifier and address.
+    pub const CLAIMS: &[u8] = &[0x03];
+    /// Queue of distributions whose claims are yet to be pruned, by expiration and identifier.
+    pub const EXPIRATIONS: &[u8] = &[0x04];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[DISTRIBUTIONS, NEXT_ID, CLAIMS, EXPIRATIONS];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the distribution with the given identifier.
+    fn get_distribution(id: u64) -> Option<types::Distribution>;
+
+    /// Whether the given address has claimed its entitlement from the given distribution.
+    ///
+    /// Claims are pruned once the distribution expires.
+    fn is_claimed(id: u64, address: Address) -> bool;
+}
+
+pub struct Module<Accounts: modules::accounts::API> {
+    _accounts: std::marker::PhantomData<Accounts>,
+}
+
+/// Module's address that holds the funds of all distributions.
+pub static ADDRESS_AIRDROP_POOL: Lazy<Address> =
+    Lazy::new(|| Address::from_module(MODULE_NAME, "pool"));
+
+/// Whether the given proof shows that the leaf is included in the tree with the given root.
+pub fn verify_proof(root: &Hash, leaf: Hash, proof: &[Hash]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| types::node_hash(&node, sibling))
+        == *root
+}
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    fn next_id() -> u64 {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::NEXT_ID).unwrap_or_default()
+        })
+    }
+
+    fn set_next_id(id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            tstore.insert(state::NEXT_ID, id);
+        });
+    }
+
+    fn set_distribution(id: u64, distribution: Option<types::Distribution>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut distributions =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::DISTRIBUTIONS));
+            match distribution {
+                Some(distribution) => distributions.insert(id.to_be_bytes(), distribution),
+                None => distributions.remove(id.to_be_bytes()),
+            }
+        });
+    }
+
+    fn set_claimed(id: u64, address: Address) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut claims =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::CLAIMS));
+            claims.insert([&id.to_be_bytes()[..], address.as_ref()].concat(), true);
+        });
+    }
+
+    /// Enqueue the distribution for pruning of its claims once it expires.
+    fn add_expiration(expiration: u64, id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::EXPIRATIONS));
+            queue.insert(ExpirationEntry::key(expiration, id), ());
+        });
+    }
+
+    fn remove_expiration(entry: &ExpirationEntry) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::EXPIRATIONS));
+            queue.remove(ExpirationEntry::key(entry.expiration, entry.id));
+        });
+    }
+
+    /// Distributions that expired at or before the given timestamp and whose claims are yet to be
+    /// pruned, in expiration order.
+    fn expired_distributions(timestamp: u64) -> Vec<ExpirationEntry> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let queue =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::EXPIRATIONS));
+            queue
+                .iter::<ExpirationEntry, ()>()
+                .map(|(entry, _)| entry)
+                .take_while(|entry| entry.expiration <= timestamp)
+                .collect()
+        })
+    }
+
+    /// Remove up to the given number of claims of the given distribution, returning the number of
+    /// removed claims.
+    fn prune_claims(id: u64, limit: usize) -> usize {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let claims = storage::PrefixStore::new(store, &state::CLAIMS);
+            let mut claims =
+                storage::TypedStore::new(storage::PrefixStore::new(claims, id.to_be_bytes()));
+            let addresses: Vec<Vec<u8>> = claims
+                .iter::<Vec<u8>, bool>()
+                .map(|(address, _)| address)
+                .take(limit)
+                .collect();
+            for address in &addresses {
+                claims.remove(address);
+            }
+            addresses.len()
+        })
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn get_distribution(id: u64) -> Option<types::Distribution> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let distributions =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::DISTRIBUTIONS));
+            distributions.get(id.to_be_bytes())
+        })
+    }
+
+    fn is_claimed(id: u64, address: Address) -> bool {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let claims = storage::TypedStore::new(storage::PrefixStore::new(store, &state::CLAIMS));
+            claims
+                .get::<_, bool>([&id.to_be_bytes()[..], address.as_ref()].concat())
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[sdk_derive(Module)]
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Create a distribution funded by the caller. Returns the distribution identifier.
+    #[handler(call = "airdrop.Create")]
+    fn tx_create<C: TxContext>(ctx: &mut C, body: types::Create) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_create)?;
+
+        if body.amount.amount() == 0 || body.expiration <= ctx.time().timestamp() {
+            return Err(Error::InvalidArgument);
+        }
+
+        let id = Self::next_id();
+        if ctx.is_check_only() {
+            return Ok(id);
+        }
+
+        let distributor = ctx.tx_caller_address();
+        Accounts::transfer(ctx, distributor, *ADDRESS_AIRDROP_POOL, &body.amount)
+            .map_err(|_| Error::InsufficientBalance)?;
+
+        Self::set_next_id(id + 1);
+        Self::set_distribution(
+            id,
+            Some(types::Distribution {
+                distributor,
+                root: body.root,
+                remaining: body.amount.clone(),
+                expiration: body.expiration,
+            }),
+        );
+        Self::add_expiration(body.expiration, id);
+
+        ctx.emit_event(Event::DistributionCreated {
+            id,
+            distributor,
+            amount: body.amount,
+        });
+
+        Ok(id)
+    }
+
+    /// Claim an entitlement from a distribution, paying it to the entitled account.
+    #[handler(call = "airdrop.Claim")]
+    fn tx_claim<C: TxContext>(ctx: &mut C, body: types::Claim) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_claim)?;
+        if body.proof.len() > params.max_proof_length as usize {
+            return Err(Error::InvalidArgument);
+        }
+        <C::Runtime as Runtime>::Core::use_tx_gas(
+            ctx,
+            params
+                .gas_costs
+                .tx_claim_proof_node
+                .saturating_mul(body.proof.len() as u64),
+        )?;
+
+        let mut distribution = Self::get_distribution(body.id).ok_or(Error::UnknownDistribution)?;
+        if ctx.time().timestamp() >= distribution.expiration {
+            return Err(Error::Expired);
+        }
+        if Self::is_claimed(body.id, body.address) {
+            return Err(Error::AlreadyClaimed);
+        }
+        let leaf = types::leaf_hash(&body.address, body.amount);
+        if !verify_proof(&distribution.root, leaf, &body.proof) {
+            return Err(Error::InvalidProof);
+        }
+        // Guard against distributors publishing entitlements exceeding the funded amount.
+        let amount =
+            token::BaseUnits::new(body.amount, distribution.remaining.denomination().clone());
+        let remaining = distribution
+            .remaining
+            .checked_sub(&amount)
+            .map_err(|_| Error::InsufficientBalance)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Accounts::transfer(ctx, *ADDRESS_AIRDROP_POOL, body.address, &amount)
+            .map_err(|_| Error::InsufficientBalance)?;
+
+        distribution.remaining = remaining;
+        Self::set_distribution(body.id, Some(distribution));
+        Self::set_claimed(body.id, body.address);
+
+        ctx.emit_event(Event::Claimed {
+            id: body.id,
+            address: body.address,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Claw back the unclaimed amount of an expired distribution. Only the distributor can claw
+    /// back the amount and the distribution is removed afterwards.
+    #[handler(call = "airdrop.Clawback")]
+    fn tx_clawback<C: TxContext>(ctx: &mut C, body: types::Clawback) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_clawback)?;
+
+        let distribution = Self::get_distribution(body.id).ok_or(Error::UnknownDistribution)?;
+        if ctx.tx_caller_address() != distribution.distributor
+            || ctx.time().timestamp() < distribution.expiration
+        {
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Accounts::transfer(
+            ctx,
+            *ADDRESS_AIRDROP_POOL,
+            distribution.distributor,
+            &distribution.remaining,
+        )
+        .map_err(|_| Error::InsufficientBalance)?;
+        Self::set_distribution(body.id, None);
+
+        ctx.emit_event(Event::ClawedBack {
+            id: body.id,
+            amount: distribution.remaining,
+        });
+
+        Ok(())
+    }
+
+    #[handler(query = "airdrop.Distribution")]
+    fn query_distribution<C: Context>(
+        _ctx: &mut C,
+        args: types::DistributionQuery,
+    ) -> Result<types::Distribution, Error> {
+        Self::get_distribution(args.id).ok_or(Error::UnknownDistribution)
+    }
+
+    /// Whether an address has claimed its entitlement from a distribution.
+    #[handler(query = "airdrop.Claimed")]
+    fn query_claimed<C: Context>(_ctx: &mut C, args: types::ClaimedQuery) -> Result<bool, Error> {
+        Ok(Self::is_claimed(args.id, args.address))
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts> {
+    fn end_block<C: Context>(ctx: &mut C) {
+        // Prune claims of expired distributions as they can no longer be claimed from. Claims
+        // that do not fit into the per-block limit are pruned in the following blocks.
+        let mut budget = MAX_PRUNED_CLAIMS_PER_BLOCK;
+        for entry in Self::expired_distributions(ctx.time().timestamp()) {
+            let pruned = Self::prune_claims(entry.id, budget);
+            if pruned == budget {
+                break;
+            }
+            budget -= pruned;
+
+            Self::remove_expiration(&entry);
+        }
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
+
+/// A struct that exists solely to decode expiration queue keys.
+struct ExpirationEntry {
+    expiration: u64,
+    id: u64,
+}
+
+impl ExpirationEntry {
+    /// Size of the expiration queue keys in bytes.
+    const SIZE: usize = 8 + 8;
+
+    /// Expiration queue key of the given distribution.
+    fn key(expiration: u64, id: u64) -> Vec<u8> {
+        [expiration.to_be_bytes(), id.to_be_bytes()].concat()
+    }
+}
+
+impl TryFrom<&[u8]> for ExpirationEntry {
+    type Error = std::array::TryFromSliceError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let key: [u8; Self::SIZE] = value.try_into()?;
+        let (expiration, id) = key.split_at(8);
+        Ok(ExpirationEntry {
+            expiration: u64::from_be_bytes(expiration.try_into()?),
+            id: u64::from_be_bytes(id.try_into()?),
+        })
+    }
+}
@@ -0,0 +1,290 @@
+//! Tests for the airdrop module.
+use std::collections::BTreeMap;
+
+use crate::{
+    context::{BatchContext, Context, RuntimeTxContext},
+    core::common::crypto::hash::Hash,
+    module::{self, BlockHandler as _},
+    modules::accounts::Module as Accounts,
+    testing::{keys, mock},
+    types::{
+        address::Address,
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+};
+
+use super::{types, verify_proof, Error, Genesis, Parameters, ADDRESS_AIRDROP_POOL, API as _};
+
+type Airdrop = super::Module<Accounts>;
+
+const EXPIRATION: u64 = 1_000;
+
+fn test_params() -> Parameters {
+    Parameters {
+        gas_costs: Default::default(),
+        max_proof_length: 4,
+    }
+}
+
+fn init<C: Context>(ctx: &mut C) {
+    mock::init_balances(
+        ctx,
+        &[
+            (keys::alice::address(), 1_000),
+            (keys::bob::address(), 1_000),
+        ],
+    );
+    Airdrop::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+}
+
+/// Merkle tree of the test entitlements together with the proofs of each entitlement.
+struct Tree {
+    root: Hash,
+    proofs: BTreeMap<Address, (u128, Vec<Hash>)>,
+}
+
+impl Tree {
+    fn new() -> Self {
+        let alice = types::leaf_hash(&keys::alice::address(), 100);
+        let bob = types::leaf_hash(&keys::bob::address(), 200);
+        let charlie = types::leaf_hash(&keys::charlie::address(), 300);
+        let inner = types::node_hash(&alice, &bob);
+        Self {
+            root: types::node_hash(&inner, &charlie),
+            proofs: BTreeMap::from([
+                (keys::alice::address(), (100, vec![bob, charlie])),
+                (keys::bob::address(), (200, vec![alice, charlie])),
+                (keys::charlie::address(), (300, vec![inner])),
+            ]),
+        }
+    }
+
+    fn claim(&self, id: u64, address: Address) -> types::Claim {
+        let (amount, proof) = self.proofs[&address].clone();
+        types::Claim {
+            id,
+            address,
+            amount,
+            proof,
+        }
+    }
+}
+
+/// Run the given function in the context of a transaction signed by alice.
+fn with_tx<R>(
+    mock: &mut mock::Mock,
+    f: impl FnOnce(&mut RuntimeTxContext<'_, '_, mock::EmptyRuntime>) -> R,
+) -> R {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![keys::alice::signer_info()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| f(&mut tx_ctx))
+}
+
+fn create(mock: &mut mock::Mock, root: Hash, amount: u128) -> Result<u64, Error> {
+    with_tx(mock, |ctx| {
+        Airdrop::tx_create(
+            ctx,
+            types::Create {
+                root,
+                amount: BaseUnits::new(amount, Denomination::NATIVE),
+                expiration: EXPIRATION,
+            },
+        )
+    })
+}
+
+fn claim(mock: &mut mock::Mock, body: types::Claim) -> Result<(), Error> {
+    with_tx(mock, |ctx| Airdrop::tx_claim(ctx, body))
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    test_params()
+        .validate_basic()
+        .expect("test parameters should be valid");
+
+    let params = Parameters {
+        max_proof_length: 0,
+        ..test_params()
+    };
+    assert!(
+        params.validate_basic().is_err(),
+        "maximum proof length should be non-zero"
+    );
+}
+
+#[test]
+fn test_verify_proof() {
+    let tree = Tree::new();
+    for (address, (amount, proof)) in &tree.proofs {
+        let leaf = types::leaf_hash(address, *amount);
+        assert!(verify_proof(&tree.root, leaf, proof));
+        assert!(!verify_proof(&tree.root, leaf, &proof[1..]));
+
+        let leaf = types::leaf_hash(address, amount + 1);
+        assert!(!verify_proof(&tree.root, leaf, proof));
+    }
+}
+
+#[test]
+fn test_claim() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let tree = Tree::new();
+    assert!(matches!(
+        create(&mut mock, tree.root, 0),
+        Err(Error::InvalidArgument)
+    ));
+    mock.runtime_header.timestamp = EXPIRATION;
+    assert!(matches!(
+        create(&mut mock, tree.root, 600),
+        Err(Error::InvalidArgument)
+    ));
+    mock.runtime_header.timestamp = 0;
+    let id = create(&mut mock, tree.root, 600).expect("create should succeed");
+    assert_eq!(mock::balance(keys::alice::address()), 400);
+    assert_eq!(mock::balance(*ADDRESS_AIRDROP_POOL), 600);
+
+    let mut body = tree.claim(id, keys::bob::address());
+    body.amount = 300;
+    assert!(matches!(claim(&mut mock, body), Err(Error::InvalidProof)));
+    let mut body = tree.claim(id, keys::bob::address());
+    body.proof = vec![Hash::empty_hash(); 5];
+    assert!(matches!(
+        claim(&mut mock, body),
+        Err(Error::InvalidArgument)
+    ));
+
+    // Claims can be submitted by anyone and are paid to the entitled account.
+    claim(&mut mock, tree.claim(id, keys::bob::address())).expect("claim should succeed");
+    assert_eq!(mock::balance(keys::bob::address()), 1_200);
+    assert!(Airdrop::is_claimed(id, keys::bob::address()));
+    assert!(!Airdrop::is_claimed(id, keys::charlie::address()));
+    assert!(matches!(
+        claim(&mut mock, tree.claim(id, keys::bob::address())),
+        Err(Error::AlreadyClaimed)
+    ));
+
+    claim(&mut mock, tree.claim(id, keys::charlie::address())).expect("claim should succeed");
+    assert_eq!(mock::balance(keys::charlie::address()), 300);
+    assert_eq!(
+        Airdrop::get_distribution(id).unwrap().remaining.amount(),
+        100
+    );
+}
+
+#[test]
+fn test_claim_exceeding_funds() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let tree = Tree::new();
+    let id = create(&mut mock, tree.root, 250).expect("create should succeed");
+    claim(&mut mock, tree.claim(id, keys::bob::address())).expect("claim should succeed");
+    assert!(matches!(
+        claim(&mut mock, tree.claim(id, keys::alice::address())),
+        Err(Error::InsufficientBalance)
+    ));
+}
+
+#[test]
+fn test_clawback() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let tree = Tree::new();
+    let id = create(&mut mock, tree.root, 600).expect("create should succeed");
+    claim(&mut mock, tree.claim(id, keys::bob::address())).expect("claim should succeed");
+
+    let clawback = |mock: &mut mock::Mock, who: &transaction::SignerInfo| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Airdrop::tx_clawback(&mut tx_ctx, types::Clawback { id })
+        })
+    };
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    // Unclaimed amounts can only be clawed back by the distributor after expiration.
+    assert!(matches!(clawback(&mut mock, &alice), Err(Error::Forbidden)));
+    mock.runtime_header.timestamp = EXPIRATION;
+    assert!(matches!(clawback(&mut mock, &bob), Err(Error::Forbidden)));
+    assert!(matches!(
+        claim(&mut mock, tree.claim(id, keys::charlie::address())),
+        Err(Error::Expired)
+    ));
+
+    clawback(&mut mock, &alice).expect("clawback should succeed");
+    assert_eq!(mock::balance(keys::alice::address()), 800);
+    assert_eq!(mock::balance(*ADDRESS_AIRDROP_POOL), 0);
+    assert_eq!(Airdrop::get_distribution(id), None);
+}
+
+#[test]
+fn test_prune_claims() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let tree = Tree::new();
+    let id = create(&mut mock, tree.root, 600).expect("create should succeed");
+    for address in [keys::alice::address(), keys::bob::address()] {
+        claim(&mut mock, tree.claim(id, address)).expect("claim should succeed");
+    }
+
+    // Claims are retained until the distribution expires.
+    mock.runtime_header.timestamp = EXPIRATION - 1;
+    let mut ctx = mock.create_ctx();
+    Airdrop::end_block(&mut ctx);
+    assert!(Airdrop::is_claimed(id, keys::alice::address()));
+    assert!(Airdrop::is_claimed(id, keys::bob::address()));
+
+    mock.runtime_header.timestamp = EXPIRATION;
+    let mut ctx = mock.create_ctx();
+    Airdrop::end_block(&mut ctx);
+    assert!(!Airdrop::is_claimed(id, keys::alice::address()));
+    assert!(!Airdrop::is_claimed(id, keys::bob::address()));
+    assert!(
+        Airdrop::get_distribution(id).is_some(),
+        "distribution should remain until clawed back"
+    );
+
+    let genesis = mock::assert_export_round_trip::<Airdrop>();
+    assert_eq!(
+        genesis.state.len(),
+        2,
+        "only the distribution and next identifier should remain"
+    );
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let tree = Tree::new();
+    let id = create(&mut mock, tree.root, 600).expect("create should succeed");
+    claim(&mut mock, tree.claim(id, keys::bob::address())).expect("claim should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Airdrop>();
+    assert_eq!(genesis.parameters.max_proof_length, 4);
+    assert_eq!(
+        genesis.state.len(),
+        4,
+        "distribution, next identifier, claim and expiration should be exported"
+    );
+}
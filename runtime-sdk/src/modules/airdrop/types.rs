@@ -0,0 +1,90 @@
+//! Airdrop module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::{
+    core::common::crypto::hash::Hash,
+    types::{address::Address, token::BaseUnits},
+};
+
+/// Domain separation context for Merkle tree leaves.
+const LEAF_CONTEXT: &[u8] = b"oasis-sdk/airdrop: leaf";
+/// Domain separation context for Merkle tree inner nodes.
+const NODE_CONTEXT: &[u8] = b"oasis-sdk/airdrop: node";
+
+/// Hash of the Merkle tree leaf entitling the given address to the given amount.
+pub fn leaf_hash(address: &Address, amount: u128) -> Hash {
+    Hash::digest_bytes_list(&[LEAF_CONTEXT, address.as_ref(), &amount.to_be_bytes()])
+}
+
+/// Hash of the Merkle tree inner node with the given children. Children are ordered before
+/// hashing so that proofs do not need to specify the position of each sibling.
+pub fn node_hash(a: &Hash, b: &Hash) -> Hash {
+    let (lo, hi) = if a.as_ref() <= b.as_ref() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    Hash::digest_bytes_list(&[NODE_CONTEXT, lo.as_ref(), hi.as_ref()])
+}
+
+/// An airdrop distribution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Distribution {
+    /// Address of the account that funded the distribution.
+    pub distributor: Address,
+    /// Root of the Merkle tree of entitlements.
+    pub root: Hash,
+    /// Amount remaining to be claimed.
+    pub remaining: BaseUnits,
+    /// Timestamp after which entitlements can no longer be claimed and the distributor can claw
+    /// back the remaining amount.
+    pub expiration: u64,
+}
+
+/// Create distribution call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Create {
+    /// Root of the Merkle tree of entitlements.
+    pub root: Hash,
+    /// Total amount to distribute.
+    pub amount: BaseUnits,
+    /// Timestamp after which entitlements can no longer be claimed.
+    pub expiration: u64,
+}
+
+/// Claim entitlement call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Claim {
+    /// Distribution identifier.
+    pub id: u64,
+    /// Address of the entitled account. Claimed amounts are always paid to it, so anyone can
+    /// submit the claim on its behalf.
+    pub address: Address,
+    /// Entitled amount.
+    pub amount: u128,
+    /// Sibling hashes on the path from the entitlement leaf to the root.
+    pub proof: Vec<Hash>,
+}
+
+/// Claw back unclaimed amount call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Clawback {
+    /// Distribution identifier.
+    pub id: u64,
+}
+
+/// Distribution query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct DistributionQuery {
+    /// Distribution identifier.
+    pub id: u64,
+}
+
+/// Claimed query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct ClaimedQuery {
+    /// Distribution identifier.
+    pub id: u64,
+    /// Address of the entitled account.
+    pub address: Address,
+}
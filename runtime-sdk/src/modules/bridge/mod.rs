@@ -0,0 +1,644 @@
+//! Bridge module for moving assets between the runtime and an external chain.
+//!
+//! Assets native to the runtime are bridged out by locking them in the bridge pool and are bridged
+//! back in by releasing them from the pool. External assets are represented by wrapped tokens that
+//! are minted when bridged in and burned when bridged out. Incoming transfers are executed once a
+//! threshold of the permissioned relayers has witnessed them, subject to per-asset daily rate
+//! limits. Relayers that disagree on the details of an incoming transfer witness different
+//! variants of it and the first variant to reach the threshold is executed. Relayers learn about
+//! outgoing transfers from the emitted events.
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    core::common::crypto::hash::Hash,
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _, safety::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{
+        address::Address,
+        token::{self, Denomination},
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "bridge";
+
+/// Maximum size of an external chain address (in bytes).
+const MAX_TARGET_SIZE: usize = 64;
+
+/// Number of seconds in a rate limiting period.
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Errors emitted by the bridge module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("unknown asset")]
+    #[sdk_error(code = 2)]
+    UnknownAsset,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 3)]
+    Forbidden,
+
+    #[error("bridge is paused")]
+    #[sdk_error(code = 4)]
+    Paused,
+
+    #[error("insufficient balance")]
+    #[sdk_error(code = 5)]
+    InsufficientBalance,
+
+    #[error("rate limit exceeded")]
+    #[sdk_error(code = 6)]
+    RateLimitExceeded,
+
+    #[error("transfer already witnessed")]
+    #[sdk_error(code = 7)]
+    AlreadyWitnessed,
+
+    #[error("transfer already executed")]
+    #[sdk_error(code = 8)]
+    AlreadyExecuted,
+
+    #[error("witness does not match the transfer")]
+    #[sdk_error(code = 9)]
+    WitnessMismatch,
+
+    #[error("unknown transfer")]
+    #[sdk_error(code = 10)]
+    UnknownTransfer,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the bridge module.
+///
+/// Relayers watch for `Locked` events to carry outgoing transfers over to the external chain.
+/// Outgoing transfers are numbered sequentially so that relayers can detect any missed events.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    Locked {
+        id: u64,
+        owner: Address,
+        target: Vec<u8>,
+        amount: token::BaseUnits,
+    },
+
+    #[sdk_event(code = 2)]
+    Witnessed { id: u64, relayer: Address },
+
+    #[sdk_event(code = 3)]
+    Released {
+        id: u64,
+        owner: Address,
+        amount: token::BaseUnits,
+    },
+
+    #[sdk_event(code = 4)]
+    PauseChanged { paused: bool },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_lock: u64,
+    pub tx_witness: u64,
+    pub tx_set_paused: u64,
+}
+
+/// Parameters for the bridge module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Relayers allowed to witness incoming transfers.
+    #[cbor(optional)]
+    pub relayers: Vec<Address>,
+    /// Number of relayers that need to witness an incoming transfer for it to be executed.
+    #[cbor(optional)]
+    pub threshold: u32,
+    /// Bridged assets.
+    #[cbor(optional)]
+    pub assets: Vec<types::AssetConfig>,
+    /// Address allowed to pause and unpause the bridge.
+    #[cbor(optional)]
+    pub guardian: Option<Address>,
+}
+
+impl Parameters {
+    /// Configuration of the asset with the given denomination.
+    pub fn asset(&self, denomination: &Denomination) -> Option<&types::AssetConfig> {
+        self.assets
+            .iter()
+            .find(|asset| &asset.denomination == denomination)
+    }
+}
+
+/// Errors emitted during bridge parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("invalid relayer threshold")]
+    InvalidThreshold,
+
+    #[error("duplicate relayer")]
+    DuplicateRelayer,
+
+    #[error("duplicate asset")]
+    DuplicateAsset,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        if !self.relayers.is_empty()
+            && (self.threshold == 0 || self.threshold as usize > self.relayers.len())
+        {
+            return Err(ParameterValidationError::InvalidThreshold);
+        }
+        for (i, relayer) in self.relayers.iter().enumerate() {
+            if self.relayers[..i].contains(relayer) {
+                return Err(ParameterValidationError::DuplicateRelayer);
+            }
+        }
+        for (i, asset) in self.assets.iter().enumerate() {
+            if self.assets[..i]
+                .iter()
+                .any(|other| other.denomination == asset.denomination)
+            {
+                return Err(ParameterValidationError::DuplicateAsset);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the bridge module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Whether the bridge is paused.
+    pub const PAUSED: &[u8] = &[0x01];
+    /// Sequence number of the next outgoing transfer.
+    pub const NEXT_OUTGOING_ID: &[u8] = &[0x02];
+    /// Map of incoming transfer sequence numbers to executed incoming transfers.
+    pub const INCOMING: &[u8] = &[0x03];
+    /// Map of denominations to amounts bridged during the current day.
+    pub const RATE_LIMITS: &[u8] = &[0x04];
+    /// Map of incoming transfer sequence numbers and witness hashes to pending incoming transfers.
+    pub const PENDING: &[u8] = &[0x05];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[PAUSED, NEXT_OUTGOING_ID, INCOMING, RATE_LIMITS, PENDING];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Whether the bridge is paused.
+    fn is_paused() -> bool;
+
+    /// Fetch the executed incoming transfer with the given sequence number.
+    fn get_incoming(id: u64) -> Option<types::Incoming>;
+
+    /// Fetch all witnessed variants of the pending incoming transfer with the given sequence
+    /// number.
+    fn get_pending_incoming(id: u64) -> Vec<types::Incoming>;
+}
+
+pub struct Module<Accounts: modules::accounts::API> {
+    _accounts: std::marker::PhantomData<Accounts>,
+}
+
+/// Module's address that holds the locked native assets.
+pub static ADDRESS_BRIDGE_POOL: Lazy<Address> =
+    Lazy::new(|| Address::from_module(MODULE_NAME, "pool"));
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    fn set_paused(paused: bool) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            tstore.insert(state::PAUSED, paused);
+        });
+    }
+
+    fn next_outgoing_id() -> u64 {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::NEXT_OUTGOING_ID).unwrap_or_default()
+        })
+    }
+
+    fn set_next_outgoing_id(id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            tstore.insert(state::NEXT_OUTGOING_ID, id);
+        });
+    }
+
+    fn set_incoming(id: u64, incoming: types::Incoming) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut transfers =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::INCOMING));
+            transfers.insert(id.to_be_bytes(), incoming);
+        });
+    }
+
+    /// Key of the pending incoming transfer variant attested to by the given witness.
+    fn pending_key(body: &types::Witness) -> Hash {
+        Hash::digest_bytes(&cbor::to_vec(body.clone()))
+    }
+
+    fn get_pending_variant(id: u64, key: &Hash) -> Option<types::Incoming> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let pending = storage::PrefixStore::new(store, &state::PENDING);
+            let variants =
+                storage::TypedStore::new(storage::PrefixStore::new(pending, id.to_be_bytes()));
+            variants.get(key)
+        })
+    }
+
+    fn set_pending_variant(id: u64, key: &Hash, incoming: types::Incoming) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let pending = storage::PrefixStore::new(store, &state::PENDING);
+            let mut variants =
+                storage::TypedStore::new(storage::PrefixStore::new(pending, id.to_be_bytes()));
+            variants.insert(key, incoming);
+        });
+    }
+
+    /// Remove all pending variants of the incoming transfer with the given sequence number.
+    fn remove_pending_incoming(id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let pending = storage::PrefixStore::new(store, &state::PENDING);
+            let mut variants =
+                storage::TypedStore::new(storage::PrefixStore::new(pending, id.to_be_bytes()));
+            let keys: Vec<Vec<u8>> = variants
+                .iter::<Vec<u8>, types::Incoming>()
+                .map(|(key, _)| key)
+                .collect();
+            for key in keys {
+                variants.remove(key);
+            }
+        });
+    }
+
+    /// Amounts of the given asset bridged during the given day.
+    fn get_rate_limit_usage(denomination: &Denomination, day: u64) -> types::RateLimitUsage {
+        let usage: types::RateLimitUsage = CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let usages =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::RATE_LIMITS));
+            usages.get(denomination).unwrap_or_default()
+        });
+        if usage.day != day {
+            return types::RateLimitUsage {
+                day,
+                ..Default::default()
+            };
+        }
+        usage
+    }
+
+    fn set_rate_limit_usage(denomination: &Denomination, usage: types::RateLimitUsage) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut usages =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::RATE_LIMITS));
+            usages.insert(denomination, usage);
+        });
+    }
+
+    /// Account for an amount of the given asset being bridged in the given direction, failing in
+    /// case this would exceed the daily limit of the asset.
+    fn use_rate_limit<C: Context>(
+        ctx: &C,
+        asset: &types::AssetConfig,
+        amount: u128,
+        incoming: bool,
+    ) -> Result<types::RateLimitUsage, Error> {
+        let day = ctx.time().timestamp() / SECONDS_PER_DAY;
+        let mut usage = Self::get_rate_limit_usage(&asset.denomination, day);
+        let used = if incoming {
+            &mut usage.incoming
+        } else {
+            &mut usage.outgoing
+        };
+        *used = used.checked_add(amount).ok_or(Error::RateLimitExceeded)?;
+        if asset.daily_limit > 0 && *used > asset.daily_limit {
+            return Err(Error::RateLimitExceeded);
+        }
+        Ok(usage)
+    }
+
+    fn ensure_not_paused() -> Result<(), Error> {
+        if Self::is_paused() {
+            return Err(Error::Paused);
+        }
+        Ok(())
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn is_paused() -> bool {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::PAUSED).unwrap_or_default()
+        })
+    }
+
+    fn get_incoming(id: u64) -> Option<types::Incoming> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let transfers =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::INCOMING));
+            transfers.get(id.to_be_bytes())
+        })
+    }
+
+    fn get_pending_incoming(id: u64) -> Vec<types::Incoming> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let pending = storage::PrefixStore::new(store, &state::PENDING);
+            let variants =
+                storage::TypedStore::new(storage::PrefixStore::new(pending, id.to_be_bytes()));
+            variants
+                .iter::<Vec<u8>, types::Incoming>()
+                .map(|(_, incoming)| incoming)
+                .collect()
+        })
+    }
+}
+
+#[sdk_derive(Module)]
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Bridge an amount out of the runtime to the given address on the external chain. Returns
+    /// the sequence number of the outgoing transfer.
+    #[handler(call = "bridge.Lock")]
+    fn tx_lock<C: TxContext>(ctx: &mut C, body: types::Lock) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_lock)?;
+
+        Self::ensure_not_paused()?;
+        let asset = params
+            .asset(body.amount.denomination())
+            .ok_or(Error::UnknownAsset)?;
+        if body.amount.amount() == 0
+            || body.target.is_empty()
+            || body.target.len() > MAX_TARGET_SIZE
+        {
+            return Err(Error::InvalidArgument);
+        }
+        let usage = Self::use_rate_limit(ctx, asset, body.amount.amount(), false)?;
+        modules::safety::Module::record_outflow(ctx, &body.amount).map_err(|_| Error::Paused)?;
+
+        let id = Self::next_outgoing_id();
+        if ctx.is_check_only() {
+            return Ok(id);
+        }
+
+        let owner = ctx.tx_caller_address();
+        match asset.kind {
+            types::AssetKind::Native => {
+                Accounts::transfer(ctx, owner, *ADDRESS_BRIDGE_POOL, &body.amount)
+            }
+            types::AssetKind::Wrapped => Accounts::burn(ctx, owner, &body.amount),
+        }
+        .map_err(|_| Error::InsufficientBalance)?;
+
+        Self::set_rate_limit_usage(&asset.denomination, usage);
+        Self::set_next_outgoing_id(id + 1);
+
+        ctx.emit_event(Event::Locked {
+            id,
+            owner,
+            target: body.target,
+            amount: body.amount,
+        });
+
+        Ok(id)
+    }
+
+    /// Witness an incoming transfer from the external chain. Only relayers can witness transfers
+    /// and each relayer can witness a single variant of a transfer. The first variant witnessed by
+    /// the threshold of relayers is executed.
+    #[handler(call = "bridge.Witness")]
+    fn tx_witness<C: TxContext>(ctx: &mut C, body: types::Witness) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_witness)?;
+
+        Self::ensure_not_paused()?;
+        let relayer = ctx.tx_caller_address();
+        if !params.relayers.contains(&relayer) {
+            return Err(Error::Forbidden);
+        }
+        let asset = params
+            .asset(body.amount.denomination())
+            .ok_or(Error::UnknownAsset)?;
+        if body.amount.amount() == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        if Self::get_incoming(body.id).is_some() {
+            return Err(Error::AlreadyExecuted);
+        }
+        for variant in Self::get_pending_incoming(body.id) {
+            if !variant.witnesses.contains(&relayer) {
+                continue;
+            }
+            if variant.owner != body.owner || variant.amount != body.amount {
+                return Err(Error::WitnessMismatch);
+            }
+            return Err(Error::AlreadyWitnessed);
+        }
+
+        let key = Self::pending_key(&body);
+        let mut incoming =
+            Self::get_pending_variant(body.id, &key).unwrap_or_else(|| types::Incoming {
+                owner: body.owner,
+                amount: body.amount.clone(),
+                witnesses: vec![],
+                executed: false,
+            });
+        incoming.witnesses.push(relayer);
+
+        let execute = incoming.witnesses.len() >= params.threshold as usize;
+        let usage = if execute {
+            Some(Self::use_rate_limit(
+                ctx,
+                asset,
+                body.amount.amount(),
+                true,
+            )?)
+        } else {
+            None
+        };
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        ctx.emit_event(Event::Witnessed {
+            id: body.id,
+            relayer,
+        });
+
+        if let Some(usage) = usage {
+            match asset.kind {
+                types::AssetKind::Native => {
+                    Accounts::transfer(ctx, *ADDRESS_BRIDGE_POOL, body.owner, &body.amount)
+                }
+                types::AssetKind::Wrapped => Accounts::mint(ctx, body.owner, &body.amount),
+            }
+            .map_err(|_| Error::InsufficientBalance)?;
+
+            Self::set_rate_limit_usage(&asset.denomination, usage);
+            incoming.executed = true;
+            Self::remove_pending_incoming(body.id);
+            Self::set_incoming(body.id, incoming);
+
+            ctx.emit_event(Event::Released {
+                id: body.id,
+                owner: body.owner,
+                amount: body.amount,
+            });
+        } else {
+            Self::set_pending_variant(body.id, &key, incoming);
+        }
+
+        Ok(())
+    }
+
+    /// Pause or unpause the bridge. While paused, no transfers can be made in either direction.
+    #[handler(call = "bridge.SetPaused")]
+    fn tx_set_paused<C: TxContext>(ctx: &mut C, body: types::SetPaused) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_set_paused)?;
+
+        if params.guardian != Some(ctx.tx_caller_address()) {
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::set_paused(body.paused);
+
+        ctx.emit_event(Event::PauseChanged {
+            paused: body.paused,
+        });
+
+        Ok(())
+    }
+
+    #[handler(query = "bridge.Paused")]
+    fn query_paused<C: Context>(_ctx: &mut C, _args: ()) -> Result<bool, Error> {
+        Ok(Self::is_paused())
+    }
+
+    /// Sequence number of the next outgoing transfer.
+    #[handler(query = "bridge.NextOutgoingId")]
+    fn query_next_outgoing_id<C: Context>(_ctx: &mut C, _args: ()) -> Result<u64, Error> {
+        Ok(Self::next_outgoing_id())
+    }
+
+    #[handler(query = "bridge.Incoming")]
+    fn query_incoming<C: Context>(
+        _ctx: &mut C,
+        args: types::IncomingQuery,
+    ) -> Result<types::Incoming, Error> {
+        Self::get_incoming(args.id).ok_or(Error::UnknownTransfer)
+    }
+
+    /// Witnessed variants of a pending incoming transfer.
+    #[handler(query = "bridge.PendingIncoming")]
+    fn query_pending_incoming<C: Context>(
+        _ctx: &mut C,
+        args: types::IncomingQuery,
+    ) -> Result<Vec<types::Incoming>, Error> {
+        Ok(Self::get_pending_incoming(args.id))
+    }
+
+    /// Amounts of an asset bridged during the current day.
+    #[handler(query = "bridge.RateLimitUsage")]
+    fn query_rate_limit_usage<C: Context>(
+        ctx: &mut C,
+        args: types::RateLimitQuery,
+    ) -> Result<types::RateLimitUsage, Error> {
+        let params = Self::params();
+        params
+            .asset(&args.denomination)
+            .ok_or(Error::UnknownAsset)?;
+        let day = ctx.time().timestamp() / SECONDS_PER_DAY;
+        Ok(Self::get_rate_limit_usage(&args.denomination, day))
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Tests for the bridge module.
+use crate::{
+    context::{BatchContext, Context},
+    module,
+    modules::accounts::{Module as Accounts, API as _},
+    testing::{keys, mock},
+    types::{
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+};
+
+use super::{types, Error, Genesis, Parameters, ADDRESS_BRIDGE_POOL, API as _};
+
+type Bridge = super::Module<Accounts>;
+
+fn wrapped() -> Denomination {
+    "WBTC".parse().unwrap()
+}
+
+fn test_params() -> Parameters {
+    Parameters {
+        gas_costs: Default::default(),
+        relayers: vec![
+            keys::alice::address(),
+            keys::bob::address(),
+            keys::charlie::address(),
+        ],
+        threshold: 2,
+        assets: vec![
+            types::AssetConfig {
+                denomination: Denomination::NATIVE,
+                kind: types::AssetKind::Native,
+                daily_limit: 1_000,
+            },
+            types::AssetConfig {
+                denomination: wrapped(),
+                kind: types::AssetKind::Wrapped,
+                daily_limit: 0,
+            },
+        ],
+        guardian: Some(keys::erin::address()),
+    }
+}
+
+fn init<C: Context>(ctx: &mut C) {
+    mock::init_balances(ctx, &[(keys::alice::address(), 10_000)]);
+    Bridge::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+}
+
+fn lock(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    body: types::Lock,
+) -> Result<u64, Error> {
+    let mut ctx = mock.create_ctx();
+    let tx = mock::transaction_from(signer, "bridge.Lock");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Bridge::tx_lock(&mut tx_ctx, body)
+    })
+}
+
+fn witness(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    body: types::Witness,
+) -> Result<(), Error> {
+    let mut ctx = mock.create_ctx();
+    let tx = mock::transaction_from(signer, "bridge.Witness");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Bridge::tx_witness(&mut tx_ctx, body)
+    })
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    test_params()
+        .validate_basic()
+        .expect("test parameters should be valid");
+
+    for (modify, check) in [
+        (
+            (|p: &mut Parameters| p.threshold = 0) as fn(&mut Parameters),
+            "threshold should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| p.threshold = 4,
+            "threshold should not exceed the number of relayers",
+        ),
+        (
+            |p: &mut Parameters| p.relayers.push(keys::alice::address()),
+            "relayers should be unique",
+        ),
+        (
+            |p: &mut Parameters| p.assets.push(p.assets[0].clone()),
+            "assets should be unique",
+        ),
+    ] {
+        let mut params = test_params();
+        modify(&mut params);
+        assert!(params.validate_basic().is_err(), "{check}");
+    }
+}
+
+#[test]
+fn test_lock() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let native = |amount| types::Lock {
+        target: vec![0xab; 20],
+        amount: BaseUnits::new(amount, Denomination::NATIVE),
+    };
+
+    let result = lock(
+        &mut mock,
+        &alice,
+        types::Lock {
+            amount: BaseUnits::new(100, "UNKNOWN".parse().unwrap()),
+            ..native(100)
+        },
+    );
+    assert!(matches!(result, Err(Error::UnknownAsset)));
+    let result = lock(
+        &mut mock,
+        &alice,
+        types::Lock {
+            target: vec![],
+            ..native(100)
+        },
+    );
+    assert!(matches!(result, Err(Error::InvalidArgument)));
+
+    // Native assets are locked in the bridge pool.
+    let mut ctx = mock.create_ctx();
+    let tx = mock::transaction_from(&alice, "bridge.Lock");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let id = Bridge::tx_lock(&mut tx_ctx, native(600)).expect("lock should succeed");
+        assert_eq!(id, 0);
+
+        let state = tx_ctx.commit();
+        let tags = state.events.into_tags();
+        // bridge.Locked (code = 1) event.
+        assert!(
+            tags.iter().any(|tag| tag.key == b"bridge\x00\x00\x00\x01"),
+            "locked event should be emitted"
+        );
+    });
+    assert_eq!(
+        Accounts::get_balance(*ADDRESS_BRIDGE_POOL, Denomination::NATIVE).unwrap(),
+        600
+    );
+    assert_eq!(
+        Accounts::get_balance(keys::alice::address(), Denomination::NATIVE).unwrap(),
+        9_400
+    );
+
+    // The daily limit applies to all outgoing transfers of the asset.
+    let result = lock(&mut mock, &alice, native(500));
+    assert!(matches!(result, Err(Error::RateLimitExceeded)));
+
+    mock.runtime_header.timestamp = 86_400;
+    let id = lock(&mut mock, &alice, native(500)).expect("lock should succeed on the next day");
+    assert_eq!(id, 1, "outgoing transfers should be numbered sequentially");
+
+    // Bridging out more than the balance fails.
+    let result = lock(
+        &mut mock,
+        &alice,
+        types::Lock {
+            amount: BaseUnits::new(1, wrapped()),
+            ..native(0)
+        },
+    );
+    assert!(matches!(result, Err(Error::InsufficientBalance)));
+}
+
+#[test]
+fn test_witness() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let charlie = keys::charlie::signer_info();
+    let dave = keys::dave::signer_info();
+
+    // Fund the pool by locking some native assets.
+    lock(
+        &mut mock,
+        &alice,
+        types::Lock {
+            target: vec![0xab; 20],
+            amount: BaseUnits::new(500, Denomination::NATIVE),
+        },
+    )
+    .expect("lock should succeed");
+
+    let transfer = |id, amount, denomination| types::Witness {
+        id,
+        owner: keys::dave::address(),
+        amount: BaseUnits::new(amount, denomination),
+    };
+
+    let result = witness(&mut mock, &dave, transfer(7, 100, Denomination::NATIVE));
+    assert!(matches!(result, Err(Error::Forbidden)));
+
+    witness(&mut mock, &bob, transfer(7, 100, Denomination::NATIVE))
+        .expect("witness should succeed");
+    let result = witness(&mut mock, &bob, transfer(7, 100, Denomination::NATIVE));
+    assert!(matches!(result, Err(Error::AlreadyWitnessed)));
+    let result = witness(&mut mock, &bob, transfer(7, 101, Denomination::NATIVE));
+    assert!(matches!(result, Err(Error::WitnessMismatch)));
+
+    // A faulty relayer witnessing different details does not block the transfer.
+    witness(&mut mock, &charlie, transfer(7, 101, Denomination::NATIVE))
+        .expect("witness of another variant should succeed");
+    assert!(Bridge::get_incoming(7).is_none());
+    let pending = Bridge::get_pending_incoming(7);
+    assert_eq!(pending.len(), 2, "both variants should be pending");
+    assert!(pending.iter().all(|incoming| !incoming.executed));
+    assert_eq!(
+        Accounts::get_balance(keys::dave::address(), Denomination::NATIVE).unwrap(),
+        0,
+        "transfers should not be executed before the threshold is reached"
+    );
+
+    // Once the threshold is reached, native assets are released from the pool.
+    witness(&mut mock, &alice, transfer(7, 100, Denomination::NATIVE))
+        .expect("witness should succeed");
+    let incoming = Bridge::get_incoming(7).expect("transfer should be executed");
+    assert!(incoming.executed);
+    assert_eq!(incoming.amount, BaseUnits::new(100, Denomination::NATIVE));
+    assert_eq!(
+        incoming.witnesses,
+        vec![keys::bob::address(), keys::alice::address()]
+    );
+    assert!(
+        Bridge::get_pending_incoming(7).is_empty(),
+        "pending variants should be removed once executed"
+    );
+    assert_eq!(
+        Accounts::get_balance(keys::dave::address(), Denomination::NATIVE).unwrap(),
+        100
+    );
+    assert_eq!(
+        Accounts::get_balance(*ADDRESS_BRIDGE_POOL, Denomination::NATIVE).unwrap(),
+        400
+    );
+    let result = witness(&mut mock, &charlie, transfer(7, 100, Denomination::NATIVE));
+    assert!(matches!(result, Err(Error::AlreadyExecuted)));
+
+    // Wrapped assets are minted.
+    for relayer in [&alice, &charlie] {
+        witness(&mut mock, relayer, transfer(8, 50, wrapped())).expect("witness should succeed");
+    }
+    assert_eq!(
+        Accounts::get_balance(keys::dave::address(), wrapped()).unwrap(),
+        50
+    );
+
+    // Incoming transfers are rate limited as well.
+    witness(&mut mock, &alice, transfer(9, 950, Denomination::NATIVE))
+        .expect("witness should succeed");
+    let result = witness(&mut mock, &bob, transfer(9, 950, Denomination::NATIVE));
+    assert!(matches!(result, Err(Error::RateLimitExceeded)));
+
+    // Wrapped assets are burned when bridged out.
+    lock(
+        &mut mock,
+        &dave,
+        types::Lock {
+            target: vec![0xab; 20],
+            amount: BaseUnits::new(20, wrapped()),
+        },
+    )
+    .expect("lock should succeed");
+    assert_eq!(
+        Accounts::get_balance(keys::dave::address(), wrapped()).unwrap(),
+        30
+    );
+}
+
+#[test]
+fn test_pause() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let set_paused = |mock: &mut mock::Mock, who: &transaction::SignerInfo, paused| {
+        let mut ctx = mock.create_ctx();
+        let tx = mock::transaction_from(who, "bridge.SetPaused");
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Bridge::tx_set_paused(&mut tx_ctx, types::SetPaused { paused })
+        })
+    };
+    let alice = keys::alice::signer_info();
+    let erin = keys::erin::signer_info();
+
+    let result = set_paused(&mut mock, &alice, true);
+    assert!(matches!(result, Err(Error::Forbidden)));
+    set_paused(&mut mock, &erin, true).expect("guardian should be able to pause");
+    assert!(Bridge::is_paused());
+
+    let body = || types::Lock {
+        target: vec![0xab; 20],
+        amount: BaseUnits::new(100, Denomination::NATIVE),
+    };
+    let result = lock(&mut mock, &alice, body());
+    assert!(matches!(result, Err(Error::Paused)));
+    let result = witness(
+        &mut mock,
+        &alice,
+        types::Witness {
+            id: 0,
+            owner: keys::alice::address(),
+            amount: BaseUnits::new(100, Denomination::NATIVE),
+        },
+    );
+    assert!(matches!(result, Err(Error::Paused)));
+
+    set_paused(&mut mock, &erin, false).expect("guardian should be able to unpause");
+    lock(&mut mock, &alice, body()).expect("lock should succeed after unpausing");
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    lock(
+        &mut mock,
+        &alice,
+        types::Lock {
+            target: vec![0xab; 20],
+            amount: BaseUnits::new(500, Denomination::NATIVE),
+        },
+    )
+    .expect("lock should succeed");
+    witness(
+        &mut mock,
+        &bob,
+        types::Witness {
+            id: 7,
+            owner: keys::dave::address(),
+            amount: BaseUnits::new(100, Denomination::NATIVE),
+        },
+    )
+    .expect("witness should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Bridge>();
+    assert_eq!(genesis.parameters.threshold, 2);
+    for prefix in [
+        super::state::NEXT_OUTGOING_ID,
+        super::state::PENDING,
+        super::state::RATE_LIMITS,
+    ] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "outgoing sequence numbers, pending transfers and rate limits should be exported"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Bridge module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::{
+    address::Address,
+    token::{BaseUnits, Denomination},
+};
+
+/// Kind of bridged asset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[repr(u8)]
+pub enum AssetKind {
+    /// Asset native to the runtime. Amounts bridged out are locked in the bridge pool and are
+    /// released from it when bridged back in.
+    #[default]
+    Native = 0,
+    /// Runtime representation of an external asset. Amounts bridged in are minted and amounts
+    /// bridged out are burned.
+    Wrapped = 1,
+}
+
+/// Bridged asset configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct AssetConfig {
+    /// Denomination of the asset in the runtime.
+    pub denomination: Denomination,
+    /// Kind of the asset.
+    pub kind: AssetKind,
+    /// Maximum amount of the asset that can be bridged in each direction per day. Zero means that
+    /// the amount is not limited.
+    #[cbor(optional)]
+    pub daily_limit: u128,
+}
+
+/// Amounts of an asset bridged during a day.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct RateLimitUsage {
+    /// Day since the Unix epoch.
+    pub day: u64,
+    /// Amount bridged out of the runtime.
+    pub outgoing: u128,
+    /// Amount bridged into the runtime.
+    pub incoming: u128,
+}
+
+/// Variant of an incoming transfer witnessed by relayers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Incoming {
+    /// Address of the account receiving the transfer.
+    pub owner: Address,
+    /// Transferred amount.
+    pub amount: BaseUnits,
+    /// Relayers that witnessed this variant of the transfer.
+    pub witnesses: Vec<Address>,
+    /// Whether the transfer has been executed.
+    pub executed: bool,
+}
+
+/// Lock call, bridging an amount out of the runtime.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Lock {
+    /// Address of the receiving account on the external chain.
+    pub target: Vec<u8>,
+    /// Amount to bridge.
+    pub amount: BaseUnits,
+}
+
+/// Witness call, attesting to an incoming transfer from the external chain.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Witness {
+    /// Sequence number of the transfer on the external chain.
+    pub id: u64,
+    /// Address of the account receiving the transfer.
+    pub owner: Address,
+    /// Transferred amount.
+    pub amount: BaseUnits,
+}
+
+/// Set paused call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct SetPaused {
+    /// Whether the bridge should be paused.
+    pub paused: bool,
+}
+
+/// Incoming transfer query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct IncomingQuery {
+    /// Sequence number of the transfer on the external chain.
+    pub id: u64,
+}
+
+/// Rate limit usage query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct RateLimitQuery {
+    /// Denomination of the asset.
+    pub denomination: Denomination,
+}
@@ -476,3 +476,11 @@ impl module::TransactionHandler for Module {}
 impl module::BlockHandler for Module {}
 
 impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+        }
+    }
+}
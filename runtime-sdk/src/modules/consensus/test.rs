@@ -503,3 +503,18 @@ fn test_init_bad_scaling_factor_2() {
         },
     });
 }
+
+#[test]
+fn test_export_state() {
+    let _mock = mock::Mock::default();
+    let params = Parameters {
+        gas_costs: Default::default(),
+        consensus_denomination: Denomination::NATIVE,
+        consensus_scaling_factor: 1_000,
+        min_delegate_amount: 10,
+    };
+    Consensus::set_params(params.clone());
+
+    let genesis = mock::assert_export_round_trip::<Consensus>();
+    assert_eq!(genesis.parameters, params);
+}
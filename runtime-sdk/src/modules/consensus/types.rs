@@ -1,7 +1,8 @@
 use oasis_core_runtime::common::namespace::Namespace;
+use oasis_runtime_sdk_macros::Schema;
 
 /// Kind of root.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
 #[repr(u8)]
 pub enum RootKind {
     State = 1,
@@ -9,7 +10,7 @@ pub enum RootKind {
 }
 
 /// Internal round root call body.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
 #[cbor(no_default)]
 pub struct RoundRootBody {
     pub runtime_id: Namespace,
@@ -19,17 +19,19 @@ use oasis_runtime_sdk_macros::{handler, sdk_derive};
 
 use crate::{
     context::{Context, TxContext},
-    error, migration, module,
+    error, export, migration, module,
     module::Module as _,
     modules,
     modules::core::{Error as CoreError, API as _},
+    modules::safety::API as _,
     runtime::Runtime,
     storage::Prefix,
     types::{
         address::Address,
-        message::{MessageEvent, MessageEventHookInvocation},
+        message::{MessageEvent, MessageEventHookInvocation, TypedMessageEvent},
+        pagination::PageResponse,
         token,
-        transaction::AuthInfo,
+        transaction::{AuthInfo, PreviewField},
     },
 };
 
@@ -159,6 +161,10 @@ pub enum Event {
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Genesis {
     pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
 }
 
 /// Interface that can be called from other modules.
@@ -255,6 +261,9 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> API
         // exists, but consensus state could be outdated since last block, so
         // just try to withdraw.
 
+        // Funds leaving the consensus account count towards any session key spending cap.
+        Accounts::use_session_spending(ctx, from, &amount)?;
+
         // Do withdraw from the consensus account and update the account state if
         // successful.
         Consensus::withdraw(
@@ -282,6 +291,9 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> API
         to: Address,
         amount: token::BaseUnits,
     ) -> Result<(), Error> {
+        // Make sure the outflow is not halted by the circuit breaker.
+        modules::safety::Module::record_outflow(ctx, &amount).map_err(|_| Error::Forbidden)?;
+
         // Transfer out of runtime account and update the account state if successful.
         Consensus::transfer(
             ctx,
@@ -318,6 +330,9 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> API
         amount: token::BaseUnits,
         receipt: bool,
     ) -> Result<(), Error> {
+        // Make sure the outflow is not halted by the circuit breaker.
+        modules::safety::Module::record_outflow(ctx, &amount).map_err(|_| Error::Forbidden)?;
+
         Consensus::escrow(
             ctx,
             to,
@@ -382,7 +397,8 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
     Module<Accounts, Consensus>
 {
     const NAME: &'static str = MODULE_NAME;
-    const VERSION: u32 = 1;
+    const VERSION: u32 = 2;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts", "consensus"];
     type Error = Error;
     type Event = Event;
     type Parameters = Parameters;
@@ -390,10 +406,19 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
 
     #[migration(init)]
     pub fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
         // Set genesis parameters.
         Self::set_params(genesis.parameters);
     }
 
+    #[migration(from = 1)]
+    fn migrate_v1_to_v2() {
+        // Version 2 indexes active delegations by their destination.
+        state::rebuild_delegators_index();
+    }
+
     /// Deposit in the runtime.
     #[handler(call = "consensus.Deposit")]
     fn tx_deposit<C: TxContext>(ctx: &mut C, body: types::Deposit) -> Result<(), Error> {
@@ -569,16 +594,16 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
     fn query_delegations<C: Context>(
         _ctx: &mut C,
         args: types::DelegationsQuery,
-    ) -> Result<Vec<types::ExtendedDelegationInfo>, Error> {
-        state::get_delegations(args.from)
+    ) -> Result<PageResponse<types::ExtendedDelegationInfo>, Error> {
+        state::get_delegations(args.from, &args.pagination)
     }
 
     #[handler(query = "consensus.Undelegations")]
     fn query_undelegations<C: Context>(
         _ctx: &mut C,
         args: types::UndelegationsQuery,
-    ) -> Result<Vec<types::UndelegationInfo>, Error> {
-        state::get_undelegations(args.to)
+    ) -> Result<PageResponse<types::UndelegationInfo>, Error> {
+        state::get_undelegations(args.to, &args.pagination)
     }
 
     #[handler(message_result = CONSENSUS_TRANSFER_HANDLER)]
@@ -656,7 +681,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
     #[handler(message_result = CONSENSUS_DELEGATE_HANDLER)]
     fn message_result_delegate<C: Context>(
         ctx: &mut C,
-        me: MessageEvent,
+        me: TypedMessageEvent<AddEscrowResult>,
         context: types::ConsensusDelegateContext,
     ) {
         if !me.is_success() {
@@ -676,7 +701,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
                     types::ReceiptKind::Delegate,
                     context.nonce,
                     types::Receipt {
-                        error: Some(me.clone().into()),
+                        error: Some(me.event.clone().into()),
                         ..Default::default()
                     },
                 );
@@ -688,7 +713,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
                 nonce: context.nonce,
                 to: context.to,
                 amount: context.amount,
-                error: Some(me.into()),
+                error: Some(me.event.into()),
             });
             return;
         }
@@ -701,7 +726,6 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
         let result = me
             .result
             .expect("event from consensus should have a result");
-        let result: AddEscrowResult = cbor::from_value(result).unwrap();
         let shares = result.new_shares.try_into().unwrap();
 
         state::add_delegation(context.from, context.to, shares).unwrap();
@@ -732,7 +756,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
     #[handler(message_result = CONSENSUS_UNDELEGATE_HANDLER)]
     fn message_result_undelegate<C: Context>(
         ctx: &mut C,
-        me: MessageEvent,
+        me: TypedMessageEvent<ReclaimEscrowResult>,
         context: types::ConsensusUndelegateContext,
     ) {
         if !me.is_success() {
@@ -746,7 +770,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
                     types::ReceiptKind::UndelegateStart,
                     context.nonce,
                     types::Receipt {
-                        error: Some(me.clone().into()),
+                        error: Some(me.event.clone().into()),
                         ..Default::default()
                     },
                 );
@@ -759,7 +783,7 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
                 to: context.to,
                 shares: context.shares,
                 debond_end_time: EPOCH_INVALID,
-                error: Some(me.into()),
+                error: Some(me.event.into()),
             });
             return;
         }
@@ -769,7 +793,6 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
         let result = me
             .result
             .expect("event from consensus should have a result");
-        let result: ReclaimEscrowResult = cbor::from_value(result).unwrap();
         let debonding_shares = result.debonding_shares.try_into().unwrap();
 
         let receipt = if context.receipt {
@@ -818,6 +841,54 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API>
 {
 }
 
+impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> module::PreviewHandler
+    for Module<Accounts, Consensus>
+{
+    fn preview_call(method: &str, body: &cbor::Value) -> Option<Vec<PreviewField>> {
+        let body = body.clone();
+        let fields = match method {
+            "consensus.Deposit" => {
+                let body: types::Deposit = cbor::from_value(body).ok()?;
+                vec![
+                    PreviewField::new(
+                        "To",
+                        body.to
+                            .map_or_else(|| "caller".to_owned(), |to| to.to_string()),
+                    ),
+                    PreviewField::new("Amount", body.amount),
+                ]
+            }
+            "consensus.Withdraw" => {
+                let body: types::Withdraw = cbor::from_value(body).ok()?;
+                vec![
+                    PreviewField::new(
+                        "To (consensus)",
+                        body.to
+                            .map_or_else(|| "caller".to_owned(), |to| to.to_string()),
+                    ),
+                    PreviewField::new("Amount", body.amount),
+                ]
+            }
+            "consensus.Delegate" => {
+                let body: types::Delegate = cbor::from_value(body).ok()?;
+                vec![
+                    PreviewField::new("To (consensus)", body.to),
+                    PreviewField::new("Amount", body.amount),
+                ]
+            }
+            "consensus.Undelegate" => {
+                let body: types::Undelegate = cbor::from_value(body).ok()?;
+                vec![
+                    PreviewField::new("From (consensus)", body.from),
+                    PreviewField::new("Shares", body.shares),
+                ]
+            }
+            _ => return None,
+        };
+        Some(fields)
+    }
+}
+
 impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> module::BlockHandler
     for Module<Accounts, Consensus>
 {
@@ -984,3 +1055,14 @@ impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> modul
         Ok(())
     }
 }
+
+impl<Accounts: modules::accounts::API, Consensus: modules::consensus::API> module::StateExporter
+    for Module<Accounts, Consensus>
+{
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
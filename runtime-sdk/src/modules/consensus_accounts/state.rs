@@ -8,7 +8,10 @@ use oasis_core_runtime::consensus::beacon::EpochTime;
 
 use crate::{
     storage::{self, CurrentStore, Store},
-    types::address::Address,
+    types::{
+        address::Address,
+        pagination::{paginate, PageRequest, PageResponse},
+    },
 };
 
 use super::{types, Error, MODULE_NAME};
@@ -21,14 +24,25 @@ pub const UNDELEGATIONS: &[u8] = &[0x02];
 pub const UNDELEGATION_QUEUE: &[u8] = &[0x03];
 /// Receipts.
 pub const RECEIPTS: &[u8] = &[0x04];
+/// Map of active delegations indexed by their destination.
+pub const DELEGATORS: &[u8] = &[0x05];
+
+/// Prefixes of module state exported as raw state.
+pub const RAW: &[&[u8]] = &[
+    DELEGATIONS,
+    UNDELEGATIONS,
+    UNDELEGATION_QUEUE,
+    RECEIPTS,
+    DELEGATORS,
+];
 
 /// Add delegation for a given (from, to) pair.
 ///
 /// The given shares are added to any existing delegation that may exist for the same (from, to)
 /// address pair. If no delegation exists a new one is created.
 pub fn add_delegation(from: Address, to: Address, shares: u128) -> Result<(), Error> {
-    CurrentStore::with(|store| {
-        let store = storage::PrefixStore::new(store, &MODULE_NAME);
+    CurrentStore::with(|mut root_store| {
+        let store = storage::PrefixStore::new(&mut root_store, &MODULE_NAME);
         let delegations = storage::PrefixStore::new(store, &DELEGATIONS);
         let mut account = storage::TypedStore::new(storage::PrefixStore::new(delegations, &from));
         let mut di: types::DelegationInfo = account.get(to).unwrap_or_default();
@@ -38,7 +52,13 @@ pub fn add_delegation(from: Address, to: Address, shares: u128) -> Result<(), Er
             .checked_add(shares)
             .ok_or(Error::InvalidArgument)?;
 
-        account.insert(to, di);
+        account.insert(to, di.clone());
+
+        // Keep the per-destination index in sync.
+        let store = storage::PrefixStore::new(root_store, &MODULE_NAME);
+        let delegators = storage::PrefixStore::new(store, &DELEGATORS);
+        let mut escrow = storage::TypedStore::new(storage::PrefixStore::new(delegators, &to));
+        escrow.insert(from, di);
 
         Ok(())
     })
@@ -46,8 +66,8 @@ pub fn add_delegation(from: Address, to: Address, shares: u128) -> Result<(), Er
 
 /// Subtract delegation from a given (from, to) pair.
 pub fn sub_delegation(from: Address, to: Address, shares: u128) -> Result<(), Error> {
-    CurrentStore::with(|store| {
-        let store = storage::PrefixStore::new(store, &MODULE_NAME);
+    CurrentStore::with(|mut root_store| {
+        let store = storage::PrefixStore::new(&mut root_store, &MODULE_NAME);
         let delegations = storage::PrefixStore::new(store, &DELEGATIONS);
         let mut account = storage::TypedStore::new(storage::PrefixStore::new(delegations, &from));
         let mut di: types::DelegationInfo = account.get(to).unwrap_or_default();
@@ -58,11 +78,21 @@ pub fn sub_delegation(from: Address, to: Address, shares: u128) -> Result<(), Er
             .ok_or(Error::InsufficientBalance)?;
 
         if di.shares > 0 {
-            account.insert(to, di);
+            account.insert(to, di.clone());
         } else {
             account.remove(to);
         }
 
+        // Keep the per-destination index in sync.
+        let store = storage::PrefixStore::new(root_store, &MODULE_NAME);
+        let delegators = storage::PrefixStore::new(store, &DELEGATORS);
+        let mut escrow = storage::TypedStore::new(storage::PrefixStore::new(delegators, &to));
+        if di.shares > 0 {
+            escrow.insert(from, di);
+        } else {
+            escrow.remove(from);
+        }
+
         Ok(())
     })
 }
@@ -80,24 +110,29 @@ pub fn get_delegation(from: Address, to: Address) -> Result<types::DelegationInf
     })
 }
 
-/// Retrieve all delegation metadata originating from a given address.
-pub fn get_delegations(from: Address) -> Result<Vec<types::ExtendedDelegationInfo>, Error> {
+/// Retrieve a page of delegation metadata originating from a given address.
+pub fn get_delegations(
+    from: Address,
+    pagination: &PageRequest,
+) -> Result<PageResponse<types::ExtendedDelegationInfo>, Error> {
+    let start = pagination
+        .start::<Address>()
+        .map_err(|_| Error::InvalidArgument)?;
+
     CurrentStore::with(|store| {
         let store = storage::PrefixStore::new(store, &MODULE_NAME);
         let delegations = storage::PrefixStore::new(store, &DELEGATIONS);
-        let account = storage::TypedStore::new(storage::PrefixStore::new(delegations, &from));
-
-        Ok(account
-            .iter()
-            .map(
-                |(to, di): (Address, types::DelegationInfo)| -> types::ExtendedDelegationInfo {
-                    types::ExtendedDelegationInfo {
-                        to,
-                        shares: di.shares,
-                    }
-                },
-            )
-            .collect())
+        let account = storage::StoreMap::<_, Address, types::DelegationInfo>::new(
+            storage::PrefixStore::new(delegations, &from),
+        );
+        let page = account.page(start.as_ref(), pagination.limit());
+
+        Ok(PageResponse::from_page(page, |(to, di)| {
+            types::ExtendedDelegationInfo {
+                to,
+                shares: di.shares,
+            }
+        }))
     })
 }
 
@@ -139,6 +174,40 @@ pub fn get_delegations_by_destination() -> Result<BTreeMap<Address, u128>, Error
     })
 }
 
+/// Return the delegators and their delegated shares for the given destination escrow account.
+///
+/// Only the delegations to the given destination are visited. Delegators are ordered by their
+/// address.
+pub fn get_delegators(to: Address) -> Result<Vec<(Address, u128)>, Error> {
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &MODULE_NAME);
+        let delegators = storage::PrefixStore::new(store, &DELEGATORS);
+        let escrow = storage::TypedStore::new(storage::PrefixStore::new(delegators, &to));
+
+        Ok(escrow
+            .iter::<Address, types::DelegationInfo>()
+            .map(|(from, di)| (from, di.shares))
+            .collect())
+    })
+}
+
+/// Rebuild the per-destination delegation index from the active delegations.
+pub fn rebuild_delegators_index() {
+    CurrentStore::with(|mut root_store| {
+        let store = storage::PrefixStore::new(&mut root_store, &MODULE_NAME);
+        let delegations = storage::TypedStore::new(storage::PrefixStore::new(store, &DELEGATIONS));
+        let entries: Vec<(AddressPair, types::DelegationInfo)> = delegations.iter().collect();
+
+        let store = storage::PrefixStore::new(root_store, &MODULE_NAME);
+        let mut delegators = storage::PrefixStore::new(store, &DELEGATORS);
+        for (ap, di) in entries {
+            let mut escrow =
+                storage::TypedStore::new(storage::PrefixStore::new(&mut delegators, &ap.1));
+            escrow.insert(ap.0, di);
+        }
+    })
+}
+
 /// Record new undelegation and add to undelegation queue.
 ///
 /// In case an undelegation for the given (from, to, epoch) tuple already exists, the undelegation
@@ -230,25 +299,36 @@ impl TryFrom<&[u8]> for AddressWithEpoch {
     }
 }
 
-/// Retrieve all undelegation metadata to a given address.
-pub fn get_undelegations(to: Address) -> Result<Vec<types::UndelegationInfo>, Error> {
+/// Retrieve a page of undelegation metadata to a given address.
+pub fn get_undelegations(
+    to: Address,
+    pagination: &PageRequest,
+) -> Result<PageResponse<types::UndelegationInfo>, Error> {
+    let start = pagination
+        .start::<Vec<u8>>()
+        .map_err(|_| Error::InvalidArgument)?;
+
     CurrentStore::with(|store| {
         let store = storage::PrefixStore::new(store, &MODULE_NAME);
         let undelegations = storage::PrefixStore::new(store, &UNDELEGATIONS);
-        let account = storage::TypedStore::new(storage::PrefixStore::new(undelegations, &to));
-
-        Ok(account
-            .iter()
-            .map(
-                |(ae, di): (AddressWithEpoch, types::DelegationInfo)| -> types::UndelegationInfo {
+        let account = storage::StoreMap::<_, Vec<u8>, types::DelegationInfo>::new(
+            storage::PrefixStore::new(undelegations, &to),
+        );
+        let iter = account
+            .iter_from(&start.unwrap_or_default())
+            .map(|(key, di)| {
+                let ae = AddressWithEpoch::try_from(key.as_slice()).expect("corrupted storage key");
+                (
+                    key,
                     types::UndelegationInfo {
                         from: ae.from,
                         epoch: ae.epoch,
                         shares: di.shares,
-                    }
-                },
-            )
-            .collect())
+                    },
+                )
+            });
+
+        Ok(paginate(iter, pagination))
     })
 }
 
@@ -347,11 +427,12 @@ mod test {
         let di = get_delegation(keys::alice::address(), keys::bob::address()).unwrap();
         assert_eq!(di.shares, 1000);
 
-        let dis = get_delegations(keys::bob::address()).unwrap();
-        assert!(dis.is_empty());
-        let dis = get_delegations(keys::alice::address()).unwrap();
-        assert_eq!(dis.len(), 1);
-        assert_eq!(dis[0].shares, 1000);
+        let dis = get_delegations(keys::bob::address(), &Default::default()).unwrap();
+        assert!(dis.items.is_empty());
+        let dis = get_delegations(keys::alice::address(), &Default::default()).unwrap();
+        assert_eq!(dis.items.len(), 1);
+        assert_eq!(dis.items[0].shares, 1000);
+        assert_eq!(dis.next, None);
 
         let totals = get_delegations_by_destination().unwrap();
         assert_eq!(totals.len(), 1);
@@ -378,6 +459,32 @@ mod test {
         assert_eq!(totals.len(), 2);
         assert_eq!(totals[&keys::alice::address()], 100);
         assert_eq!(totals[&keys::bob::address()], 1100);
+
+        assert_eq!(
+            get_delegators(keys::alice::address()).unwrap(),
+            vec![(keys::bob::address(), 100)]
+        );
+        let mut expected = vec![(keys::alice::address(), 900), (keys::bob::address(), 200)];
+        expected.sort();
+        assert_eq!(get_delegators(keys::bob::address()).unwrap(), expected);
+
+        sub_delegation(keys::bob::address(), keys::alice::address(), 100).unwrap();
+        assert!(get_delegators(keys::alice::address()).unwrap().is_empty());
+
+        // Rebuilding the index should restore the delegators.
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let delegators = storage::PrefixStore::new(store, &DELEGATORS);
+            let mut escrow = storage::TypedStore::new(storage::PrefixStore::new(
+                delegators,
+                keys::bob::address(),
+            ));
+            escrow.remove(keys::alice::address());
+            escrow.remove(keys::bob::address());
+        });
+        assert!(get_delegators(keys::bob::address()).unwrap().is_empty());
+        rebuild_delegators_index();
+        assert_eq!(get_delegators(keys::bob::address()).unwrap(), expected);
     }
 
     #[test]
@@ -401,16 +508,33 @@ mod test {
         assert_eq!(qd[0].to, keys::bob::address());
         assert_eq!(qd[0].epoch, 42);
 
-        let udis = get_undelegations(keys::alice::address()).unwrap();
-        assert!(udis.is_empty());
-        let udis = get_undelegations(keys::bob::address()).unwrap();
-        assert_eq!(udis.len(), 2);
-        assert_eq!(udis[0].from, keys::alice::address());
-        assert_eq!(udis[0].shares, 1000);
-        assert_eq!(udis[0].epoch, 42);
-        assert_eq!(udis[1].from, keys::alice::address());
-        assert_eq!(udis[1].shares, 200);
-        assert_eq!(udis[1].epoch, 84);
+        let udis = get_undelegations(keys::alice::address(), &Default::default()).unwrap();
+        assert!(udis.items.is_empty());
+        let udis = get_undelegations(keys::bob::address(), &Default::default()).unwrap();
+        assert_eq!(udis.items.len(), 2);
+        assert_eq!(udis.items[0].from, keys::alice::address());
+        assert_eq!(udis.items[0].shares, 1000);
+        assert_eq!(udis.items[0].epoch, 42);
+        assert_eq!(udis.items[1].from, keys::alice::address());
+        assert_eq!(udis.items[1].shares, 200);
+        assert_eq!(udis.items[1].epoch, 84);
+
+        // Undelegations can be fetched one page at a time.
+        let page = PageRequest {
+            cursor: None,
+            limit: 1,
+        };
+        let udis = get_undelegations(keys::bob::address(), &page).unwrap();
+        assert_eq!(udis.items.len(), 1);
+        assert_eq!(udis.items[0].epoch, 42);
+        let page = PageRequest {
+            cursor: udis.next,
+            limit: 1,
+        };
+        let udis = get_undelegations(keys::bob::address(), &page).unwrap();
+        assert_eq!(udis.items.len(), 1);
+        assert_eq!(udis.items[0].epoch, 84);
+        assert_eq!(udis.next, None);
 
         let di = take_undelegation(&qd[0]).unwrap();
         assert_eq!(di.shares, 1000);
@@ -419,8 +543,8 @@ mod test {
         let qd = get_queued_undelegations(42).unwrap();
         assert!(qd.is_empty());
 
-        let udis = get_undelegations(keys::bob::address()).unwrap();
-        assert_eq!(udis.len(), 1);
+        let udis = get_undelegations(keys::bob::address(), &Default::default()).unwrap();
+        assert_eq!(udis.items.len(), 1);
     }
 
     #[test]
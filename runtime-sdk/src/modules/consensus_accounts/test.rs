@@ -30,6 +30,7 @@ use crate::{
     },
     types::{
         address::SignatureAddressSpec,
+        message::FromMessageEvent as _,
         token::{BaseUnits, Denomination},
         transaction,
     },
@@ -800,7 +801,7 @@ fn perform_delegation<C: BatchContext>(ctx: &mut C, success: bool) -> u64 {
     };
     Module::<Accounts, Consensus>::message_result_delegate(
         ctx,
-        me,
+        TypedMessageEvent::from_message_event(me).unwrap(),
         cbor::from_value(hook.payload).unwrap(),
     );
 
@@ -871,11 +872,13 @@ fn test_api_delegate() {
         &mut ctx,
         types::DelegationsQuery {
             from: keys::alice::address(),
+            ..Default::default()
         },
     )
     .expect("delegations query should succeed");
-    assert_eq!(dis.len(), 1);
-    assert_eq!(dis[0].shares, 1_000);
+    assert_eq!(dis.items.len(), 1);
+    assert_eq!(dis.items[0].shares, 1_000);
+    assert_eq!(dis.next, None);
 }
 
 #[test]
@@ -1102,7 +1105,7 @@ fn perform_undelegation<C: BatchContext>(
     };
     Module::<Accounts, Consensus>::message_result_undelegate(
         ctx,
-        me,
+        TypedMessageEvent::from_message_event(me).unwrap(),
         cbor::from_value(hook.payload).unwrap(),
     );
 
@@ -1454,7 +1457,7 @@ fn test_api_undelegate_suspension() {
     };
     Module::<Accounts, Consensus>::message_result_undelegate(
         &mut ctx,
-        me,
+        TypedMessageEvent::from_message_event(me).unwrap(),
         cbor::from_value(hook_payload.unwrap()).unwrap(),
     );
 
@@ -1592,3 +1595,20 @@ fn test_prefetch() {
         );
     });
 }
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init_accounts(&mut ctx);
+    perform_delegation(&mut ctx, true);
+
+    let genesis = mock::assert_export_round_trip::<Module<Accounts, Consensus>>();
+    assert!(
+        genesis
+            .state
+            .keys()
+            .any(|key| key.starts_with(state::DELEGATIONS)),
+        "delegations should be exported"
+    );
+}
@@ -1,13 +1,14 @@
 //! Consensus module types.
 use oasis_core_runtime::consensus::beacon::EpochTime;
+use oasis_runtime_sdk_macros::Schema;
 
-use crate::types::{address::Address, message::MessageEvent, token};
+use crate::types::{address::Address, message::MessageEvent, pagination::PageRequest, token};
 
 /// Deposit into runtime call.
 /// Transfer from consensus staking to an account in this runtime.
 /// The transaction signer has a consensus layer allowance benefiting this runtime's staking
 /// address. The `to` address runtime account gets the tokens.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct Deposit {
     #[cbor(optional)]
     pub to: Option<Address>,
@@ -17,7 +18,7 @@ pub struct Deposit {
 /// Withdraw from runtime call.
 /// Transfer from an account in this runtime to consensus staking.
 /// The `to` address consensus staking account gets the tokens.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct Withdraw {
     #[cbor(optional)]
     pub to: Option<Address>,
@@ -25,7 +26,7 @@ pub struct Withdraw {
 }
 
 /// Delegate from runtime call.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct Delegate {
     pub to: Address,
     pub amount: token::BaseUnits,
@@ -34,7 +35,7 @@ pub struct Delegate {
 }
 
 /// Undelegate into runtime call.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct Undelegate {
     pub from: Address,
     pub shares: u128,
@@ -43,7 +44,7 @@ pub struct Undelegate {
 }
 
 /// Kind of receipt.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[repr(u8)]
 pub enum ReceiptKind {
@@ -62,14 +63,14 @@ impl ReceiptKind {
 }
 
 /// Take receipt internal runtime call.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct TakeReceipt {
     pub kind: ReceiptKind,
     pub id: u64,
 }
 
 /// A receipt.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Receipt {
     /// Shares received (for delegations).
@@ -94,43 +95,49 @@ pub struct Receipt {
 }
 
 /// Balance query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct BalanceQuery {
     pub address: Address,
 }
 
 /// Consensus account query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct ConsensusAccountQuery {
     pub address: Address,
 }
 
 /// Delegation query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct DelegationQuery {
     pub from: Address,
     pub to: Address,
 }
 
 /// Delegations query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct DelegationsQuery {
     pub from: Address,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
 }
 
 /// Undelegations query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct UndelegationsQuery {
     pub to: Address,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
 }
 
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct AccountBalance {
     pub balance: u128,
 }
 
 /// Information about a delegation.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct DelegationInfo {
     /// The amount of owned shares.
     pub shares: u128,
@@ -141,7 +148,7 @@ pub struct DelegationInfo {
 }
 
 /// Extended information about a delegation.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct ExtendedDelegationInfo {
     /// Address delegated to.
     pub to: Address,
@@ -150,7 +157,7 @@ pub struct ExtendedDelegationInfo {
 }
 
 /// Information about an undelegation.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct UndelegationInfo {
     /// Address being undelegated from.
     pub from: Address,
@@ -161,7 +168,7 @@ pub struct UndelegationInfo {
 }
 
 /// Context for consensus transfer message handler.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct ConsensusTransferContext {
     pub address: Address,
     #[cbor(optional)]
@@ -172,7 +179,7 @@ pub struct ConsensusTransferContext {
 }
 
 /// Context for consensus withdraw message handler.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct ConsensusWithdrawContext {
     #[cbor(optional)]
     pub from: Address,
@@ -183,7 +190,7 @@ pub struct ConsensusWithdrawContext {
 }
 
 /// Context for consensus delegate message handler.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Default)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Default, Schema)]
 pub struct ConsensusDelegateContext {
     pub from: Address,
     pub nonce: u64,
@@ -194,7 +201,7 @@ pub struct ConsensusDelegateContext {
 }
 
 /// Context for consensus undelegate message handler.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Default)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Default, Schema)]
 pub struct ConsensusUndelegateContext {
     pub from: Address,
     pub nonce: u64,
@@ -205,7 +212,7 @@ pub struct ConsensusUndelegateContext {
 }
 
 /// Error details from the consensus layer.
-#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
 pub struct ConsensusError {
     #[cbor(optional)]
     pub module: String,
@@ -11,21 +11,26 @@ use thiserror::Error;
 
 use crate::{
     callformat,
-    context::{BatchContext, Context, TransactionWithMeta, TxContext},
-    core::consensus::beacon::EpochTime,
+    context::{BatchContext, Context, Mode, TxContext},
+    core::{common::crypto::hash::Hash, consensus::beacon::EpochTime, transaction::tags::Tag},
+    crypto::{signature::PublicKey, webauthn},
     dispatcher,
-    error::Error as SDKError,
-    keymanager, migration,
+    error::{Error as SDKError, ErrorCatalog, ErrorInfo},
+    event::AttributedEvent,
+    export, keymanager, migration,
     module::{
         self, CallResult, InvariantHandler as _, MethodHandler as _, Module as _,
         ModuleInfoHandler as _,
     },
     sender::SenderMeta,
-    storage::{self, current::TransactionResult, CurrentStore},
+    storage::{self, CurrentStore},
+    subcall,
     types::{
+        pagination::PageResponse,
         token::{self, Denomination},
         transaction::{
-            self, AddressSpec, AuthProof, Call, CallFormat, CallerAddress, UnverifiedTransaction,
+            self, AddressSpec, AuthProof, Call, CallFormat, CallerAddress, Transaction,
+            UnverifiedTransaction,
         },
     },
     Runtime,
@@ -33,6 +38,9 @@ use crate::{
 
 use self::types::RuntimeInfoResponse;
 
+pub mod query_limits;
+pub mod rate_limit;
+pub mod storage_deposit;
 #[cfg(test)]
 mod test;
 pub mod types;
@@ -143,9 +151,53 @@ pub enum Error {
     #[sdk_error(code = 27)]
     CallDepthExceeded(u16, u16),
 
+    #[error("rate limit exceeded")]
+    #[sdk_error(code = 28)]
+    RateLimited,
+
+    #[error("reentrant call")]
+    #[sdk_error(code = 29)]
+    ReentrantCall,
+
+    #[error("block resource limit exceeded")]
+    #[sdk_error(code = 30)]
+    BlockResourcesExhausted,
+
+    #[error("session key not authorized: {0}")]
+    #[sdk_error(code = 31)]
+    SessionKeyNotAuthorized(String),
+
+    #[error("oversized memo")]
+    #[sdk_error(code = 32)]
+    OversizedMemo,
+
+    #[error("invalid signed query: {0}")]
+    #[sdk_error(code = 33)]
+    InvalidSignedQuery(&'static str),
+
+    #[error("rofl origin not authorized: {0}")]
+    #[sdk_error(code = 34)]
+    RoflOriginNotAuthorized(String),
+
+    #[error("query resources exhausted: {0}")]
+    #[sdk_error(code = 35)]
+    QueryResourcesExhausted(query_limits::Resource),
+
+    #[error("unmet transaction dependency: {0}")]
+    #[sdk_error(code = 36)]
+    UnmetDependency(Hash),
+
+    #[error("too many deferred subcalls")]
+    #[sdk_error(code = 37)]
+    TooManyDeferredSubcalls,
+
     #[error("{0}")]
     #[sdk_error(transparent)]
     TxSimulationFailed(#[from] TxSimulationFailure),
+
+    #[error("{0}")]
+    #[sdk_error(transparent)]
+    SubcallFailed(#[from] subcall::SubcallFailure),
 }
 
 impl Error {
@@ -162,7 +214,7 @@ impl Error {
 }
 
 /// Simulation failure error.
-#[derive(Error, Debug)]
+#[derive(Clone, Error, Debug)]
 pub struct TxSimulationFailure {
     message: String,
     module_name: String,
@@ -213,10 +265,31 @@ impl TryFrom<CallResult> for TxSimulationFailure {
 
 /// Events emitted by the core module.
 #[derive(Debug, PartialEq, Eq, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(untagged)]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Event {
     #[sdk_event(code = 1)]
     GasUsed { amount: u64 },
+
+    #[sdk_event(code = 2)]
+    Memo {
+        #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+        memo: Vec<u8>,
+    },
+
+    #[sdk_event(code = 3)]
+    ParametersChanged {
+        module: String,
+        changes: Vec<types::ParameterChange>,
+    },
+
+    #[sdk_event(code = 4)]
+    MessageResultDropped {
+        hook_name: String,
+        round: u64,
+        index: u32,
+    },
 }
 
 /// Gas costs.
@@ -227,7 +300,62 @@ pub struct GasCosts {
     pub auth_signature: u64,
     pub auth_multisig_signer: u64,
 
+    /// Gas charged for verifying a BLS12-381 signature. If zero, the cost of a regular signature
+    /// (`auth_signature` or `auth_multisig_signer`) is charged instead.
+    #[cbor(optional)]
+    pub auth_signature_bls12381: u64,
+    /// Gas charged for verifying a Secp256k1 Schnorr (BIP-340) signature. If zero, the cost of a
+    /// regular signature (`auth_signature` or `auth_multisig_signer`) is charged instead.
+    #[cbor(optional)]
+    pub auth_signature_secp256k1schnorr: u64,
+    /// Gas charged for verifying a Dilithium3 signature. If zero, the cost of a regular signature
+    /// (`auth_signature` or `auth_multisig_signer`) is charged instead.
+    #[cbor(optional)]
+    pub auth_signature_dilithium3: u64,
+
     pub callformat_x25519_deoxysii: u64,
+    #[cbor(optional)]
+    pub callformat_x25519_xchacha20poly1305: u64,
+
+    #[cbor(optional)]
+    pub tx_memo_byte: u64,
+
+    /// Gas refunded for each storage key that existed before the transaction and is removed by it.
+    ///
+    /// Storage refunds of a transaction never exceed the storage access gas charged to it.
+    #[cbor(optional)]
+    pub storage_remove_refund: u64,
+    /// Gas refunded for each storage key that is changed and then written back to the value it
+    /// had before the transaction.
+    #[cbor(optional)]
+    pub storage_restore_refund: u64,
+
+    /// Gas charged for the first access to a storage key within a transaction.
+    #[cbor(optional)]
+    pub storage_cold_access: u64,
+    /// Gas charged for each subsequent access to an already accessed storage key within a
+    /// transaction.
+    #[cbor(optional)]
+    pub storage_warm_access: u64,
+}
+
+impl GasCosts {
+    /// Gas charged for verifying a signature made with the given public key. Schemes without a
+    /// dedicated cost are charged the given default cost.
+    fn signature_cost(&self, pk: &PublicKey, default: u64) -> u64 {
+        let cost = match pk {
+            PublicKey::Bls12381(_) => self.auth_signature_bls12381,
+            PublicKey::Secp256k1Schnorr(_) => self.auth_signature_secp256k1schnorr,
+            #[cfg(feature = "experimental-dilithium")]
+            PublicKey::Dilithium3(_) => self.auth_signature_dilithium3,
+            _ => 0,
+        };
+        if cost == 0 {
+            default
+        } else {
+            cost
+        }
+    }
 }
 
 /// Dynamic min gas price parameters.
@@ -253,6 +381,157 @@ pub struct DynamicMinGasPrice {
     pub min_price_max_change_denominator: u8,
 }
 
+/// Runtime-wide limits applied to all subcalls, regardless of the limits requested by the caller.
+///
+/// All limits are opt-in. With the default (zero) values no runtime-wide limits apply and only the
+/// limits requested by the caller are enforced, so runtimes that expose subcalls to untrusted
+/// callers should configure them explicitly in their genesis parameters.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct SubcallParameters {
+    /// Maximum depth of nested subcalls. The special value of 0 means that only the depth
+    /// requested by the caller applies.
+    #[cbor(optional)]
+    pub max_depth: u16,
+
+    /// Maximum percentage of the caller's remaining gas that can be forwarded to a subcall. The
+    /// special value of 0 means that there is no limit.
+    #[cbor(optional)]
+    pub max_gas_percentage: u8,
+
+    /// Denominator of the fraction of the caller's remaining gas that is always retained by the
+    /// caller. For example, a value of 64 means that at most 63/64 of the remaining gas can be
+    /// forwarded to a subcall. The special value of 0 means that no gas is retained.
+    #[cbor(optional)]
+    pub gas_retention_denominator: u64,
+}
+
+impl SubcallParameters {
+    /// Effective maximum subcall depth given the depth requested by the caller.
+    pub fn max_depth(&self, requested: u16) -> u16 {
+        if self.max_depth == 0 {
+            return requested;
+        }
+        requested.min(self.max_depth)
+    }
+
+    /// Maximum amount of gas that can be forwarded to a subcall given the amount of gas remaining
+    /// to the caller.
+    pub fn max_forwarded_gas(&self, remaining: u64) -> u64 {
+        let mut max_gas = remaining;
+        if self.max_gas_percentage > 0 {
+            let fraction = (remaining as u128) * (self.max_gas_percentage as u128) / 100;
+            max_gas = max_gas.min(fraction as u64);
+        }
+        if self.gas_retention_denominator > 0 {
+            max_gas = max_gas.min(remaining - remaining / self.gas_retention_denominator);
+        }
+        max_gas
+    }
+}
+
+/// Rotation policy of the ephemeral keys used for encrypting call data.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct EphemeralKeyParameters {
+    /// Number of epochs during which the same ephemeral key is used for encrypting calls. The
+    /// special value of 0 means that keys are rotated every epoch.
+    #[cbor(optional)]
+    pub rotation_interval: u64,
+
+    /// Number of epochs after a key has been rotated out during which calls encrypted with it are
+    /// still accepted. The special value of 0 means the longest window supported by the key
+    /// manager.
+    #[cbor(optional)]
+    pub grace_epochs: u64,
+}
+
+impl EphemeralKeyParameters {
+    /// Effective number of epochs during which the same ephemeral key is used.
+    pub fn rotation_interval(&self) -> u64 {
+        self.rotation_interval.max(1)
+    }
+
+    /// Effective number of epochs during which rotated out keys are still accepted.
+    pub fn grace_epochs(&self) -> u64 {
+        if self.grace_epochs == 0 {
+            return callformat::MAX_EPHEMERAL_KEY_AGE.saturating_sub(self.rotation_interval() - 1);
+        }
+        self.grace_epochs
+    }
+
+    /// Epoch of the ephemeral key that should be used for encrypting calls in the given epoch.
+    pub fn key_epoch(&self, epoch: EpochTime) -> EpochTime {
+        epoch - epoch % self.rotation_interval()
+    }
+
+    /// Last epoch in which calls encrypted with the ephemeral key of the given epoch are accepted.
+    pub fn key_valid_until(&self, key_epoch: EpochTime) -> EpochTime {
+        key_epoch
+            .saturating_add(self.rotation_interval() - 1)
+            .saturating_add(self.grace_epochs())
+    }
+}
+
+/// Per-block limits on resources that are not captured by gas.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct BlockLimits {
+    /// Maximum number of bytes (keys and values) written to storage by all transactions in a
+    /// block. The special value of 0 means that there is no limit.
+    #[cbor(optional)]
+    pub max_storage_write_bytes: u64,
+
+    /// Maximum number of consensus messages emitted by all transactions in a block. The special
+    /// value of 0 means that only the consensus layer limit applies.
+    #[cbor(optional)]
+    pub max_messages: u32,
+
+    /// Maximum number of events emitted by all transactions in a block. The special value of 0
+    /// means that there is no limit.
+    #[cbor(optional)]
+    pub max_events: u64,
+
+    /// Maximum amount of gas that can be used by all block workers in a block. The special value
+    /// of 0 means that only the per-worker limits apply.
+    #[cbor(optional)]
+    pub max_block_worker_gas: u64,
+}
+
+/// Resources used by transactions that are subject to block limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockResources {
+    /// Number of bytes written to storage.
+    pub storage_write_bytes: u64,
+    /// Number of emitted consensus messages.
+    pub messages: u32,
+    /// Number of emitted events.
+    pub events: u64,
+}
+
+impl BlockResources {
+    fn checked_add(&self, other: &BlockResources) -> Option<BlockResources> {
+        Some(BlockResources {
+            storage_write_bytes: self
+                .storage_write_bytes
+                .checked_add(other.storage_write_bytes)?,
+            messages: self.messages.checked_add(other.messages)?,
+            events: self.events.checked_add(other.events)?,
+        })
+    }
+
+    fn exceeds(&self, limits: &BlockLimits) -> bool {
+        (limits.max_storage_write_bytes > 0
+            && self.storage_write_bytes > limits.max_storage_write_bytes)
+            || (limits.max_messages > 0 && self.messages > limits.max_messages)
+            || (limits.max_events > 0 && self.events > limits.max_events)
+    }
+
+    fn reaches(&self, limits: &BlockLimits) -> bool {
+        (limits.max_storage_write_bytes > 0
+            && self.storage_write_bytes >= limits.max_storage_write_bytes)
+            || (limits.max_messages > 0 && self.messages >= limits.max_messages)
+            || (limits.max_events > 0 && self.events >= limits.max_events)
+    }
+}
+
 /// Errors emitted during core parameter validation.
 #[derive(Error, Debug)]
 pub enum ParameterValidationError {
@@ -260,6 +539,10 @@ pub enum ParameterValidationError {
     InvalidTargetBlockGasUsagePercentage,
     #[error("invalid dynamic min price max change denominator (1-50)")]
     InvalidMinPriceMaxChangeDenominator,
+    #[error("invalid subcall max gas percentage (0-100)")]
+    InvalidSubcallMaxGasPercentage,
+    #[error("ephemeral key validity exceeds the key manager limit")]
+    InvalidEphemeralKeyValidity,
 }
 /// Parameters for the core module.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
@@ -271,6 +554,15 @@ pub struct Parameters {
     pub gas_costs: GasCosts,
     pub min_gas_price: BTreeMap<token::Denomination, u128>,
     pub dynamic_min_gas_price: DynamicMinGasPrice,
+    /// Runtime-wide subcall limits. These are disabled unless explicitly configured.
+    #[cbor(optional)]
+    pub subcall: SubcallParameters,
+    #[cbor(optional)]
+    pub block_limits: BlockLimits,
+    #[cbor(optional)]
+    pub ephemeral_keys: EphemeralKeyParameters,
+    #[cbor(optional)]
+    pub storage_deposit: storage_deposit::Parameters,
 }
 
 impl module::Parameters for Parameters {
@@ -291,6 +583,21 @@ impl module::Parameters for Parameters {
                 return Err(ParameterValidationError::InvalidMinPriceMaxChangeDenominator);
             }
         }
+        // Validate subcall parameters.
+        if self.subcall.max_gas_percentage > 100 {
+            return Err(ParameterValidationError::InvalidSubcallMaxGasPercentage);
+        }
+        // Validate ephemeral key parameters. Keys must remain available in the key manager for as
+        // long as calls encrypted with them are accepted.
+        let ephemeral_keys = &self.ephemeral_keys;
+        let key_age = ephemeral_keys
+            .rotation_interval()
+            .checked_sub(1)
+            .and_then(|age| age.checked_add(ephemeral_keys.grace_epochs()))
+            .ok_or(ParameterValidationError::InvalidEphemeralKeyValidity)?;
+        if key_age > callformat::MAX_EPHEMERAL_KEY_AGE {
+            return Err(ParameterValidationError::InvalidEphemeralKeyValidity);
+        }
         Ok(())
     }
 }
@@ -301,12 +608,12 @@ pub trait API {
 
     /// Attempt to use gas. If the gas specified would cause either total used to exceed
     /// its limit, fails with Error::OutOfGas or Error::BatchOutOfGas, and neither gas usage is
-    /// increased.
+    /// increased. Fails with Error::QueryResourcesExhausted in queries that exceeded their limits.
     fn use_batch_gas<C: Context>(ctx: &mut C, gas: u64) -> Result<(), Error>;
 
     /// Attempt to use gas. If the gas specified would cause either total used to exceed
     /// its limit, fails with Error::OutOfGas or Error::BatchOutOfGas, and neither gas usage is
-    /// increased.
+    /// increased. Fails with Error::QueryResourcesExhausted in queries that exceeded their limits.
     fn use_tx_gas<C: TxContext>(ctx: &mut C, gas: u64) -> Result<(), Error>;
 
     /// Returns the remaining batch-wide gas.
@@ -321,12 +628,49 @@ pub trait API {
     /// Return the used tx-wide gas.
     fn used_tx_gas<C: TxContext>(ctx: &mut C) -> u64;
 
+    /// Record gas that should be refunded to the transaction, e.g. for clearing storage.
+    ///
+    /// Refunds are applied when the top-level call completes and are limited to a fraction of the
+    /// used gas as configured by `Config::MAX_GAS_REFUND_QUOTIENT`.
+    fn refund_tx_gas<C: TxContext>(ctx: &mut C, gas: u64);
+
+    /// Return the tx-wide gas refund recorded so far.
+    fn refunded_tx_gas<C: TxContext>(ctx: &mut C) -> u64;
+
+    /// Attempt to use block resources. If the given usage would cause any of the block limits to
+    /// be exceeded, fails with Error::BlockResourcesExhausted and no usage is recorded.
+    fn use_block_resources<C: Context>(ctx: &mut C, usage: BlockResources) -> Result<(), Error>;
+
+    /// Returns the block resources used so far.
+    fn used_block_resources<C: Context>(ctx: &mut C) -> BlockResources;
+
+    /// Whether any of the block resource limits has been reached.
+    fn block_resources_exhausted<C: Context>(ctx: &mut C) -> bool;
+
     /// Configured maximum amount of gas that can be used in a batch.
     fn max_batch_gas<C: Context>(ctx: &mut C) -> u64;
 
     /// Configured minimum gas price.
     fn min_gas_price<C: Context>(ctx: &C, denom: &token::Denomination) -> Option<u128>;
 
+    /// Configured runtime-wide subcall limits.
+    fn subcall_parameters() -> SubcallParameters;
+
+    /// Configured per-block resource limits.
+    fn block_limits() -> BlockLimits;
+
+    /// Amount of gas charged for the given storage accesses.
+    fn storage_access_gas(stats: &storage::AccessStats) -> Result<u64, Error>;
+
+    /// Configured ephemeral call data key rotation policy.
+    fn ephemeral_key_parameters() -> EphemeralKeyParameters;
+
+    /// Configured storage deposit parameters.
+    fn storage_deposit_parameters() -> storage_deposit::Parameters;
+
+    /// Configured maximum (decompressed) transaction size in bytes.
+    fn max_tx_size() -> u32;
+
     /// Sets the transaction priority to the provided amount.
     fn set_priority<C: Context>(ctx: &mut C, priority: u64);
 
@@ -345,12 +689,65 @@ pub trait API {
 
     /// Check whether the epoch has changed since last processed block.
     fn has_epoch_changed<C: Context>(ctx: &mut C) -> bool;
+
+    /// Returns the conversion rate used for paying fees in the given non-native denomination.
+    fn fee_conversion_rate(denom: &token::Denomination) -> Option<types::FeeConversionRate>;
+
+    /// Sets or clears the conversion rate used for paying fees in the given non-native
+    /// denomination.
+    ///
+    /// This is meant to be called by modules acting as price oracles, such as the oracle module for
+    /// feeds configured with a fee conversion. Once a rate is set, fees can be paid in the given
+    /// denomination even when it has no configured minimum gas price, in which case the minimum
+    /// gas price is derived from the native minimum gas price.
+    fn set_fee_conversion_rate(
+        denom: token::Denomination,
+        rate: Option<types::FeeConversionRate>,
+    ) -> Result<(), Error>;
+
+    /// Returns the gas price of the given fee expressed in the native denomination.
+    ///
+    /// Fees in denominations without a conversion rate are returned unchanged.
+    fn native_gas_price(fee: &transaction::Fee) -> u128;
+
+    /// Returns the state size statistics of all modules.
+    fn state_stats() -> BTreeMap<String, types::StateStats>;
+
+    /// Update per-module state size statistics with the given state changes.
+    ///
+    /// Each changed key is attributed to the module with the longest name that is a prefix of the
+    /// key. Changes to keys outside of module stores are not accounted for.
+    fn update_state_stats(changes: &storage::StateChanges);
+
+    /// Commit the execution receipt of the transaction with the given index in the current round.
+    fn store_receipt<C: Context>(ctx: &mut C, tx_index: u32, receipt: types::Receipt);
+
+    /// Returns the execution receipt of the transaction with the given index in the given round.
+    fn receipt(round: u64, tx_index: u32) -> Option<types::Receipt>;
+
+    /// Record the events emitted by the transaction with the given index in the current round in
+    /// the secondary event indices declared via `Config::EVENT_INDICES`.
+    fn index_events<C: Context>(ctx: &mut C, tx_index: u32, tags: &[Tag]);
+
+    /// Compare the given current parameters of all modules against the ones they replaced during
+    /// the current block, emitting an event and recording the change in the parameter history
+    /// for each module whose parameters have changed.
+    ///
+    /// Parameters set for the first time, for example at genesis, are not recorded.
+    fn record_parameter_changes<C: Context>(ctx: &mut C, params: BTreeMap<String, cbor::Value>);
+
+    /// Return the recorded parameter changes of the given module, oldest first.
+    fn parameter_history(module: &str) -> Vec<types::ParametersChange>;
 }
 
 /// Genesis state for the accounts module.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Genesis {
     pub parameters: Parameters,
+
+    /// Module state other than parameters and metadata, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
 }
 
 /// Local configuration that can be provided by the node operator.
@@ -374,6 +771,16 @@ pub struct LocalConfig {
     /// This setting should likely be kept at 0, unless the runtime is using the EVM module.
     #[cbor(optional)]
     pub estimate_gas_search_max_iters: u64,
+
+    /// Per-signer rate limits applied when checking transactions for inclusion in the local
+    /// transaction pool. Rate limiting is disabled by default.
+    #[cbor(optional)]
+    pub rate_limit: rate_limit::LocalConfig,
+
+    /// Resource limits applied to all queries served by the node. Queries are not limited by
+    /// default.
+    #[cbor(optional)]
+    pub query_limits: query_limits::LocalConfig,
 }
 
 /// State schema constants.
@@ -386,6 +793,126 @@ pub mod state {
     pub const LAST_EPOCH: &[u8] = &[0x03];
     /// Dynamic min gas price.
     pub const DYNAMIC_MIN_GAS_PRICE: &[u8] = &[0x04];
+    /// Conversion rates for paying fees in non-native denominations.
+    pub const FEE_CONVERSION_RATES: &[u8] = &[0x05];
+    /// Message results from previous rounds that could not be handled yet.
+    pub const PENDING_MESSAGE_RESULTS: &[u8] = &[0x06];
+    /// Map of owner addresses to their storage deposits.
+    pub const STORAGE_DEPOSITS: &[u8] = &[0x07];
+    /// State size statistics of all modules.
+    pub const STATE_STATS: &[u8] = &[0x08];
+    /// Execution receipts of transactions, keyed by round and transaction index.
+    pub const RECEIPTS: &[u8] = &[0x09];
+    /// Secondary event indices, keyed by index key, round, transaction index and sequence number.
+    pub const EVENT_INDEX: &[u8] = &[0x0a];
+    /// Map of rounds to event index keys updated in that round, used for pruning.
+    pub const EVENT_INDEX_ROUNDS: &[u8] = &[0x0b];
+    /// Map of module names to their parameters before being changed in the current block. The
+    /// snapshots are removed at the end of each block so they are not exported.
+    pub const PARAMETER_SNAPSHOTS: &[u8] = &[0x0c];
+    /// Per-module queues of recorded parameter changes, oldest first.
+    pub const PARAMETER_HISTORY: &[u8] = &[0x0d];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[
+        MESSAGE_HANDLERS,
+        LAST_EPOCH,
+        DYNAMIC_MIN_GAS_PRICE,
+        FEE_CONVERSION_RATES,
+        PENDING_MESSAGE_RESULTS,
+        STORAGE_DEPOSITS,
+        STATE_STATS,
+        RECEIPTS,
+        EVENT_INDEX,
+        EVENT_INDEX_ROUNDS,
+        PARAMETER_HISTORY,
+    ];
+}
+
+/// Declaration of a secondary index over the values of an event field.
+///
+/// For example `EventIndex { module: "accounts", code: 1, field: "to" }` indexes transfers by
+/// their recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventIndex {
+    /// Name of the module emitting the event.
+    pub module: &'static str,
+    /// Code of the event.
+    pub code: u32,
+    /// Name of the indexed event field.
+    pub field: &'static str,
+}
+
+impl EventIndex {
+    /// Whether the given event tag key belongs to the indexed event.
+    fn matches(&self, key: &[u8]) -> bool {
+        key.strip_prefix(self.module.as_bytes()) == Some(&self.code.to_be_bytes()[..])
+    }
+}
+
+/// Look up the value of the named field of an encoded event.
+fn event_field<'a>(event: &'a cbor::Value, field: &str) -> Option<&'a cbor::Value> {
+    match event {
+        cbor::Value::Map(entries) => entries.iter().find_map(|(key, value)| match key {
+            cbor::Value::TextString(key) if key == field => Some(value),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Remember the parameters of the given module as they were before being first changed in the
+/// current block, so that the change can be recorded at the end of the block.
+pub(crate) fn snapshot_parameters(module: &str, params: cbor::Value) {
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &MODULE_NAME);
+        let mut snapshots = storage::StoreMap::<_, String, cbor::Value>::new(
+            storage::PrefixStore::new(store, &state::PARAMETER_SNAPSHOTS),
+        );
+        let module = module.to_owned();
+        if !snapshots.contains(&module) {
+            snapshots.insert(&module, params);
+        }
+    })
+}
+
+/// Compute the changes between the given old and new module parameters.
+///
+/// Parameters encoded as maps are compared by their top-level keys.
+fn diff_parameters(old: &cbor::Value, new: &cbor::Value) -> Vec<types::ParameterChange> {
+    let (old_fields, new_fields) = match (old, new) {
+        (cbor::Value::Map(old_fields), cbor::Value::Map(new_fields)) => (old_fields, new_fields),
+        _ if old == new => return vec![],
+        _ => {
+            return vec![types::ParameterChange {
+                key: String::new(),
+                old: Some(old.clone()),
+                new: Some(new.clone()),
+            }]
+        }
+    };
+
+    let key = |value: &cbor::Value| match value {
+        cbor::Value::TextString(key) => key.clone(),
+        other => hex::encode(cbor::to_vec(other.clone())),
+    };
+    let old_fields: BTreeMap<String, &cbor::Value> =
+        old_fields.iter().map(|(k, v)| (key(k), v)).collect();
+    let new_fields: BTreeMap<String, &cbor::Value> =
+        new_fields.iter().map(|(k, v)| (key(k), v)).collect();
+
+    let keys: BTreeSet<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = old_fields.get(key).copied();
+            let new = new_fields.get(key).copied();
+            (old != new).then(|| types::ParameterChange {
+                key: key.clone(),
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect()
 }
 
 /// Module configuration.
@@ -414,10 +941,48 @@ pub trait Config: 'static {
     /// Confidential runtimes may want to disable this as it is a possible side channel.
     const EMIT_GAS_USED_EVENTS: bool = true;
 
+    /// Whether emitted events should be wrapped together with their attribution to the
+    /// transaction and call (or subcall) that emitted them.
+    ///
+    /// See `event::AttributedEvent` for the format of attributed events.
+    const EMIT_EVENT_ATTRIBUTION: bool = false;
+
     /// Whether to allow submission of read-only transactions in an interactive way.
     ///
     /// Note that execution of such transactions is allowed to access confidential state.
     const ALLOW_INTERACTIVE_READ_ONLY_TRANSACTIONS: bool = false;
+
+    /// Maximum fraction of the gas used by a transaction that can be refunded, expressed as the
+    /// denominator of the fraction (e.g. 5 means that at most 1/5 of the used gas is refunded). The
+    /// special value of 0 means that refunds are only limited by the used gas.
+    const MAX_GAS_REFUND_QUOTIENT: u64 = 5;
+
+    /// Whether to maintain per-module state size statistics, updated at the end of each block.
+    ///
+    /// Statistics only account for changes made while they are enabled.
+    const TRACK_STATE_STATS: bool = false;
+
+    /// Whether to commit an execution receipt of every executed transaction into runtime state.
+    const STORE_RECEIPTS: bool = false;
+
+    /// Number of rounds for which execution receipts are retained. The special value of 0 means
+    /// that receipts are never pruned.
+    const RECEIPT_RETENTION_ROUNDS: u64 = 0;
+
+    /// Event fields for which secondary indices are maintained in runtime state so that events
+    /// can be looked up via the `core.IndexedEvents` query without scanning all blocks.
+    ///
+    /// Only events emitted by executed transactions are indexed.
+    const EVENT_INDICES: once_cell::unsync::Lazy<Vec<EventIndex>> =
+        once_cell::unsync::Lazy::new(Vec::new);
+
+    /// Number of rounds for which indexed events are retained. The special value of 0 means that
+    /// indexed events are never pruned.
+    const EVENT_INDEX_RETENTION_ROUNDS: u64 = 0;
+
+    /// Maximum number of parameter changes retained per module for the `core.ParameterHistory`
+    /// query. The special value of 0 means that no history is retained.
+    const PARAMETER_HISTORY_LIMIT: u64 = 32;
 }
 
 pub struct Module<Cfg: Config> {
@@ -425,6 +990,10 @@ pub struct Module<Cfg: Config> {
 }
 
 const CONTEXT_KEY_GAS_USED: &str = "core.GasUsed";
+const CONTEXT_KEY_GAS_REFUND: &str = "core.GasRefund";
+const CONTEXT_KEY_STORAGE_GAS: &str = "core.StorageGas";
+const CONTEXT_KEY_STORAGE_ACCESS_COSTS: &str = "core.StorageAccessCosts";
+const CONTEXT_KEY_BLOCK_RESOURCES: &str = "core.BlockResources";
 const CONTEXT_KEY_PRIORITY: &str = "core.Priority";
 const CONTEXT_KEY_SENDER_META: &str = "core.SenderMeta";
 const CONTEXT_KEY_EPOCH_CHANGED: &str = "core.EpochChanged";
@@ -433,6 +1002,9 @@ impl<Cfg: Config> API for Module<Cfg> {
     type Config = Cfg;
 
     fn use_batch_gas<C: Context>(ctx: &mut C, gas: u64) -> Result<(), Error> {
+        // Stop queries that have exhausted their resources.
+        query_limits::check().map_err(Error::QueryResourcesExhausted)?;
+
         // Do not enforce batch limits for check-tx.
         if ctx.is_check_only() {
             return Ok(());
@@ -456,21 +1028,13 @@ impl<Cfg: Config> API for Module<Cfg> {
     }
 
     fn use_tx_gas<C: TxContext>(ctx: &mut C, gas: u64) -> Result<(), Error> {
-        let gas_limit = ctx.tx_auth_info().fee.gas;
-        let gas_used = ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default();
-        let new_gas_used = {
-            let sum = gas_used.checked_add(gas).ok_or(Error::GasOverflow)?;
-            if sum > gas_limit {
-                return Err(Error::out_of_gas::<Cfg>(gas_limit, sum));
-            }
-            sum
-        };
+        // Stop queries that have exhausted their resources.
+        query_limits::check().map_err(Error::QueryResourcesExhausted)?;
 
-        Self::use_batch_gas(ctx, gas)?;
-
-        *ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default() = new_gas_used;
-
-        Ok(())
+        // Charge for storage accesses made since gas was last used so that accesses are paid for
+        // as they happen instead of only once the call completes.
+        Self::charge_storage_access_gas(ctx)?;
+        Self::charge_tx_gas(ctx, gas)
     }
 
     fn remaining_batch_gas<C: Context>(ctx: &mut C) -> u64 {
@@ -498,12 +1062,82 @@ impl<Cfg: Config> API for Module<Cfg> {
         *ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default()
     }
 
+    fn refund_tx_gas<C: TxContext>(ctx: &mut C, gas: u64) {
+        let refund = ctx.tx_value::<u64>(CONTEXT_KEY_GAS_REFUND).or_default();
+        *refund = refund.saturating_add(gas);
+    }
+
+    fn refunded_tx_gas<C: TxContext>(ctx: &mut C) -> u64 {
+        *ctx.tx_value::<u64>(CONTEXT_KEY_GAS_REFUND).or_default()
+    }
+
+    fn use_block_resources<C: Context>(ctx: &mut C, usage: BlockResources) -> Result<(), Error> {
+        // Do not enforce block limits for check-tx. Pre-schedule checks only record usage in their
+        // own context so the scheduler can skip transactions that would exceed the limits.
+        if ctx.mode() == Mode::CheckTx {
+            return Ok(());
+        }
+        let limits = Self::params().block_limits;
+        let used = Self::used_block_resources(ctx)
+            .checked_add(&usage)
+            .ok_or(Error::BlockResourcesExhausted)?;
+        if used.exceeds(&limits) {
+            return Err(Error::BlockResourcesExhausted);
+        }
+
+        ctx.value::<BlockResources>(CONTEXT_KEY_BLOCK_RESOURCES)
+            .set(used);
+
+        Ok(())
+    }
+
+    fn used_block_resources<C: Context>(ctx: &mut C) -> BlockResources {
+        ctx.value::<BlockResources>(CONTEXT_KEY_BLOCK_RESOURCES)
+            .get()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn block_resources_exhausted<C: Context>(ctx: &mut C) -> bool {
+        let limits = Self::params().block_limits;
+        Self::used_block_resources(ctx).reaches(&limits)
+    }
+
     fn max_batch_gas<C: Context>(_ctx: &mut C) -> u64 {
         Self::params().max_batch_gas
     }
 
     fn min_gas_price<C: Context>(ctx: &C, denom: &token::Denomination) -> Option<u128> {
-        Self::min_gas_prices(ctx).get(denom).copied()
+        Self::effective_min_gas_prices(ctx).get(denom).copied()
+    }
+
+    fn subcall_parameters() -> SubcallParameters {
+        Self::params().subcall
+    }
+
+    fn block_limits() -> BlockLimits {
+        Self::params().block_limits
+    }
+
+    fn storage_access_gas(stats: &storage::AccessStats) -> Result<u64, Error> {
+        let gas_costs = Self::params().gas_costs;
+        Self::access_gas(
+            stats,
+            gas_costs.storage_cold_access,
+            gas_costs.storage_warm_access,
+        )
+    }
+
+    fn ephemeral_key_parameters() -> EphemeralKeyParameters {
+        Self::params().ephemeral_keys
+    }
+
+    fn storage_deposit_parameters() -> storage_deposit::Parameters {
+        Self::params().storage_deposit
+    }
+
+    fn max_tx_size() -> u32 {
+        Self::params().max_tx_size
     }
 
     fn set_priority<C: Context>(ctx: &mut C, priority: u64) {
@@ -536,6 +1170,262 @@ impl<Cfg: Config> API for Module<Cfg> {
     fn has_epoch_changed<C: Context>(ctx: &mut C) -> bool {
         *ctx.value(CONTEXT_KEY_EPOCH_CHANGED).get().unwrap_or(&false)
     }
+
+    fn fee_conversion_rate(denom: &token::Denomination) -> Option<types::FeeConversionRate> {
+        Self::fee_conversion_rates().remove(denom)
+    }
+
+    fn set_fee_conversion_rate(
+        denom: token::Denomination,
+        rate: Option<types::FeeConversionRate>,
+    ) -> Result<(), Error> {
+        if denom.is_native() {
+            return Err(Error::InvalidArgument(anyhow!(
+                "native denomination cannot have a conversion rate"
+            )));
+        }
+
+        let mut rates = Self::fee_conversion_rates();
+        match rate {
+            Some(rate) if !rate.is_valid() => {
+                return Err(Error::InvalidArgument(anyhow!("invalid conversion rate")));
+            }
+            Some(rate) => {
+                rates.insert(denom, rate);
+            }
+            None => {
+                rates.remove(&denom);
+            }
+        }
+
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(&mut store);
+            if rates.is_empty() {
+                tstore.remove(state::FEE_CONVERSION_RATES);
+            } else {
+                tstore.insert(state::FEE_CONVERSION_RATES, rates);
+            }
+        });
+
+        Ok(())
+    }
+
+    fn native_gas_price(fee: &transaction::Fee) -> u128 {
+        let gas_price = fee.gas_price();
+        if fee.amount.denomination().is_native() {
+            return gas_price;
+        }
+
+        match Self::fee_conversion_rate(fee.amount.denomination()) {
+            Some(rate) => rate.to_native(gas_price),
+            None => gas_price,
+        }
+    }
+
+    fn state_stats() -> BTreeMap<String, types::StateStats> {
+        CurrentStore::with(|store| {
+            let store = storage::TypedStore::new(storage::PrefixStore::new(store, &MODULE_NAME));
+            store.get(state::STATE_STATS).unwrap_or_default()
+        })
+    }
+
+    fn update_state_stats(changes: &storage::StateChanges) {
+        if changes.is_empty() {
+            return;
+        }
+
+        CurrentStore::with(|store| {
+            let mut store =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &MODULE_NAME));
+            let metadata: types::Metadata = store.get(state::METADATA).unwrap_or_default();
+            let mut stats: BTreeMap<String, types::StateStats> =
+                store.get(state::STATE_STATS).unwrap_or_default();
+
+            for (key, delta) in changes.iter() {
+                let module = metadata
+                    .versions
+                    .keys()
+                    .filter(|name| !name.is_empty() && key.starts_with(name.as_bytes()))
+                    .max_by_key(|name| name.len());
+                let module = match module {
+                    Some(module) => module,
+                    None => continue,
+                };
+
+                let entry = stats.entry(module.clone()).or_default();
+                entry.keys = entry.keys.saturating_add_signed(delta.keys);
+                entry.bytes = entry.bytes.saturating_add_signed(delta.bytes);
+            }
+
+            store.insert(state::STATE_STATS, stats);
+        })
+    }
+
+    fn store_receipt<C: Context>(ctx: &mut C, tx_index: u32, receipt: types::Receipt) {
+        let round = ctx.time().round();
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut receipts = storage::TypedStore::new(storage::PrefixStore::new(
+                storage::PrefixStore::new(store, &state::RECEIPTS),
+                round.to_be_bytes(),
+            ));
+            receipts.insert(tx_index.to_be_bytes(), receipt);
+        })
+    }
+
+    fn receipt(round: u64, tx_index: u32) -> Option<types::Receipt> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let receipts = storage::TypedStore::new(storage::PrefixStore::new(
+                storage::PrefixStore::new(store, &state::RECEIPTS),
+                round.to_be_bytes(),
+            ));
+            receipts.get(tx_index.to_be_bytes())
+        })
+    }
+
+    fn index_events<C: Context>(ctx: &mut C, tx_index: u32, tags: &[Tag]) {
+        let declared = Cfg::EVENT_INDICES;
+        if declared.is_empty() {
+            return;
+        }
+
+        let mut entries = Vec::new();
+        for tag in tags {
+            let indices: Vec<_> = declared
+                .iter()
+                .filter(|index| index.matches(&tag.key))
+                .collect();
+            if indices.is_empty() {
+                continue;
+            }
+            let events: Vec<cbor::Value> = match cbor::from_slice(&tag.value) {
+                Ok(events) => events,
+                Err(_) => continue,
+            };
+            for event in events {
+                let event = if Cfg::EMIT_EVENT_ATTRIBUTION {
+                    match cbor::from_value::<AttributedEvent>(event) {
+                        Ok(attributed) => attributed.event,
+                        Err(_) => continue,
+                    }
+                } else {
+                    event
+                };
+                for index in &indices {
+                    if let Some(value) = event_field(&event, index.field) {
+                        let key =
+                            types::event_index_key(index.module, index.code, index.field, value);
+                        entries.push((key, event.clone()));
+                    }
+                }
+            }
+        }
+        if entries.is_empty() {
+            return;
+        }
+
+        let round = ctx.time().round();
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut updated = BTreeSet::new();
+            for (seq, (key, value)) in entries.into_iter().enumerate() {
+                let mut events = storage::StoreMap::<_, Vec<u8>, types::IndexedEvent>::new(
+                    storage::PrefixStore::new(
+                        storage::PrefixStore::new(&mut store, &state::EVENT_INDEX),
+                        key,
+                    ),
+                );
+                let position = [
+                    &round.to_be_bytes()[..],
+                    &tx_index.to_be_bytes(),
+                    &(seq as u32).to_be_bytes(),
+                ]
+                .concat();
+                events.insert(
+                    &position,
+                    types::IndexedEvent {
+                        round,
+                        tx_index,
+                        value,
+                    },
+                );
+                updated.insert(key);
+            }
+
+            // Remember which keys have been updated so that they can be pruned later.
+            if Cfg::EVENT_INDEX_RETENTION_ROUNDS > 0 {
+                let mut rounds = storage::StoreMap::<_, u64, Vec<Hash>>::new(
+                    storage::PrefixStore::new(&mut store, &state::EVENT_INDEX_ROUNDS),
+                );
+                let mut keys: BTreeSet<Hash> =
+                    rounds.get(&round).unwrap_or_default().into_iter().collect();
+                keys.extend(updated);
+                rounds.insert(&round, keys.into_iter().collect());
+            }
+        })
+    }
+
+    fn record_parameter_changes<C: Context>(ctx: &mut C, params: BTreeMap<String, cbor::Value>) {
+        let round = ctx.time().round();
+        let mut events = Vec::new();
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let snapshots: Vec<(String, cbor::Value)> = {
+                let mut snapshots = storage::StoreMap::<_, String, cbor::Value>::new(
+                    storage::PrefixStore::new(&mut store, &state::PARAMETER_SNAPSHOTS),
+                );
+                let entries: Vec<_> = snapshots.iter().collect();
+                for (module, _) in &entries {
+                    snapshots.remove(module);
+                }
+                entries
+            };
+
+            for (module, old) in snapshots {
+                let changes = match params.get(&module) {
+                    Some(new) => diff_parameters(&old, new),
+                    None => continue,
+                };
+                if changes.is_empty() {
+                    continue;
+                }
+
+                if Cfg::PARAMETER_HISTORY_LIMIT > 0 {
+                    let mut history = storage::StoreQueue::<_, types::ParametersChange>::new(
+                        storage::PrefixStore::new(
+                            storage::PrefixStore::new(&mut store, &state::PARAMETER_HISTORY),
+                            module.clone(),
+                        ),
+                    );
+                    history.push_back(types::ParametersChange {
+                        round,
+                        changes: changes.clone(),
+                    });
+                    while history.len() > Cfg::PARAMETER_HISTORY_LIMIT {
+                        history.pop_front();
+                    }
+                }
+                events.push(Event::ParametersChanged { module, changes });
+            }
+        });
+        for event in events {
+            ctx.emit_event(event);
+        }
+    }
+
+    fn parameter_history(module: &str) -> Vec<types::ParametersChange> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let history =
+                storage::StoreQueue::<_, types::ParametersChange>::new(storage::PrefixStore::new(
+                    storage::PrefixStore::new(store, &state::PARAMETER_HISTORY),
+                    module,
+                ));
+            history.iter().collect()
+        })
+    }
 }
 
 #[sdk_derive(Module)]
@@ -548,6 +1438,9 @@ impl<Cfg: Config> Module<Cfg> {
 
     #[migration(init)]
     pub fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
         // Set genesis parameters.
         Self::set_params(genesis.parameters);
     }
@@ -596,11 +1489,13 @@ impl<Cfg: Config> Module<Cfg> {
         let auth_proofs: Result<_, Error> = args
             .tx
             .auth_info
-            .signer_info
-            .iter()
+            .signers()
             .map(|si| match si.address_spec {
-                // For the signature address spec we assume a signature auth proof of 64 bytes.
-                transaction::AddressSpec::Signature(_) => {
+                // For the signature, session key and ROFL origin address specs we assume a
+                // signature auth proof of 64 bytes.
+                transaction::AddressSpec::Signature(_)
+                | transaction::AddressSpec::SessionKey(_)
+                | transaction::AddressSpec::Rofl(_) => {
                     Ok(transaction::AuthProof::Signature(vec![0; 64].into()))
                 }
                 // For the multisig address spec assume all the signers sign with a 64-byte signature.
@@ -612,6 +1507,15 @@ impl<Cfg: Config> Module<Cfg> {
                             .collect(),
                     ))
                 }
+                // For the WebAuthn address spec assume typical authenticator and client data sizes
+                // and a DER-encoded P-256 signature.
+                transaction::AddressSpec::WebAuthn(_) => {
+                    Ok(transaction::AuthProof::WebAuthn(webauthn::Assertion {
+                        authenticator_data: vec![0; 37],
+                        client_data_json: vec![0; 256],
+                        signature: vec![0; 72].into(),
+                    }))
+                }
                 // Internal address specs should never appear as they are not serializable.
                 transaction::AddressSpec::Internal(_) => Err(Error::MalformedTransaction(anyhow!(
                     "internal address spec used"
@@ -656,41 +1560,19 @@ impl<Cfg: Config> Module<Cfg> {
             let mut tx = tx.clone();
             tx.auth_info.fee.gas = gas;
 
-            CurrentStore::with_transaction(|| {
-                let result = ctx.with_simulation(|mut sim_ctx| {
-                    sim_ctx.with_tx(
-                        TransactionWithMeta {
-                            tx,
-                            tx_size,
-                            tx_index: 0,
-                            tx_hash: Default::default(),
-                        },
-                        |mut tx_ctx, call| {
-                            let (result, _) =
-                                dispatcher::Dispatcher::<C::Runtime>::dispatch_tx_call(
-                                    &mut tx_ctx,
-                                    call,
-                                    &Default::default(),
-                                );
-                            if !result.is_success() && report_failure {
-                                // Report failure.
-                                let err: TxSimulationFailure = result.try_into().unwrap(); // Guaranteed to be a Failed CallResult.
-                                return Err(Error::TxSimulationFailed(err));
-                            }
-                            // Don't report success or failure. If the call fails, we still report
-                            // how much gas it uses while it fails.
-                            let gas_used = *tx_ctx.value::<u64>(CONTEXT_KEY_GAS_USED).or_default();
-                            if result.is_success() {
-                                Ok(gas_used)
-                            } else {
-                                Ok(gas_used.saturating_add(extra_gas_fail).clamp(0, gas))
-                            }
-                        },
-                    )
-                });
-
-                TransactionResult::Rollback(result) // Always rollback storage changes.
-            })
+            let simulation = dispatcher::Dispatcher::<C::Runtime>::simulate_tx(ctx, tx, tx_size)
+                .map_err(Error::Abort)?;
+            match simulation.result {
+                Ok(_) => Ok(simulation.gas_used),
+                // Report failure.
+                Err(err) if report_failure => Err(Error::TxSimulationFailed(err)),
+                // Don't report success or failure. If the call fails, we still report how much gas
+                // it uses while it fails.
+                Err(_) => Ok(simulation
+                    .gas_used
+                    .saturating_add(extra_gas_fail)
+                    .clamp(0, gas)),
+            }
         };
 
         // Do a binary search for exact gas limit.
@@ -811,7 +1693,7 @@ impl<Cfg: Config> Module<Cfg> {
         let key_manager = ctx
             .key_manager()
             .ok_or_else(|| Error::InvalidArgument(anyhow!("key manager not available")))?;
-        let epoch = ctx.epoch();
+        let epoch = Self::params().ephemeral_keys.key_epoch(ctx.epoch());
         let public_key = key_manager
             .get_public_ephemeral_key(callformat::get_key_pair_id(epoch), epoch)
             .map_err(|err| match err {
@@ -821,7 +1703,30 @@ impl<Cfg: Config> Module<Cfg> {
                 _ => Error::Abort(err.into()),
             })?;
 
-        Ok(types::CallDataPublicKeyQueryResponse { public_key, epoch })
+        Ok(types::CallDataPublicKeyQueryResponse {
+            public_key,
+            epoch,
+            formats: callformat::ENCRYPTED_CALL_FORMATS.to_vec(),
+        })
+    }
+
+    /// Retrieve the public key for encrypting call data together with the range of epochs during
+    /// which it is valid.
+    #[handler(query = "core.CallDataPublicKeyValidity")]
+    fn query_calldata_public_key_validity<C: Context>(
+        ctx: &mut C,
+        _args: (),
+    ) -> Result<types::CallDataPublicKeyValidityQueryResponse, Error> {
+        let params = Self::params().ephemeral_keys;
+        let key = Self::query_calldata_public_key(ctx, ())?;
+
+        Ok(types::CallDataPublicKeyValidityQueryResponse {
+            rotate_after: key.epoch + params.rotation_interval() - 1,
+            valid_until: params.key_valid_until(key.epoch),
+            public_key: key.public_key,
+            epoch: key.epoch,
+            formats: key.formats,
+        })
     }
 
     /// Query the minimum gas price.
@@ -830,7 +1735,7 @@ impl<Cfg: Config> Module<Cfg> {
         ctx: &mut C,
         _args: (),
     ) -> Result<BTreeMap<token::Denomination, u128>, Error> {
-        let mut mgp = Self::min_gas_prices(ctx);
+        let mut mgp = Self::effective_min_gas_prices(ctx);
 
         // Generate a combined view with local overrides.
         for (denom, price) in mgp.iter_mut() {
@@ -843,7 +1748,85 @@ impl<Cfg: Config> Module<Cfg> {
         Ok(mgp)
     }
 
-    /// Return basic information about the module and the containing runtime.
+    /// Query the storage deposit of the given owner.
+    #[handler(query = "core.StorageDeposit")]
+    fn query_storage_deposit<C: Context>(
+        _ctx: &mut C,
+        args: types::StorageDepositQuery,
+    ) -> Result<storage_deposit::Deposit, Error> {
+        Ok(storage_deposit::get_deposit(args.owner))
+    }
+
+    /// Query the state size statistics of all modules.
+    #[handler(query = "core.StateStats")]
+    fn query_state_stats<C: Context>(
+        _ctx: &mut C,
+        _args: (),
+    ) -> Result<BTreeMap<String, types::StateStats>, Error> {
+        Ok(Self::state_stats())
+    }
+
+    /// Query the execution receipt of a transaction.
+    #[handler(query = "core.Receipt")]
+    fn query_receipt<C: Context>(
+        _ctx: &mut C,
+        args: types::ReceiptQuery,
+    ) -> Result<types::Receipt, Error> {
+        Self::receipt(args.round, args.index)
+            .ok_or_else(|| Error::InvalidArgument(anyhow::anyhow!("receipt not found")))
+    }
+
+    /// Query events recorded in a secondary event index, oldest first.
+    #[handler(query = "core.IndexedEvents")]
+    fn query_indexed_events<C: Context>(
+        _ctx: &mut C,
+        args: types::IndexedEventsQuery,
+    ) -> Result<PageResponse<types::IndexedEvent>, Error> {
+        #[allow(clippy::borrow_interior_mutable_const)]
+        if !Cfg::EVENT_INDICES.iter().any(|index| {
+            index.module == args.module && index.code == args.code && index.field == args.field
+        }) {
+            return Err(Error::InvalidArgument(anyhow!(
+                "event field is not indexed"
+            )));
+        }
+        let start = args
+            .pagination
+            .start::<Vec<u8>>()
+            .map_err(|err| Error::InvalidArgument(err.into()))?;
+        let key = types::event_index_key(&args.module, args.code, &args.field, &args.value);
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let events = storage::StoreMap::<_, Vec<u8>, types::IndexedEvent>::new(
+                storage::PrefixStore::new(
+                    storage::PrefixStore::new(store, &state::EVENT_INDEX),
+                    key,
+                ),
+            );
+            let page = events.page(start.as_ref(), args.pagination.limit());
+            Ok(PageResponse::from_page(page, |(_, event)| event))
+        })
+    }
+
+    /// Query the recorded parameter changes of a module within the given round range, oldest
+    /// first.
+    #[handler(query = "core.ParameterHistory")]
+    fn query_parameter_history<C: Context>(
+        _ctx: &mut C,
+        args: types::ParameterHistoryQuery,
+    ) -> Result<Vec<types::ParametersChange>, Error> {
+        if args.to_round != 0 && args.to_round < args.from_round {
+            return Err(Error::InvalidArgument(anyhow!("invalid round range")));
+        }
+        Ok(Self::parameter_history(&args.module)
+            .into_iter()
+            .filter(|change| change.round >= args.from_round)
+            .filter(|change| args.to_round == 0 || change.round <= args.to_round)
+            .collect())
+    }
+
+    /// Return basic information about the modules and the capabilities of the containing runtime.
     #[handler(query = "core.RuntimeInfo")]
     fn query_runtime_info<C: Context>(
         ctx: &mut C,
@@ -853,6 +1836,28 @@ impl<Cfg: Config> Module<Cfg> {
             runtime_version: <C::Runtime as Runtime>::VERSION,
             state_version: <C::Runtime as Runtime>::STATE_VERSION,
             modules: <C::Runtime as Runtime>::Modules::module_info(ctx),
+            confidential: ctx.is_confidential(),
+            transaction_versions: vec![transaction::LATEST_TRANSACTION_VERSION],
+        })
+    }
+
+    #[handler(query = "core.ErrorCatalog")]
+    fn query_error_catalog<C: Context>(_ctx: &mut C, _args: ()) -> Result<Vec<ErrorInfo>, Error> {
+        let catalog = ErrorCatalog::new(<C::Runtime as Runtime>::Modules::error_catalog())
+            .map_err(|err| Error::InvariantViolation(err.to_string()))?;
+        Ok(catalog.errors())
+    }
+
+    /// Return schema metadata (methods with their argument and result types, events) for all
+    /// modules of the containing runtime.
+    #[handler(query = "core.RuntimeMetadata")]
+    fn query_runtime_metadata<C: Context>(
+        _ctx: &mut C,
+        _args: (),
+    ) -> Result<types::RuntimeMetadataResponse, Error> {
+        Ok(types::RuntimeMetadataResponse {
+            runtime_version: <C::Runtime as Runtime>::VERSION,
+            modules: <C::Runtime as Runtime>::Modules::module_metadata(),
         })
     }
 
@@ -917,6 +1922,179 @@ impl<Cfg: Config> Module<Cfg> {
 }
 
 impl<Cfg: Config> Module<Cfg> {
+    /// Apply the recorded gas refund by reducing the amount of used tx-wide and batch-wide gas.
+    fn apply_tx_gas_refund<C: TxContext>(ctx: &mut C) {
+        let refund = ctx
+            .tx_value::<u64>(CONTEXT_KEY_GAS_REFUND)
+            .take()
+            .unwrap_or_default();
+        if refund == 0 {
+            return;
+        }
+
+        let gas_used = Self::used_tx_gas(ctx);
+        let refund = match Cfg::MAX_GAS_REFUND_QUOTIENT {
+            0 => refund.min(gas_used),
+            quotient => refund.min(gas_used / quotient),
+        };
+
+        *ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default() = gas_used - refund;
+        let batch_gas_used = Self::used_batch_gas(ctx);
+        ctx.value::<u64>(CONTEXT_KEY_GAS_USED)
+            .set(batch_gas_used.saturating_sub(refund));
+    }
+
+    /// Record gas refunds for storage keys that the current transaction removes or writes back to
+    /// their original values. Only storage gas that has actually been charged is refunded.
+    fn refund_storage_gas<C: TxContext>(ctx: &mut C) {
+        let charged = ctx
+            .tx_value::<u64>(CONTEXT_KEY_STORAGE_GAS)
+            .take()
+            .unwrap_or_default();
+        let gas_costs = Self::params().gas_costs;
+        if charged == 0
+            || (gas_costs.storage_remove_refund == 0 && gas_costs.storage_restore_refund == 0)
+        {
+            return;
+        }
+
+        let summary = CurrentStore::pending_update_summary();
+        let refund = summary
+            .removed
+            .saturating_mul(gas_costs.storage_remove_refund)
+            .saturating_add(
+                summary
+                    .restored
+                    .saturating_mul(gas_costs.storage_restore_refund),
+            )
+            .min(charged);
+        if refund > 0 {
+            Self::refund_tx_gas(ctx, refund);
+        }
+    }
+
+    /// Use the given amount of tx-wide gas without charging for pending storage accesses.
+    fn charge_tx_gas<C: TxContext>(ctx: &mut C, gas: u64) -> Result<(), Error> {
+        let gas_limit = ctx.tx_auth_info().fee.gas;
+        let gas_used = ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default();
+        let new_gas_used = {
+            let sum = gas_used.checked_add(gas).ok_or(Error::GasOverflow)?;
+            if sum > gas_limit {
+                return Err(Error::out_of_gas::<Cfg>(gas_limit, sum));
+            }
+            sum
+        };
+
+        Self::use_batch_gas(ctx, gas)?;
+
+        *ctx.tx_value::<u64>(CONTEXT_KEY_GAS_USED).or_default() = new_gas_used;
+
+        Ok(())
+    }
+
+    /// Gas cost of the given storage accesses at the given per-access costs.
+    fn access_gas(stats: &storage::AccessStats, cold: u64, warm: u64) -> Result<u64, Error> {
+        (|| {
+            let cold = stats.cold.checked_mul(cold)?;
+            let warm = stats.warm.checked_mul(warm)?;
+            cold.checked_add(warm)
+        })()
+        .ok_or(Error::GasOverflow)
+    }
+
+    /// Charge for storage accesses made by the current call since they were last charged.
+    fn charge_storage_access_gas<C: TxContext>(ctx: &mut C) -> Result<(), Error> {
+        let (cold, warm) = match ctx
+            .tx_value::<(u64, u64)>(CONTEXT_KEY_STORAGE_ACCESS_COSTS)
+            .get()
+        {
+            Some(costs) => *costs,
+            None => return Ok(()), // Accesses are not tracked for the current call.
+        };
+        let stats = CurrentStore::take_pending_access_stats();
+        let gas = Self::access_gas(&stats, cold, warm)?;
+        if gas == 0 {
+            return Ok(());
+        }
+        Self::charge_tx_gas(ctx, gas)?;
+
+        let charged = ctx.tx_value::<u64>(CONTEXT_KEY_STORAGE_GAS).or_default();
+        *charged = charged.saturating_add(gas);
+        Ok(())
+    }
+
+    /// Remove execution receipts of the round that has dropped out of the retention window.
+    fn prune_receipts<C: Context>(ctx: &mut C) {
+        if !Cfg::STORE_RECEIPTS || Cfg::RECEIPT_RETENTION_ROUNDS == 0 {
+            return;
+        }
+        let round = match ctx
+            .time()
+            .round()
+            .checked_sub(Cfg::RECEIPT_RETENTION_ROUNDS)
+        {
+            Some(round) => round,
+            None => return,
+        };
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut receipts = storage::TypedStore::new(storage::PrefixStore::new(
+                storage::PrefixStore::new(store, &state::RECEIPTS),
+                round.to_be_bytes(),
+            ));
+            let indices: Vec<[u8; 4]> = receipts
+                .iter::<[u8; 4], types::Receipt>()
+                .map(|(index, _)| index)
+                .collect();
+            for index in indices {
+                receipts.remove(index);
+            }
+        })
+    }
+
+    /// Remove indexed events of the round that has dropped out of the retention window.
+    fn prune_event_indices<C: Context>(ctx: &mut C) {
+        if Cfg::EVENT_INDEX_RETENTION_ROUNDS == 0 {
+            return;
+        }
+        let round = match ctx
+            .time()
+            .round()
+            .checked_sub(Cfg::EVENT_INDEX_RETENTION_ROUNDS)
+        {
+            Some(round) => round,
+            None => return,
+        };
+
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let keys = {
+                let mut rounds = storage::StoreMap::<_, u64, Vec<Hash>>::new(
+                    storage::PrefixStore::new(&mut store, &state::EVENT_INDEX_ROUNDS),
+                );
+                let keys = rounds.get(&round).unwrap_or_default();
+                rounds.remove(&round);
+                keys
+            };
+            for key in keys {
+                let mut events = storage::StoreMap::<_, Vec<u8>, types::IndexedEvent>::new(
+                    storage::PrefixStore::new(
+                        storage::PrefixStore::new(
+                            storage::PrefixStore::new(&mut store, &state::EVENT_INDEX),
+                            key,
+                        ),
+                        round.to_be_bytes(),
+                    ),
+                );
+                let positions: Vec<Vec<u8>> = events.iter().map(|(position, _)| position).collect();
+                for position in positions {
+                    events.remove(&position);
+                }
+            }
+        })
+    }
+
     fn min_gas_prices<C: Context>(_ctx: &C) -> BTreeMap<Denomination, u128> {
         let params = Self::params();
         if params.dynamic_min_gas_price.enabled {
@@ -933,13 +2111,47 @@ impl<Cfg: Config> Module<Cfg> {
         }
     }
 
+    fn fee_conversion_rates() -> BTreeMap<Denomination, types::FeeConversionRate> {
+        CurrentStore::with(|store| {
+            let store = storage::TypedStore::new(storage::PrefixStore::new(store, &MODULE_NAME));
+            store.get(state::FEE_CONVERSION_RATES).unwrap_or_default()
+        })
+    }
+
+    /// Minimum gas prices including the prices derived for denominations that can be used for
+    /// paying fees via a conversion rate.
+    fn effective_min_gas_prices<C: Context>(ctx: &C) -> BTreeMap<Denomination, u128> {
+        let mut mgp = Self::min_gas_prices(ctx);
+        let native_mgp = match mgp.get(&Denomination::NATIVE) {
+            Some(native_mgp) => *native_mgp,
+            None => return mgp,
+        };
+
+        for (denom, rate) in Self::fee_conversion_rates() {
+            mgp.entry(denom)
+                .or_insert_with(|| rate.from_native(native_mgp));
+        }
+        mgp
+    }
+
     fn get_local_min_gas_price<C: Context>(ctx: &C, denom: &token::Denomination) -> u128 {
         #[allow(clippy::borrow_interior_mutable_const)]
-        ctx.local_config(MODULE_NAME)
+        let local_mgp = ctx
+            .local_config(MODULE_NAME)
             .as_ref()
             .map(|cfg: &LocalConfig| cfg.min_gas_price.get(denom).copied())
-            .unwrap_or_else(|| Cfg::DEFAULT_LOCAL_MIN_GAS_PRICE.get(denom).copied())
-            .unwrap_or_default()
+            .unwrap_or_else(|| Cfg::DEFAULT_LOCAL_MIN_GAS_PRICE.get(denom).copied());
+
+        match local_mgp {
+            Some(local_mgp) => local_mgp,
+            // Derive the local minimum gas price from the native one for converted denominations.
+            None if !denom.is_native() => Self::fee_conversion_rate(denom)
+                .map(|rate| {
+                    rate.from_native(Self::get_local_min_gas_price(ctx, &Denomination::NATIVE))
+                })
+                .unwrap_or_default(),
+            None => 0,
+        }
     }
 
     fn get_local_max_estimated_gas<C: Context>(ctx: &C) -> u64 {
@@ -949,6 +2161,27 @@ impl<Cfg: Config> Module<Cfg> {
             .unwrap_or_default()
     }
 
+    fn enforce_rate_limit<C: Context>(ctx: &C, tx: &Transaction) -> Result<(), Error> {
+        let cfg = match ctx.local_config::<LocalConfig>(MODULE_NAME) {
+            Some(cfg) if cfg.rate_limit.is_enabled() => cfg.rate_limit,
+            _ => return Ok(()),
+        };
+        let signer = match tx.auth_info.signer_info.first() {
+            Some(si) => si.address_spec.address(),
+            None => return Ok(()),
+        };
+
+        // Only the first check of each transaction is rate limited.
+        let tx_hash = Hash::digest_bytes(&cbor::to_vec(tx.clone()));
+        if !rate_limit::check(signer, tx_hash, ctx.time().round(), &cfg) {
+            slog::debug!(ctx.get_logger(MODULE_NAME), "rate limited transaction check";
+                "signer" => %signer,
+            );
+            return Err(Error::RateLimited);
+        }
+        Ok(())
+    }
+
     fn enforce_min_gas_price<C: TxContext>(ctx: &C, call: &Call) -> Result<(), Error> {
         // If the method is exempt from min gas price requirements, checks always pass.
         #[allow(clippy::borrow_interior_mutable_const)]
@@ -1011,6 +2244,14 @@ impl<Cfg: Config> module::TransactionHandler for Module<Cfg> {
         Ok(())
     }
 
+    fn authenticate_tx<C: Context>(ctx: &mut C, tx: &Transaction) -> Result<(), Error> {
+        // Rate limits are node-local and must only be applied during local transaction checks.
+        if ctx.mode() == Mode::CheckTx {
+            Self::enforce_rate_limit(ctx, tx)?;
+        }
+        Ok(())
+    }
+
     fn before_handle_call<C: TxContext>(ctx: &mut C, call: &Call) -> Result<(), Error> {
         // Ensure that specified gas limit is not greater than batch gas limit.
         let params = Self::params();
@@ -1042,29 +2283,30 @@ impl<Cfg: Config> module::TransactionHandler for Module<Cfg> {
         )?;
 
         // Charge gas for signature verification.
-        let mut num_signature: u64 = 0;
-        let mut num_multisig_signer: u64 = 0;
-        for si in &ctx.tx_auth_info().signer_info {
-            match &si.address_spec {
-                AddressSpec::Signature(_) => {
-                    num_signature = num_signature.checked_add(1).ok_or(Error::GasOverflow)?;
-                }
-                AddressSpec::Multisig(config) => {
-                    num_multisig_signer = num_multisig_signer
-                        .checked_add(config.signers.len() as u64)
-                        .ok_or(Error::GasOverflow)?;
+        let gas_costs = &params.gas_costs;
+        let mut total: u64 = 0;
+        for si in ctx.tx_auth_info().signers() {
+            let cost = match &si.address_spec {
+                AddressSpec::Signature(spec)
+                | AddressSpec::SessionKey(transaction::SessionKeySpec { key: spec, .. })
+                | AddressSpec::Rofl(transaction::RoflOriginSpec { key: spec, .. }) => {
+                    gas_costs.signature_cost(&spec.public_key(), gas_costs.auth_signature)
                 }
-                AddressSpec::Internal(_) => {}
-            }
+                AddressSpec::WebAuthn(_) => gas_costs.auth_signature,
+                AddressSpec::Multisig(config) => config
+                    .signers
+                    .iter()
+                    .try_fold(0u64, |acc, signer| {
+                        acc.checked_add(
+                            gas_costs
+                                .signature_cost(&signer.public_key, gas_costs.auth_multisig_signer),
+                        )
+                    })
+                    .ok_or(Error::GasOverflow)?,
+                AddressSpec::Internal(_) => 0,
+            };
+            total = total.checked_add(cost).ok_or(Error::GasOverflow)?;
         }
-        let total = (|| {
-            let signature_cost = num_signature.checked_mul(params.gas_costs.auth_signature)?;
-            let multisig_signer_cost =
-                num_multisig_signer.checked_mul(params.gas_costs.auth_multisig_signer)?;
-            let sum = signature_cost.checked_add(multisig_signer_cost)?;
-            Some(sum)
-        })()
-        .ok_or(Error::GasOverflow)?;
         Self::use_tx_gas(ctx, total)?;
 
         // Charge gas for callformat.
@@ -1073,6 +2315,39 @@ impl<Cfg: Config> module::TransactionHandler for Module<Cfg> {
             CallFormat::EncryptedX25519DeoxysII => {
                 Self::use_tx_gas(ctx, params.gas_costs.callformat_x25519_deoxysii)?
             }
+            CallFormat::EncryptedX25519XChaCha20Poly1305 => {
+                Self::use_tx_gas(ctx, params.gas_costs.callformat_x25519_xchacha20poly1305)?
+            }
+        }
+
+        // Charge gas for the memo and include it in the emitted events unless the call is
+        // confidential.
+        if let Some(memo) = &call.memo {
+            if memo.len() > transaction::MAX_MEMO_SIZE {
+                return Err(Error::OversizedMemo);
+            }
+            Self::use_tx_gas(
+                ctx,
+                params
+                    .gas_costs
+                    .tx_memo_byte
+                    .checked_mul(memo.len() as u64)
+                    .ok_or(Error::GasOverflow)?,
+            )?;
+            if call.format == CallFormat::Plain {
+                ctx.emit_event(Event::Memo { memo: memo.clone() });
+            }
+        }
+
+        // Track storage accesses made by the call in a new scope so that they can be charged for
+        // as they happen. Accesses of nested calls accumulate into the scope of the parent call.
+        if params.gas_costs.storage_cold_access > 0 || params.gas_costs.storage_warm_access > 0 {
+            ctx.tx_value::<(u64, u64)>(CONTEXT_KEY_STORAGE_ACCESS_COSTS)
+                .set((
+                    params.gas_costs.storage_cold_access,
+                    params.gas_costs.storage_warm_access,
+                ));
+            CurrentStore::start_access_tracking();
         }
 
         Ok(())
@@ -1082,6 +2357,32 @@ impl<Cfg: Config> module::TransactionHandler for Module<Cfg> {
         ctx: &mut C,
         result: module::CallResult,
     ) -> Result<module::CallResult, Error> {
+        // Charge for any remaining storage accesses made by the call and close its tracking scope.
+        let result = match Self::charge_storage_access_gas(ctx) {
+            Ok(()) => result,
+            Err(err) => err.into_call_result(),
+        };
+        if ctx
+            .tx_value::<(u64, u64)>(CONTEXT_KEY_STORAGE_ACCESS_COSTS)
+            .take()
+            .is_some()
+        {
+            CurrentStore::stop_access_tracking();
+        }
+
+        // Apply any gas refunds (if this is not an internally generated call). Refunds recorded by
+        // internal calls are propagated to the parent call by `subcall::call`. Refunds only apply
+        // when the state changes are kept, so they are discarded for failed calls.
+        if !ctx.is_internal() {
+            if result.is_success() {
+                Self::refund_storage_gas(ctx);
+                Self::apply_tx_gas_refund(ctx);
+            } else {
+                ctx.tx_value::<u64>(CONTEXT_KEY_GAS_REFUND).take();
+                ctx.tx_value::<u64>(CONTEXT_KEY_STORAGE_GAS).take();
+            }
+        }
+
         // Emit gas used event (if this is not an internally generated call).
         if Cfg::EMIT_GAS_USED_EVENTS && !ctx.is_internal() {
             let used_gas = Self::used_tx_gas(ctx);
@@ -1146,6 +2447,9 @@ impl<Cfg: Config> module::BlockHandler for Module<Cfg> {
     }
 
     fn end_block<C: Context>(ctx: &mut C) {
+        Self::prune_receipts(ctx);
+        Self::prune_event_indices(ctx);
+
         let params = Self::params();
         if !params.dynamic_min_gas_price.enabled {
             return;
@@ -1194,3 +2498,12 @@ impl<Cfg: Config> module::BlockHandler for Module<Cfg> {
 }
 
 impl<Cfg: Config> module::InvariantHandler for Module<Cfg> {}
+
+impl<Cfg: Config> module::StateExporter for Module<Cfg> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
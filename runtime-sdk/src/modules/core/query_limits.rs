@@ -0,0 +1,274 @@
+//! Node-local resource limits of query execution.
+//!
+//! Queries do not use gas, so the dispatcher enforces the configured limits uniformly for all
+//! queries independent of any checks performed by the queried module. Limits are never applied
+//! during transaction execution so they have no effect on consensus and different nodes may use
+//! different limits.
+//!
+//! Storage reads are accounted for by the `LimitedStore` the query is executed over, while the
+//! execution time is checked both on storage reads and whenever gas is used. Once any of the
+//! resources is exhausted, further storage reads return no data and using gas fails so that the
+//! query stops as soon as possible. The dispatcher then reports `Error::QueryResourcesExhausted`
+//! instead of the query result.
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use oasis_core_runtime::storage::mkvs;
+
+use crate::storage::{Prefix, Store};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Budget>> = RefCell::new(None);
+}
+
+/// Resource limits of a single query.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub struct Limits {
+    /// Maximum number of storage reads (key lookups and iterator steps) that a single query can
+    /// perform. The special value of 0 means that storage reads are not limited.
+    #[cbor(optional)]
+    pub max_storage_reads: u64,
+
+    /// Maximum execution time of a single query in milliseconds. The special value of 0 means
+    /// that execution time is not limited.
+    ///
+    /// Execution time is checked whenever the query reads storage or uses gas.
+    #[cbor(optional)]
+    pub max_execution_time_ms: u64,
+}
+
+impl Limits {
+    /// Whether these limits disable all checks.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_storage_reads == 0 && self.max_execution_time_ms == 0
+    }
+}
+
+/// Query limits configuration that can be provided by the node operator.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct LocalConfig {
+    /// Limits applied to all queries without an explicit override.
+    #[cbor(optional)]
+    pub limits: Limits,
+
+    /// Per-method overrides, keyed by query method name.
+    #[cbor(optional)]
+    pub overrides: BTreeMap<String, Limits>,
+}
+
+impl LocalConfig {
+    /// Resolve the limits for the given query method.
+    pub fn limits_for(&self, method: &str) -> Limits {
+        self.overrides.get(method).unwrap_or(&self.limits).clone()
+    }
+}
+
+/// A query resource that can be exhausted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    StorageReads,
+    ExecutionTime,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StorageReads => write!(f, "storage reads"),
+            Self::ExecutionTime => write!(f, "execution time"),
+        }
+    }
+}
+
+/// Resource usage of a single query.
+struct Budget {
+    limits: Limits,
+    reads: Cell<u64>,
+    deadline: Option<Instant>,
+    exhausted: Cell<Option<Resource>>,
+}
+
+impl Budget {
+    fn new(limits: Limits) -> Self {
+        let deadline = (limits.max_execution_time_ms > 0)
+            .then(|| Instant::now() + Duration::from_millis(limits.max_execution_time_ms));
+        Self {
+            limits,
+            reads: Cell::new(0),
+            deadline,
+            exhausted: Cell::new(None),
+        }
+    }
+
+    /// Check whether any of the resources has been exhausted.
+    fn check(&self) -> Result<(), Resource> {
+        if let Some(resource) = self.exhausted.get() {
+            return Err(resource);
+        }
+        if matches!(self.deadline, Some(deadline) if Instant::now() > deadline) {
+            return self.exhaust(Resource::ExecutionTime);
+        }
+        Ok(())
+    }
+
+    /// Record a storage read.
+    fn read(&self) -> Result<(), Resource> {
+        self.check()?;
+
+        let reads = self.reads.get().saturating_add(1);
+        self.reads.set(reads);
+        if self.limits.max_storage_reads > 0 && reads > self.limits.max_storage_reads {
+            return self.exhaust(Resource::StorageReads);
+        }
+        Ok(())
+    }
+
+    fn exhaust(&self, resource: Resource) -> Result<(), Resource> {
+        self.exhausted.set(Some(resource));
+        Err(resource)
+    }
+}
+
+/// Guard restoring the previously active budget.
+struct BudgetGuard(Option<Budget>);
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+    }
+}
+
+/// Run a query with the given limits enforced.
+///
+/// Returns the exhausted resource in case the query exceeded any of its limits, in which case
+/// the query result is not meaningful and must be discarded.
+pub fn with_limits<F, R>(limits: Limits, f: F) -> Result<R, Resource>
+where
+    F: FnOnce() -> R,
+{
+    if limits.is_unlimited() {
+        return Ok(f());
+    }
+
+    let _guard = BudgetGuard(CURRENT.with(|c| c.replace(Some(Budget::new(limits)))));
+    let result = f();
+    check()?;
+    Ok(result)
+}
+
+/// Check whether the currently executing query has exhausted any of its resources.
+///
+/// Outside of queries with limits this always succeeds.
+pub fn check() -> Result<(), Resource> {
+    with_budget(|budget| budget.check())
+}
+
+/// Record a storage read made by the currently executing query.
+fn read() -> Result<(), Resource> {
+    with_budget(|budget| budget.read())
+}
+
+fn with_budget<F>(f: F) -> Result<(), Resource>
+where
+    F: FnOnce(&Budget) -> Result<(), Resource>,
+{
+    CURRENT.with(|c| c.borrow().as_ref().map_or(Ok(()), f))
+}
+
+/// A store that accounts its reads towards the resource limits of the currently executing query.
+///
+/// Once the query has exhausted any of its resources, reads return no data. Only reads are
+/// accounted for as any writes made by queries are always discarded.
+pub struct LimitedStore<S: Store> {
+    inner: S,
+}
+
+impl<S: Store> LimitedStore<S> {
+    /// Create a new store accounting reads towards the query limits.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: Store> Store for LimitedStore<S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        read().ok()?;
+        self.inner.get(key)
+    }
+
+    fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.inner.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.inner.remove(key)
+    }
+
+    fn iter(&self) -> Box<dyn mkvs::Iterator + '_> {
+        Box::new(LimitedIterator {
+            inner: self.inner.iter(),
+        })
+    }
+
+    fn prefetch_prefixes(&mut self, prefixes: Vec<Prefix>, limit: u16) {
+        self.inner.prefetch_prefixes(prefixes, limit)
+    }
+}
+
+/// An iterator that accounts each step as a storage read.
+struct LimitedIterator<'a> {
+    inner: Box<dyn mkvs::Iterator + 'a>,
+}
+
+impl<'a> Iterator for LimitedIterator<'a> {
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read().ok()?;
+        Iterator::next(&mut *self.inner)
+    }
+}
+
+impl<'a> mkvs::Iterator for LimitedIterator<'a> {
+    fn set_prefetch(&mut self, prefetch: usize) {
+        self.inner.set_prefetch(prefetch)
+    }
+
+    fn is_valid(&self) -> bool {
+        check().is_ok() && self.inner.is_valid()
+    }
+
+    fn error(&self) -> &Option<anyhow::Error> {
+        self.inner.error()
+    }
+
+    fn rewind(&mut self) {
+        if read().is_ok() {
+            self.inner.rewind()
+        }
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        if read().is_ok() {
+            self.inner.seek(key)
+        }
+    }
+
+    fn get_key(&self) -> &Option<mkvs::Key> {
+        self.inner.get_key()
+    }
+
+    fn get_value(&self) -> &Option<Vec<u8>> {
+        self.inner.get_value()
+    }
+
+    fn next(&mut self) {
+        if read().is_ok() {
+            mkvs::Iterator::next(&mut *self.inner)
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Node-local per-signer rate limiting of transaction checks.
+//!
+//! The rate limiter is only applied while checking transactions for inclusion in the local
+//! transaction pool. It is never consulted during execution so it has no effect on consensus and
+//! different nodes may use different limits.
+//!
+//! Transactions are re-checked while they remain in the local transaction pool, so only the first
+//! check of each transaction consumes a token.
+use std::{collections::BTreeMap, num::NonZeroUsize, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{core::common::crypto::hash::Hash, metrics, types::address::Address};
+
+/// Default maximum number of accounts for which rate limiting state is tracked.
+pub const DEFAULT_MAX_TRACKED_ACCOUNTS: usize = 65_536;
+
+/// Token bucket configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub struct BucketConfig {
+    /// Maximum number of transactions that a single signer can submit in a burst. The special
+    /// value of 0 means that the signer is not rate limited.
+    #[cbor(optional)]
+    pub capacity: u64,
+
+    /// Number of transactions that are added back to the bucket for each elapsed round.
+    #[cbor(optional)]
+    pub refill_per_round: u64,
+}
+
+impl BucketConfig {
+    /// Whether this configuration disables rate limiting.
+    pub fn is_unlimited(&self) -> bool {
+        self.capacity == 0
+    }
+}
+
+/// Rate limiter configuration that can be provided by the node operator.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct LocalConfig {
+    /// Limits applied to all signers without an explicit override.
+    #[cbor(optional)]
+    pub limit: BucketConfig,
+
+    /// Per-signer overrides, keyed by Bech32-encoded signer address.
+    #[cbor(optional)]
+    pub overrides: BTreeMap<String, BucketConfig>,
+
+    /// Maximum number of accounts for which state is tracked. When more accounts are seen, the
+    /// least recently used ones are forgotten. The same number of admitted transactions is
+    /// remembered for not charging their re-checks. The special value of 0 means that the default
+    /// of `DEFAULT_MAX_TRACKED_ACCOUNTS` is used.
+    #[cbor(optional)]
+    pub max_tracked_accounts: u64,
+}
+
+impl LocalConfig {
+    /// Resolve the bucket configuration for the given signer address.
+    pub fn bucket_for(&self, address: &Address) -> BucketConfig {
+        if !self.overrides.is_empty() {
+            let encoded = address.to_bech32();
+            if let Some(cfg) = self.overrides.get(&encoded) {
+                return cfg.clone();
+            }
+        }
+        self.limit.clone()
+    }
+
+    /// Whether the configuration can limit any signer.
+    pub fn is_enabled(&self) -> bool {
+        !self.limit.is_unlimited() || self.overrides.values().any(|cfg| !cfg.is_unlimited())
+    }
+
+    fn max_tracked_accounts(&self) -> NonZeroUsize {
+        usize::try_from(self.max_tracked_accounts)
+            .ok()
+            .and_then(NonZeroUsize::new)
+            .unwrap_or_else(|| NonZeroUsize::new(DEFAULT_MAX_TRACKED_ACCOUNTS).unwrap())
+    }
+}
+
+/// Rate limiter metrics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of transaction checks that were allowed by the rate limiter.
+    pub allowed: u64,
+    /// Number of transaction checks that were rejected by the rate limiter.
+    pub limited: u64,
+    /// Number of re-checks of previously admitted transactions, which are not rate limited.
+    pub rechecked: u64,
+    /// Number of accounts currently tracked by the rate limiter.
+    pub tracked_accounts: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    tokens: u64,
+    last_round: u64,
+}
+
+/// A token bucket rate limiter keyed by signer address.
+pub struct RateLimiter {
+    buckets: lru::LruCache<Address, Bucket>,
+    admitted: lru::LruCache<Hash, ()>,
+    allowed: u64,
+    limited: u64,
+    rechecked: u64,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter tracking at most the given number of accounts.
+    pub fn new(max_tracked_accounts: NonZeroUsize) -> Self {
+        Self {
+            buckets: lru::LruCache::new(max_tracked_accounts),
+            admitted: lru::LruCache::new(max_tracked_accounts),
+            allowed: 0,
+            limited: 0,
+            rechecked: 0,
+        }
+    }
+
+    fn resize(&mut self, max_tracked_accounts: NonZeroUsize) {
+        if self.buckets.cap() != max_tracked_accounts {
+            self.buckets.resize(max_tracked_accounts);
+            self.admitted.resize(max_tracked_accounts);
+        }
+    }
+
+    /// Attempt to consume a single token from the bucket of the given signer at the given round
+    /// for checking the transaction with the given hash. Re-checks of transactions that have
+    /// already been allowed do not consume any tokens.
+    ///
+    /// Returns `true` in case the signer is allowed to proceed.
+    pub fn check(
+        &mut self,
+        address: Address,
+        tx_hash: Hash,
+        round: u64,
+        cfg: &BucketConfig,
+    ) -> bool {
+        if cfg.is_unlimited() {
+            self.allowed = self.allowed.saturating_add(1);
+            return true;
+        }
+        if self.admitted.get(&tx_hash).is_some() {
+            self.rechecked = self.rechecked.saturating_add(1);
+            return true;
+        }
+
+        let bucket = match self.buckets.get_mut(&address) {
+            Some(bucket) => bucket,
+            None => {
+                self.buckets.put(
+                    address,
+                    Bucket {
+                        tokens: cfg.capacity,
+                        last_round: round,
+                    },
+                );
+                self.buckets.get_mut(&address).unwrap()
+            }
+        };
+
+        // Refill the bucket based on the number of rounds that have elapsed.
+        let elapsed = round.saturating_sub(bucket.last_round);
+        if elapsed > 0 {
+            bucket.tokens = bucket
+                .tokens
+                .saturating_add(elapsed.saturating_mul(cfg.refill_per_round))
+                .min(cfg.capacity);
+            bucket.last_round = round;
+        }
+        // Make sure that a lowered capacity takes effect immediately.
+        bucket.tokens = bucket.tokens.min(cfg.capacity);
+
+        if bucket.tokens == 0 {
+            self.limited = self.limited.saturating_add(1);
+            return false;
+        }
+        bucket.tokens -= 1;
+        self.admitted.put(tx_hash, ());
+        self.allowed = self.allowed.saturating_add(1);
+        true
+    }
+
+    /// Forget all state for the given signer.
+    pub fn reset(&mut self, address: &Address) {
+        self.buckets.pop(address);
+    }
+
+    /// Current rate limiter metrics.
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            allowed: self.allowed,
+            limited: self.limited,
+            rechecked: self.rechecked,
+            tracked_accounts: self.buckets.len() as u64,
+        }
+    }
+}
+
+static RATE_LIMITER: Lazy<Mutex<RateLimiter>> = Lazy::new(|| {
+    Mutex::new(RateLimiter::new(
+        NonZeroUsize::new(DEFAULT_MAX_TRACKED_ACCOUNTS).unwrap(),
+    ))
+});
+
+/// Attempt to consume a single token from the node-wide bucket of the given signer for checking
+/// the transaction with the given hash. The updated metrics are recorded in the node-wide metrics
+/// sink (if any).
+///
+/// Returns `true` in case the signer is allowed to proceed.
+pub fn check(address: Address, tx_hash: Hash, round: u64, cfg: &LocalConfig) -> bool {
+    let mut limiter = RATE_LIMITER.lock().unwrap();
+    limiter.resize(cfg.max_tracked_accounts());
+    let allowed = limiter.check(address, tx_hash, round, &cfg.bucket_for(&address));
+    if metrics::is_enabled() {
+        metrics::record_rate_limit(&limiter.metrics());
+    }
+    allowed
+}
+
+/// Current node-wide rate limiter metrics.
+pub fn metrics() -> Metrics {
+    RATE_LIMITER.lock().unwrap().metrics()
+}
@@ -0,0 +1,173 @@
+//! Storage deposits.
+//!
+//! Runtimes can require a per-byte deposit in the native denomination for state created on behalf
+//! of an owner. Modules (or contracts) call `charge` when they grow state owned by an account and
+//! `refund` when the state is removed, as done by the escrow module for escrowed payments and by
+//! the names module for reverse records. Deposits are escrowed in a dedicated account and tracked
+//! per owner so that refunds return exactly what has been paid, even if the per-byte deposit is
+//! changed in the meantime.
+use oasis_runtime_sdk_macros::Schema;
+use once_cell::sync::Lazy;
+
+use crate::{
+    context::Context,
+    modules::{accounts, core::API as _},
+    runtime::Runtime,
+    storage::{self, CurrentStore},
+    types::{address::Address, token},
+};
+
+/// Address of the account holding all escrowed storage deposits.
+pub static ADDRESS_STORAGE_DEPOSIT_ESCROW: Lazy<Address> =
+    Lazy::new(|| Address::from_module(super::MODULE_NAME, "storage-deposit-escrow"));
+
+/// Storage deposit parameters.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Parameters {
+    /// Amount of native base units escrowed for each byte of state. The special value of 0 means
+    /// that storage deposits are disabled.
+    #[cbor(optional)]
+    pub deposit_per_byte: u128,
+}
+
+/// Storage deposit of an owner.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Deposit {
+    /// Number of bytes of state covered by the deposit.
+    pub bytes: u64,
+    /// Escrowed amount in native base units.
+    pub amount: u128,
+}
+
+/// Fetch the storage deposit of the given owner.
+pub fn get_deposit(owner: Address) -> Deposit {
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let deposits = storage::StoreMap::<_, Address, Deposit>::new(storage::PrefixStore::new(
+            store,
+            &super::state::STORAGE_DEPOSITS,
+        ));
+        deposits.get(&owner).unwrap_or_default()
+    })
+}
+
+fn set_deposit(owner: Address, deposit: Deposit) {
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let mut deposits = storage::StoreMap::<_, Address, Deposit>::new(
+            storage::PrefixStore::new(store, &super::state::STORAGE_DEPOSITS),
+        );
+        if deposit.bytes == 0 {
+            deposits.remove(&owner);
+        } else {
+            deposits.insert(&owner, deposit);
+        }
+    })
+}
+
+/// Charge the storage deposit for `bytes` bytes of new state owned by `owner`, escrowing it from
+/// the owner's balance.
+///
+/// Returns the escrowed amount. Nothing is charged or tracked while deposits are disabled.
+pub fn charge<C: Context, A: accounts::API>(
+    ctx: &mut C,
+    owner: Address,
+    bytes: u64,
+) -> Result<u128, accounts::Error> {
+    let deposit_per_byte =
+        <C::Runtime as Runtime>::Core::storage_deposit_parameters().deposit_per_byte;
+    if deposit_per_byte == 0 || bytes == 0 {
+        return Ok(0);
+    }
+
+    let amount = deposit_per_byte
+        .checked_mul(bytes.into())
+        .ok_or(accounts::Error::InvalidArgument)?;
+    A::transfer(
+        ctx,
+        owner,
+        *ADDRESS_STORAGE_DEPOSIT_ESCROW,
+        &token::BaseUnits::new(amount, token::Denomination::NATIVE),
+    )?;
+
+    let mut deposit = get_deposit(owner);
+    deposit.bytes = deposit
+        .bytes
+        .checked_add(bytes)
+        .ok_or(accounts::Error::InvalidArgument)?;
+    deposit.amount = deposit
+        .amount
+        .checked_add(amount)
+        .ok_or(accounts::Error::InvalidArgument)?;
+    set_deposit(owner, deposit);
+
+    Ok(amount)
+}
+
+/// Refund the storage deposit for `bytes` bytes of removed state owned by `owner`.
+///
+/// The refund is proportional to the share of the owner's tracked bytes being removed. Removing
+/// more bytes than tracked refunds the whole deposit. Returns the refunded amount.
+pub fn refund<C: Context, A: accounts::API>(
+    ctx: &mut C,
+    owner: Address,
+    bytes: u64,
+) -> Result<u128, accounts::Error> {
+    let mut deposit = get_deposit(owner);
+    if deposit.bytes == 0 || bytes == 0 {
+        return Ok(0);
+    }
+
+    // Refund the share of the escrowed amount corresponding to the removed bytes, releasing any
+    // rounding remainder together with the last byte.
+    let bytes = bytes.min(deposit.bytes);
+    let amount = if bytes == deposit.bytes {
+        deposit.amount
+    } else {
+        deposit
+            .amount
+            .checked_mul(bytes.into())
+            .ok_or(accounts::Error::InvalidArgument)?
+            / u128::from(deposit.bytes)
+    };
+    if amount > 0 {
+        A::transfer(
+            ctx,
+            *ADDRESS_STORAGE_DEPOSIT_ESCROW,
+            owner,
+            &token::BaseUnits::new(amount, token::Denomination::NATIVE),
+        )?;
+    }
+
+    deposit.bytes -= bytes;
+    deposit.amount -= amount;
+    set_deposit(owner, deposit);
+
+    Ok(amount)
+}
+
+/// Check that the escrow account holds at least the sum of all tracked deposits.
+///
+/// Anyone can transfer additional funds to the escrow account, so it may hold more than what is
+/// tracked.
+pub fn check_invariants<A: accounts::API>() -> Result<(), super::Error> {
+    let total = CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let deposits = storage::StoreMap::<_, Address, Deposit>::new(storage::PrefixStore::new(
+            store,
+            &super::state::STORAGE_DEPOSITS,
+        ));
+        deposits.iter().try_fold(0u128, |total, (_, deposit)| {
+            total.checked_add(deposit.amount)
+        })
+    })
+    .ok_or_else(|| super::Error::InvariantViolation("storage deposit overflow".to_owned()))?;
+    let escrowed = A::get_balance(*ADDRESS_STORAGE_DEPOSIT_ESCROW, token::Denomination::NATIVE)
+        .map_err(|err| super::Error::InvariantViolation(err.to_string()))?;
+    if escrowed < total {
+        return Err(super::Error::InvariantViolation(format!(
+            "escrowed storage deposits ({escrowed}) do not cover tracked deposits ({total})"
+        )));
+    }
+    Ok(())
+}
@@ -1,21 +1,39 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use once_cell::unsync::Lazy;
 
 use crate::{
     context::{BatchContext, Context, Mode, TxContext},
-    core::common::version::Version,
-    crypto::multisig,
+    core::common::{crypto::hash::Hash, version::Version},
+    crypto::{
+        multisig,
+        signature::{self, PublicKey, SignatureType},
+    },
     error::Error,
     event::IntoTags,
     handler,
-    module::{self, BlockHandler, Module as _, TransactionHandler as _},
-    modules::core::min_gas_price_update,
+    module::{
+        self, BlockHandler, MethodHandler as _, Module as _, ModuleInfoHandler as _,
+        TransactionHandler as _,
+    },
+    modules::{
+        accounts::{self, API as _},
+        core::min_gas_price_update,
+    },
     runtime::Runtime,
+    schema::{TypeKind, TypeSchema},
     sdk_derive,
     sender::SenderMeta,
+    storage::{self, current::TransactionResult, CurrentStore, Store as _},
     testing::{configmap, keys, mock},
-    types::{address::Address, token, transaction, transaction::CallerAddress},
+    types::{
+        address::{Address, SignatureAddressSpec},
+        token, transaction,
+        transaction::CallerAddress,
+    },
 };
 
 use super::{types, Event, Parameters, API as _};
@@ -40,6 +58,10 @@ fn test_use_gas() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     assert_eq!(Core::max_batch_gas(&mut ctx), BLOCK_MAX_GAS);
@@ -121,6 +143,754 @@ fn test_use_gas() {
     });
 }
 
+#[test]
+fn test_gas_refund() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Core::set_params(Parameters {
+        max_batch_gas: u64::MAX,
+        ..Default::default()
+    });
+
+    let mut tx = mock::transaction();
+    tx.auth_info.fee.gas = 1_000;
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, _call| {
+        Core::use_tx_gas(&mut tx_ctx, 500).unwrap();
+        Core::refund_tx_gas(&mut tx_ctx, 50);
+        Core::refund_tx_gas(&mut tx_ctx, 20);
+        assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 70);
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            500,
+            "refund should only be applied after the call"
+        );
+
+        Core::after_handle_call(
+            &mut tx_ctx,
+            module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+        )
+        .unwrap();
+        assert_eq!(Core::used_tx_gas(&mut tx_ctx), 430);
+        assert_eq!(Core::used_batch_gas(&mut tx_ctx), 430);
+        assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 0);
+    });
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, _call| {
+        Core::use_tx_gas(&mut tx_ctx, 500).unwrap();
+        Core::refund_tx_gas(&mut tx_ctx, 1_000);
+
+        Core::after_handle_call(
+            &mut tx_ctx,
+            module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+        )
+        .unwrap();
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            400,
+            "refund should be limited to a fraction of used gas"
+        );
+    });
+
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Core::use_tx_gas(&mut tx_ctx, 500).unwrap();
+        Core::refund_tx_gas(&mut tx_ctx, 50);
+
+        Core::after_handle_call(
+            &mut tx_ctx,
+            module::CallResult::Failed {
+                module: "test".to_owned(),
+                code: 1,
+                message: "failed".to_owned(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            500,
+            "refund should not be applied to failed calls"
+        );
+        assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 0);
+    });
+}
+
+#[test]
+fn test_storage_deposit() {
+    use super::storage_deposit::{self, Deposit, ADDRESS_STORAGE_DEPOSIT_ESCROW};
+
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    accounts::test::init_accounts(&mut ctx);
+    let set_deposit_per_byte = |deposit_per_byte| {
+        Core::set_params(Parameters {
+            max_batch_gas: u64::MAX,
+            storage_deposit: storage_deposit::Parameters { deposit_per_byte },
+            ..Default::default()
+        })
+    };
+    let balance =
+        |address| accounts::Module::get_balance(address, token::Denomination::NATIVE).unwrap();
+
+    ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+        // Nothing should be charged or tracked while deposits are disabled.
+        set_deposit_per_byte(0);
+        let charged = storage_deposit::charge::<_, accounts::Module>(
+            &mut tx_ctx,
+            keys::alice::address(),
+            100,
+        )
+        .unwrap();
+        assert_eq!(charged, 0);
+        assert_eq!(
+            storage_deposit::get_deposit(keys::alice::address()),
+            Deposit::default()
+        );
+
+        set_deposit_per_byte(10);
+        let charged = storage_deposit::charge::<_, accounts::Module>(
+            &mut tx_ctx,
+            keys::alice::address(),
+            100,
+        )
+        .unwrap();
+        assert_eq!(charged, 1_000);
+        assert_eq!(balance(keys::alice::address()), 999_000);
+        assert_eq!(balance(*ADDRESS_STORAGE_DEPOSIT_ESCROW), 1_000);
+
+        // Refunds should return what has been paid even after the deposit changes.
+        set_deposit_per_byte(20);
+        storage_deposit::charge::<_, accounts::Module>(&mut tx_ctx, keys::alice::address(), 50)
+            .unwrap();
+        assert_eq!(
+            Core::query_storage_deposit(
+                &mut tx_ctx,
+                types::StorageDepositQuery {
+                    owner: keys::alice::address(),
+                },
+            )
+            .unwrap(),
+            Deposit {
+                bytes: 150,
+                amount: 2_000,
+            }
+        );
+
+        let refunded =
+            storage_deposit::refund::<_, accounts::Module>(&mut tx_ctx, keys::alice::address(), 75)
+                .unwrap();
+        assert_eq!(refunded, 1_000);
+        assert_eq!(
+            storage_deposit::get_deposit(keys::alice::address()),
+            Deposit {
+                bytes: 75,
+                amount: 1_000,
+            }
+        );
+        storage_deposit::check_invariants::<accounts::Module>().expect("invariants should hold");
+
+        // Removing more bytes than tracked should refund the whole deposit.
+        let refunded = storage_deposit::refund::<_, accounts::Module>(
+            &mut tx_ctx,
+            keys::alice::address(),
+            1_000,
+        )
+        .unwrap();
+        assert_eq!(refunded, 1_000);
+        assert_eq!(balance(keys::alice::address()), 1_000_000);
+        assert_eq!(balance(*ADDRESS_STORAGE_DEPOSIT_ESCROW), 0);
+        assert_eq!(
+            storage_deposit::get_deposit(keys::alice::address()),
+            Deposit::default()
+        );
+
+        let result =
+            storage_deposit::charge::<_, accounts::Module>(&mut tx_ctx, keys::bob::address(), 1);
+        assert!(matches!(result, Err(accounts::Error::InsufficientBalance)));
+
+        // Anyone can send funds to the escrow account without violating the invariants.
+        accounts::Module::transfer(
+            &mut tx_ctx,
+            keys::alice::address(),
+            *ADDRESS_STORAGE_DEPOSIT_ESCROW,
+            &token::BaseUnits::new(10, token::Denomination::NATIVE),
+        )
+        .unwrap();
+        storage_deposit::check_invariants::<accounts::Module>().expect("invariants should hold");
+    });
+}
+
+#[test]
+fn test_storage_gas_refund() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    CurrentStore::with(|store| {
+        store.insert(b"removed", b"value");
+        store.insert(b"restored", b"value");
+        store.insert(b"rewritten", b"value");
+    });
+
+    let mut tx = mock::transaction();
+    tx.auth_info.fee.gas = 1_000;
+
+    for (cold_access, success, expected_gas, msg) in [
+        (
+            20,
+            true,
+            560,
+            "removing and restoring keys should be refunded",
+        ),
+        (20, false, 600, "failed calls should not be refunded"),
+        (
+            5,
+            true,
+            500,
+            "refunds should not exceed the charged storage gas",
+        ),
+        (
+            0,
+            true,
+            500,
+            "nothing should be refunded without storage gas",
+        ),
+    ] {
+        Core::set_params(Parameters {
+            max_batch_gas: u64::MAX,
+            gas_costs: super::GasCosts {
+                storage_remove_refund: 30,
+                storage_restore_refund: 10,
+                storage_cold_access: cold_access,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+            CurrentStore::with_transaction(|| {
+                Core::use_tx_gas(&mut tx_ctx, 500).unwrap();
+                Core::before_handle_call(&mut tx_ctx, &call).unwrap();
+                CurrentStore::with(|store| {
+                    store.remove(b"removed");
+                    store.insert(b"restored", b"other");
+                    store.insert(b"restored", b"value");
+                    store.insert(b"created", b"value");
+                    store.remove(b"missing");
+                    // Writing the current value is not a restoration.
+                    store.insert(b"rewritten", b"value");
+                });
+
+                let result = if success {
+                    module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue))
+                } else {
+                    super::Error::Forbidden.into_call_result()
+                };
+                Core::after_handle_call(&mut tx_ctx, result).unwrap();
+
+                TransactionResult::Rollback(())
+            });
+
+            assert_eq!(Core::used_tx_gas(&mut tx_ctx), expected_gas, "{msg}");
+        });
+    }
+}
+
+#[test]
+fn test_storage_access_gas() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Core::set_params(Parameters {
+        max_batch_gas: u64::MAX,
+        gas_costs: super::GasCosts {
+            storage_cold_access: 100,
+            storage_warm_access: 10,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    for (gas_limit, success) in [(1_000, true), (200, false)] {
+        let mut tx = mock::transaction();
+        tx.auth_info.fee.gas = gas_limit;
+
+        ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+            Core::before_handle_call(&mut tx_ctx, &call).unwrap();
+            CurrentStore::with(|store| {
+                store.get(b"key");
+                store.get(b"key");
+                store.insert(b"other", b"value");
+            });
+
+            let result = Core::after_handle_call(
+                &mut tx_ctx,
+                module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+            )
+            .unwrap();
+            assert_eq!(result.is_success(), success);
+            if success {
+                assert_eq!(
+                    Core::used_tx_gas(&mut tx_ctx),
+                    210,
+                    "two cold and one warm access should be charged"
+                );
+            } else {
+                assert!(matches!(
+                    result,
+                    module::CallResult::Failed { ref module, code, .. } if module == "core" && code == 12
+                ));
+            }
+        });
+    }
+
+    // Accesses should be charged as soon as gas is used so that they are bounded while the call
+    // is still running.
+    let mut tx = mock::transaction();
+    tx.auth_info.fee.gas = 1_000;
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).unwrap();
+        let used = Core::used_tx_gas(&mut tx_ctx);
+        CurrentStore::with(|store| store.get(b"key"));
+        Core::use_tx_gas(&mut tx_ctx, 0).unwrap();
+        assert_eq!(Core::used_tx_gas(&mut tx_ctx), used + 100);
+
+        // Accesses of nested calls accumulate into the parent call.
+        CurrentStore::start_access_tracking();
+        CurrentStore::with(|store| {
+            store.get(b"key");
+            store.get(b"other");
+        });
+        CurrentStore::stop_access_tracking();
+
+        let result = Core::after_handle_call(
+            &mut tx_ctx,
+            module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+        )
+        .unwrap();
+        assert!(result.is_success());
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            used + 210,
+            "accesses should only be charged once"
+        );
+    });
+
+    // Accesses should not be tracked outside of calls.
+    CurrentStore::with(|store| store.get(b"key"));
+    assert_eq!(CurrentStore::access_stats(), Default::default());
+}
+
+#[test]
+fn test_signature_gas_costs() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Core::set_params(Parameters {
+        max_batch_gas: u64::MAX,
+        gas_costs: super::GasCosts {
+            auth_signature: 1_000,
+            auth_multisig_signer: 100,
+            auth_signature_bls12381: 5_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let bls_pk = signature::MemorySigner::new_test(SignatureType::Bls12381, "bls").public_key();
+    let bls_sigspec = match bls_pk.clone() {
+        PublicKey::Bls12381(pk) => SignatureAddressSpec::Bls12381(pk),
+        _ => unreachable!(),
+    };
+    let schnorr_sigspec =
+        match signature::MemorySigner::new_test(SignatureType::Secp256k1_Bip340, "schnorr")
+            .public_key()
+        {
+            PublicKey::Secp256k1Schnorr(pk) => SignatureAddressSpec::Secp256k1Schnorr(pk),
+            _ => unreachable!(),
+        };
+
+    for (signer_info, expected_gas) in [
+        (
+            transaction::SignerInfo::new_sigspec(keys::alice::sigspec(), 0),
+            1_000,
+        ),
+        (transaction::SignerInfo::new_sigspec(bls_sigspec, 0), 5_000),
+        // Schemes without a dedicated cost are charged as regular signatures.
+        (
+            transaction::SignerInfo::new_sigspec(schnorr_sigspec, 0),
+            1_000,
+        ),
+        (
+            transaction::SignerInfo::new_multisig(
+                multisig::Config {
+                    signers: vec![
+                        multisig::Signer {
+                            public_key: keys::bob::pk(),
+                            weight: 1,
+                        },
+                        multisig::Signer {
+                            public_key: bls_pk.clone(),
+                            weight: 1,
+                        },
+                    ],
+                    threshold: 1,
+                },
+                0,
+            ),
+            5_100,
+        ),
+    ] {
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![signer_info];
+        tx.auth_info.fee.gas = 1_000_000;
+
+        ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+            Core::before_handle_call(&mut tx_ctx, &call).unwrap();
+            assert_eq!(Core::used_tx_gas(&mut tx_ctx), expected_gas);
+        });
+    }
+}
+
+#[test]
+fn test_state_stats() {
+    let mut mock = mock::Mock::default();
+    CurrentStore::with(|store| {
+        let mut store = storage::TypedStore::new(storage::PrefixStore::new(store, "core"));
+        store.insert(
+            super::state::METADATA,
+            types::Metadata {
+                versions: BTreeMap::from([
+                    ("".to_owned(), 1),
+                    ("consensus".to_owned(), 1),
+                    ("consensus_accounts".to_owned(), 1),
+                ]),
+            },
+        );
+    });
+
+    let changes = RefCell::new(storage::StateChanges::default());
+    let mut base = mock::empty_store();
+    base.insert(b"consensusremoved", b"value");
+    let mut store = storage::ChangeTrackingStore::new(&mut base, Some(&changes));
+    store.insert(b"consensus_accountskey", b"value");
+    store.insert(b"consensuskey", b"value");
+    store.insert(b"consensusother", b"value");
+    store.remove(b"consensusremoved");
+    store.insert(b"unknown", b"value");
+    drop(store);
+
+    Core::update_state_stats(&changes.into_inner());
+    assert_eq!(
+        Core::state_stats(),
+        BTreeMap::from([
+            (
+                "consensus".to_owned(),
+                types::StateStats { keys: 1, bytes: 15 }
+            ),
+            (
+                "consensus_accounts".to_owned(),
+                types::StateStats { keys: 1, bytes: 26 }
+            ),
+        ]),
+        "changes should be attributed to the module with the longest matching name"
+    );
+
+    let stats = Core::query_state_stats(&mut mock.create_ctx(), ()).unwrap();
+    assert_eq!(stats, Core::state_stats());
+}
+
+#[test]
+fn test_receipts() {
+    struct ReceiptsConfig;
+
+    impl super::Config for ReceiptsConfig {
+        const STORE_RECEIPTS: bool = true;
+        const RECEIPT_RETENTION_ROUNDS: u64 = 2;
+    }
+
+    type Core = super::Module<ReceiptsConfig>;
+
+    let mut mock = mock::Mock::default();
+    let receipt = types::Receipt {
+        success: true,
+        gas_used: 10,
+        events_root: types::merkle_root(&[Hash::digest_bytes(b"event")]),
+        messages_root: types::merkle_root(&[]),
+        ..Default::default()
+    };
+    for round in 1..=3 {
+        mock.runtime_header.round = round;
+        let mut ctx = mock.create_ctx();
+        Core::store_receipt(&mut ctx, 0, receipt.clone());
+        Core::end_block(&mut ctx);
+    }
+
+    // Receipts outside of the retention window are pruned.
+    assert_eq!(Core::receipt(1, 0), None);
+    assert!(Core::receipt(2, 0).is_some());
+    assert_eq!(Core::receipt(3, 1), None);
+
+    let mut ctx = mock.create_ctx();
+    let result = Core::query_receipt(&mut ctx, types::ReceiptQuery { round: 3, index: 0 })
+        .expect("receipt should exist");
+    assert_eq!(result, receipt);
+    assert_eq!(result.hash(), receipt.hash());
+    let result = Core::query_receipt(&mut ctx, types::ReceiptQuery { round: 1, index: 0 });
+    assert!(matches!(result, Err(super::Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_event_indices() {
+    struct IndexConfig;
+
+    impl super::Config for IndexConfig {
+        const EVENT_INDICES: Lazy<Vec<super::EventIndex>> = Lazy::new(|| {
+            vec![super::EventIndex {
+                module: accounts::MODULE_NAME,
+                code: 1,
+                field: "to",
+            }]
+        });
+        const EVENT_INDEX_RETENTION_ROUNDS: u64 = 2;
+    }
+
+    type Core = super::Module<IndexConfig>;
+
+    let transfer = |to: Address, amount: u128| {
+        cbor::to_value(accounts::Event::Transfer {
+            from: keys::alice::address(),
+            to,
+            amount: token::BaseUnits::new(amount, token::Denomination::NATIVE),
+        })
+    };
+    let query = |ctx: &mut crate::context::RuntimeBatchContext<'_, mock::EmptyRuntime>,
+                 to: Address,
+                 limit: u16,
+                 cursor: Option<Vec<u8>>| {
+        Core::query_indexed_events(
+            ctx,
+            types::IndexedEventsQuery {
+                module: accounts::MODULE_NAME.to_owned(),
+                code: 1,
+                field: "to".to_owned(),
+                value: cbor::to_value(to),
+                pagination: crate::types::pagination::PageRequest { cursor, limit },
+            },
+        )
+    };
+
+    let mut mock = mock::Mock::default();
+    for round in 1..=3 {
+        mock.runtime_header.round = round;
+        let mut ctx = mock.create_ctx();
+        let mut etags = BTreeMap::new();
+        etags.insert(
+            [accounts::MODULE_NAME.as_bytes(), &1u32.to_be_bytes()].concat(),
+            vec![
+                transfer(keys::bob::address(), round.into()),
+                transfer(keys::charlie::address(), 1),
+            ],
+        );
+        // Burns are not indexed.
+        etags.insert(
+            [accounts::MODULE_NAME.as_bytes(), &2u32.to_be_bytes()].concat(),
+            vec![cbor::to_value(accounts::Event::Burn {
+                owner: keys::bob::address(),
+                amount: token::BaseUnits::new(1, token::Denomination::NATIVE),
+            })],
+        );
+        Core::index_events(&mut ctx, 0, &etags.into_tags());
+        Core::end_block(&mut ctx);
+    }
+
+    // Events outside of the retention window are pruned.
+    let mut ctx = mock.create_ctx();
+    let page = query(&mut ctx, keys::bob::address(), 1, None).expect("query should succeed");
+    assert_eq!(
+        page.items,
+        vec![types::IndexedEvent {
+            round: 2,
+            tx_index: 0,
+            value: transfer(keys::bob::address(), 2),
+        }]
+    );
+    let page = query(&mut ctx, keys::bob::address(), 1, page.next).expect("query should succeed");
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].round, 3);
+    assert_eq!(page.next, None);
+
+    let page = query(&mut ctx, keys::charlie::address(), 0, None).expect("query should succeed");
+    assert_eq!(page.items.len(), 2);
+    let page = query(&mut ctx, keys::dave::address(), 0, None).expect("query should succeed");
+    assert!(page.items.is_empty());
+
+    // Only declared indices can be queried.
+    let result = Core::query_indexed_events(
+        &mut ctx,
+        types::IndexedEventsQuery {
+            module: accounts::MODULE_NAME.to_owned(),
+            code: 1,
+            field: "from".to_owned(),
+            value: cbor::to_value(keys::alice::address()),
+            pagination: Default::default(),
+        },
+    );
+    assert!(matches!(result, Err(super::Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_parameter_changes() {
+    struct HistoryConfig;
+
+    impl super::Config for HistoryConfig {
+        const PARAMETER_HISTORY_LIMIT: u64 = 2;
+    }
+
+    type Core = super::Module<HistoryConfig>;
+
+    let change = |old: u64, new: u64| types::ParameterChange {
+        key: "max_batch_gas".to_owned(),
+        old: Some(cbor::to_value(old)),
+        new: Some(cbor::to_value(new)),
+    };
+
+    let mut mock = mock::Mock::default();
+    for (round, max_batch_gas) in [(1, 100), (2, 100), (3, 200), (4, 300), (5, 400)] {
+        mock.runtime_header.round = round;
+        let mut ctx = mock.create_ctx();
+        Core::set_params(Parameters {
+            max_batch_gas,
+            ..Default::default()
+        });
+        Core::record_parameter_changes(&mut ctx, Core::module_params());
+
+        let tags = ctx.commit().events.into_tags();
+        match round {
+            // Initially set and unchanged parameters do not emit any events.
+            1 | 2 => assert!(tags.is_empty(), "no events should be emitted"),
+            _ => {
+                assert_eq!(tags.len(), 1, "parameter change event should be emitted");
+                assert_eq!(
+                    tags[0].key,
+                    ["core".as_bytes(), &3u32.to_be_bytes()].concat()
+                );
+                assert_eq!(
+                    tags[0].value,
+                    cbor::to_vec(vec![Event::ParametersChanged {
+                        module: "core".to_owned(),
+                        changes: vec![change(max_batch_gas - 100, max_batch_gas)],
+                    }])
+                );
+            }
+        }
+    }
+
+    // Only the changes are stored, not the parameters they replaced.
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let snapshots = storage::StoreMap::<_, String, cbor::Value>::new(
+            storage::PrefixStore::new(store, &super::state::PARAMETER_SNAPSHOTS),
+        );
+        assert!(
+            snapshots.iter().next().is_none(),
+            "replaced parameters should be removed"
+        );
+    });
+
+    // Only the most recent changes are retained.
+    let mut ctx = mock.create_ctx();
+    let query = |ctx: &mut crate::context::RuntimeBatchContext<'_, mock::EmptyRuntime>,
+                 from_round: u64,
+                 to_round: u64| {
+        Core::query_parameter_history(
+            ctx,
+            types::ParameterHistoryQuery {
+                module: "core".to_owned(),
+                from_round,
+                to_round,
+            },
+        )
+    };
+    let history = query(&mut ctx, 0, 0).expect("query should succeed");
+    assert_eq!(
+        history,
+        vec![
+            types::ParametersChange {
+                round: 4,
+                changes: vec![change(200, 300)],
+            },
+            types::ParametersChange {
+                round: 5,
+                changes: vec![change(300, 400)],
+            },
+        ]
+    );
+    let history = query(&mut ctx, 5, 0).expect("query should succeed");
+    assert_eq!(history.len(), 1);
+    let history = query(&mut ctx, 0, 4).expect("query should succeed");
+    assert_eq!(history.len(), 1);
+    let result = query(&mut ctx, 5, 4);
+    assert!(matches!(result, Err(super::Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_merkle_root() {
+    let leaves: Vec<_> = (0u8..3).map(|i| Hash::digest_bytes(&[i])).collect();
+    let leaf = |h: &Hash| Hash::digest_bytes_list(&[&[0x00], h.as_ref()]);
+    let node = |a: Hash, b: Hash| Hash::digest_bytes_list(&[&[0x01], a.as_ref(), b.as_ref()]);
+
+    assert_eq!(types::merkle_root(&[]), Hash::empty_hash());
+    assert_eq!(types::merkle_root(&leaves[..1]), leaf(&leaves[0]));
+    assert_eq!(
+        types::merkle_root(&leaves),
+        node(node(leaf(&leaves[0]), leaf(&leaves[1])), leaf(&leaves[2]))
+    );
+}
+
+#[test]
+fn test_block_resources() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Core::set_params(Parameters {
+        max_batch_gas: u64::MAX,
+        block_limits: super::BlockLimits {
+            max_storage_write_bytes: 100,
+            max_messages: 0,
+            max_events: 10,
+            max_block_worker_gas: 0,
+        },
+        ..Default::default()
+    });
+
+    let usage = super::BlockResources {
+        storage_write_bytes: 60,
+        messages: 1_000,
+        events: 5,
+    };
+    Core::use_block_resources(&mut ctx, usage).expect("using resources under limit should succeed");
+    assert_eq!(Core::used_block_resources(&mut ctx), usage);
+    assert!(!Core::block_resources_exhausted(&mut ctx));
+
+    let result = Core::use_block_resources(&mut ctx, usage);
+    assert!(matches!(result, Err(super::Error::BlockResourcesExhausted)));
+    assert_eq!(
+        Core::used_block_resources(&mut ctx),
+        usage,
+        "usage should not be recorded when limits are exceeded"
+    );
+
+    Core::use_block_resources(
+        &mut ctx,
+        super::BlockResources {
+            events: 5,
+            ..Default::default()
+        },
+    )
+    .expect("using resources up to the limit should succeed");
+    assert!(Core::block_resources_exhausted(&mut ctx));
+
+    // Limits should not be enforced during checks.
+    let mut check_ctx = mock.create_check_ctx();
+    Core::use_block_resources(&mut check_ctx, usage).unwrap();
+    Core::use_block_resources(&mut check_ctx, usage).unwrap();
+}
+
 #[test]
 fn test_query_min_gas_price() {
     let mut mock = mock::Mock::default();
@@ -138,6 +908,10 @@ fn test_query_min_gas_price() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     assert_eq!(
@@ -208,6 +982,7 @@ impl GasWasterModule {
     const METHOD_WASTE_GAS_CALLER: &'static str = "test.WasteGasCaller";
     const METHOD_SPECIFIC_GAS_REQUIRED: &'static str = "test.SpecificGasRequired";
     const METHOD_SPECIFIC_GAS_REQUIRED_HUGE: &'static str = "test.SpecificGasRequiredHuge";
+    const METHOD_INTRODUCED: &'static str = "test.Introduced";
 }
 
 #[sdk_derive(Module)]
@@ -297,6 +1072,14 @@ impl GasWasterModule {
             Ok(())
         }
     }
+
+    #[handler(call = Self::METHOD_INTRODUCED, introduced_in = 42)]
+    fn introduced<C: TxContext>(
+        _ctx: &mut C,
+        _args: (),
+    ) -> Result<(), <GasWasterModule as module::Module>::Error> {
+        Ok(())
+    }
 }
 
 impl module::BlockHandler for GasWasterModule {}
@@ -342,7 +1125,16 @@ impl Runtime for GasWasterRuntime {
                         tx_byte: 0,
                         auth_signature: Self::AUTH_SIGNATURE_GAS,
                         auth_multisig_signer: Self::AUTH_MULTISIG_GAS,
+                        auth_signature_bls12381: 0,
+                        auth_signature_secp256k1schnorr: 0,
+                        auth_signature_dilithium3: 0,
                         callformat_x25519_deoxysii: 0,
+                        callformat_x25519_xchacha20poly1305: 0,
+                        tx_memo_byte: 0,
+                        storage_remove_refund: 0,
+                        storage_restore_refund: 0,
+                        storage_cold_access: 0,
+                        storage_warm_access: 0,
                     },
                     min_gas_price: {
                         let mut mgp = BTreeMap::new();
@@ -350,7 +1142,12 @@ impl Runtime for GasWasterRuntime {
                         mgp
                     },
                     dynamic_min_gas_price: Default::default(),
+                    subcall: Default::default(),
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
             (),
         )
@@ -787,6 +1584,10 @@ fn test_approve_unverified_tx() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
     let dummy_bytes = b"you look, you die".to_vec();
     Core::approve_unverified_tx(
@@ -884,7 +1685,16 @@ fn test_min_gas_price() {
             tx_byte: 0,
             auth_signature: GasWasterRuntime::AUTH_SIGNATURE_GAS,
             auth_multisig_signer: GasWasterRuntime::AUTH_MULTISIG_GAS,
+            auth_signature_bls12381: 0,
+            auth_signature_secp256k1schnorr: 0,
+            auth_signature_dilithium3: 0,
             callformat_x25519_deoxysii: 0,
+            callformat_x25519_xchacha20poly1305: 0,
+            tx_memo_byte: 0,
+            storage_remove_refund: 0,
+            storage_restore_refund: 0,
+            storage_cold_access: 0,
+            storage_warm_access: 0,
         },
         min_gas_price: {
             let mut mgp = BTreeMap::new();
@@ -893,6 +1703,10 @@ fn test_min_gas_price() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     let mut tx = transaction::Transaction {
@@ -1066,6 +1880,10 @@ fn test_gas_used_events() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     let mut tx = mock::transaction();
@@ -1100,6 +1918,82 @@ fn test_gas_used_events() {
     assert_eq!(tags[0].value, expected, "expected events emitted");
 }
 
+#[test]
+fn test_memo() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Core::set_params(Parameters {
+        max_batch_gas: 1_000_000,
+        max_tx_size: 32 * 1024,
+        max_tx_signers: 8,
+        max_multisig_signers: 8,
+        gas_costs: super::GasCosts {
+            tx_memo_byte: 10,
+            storage_remove_refund: 0,
+            storage_restore_refund: 0,
+            storage_cold_access: 0,
+            storage_warm_access: 0,
+            ..Default::default()
+        },
+        min_gas_price: {
+            let mut mgp = BTreeMap::new();
+            mgp.insert(token::Denomination::NATIVE, 0);
+            mgp
+        },
+        dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
+    });
+
+    let mut tx = mock::transaction();
+    tx.call.memo = Some(b"invoice-42".to_vec());
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect("memo should be accepted");
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            100,
+            "memo should be priced per byte"
+        );
+
+        let state = tx_ctx.commit();
+        let tags = state.events.into_tags();
+        assert_eq!(tags.len(), 1, "1 emitted tag expected");
+
+        let expected = cbor::to_vec(vec![Event::Memo {
+            memo: b"invoice-42".to_vec(),
+        }]);
+        assert_eq!(tags[0].value, expected, "expected events emitted");
+    });
+
+    // Memos of encrypted calls should be charged for but not emitted.
+    let mut encrypted_tx = tx.clone();
+    encrypted_tx.call.format = transaction::CallFormat::EncryptedX25519DeoxysII;
+    ctx.with_tx(encrypted_tx.into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect("memo should be accepted");
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            100,
+            "memo should be priced per byte"
+        );
+
+        let state = tx_ctx.commit();
+        let tags = state.events.into_tags();
+        assert!(
+            tags.is_empty(),
+            "memo of encrypted calls should not be emitted"
+        );
+    });
+
+    tx.call.memo = Some(vec![0; transaction::MAX_MEMO_SIZE + 1]);
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        let result = Core::before_handle_call(&mut tx_ctx, &call);
+        assert!(matches!(result, Err(super::Error::OversizedMemo)));
+    });
+}
+
 /// Constructs a BTreeMap using a `btreemap! { key => value, ... }` syntax.
 macro_rules! btreemap {
     // allow trailing comma
@@ -1113,6 +2007,54 @@ macro_rules! btreemap {
     };
 }
 
+#[test]
+fn test_ephemeral_key_parameters() {
+    use module::Parameters as _;
+
+    // Defaults rotate keys every epoch and accept them for the maximum key age.
+    let params = super::EphemeralKeyParameters::default();
+    assert_eq!(params.rotation_interval(), 1);
+    assert_eq!(params.key_epoch(42), 42);
+    assert_eq!(params.key_valid_until(42), 47);
+
+    let params = super::EphemeralKeyParameters {
+        rotation_interval: 3,
+        grace_epochs: 1,
+    };
+    assert_eq!(params.key_epoch(42), 42);
+    assert_eq!(params.key_epoch(44), 42);
+    assert_eq!(params.key_epoch(45), 45);
+    assert_eq!(params.key_valid_until(42), 45);
+
+    // The default grace window shrinks with longer rotation intervals.
+    let params = super::EphemeralKeyParameters {
+        rotation_interval: 4,
+        grace_epochs: 0,
+    };
+    assert_eq!(params.grace_epochs(), 2);
+    assert_eq!(params.key_valid_until(40), 45);
+
+    let mut core_params = Parameters {
+        ephemeral_keys: params,
+        storage_deposit: Default::default(),
+        ..Default::default()
+    };
+    core_params
+        .validate_basic()
+        .expect("default grace window should be valid");
+    core_params.ephemeral_keys.grace_epochs = 3;
+    assert!(matches!(
+        core_params.validate_basic(),
+        Err(super::ParameterValidationError::InvalidEphemeralKeyValidity)
+    ));
+    core_params.ephemeral_keys.rotation_interval = u64::MAX;
+    core_params.ephemeral_keys.grace_epochs = u64::MAX;
+    assert!(matches!(
+        core_params.validate_basic(),
+        Err(super::ParameterValidationError::InvalidEphemeralKeyValidity)
+    ));
+}
+
 #[test]
 fn test_module_info() {
     use cbor::Encode;
@@ -1140,20 +2082,31 @@ fn test_module_info() {
                 "core" =>
                     types::ModuleInfo {
                         version: 1,
-                        params: core_params.into_cbor_value(),
+                        params: core_params.clone().into_cbor_value(),
+                        params_digest: Hash::digest_bytes(&cbor::to_vec(core_params)),
                         methods: vec![
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.EstimateGas".to_string() },
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.CheckInvariants".to_string() },
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.CallDataPublicKey".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.CallDataPublicKeyValidity".to_string() },
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.MinGasPrice".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.StorageDeposit".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.StateStats".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.Receipt".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.IndexedEvents".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.ParameterHistory".to_string() },
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.RuntimeInfo".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.ErrorCatalog".to_string() },
+                            MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.RuntimeMetadata".to_string() },
                             MethodHandlerInfo { kind: MethodHandlerKind::Query, name: "core.ExecuteReadOnlyTx".to_string() },
-                        ]
+                        ],
+                        features: BTreeMap::new(),
                     },
                 "gaswaster" =>
                     types::ModuleInfo {
                         version: 42,
                         params: ().into_cbor_value(),
+                        params_digest: Hash::digest_bytes(&cbor::to_vec(())),
                         methods: vec![
                             MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.WasteGas".to_string() },
                             MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.WasteGasAndFail".to_string() },
@@ -1162,13 +2115,151 @@ fn test_module_info() {
                             MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.WasteGasCaller".to_string() },
                             MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.SpecificGasRequired".to_string() },
                             MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.SpecificGasRequiredHuge".to_string() },
+                            MethodHandlerInfo { kind: types::MethodHandlerKind::Call, name: "test.Introduced".to_string() },
                         ],
+                        features: BTreeMap::new(),
                     },
-            }
+            },
+            confidential: false,
+            transaction_versions: vec![transaction::LATEST_TRANSACTION_VERSION],
         }
     );
 }
 
+#[test]
+fn test_error_catalog() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<GasWasterRuntime>(Mode::CheckTx, false);
+
+    // Both modules share the core module error type, which should only be included once.
+    let errors = Core::query_error_catalog(&mut ctx, ()).unwrap();
+    assert_eq!(errors, <super::Error as Error>::catalog());
+    assert!(errors.iter().all(|info| info.module == super::MODULE_NAME));
+    assert!(
+        errors.windows(2).all(|w| w[0].code < w[1].code),
+        "errors should be sorted by code"
+    );
+
+    let info = errors
+        .iter()
+        .find(|info| info.code == 12)
+        .expect("out of gas error should be included");
+    assert_eq!(info.name, "OutOfGas");
+    assert_eq!(info.message, "out of gas (limit: {0} wanted: {1})");
+}
+
+#[test]
+fn test_runtime_metadata() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<GasWasterRuntime>(Mode::CheckTx, false);
+
+    let metadata = Core::query_runtime_metadata(&mut ctx, ()).unwrap();
+    assert_eq!(
+        metadata.modules.keys().collect::<Vec<_>>(),
+        vec!["core", "gaswaster"]
+    );
+
+    let core = &metadata.modules["core"];
+    let estimate_gas = core
+        .methods
+        .iter()
+        .find(|m| m.name == "core.EstimateGas")
+        .expect("estimate gas query should be included");
+    assert_eq!(estimate_gas.kind, types::MethodHandlerKind::Query);
+    assert_eq!(
+        estimate_gas.args,
+        "oasis_runtime_sdk::modules::core::types::EstimateGasQuery"
+    );
+    assert_eq!(estimate_gas.result, "u64", "error type should be stripped");
+
+    assert_eq!(
+        core.events,
+        vec![
+            crate::event::EventInfo {
+                module: "core".to_string(),
+                code: 1,
+                name: "GasUsed".to_string(),
+                fields: vec![crate::event::EventFieldInfo {
+                    name: "amount".to_string(),
+                    ty: "u64".to_string(),
+                }],
+            },
+            crate::event::EventInfo {
+                module: "core".to_string(),
+                code: 2,
+                name: "Memo".to_string(),
+                fields: vec![crate::event::EventFieldInfo {
+                    name: "memo".to_string(),
+                    ty: "Vec<u8>".to_string(),
+                }],
+            },
+        ]
+    );
+
+    // Referenced types are described by the module's type schemas.
+    assert_eq!(core.types["u64"], TypeSchema::sized(TypeKind::Uint, 64));
+    assert_eq!(core.types["Vec<u8>"], TypeSchema::new(TypeKind::Bytes));
+    let query = &core.types[&estimate_gas.args];
+    assert_eq!(query.kind, TypeKind::Struct);
+    assert_eq!(
+        query
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["caller", "tx", "propagate_failures"]
+    );
+    for field in &query.fields {
+        assert!(
+            core.types.contains_key(&field.ty),
+            "field types should be registered"
+        );
+    }
+
+    // Modules without events report an empty event list.
+    assert!(metadata.modules["gaswaster"].events.is_empty());
+    assert_eq!(metadata.modules["gaswaster"].methods.len(), 8);
+    let introduced = metadata.modules["gaswaster"]
+        .methods
+        .iter()
+        .find(|m| m.name == GasWasterModule::METHOD_INTRODUCED)
+        .expect("introduced method should be included");
+    assert_eq!(introduced.introduced_in, Some(42));
+}
+
+#[test]
+fn test_introduced_in() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<GasWasterRuntime>(Mode::ExecuteTx, false);
+
+    let mut tx = mock::transaction();
+    tx.call.method = GasWasterModule::METHOD_INTRODUCED.to_owned();
+
+    // Methods should be rejected until the module state has reached the introducing version.
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        let result = GasWasterModule::dispatch_call(&mut tx_ctx, &call.method, call.body);
+        assert!(matches!(
+            result,
+            module::DispatchResult::Handled(module::CallResult::Failed { ref module, code, .. })
+                if module == "core" && code == 3
+        ));
+    });
+
+    GasWasterRuntime::migrate(&mut ctx);
+    assert_eq!(GasWasterModule::state_version(), 42);
+
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        let result = GasWasterModule::dispatch_call(&mut tx_ctx, &call.method, call.body);
+        assert!(
+            matches!(
+                result,
+                module::DispatchResult::Handled(module::CallResult::Ok(_))
+            ),
+            "methods should be accepted once the module state has been migrated"
+        );
+    });
+}
+
 #[test]
 fn test_min_gas_price_update() {
     let cases: Vec<(u128, u128, u128, u128, u128)> = vec![
@@ -1221,7 +2312,16 @@ fn test_dynamic_min_gas_price() {
             tx_byte: 0,
             auth_signature: GasWasterRuntime::AUTH_SIGNATURE_GAS,
             auth_multisig_signer: GasWasterRuntime::AUTH_MULTISIG_GAS,
+            auth_signature_bls12381: 0,
+            auth_signature_secp256k1schnorr: 0,
+            auth_signature_dilithium3: 0,
             callformat_x25519_deoxysii: 0,
+            callformat_x25519_xchacha20poly1305: 0,
+            tx_memo_byte: 0,
+            storage_remove_refund: 0,
+            storage_restore_refund: 0,
+            storage_cold_access: 0,
+            storage_warm_access: 0,
         },
         min_gas_price: {
             let mut mgp = BTreeMap::new();
@@ -1234,6 +2334,10 @@ fn test_dynamic_min_gas_price() {
             target_block_gas_usage_percentage: 50,
             min_price_max_change_denominator: 8,
         },
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     let tx = transaction::Transaction {
@@ -1311,3 +2415,235 @@ fn test_dynamic_min_gas_price() {
     );
     assert_eq!(Core::min_gas_price(&mut ctx, &denom), Some(100));
 }
+
+#[test]
+fn test_rate_limiter() {
+    use std::num::NonZeroUsize;
+
+    use super::rate_limit::{BucketConfig, Metrics, RateLimiter};
+
+    let cfg = BucketConfig {
+        capacity: 2,
+        refill_per_round: 1,
+    };
+    let mut limiter = RateLimiter::new(NonZeroUsize::new(1).unwrap());
+    let mut tx_hashes = (0u64..).map(|i| Hash::digest_bytes(&i.to_be_bytes()));
+    let mut check = |limiter: &mut RateLimiter, address, round, cfg: &BucketConfig| {
+        limiter.check(address, tx_hashes.next().unwrap(), round, cfg)
+    };
+
+    // Burst up to capacity.
+    assert!(check(&mut limiter, keys::alice::address(), 10, &cfg));
+    assert!(check(&mut limiter, keys::alice::address(), 10, &cfg));
+    assert!(!check(&mut limiter, keys::alice::address(), 10, &cfg));
+
+    // Tokens are refilled as rounds elapse, but never above capacity.
+    assert!(check(&mut limiter, keys::alice::address(), 11, &cfg));
+    assert!(!check(&mut limiter, keys::alice::address(), 11, &cfg));
+    assert!(check(&mut limiter, keys::alice::address(), 100, &cfg));
+    assert!(check(&mut limiter, keys::alice::address(), 100, &cfg));
+    assert!(!check(&mut limiter, keys::alice::address(), 100, &cfg));
+
+    // Unlimited configuration always passes.
+    assert!(check(
+        &mut limiter,
+        keys::alice::address(),
+        100,
+        &Default::default()
+    ));
+
+    // Least recently used accounts are evicted.
+    assert!(check(&mut limiter, keys::bob::address(), 100, &cfg));
+    assert!(check(&mut limiter, keys::alice::address(), 100, &cfg));
+
+    // Re-checks of the last allowed transaction do not consume tokens.
+    let tx_hash = Hash::digest_bytes(b"recheck");
+    assert!(limiter.check(keys::alice::address(), tx_hash, 100, &cfg));
+    assert!(!check(&mut limiter, keys::alice::address(), 100, &cfg));
+    assert!(limiter.check(keys::alice::address(), tx_hash, 100, &cfg));
+
+    assert_eq!(
+        limiter.metrics(),
+        Metrics {
+            allowed: 9,
+            limited: 4,
+            rechecked: 1,
+            tracked_accounts: 1,
+        }
+    );
+}
+
+#[test]
+fn test_rate_limit_check_tx() {
+    let local_config = configmap! {
+        "core" => configmap! {
+            "rate_limit" => configmap! {
+                "limit" => configmap! {
+                    "capacity" => 1,
+                    "refill_per_round" => 1,
+                },
+                "overrides" => configmap! {
+                    keys::bob::address().to_bech32() => configmap! {
+                        "capacity" => 0,
+                    },
+                },
+            },
+        },
+    };
+    let mut mock = mock::Mock::with_local_config(local_config);
+    mock.runtime_header.round = 1;
+
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::dave::sigspec(),
+        0,
+    )];
+    let mut tx_override = mock::transaction();
+    tx_override.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+        keys::bob::sigspec(),
+        0,
+    )];
+
+    // Rate limits should not be applied during execution.
+    let mut ctx = mock.create_ctx();
+    for _ in 0..3 {
+        Core::authenticate_tx(&mut ctx, &tx).expect("execution should not be rate limited");
+    }
+
+    let mut ctx = mock.create_check_ctx();
+    Core::authenticate_tx(&mut ctx, &tx).expect("first check should be allowed");
+    Core::authenticate_tx(&mut ctx, &tx).expect("re-checks should not be limited");
+    tx.auth_info.signer_info[0].nonce = 1;
+    let err = Core::authenticate_tx(&mut ctx, &tx).expect_err("second check should be limited");
+    assert_eq!(err.module_name(), "core");
+    assert_eq!(err.code(), 28);
+
+    // Overrides should take precedence.
+    for _ in 0..3 {
+        Core::authenticate_tx(&mut ctx, &tx_override)
+            .expect("overridden signer should not be rate limited");
+    }
+
+    // Advancing the round should refill the bucket.
+    mock.runtime_header.round = 2;
+    let mut ctx = mock.create_check_ctx();
+    Core::authenticate_tx(&mut ctx, &tx).expect("check after refill should be allowed");
+}
+
+#[test]
+fn test_fee_conversion_rate() {
+    let rate = types::FeeConversionRate {
+        native_amount: 2,
+        denomination_amount: 3,
+    };
+    assert_eq!(rate.to_native(3), 2);
+    assert_eq!(
+        rate.to_native(4),
+        2,
+        "conversion to native should round down"
+    );
+    assert_eq!(rate.from_native(2), 3);
+    assert_eq!(
+        rate.from_native(3),
+        5,
+        "conversion from native should round up"
+    );
+    assert!(!types::FeeConversionRate::default().is_valid());
+}
+
+#[test]
+fn test_fee_conversion() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    let denom: token::Denomination = "FOO".parse().unwrap();
+
+    Core::set_params(Parameters {
+        max_batch_gas: 10_000_000,
+        max_tx_size: 32 * 1024,
+        max_tx_signers: 8,
+        max_multisig_signers: 8,
+        min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 1000)]),
+        ..Default::default()
+    });
+
+    // Denominations without a conversion rate cannot be used.
+    assert_eq!(Core::min_gas_price(&ctx, &denom), None);
+
+    Core::set_fee_conversion_rate(
+        token::Denomination::NATIVE,
+        Some(types::FeeConversionRate {
+            native_amount: 1,
+            denomination_amount: 1,
+        }),
+    )
+    .expect_err("native denomination should not have a conversion rate");
+    Core::set_fee_conversion_rate(denom.clone(), Some(Default::default()))
+        .expect_err("invalid conversion rate should be rejected");
+
+    // One native unit is worth two units of FOO.
+    Core::set_fee_conversion_rate(
+        denom.clone(),
+        Some(types::FeeConversionRate {
+            native_amount: 1,
+            denomination_amount: 2,
+        }),
+    )
+    .expect("setting conversion rate should succeed");
+    assert_eq!(Core::min_gas_price(&ctx, &denom), Some(2000));
+    assert_eq!(
+        Core::query_min_gas_price(&mut ctx, ()).unwrap(),
+        BTreeMap::from([(token::Denomination::NATIVE, 1000), (denom.clone(), 2000)]),
+    );
+
+    let mut tx = mock::transaction();
+    tx.auth_info.fee.gas = 100;
+    tx.auth_info.fee.amount = token::BaseUnits::new(100_000, denom.clone());
+    assert_eq!(Core::native_gas_price(&tx.auth_info.fee), 500);
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect_err("gas price should be too low");
+    });
+
+    tx.auth_info.fee.amount = token::BaseUnits::new(200_000, denom.clone());
+    assert_eq!(Core::native_gas_price(&tx.auth_info.fee), 1000);
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect("gas price should be ok");
+    });
+
+    // Clearing the conversion rate disables the denomination again.
+    Core::set_fee_conversion_rate(denom.clone(), None).expect("clearing should succeed");
+    assert_eq!(Core::fee_conversion_rate(&denom), None);
+    assert_eq!(Core::min_gas_price(&ctx, &denom), None);
+    assert_eq!(Core::native_gas_price(&tx.auth_info.fee), 2000);
+}
+
+#[test]
+fn test_export_state() {
+    let _mock = mock::Mock::default();
+    let denom: token::Denomination = "FOO".parse().unwrap();
+
+    Core::set_params(Parameters {
+        max_batch_gas: 10_000_000,
+        min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 1000)]),
+        ..Default::default()
+    });
+    Core::set_fee_conversion_rate(
+        denom,
+        Some(types::FeeConversionRate {
+            native_amount: 1,
+            denomination_amount: 2,
+        }),
+    )
+    .expect("setting conversion rate should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Core>();
+    assert_eq!(genesis.parameters.max_batch_gas, 10_000_000);
+    assert!(
+        genesis
+            .state
+            .keys()
+            .any(|key| key.starts_with(super::state::FEE_CONVERSION_RATES)),
+        "fee conversion rates should be exported"
+    );
+}
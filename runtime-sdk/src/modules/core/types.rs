@@ -1,22 +1,31 @@
 use std::collections::BTreeMap;
 
+use oasis_runtime_sdk_macros::Schema;
+
 use crate::{
+    core::common::crypto::hash::Hash,
     keymanager::SignedPublicKey,
-    types::transaction::{CallResult, CallerAddress, Transaction},
+    types::{
+        address::Address,
+        pagination::PageRequest,
+        transaction::{CallFormat, CallResult, CallerAddress, Transaction},
+    },
 };
 
 /// Key in the versions map used for the global state version.
 pub const VERSION_GLOBAL_KEY: &str = "";
 
 /// Basic per-module metadata; tracked in core module's state.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Metadata {
     /// A set of state versions for all supported modules.
     pub versions: BTreeMap<String, u32>,
 }
 
 /// Arguments for the EstimateGas query.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(no_default)]
 pub struct EstimateGasQuery {
     /// The address of the caller for which to do estimation. If not specified the authentication
@@ -32,28 +41,108 @@ pub struct EstimateGasQuery {
     pub propagate_failures: bool,
 }
 
+/// Conversion rate used when paying fees in a non-native denomination.
+///
+/// An amount of `denomination_amount` base units of the fee denomination is considered to be
+/// equivalent to `native_amount` base units of the native denomination.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FeeConversionRate {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::u128_string"))]
+    pub native_amount: u128,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::u128_string"))]
+    pub denomination_amount: u128,
+}
+
+impl FeeConversionRate {
+    /// Whether the conversion rate can be used.
+    pub fn is_valid(&self) -> bool {
+        self.native_amount > 0 && self.denomination_amount > 0
+    }
+
+    /// Convert the given amount of the fee denomination into the native denomination, rounding
+    /// down.
+    pub fn to_native(&self, amount: u128) -> u128 {
+        if !self.is_valid() {
+            return 0;
+        }
+        amount
+            .checked_mul(self.native_amount)
+            .map(|v| v / self.denomination_amount)
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Convert the given amount of the native denomination into the fee denomination, rounding
+    /// up.
+    pub fn from_native(&self, amount: u128) -> u128 {
+        if !self.is_valid() {
+            return u128::MAX;
+        }
+        amount
+            .checked_mul(self.denomination_amount)
+            .map(|v| {
+                let q = v / self.native_amount;
+                if v % self.native_amount > 0 {
+                    q.saturating_add(1)
+                } else {
+                    q
+                }
+            })
+            .unwrap_or(u128::MAX)
+    }
+}
+
 /// Response to the call data public key query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CallDataPublicKeyQueryResponse {
     /// Public key used for deriving the shared secret for encrypting call data.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
     pub public_key: SignedPublicKey,
     /// Epoch of the ephemeral runtime key.
     pub epoch: u64,
+    /// Encrypted call formats supported by the runtime, in order of preference.
+    #[cbor(optional)]
+    pub formats: Vec<CallFormat>,
+}
+
+/// Response to the call data public key validity query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CallDataPublicKeyValidityQueryResponse {
+    /// Public key used for deriving the shared secret for encrypting call data.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+    pub public_key: SignedPublicKey,
+    /// Epoch of the ephemeral runtime key, which is also the first epoch in which it is used.
+    pub epoch: u64,
+    /// Last epoch in which the key is used for encrypting new calls. Clients should fetch the
+    /// next key after this epoch.
+    pub rotate_after: u64,
+    /// Last epoch in which calls encrypted with the key are accepted.
+    pub valid_until: u64,
+    /// Encrypted call formats supported by the runtime, in order of preference.
+    #[cbor(optional)]
+    pub formats: Vec<CallFormat>,
 }
 
-#[derive(Debug, Copy, Clone, cbor::Encode, cbor::Decode)]
+#[derive(Debug, Copy, Clone, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum MethodHandlerKind {
     #[cbor(rename = "call")]
+    #[cfg_attr(feature = "serde", serde(rename = "call"))]
     Call,
     // `Prefetch` is omitted because it is an implementation detail of handling `Call`s.
     #[cbor(rename = "query")]
+    #[cfg_attr(feature = "serde", serde(rename = "query"))]
     Query,
     #[cbor(rename = "message_result")]
+    #[cfg_attr(feature = "serde", serde(rename = "message_result"))]
     MessageResult,
 }
 
-#[derive(Debug, Clone, cbor::Encode, cbor::Decode)]
+#[derive(Debug, Clone, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[cbor(no_default)]
 pub struct MethodHandlerInfo {
@@ -62,33 +151,266 @@ pub struct MethodHandlerInfo {
 }
 
 /// Metadata for an individual module.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[cbor(no_default)]
 pub struct ModuleInfo {
     pub version: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))]
     pub params: cbor::Value,
+    /// Hash of the CBOR-encoded module parameters.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+    pub params_digest: Hash,
     pub methods: Vec<MethodHandlerInfo>,
+    /// Enabled module features, keyed by feature name.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+    pub features: BTreeMap<String, cbor::Value>,
 }
 
 /// Response to the RuntimeInfo query.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[cbor(no_default)]
 pub struct RuntimeInfoResponse {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
     pub runtime_version: oasis_core_runtime::common::version::Version,
     pub state_version: u32,
     pub modules: BTreeMap<String, ModuleInfo>,
+    /// Whether the runtime is confidential.
+    #[cbor(optional)]
+    pub confidential: bool,
+    /// Supported transaction format versions.
+    #[cbor(optional)]
+    pub transaction_versions: Vec<u16>,
+}
+
+/// Metadata for an individual method, including the names of its argument and result types.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cbor(no_default)]
+pub struct MethodMetadata {
+    pub kind: MethodHandlerKind,
+    pub name: String,
+    /// Name of the argument type in the module's type schemas.
+    pub args: String,
+    /// Name of the result type in the module's type schemas.
+    pub result: String,
+    /// Module version in which the method was introduced. Calls to the method are rejected while
+    /// the module state version is lower.
+    #[cbor(optional)]
+    pub introduced_in: Option<u32>,
+    /// Module version since which the method is deprecated.
+    #[cbor(optional)]
+    pub deprecated_since: Option<u32>,
+}
+
+/// Schema metadata for an individual module.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cbor(no_default)]
+pub struct ModuleMetadata {
+    pub version: u32,
+    pub methods: Vec<MethodMetadata>,
+    pub events: Vec<crate::event::EventInfo>,
+    /// Schemas of the types referred to by methods and events, by name.
+    #[cbor(optional)]
+    pub types: BTreeMap<String, crate::schema::TypeSchema>,
+}
+
+/// Response to the RuntimeMetadata query.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cbor(no_default)]
+pub struct RuntimeMetadataResponse {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+    pub runtime_version: oasis_core_runtime::common::version::Version,
+    pub modules: BTreeMap<String, ModuleMetadata>,
 }
 
 /// Arguments for the ExecuteReadOnlyTx query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExecuteReadOnlyTxQuery {
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
     pub tx: Vec<u8>,
 }
 
 /// Response to the ExecuteReadOnlyTx query.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExecuteReadOnlyTxResponse {
     pub result: CallResult,
 }
+
+/// State size statistics of a module.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StateStats {
+    /// Number of keys in the module's state.
+    pub keys: u64,
+    /// Number of bytes used by keys and values in the module's state.
+    pub bytes: u64,
+}
+
+/// Arguments for the StorageDeposit query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StorageDepositQuery {
+    pub owner: Address,
+}
+
+/// Execution receipt of a transaction.
+///
+/// Receipts are committed into runtime state when enabled via `Config::STORE_RECEIPTS` so that
+/// the outcome of a transaction can be verified with a proof for a single state key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Receipt {
+    /// Whether the transaction succeeded.
+    pub success: bool,
+    /// Module of the error in case the transaction failed.
+    #[cbor(optional)]
+    pub module: String,
+    /// Error code in case the transaction failed.
+    #[cbor(optional)]
+    pub code: u32,
+    /// Amount of gas used by the transaction.
+    pub gas_used: u64,
+    /// Merkle root of the events emitted by the transaction (see `merkle_root`).
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+    pub events_root: Hash,
+    /// Merkle root of the messages emitted by the transaction (see `merkle_root`).
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))]
+    pub messages_root: Hash,
+}
+
+impl Receipt {
+    /// Hash of the canonical encoding of the receipt.
+    pub fn hash(&self) -> Hash {
+        Hash::digest_bytes(&cbor::to_vec(self.clone()))
+    }
+}
+
+/// Compute the root of a binary Merkle tree over the given leaves.
+///
+/// Leaves and inner nodes are hashed with distinct prefixes and a node without a sibling is
+/// carried over to the next level unchanged. The root of an empty tree is the empty hash.
+pub fn merkle_root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return Hash::empty_hash();
+    }
+
+    let mut level: Vec<Hash> = leaves
+        .iter()
+        .map(|leaf| Hash::digest_bytes_list(&[&[0x00], leaf.as_ref()]))
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => Hash::digest_bytes_list(&[&[0x01], left.as_ref(), right.as_ref()]),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Arguments for the Receipt query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReceiptQuery {
+    pub round: u64,
+    pub index: u32,
+}
+
+/// An event recorded in a secondary event index (see `Config::EVENT_INDICES`).
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct IndexedEvent {
+    /// Round in which the event was emitted.
+    pub round: u64,
+    /// Index of the transaction that emitted the event within the round.
+    pub tx_index: u32,
+    /// Value of the event.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))]
+    pub value: cbor::Value,
+}
+
+/// Arguments for the IndexedEvents query.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct IndexedEventsQuery {
+    /// Name of the module that emitted the events.
+    pub module: String,
+    /// Code of the events.
+    pub code: u32,
+    /// Name of the indexed event field.
+    pub field: String,
+    /// Value of the indexed event field to look up.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))]
+    pub value: cbor::Value,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
+
+/// Compute the key under which events with the given field value are indexed.
+pub fn event_index_key(module: &str, code: u32, field: &str, value: &cbor::Value) -> Hash {
+    Hash::digest_bytes(&cbor::to_vec(cbor::Value::Array(vec![
+        cbor::Value::TextString(module.to_owned()),
+        cbor::Value::Unsigned(code.into()),
+        cbor::Value::TextString(field.to_owned()),
+        value.clone(),
+    ])))
+}
+
+/// Change of a single top-level module parameter.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParameterChange {
+    /// Name of the parameter. Empty in case the parameters are not a map.
+    pub key: String,
+    /// Value before the change, if the parameter was set.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_value"))]
+    pub old: Option<cbor::Value>,
+    /// Value after the change, if the parameter is set.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_value"))]
+    pub new: Option<cbor::Value>,
+}
+
+/// A recorded change of module parameters.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParametersChange {
+    /// Round in which the parameters changed.
+    pub round: u64,
+    /// Changed parameters.
+    pub changes: Vec<ParameterChange>,
+}
+
+/// Arguments for the ParameterHistory query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParameterHistoryQuery {
+    /// Name of the module.
+    pub module: String,
+    /// First round to include.
+    #[cbor(optional)]
+    pub from_round: u64,
+    /// Last round to include. The special value of 0 means that no upper bound is applied.
+    #[cbor(optional)]
+    pub to_round: u64,
+}
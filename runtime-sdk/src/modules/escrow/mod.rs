@@ -0,0 +1,480 @@
+//! Escrow module for conditional payments between accounts.
+//!
+//! A sender locks a payment in escrow for a recipient, optionally naming an arbiter. The sender
+//! can release the payment to the recipient at any time and the recipient can refund it to the
+//! sender at any time. Payments can also be time-locked so that the recipient can claim them after
+//! the release time and the sender can reclaim them after the refund time. Until the release time,
+//! either party can dispute an escrow with an arbiter, after which only the arbiter can resolve it
+//! in favour of either party.
+//!
+//! In case the runtime requires storage deposits, the sender pays the deposit for the escrow state
+//! and gets it back once the escrow is settled.
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _},
+    modules::{
+        self,
+        core::{storage_deposit, API as _},
+    },
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{address::Address, pagination::PageResponse, token},
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "escrow";
+
+/// Errors emitted by the escrow module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("unknown escrow")]
+    #[sdk_error(code = 2)]
+    UnknownEscrow,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 3)]
+    Forbidden,
+
+    #[error("insufficient balance")]
+    #[sdk_error(code = 4)]
+    InsufficientBalance,
+
+    #[error("escrow is disputed")]
+    #[sdk_error(code = 5)]
+    Disputed,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the escrow module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    Created {
+        id: u64,
+        sender: Address,
+        recipient: Address,
+        amount: token::BaseUnits,
+    },
+
+    #[sdk_event(code = 2)]
+    Released { id: u64, by: Address },
+
+    #[sdk_event(code = 3)]
+    Refunded { id: u64, by: Address },
+
+    #[sdk_event(code = 4)]
+    Disputed { id: u64, by: Address },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_create: u64,
+    pub tx_release: u64,
+    pub tx_refund: u64,
+    pub tx_dispute: u64,
+}
+
+/// Parameters for the escrow module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+}
+
+impl module::Parameters for Parameters {
+    type Error = std::convert::Infallible;
+}
+
+/// Genesis state for the escrow module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of escrow identifiers to escrows.
+    pub const ESCROWS: &[u8] = &[0x01];
+    /// Identifier of the next escrow.
+    pub const NEXT_ID: &[u8] = &[0x02];
+    /// Set of escrow identifiers by the addresses of their parties.
+    pub const ACCOUNTS: &[u8] = &[0x03];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[ESCROWS, NEXT_ID, ACCOUNTS];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the escrow with the given identifier.
+    fn get_escrow(id: u64) -> Option<types::Escrow>;
+
+    /// Identifiers of the escrows that the given address is a party of.
+    fn get_account_escrows(address: Address) -> Vec<u64>;
+}
+
+pub struct Module<Accounts: modules::accounts::API> {
+    _accounts: std::marker::PhantomData<Accounts>,
+}
+
+/// Module's address that holds the escrowed payments.
+pub static ADDRESS_ESCROW: Lazy<Address> =
+    Lazy::new(|| Address::from_module(MODULE_NAME, "escrow"));
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    fn next_id() -> u64 {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::NEXT_ID).unwrap_or_default()
+        })
+    }
+
+    fn set_next_id(id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            tstore.insert(state::NEXT_ID, id);
+        });
+    }
+
+    /// Addresses of all parties of the given escrow.
+    fn parties(escrow: &types::Escrow) -> impl Iterator<Item = Address> {
+        [Some(escrow.sender), Some(escrow.recipient), escrow.arbiter]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Number of bytes of state covered by the storage deposit of the given escrow. Disputes do
+    /// not change the amount of state the sender pays for.
+    fn deposit_bytes(escrow: &types::Escrow) -> u64 {
+        let id_size = std::mem::size_of::<u64>();
+        let record = cbor::to_vec(types::Escrow {
+            disputed: false,
+            ..escrow.clone()
+        });
+        let index = Self::parties(escrow).count() * (Address::SIZE + id_size);
+        (id_size + record.len() + index) as u64
+    }
+
+    fn set_escrow(id: u64, escrow: types::Escrow) {
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            for address in Self::parties(&escrow) {
+                let accounts = storage::PrefixStore::new(&mut store, &state::ACCOUNTS);
+                let mut escrows =
+                    storage::TypedStore::new(storage::PrefixStore::new(accounts, &address));
+                escrows.insert(id.to_be_bytes(), ());
+            }
+
+            let mut escrows =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::ESCROWS));
+            escrows.insert(id.to_be_bytes(), escrow);
+        });
+    }
+
+    fn remove_escrow(id: u64, escrow: &types::Escrow) {
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            for address in Self::parties(escrow) {
+                let accounts = storage::PrefixStore::new(&mut store, &state::ACCOUNTS);
+                let mut escrows =
+                    storage::TypedStore::new(storage::PrefixStore::new(accounts, &address));
+                escrows.remove(id.to_be_bytes());
+            }
+
+            let mut escrows =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::ESCROWS));
+            escrows.remove(id.to_be_bytes());
+        });
+    }
+
+    /// Pay out the escrow to the given address and remove it.
+    fn settle<C: Context>(
+        ctx: &mut C,
+        id: u64,
+        escrow: &types::Escrow,
+        to: Address,
+    ) -> Result<(), Error> {
+        Accounts::transfer(ctx, *ADDRESS_ESCROW, to, &escrow.amount)
+            .map_err(|_| Error::InsufficientBalance)?;
+        storage_deposit::refund::<_, Accounts>(ctx, escrow.sender, Self::deposit_bytes(escrow))
+            .map_err(|_| Error::InsufficientBalance)?;
+        Self::remove_escrow(id, escrow);
+        Ok(())
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn get_escrow(id: u64) -> Option<types::Escrow> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let escrows =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::ESCROWS));
+            escrows.get(id.to_be_bytes())
+        })
+    }
+
+    fn get_account_escrows(address: Address) -> Vec<u64> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let accounts = storage::PrefixStore::new(store, &state::ACCOUNTS);
+            let escrows = storage::TypedStore::new(storage::PrefixStore::new(accounts, &address));
+            escrows.iter::<EscrowId, ()>().map(|(id, _)| id.0).collect()
+        })
+    }
+}
+
+#[sdk_derive(Module)]
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Lock a payment from the caller in escrow for the recipient. Returns the escrow identifier.
+    #[handler(call = "escrow.Create")]
+    fn tx_create<C: TxContext>(ctx: &mut C, body: types::Create) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_create)?;
+
+        let sender = ctx.tx_caller_address();
+        if body.amount.amount() == 0
+            || body.recipient == sender
+            || body.arbiter == Some(sender)
+            || body.arbiter == Some(body.recipient)
+            || (body.release_time > 0
+                && body.refund_time > 0
+                && body.refund_time <= body.release_time)
+        {
+            return Err(Error::InvalidArgument);
+        }
+
+        let id = Self::next_id();
+        if ctx.is_check_only() {
+            return Ok(id);
+        }
+
+        Accounts::transfer(ctx, sender, *ADDRESS_ESCROW, &body.amount)
+            .map_err(|_| Error::InsufficientBalance)?;
+
+        let escrow = types::Escrow {
+            sender,
+            recipient: body.recipient,
+            arbiter: body.arbiter,
+            amount: body.amount.clone(),
+            release_time: body.release_time,
+            refund_time: body.refund_time,
+            disputed: false,
+        };
+        storage_deposit::charge::<_, Accounts>(ctx, sender, Self::deposit_bytes(&escrow))
+            .map_err(|_| Error::InsufficientBalance)?;
+
+        Self::set_next_id(id + 1);
+        Self::set_escrow(id, escrow);
+
+        ctx.emit_event(Event::Created {
+            id,
+            sender,
+            recipient: body.recipient,
+            amount: body.amount,
+        });
+
+        Ok(id)
+    }
+
+    /// Release an escrowed payment to its recipient. The sender and the arbiter can release the
+    /// payment at any time, the recipient only after the release time unless disputed.
+    #[handler(call = "escrow.Release")]
+    fn tx_release<C: TxContext>(ctx: &mut C, body: types::Release) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_release)?;
+
+        let escrow = Self::get_escrow(body.id).ok_or(Error::UnknownEscrow)?;
+        let caller = ctx.tx_caller_address();
+        if caller == escrow.recipient {
+            if escrow.disputed {
+                return Err(Error::Disputed);
+            }
+            if escrow.release_time == 0 || ctx.time().timestamp() < escrow.release_time {
+                return Err(Error::Forbidden);
+            }
+        } else if caller != escrow.sender && Some(caller) != escrow.arbiter {
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::settle(ctx, body.id, &escrow, escrow.recipient)?;
+
+        ctx.emit_event(Event::Released {
+            id: body.id,
+            by: caller,
+        });
+
+        Ok(())
+    }
+
+    /// Refund an escrowed payment to its sender. The recipient and the arbiter can refund the
+    /// payment at any time, the sender only after the refund time unless disputed.
+    #[handler(call = "escrow.Refund")]
+    fn tx_refund<C: TxContext>(ctx: &mut C, body: types::Refund) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_refund)?;
+
+        let escrow = Self::get_escrow(body.id).ok_or(Error::UnknownEscrow)?;
+        let caller = ctx.tx_caller_address();
+        if caller == escrow.sender {
+            if escrow.disputed {
+                return Err(Error::Disputed);
+            }
+            if escrow.refund_time == 0 || ctx.time().timestamp() < escrow.refund_time {
+                return Err(Error::Forbidden);
+            }
+        } else if caller != escrow.recipient && Some(caller) != escrow.arbiter {
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::settle(ctx, body.id, &escrow, escrow.sender)?;
+
+        ctx.emit_event(Event::Refunded {
+            id: body.id,
+            by: caller,
+        });
+
+        Ok(())
+    }
+
+    /// Dispute an escrow, leaving its resolution to the arbiter. Only the sender and the recipient
+    /// can dispute escrows that have an arbiter, and only before the release time.
+    #[handler(call = "escrow.Dispute")]
+    fn tx_dispute<C: TxContext>(ctx: &mut C, body: types::Dispute) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_dispute)?;
+
+        let mut escrow = Self::get_escrow(body.id).ok_or(Error::UnknownEscrow)?;
+        let caller = ctx.tx_caller_address();
+        if caller != escrow.sender && caller != escrow.recipient {
+            return Err(Error::Forbidden);
+        }
+        if escrow.arbiter.is_none() {
+            return Err(Error::InvalidArgument);
+        }
+        if escrow.disputed {
+            return Err(Error::Disputed);
+        }
+        if escrow.release_time > 0 && ctx.time().timestamp() >= escrow.release_time {
+            // The dispute window has closed.
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        escrow.disputed = true;
+        Self::set_escrow(body.id, escrow);
+
+        ctx.emit_event(Event::Disputed {
+            id: body.id,
+            by: caller,
+        });
+
+        Ok(())
+    }
+
+    #[handler(query = "escrow.Escrow")]
+    fn query_escrow<C: Context>(
+        _ctx: &mut C,
+        args: types::EscrowQuery,
+    ) -> Result<types::Escrow, Error> {
+        Self::get_escrow(args.id).ok_or(Error::UnknownEscrow)
+    }
+
+    /// Identifiers of the escrows that an account is a party of.
+    #[handler(query = "escrow.AccountEscrows")]
+    fn query_account_escrows<C: Context>(
+        _ctx: &mut C,
+        args: types::AccountEscrowsQuery,
+    ) -> Result<PageResponse<u64>, Error> {
+        let start = args
+            .pagination
+            .start::<u64>()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let accounts = storage::PrefixStore::new(store, &state::ACCOUNTS);
+            let escrows = storage::StoreSet::<_, u64>::new(storage::PrefixStore::new(
+                accounts,
+                &args.address,
+            ));
+            let page = escrows.page(start.as_ref(), args.pagination.limit());
+            Ok(PageResponse::from_page(page, |id| id))
+        })
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
+
+/// Key of the per-account escrow index.
+struct EscrowId(u64);
+
+impl TryFrom<&[u8]> for EscrowId {
+    type Error = std::array::TryFromSliceError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Ok(EscrowId(u64::from_be_bytes(value.try_into()?)))
+    }
+}
@@ -0,0 +1,365 @@
+//! Tests for the escrow module.
+use crate::{
+    context::{BatchContext, Context, RuntimeTxContext},
+    module::Module as _,
+    modules::{
+        accounts::Module as Accounts,
+        core::{
+            self,
+            storage_deposit::{self, ADDRESS_STORAGE_DEPOSIT_ESCROW},
+        },
+    },
+    testing::{keys, mock},
+    types::{
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+};
+
+use super::{types, Error, Genesis, ADDRESS_ESCROW, API as _};
+
+type Escrow = super::Module<Accounts>;
+
+fn init<C: Context>(ctx: &mut C) {
+    mock::init_balances(
+        ctx,
+        &[
+            (keys::alice::address(), 1_000),
+            (keys::bob::address(), 1_000),
+        ],
+    );
+    Escrow::init(Genesis::default());
+}
+
+/// Run the given function in the context of a transaction signed by the given signer.
+fn with_signer<R>(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    f: impl FnOnce(&mut RuntimeTxContext<'_, '_, mock::EmptyRuntime>) -> R,
+) -> R {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| f(&mut tx_ctx))
+}
+
+fn create(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    body: types::Create,
+) -> Result<u64, Error> {
+    with_signer(mock, signer, |ctx| Escrow::tx_create(ctx, body))
+}
+
+fn release(mock: &mut mock::Mock, signer: &transaction::SignerInfo, id: u64) -> Result<(), Error> {
+    with_signer(mock, signer, |ctx| {
+        Escrow::tx_release(ctx, types::Release { id })
+    })
+}
+
+fn refund(mock: &mut mock::Mock, signer: &transaction::SignerInfo, id: u64) -> Result<(), Error> {
+    with_signer(mock, signer, |ctx| {
+        Escrow::tx_refund(ctx, types::Refund { id })
+    })
+}
+
+fn dispute(mock: &mut mock::Mock, signer: &transaction::SignerInfo, id: u64) -> Result<(), Error> {
+    with_signer(mock, signer, |ctx| {
+        Escrow::tx_dispute(ctx, types::Dispute { id })
+    })
+}
+
+fn payment(amount: u128) -> types::Create {
+    types::Create {
+        recipient: keys::bob::address(),
+        amount: BaseUnits::new(amount, Denomination::NATIVE),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_create() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+
+    for (body, check) in [
+        (payment(0), "zero amount should be rejected"),
+        (
+            types::Create {
+                recipient: keys::alice::address(),
+                ..payment(100)
+            },
+            "payments to self should be rejected",
+        ),
+        (
+            types::Create {
+                arbiter: Some(keys::bob::address()),
+                ..payment(100)
+            },
+            "recipient should not be the arbiter",
+        ),
+        (
+            types::Create {
+                release_time: 100,
+                refund_time: 100,
+                ..payment(100)
+            },
+            "refund time should be after the release time",
+        ),
+    ] {
+        let result = create(&mut mock, &alice, body);
+        assert!(matches!(result, Err(Error::InvalidArgument)), "{check}");
+    }
+    let result = create(&mut mock, &alice, payment(1_001));
+    assert!(matches!(result, Err(Error::InsufficientBalance)));
+
+    let id = create(&mut mock, &alice, payment(100)).expect("create should succeed");
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 100);
+    assert_eq!(
+        Escrow::get_escrow(id).unwrap(),
+        types::Escrow {
+            sender: keys::alice::address(),
+            recipient: keys::bob::address(),
+            amount: BaseUnits::new(100, Denomination::NATIVE),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        Escrow::get_account_escrows(keys::alice::address()),
+        vec![id]
+    );
+    assert_eq!(Escrow::get_account_escrows(keys::bob::address()), vec![id]);
+
+    let mut ctx = mock.create_ctx();
+    let escrows = Escrow::query_account_escrows(
+        &mut ctx,
+        types::AccountEscrowsQuery {
+            address: keys::bob::address(),
+            ..Default::default()
+        },
+    )
+    .expect("account escrows query should succeed");
+    assert_eq!(escrows.items, vec![id]);
+    assert_eq!(escrows.next, None);
+}
+
+#[test]
+fn test_release_and_refund() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let charlie = keys::charlie::signer_info();
+
+    // Only the sender can release payments without a release time.
+    let id = create(&mut mock, &alice, payment(100)).expect("create should succeed");
+    assert!(matches!(
+        release(&mut mock, &bob, id),
+        Err(Error::Forbidden)
+    ));
+    assert!(matches!(
+        release(&mut mock, &charlie, id),
+        Err(Error::Forbidden)
+    ));
+    release(&mut mock, &alice, id).expect("sender should be able to release");
+    assert_eq!(mock::balance(keys::bob::address()), 1_100);
+    assert_eq!(Escrow::get_escrow(id), None);
+    assert!(Escrow::get_account_escrows(keys::alice::address()).is_empty());
+    assert!(matches!(
+        release(&mut mock, &alice, id),
+        Err(Error::UnknownEscrow)
+    ));
+
+    // Only the recipient can refund payments without a refund time.
+    let id = create(&mut mock, &alice, payment(100)).expect("create should succeed");
+    assert!(matches!(
+        refund(&mut mock, &alice, id),
+        Err(Error::Forbidden)
+    ));
+    refund(&mut mock, &bob, id).expect("recipient should be able to refund");
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 0);
+}
+
+#[test]
+fn test_time_lock() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let locked = types::Create {
+        release_time: 100,
+        refund_time: 200,
+        ..payment(100)
+    };
+
+    let first = create(&mut mock, &alice, locked.clone()).expect("create should succeed");
+    let second = create(&mut mock, &alice, locked).expect("create should succeed");
+
+    mock.runtime_header.timestamp = 99;
+    assert!(matches!(
+        release(&mut mock, &bob, first),
+        Err(Error::Forbidden)
+    ));
+    mock.runtime_header.timestamp = 100;
+    release(&mut mock, &bob, first).expect("recipient should be able to claim after release time");
+    assert_eq!(mock::balance(keys::bob::address()), 1_100);
+
+    mock.runtime_header.timestamp = 199;
+    assert!(matches!(
+        refund(&mut mock, &alice, second),
+        Err(Error::Forbidden)
+    ));
+    mock.runtime_header.timestamp = 200;
+    refund(&mut mock, &alice, second).expect("sender should be able to reclaim after refund time");
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+}
+
+#[test]
+fn test_dispute() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let charlie = keys::charlie::signer_info();
+    let arbitrated = types::Create {
+        arbiter: Some(keys::charlie::address()),
+        release_time: 100,
+        refund_time: 200,
+        ..payment(100)
+    };
+
+    // Escrows without an arbiter cannot be disputed.
+    let id = create(&mut mock, &alice, payment(100)).expect("create should succeed");
+    assert!(matches!(
+        dispute(&mut mock, &alice, id),
+        Err(Error::InvalidArgument)
+    ));
+
+    let id = create(&mut mock, &alice, arbitrated.clone()).expect("create should succeed");
+    assert_eq!(
+        Escrow::get_account_escrows(keys::charlie::address()),
+        vec![id]
+    );
+    assert!(matches!(
+        dispute(&mut mock, &charlie, id),
+        Err(Error::Forbidden)
+    ));
+    mock.runtime_header.timestamp = 50;
+    dispute(&mut mock, &bob, id).expect("recipient should be able to dispute");
+    assert!(Escrow::get_escrow(id).unwrap().disputed);
+    assert!(matches!(
+        dispute(&mut mock, &alice, id),
+        Err(Error::Disputed)
+    ));
+
+    // Disputed escrows cannot be claimed after the release or refund times.
+    mock.runtime_header.timestamp = 200;
+    assert!(matches!(release(&mut mock, &bob, id), Err(Error::Disputed)));
+    assert!(matches!(
+        refund(&mut mock, &alice, id),
+        Err(Error::Disputed)
+    ));
+
+    // The arbiter resolves the dispute.
+    refund(&mut mock, &charlie, id).expect("arbiter should be able to refund");
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+
+    // The dispute window closes at the release time.
+    mock.runtime_header.timestamp = 0;
+    let id = create(&mut mock, &alice, arbitrated).expect("create should succeed");
+    mock.runtime_header.timestamp = 100;
+    assert!(matches!(
+        dispute(&mut mock, &alice, id),
+        Err(Error::Forbidden)
+    ));
+}
+
+#[test]
+fn test_storage_deposit() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+    core::Module::<mock::Config>::set_params(core::Parameters {
+        max_batch_gas: u64::MAX,
+        storage_deposit: storage_deposit::Parameters {
+            deposit_per_byte: 1,
+        },
+        ..Default::default()
+    });
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    // The sender should pay the storage deposit for the escrow.
+    let id = create(&mut mock, &alice, payment(100)).expect("create should succeed");
+    let deposit = storage_deposit::get_deposit(keys::alice::address());
+    assert!(deposit.bytes > 0);
+    assert_eq!(deposit.amount, u128::from(deposit.bytes));
+    assert_eq!(mock::balance(keys::alice::address()), 900 - deposit.amount);
+    assert_eq!(
+        mock::balance(*ADDRESS_STORAGE_DEPOSIT_ESCROW),
+        deposit.amount
+    );
+
+    // Disputes should not change the deposit.
+    let disputed = create(
+        &mut mock,
+        &alice,
+        types::Create {
+            arbiter: Some(keys::charlie::address()),
+            ..payment(100)
+        },
+    )
+    .expect("create should succeed");
+    let total = storage_deposit::get_deposit(keys::alice::address());
+    dispute(&mut mock, &bob, disputed).expect("recipient should be able to dispute");
+    assert_eq!(storage_deposit::get_deposit(keys::alice::address()), total);
+
+    // Settling should refund the deposit to the sender, whoever settles the escrow.
+    refund(&mut mock, &bob, id).expect("recipient should be able to refund");
+    refund(&mut mock, &bob, disputed).expect("recipient should be able to refund");
+    assert_eq!(
+        storage_deposit::get_deposit(keys::alice::address()),
+        storage_deposit::Deposit::default()
+    );
+    assert_eq!(mock::balance(keys::alice::address()), 1_000);
+    assert_eq!(mock::balance(*ADDRESS_STORAGE_DEPOSIT_ESCROW), 0);
+    storage_deposit::check_invariants::<Accounts>().expect("invariants should hold");
+
+    // Escrows should not be created without funds for the deposit.
+    let result = create(&mut mock, &alice, payment(1_000));
+    assert!(matches!(result, Err(Error::InsufficientBalance)));
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    create(&mut mock, &alice, payment(100)).expect("create should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Escrow>();
+    for prefix in [
+        super::state::ESCROWS,
+        super::state::NEXT_ID,
+        super::state::ACCOUNTS,
+    ] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "escrows, the next identifier and the account index should be exported"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Escrow module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::{address::Address, pagination::PageRequest, token::BaseUnits};
+
+/// A payment held in escrow.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Escrow {
+    /// Address of the account that funded the escrow.
+    pub sender: Address,
+    /// Address of the account the payment is intended for.
+    pub recipient: Address,
+    /// Address of the account that can resolve disputes, if any.
+    #[cbor(optional)]
+    pub arbiter: Option<Address>,
+    /// Amount held in escrow.
+    pub amount: BaseUnits,
+    /// Timestamp after which the recipient can claim the payment. Zero means that the payment
+    /// can only be released by the sender or the arbiter.
+    #[cbor(optional)]
+    pub release_time: u64,
+    /// Timestamp after which the sender can claim a refund. Zero means that the payment can only
+    /// be refunded by the recipient or the arbiter.
+    #[cbor(optional)]
+    pub refund_time: u64,
+    /// Whether the escrow is disputed and awaiting resolution by the arbiter.
+    #[cbor(optional)]
+    pub disputed: bool,
+}
+
+/// Create escrow call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Create {
+    /// Address of the account the payment is intended for.
+    pub recipient: Address,
+    /// Address of the account that can resolve disputes, if any.
+    #[cbor(optional)]
+    pub arbiter: Option<Address>,
+    /// Amount to hold in escrow.
+    pub amount: BaseUnits,
+    /// Timestamp after which the recipient can claim the payment.
+    #[cbor(optional)]
+    pub release_time: u64,
+    /// Timestamp after which the sender can claim a refund. Must be after the release time.
+    #[cbor(optional)]
+    pub refund_time: u64,
+}
+
+/// Release escrow call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Release {
+    /// Escrow identifier.
+    pub id: u64,
+}
+
+/// Refund escrow call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Refund {
+    /// Escrow identifier.
+    pub id: u64,
+}
+
+/// Dispute escrow call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Dispute {
+    /// Escrow identifier.
+    pub id: u64,
+}
+
+/// Escrow query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct EscrowQuery {
+    /// Escrow identifier.
+    pub id: u64,
+}
+
+/// Account escrows query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AccountEscrowsQuery {
+    /// Address of the account.
+    pub address: Address,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
@@ -0,0 +1,422 @@
+//! Fee grant module.
+//!
+//! Allows an account (the granter) to give another account (the grantee) a budgeted allowance for
+//! paying transaction fees. A transaction names the granter whose allowance should pay its fee in
+//! the `fee_granter` field of its auth info. When charging transaction fees, the accounts module
+//! then uses that allowance instead of the grantee's balance, and refunded fees are returned to
+//! the allowance.
+//!
+//! Fee grants are only supported by runtimes that set `Runtime::FeeGrants` to this module.
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _},
+    modules::{
+        self,
+        core::{Error as CoreError, API as _},
+    },
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{
+        address::Address,
+        pagination::PageResponse,
+        token,
+        transaction::{Call, Transaction},
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "feegrant";
+
+/// Errors emitted by the fee grant module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("not found")]
+    #[sdk_error(code = 2)]
+    NotFound,
+
+    #[error("insufficient allowance")]
+    #[sdk_error(code = 3)]
+    InsufficientAllowance,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the fee grant module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    Granted {
+        granter: Address,
+        grantee: Address,
+        allowance: types::Allowance,
+    },
+
+    #[sdk_event(code = 2)]
+    Revoked { granter: Address, grantee: Address },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_grant: u64,
+    pub tx_revoke: u64,
+
+    /// Gas charged to transactions that pay their fee from an allowance.
+    #[cbor(optional)]
+    pub fee_grant: u64,
+}
+
+/// Parameters for the fee grant module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Maximum number of methods that can be specified in an allowance method filter.
+    pub max_methods: u32,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ();
+}
+
+/// Genesis state for the fee grant module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of grantee addresses to map of granter addresses to allowances.
+    pub const ALLOWANCES: &[u8] = &[0x01];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[ALLOWANCES];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the allowance given by the granter to the grantee.
+    fn get_allowance(granter: Address, grantee: Address) -> Option<types::Allowance>;
+
+    /// Fetch all allowances given to the grantee.
+    fn get_allowances(grantee: Address) -> Vec<types::AllowanceInfo>;
+
+    /// Consume the given fee from the allowance given by the granter to the grantee. The allowance
+    /// is removed once it has been fully used.
+    ///
+    /// Returns the allowance after the fee has been consumed.
+    fn use_allowance(
+        granter: Address,
+        grantee: Address,
+        fee: &token::BaseUnits,
+    ) -> Result<types::Allowance, Error>;
+
+    /// Return a refunded part of a previously consumed fee to the allowance given by the granter to
+    /// the grantee. The `used` allowance is the one returned by `use_allowance` and is restored in
+    /// case it has been removed after being fully used.
+    fn refund_allowance(
+        granter: Address,
+        grantee: Address,
+        used: types::Allowance,
+        refund: &token::BaseUnits,
+    );
+}
+
+/// Context key for the allowance used by the transaction being executed.
+const CONTEXT_KEY_USED_ALLOWANCE: &str = "feegrant.UsedAllowance";
+
+pub struct Module;
+
+impl Module {
+    fn set_allowance(granter: Address, grantee: Address, allowance: Option<types::Allowance>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let mut grantee_allowances =
+                storage::TypedStore::new(storage::PrefixStore::new(allowances, &grantee));
+            match allowance {
+                Some(allowance) => grantee_allowances.insert(granter, allowance),
+                None => grantee_allowances.remove(granter),
+            }
+        });
+    }
+}
+
+impl API for Module {
+    fn get_allowance(granter: Address, grantee: Address) -> Option<types::Allowance> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let grantee_allowances =
+                storage::TypedStore::new(storage::PrefixStore::new(allowances, &grantee));
+            grantee_allowances.get(granter)
+        })
+    }
+
+    fn get_allowances(grantee: Address) -> Vec<types::AllowanceInfo> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let grantee_allowances =
+                storage::TypedStore::new(storage::PrefixStore::new(allowances, &grantee));
+            grantee_allowances
+                .iter()
+                .map(|(granter, allowance)| types::AllowanceInfo { granter, allowance })
+                .collect()
+        })
+    }
+
+    fn use_allowance(
+        granter: Address,
+        grantee: Address,
+        fee: &token::BaseUnits,
+    ) -> Result<types::Allowance, Error> {
+        let mut allowance = Self::get_allowance(granter, grantee).ok_or(Error::NotFound)?;
+        allowance.remaining = allowance
+            .remaining
+            .checked_sub(fee)
+            .map_err(|_| Error::InsufficientAllowance)?;
+
+        if allowance.remaining.amount() == 0 {
+            Self::set_allowance(granter, grantee, None);
+        } else {
+            Self::set_allowance(granter, grantee, Some(allowance.clone()));
+        }
+
+        Ok(allowance)
+    }
+
+    fn refund_allowance(
+        granter: Address,
+        grantee: Address,
+        used: types::Allowance,
+        refund: &token::BaseUnits,
+    ) {
+        if refund.amount() == 0 {
+            return;
+        }
+
+        let mut allowance = match Self::get_allowance(granter, grantee) {
+            Some(allowance) => allowance,
+            // Only restore allowances that were removed after being fully used and not the ones
+            // that have been revoked in the meantime.
+            None if used.remaining.amount() == 0 => used,
+            None => return,
+        };
+        allowance.remaining = match allowance.remaining.checked_add(refund) {
+            Ok(remaining) => remaining,
+            Err(token::Error::DenominationMismatch(..)) => return,
+            Err(_) => token::BaseUnits::new(u128::MAX, refund.denomination().clone()),
+        };
+        Self::set_allowance(granter, grantee, Some(allowance));
+    }
+}
+
+#[sdk_derive(Module)]
+impl Module {
+    const NAME: &'static str = MODULE_NAME;
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    #[handler(call = "feegrant.Grant")]
+    fn tx_grant<C: TxContext>(ctx: &mut C, body: types::Grant) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_grant)?;
+
+        let granter = ctx.tx_caller_address();
+        if body.grantee == granter {
+            return Err(Error::InvalidArgument);
+        }
+        if body.allowance.remaining.amount() == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        if body.allowance.methods.len() > params.max_methods as usize {
+            return Err(Error::InvalidArgument);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::set_allowance(granter, body.grantee, Some(body.allowance.clone()));
+
+        ctx.emit_event(Event::Granted {
+            granter,
+            grantee: body.grantee,
+            allowance: body.allowance,
+        });
+
+        Ok(())
+    }
+
+    #[handler(call = "feegrant.Revoke")]
+    fn tx_revoke<C: TxContext>(ctx: &mut C, body: types::Revoke) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_revoke)?;
+
+        let granter = ctx.tx_caller_address();
+        if Self::get_allowance(granter, body.grantee).is_none() {
+            return Err(Error::NotFound);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::set_allowance(granter, body.grantee, None);
+
+        ctx.emit_event(Event::Revoked {
+            granter,
+            grantee: body.grantee,
+        });
+
+        Ok(())
+    }
+
+    #[handler(query = "feegrant.Allowance")]
+    fn query_allowance<C: Context>(
+        _ctx: &mut C,
+        args: types::AllowanceQuery,
+    ) -> Result<types::Allowance, Error> {
+        Self::get_allowance(args.granter, args.grantee).ok_or(Error::NotFound)
+    }
+
+    #[handler(query = "feegrant.Allowances")]
+    fn query_allowances<C: Context>(
+        _ctx: &mut C,
+        args: types::AllowancesQuery,
+    ) -> Result<PageResponse<types::AllowanceInfo>, Error> {
+        let start = args
+            .pagination
+            .start()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let allowances = storage::PrefixStore::new(store, &state::ALLOWANCES);
+            let grantee_allowances = storage::StoreMap::<_, Address, types::Allowance>::new(
+                storage::PrefixStore::new(allowances, &args.grantee),
+            );
+            let page = grantee_allowances.page(start.as_ref(), args.pagination.limit());
+            Ok(PageResponse::from_page(page, |(granter, allowance)| {
+                types::AllowanceInfo { granter, allowance }
+            }))
+        })
+    }
+}
+
+impl module::TransactionHandler for Module {
+    fn before_handle_call<C: TxContext>(ctx: &mut C, _call: &Call) -> Result<(), CoreError> {
+        if ctx.tx_auth_info().fee_granter.is_none() {
+            return Ok(());
+        }
+
+        // Charge for looking up and updating the allowance.
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.fee_grant)?;
+
+        Ok(())
+    }
+}
+
+impl module::FeeGrantHandler for Module {
+    fn fee_granter<C: Context>(
+        ctx: &mut C,
+        tx: &Transaction,
+        payer: Address,
+    ) -> Result<Option<Address>, CoreError> {
+        let granter = match tx.auth_info.fee_granter {
+            Some(granter) => granter,
+            None => return Ok(None),
+        };
+
+        let allowance =
+            Self::get_allowance(granter, payer).ok_or(CoreError::InsufficientFeeBalance)?;
+        if !allowance.covers(
+            &tx.auth_info.fee.amount,
+            &tx.call.method,
+            ctx.time().round(),
+        ) {
+            return Err(CoreError::InsufficientFeeBalance);
+        }
+
+        Ok(Some(granter))
+    }
+
+    fn use_fee_grant<C: Context>(
+        ctx: &mut C,
+        granter: Address,
+        payer: Address,
+        fee: &token::BaseUnits,
+    ) -> Result<(), CoreError> {
+        let used = Self::use_allowance(granter, payer, fee)
+            .map_err(|_| CoreError::InsufficientFeeBalance)?;
+
+        if !ctx.is_check_only() {
+            // Remember the used allowance so that any refunded fees can be returned to it.
+            ctx.value(CONTEXT_KEY_USED_ALLOWANCE)
+                .set((granter, payer, used));
+        }
+
+        Ok(())
+    }
+
+    fn refund_fee_grant<C: Context>(
+        ctx: &mut C,
+        granter: Address,
+        payer: Address,
+        refund: &token::BaseUnits,
+    ) {
+        let used: Option<(Address, Address, types::Allowance)> =
+            ctx.value(CONTEXT_KEY_USED_ALLOWANCE).take();
+        if let Some((used_granter, used_payer, used)) = used {
+            if used_granter == granter && used_payer == payer {
+                Self::refund_allowance(granter, payer, used, refund);
+            }
+        }
+    }
+}
+
+impl module::BlockHandler for Module {}
+
+impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! Tests for the fee grant module.
+use crate::{
+    context::{BatchContext, Mode},
+    module::{self, TransactionHandler as _},
+    modules::{
+        accounts::{self, test::init_accounts, API as _},
+        core::{self, API as _},
+    },
+    runtime::Runtime,
+    testing::{keys, mock},
+    types::{
+        pagination::PageRequest,
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+    Version,
+};
+
+use super::{types, Error, Genesis, Module as FeeGrant, Parameters, API as _};
+
+type Core = core::Module<mock::Config>;
+
+/// A runtime that pays fees from fee grants.
+struct FeeGrantRuntime;
+
+impl Runtime for FeeGrantRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+    type Core = Core;
+    type Modules = (Core, accounts::Module, FeeGrant);
+    type FeeGrants = FeeGrant;
+
+    fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+        Default::default()
+    }
+}
+
+fn init_feegrant() {
+    FeeGrant::init(Genesis {
+        parameters: Parameters {
+            max_methods: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+}
+
+fn transaction_from(
+    signer: &transaction::SignerInfo,
+    method: &str,
+    fee: u128,
+) -> transaction::Transaction {
+    let mut tx = mock::transaction_from(signer, method);
+    tx.auth_info.fee.amount = BaseUnits::new(fee, Denomination::NATIVE);
+    tx.auth_info.fee.gas = 1_000;
+    tx
+}
+
+#[test]
+fn test_allowance_covers() {
+    let allowance = types::Allowance {
+        remaining: BaseUnits::new(1_000, Denomination::NATIVE),
+        per_tx_limit: 100,
+        expiration: Some(10),
+        methods: vec!["accounts.Transfer".to_owned()],
+    };
+    let fee = BaseUnits::new(100, Denomination::NATIVE);
+
+    assert!(allowance.covers(&fee, "accounts.Transfer", 10));
+    assert!(
+        !allowance.covers(&fee, "accounts.Transfer", 11),
+        "expired allowance should not be used"
+    );
+    assert!(
+        !allowance.covers(&fee, "consensus.Deposit", 10),
+        "allowance should not be used for other methods"
+    );
+    assert!(
+        !allowance.covers(
+            &BaseUnits::new(101, Denomination::NATIVE),
+            "accounts.Transfer",
+            10
+        ),
+        "allowance should not be used above the per-transaction limit"
+    );
+    assert!(
+        !allowance.covers(
+            &BaseUnits::new(100, "FOO".parse().unwrap()),
+            "accounts.Transfer",
+            10
+        ),
+        "allowance should not be used for other denominations"
+    );
+}
+
+#[test]
+fn test_grant_revoke() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init_feegrant();
+
+    let tx = transaction_from(&keys::alice::signer_info(), "feegrant.Grant", 0);
+    let allowance = types::Allowance {
+        remaining: BaseUnits::new(1_000, Denomination::NATIVE),
+        ..Default::default()
+    };
+
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, _call| {
+        let result = FeeGrant::tx_grant(
+            &mut tx_ctx,
+            types::Grant {
+                grantee: keys::alice::address(),
+                allowance: allowance.clone(),
+            },
+        );
+        assert!(matches!(result, Err(Error::InvalidArgument)));
+
+        let result = FeeGrant::tx_grant(
+            &mut tx_ctx,
+            types::Grant {
+                grantee: keys::bob::address(),
+                allowance: types::Allowance {
+                    methods: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+                    ..allowance.clone()
+                },
+            },
+        );
+        assert!(matches!(result, Err(Error::InvalidArgument)));
+
+        FeeGrant::tx_grant(
+            &mut tx_ctx,
+            types::Grant {
+                grantee: keys::bob::address(),
+                allowance: allowance.clone(),
+            },
+        )
+        .expect("grant should succeed");
+    });
+
+    assert_eq!(
+        FeeGrant::get_allowance(keys::alice::address(), keys::bob::address()),
+        Some(allowance.clone())
+    );
+    assert_eq!(
+        FeeGrant::get_allowances(keys::bob::address()),
+        vec![types::AllowanceInfo {
+            granter: keys::alice::address(),
+            allowance,
+        }]
+    );
+
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        FeeGrant::tx_revoke(
+            &mut tx_ctx,
+            types::Revoke {
+                grantee: keys::bob::address(),
+            },
+        )
+        .expect("revoke should succeed");
+
+        let result = FeeGrant::tx_revoke(
+            &mut tx_ctx,
+            types::Revoke {
+                grantee: keys::bob::address(),
+            },
+        );
+        assert!(matches!(result, Err(Error::NotFound)));
+    });
+
+    assert!(FeeGrant::get_allowances(keys::bob::address()).is_empty());
+}
+
+#[test]
+fn test_query_allowances() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init_feegrant();
+
+    let allowance = types::Allowance {
+        remaining: BaseUnits::new(1_000, Denomination::NATIVE),
+        ..Default::default()
+    };
+    let mut granters = vec![keys::alice::address(), keys::bob::address()];
+    granters.sort();
+    for granter in &granters {
+        FeeGrant::set_allowance(*granter, keys::charlie::address(), Some(allowance.clone()));
+    }
+
+    let mut args = types::AllowancesQuery {
+        grantee: keys::charlie::address(),
+        pagination: PageRequest {
+            cursor: None,
+            limit: 1,
+        },
+    };
+    let page = FeeGrant::query_allowances(&mut ctx, args.clone()).unwrap();
+    assert_eq!(
+        page.items,
+        vec![types::AllowanceInfo {
+            granter: granters[0],
+            allowance: allowance.clone(),
+        }]
+    );
+    assert!(page.next.is_some(), "there should be another page");
+
+    args.pagination.cursor = page.next;
+    let page = FeeGrant::query_allowances(&mut ctx, args.clone()).unwrap();
+    assert_eq!(
+        page.items,
+        vec![types::AllowanceInfo {
+            granter: granters[1],
+            allowance,
+        }]
+    );
+    assert!(page.next.is_none(), "this should be the last page");
+
+    args.pagination.cursor = Some(b"invalid".to_vec());
+    let result = FeeGrant::query_allowances(&mut ctx, args);
+    assert!(matches!(result, Err(Error::InvalidArgument)));
+}
+
+#[test]
+fn test_fee_payment() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<FeeGrantRuntime>(Mode::ExecuteTx, false);
+    init_accounts(&mut ctx);
+    init_feegrant();
+
+    // Bob has no balance to pay any fees.
+    let bob = keys::bob::signer_info();
+    let mut tx = transaction_from(&bob, "accounts.Transfer", 400);
+    let result = accounts::Module::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    // There is no allowance to pay from yet.
+    tx.auth_info.fee_granter = Some(keys::alice::address());
+    let result = accounts::Module::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    // Alice grants Bob an allowance.
+    FeeGrant::set_allowance(
+        keys::alice::address(),
+        keys::bob::address(),
+        Some(types::Allowance {
+            remaining: BaseUnits::new(500, Denomination::NATIVE),
+            methods: vec!["accounts.Transfer".to_owned()],
+            ..Default::default()
+        }),
+    );
+
+    // Allowance must be named by the transaction.
+    let mut other = transaction_from(&bob, "accounts.Transfer", 400);
+    let result = accounts::Module::authenticate_tx(&mut ctx, &other);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    // Allowance should not be used for other methods.
+    other.call.method = "consensus.Deposit".to_owned();
+    other.auth_info.fee_granter = Some(keys::alice::address());
+    let result = accounts::Module::authenticate_tx(&mut ctx, &other);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    accounts::Module::authenticate_tx(&mut ctx, &tx)
+        .expect("transaction authentication should succeed");
+    assert_eq!(
+        accounts::Module::get_balance(keys::alice::address(), Denomination::NATIVE).unwrap(),
+        999_600,
+        "fees should be paid by the granter"
+    );
+    assert_eq!(
+        FeeGrant::get_allowance(keys::alice::address(), keys::bob::address())
+            .unwrap()
+            .remaining,
+        BaseUnits::new(100, Denomination::NATIVE),
+        "allowance should be consumed"
+    );
+    assert_eq!(
+        accounts::Module::get_nonce(keys::bob::address()).unwrap(),
+        1,
+        "signer nonce should be incremented"
+    );
+
+    // Remaining allowance is not enough for another transaction.
+    let bob = transaction::SignerInfo::new_sigspec(keys::bob::sigspec(), 1);
+    let mut tx = transaction_from(&bob, "accounts.Transfer", 400);
+    tx.auth_info.fee_granter = Some(keys::alice::address());
+    let result = accounts::Module::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    // Fully used allowances are removed.
+    tx.auth_info.fee.amount = BaseUnits::new(100, Denomination::NATIVE);
+    accounts::Module::authenticate_tx(&mut ctx, &tx)
+        .expect("transaction authentication should succeed");
+    assert_eq!(
+        FeeGrant::get_allowance(keys::alice::address(), keys::bob::address()),
+        None
+    );
+}
+
+#[test]
+fn test_fee_payment_granter_balance() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<FeeGrantRuntime>(Mode::ExecuteTx, false);
+    init_accounts(&mut ctx);
+    init_feegrant();
+
+    // Charlie grants Bob an allowance but has no balance to pay any fees.
+    FeeGrant::set_allowance(
+        keys::charlie::address(),
+        keys::bob::address(),
+        Some(types::Allowance {
+            remaining: BaseUnits::new(500, Denomination::NATIVE),
+            ..Default::default()
+        }),
+    );
+    accounts::Module::transfer(
+        &mut ctx,
+        keys::alice::address(),
+        keys::bob::address(),
+        &BaseUnits::new(1_000, Denomination::NATIVE),
+    )
+    .unwrap();
+
+    // Granter must be able to pay the fee.
+    let bob = keys::bob::signer_info();
+    let mut tx = transaction_from(&bob, "accounts.Transfer", 400);
+    tx.auth_info.fee_granter = Some(keys::charlie::address());
+    let result = accounts::Module::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(result, Err(core::Error::InsufficientFeeBalance)));
+
+    // Fees are paid by the signer when no granter is named.
+    tx.auth_info.fee_granter = None;
+    accounts::Module::authenticate_tx(&mut ctx, &tx)
+        .expect("transaction authentication should succeed");
+    assert_eq!(
+        accounts::Module::get_balance(keys::bob::address(), Denomination::NATIVE).unwrap(),
+        600,
+        "fees should be paid by the signer"
+    );
+    assert_eq!(
+        FeeGrant::get_allowance(keys::charlie::address(), keys::bob::address())
+            .unwrap()
+            .remaining,
+        BaseUnits::new(500, Denomination::NATIVE),
+        "allowance should not be consumed"
+    );
+}
+
+#[test]
+fn test_fee_payment_unsupported() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init_accounts(&mut ctx);
+
+    let mut tx = transaction_from(&keys::alice::signer_info(), "accounts.Transfer", 400);
+    tx.auth_info.fee_granter = Some(keys::bob::address());
+    let result = accounts::Module::authenticate_tx(&mut ctx, &tx);
+    assert!(
+        matches!(result, Err(core::Error::MalformedTransaction(_))),
+        "fee granters should be rejected when fee grants are not supported"
+    );
+}
+
+#[test]
+fn test_fee_grant_gas() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<FeeGrantRuntime>(Mode::ExecuteTx, false);
+    FeeGrant::init(Genesis {
+        parameters: Parameters {
+            gas_costs: super::GasCosts {
+                fee_grant: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let mut tx = transaction_from(&keys::bob::signer_info(), "accounts.Transfer", 400);
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        FeeGrant::before_handle_call(&mut tx_ctx, &call).unwrap();
+        assert_eq!(Core::used_tx_gas(&mut tx_ctx), 0);
+    });
+
+    tx.auth_info.fee_granter = Some(keys::alice::address());
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        FeeGrant::before_handle_call(&mut tx_ctx, &call).unwrap();
+        assert_eq!(
+            Core::used_tx_gas(&mut tx_ctx),
+            100,
+            "fee grant lookup should be charged"
+        );
+    });
+}
+
+#[test]
+fn test_refund_allowance() {
+    let _mock = mock::Mock::default();
+    let (granter, grantee) = (keys::alice::address(), keys::bob::address());
+    let allowance = types::Allowance {
+        remaining: BaseUnits::new(500, Denomination::NATIVE),
+        per_tx_limit: 500,
+        ..Default::default()
+    };
+    FeeGrant::set_allowance(granter, grantee, Some(allowance.clone()));
+
+    // Partially used allowances should be topped up.
+    let used =
+        FeeGrant::use_allowance(granter, grantee, &BaseUnits::new(100, Denomination::NATIVE))
+            .unwrap();
+    FeeGrant::refund_allowance(
+        granter,
+        grantee,
+        used,
+        &BaseUnits::new(60, Denomination::NATIVE),
+    );
+    assert_eq!(
+        FeeGrant::get_allowance(granter, grantee).unwrap().remaining,
+        BaseUnits::new(460, Denomination::NATIVE)
+    );
+
+    // Fully used allowances should be restored.
+    let used =
+        FeeGrant::use_allowance(granter, grantee, &BaseUnits::new(460, Denomination::NATIVE))
+            .unwrap();
+    assert_eq!(FeeGrant::get_allowance(granter, grantee), None);
+    FeeGrant::refund_allowance(
+        granter,
+        grantee,
+        used,
+        &BaseUnits::new(10, Denomination::NATIVE),
+    );
+    assert_eq!(
+        FeeGrant::get_allowance(granter, grantee),
+        Some(types::Allowance {
+            remaining: BaseUnits::new(10, Denomination::NATIVE),
+            ..allowance
+        })
+    );
+
+    // Revoked allowances should not be restored.
+    let used = FeeGrant::use_allowance(granter, grantee, &BaseUnits::new(5, Denomination::NATIVE))
+        .unwrap();
+    FeeGrant::set_allowance(granter, grantee, None);
+    FeeGrant::refund_allowance(
+        granter,
+        grantee,
+        used,
+        &BaseUnits::new(5, Denomination::NATIVE),
+    );
+    assert_eq!(FeeGrant::get_allowance(granter, grantee), None);
+}
+
+#[test]
+fn test_export_state() {
+    init_feegrant();
+
+    let allowance = types::Allowance {
+        remaining: BaseUnits::new(1_000, Denomination::NATIVE),
+        ..Default::default()
+    };
+    FeeGrant::set_allowance(
+        keys::alice::address(),
+        keys::charlie::address(),
+        Some(allowance),
+    );
+
+    let genesis = mock::assert_export_round_trip::<FeeGrant>();
+    assert_eq!(genesis.parameters.max_methods, 2);
+    assert_eq!(genesis.state.len(), 1, "allowance should be exported");
+}
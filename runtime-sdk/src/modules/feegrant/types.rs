@@ -0,0 +1,87 @@
+//! Fee grant module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::{address::Address, pagination::PageRequest, token};
+
+/// A budgeted allowance for paying transaction fees on behalf of the grantee.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Allowance {
+    /// Remaining amount of fees that can be paid using this allowance. Only fees in the same
+    /// denomination can be paid.
+    pub remaining: token::BaseUnits,
+
+    /// Maximum amount of fees that can be paid for a single transaction. The special value of 0
+    /// means that there is no per-transaction limit.
+    #[cbor(optional)]
+    pub per_tx_limit: u128,
+
+    /// Round after which the allowance can no longer be used.
+    #[cbor(optional)]
+    pub expiration: Option<u64>,
+
+    /// Methods for which the allowance can be used. An empty list means that the allowance can be
+    /// used for any method.
+    #[cbor(optional)]
+    pub methods: Vec<String>,
+}
+
+impl Allowance {
+    /// Whether the allowance can be used to pay the given fee for the given method at the given
+    /// round.
+    pub fn covers(&self, fee: &token::BaseUnits, method: &str, round: u64) -> bool {
+        if let Some(expiration) = self.expiration {
+            if round > expiration {
+                return false;
+            }
+        }
+        if fee.denomination() != self.remaining.denomination() {
+            return false;
+        }
+        if fee.amount() > self.remaining.amount() {
+            return false;
+        }
+        if self.per_tx_limit > 0 && fee.amount() > self.per_tx_limit {
+            return false;
+        }
+        if !self.methods.is_empty() && !self.methods.iter().any(|m| m == method) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Grant call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Grant {
+    pub grantee: Address,
+    pub allowance: Allowance,
+}
+
+/// Revoke call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Revoke {
+    pub grantee: Address,
+}
+
+/// Arguments for the Allowance query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AllowanceQuery {
+    pub granter: Address,
+    pub grantee: Address,
+}
+
+/// Arguments for the Allowances query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AllowancesQuery {
+    pub grantee: Address,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
+
+/// Information about an allowance given to a grantee.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct AllowanceInfo {
+    pub granter: Address,
+    pub allowance: Allowance,
+}
@@ -1,7 +1,17 @@
 //! Runtime modules included with the SDK.
 
+pub mod access;
 pub mod accounts;
+pub mod airdrop;
+pub mod bridge;
 pub mod consensus;
 pub mod consensus_accounts;
 pub mod core;
+pub mod escrow;
+pub mod feegrant;
+pub mod names;
+pub mod oracle;
 pub mod rewards;
+pub mod rofl;
+pub mod safety;
+pub mod scheduler;
@@ -0,0 +1,552 @@
+//! Name service module mapping human-readable names to addresses.
+//!
+//! Names are registered for a number of registration periods, paid for upfront. Registration and
+//! renewal fees are transferred to the treasury. Once a registration expires, the name no longer
+//! resolves and can be registered by anyone. Accounts can also set a reverse record, mapping their
+//! address back to a name that resolves to it. In case the runtime requires storage deposits, the
+//! account pays the deposit for its reverse record until the record is removed.
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{
+        self,
+        core::{storage_deposit, API as _},
+    },
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{
+        address::Address,
+        token::{self, Denomination},
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "names";
+
+/// Maximum length of a name.
+const MAX_NAME_LENGTH: u32 = 64;
+
+/// Errors emitted by the names module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("invalid name")]
+    #[sdk_error(code = 2)]
+    InvalidName,
+
+    #[error("name already registered")]
+    #[sdk_error(code = 3)]
+    NameTaken,
+
+    #[error("unknown name")]
+    #[sdk_error(code = 4)]
+    UnknownName,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 5)]
+    Forbidden,
+
+    #[error("insufficient balance")]
+    #[sdk_error(code = 6)]
+    InsufficientBalance,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the names module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    NameRegistered {
+        name: String,
+        owner: Address,
+        expiration: u64,
+    },
+
+    #[sdk_event(code = 2)]
+    NameRenewed { name: String, expiration: u64 },
+
+    #[sdk_event(code = 3)]
+    NameTransferred {
+        name: String,
+        from: Address,
+        to: Address,
+    },
+
+    #[sdk_event(code = 4)]
+    TargetChanged { name: String, address: Address },
+
+    #[sdk_event(code = 5)]
+    ReverseChanged {
+        address: Address,
+        name: Option<String>,
+    },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_register: u64,
+    pub tx_renew: u64,
+    pub tx_transfer: u64,
+    pub tx_set_target: u64,
+    pub tx_set_reverse: u64,
+    pub tx_resolve: u64,
+    pub tx_reverse_resolve: u64,
+}
+
+/// Parameters for the names module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Duration of a registration period (in seconds).
+    #[cbor(optional)]
+    pub period: u64,
+    /// Fee for a single registration period, in the native denomination.
+    #[cbor(optional)]
+    pub fee_per_period: u128,
+    /// Maximum number of registration periods that can be paid for at once.
+    #[cbor(optional)]
+    pub max_periods: u64,
+    /// Minimum length of a name.
+    #[cbor(optional)]
+    pub min_name_length: u32,
+    /// Address receiving the registration fees. If not specified, fees are transferred to the
+    /// common pool.
+    #[cbor(optional)]
+    pub treasury: Option<Address>,
+}
+
+impl Parameters {
+    /// Address receiving the registration fees.
+    pub fn treasury(&self) -> Address {
+        self.treasury
+            .unwrap_or(*modules::accounts::ADDRESS_COMMON_POOL)
+    }
+}
+
+/// Errors emitted during names parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("registration period must be non-zero")]
+    ZeroPeriod,
+
+    #[error("invalid minimum name length")]
+    InvalidMinNameLength,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        if self.period == 0 {
+            return Err(ParameterValidationError::ZeroPeriod);
+        }
+        if self.min_name_length == 0 || self.min_name_length > MAX_NAME_LENGTH {
+            return Err(ParameterValidationError::InvalidMinNameLength);
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the names module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of names to name records.
+    pub const NAMES: &[u8] = &[0x01];
+    /// Map of addresses to names of their reverse records.
+    pub const REVERSE: &[u8] = &[0x02];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[NAMES, REVERSE];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Resolve the given name, failing in case it is not registered or the registration has
+    /// expired.
+    fn resolve<C: Context>(ctx: &C, name: &str) -> Result<types::NameRecord, Error>;
+
+    /// Name of the reverse record of the given address. Reverse records only resolve as long as
+    /// the name resolves to the address.
+    fn reverse<C: Context>(ctx: &C, address: Address) -> Result<String, Error>;
+}
+
+pub struct Module<Accounts: modules::accounts::API> {
+    _accounts: std::marker::PhantomData<Accounts>,
+}
+
+/// Whether the given string is a valid name.
+///
+/// Names consist of lowercase ASCII letters, digits and hyphens, and must not start or end with a
+/// hyphen.
+pub fn is_valid_name(name: &str, min_length: u32) -> bool {
+    name.len() >= min_length as usize
+        && name.len() <= MAX_NAME_LENGTH as usize
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    fn get_record(name: &str) -> Option<types::NameRecord> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let names = storage::TypedStore::new(storage::PrefixStore::new(store, &state::NAMES));
+            names.get(name)
+        })
+    }
+
+    fn set_record(name: &str, record: types::NameRecord) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut names =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::NAMES));
+            names.insert(name, record);
+        });
+    }
+
+    fn get_reverse(address: Address) -> Option<String> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let reverse =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::REVERSE));
+            reverse.get(address)
+        })
+    }
+
+    fn set_reverse(address: Address, name: Option<&str>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut reverse =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::REVERSE));
+            match name {
+                Some(name) => reverse.insert(address, name.to_owned()),
+                None => reverse.remove(address),
+            }
+        });
+    }
+
+    /// Number of bytes of state covered by the storage deposit of a reverse record.
+    fn reverse_bytes(name: &str) -> u64 {
+        (Address::SIZE + name.len()) as u64
+    }
+
+    /// Fetch a name record that is owned by the caller and has not yet expired.
+    fn get_owned_record<C: TxContext>(ctx: &C, name: &str) -> Result<types::NameRecord, Error> {
+        let record = Self::resolve(ctx, name)?;
+        if record.owner != ctx.tx_caller_address() {
+            return Err(Error::Forbidden);
+        }
+        Ok(record)
+    }
+
+    /// Total duration and fee of the given number of registration periods.
+    fn periods_cost(params: &Parameters, periods: u64) -> Result<(u64, u128), Error> {
+        if periods == 0 || periods > params.max_periods {
+            return Err(Error::InvalidArgument);
+        }
+        let duration = params
+            .period
+            .checked_mul(periods)
+            .ok_or(Error::InvalidArgument)?;
+        let fee = params
+            .fee_per_period
+            .checked_mul(periods as u128)
+            .ok_or(Error::InvalidArgument)?;
+        Ok((duration, fee))
+    }
+
+    /// Transfer the given registration fee from the caller to the treasury.
+    fn pay_fee<C: TxContext>(ctx: &mut C, params: &Parameters, fee: u128) -> Result<(), Error> {
+        let payer = ctx.tx_caller_address();
+        Accounts::transfer(
+            ctx,
+            payer,
+            params.treasury(),
+            &token::BaseUnits::new(fee, Denomination::NATIVE),
+        )
+        .map_err(|_| Error::InsufficientBalance)
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn resolve<C: Context>(ctx: &C, name: &str) -> Result<types::NameRecord, Error> {
+        let record = Self::get_record(name).ok_or(Error::UnknownName)?;
+        if record.expiration <= ctx.time().timestamp() {
+            return Err(Error::UnknownName);
+        }
+        Ok(record)
+    }
+
+    fn reverse<C: Context>(ctx: &C, address: Address) -> Result<String, Error> {
+        let name = Self::get_reverse(address).ok_or(Error::UnknownName)?;
+        if Self::resolve(ctx, &name)?.address != address {
+            return Err(Error::UnknownName);
+        }
+        Ok(name)
+    }
+}
+
+#[sdk_derive(Module)]
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Register a name that is not registered or whose registration has expired. The caller
+    /// becomes the owner of the name.
+    #[handler(call = "names.Register")]
+    fn tx_register<C: TxContext>(ctx: &mut C, body: types::Register) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_register)?;
+
+        if !is_valid_name(&body.name, params.min_name_length) {
+            return Err(Error::InvalidName);
+        }
+        if Self::resolve(ctx, &body.name).is_ok() {
+            return Err(Error::NameTaken);
+        }
+        let (duration, fee) = Self::periods_cost(&params, body.periods)?;
+        let expiration = ctx
+            .time()
+            .timestamp()
+            .checked_add(duration)
+            .ok_or(Error::InvalidArgument)?;
+
+        if ctx.is_check_only() {
+            return Ok(expiration);
+        }
+
+        Self::pay_fee(ctx, &params, fee)?;
+        let owner = ctx.tx_caller_address();
+        Self::set_record(
+            &body.name,
+            types::NameRecord {
+                owner,
+                address: body.address,
+                eth_address: body.eth_address,
+                expiration,
+            },
+        );
+
+        ctx.emit_event(Event::NameRegistered {
+            name: body.name,
+            owner,
+            expiration,
+        });
+
+        Ok(expiration)
+    }
+
+    /// Extend the registration of a name that has not yet expired. Anyone can renew a name.
+    #[handler(call = "names.Renew")]
+    fn tx_renew<C: TxContext>(ctx: &mut C, body: types::Renew) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_renew)?;
+
+        let mut record = Self::resolve(ctx, &body.name)?;
+        let (duration, fee) = Self::periods_cost(&params, body.periods)?;
+        record.expiration = record
+            .expiration
+            .checked_add(duration)
+            .ok_or(Error::InvalidArgument)?;
+        let expiration = record.expiration;
+
+        if ctx.is_check_only() {
+            return Ok(expiration);
+        }
+
+        Self::pay_fee(ctx, &params, fee)?;
+        Self::set_record(&body.name, record);
+
+        ctx.emit_event(Event::NameRenewed {
+            name: body.name,
+            expiration,
+        });
+
+        Ok(expiration)
+    }
+
+    /// Transfer ownership of a name. Only the owner can transfer a name.
+    #[handler(call = "names.Transfer")]
+    fn tx_transfer<C: TxContext>(ctx: &mut C, body: types::Transfer) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_transfer)?;
+
+        let mut record = Self::get_owned_record(ctx, &body.name)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        let from = record.owner;
+        record.owner = body.to;
+        Self::set_record(&body.name, record);
+
+        ctx.emit_event(Event::NameTransferred {
+            name: body.name,
+            from,
+            to: body.to,
+        });
+
+        Ok(())
+    }
+
+    /// Change the addresses that a name resolves to. Only the owner can change them.
+    #[handler(call = "names.SetTarget")]
+    fn tx_set_target<C: TxContext>(ctx: &mut C, body: types::SetTarget) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_set_target)?;
+
+        let mut record = Self::get_owned_record(ctx, &body.name)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        record.address = body.address;
+        record.eth_address = body.eth_address;
+        Self::set_record(&body.name, record);
+
+        ctx.emit_event(Event::TargetChanged {
+            name: body.name,
+            address: body.address,
+        });
+
+        Ok(())
+    }
+
+    /// Set or remove the reverse record of the caller.
+    #[handler(call = "names.SetReverse")]
+    fn tx_set_reverse<C: TxContext>(ctx: &mut C, body: types::SetReverse) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_set_reverse)?;
+
+        let caller = ctx.tx_caller_address();
+        if let Some(name) = &body.name {
+            if Self::resolve(ctx, name)?.address != caller {
+                return Err(Error::Forbidden);
+            }
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        // Release the storage deposit of any previous reverse record and charge it for the new one.
+        if let Some(previous) = Self::get_reverse(caller) {
+            storage_deposit::refund::<_, Accounts>(ctx, caller, Self::reverse_bytes(&previous))
+                .map_err(|_| Error::InsufficientBalance)?;
+        }
+        if let Some(name) = &body.name {
+            storage_deposit::charge::<_, Accounts>(ctx, caller, Self::reverse_bytes(name))
+                .map_err(|_| Error::InsufficientBalance)?;
+        }
+        Self::set_reverse(caller, body.name.as_deref());
+
+        ctx.emit_event(Event::ReverseChanged {
+            address: caller,
+            name: body.name,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a name. This is exposed as a call so that it can be used via subcalls, for example
+    /// from smart contracts.
+    #[handler(call = "names.Resolve")]
+    fn tx_resolve<C: TxContext>(
+        ctx: &mut C,
+        body: types::NameQuery,
+    ) -> Result<types::NameRecord, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_resolve)?;
+
+        Self::resolve(ctx, &body.name)
+    }
+
+    /// Resolve the reverse record of an address. This is exposed as a call so that it can be used
+    /// via subcalls, for example from smart contracts.
+    #[handler(call = "names.ReverseResolve")]
+    fn tx_reverse_resolve<C: TxContext>(
+        ctx: &mut C,
+        body: types::ReverseQuery,
+    ) -> Result<String, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_reverse_resolve)?;
+
+        Self::reverse(ctx, body.address)
+    }
+
+    #[handler(query = "names.Record")]
+    fn query_record<C: Context>(
+        ctx: &mut C,
+        args: types::NameQuery,
+    ) -> Result<types::NameRecord, Error> {
+        Self::resolve(ctx, &args.name)
+    }
+
+    #[handler(query = "names.Reverse")]
+    fn query_reverse<C: Context>(ctx: &mut C, args: types::ReverseQuery) -> Result<String, Error> {
+        Self::reverse(ctx, args.address)
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
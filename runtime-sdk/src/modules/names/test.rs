@@ -0,0 +1,356 @@
+//! Tests for the names module.
+use crate::{
+    context::{BatchContext, Context},
+    module::{self, Module as _},
+    modules::{
+        accounts::Module as Accounts,
+        core::{self, storage_deposit},
+    },
+    testing::{keys, mock},
+    types::{address::Address, transaction},
+};
+
+use super::{is_valid_name, types, Error, Genesis, Parameters, API as _};
+
+type Names = super::Module<Accounts>;
+
+const PERIOD: u64 = 1_000;
+
+fn test_params() -> Parameters {
+    Parameters {
+        gas_costs: Default::default(),
+        period: PERIOD,
+        fee_per_period: 100,
+        max_periods: 5,
+        min_name_length: 3,
+        treasury: Some(keys::charlie::address()),
+    }
+}
+
+fn init<C: Context>(ctx: &mut C) {
+    mock::init_balances(
+        ctx,
+        &[
+            (keys::alice::address(), 1_000),
+            (keys::bob::address(), 1_000),
+        ],
+    );
+    Names::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+}
+
+fn register(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    name: &str,
+    periods: u64,
+) -> Result<u64, Error> {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.call.method = "names.Register".to_owned();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Names::tx_register(
+            &mut tx_ctx,
+            types::Register {
+                name: name.to_owned(),
+                address: keys::alice::address(),
+                eth_address: Some([0xaa; 20]),
+                periods,
+            },
+        )
+    })
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    test_params()
+        .validate_basic()
+        .expect("test parameters should be valid");
+
+    for (modify, check) in [
+        (
+            (|p: &mut Parameters| p.period = 0) as fn(&mut Parameters),
+            "period should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| p.min_name_length = 0,
+            "minimum name length should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| p.min_name_length = 65,
+            "minimum name length should not exceed the maximum name length",
+        ),
+    ] {
+        let mut params = test_params();
+        modify(&mut params);
+        assert!(params.validate_basic().is_err(), "{check}");
+    }
+}
+
+#[test]
+fn test_valid_names() {
+    for (name, valid) in [
+        ("abc", true),
+        ("oasis-sdk", true),
+        ("v2", false),
+        ("Upper", false),
+        ("-abc", false),
+        ("abc-", false),
+        ("a.b.c", false),
+        ("ünï", false),
+    ] {
+        assert_eq!(is_valid_name(name, 3), valid, "{name}");
+    }
+    assert!(!is_valid_name(&"a".repeat(65), 3));
+}
+
+#[test]
+fn test_register_and_expire() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    let result = register(&mut mock, &alice, "X", 1);
+    assert!(matches!(result, Err(Error::InvalidName)));
+    let result = register(&mut mock, &alice, "alice", 6);
+    assert!(matches!(result, Err(Error::InvalidArgument)));
+
+    // Fees are transferred to the treasury.
+    mock.runtime_header.timestamp = 10;
+    let expiration = register(&mut mock, &alice, "alice", 2).expect("register should succeed");
+    assert_eq!(expiration, 10 + 2 * PERIOD);
+    assert_eq!(mock::balance(keys::alice::address()), 800);
+    assert_eq!(mock::balance(keys::charlie::address()), 200);
+
+    let result = register(&mut mock, &bob, "alice", 1);
+    assert!(matches!(result, Err(Error::NameTaken)));
+
+    let ctx = mock.create_ctx();
+    let record = Names::resolve(&ctx, "alice").expect("name should resolve");
+    assert_eq!(
+        record,
+        types::NameRecord {
+            owner: keys::alice::address(),
+            address: keys::alice::address(),
+            eth_address: Some([0xaa; 20]),
+            expiration,
+        }
+    );
+
+    // Expired names no longer resolve and can be registered by anyone.
+    mock.runtime_header.timestamp = expiration;
+    let ctx = mock.create_ctx();
+    assert!(matches!(
+        Names::resolve(&ctx, "alice"),
+        Err(Error::UnknownName)
+    ));
+    register(&mut mock, &bob, "alice", 1).expect("expired name should be available");
+    let ctx = mock.create_ctx();
+    assert_eq!(
+        Names::resolve(&ctx, "alice").unwrap().owner,
+        keys::bob::address()
+    );
+}
+
+#[test]
+fn test_manage() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let expiration = register(&mut mock, &alice, "alice", 1).expect("register should succeed");
+
+    // Anyone can renew a name.
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![bob.clone()];
+    let renewed = ctx
+        .with_tx(tx.into(), |mut tx_ctx, _call| {
+            Names::tx_renew(
+                &mut tx_ctx,
+                types::Renew {
+                    name: "alice".to_owned(),
+                    periods: 1,
+                },
+            )
+        })
+        .expect("renew should succeed");
+    assert_eq!(renewed, expiration + PERIOD);
+    assert_eq!(mock::balance(keys::bob::address()), 900);
+
+    // Only the owner can change the target and transfer the name.
+    let set_target = |mock: &mut mock::Mock, who: &transaction::SignerInfo| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Names::tx_set_target(
+                &mut tx_ctx,
+                types::SetTarget {
+                    name: "alice".to_owned(),
+                    address: keys::bob::address(),
+                    eth_address: None,
+                },
+            )
+        })
+    };
+    let transfer = |mock: &mut mock::Mock, who: &transaction::SignerInfo| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Names::tx_transfer(
+                &mut tx_ctx,
+                types::Transfer {
+                    name: "alice".to_owned(),
+                    to: keys::bob::address(),
+                },
+            )
+        })
+    };
+    assert!(matches!(set_target(&mut mock, &bob), Err(Error::Forbidden)));
+    assert!(matches!(transfer(&mut mock, &bob), Err(Error::Forbidden)));
+    set_target(&mut mock, &alice).expect("owner should be able to change the target");
+    transfer(&mut mock, &alice).expect("owner should be able to transfer the name");
+
+    let ctx = mock.create_ctx();
+    let record = Names::resolve(&ctx, "alice").unwrap();
+    assert_eq!(record.owner, keys::bob::address());
+    assert_eq!(record.address, keys::bob::address());
+    assert_eq!(record.eth_address, None);
+}
+
+#[test]
+fn test_reverse() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+    core::Module::<mock::Config>::set_params(core::Parameters {
+        max_batch_gas: u64::MAX,
+        storage_deposit: storage_deposit::Parameters {
+            deposit_per_byte: 1,
+        },
+        ..Default::default()
+    });
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let expiration = register(&mut mock, &alice, "alice", 1).expect("register should succeed");
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+
+    let set_reverse = |mock: &mut mock::Mock, who: &transaction::SignerInfo, name: Option<&str>| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Names::tx_set_reverse(
+                &mut tx_ctx,
+                types::SetReverse {
+                    name: name.map(ToOwned::to_owned),
+                },
+            )
+        })
+    };
+
+    // Reverse records can only point to names resolving to the caller.
+    let result = set_reverse(&mut mock, &bob, Some("alice"));
+    assert!(matches!(result, Err(Error::Forbidden)));
+    set_reverse(&mut mock, &alice, Some("alice")).expect("set reverse should succeed");
+
+    // The storage deposit for the reverse record should be paid by the caller.
+    let deposit = (Address::SIZE + "alice".len()) as u128;
+    assert_eq!(mock::balance(keys::alice::address()), 900 - deposit);
+    assert_eq!(
+        storage_deposit::get_deposit(keys::alice::address()).amount,
+        deposit
+    );
+
+    let ctx = mock.create_ctx();
+    assert_eq!(
+        Names::reverse(&ctx, keys::alice::address()).unwrap(),
+        "alice"
+    );
+    assert!(matches!(
+        Names::reverse(&ctx, keys::bob::address()),
+        Err(Error::UnknownName)
+    ));
+
+    // Reverse records can also be resolved via calls.
+    let mut ctx = mock.create_ctx();
+    let tx = mock::transaction_from(&bob, "names.ReverseResolve");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let name = Names::tx_reverse_resolve(
+            &mut tx_ctx,
+            types::ReverseQuery {
+                address: keys::alice::address(),
+            },
+        )
+        .expect("reverse resolve should succeed");
+        assert_eq!(name, "alice");
+    });
+
+    // Reverse records stop resolving together with the name.
+    mock.runtime_header.timestamp = expiration;
+    let ctx = mock.create_ctx();
+    assert!(matches!(
+        Names::reverse(&ctx, keys::alice::address()),
+        Err(Error::UnknownName)
+    ));
+
+    mock.runtime_header.timestamp = 0;
+    set_reverse(&mut mock, &alice, None).expect("remove reverse should succeed");
+    let ctx = mock.create_ctx();
+    assert!(matches!(
+        Names::reverse(&ctx, keys::alice::address()),
+        Err(Error::UnknownName)
+    ));
+
+    // Removing the reverse record should refund the storage deposit.
+    assert_eq!(mock::balance(keys::alice::address()), 900);
+    assert_eq!(
+        storage_deposit::get_deposit(keys::alice::address()),
+        storage_deposit::Deposit::default()
+    );
+    storage_deposit::check_invariants::<Accounts>().expect("invariants should hold");
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    init(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    register(&mut mock, &alice, "alice", 1).expect("register should succeed");
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.auth_info.signer_info = vec![alice];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Names::tx_set_reverse(
+            &mut tx_ctx,
+            types::SetReverse {
+                name: Some("alice".to_owned()),
+            },
+        )
+    })
+    .expect("set reverse should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Names>();
+    assert_eq!(genesis.parameters.period, PERIOD);
+    for prefix in [super::state::NAMES, super::state::REVERSE] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "names and reverse records should be exported"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Name service module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::address::Address;
+
+/// A registered name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct NameRecord {
+    /// Address of the account that owns the name.
+    pub owner: Address,
+    /// Address that the name resolves to.
+    pub address: Address,
+    /// Ethereum-compatible address that the name resolves to, if any.
+    #[cbor(optional)]
+    pub eth_address: Option<[u8; 20]>,
+    /// Timestamp at which the registration expires.
+    pub expiration: u64,
+}
+
+/// Register name call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Register {
+    /// Name to register.
+    pub name: String,
+    /// Address that the name should resolve to.
+    pub address: Address,
+    /// Ethereum-compatible address that the name should resolve to, if any.
+    #[cbor(optional)]
+    pub eth_address: Option<[u8; 20]>,
+    /// Number of registration periods to pay for.
+    pub periods: u64,
+}
+
+/// Renew name call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Renew {
+    /// Name to renew.
+    pub name: String,
+    /// Number of registration periods to pay for.
+    pub periods: u64,
+}
+
+/// Transfer name call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Transfer {
+    /// Name to transfer.
+    pub name: String,
+    /// Address of the new owner.
+    pub to: Address,
+}
+
+/// Set target call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct SetTarget {
+    /// Name to update.
+    pub name: String,
+    /// Address that the name should resolve to.
+    pub address: Address,
+    /// Ethereum-compatible address that the name should resolve to, if any.
+    #[cbor(optional)]
+    pub eth_address: Option<[u8; 20]>,
+}
+
+/// Set reverse record call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct SetReverse {
+    /// Name that the caller's address should resolve to. The name must resolve to the caller's
+    /// address. If not specified, the reverse record is removed.
+    #[cbor(optional)]
+    pub name: Option<String>,
+}
+
+/// Name query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct NameQuery {
+    /// Name to look up.
+    pub name: String,
+}
+
+/// Reverse record query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct ReverseQuery {
+    /// Address to look up.
+    pub address: Address,
+}
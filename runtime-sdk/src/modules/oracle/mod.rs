@@ -0,0 +1,446 @@
+//! Oracle module for externally-posted data feeds.
+//!
+//! Allowlisted reporters post values for each configured feed during reporting rounds spanning a
+//! number of runtime rounds. At the end of each reporting round the reported values are aggregated
+//! by taking their median, given that enough reports were submitted. Aggregated values can be
+//! read by other modules, via queries and via subcalls, for example from smart contracts.
+//!
+//! Feeds can also provide the conversion rates used for paying transaction fees in non-native
+//! denominations (see `types::FeeConversion`).
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::address::Address,
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "oracle";
+
+/// Errors emitted by the oracle module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("unknown feed")]
+    #[sdk_error(code = 1)]
+    UnknownFeed,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 2)]
+    Forbidden,
+
+    #[error("invalid reporting round")]
+    #[sdk_error(code = 3)]
+    InvalidRound,
+
+    #[error("already reported")]
+    #[sdk_error(code = 4)]
+    AlreadyReported,
+
+    #[error("no value")]
+    #[sdk_error(code = 5)]
+    NoValue,
+
+    #[error("stale value")]
+    #[sdk_error(code = 6)]
+    StaleValue,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the oracle module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    ValueReported {
+        feed: String,
+        reporter: Address,
+        round: u64,
+        value: u128,
+    },
+
+    #[sdk_event(code = 2)]
+    ValueAggregated {
+        feed: String,
+        round: u64,
+        value: u128,
+        num_reports: u32,
+    },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_submit: u64,
+    pub tx_read: u64,
+}
+
+/// Parameters for the oracle module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Configured data feeds.
+    #[cbor(optional)]
+    pub feeds: Vec<types::FeedConfig>,
+}
+
+impl Parameters {
+    /// Configuration of the given feed.
+    pub fn feed(&self, id: &str) -> Option<&types::FeedConfig> {
+        self.feeds.iter().find(|feed| feed.id == id)
+    }
+}
+
+/// Errors emitted during oracle parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("duplicate feed: {0}")]
+    DuplicateFeed(String),
+
+    #[error("invalid round duration for feed {0}")]
+    InvalidRoundDuration(String),
+
+    #[error("invalid minimum number of reports for feed {0}")]
+    InvalidMinReports(String),
+
+    #[error("invalid fee conversion for feed {0}")]
+    InvalidFeeConversion(String),
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        for (i, feed) in self.feeds.iter().enumerate() {
+            if self.feeds[..i].iter().any(|other| other.id == feed.id) {
+                return Err(ParameterValidationError::DuplicateFeed(feed.id.clone()));
+            }
+            if feed.round_duration == 0 {
+                return Err(ParameterValidationError::InvalidRoundDuration(
+                    feed.id.clone(),
+                ));
+            }
+            if feed.min_reports == 0 || feed.min_reports as usize > feed.reporters.len() {
+                return Err(ParameterValidationError::InvalidMinReports(feed.id.clone()));
+            }
+            if let Some(conversion) = &feed.fee_conversion {
+                if conversion.denomination.is_native()
+                    || conversion.denomination_amount == 0
+                    || self.feeds[..i].iter().any(|other| {
+                        other.fee_conversion.as_ref().map(|c| &c.denomination)
+                            == Some(&conversion.denomination)
+                    })
+                {
+                    return Err(ParameterValidationError::InvalidFeeConversion(
+                        feed.id.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the oracle module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of feed identifiers to reports of the current reporting round.
+    pub const REPORTS: &[u8] = &[0x01];
+    /// Map of feed identifiers to latest aggregated values.
+    pub const OBSERVATIONS: &[u8] = &[0x02];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[REPORTS, OBSERVATIONS];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the latest aggregated value of the given feed, failing in case it is stale.
+    fn get_value<C: Context>(ctx: &C, feed: &str) -> Result<types::Observation, Error>;
+
+    /// Fetch the latest aggregated value of the given feed, regardless of its staleness.
+    fn get_observation(feed: &str) -> Option<types::Observation>;
+}
+
+pub struct Module;
+
+impl Module {
+    fn get_reports(feed: &str) -> types::RoundReports {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let reports =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::REPORTS));
+            reports.get(feed).unwrap_or_default()
+        })
+    }
+
+    fn set_reports(feed: &str, round_reports: types::RoundReports) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut reports =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::REPORTS));
+            if round_reports.values.is_empty() {
+                reports.remove(feed);
+            } else {
+                reports.insert(feed, round_reports);
+            }
+        });
+    }
+
+    fn set_observation(feed: &str, observation: types::Observation) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut observations =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::OBSERVATIONS));
+            observations.insert(feed, observation);
+        });
+    }
+
+    /// Aggregate the reports of a finished reporting round of the given feed. Rounds with fewer
+    /// than the minimum number of reports are discarded.
+    fn aggregate<C: Context>(ctx: &mut C, feed: &types::FeedConfig, reports: types::RoundReports) {
+        let num_reports = reports.values.len() as u32;
+        if num_reports < feed.min_reports {
+            return;
+        }
+
+        let mut values: Vec<u128> = reports.values.into_values().collect();
+        let value = median(&mut values);
+
+        Self::set_observation(
+            &feed.id,
+            types::Observation {
+                round: reports.round,
+                value,
+                num_reports,
+                aggregated_at: ctx.time().round(),
+            },
+        );
+        if let Some(conversion) = &feed.fee_conversion {
+            Self::set_fee_conversion_rate::<C>(conversion, value);
+        }
+
+        ctx.emit_event(Event::ValueAggregated {
+            feed: feed.id.clone(),
+            round: reports.round,
+            value,
+            num_reports,
+        });
+    }
+
+    /// Update the fee conversion rate provided by a feed. A zero value clears the rate.
+    fn set_fee_conversion_rate<C: Context>(conversion: &types::FeeConversion, value: u128) {
+        let rate = Some(modules::core::types::FeeConversionRate {
+            native_amount: value,
+            denomination_amount: conversion.denomination_amount,
+        })
+        .filter(|rate| rate.is_valid());
+        <C::Runtime as Runtime>::Core::set_fee_conversion_rate(
+            conversion.denomination.clone(),
+            rate,
+        )
+        .unwrap(); // Denomination is validated to be non-native.
+    }
+}
+
+/// Median of the given non-empty list of values. For an even number of values, the mean of the
+/// two middle values is taken, rounding down.
+fn median(values: &mut [u128]) -> u128 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        return values[mid];
+    }
+    let (a, b) = (values[mid - 1], values[mid]);
+    a / 2 + b / 2 + (a % 2 + b % 2) / 2
+}
+
+impl API for Module {
+    fn get_value<C: Context>(ctx: &C, feed: &str) -> Result<types::Observation, Error> {
+        let params = Self::params();
+        let cfg = params.feed(feed).ok_or(Error::UnknownFeed)?;
+        let observation = Self::get_observation(feed).ok_or(Error::NoValue)?;
+        if cfg.max_staleness > 0
+            && ctx.time().round().saturating_sub(observation.aggregated_at) > cfg.max_staleness
+        {
+            return Err(Error::StaleValue);
+        }
+        Ok(observation)
+    }
+
+    fn get_observation(feed: &str) -> Option<types::Observation> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let observations =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::OBSERVATIONS));
+            observations.get(feed)
+        })
+    }
+}
+
+#[sdk_derive(Module)]
+impl Module {
+    const NAME: &'static str = MODULE_NAME;
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Report a value for the current reporting round of a feed. Only allowlisted reporters of
+    /// the feed can report values and each of them can report once per reporting round.
+    #[handler(call = "oracle.Submit")]
+    fn tx_submit<C: TxContext>(ctx: &mut C, body: types::Submit) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_submit)?;
+
+        let feed = params.feed(&body.feed).ok_or(Error::UnknownFeed)?;
+        let reporter = ctx.tx_caller_address();
+        if !feed.reporters.contains(&reporter) {
+            return Err(Error::Forbidden);
+        }
+        if body.round != feed.reporting_round(ctx.time().round()) {
+            return Err(Error::InvalidRound);
+        }
+
+        let mut reports = Self::get_reports(&body.feed);
+        if reports.round != body.round {
+            // This is the first report of the reporting round.
+            reports = types::RoundReports {
+                round: body.round,
+                values: Default::default(),
+            };
+        }
+        if reports.values.contains_key(&reporter) {
+            return Err(Error::AlreadyReported);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        reports.values.insert(reporter, body.value);
+        Self::set_reports(&body.feed, reports);
+
+        ctx.emit_event(Event::ValueReported {
+            feed: body.feed,
+            reporter,
+            round: body.round,
+            value: body.value,
+        });
+
+        Ok(())
+    }
+
+    /// Read the latest aggregated value of a feed. This is exposed as a call so that it can be
+    /// used via subcalls, for example from smart contracts.
+    #[handler(call = "oracle.Read")]
+    fn tx_read<C: TxContext>(
+        ctx: &mut C,
+        body: types::FeedQuery,
+    ) -> Result<types::Observation, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_read)?;
+
+        Self::get_value(ctx, &body.feed)
+    }
+
+    /// Latest aggregated value of a feed.
+    #[handler(query = "oracle.Value")]
+    fn query_value<C: Context>(
+        ctx: &mut C,
+        args: types::FeedQuery,
+    ) -> Result<types::Observation, Error> {
+        Self::get_value(ctx, &args.feed)
+    }
+
+    /// Reports submitted for the current reporting round of a feed.
+    #[handler(query = "oracle.Reports")]
+    fn query_reports<C: Context>(
+        _ctx: &mut C,
+        args: types::FeedQuery,
+    ) -> Result<types::RoundReports, Error> {
+        let params = Self::params();
+        params.feed(&args.feed).ok_or(Error::UnknownFeed)?;
+        Ok(Self::get_reports(&args.feed))
+    }
+}
+
+impl module::TransactionHandler for Module {}
+
+impl module::BlockHandler for Module {
+    fn end_block<C: Context>(ctx: &mut C) {
+        // Aggregate reports of reporting rounds that end with this block.
+        let next_round = ctx.time().round().saturating_add(1);
+        let params = Self::params();
+        for feed in &params.feeds {
+            let reports = Self::get_reports(&feed.id);
+            if reports.values.is_empty() || reports.round >= feed.reporting_round(next_round) {
+                continue;
+            }
+
+            Self::set_reports(&feed.id, Default::default());
+            Self::aggregate(ctx, feed, reports);
+        }
+
+        // Stop using stale values as fee conversion rates.
+        for feed in &params.feeds {
+            let conversion = match &feed.fee_conversion {
+                Some(conversion) => conversion,
+                None => continue,
+            };
+            if matches!(Self::get_value(ctx, &feed.id), Err(Error::StaleValue))
+                && <C::Runtime as Runtime>::Core::fee_conversion_rate(&conversion.denomination)
+                    .is_some()
+            {
+                Self::set_fee_conversion_rate::<C>(conversion, 0);
+            }
+        }
+    }
+}
+
+impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
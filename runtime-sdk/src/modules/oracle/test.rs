@@ -0,0 +1,376 @@
+//! Tests for the oracle module.
+use std::collections::BTreeMap;
+
+use crate::{
+    context::BatchContext,
+    module::{self, BlockHandler, TransactionHandler as _},
+    modules::core::{self, API as _},
+    testing::{keys, mock},
+    types::{token, transaction},
+};
+
+use super::{types, Error, Genesis, Module as Oracle, Parameters, API as _};
+
+const FEED: &str = "ROSE/USD";
+
+fn test_params() -> Parameters {
+    Parameters {
+        gas_costs: Default::default(),
+        feeds: vec![types::FeedConfig {
+            id: FEED.to_owned(),
+            reporters: vec![
+                keys::alice::address(),
+                keys::bob::address(),
+                keys::charlie::address(),
+            ],
+            min_reports: 2,
+            round_duration: 10,
+            max_staleness: 20,
+            fee_conversion: None,
+        }],
+    }
+}
+
+fn submit(
+    mock: &mut mock::Mock,
+    reporter: &transaction::SignerInfo,
+    body: types::Submit,
+) -> Result<(), Error> {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.call.method = "oracle.Submit".to_owned();
+    tx.auth_info.signer_info = vec![reporter.clone()];
+
+    let result = ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Oracle::tx_submit(&mut tx_ctx, body)
+    });
+    Oracle::end_block(&mut ctx);
+    result
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    let params = test_params();
+    params
+        .validate_basic()
+        .expect("test parameters should be valid");
+
+    for (modify, check) in [
+        (
+            (|p: &mut Parameters| p.feeds.push(p.feeds[0].clone())) as fn(&mut Parameters),
+            "feeds should be unique",
+        ),
+        (
+            |p: &mut Parameters| p.feeds[0].round_duration = 0,
+            "round duration should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| p.feeds[0].min_reports = 0,
+            "minimum number of reports should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| p.feeds[0].min_reports = 4,
+            "minimum number of reports should not exceed the number of reporters",
+        ),
+        (
+            |p: &mut Parameters| {
+                p.feeds[0].fee_conversion = Some(types::FeeConversion {
+                    denomination: token::Denomination::NATIVE,
+                    denomination_amount: 1,
+                })
+            },
+            "fee conversion should not be for the native denomination",
+        ),
+        (
+            |p: &mut Parameters| {
+                p.feeds[0].fee_conversion = Some(types::FeeConversion {
+                    denomination: "FOO".parse().unwrap(),
+                    denomination_amount: 0,
+                })
+            },
+            "fee conversion amount should be non-zero",
+        ),
+        (
+            |p: &mut Parameters| {
+                let conversion = types::FeeConversion {
+                    denomination: "FOO".parse().unwrap(),
+                    denomination_amount: 1,
+                };
+                p.feeds[0].fee_conversion = Some(conversion.clone());
+                p.feeds.push(types::FeedConfig {
+                    id: "FOO/USD".to_owned(),
+                    fee_conversion: Some(conversion),
+                    ..p.feeds[0].clone()
+                });
+            },
+            "fee conversion denominations should be unique",
+        ),
+    ] {
+        let mut params = test_params();
+        modify(&mut params);
+        assert!(params.validate_basic().is_err(), "{check}");
+    }
+}
+
+#[test]
+fn test_median() {
+    for (mut values, expected) in [
+        (vec![5], 5),
+        (vec![3, 1, 2], 2),
+        (vec![4, 1, 3, 2], 2),
+        (vec![1, 2], 1),
+        (vec![u128::MAX, u128::MAX - 2], u128::MAX - 1),
+    ] {
+        assert_eq!(super::median(&mut values), expected);
+    }
+}
+
+#[test]
+fn test_report_and_aggregate() {
+    let mut mock = mock::Mock::default();
+    Oracle::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let charlie = keys::charlie::signer_info();
+    let dave = keys::dave::signer_info();
+    let report = |round, value| types::Submit {
+        feed: FEED.to_owned(),
+        round,
+        value,
+    };
+
+    // Reports are accepted from allowlisted reporters for the current reporting round.
+    mock.runtime_header.round = 12;
+    let result = submit(
+        &mut mock,
+        &alice,
+        types::Submit {
+            feed: "unknown".to_owned(),
+            ..report(1, 100)
+        },
+    );
+    assert!(matches!(result, Err(Error::UnknownFeed)));
+    let result = submit(&mut mock, &dave, report(1, 100));
+    assert!(matches!(result, Err(Error::Forbidden)));
+    let result = submit(&mut mock, &alice, report(0, 100));
+    assert!(matches!(result, Err(Error::InvalidRound)));
+
+    submit(&mut mock, &alice, report(1, 100)).expect("report should succeed");
+    let result = submit(&mut mock, &alice, report(1, 101));
+    assert!(matches!(result, Err(Error::AlreadyReported)));
+
+    mock.runtime_header.round = 15;
+    submit(&mut mock, &bob, report(1, 110)).expect("report should succeed");
+    submit(&mut mock, &charlie, report(1, 90)).expect("report should succeed");
+
+    let mut ctx = mock.create_ctx();
+    let result = Oracle::get_value(&ctx, FEED);
+    assert!(
+        matches!(result, Err(Error::NoValue)),
+        "values should only be aggregated at the end of the reporting round"
+    );
+    let reports = Oracle::query_reports(
+        &mut ctx,
+        types::FeedQuery {
+            feed: FEED.to_owned(),
+        },
+    )
+    .expect("reports query should succeed");
+    assert_eq!(reports.round, 1);
+    assert_eq!(reports.values.len(), 3);
+
+    // The last block of the reporting round aggregates the reports.
+    mock.runtime_header.round = 19;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+    let tags = ctx.commit().events.into_tags();
+    assert_eq!(tags.len(), 1, "value aggregated event should be emitted");
+    assert_eq!(tags[0].key, b"oracle\x00\x00\x00\x02"); // oracle.ValueAggregated (code = 2) event
+
+    let ctx = mock.create_ctx();
+    let observation = Oracle::get_value(&ctx, FEED).expect("value should be aggregated");
+    assert_eq!(
+        observation,
+        types::Observation {
+            round: 1,
+            value: 100,
+            num_reports: 3,
+            aggregated_at: 19,
+        }
+    );
+    assert!(Oracle::get_reports(FEED).values.is_empty());
+
+    // Reporting rounds without enough reports are discarded.
+    mock.runtime_header.round = 25;
+    submit(&mut mock, &alice, report(2, 200)).expect("report should succeed");
+    mock.runtime_header.round = 29;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+    assert!(Oracle::get_reports(FEED).values.is_empty());
+    assert_eq!(Oracle::get_observation(FEED), Some(observation.clone()));
+
+    // Values become stale after the maximum staleness.
+    mock.runtime_header.round = 39;
+    let ctx = mock.create_ctx();
+    assert_eq!(Oracle::get_value(&ctx, FEED).unwrap(), observation);
+    mock.runtime_header.round = 40;
+    let ctx = mock.create_ctx();
+    assert!(matches!(
+        Oracle::get_value(&ctx, FEED),
+        Err(Error::StaleValue)
+    ));
+}
+
+#[test]
+fn test_read() {
+    let mut mock = mock::Mock::default();
+    Oracle::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+    Oracle::set_observation(
+        FEED,
+        types::Observation {
+            round: 0,
+            value: 42,
+            num_reports: 2,
+            aggregated_at: 9,
+        },
+    );
+
+    mock.runtime_header.round = 10;
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.call.method = "oracle.Read".to_owned();
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let observation = Oracle::tx_read(
+            &mut tx_ctx,
+            types::FeedQuery {
+                feed: FEED.to_owned(),
+            },
+        )
+        .expect("read should succeed");
+        assert_eq!(observation.value, 42);
+    });
+}
+
+#[test]
+fn test_fee_conversion() {
+    type Core = core::Module<mock::Config>;
+
+    let mut mock = mock::Mock::default();
+    let denom: token::Denomination = "FOO".parse().unwrap();
+    Core::set_params(core::Parameters {
+        max_batch_gas: 10_000_000,
+        max_tx_size: 32 * 1024,
+        max_tx_signers: 8,
+        max_multisig_signers: 8,
+        min_gas_price: BTreeMap::from([(token::Denomination::NATIVE, 1000)]),
+        ..Default::default()
+    });
+    let mut params = test_params();
+    // The feed reports the worth of 1000 FOO in native base units.
+    params.feeds[0].fee_conversion = Some(types::FeeConversion {
+        denomination: denom.clone(),
+        denomination_amount: 1000,
+    });
+    Oracle::init(Genesis {
+        parameters: params,
+        ..Default::default()
+    });
+
+    let report = |round, value| types::Submit {
+        feed: FEED.to_owned(),
+        round,
+        value,
+    };
+    let mut tx = mock::transaction();
+    tx.auth_info.fee.gas = 100;
+    tx.auth_info.fee.amount = token::BaseUnits::new(200_000, denom.clone());
+
+    // Fees cannot be paid in FOO before the feed has a value.
+    let ctx = mock.create_ctx();
+    assert_eq!(Core::min_gas_price(&ctx, &denom), None);
+
+    // One native unit is worth two units of FOO.
+    mock.runtime_header.round = 12;
+    submit(&mut mock, &keys::alice::signer_info(), report(1, 500)).expect("report should succeed");
+    submit(&mut mock, &keys::bob::signer_info(), report(1, 500)).expect("report should succeed");
+    mock.runtime_header.round = 19;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+
+    assert_eq!(
+        Core::fee_conversion_rate(&denom),
+        Some(core::types::FeeConversionRate {
+            native_amount: 500,
+            denomination_amount: 1000,
+        })
+    );
+    assert_eq!(Core::min_gas_price(&ctx, &denom), Some(2000));
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect("gas price should be ok");
+    });
+
+    // The price of FOO halves.
+    mock.runtime_header.round = 22;
+    submit(&mut mock, &keys::alice::signer_info(), report(2, 250)).expect("report should succeed");
+    submit(&mut mock, &keys::bob::signer_info(), report(2, 250)).expect("report should succeed");
+    mock.runtime_header.round = 29;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+
+    assert_eq!(Core::min_gas_price(&ctx, &denom), Some(4000));
+    ctx.with_tx(tx.clone().into(), |mut tx_ctx, call| {
+        Core::before_handle_call(&mut tx_ctx, &call).expect_err("gas price should be too low");
+    });
+
+    // Fees cannot be paid in FOO once the value is stale.
+    mock.runtime_header.round = 50;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+    assert_eq!(Core::fee_conversion_rate(&denom), None);
+    assert_eq!(Core::min_gas_price(&ctx, &denom), None);
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    Oracle::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let report = |round, value| types::Submit {
+        feed: FEED.to_owned(),
+        round,
+        value,
+    };
+
+    // Aggregate a value in the first reporting round and leave a pending report in the second.
+    mock.runtime_header.round = 12;
+    submit(&mut mock, &alice, report(1, 100)).expect("report should succeed");
+    submit(&mut mock, &bob, report(1, 110)).expect("report should succeed");
+    mock.runtime_header.round = 19;
+    let mut ctx = mock.create_ctx();
+    Oracle::end_block(&mut ctx);
+    mock.runtime_header.round = 25;
+    submit(&mut mock, &alice, report(2, 200)).expect("report should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Oracle>();
+    assert_eq!(genesis.parameters.feeds.len(), 1);
+    for prefix in [super::state::REPORTS, super::state::OBSERVATIONS] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "reports and observations should be exported"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Oracle module types.
+use std::collections::BTreeMap;
+
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::{address::Address, token::Denomination};
+
+/// Data feed configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct FeedConfig {
+    /// Feed identifier.
+    pub id: String,
+    /// Addresses of the reporters allowed to report values for the feed.
+    pub reporters: Vec<Address>,
+    /// Minimum number of reports needed for a reporting round to be aggregated.
+    pub min_reports: u32,
+    /// Duration of a reporting round in runtime rounds.
+    pub round_duration: u64,
+    /// Number of runtime rounds after which an aggregated value is considered stale. Zero means
+    /// that values never become stale.
+    #[cbor(optional)]
+    pub max_staleness: u64,
+    /// Optional conversion of transaction fees that the feed provides the rate for. Aggregated
+    /// values of the feed then set the core module's fee conversion rate for the denomination,
+    /// which is cleared again once the value becomes stale.
+    #[cbor(optional)]
+    pub fee_conversion: Option<FeeConversion>,
+}
+
+impl FeedConfig {
+    /// Reporting round that the given runtime round belongs to.
+    pub fn reporting_round(&self, round: u64) -> u64 {
+        round / self.round_duration
+    }
+}
+
+/// Conversion of transaction fees paid in a non-native denomination.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct FeeConversion {
+    /// Denomination in which fees can be paid.
+    pub denomination: Denomination,
+    /// Amount of base units of the denomination whose worth in base units of the native
+    /// denomination is reported by the feed.
+    pub denomination_amount: u128,
+}
+
+/// Reports submitted during a reporting round, not yet aggregated.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct RoundReports {
+    /// Reporting round.
+    pub round: u64,
+    /// Values reported by each reporter.
+    pub values: BTreeMap<Address, u128>,
+}
+
+/// Aggregated value of a feed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Observation {
+    /// Reporting round in which the value was reported.
+    pub round: u64,
+    /// Median of the reported values.
+    pub value: u128,
+    /// Number of reports the value was aggregated from.
+    pub num_reports: u32,
+    /// Runtime round in which the value was aggregated.
+    pub aggregated_at: u64,
+}
+
+/// Submit report call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Submit {
+    /// Feed identifier.
+    pub feed: String,
+    /// Reporting round the value is reported for. Must be the current reporting round.
+    pub round: u64,
+    /// Reported value.
+    pub value: u128,
+}
+
+/// Feed-related query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct FeedQuery {
+    /// Feed identifier.
+    pub feed: String,
+}
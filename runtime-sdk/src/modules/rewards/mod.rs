@@ -1,20 +1,27 @@
 //! Rewards module.
-use std::convert::{TryFrom, TryInto};
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+};
 
 use num_traits::Zero;
 use once_cell::sync::Lazy;
 use thiserror::Error;
 
 use crate::{
-    context::Context,
+    context::{Context, TxContext},
     core::consensus::beacon,
-    migration,
+    export, handler, migration,
     module::{self, Module as _, Parameters as _},
     modules::{self, core::API as _},
     runtime::Runtime,
     sdk_derive,
     storage::{self, CurrentStore, Store},
-    types::address::{Address, SignatureAddressSpec},
+    types::{
+        address::{Address, SignatureAddressSpec},
+        pagination::{paginate, PageResponse},
+        token,
+    },
 };
 
 #[cfg(test)]
@@ -24,12 +31,48 @@ pub mod types;
 /// Unique module name.
 const MODULE_NAME: &str = "rewards";
 
+/// Maximum number of epochs returned by the emission schedule query.
+const MAX_EMISSION_SCHEDULE_EPOCHS: u64 = 1000;
+
 /// Errors emitted by the rewards module.
 #[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
 pub enum Error {
     #[error("invalid argument")]
     #[sdk_error(code = 1)]
     InvalidArgument,
+
+    #[error("invalid epoch")]
+    #[sdk_error(code = 2)]
+    InvalidEpoch,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 3)]
+    Forbidden,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the rewards module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    InfractionRegistered {
+        address: Address,
+        epoch: beacon::EpochTime,
+        rate: u64,
+        #[cbor(optional)]
+        reason: String,
+    },
+
+    #[sdk_event(code = 2)]
+    RewardPenalized {
+        address: Address,
+        epoch: beacon::EpochTime,
+        withheld: token::BaseUnits,
+    },
 }
 
 /// Parameters for the rewards module.
@@ -39,6 +82,20 @@ pub struct Parameters {
 
     pub participation_threshold_numerator: u64,
     pub participation_threshold_denominator: u64,
+
+    /// Minimum number of rounds in an epoch in which an entity must have participated in order to
+    /// be eligible for rewards for that epoch.
+    #[cbor(optional)]
+    pub min_participation_rounds: u64,
+
+    /// Delegation-aware reward distribution parameters. In case they are not specified, all
+    /// rewards are paid to the entity address.
+    #[cbor(optional)]
+    pub delegation: Option<types::DelegationParameters>,
+
+    /// Reward penalty parameters.
+    #[cbor(optional)]
+    pub penalties: types::PenaltyParameters,
 }
 
 /// Errors emitted during rewards parameter validation.
@@ -49,6 +106,9 @@ pub enum ParameterValidationError {
 
     #[error("invalid schedule")]
     InvalidSchedule(#[from] types::RewardScheduleError),
+
+    #[error("invalid commission rate")]
+    InvalidCommissionRate,
 }
 
 impl module::Parameters for Parameters {
@@ -63,6 +123,14 @@ impl module::Parameters for Parameters {
         if self.participation_threshold_denominator.is_zero() {
             return Err(ParameterValidationError::InvalidParticipationThreshold);
         }
+        if let Some(delegation) = &self.delegation {
+            if std::iter::once(&delegation.default_commission_rate)
+                .chain(delegation.commission_rates.values())
+                .any(|rate| *rate > types::COMMISSION_RATE_DENOMINATOR)
+            {
+                return Err(ParameterValidationError::InvalidCommissionRate);
+            }
+        }
 
         Ok(())
     }
@@ -72,6 +140,10 @@ impl module::Parameters for Parameters {
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct Genesis {
     pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
 }
 
 /// State schema constants.
@@ -80,6 +152,28 @@ pub mod state {
 
     /// Map of epochs to rewards pending distribution.
     pub const REWARDS: &[u8] = &[0x02];
+    /// Map of epochs to penalties pending application, keyed by address.
+    pub const PENALTIES: &[u8] = &[0x03];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[REWARDS, PENALTIES];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Register an infraction against an address, reducing its reward for the infraction's epoch
+    /// by the infraction's rate at distribution time. Rates of multiple infractions in the same
+    /// epoch are added up, a total rate of PENALTY_RATE_DENOMINATOR zeroes the reward.
+    ///
+    /// Infractions can be registered for the current epoch or for previous epochs whose rewards
+    /// have not been disbursed yet.
+    fn register_infraction<C: Context>(
+        ctx: &mut C,
+        infraction: types::Infraction,
+    ) -> Result<(), Error>;
+
+    /// Retrieve the penalty pending application for the given address and epoch.
+    fn get_penalty(address: Address, epoch: beacon::EpochTime) -> types::Penalty;
 }
 
 pub struct Module<Accounts: modules::accounts::API> {
@@ -94,8 +188,9 @@ pub static ADDRESS_REWARD_POOL: Lazy<Address> =
 impl<Accounts: modules::accounts::API> Module<Accounts> {
     const NAME: &'static str = MODULE_NAME;
     const VERSION: u32 = 2;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
     type Error = Error;
-    type Event = ();
+    type Event = Event;
     type Parameters = Parameters;
     type Genesis = Genesis;
 
@@ -106,6 +201,9 @@ impl<Accounts: modules::accounts::API> Module<Accounts> {
             .validate_basic()
             .expect("invalid genesis parameters");
 
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
         // Set genesis parameters.
         Self::set_params(genesis.parameters);
     }
@@ -118,6 +216,230 @@ impl<Accounts: modules::accounts::API> Module<Accounts> {
             store.remove(&[0x01]);
         });
     }
+
+    /// Projected per-entity reward emission for upcoming epochs according to the reward schedule.
+    #[handler(query = "rewards.EmissionSchedule")]
+    fn query_emission_schedule<C: Context>(
+        ctx: &mut C,
+        args: types::EmissionScheduleQuery,
+    ) -> Result<types::EmissionSchedule, Error> {
+        let params = Self::params();
+        let from = args.from.unwrap_or_else(|| ctx.epoch());
+        let limit = match args.limit {
+            0 => MAX_EMISSION_SCHEDULE_EPOCHS,
+            limit => std::cmp::min(limit, MAX_EMISSION_SCHEDULE_EPOCHS),
+        };
+        let end = params
+            .schedule
+            .steps
+            .last()
+            .map(|step| step.until)
+            .unwrap_or_default();
+
+        let epochs = (from..std::cmp::min(end, from.saturating_add(limit)))
+            .map(|epoch| types::EpochEmission {
+                epoch,
+                amount: params.schedule.for_epoch(epoch),
+            })
+            .collect();
+
+        Ok(types::EmissionSchedule { epochs })
+    }
+
+    /// Register an infraction against an address. Only configured reporters are allowed to
+    /// register infractions.
+    #[handler(call = "rewards.RegisterInfraction")]
+    fn tx_register_infraction<C: TxContext>(
+        ctx: &mut C,
+        body: types::Infraction,
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.penalties.tx_register_infraction)?;
+
+        if !params
+            .penalties
+            .reporters
+            .contains(&ctx.tx_caller_address())
+        {
+            return Err(Error::Forbidden);
+        }
+
+        Self::register_infraction(ctx, body)
+    }
+
+    /// Penalties pending application at reward distribution time.
+    #[handler(query = "rewards.PendingPenalties")]
+    fn query_pending_penalties<C: Context>(
+        _ctx: &mut C,
+        args: types::PendingPenaltiesQuery,
+    ) -> Result<PageResponse<types::PendingPenalty>, Error> {
+        let start = args
+            .pagination
+            .start::<Vec<u8>>()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let penalties = storage::StoreMap::<_, Vec<u8>, types::Penalty>::new(
+                storage::PrefixStore::new(store, &state::PENALTIES),
+            );
+            let pending =
+                penalties
+                    .iter_from(&start.unwrap_or_default())
+                    .filter_map(|(raw_key, penalty)| {
+                        let key = EpochAddress::try_from(raw_key.as_slice())
+                            .expect("corrupted storage key");
+                        if args.address.map_or(false, |address| address != key.1) {
+                            return None;
+                        }
+                        Some((
+                            raw_key,
+                            types::PendingPenalty {
+                                address: key.1,
+                                epoch: key.0,
+                                penalty,
+                            },
+                        ))
+                    });
+            Ok(paginate(pending, &args.pagination))
+        })
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn register_infraction<C: Context>(
+        ctx: &mut C,
+        infraction: types::Infraction,
+    ) -> Result<(), Error> {
+        if infraction.rate == 0 || infraction.rate > types::PENALTY_RATE_DENOMINATOR {
+            return Err(Error::InvalidArgument);
+        }
+        let current_epoch = ctx.epoch();
+        let pending = CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let epochs =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::REWARDS));
+            epochs
+                .get::<_, types::EpochRewards>(infraction.epoch.to_storage_key())
+                .is_some()
+        });
+        if infraction.epoch > current_epoch || (infraction.epoch < current_epoch && !pending) {
+            return Err(Error::InvalidEpoch);
+        }
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let penalties = storage::PrefixStore::new(store, &state::PENALTIES);
+            let mut epoch_penalties = storage::TypedStore::new(storage::PrefixStore::new(
+                penalties,
+                infraction.epoch.to_storage_key(),
+            ));
+            let mut penalty: types::Penalty =
+                epoch_penalties.get(infraction.address).unwrap_or_default();
+            penalty.rate = std::cmp::min(
+                penalty.rate.saturating_add(infraction.rate),
+                types::PENALTY_RATE_DENOMINATOR,
+            );
+            penalty.infractions = penalty.infractions.saturating_add(1);
+            epoch_penalties.insert(infraction.address, penalty);
+        });
+
+        ctx.emit_event(Event::InfractionRegistered {
+            address: infraction.address,
+            epoch: infraction.epoch,
+            rate: infraction.rate,
+            reason: infraction.reason,
+        });
+
+        Ok(())
+    }
+
+    fn get_penalty(address: Address, epoch: beacon::EpochTime) -> types::Penalty {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let penalties = storage::PrefixStore::new(store, &state::PENALTIES);
+            let epoch_penalties = storage::TypedStore::new(storage::PrefixStore::new(
+                penalties,
+                epoch.to_storage_key(),
+            ));
+            epoch_penalties.get(address).unwrap_or_default()
+        })
+    }
+}
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    /// Remove and return all penalties pending application for the given epoch.
+    fn take_penalties(epoch: beacon::EpochTime) -> BTreeMap<Address, types::Penalty> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let penalties = storage::PrefixStore::new(store, &state::PENALTIES);
+            let mut epoch_penalties = storage::TypedStore::new(storage::PrefixStore::new(
+                penalties,
+                epoch.to_storage_key(),
+            ));
+            let taken: BTreeMap<Address, types::Penalty> = epoch_penalties.iter().collect();
+            for address in taken.keys() {
+                epoch_penalties.remove(address);
+            }
+            taken
+        })
+    }
+
+    /// Remove all penalties recorded for epochs before the given epoch.
+    ///
+    /// Penalties are normally applied when the rewards of their epoch are disbursed, but epochs
+    /// without a rewards entry are never processed so their penalties would otherwise remain.
+    fn prune_penalties(before: beacon::EpochTime) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut penalties = storage::StoreMap::<_, Vec<u8>, types::Penalty>::new(
+                storage::PrefixStore::new(store, &state::PENALTIES),
+            );
+            let stale: Vec<Vec<u8>> = penalties
+                .iter()
+                .map(|(raw_key, _)| raw_key)
+                .take_while(|raw_key| {
+                    let key =
+                        EpochAddress::try_from(raw_key.as_slice()).expect("corrupted storage key");
+                    key.0 < before
+                })
+                .collect();
+            for raw_key in stale {
+                penalties.remove(&raw_key);
+            }
+        })
+    }
+
+    /// Disburse the reward of an entity, splitting it between the entity and its delegators.
+    ///
+    /// Only the delegations to the given entity are loaded.
+    fn disburse_delegated<C: Context>(
+        ctx: &mut C,
+        params: &types::DelegationParameters,
+        entity: Address,
+        reward: &token::BaseUnits,
+    ) -> Result<(), DisbursementError> {
+        let delegators = modules::consensus_accounts::state::get_delegators(entity)?;
+        let denomination = reward.denomination();
+        let (retained, shares) = params.split(&entity, reward.amount(), &delegators);
+
+        // Make sure that the whole reward can be disbursed before making any transfers so that
+        // rewards are either disbursed to everyone or no one.
+        Accounts::ensure_balance(*ADDRESS_REWARD_POOL, reward)?;
+
+        for (address, amount) in std::iter::once((entity, retained)).chain(shares) {
+            if amount == 0 {
+                continue;
+            }
+            Accounts::transfer(
+                ctx,
+                *ADDRESS_REWARD_POOL,
+                address,
+                &token::BaseUnits::new(amount, denomination.clone()),
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
@@ -167,6 +489,7 @@ impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts>
             let params = Self::params();
             'epochs: for (epoch, rewards) in epoch_rewards {
                 let epoch = epoch.0;
+                let penalties = Self::take_penalties(epoch);
 
                 // Fetch reward schedule for the given epoch.
                 let reward = params.schedule.for_epoch(epoch);
@@ -178,19 +501,63 @@ impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts>
                 for address in rewards.for_disbursement(
                     params.participation_threshold_numerator,
                     params.participation_threshold_denominator,
+                    params.min_participation_rounds,
                 ) {
-                    match Accounts::transfer(ctx, *ADDRESS_REWARD_POOL, address, &reward) {
+                    // Apply any penalties.
+                    let reward = match penalties.get(&address) {
+                        Some(penalty) => {
+                            let amount = token::BaseUnits::new(
+                                penalty.apply(reward.amount()),
+                                reward.denomination().clone(),
+                            );
+                            ctx.emit_event(Event::RewardPenalized {
+                                address,
+                                epoch,
+                                withheld: reward
+                                    .checked_sub(&amount)
+                                    .expect("penalty should never increase the reward"),
+                            });
+                            amount
+                        }
+                        None => reward.clone(),
+                    };
+                    if reward.amount() == 0 {
+                        continue;
+                    }
+
+                    let result = match &params.delegation {
+                        Some(delegation) => {
+                            Self::disburse_delegated(ctx, delegation, address, &reward)
+                        }
+                        None => Accounts::transfer(ctx, *ADDRESS_REWARD_POOL, address, &reward)
+                            .map_err(DisbursementError::from),
+                    };
+                    match result {
                         Ok(_) => {}
-                        Err(modules::accounts::Error::InsufficientBalance) => {
+                        Err(DisbursementError::Accounts(
+                            modules::accounts::Error::InsufficientBalance,
+                        )) => {
                             // Since rewards are the same for the whole epoch, if there is not
                             // enough in the pool, just continue with the next epoch which may
                             // specify a lower amount or a different denomination.
                             continue 'epochs;
                         }
+                        Err(DisbursementError::Delegations(err)) => {
+                            // Leave the reward in the pool instead of paying it out without
+                            // honoring the delegations.
+                            slog::error!(ctx.get_logger(MODULE_NAME), "failed to load delegations";
+                                "address" => %address,
+                                "epoch" => epoch,
+                                "err" => ?err,
+                            );
+                        }
                         Err(err) => panic!("failed to disburse rewards: {err:?}"),
                     }
                 }
             }
+
+            // Drop penalties of epochs that had no rewards to disburse.
+            Self::prune_penalties(epoch);
         }
 
         // Update rewards for current epoch.
@@ -205,6 +572,25 @@ impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts>
 
 impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
 
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
+
+/// Errors that may occur while disbursing rewards.
+#[derive(Error, Debug)]
+enum DisbursementError {
+    #[error("accounts: {0}")]
+    Accounts(#[from] modules::accounts::Error),
+
+    #[error("delegations: {0}")]
+    Delegations(#[from] modules::consensus_accounts::Error),
+}
+
 /// A trait that exists solely to convert `beacon::EpochTime` to bytes for use as a storage key.
 trait ToStorageKey {
     fn to_storage_key(&self) -> [u8; 8];
@@ -228,3 +614,30 @@ impl TryFrom<&[u8]> for DecodableEpochTime {
         )))
     }
 }
+
+/// A struct that exists solely to decode penalty storage keys of the form `epoch || address`.
+struct EpochAddress(beacon::EpochTime, Address);
+
+#[derive(Error, Debug)]
+enum EpochAddressError {
+    #[error("malformed epoch")]
+    MalformedEpoch,
+
+    #[error("malformed address")]
+    MalformedAddress,
+}
+
+impl TryFrom<&[u8]> for EpochAddress {
+    type Error = EpochAddressError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() < 8 {
+            return Err(EpochAddressError::MalformedEpoch);
+        }
+        let epoch = DecodableEpochTime::try_from(&value[..8])
+            .map_err(|_| EpochAddressError::MalformedEpoch)?;
+        let address =
+            Address::try_from(&value[8..]).map_err(|_| EpochAddressError::MalformedAddress)?;
+        Ok(EpochAddress(epoch.0, address))
+    }
+}
@@ -6,17 +6,22 @@ use crate::{
     module::{BlockHandler, MigrationHandler},
     modules::{
         accounts::{self, Module as Accounts, API as _},
-        core,
+        consensus_accounts, core,
     },
     runtime::Runtime,
+    storage::{self, CurrentStore},
     testing::{
         keys,
         mock::{self, EmptyRuntime},
     },
-    types::token::{BaseUnits, Denomination},
+    types::{
+        pagination::PageRequest,
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
 };
 
-use super::{types, Genesis, Parameters, ADDRESS_REWARD_POOL};
+use super::{types, Error, Genesis, Parameters, ADDRESS_REWARD_POOL, API as _};
 
 type Rewards = super::Module<Accounts>;
 
@@ -61,16 +66,22 @@ fn test_init_incorrect_rewards_schedule() {
                         types::RewardStep {
                             until: 10,
                             amount: BaseUnits::new(1000, Denomination::NATIVE),
+                            curve: None,
                         },
                         types::RewardStep {
                             until: 1, // Not sorted.
                             amount: BaseUnits::new(1000, Denomination::NATIVE),
+                            curve: None,
                         },
                     ],
                 },
                 participation_threshold_numerator: 3,
                 participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
             },
+            ..Default::default()
         },
     );
 }
@@ -90,11 +101,16 @@ fn test_init_incorrect_participation_threshold() {
                     steps: vec![types::RewardStep {
                         until: 10,
                         amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
                     }],
                 },
                 participation_threshold_numerator: 10, // Invalid numerator.
                 participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
             },
+            ..Default::default()
         },
     );
 }
@@ -125,11 +141,16 @@ fn test_reward_disbursement() {
                     steps: vec![types::RewardStep {
                         until: 1000,
                         amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
                     }],
                 },
                 participation_threshold_numerator: 3,
                 participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
             },
+            ..Default::default()
         },
     );
 
@@ -306,6 +327,242 @@ fn test_reward_disbursement() {
     );
 }
 
+#[test]
+fn test_reward_min_participation() {
+    let mut mock = mock::Mock {
+        epoch: 0,
+        ..Default::default()
+    };
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                schedule: types::RewardSchedule {
+                    steps: vec![types::RewardStep {
+                        until: 1000,
+                        amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
+                    }],
+                },
+                participation_threshold_numerator: 0,
+                participation_threshold_denominator: 4,
+                min_participation_rounds: 5,
+                delegation: None,
+                penalties: Default::default(),
+            },
+            ..Default::default()
+        },
+    );
+
+    // Bob participates in all rounds while Charlie only participates in a few.
+    for round in 0..=10 {
+        mock.runtime_header.round = round;
+        mock.runtime_round_results.good_compute_entities = if round < 4 {
+            vec![
+                keys::bob::pk_ed25519().into(),
+                keys::charlie::pk_ed25519().into(),
+            ]
+        } else {
+            vec![keys::bob::pk_ed25519().into()]
+        };
+
+        let mut ctx = mock.create_ctx();
+        <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+        Rewards::end_block(&mut ctx);
+    }
+
+    // Simulate an epoch transition.
+    mock.epoch += 1;
+    mock.runtime_round_results.good_compute_entities = vec![];
+
+    let mut ctx = mock.create_ctx();
+    <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+    Rewards::end_block(&mut ctx);
+
+    let bals = Accounts::get_balances(keys::bob::address()).expect("get_balances should succeed");
+    assert_eq!(
+        bals.balances[&Denomination::NATIVE],
+        1_000,
+        "rewards should have been disbursed to entities reaching minimum participation"
+    );
+    let bals =
+        Accounts::get_balances(keys::charlie::address()).expect("get_balances should succeed");
+    assert!(
+        !bals.balances.contains_key(&Denomination::NATIVE),
+        "rewards should not have been disbursed to entities below minimum participation"
+    );
+}
+
+#[test]
+fn test_reward_disbursement_delegated() {
+    let mut mock = mock::Mock {
+        epoch: 0,
+        ..Default::default()
+    };
+    mock.runtime_round_results.good_compute_entities = vec![keys::bob::pk_ed25519().into()];
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                schedule: types::RewardSchedule {
+                    steps: vec![types::RewardStep {
+                        until: 1000,
+                        amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
+                    }],
+                },
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: Some(types::DelegationParameters {
+                    default_commission_rate: 20_000, // 20%
+                    ..Default::default()
+                }),
+                penalties: Default::default(),
+            },
+            ..Default::default()
+        },
+    );
+
+    // Alice and Charlie delegate to Bob.
+    consensus_accounts::state::add_delegation(keys::alice::address(), keys::bob::address(), 100)
+        .expect("delegation should succeed");
+    consensus_accounts::state::add_delegation(keys::charlie::address(), keys::bob::address(), 300)
+        .expect("delegation should succeed");
+
+    for round in 0..=10 {
+        mock.runtime_header.round = round;
+
+        let mut ctx = mock.create_ctx();
+        <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+        Rewards::end_block(&mut ctx);
+    }
+
+    // Simulate an epoch transition.
+    mock.epoch += 1;
+
+    let mut ctx = mock.create_ctx();
+    <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+    Rewards::end_block(&mut ctx);
+
+    let bals = Accounts::get_balances(*ADDRESS_REWARD_POOL).expect("get_balances should succeed");
+    assert_eq!(bals.balances[&Denomination::NATIVE], 999_000);
+
+    for (address, expected) in [
+        (keys::bob::address(), 200),
+        (keys::alice::address(), 200),
+        (keys::charlie::address(), 600),
+    ] {
+        let bals = Accounts::get_balances(address).expect("get_balances should succeed");
+        assert_eq!(
+            bals.balances[&Denomination::NATIVE],
+            expected,
+            "rewards should be split between the entity and its delegators"
+        );
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_init_incorrect_commission_rate() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                delegation: Some(types::DelegationParameters {
+                    default_commission_rate: types::COMMISSION_RATE_DENOMINATOR + 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn test_query_emission_schedule() {
+    let mut mock = mock::Mock {
+        epoch: 5,
+        ..Default::default()
+    };
+    let mut ctx = mock.create_ctx();
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                schedule: types::RewardSchedule {
+                    steps: vec![types::RewardStep {
+                        until: 10,
+                        amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: Some(types::RewardCurve::Halving { interval: 6 }),
+                    }],
+                },
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
+            },
+            ..Default::default()
+        },
+    );
+
+    let schedule = Rewards::query_emission_schedule(
+        &mut ctx,
+        types::EmissionScheduleQuery {
+            from: None,
+            limit: 2,
+        },
+    )
+    .expect("query should succeed");
+    assert_eq!(
+        schedule.epochs,
+        vec![
+            types::EpochEmission {
+                epoch: 5,
+                amount: BaseUnits::new(1000, Denomination::NATIVE),
+            },
+            types::EpochEmission {
+                epoch: 6,
+                amount: BaseUnits::new(500, Denomination::NATIVE),
+            },
+        ]
+    );
+
+    // The projection should end together with the schedule.
+    let schedule = Rewards::query_emission_schedule(
+        &mut ctx,
+        types::EmissionScheduleQuery {
+            from: Some(8),
+            limit: 0,
+        },
+    )
+    .expect("query should succeed");
+    assert_eq!(
+        schedule.epochs.iter().map(|e| e.epoch).collect::<Vec<_>>(),
+        vec![8, 9]
+    );
+}
+
 #[test]
 fn test_reward_pool_address() {
     // Make sure the reward pool address doesn't change.
@@ -314,3 +571,319 @@ fn test_reward_pool_address() {
         "oasis1qp7x0q9qahahhjas0xde8w0v04ctp4pqzu5mhjav"
     );
 }
+
+#[test]
+fn test_reward_penalties() {
+    let mut mock = mock::Mock {
+        epoch: 0,
+        ..Default::default()
+    };
+    mock.runtime_round_results.good_compute_entities = vec![
+        keys::bob::pk_ed25519().into(),
+        keys::charlie::pk_ed25519().into(),
+    ];
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                schedule: types::RewardSchedule {
+                    steps: vec![types::RewardStep {
+                        until: 1000,
+                        amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
+                    }],
+                },
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
+            },
+            ..Default::default()
+        },
+    );
+
+    for round in 0..=10 {
+        mock.runtime_header.round = round;
+
+        let mut ctx = mock.create_ctx();
+        <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+        Rewards::end_block(&mut ctx);
+    }
+
+    // Register infractions for the current epoch.
+    let mut ctx = mock.create_ctx();
+    for (address, rate) in [
+        (keys::bob::address(), 50_000),
+        (keys::charlie::address(), 60_000),
+        (keys::charlie::address(), 60_000),
+    ] {
+        Rewards::register_infraction(
+            &mut ctx,
+            types::Infraction {
+                address,
+                epoch: 0,
+                rate,
+                reason: "test".to_owned(),
+            },
+        )
+        .expect("infraction registration should succeed");
+    }
+
+    // Invalid infractions should be rejected.
+    let result = Rewards::register_infraction(
+        &mut ctx,
+        types::Infraction {
+            address: keys::bob::address(),
+            epoch: 1,
+            rate: 10_000,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(result, Err(Error::InvalidEpoch)));
+    let result = Rewards::register_infraction(
+        &mut ctx,
+        types::Infraction {
+            address: keys::bob::address(),
+            epoch: 0,
+            rate: 0,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(result, Err(Error::InvalidArgument)));
+
+    let penalties = Rewards::query_pending_penalties(
+        &mut ctx,
+        types::PendingPenaltiesQuery {
+            address: Some(keys::charlie::address()),
+            ..Default::default()
+        },
+    )
+    .expect("query should succeed");
+    assert_eq!(
+        penalties.items,
+        vec![types::PendingPenalty {
+            address: keys::charlie::address(),
+            epoch: 0,
+            penalty: types::Penalty {
+                rate: types::PENALTY_RATE_DENOMINATOR,
+                infractions: 2,
+            },
+        }],
+        "penalty rates should accumulate up to the maximum"
+    );
+
+    // Pending penalties can be fetched one page at a time.
+    let first = Rewards::query_pending_penalties(
+        &mut ctx,
+        types::PendingPenaltiesQuery {
+            address: None,
+            pagination: PageRequest {
+                cursor: None,
+                limit: 1,
+            },
+        },
+    )
+    .expect("query should succeed");
+    assert_eq!(first.items.len(), 1);
+    let second = Rewards::query_pending_penalties(
+        &mut ctx,
+        types::PendingPenaltiesQuery {
+            address: None,
+            pagination: PageRequest {
+                cursor: first.next,
+                limit: 1,
+            },
+        },
+    )
+    .expect("query should succeed");
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.next, None);
+    assert_ne!(first.items[0].address, second.items[0].address);
+
+    let state = ctx.commit();
+    let tags = state.events.into_tags();
+    assert_eq!(tags.len(), 1, "infraction events should be emitted");
+    assert_eq!(tags[0].key, b"rewards\x00\x00\x00\x01"); // rewards.InfractionRegistered (code = 1) event
+
+    // Simulate an epoch transition.
+    mock.epoch += 1;
+
+    let mut ctx = mock.create_ctx();
+    <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+    Rewards::end_block(&mut ctx);
+
+    let bals = Accounts::get_balances(*ADDRESS_REWARD_POOL).expect("get_balances should succeed");
+    assert_eq!(
+        bals.balances[&Denomination::NATIVE],
+        999_500,
+        "penalized rewards should be withheld"
+    );
+    let bals = Accounts::get_balances(keys::bob::address()).expect("get_balances should succeed");
+    assert_eq!(bals.balances[&Denomination::NATIVE], 500);
+    let bals =
+        Accounts::get_balances(keys::charlie::address()).expect("get_balances should succeed");
+    assert!(bals.balances.get(&Denomination::NATIVE).is_none());
+
+    let penalties =
+        Rewards::query_pending_penalties(&mut ctx, types::PendingPenaltiesQuery::default())
+            .expect("query should succeed");
+    assert!(penalties.items.is_empty(), "penalties should be applied");
+
+    // Infractions for already disbursed epochs should be rejected.
+    let result = Rewards::register_infraction(
+        &mut ctx,
+        types::Infraction {
+            address: keys::bob::address(),
+            epoch: 0,
+            rate: 10_000,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(result, Err(Error::InvalidEpoch)));
+
+    // Penalties of epochs without any rewards should be dropped on the next epoch transition.
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let penalties = storage::PrefixStore::new(store, &super::state::PENALTIES);
+        let mut epoch_penalties =
+            storage::TypedStore::new(storage::PrefixStore::new(penalties, 2u64.to_be_bytes()));
+        epoch_penalties.insert(keys::bob::address(), types::Penalty::default());
+    });
+    mock.epoch = 3;
+
+    let mut ctx = mock.create_ctx();
+    <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+    Rewards::end_block(&mut ctx);
+
+    let penalties =
+        Rewards::query_pending_penalties(&mut ctx, types::PendingPenaltiesQuery::default())
+            .expect("query should succeed");
+    assert!(
+        penalties.items.is_empty(),
+        "orphaned penalties should be removed"
+    );
+}
+
+#[test]
+fn test_register_infraction_forbidden() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                penalties: types::PenaltyParameters {
+                    reporters: vec![keys::alice::address()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let infraction = types::Infraction {
+        address: keys::charlie::address(),
+        epoch: 1,
+        rate: 10_000,
+        ..Default::default()
+    };
+
+    for (sigspec, allowed) in [
+        (keys::alice::sigspec(), true),
+        (keys::bob::sigspec(), false),
+    ] {
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(sigspec, 0)];
+
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            let result = Rewards::tx_register_infraction(&mut tx_ctx, infraction.clone());
+            if allowed {
+                result.expect("registration by a reporter should succeed");
+            } else {
+                assert!(matches!(result, Err(Error::Forbidden)));
+            }
+        });
+    }
+
+    assert_eq!(
+        Rewards::get_penalty(keys::charlie::address(), 1),
+        types::Penalty {
+            rate: 10_000,
+            infractions: 1,
+        }
+    );
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock {
+        epoch: 0,
+        ..Default::default()
+    };
+    mock.runtime_round_results.good_compute_entities = vec![keys::bob::pk_ed25519().into()];
+    let mut ctx = mock.create_ctx();
+
+    init_accounts(&mut ctx);
+
+    Rewards::init_or_migrate(
+        &mut ctx,
+        &mut core::types::Metadata::default(),
+        Genesis {
+            parameters: Parameters {
+                schedule: types::RewardSchedule {
+                    steps: vec![types::RewardStep {
+                        until: 1000,
+                        amount: BaseUnits::new(1000, Denomination::NATIVE),
+                        curve: None,
+                    }],
+                },
+                participation_threshold_numerator: 3,
+                participation_threshold_denominator: 4,
+                min_participation_rounds: 0,
+                delegation: None,
+                penalties: Default::default(),
+            },
+            ..Default::default()
+        },
+    );
+
+    // Accumulate pending rewards and penalties for the current epoch.
+    for round in 0..=10 {
+        mock.runtime_header.round = round;
+
+        let mut ctx = mock.create_ctx();
+        <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+        Rewards::end_block(&mut ctx);
+    }
+    let mut ctx = mock.create_ctx();
+    Rewards::register_infraction(
+        &mut ctx,
+        types::Infraction {
+            address: keys::bob::address(),
+            epoch: 0,
+            rate: 50_000,
+            reason: "test".to_owned(),
+        },
+    )
+    .expect("infraction registration should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Rewards>();
+    assert_eq!(genesis.parameters.participation_threshold_numerator, 3);
+    for prefix in [super::state::REWARDS, super::state::PENALTIES] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "pending rewards and penalties should be exported"
+        );
+    }
+}
@@ -1,22 +1,117 @@
 //! Rewards module types.
 use std::collections::BTreeMap;
 
+use oasis_runtime_sdk_macros::Schema;
 use thiserror::Error;
 
 use crate::{
     core::consensus::beacon,
-    types::{address::Address, token},
+    types::{address::Address, pagination::PageRequest, token},
 };
 
+/// Curve describing how the reward amount changes over the epochs of a reward step.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub enum RewardCurve {
+    /// The reward amount is halved every `interval` epochs.
+    #[cbor(rename = "halving")]
+    Halving { interval: beacon::EpochTime },
+
+    /// The reward amount decreases linearly towards `end_amount` which is reached at the end of
+    /// the step.
+    #[cbor(rename = "linear_decay")]
+    LinearDecay { end_amount: u128 },
+
+    /// The reward amount is multiplied by `numerator / denominator` every epoch.
+    #[cbor(rename = "exponential_decay")]
+    ExponentialDecay { numerator: u64, denominator: u64 },
+}
+
 /// One of the time periods in the reward schedule.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct RewardStep {
     pub until: beacon::EpochTime,
     pub amount: token::BaseUnits,
+
+    /// Curve applied to the amount over the epochs of the step. In case no curve is specified
+    /// the amount is constant.
+    #[cbor(optional)]
+    pub curve: Option<RewardCurve>,
+}
+
+impl RewardStep {
+    /// Perform basic reward step validation.
+    pub fn validate_basic(&self) -> Result<(), RewardScheduleError> {
+        match self.curve {
+            None => Ok(()),
+            Some(RewardCurve::Halving { interval }) if interval > 0 => Ok(()),
+            Some(RewardCurve::LinearDecay { end_amount }) if end_amount <= self.amount.amount() => {
+                Ok(())
+            }
+            Some(RewardCurve::ExponentialDecay {
+                numerator,
+                denominator,
+            }) if denominator > 0 && numerator <= denominator => Ok(()),
+            _ => Err(RewardScheduleError::InvalidCurve),
+        }
+    }
+
+    /// Compute the reward amount for the given epoch of the step which starts at `start`.
+    pub fn amount_at(&self, start: beacon::EpochTime, epoch: beacon::EpochTime) -> u128 {
+        let amount = self.amount.amount();
+        let elapsed = epoch.saturating_sub(start);
+
+        match self.curve {
+            None => amount,
+            Some(RewardCurve::Halving { interval }) => {
+                let halvings = elapsed.checked_div(interval).unwrap_or(0);
+                if halvings >= u128::BITS as u64 {
+                    return 0;
+                }
+                amount >> halvings
+            }
+            Some(RewardCurve::LinearDecay { end_amount }) => {
+                let length = self.until.saturating_sub(start);
+                if length == 0 {
+                    return amount;
+                }
+                let decrease = amount.saturating_sub(end_amount);
+                let decrease = decrease
+                    .checked_mul(elapsed.into())
+                    .map(|d| d / u128::from(length))
+                    .unwrap_or_else(|| decrease / u128::from(length) * u128::from(elapsed));
+                amount.saturating_sub(decrease)
+            }
+            Some(RewardCurve::ExponentialDecay {
+                numerator,
+                denominator,
+            }) => {
+                if denominator == 0 || numerator >= denominator {
+                    return amount;
+                }
+
+                // Compute the decay factor for the elapsed epochs in fixed point arithmetic with
+                // 63 fractional bits so that intermediate products can never overflow.
+                const FRAC_BITS: u32 = 63;
+                const ONE: u128 = 1 << FRAC_BITS;
+                let mut factor = (u128::from(numerator) << FRAC_BITS) / u128::from(denominator);
+                let mut scale = ONE;
+                let mut exp = elapsed;
+                while exp > 0 && scale > 0 {
+                    if exp & 1 == 1 {
+                        scale = (scale * factor) >> FRAC_BITS;
+                    }
+                    factor = (factor * factor) >> FRAC_BITS;
+                    exp >>= 1;
+                }
+
+                (amount >> FRAC_BITS) * scale + (((amount & (ONE - 1)) * scale) >> FRAC_BITS)
+            }
+        }
+    }
 }
 
 /// A reward schedule.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct RewardSchedule {
     pub steps: Vec<RewardStep>,
 }
@@ -26,6 +121,9 @@ pub struct RewardSchedule {
 pub enum RewardScheduleError {
     #[error("steps not sorted correctly")]
     StepsNotSorted,
+
+    #[error("invalid reward curve")]
+    InvalidCurve,
 }
 
 impl RewardSchedule {
@@ -36,6 +134,7 @@ impl RewardSchedule {
             if step.until <= last_epoch {
                 return Err(RewardScheduleError::StepsNotSorted);
             }
+            step.validate_basic()?;
             last_epoch = step.until;
         }
         Ok(())
@@ -43,10 +142,15 @@ impl RewardSchedule {
 
     /// Compute the per-entity reward amount for the given epoch based on the schedule.
     pub fn for_epoch(&self, epoch: beacon::EpochTime) -> token::BaseUnits {
+        let mut start = Default::default();
         for step in &self.steps {
             if epoch < step.until {
-                return step.amount.clone();
+                return token::BaseUnits::new(
+                    step.amount_at(start, epoch),
+                    step.amount.denomination().clone(),
+                );
             }
+            start = step.until;
         }
 
         // End of the schedule, default to no rewards.
@@ -54,6 +158,190 @@ impl RewardSchedule {
     }
 }
 
+/// Denominator of reward commission rates.
+pub const COMMISSION_RATE_DENOMINATOR: u64 = 100_000;
+
+/// Parameters of delegation-aware reward distribution.
+///
+/// Rewards of entities that have delegations in the consensus accounts module are split between
+/// the entity, which retains its commission, and its delegators in proportion to their shares.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct DelegationParameters {
+    /// Commission rate retained by entities, in units of 1/COMMISSION_RATE_DENOMINATOR.
+    pub default_commission_rate: u64,
+    /// Per-entity commission rates, overriding the default commission rate.
+    #[cbor(optional)]
+    pub commission_rates: BTreeMap<Address, u64>,
+}
+
+impl DelegationParameters {
+    /// Commission rate for the given entity address.
+    pub fn commission_rate(&self, address: &Address) -> u64 {
+        self.commission_rates
+            .get(address)
+            .copied()
+            .unwrap_or(self.default_commission_rate)
+    }
+
+    /// Split the given reward amount between the entity and its delegators.
+    ///
+    /// Returns the amount retained by the entity and the amounts for each delegator. Any amount
+    /// lost to rounding is retained by the entity.
+    pub fn split(
+        &self,
+        entity: &Address,
+        amount: u128,
+        delegators: &[(Address, u128)],
+    ) -> (u128, Vec<(Address, u128)>) {
+        let total_shares = delegators
+            .iter()
+            .fold(0u128, |acc, (_, shares)| acc.saturating_add(*shares));
+        if total_shares == 0 {
+            return (amount, vec![]);
+        }
+
+        let rate = std::cmp::min(self.commission_rate(entity), COMMISSION_RATE_DENOMINATOR);
+        let commission = mul_div(amount, rate.into(), COMMISSION_RATE_DENOMINATOR.into());
+        let distributable = amount - commission;
+
+        let mut distributed = 0u128;
+        let shares = delegators
+            .iter()
+            .map(|(delegator, shares)| {
+                let share = mul_div(distributable, *shares, total_shares);
+                distributed += share;
+                (*delegator, share)
+            })
+            .collect();
+
+        (amount - distributed, shares)
+    }
+}
+
+/// Compute `a * b / c` rounding down, where `0 < c` and `b <= c` so that the result always fits.
+fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    if let Some(ab) = a.checked_mul(b) {
+        return ab / c;
+    }
+
+    // Compute the full 256-bit product.
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let (ll, lh, hl, hh) = (a_lo * b_lo, a_lo * b_hi, a_hi * b_lo, a_hi * b_hi);
+    let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+    let lo = (ll & MASK) | (mid << 64);
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+
+    // Binary long division of the product.
+    let mut quotient = 0u128;
+    let mut remainder = 0u128;
+    for i in (0..256).rev() {
+        let bit = if i >= 128 {
+            (hi >> (i - 128)) & 1
+        } else {
+            (lo >> i) & 1
+        };
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | bit;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            if i < 128 {
+                quotient |= 1 << i;
+            }
+        }
+    }
+    quotient
+}
+
+/// Denominator of penalty rates.
+pub const PENALTY_RATE_DENOMINATOR: u64 = 100_000;
+
+/// Parameters of reward penalties.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct PenaltyParameters {
+    /// Addresses allowed to register infractions via transactions.
+    #[cbor(optional)]
+    pub reporters: Vec<Address>,
+    /// Gas cost of the register infraction transaction.
+    #[cbor(optional)]
+    pub tx_register_infraction: u64,
+}
+
+/// An infraction registered against an address, reducing its reward for the given epoch.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Infraction {
+    pub address: Address,
+    pub epoch: beacon::EpochTime,
+    /// Fraction of the reward that is withheld, in units of 1/PENALTY_RATE_DENOMINATOR.
+    pub rate: u64,
+    /// Optional description of the infraction.
+    #[cbor(optional)]
+    pub reason: String,
+}
+
+/// Penalty accumulated from all infractions registered against an address for an epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Penalty {
+    /// Fraction of the reward that is withheld, in units of 1/PENALTY_RATE_DENOMINATOR.
+    pub rate: u64,
+    /// Number of registered infractions.
+    pub infractions: u64,
+}
+
+impl Penalty {
+    /// Apply the penalty to the given reward amount, returning the reduced amount.
+    pub fn apply(&self, amount: u128) -> u128 {
+        let rate = std::cmp::min(self.rate, PENALTY_RATE_DENOMINATOR);
+        amount - mul_div(amount, rate.into(), PENALTY_RATE_DENOMINATOR.into())
+    }
+}
+
+/// Penalty pending application at reward distribution time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct PendingPenalty {
+    pub address: Address,
+    pub epoch: beacon::EpochTime,
+    pub penalty: Penalty,
+}
+
+/// Pending penalties query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct PendingPenaltiesQuery {
+    /// Only return penalties of the given address.
+    #[cbor(optional)]
+    pub address: Option<Address>,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
+
+/// Emission schedule query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct EmissionScheduleQuery {
+    /// First epoch to project the emission for. Defaults to the current epoch.
+    #[cbor(optional)]
+    pub from: Option<beacon::EpochTime>,
+    /// Maximum number of epochs to project. Zero means the maximum allowed number.
+    #[cbor(optional)]
+    pub limit: u64,
+}
+
+/// Per-entity reward emitted in a given epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct EpochEmission {
+    pub epoch: beacon::EpochTime,
+    pub amount: token::BaseUnits,
+}
+
+/// Projected emission schedule.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct EmissionSchedule {
+    /// Per-entity rewards for the projected epochs. Epochs after the end of the reward schedule
+    /// are omitted.
+    pub epochs: Vec<EpochEmission>,
+}
+
 /// Action that should be taken for a given address when disbursing rewards.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum RewardAction {
@@ -118,17 +406,21 @@ impl cbor::Decode for RewardAction {
 }
 
 /// Rewards for the epoch.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
 pub struct EpochRewards {
     pub pending: BTreeMap<Address, RewardAction>,
 }
 
 impl EpochRewards {
     /// Returns an iterator over addresses that should be rewarded.
+    ///
+    /// An address is rewarded in case its reward counter reaches both the given fraction of the
+    /// highest counter in the epoch and the given minimum.
     pub fn for_disbursement(
         &self,
         threshold_numerator: u64,
         threshold_denominator: u64,
+        min_participation: u64,
     ) -> impl Iterator<Item = Address> + '_ {
         let max_v = self
             .pending
@@ -147,6 +439,7 @@ impl EpochRewards {
                 .checked_div(threshold_denominator)
                 .unwrap_or(0)
         };
+        let threshold = std::cmp::max(threshold, min_participation);
 
         self.pending
             .iter()
@@ -208,14 +501,17 @@ mod test {
                 RewardStep {
                     until: 10,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 10,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 15,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
             ],
         };
@@ -231,14 +527,17 @@ mod test {
                 RewardStep {
                     until: 10,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 5,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 15,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
             ],
         };
@@ -254,14 +553,17 @@ mod test {
                 RewardStep {
                     until: 5,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 10,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 15,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
             ],
         };
@@ -277,14 +579,17 @@ mod test {
                 RewardStep {
                     until: 5,
                     amount: token::BaseUnits::new(3000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 10,
                     amount: token::BaseUnits::new(2000, token::Denomination::NATIVE),
+                    curve: None,
                 },
                 RewardStep {
                     until: 15,
                     amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: None,
                 },
             ],
         };
@@ -301,6 +606,154 @@ mod test {
         assert_eq!(schedule.for_epoch(100).amount(), 0);
     }
 
+    #[test]
+    fn test_reward_curves() {
+        let schedule = RewardSchedule {
+            steps: vec![
+                RewardStep {
+                    until: 10,
+                    amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: Some(RewardCurve::Halving { interval: 3 }),
+                },
+                RewardStep {
+                    until: 20,
+                    amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: Some(RewardCurve::LinearDecay { end_amount: 500 }),
+                },
+                RewardStep {
+                    until: 1_000_000,
+                    amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: Some(RewardCurve::ExponentialDecay {
+                        numerator: 1,
+                        denominator: 2,
+                    }),
+                },
+            ],
+        };
+        schedule
+            .validate_basic()
+            .expect("validation of correct schedule should not fail");
+
+        assert_eq!(schedule.for_epoch(0).amount(), 1000);
+        assert_eq!(schedule.for_epoch(2).amount(), 1000);
+        assert_eq!(schedule.for_epoch(3).amount(), 500);
+        assert_eq!(schedule.for_epoch(6).amount(), 250);
+        assert_eq!(schedule.for_epoch(9).amount(), 125);
+        assert_eq!(schedule.for_epoch(10).amount(), 1000);
+        assert_eq!(schedule.for_epoch(15).amount(), 750);
+        assert_eq!(schedule.for_epoch(19).amount(), 550);
+        assert_eq!(schedule.for_epoch(20).amount(), 1000);
+        assert_eq!(schedule.for_epoch(21).amount(), 500);
+        assert_eq!(schedule.for_epoch(23).amount(), 125);
+        assert_eq!(schedule.for_epoch(999_999).amount(), 0);
+        assert_eq!(schedule.for_epoch(1_000_000).amount(), 0);
+    }
+
+    #[test]
+    fn test_reward_curves_validation() {
+        let invalid = vec![
+            RewardCurve::Halving { interval: 0 },
+            RewardCurve::LinearDecay { end_amount: 2000 },
+            RewardCurve::ExponentialDecay {
+                numerator: 1,
+                denominator: 0,
+            },
+            RewardCurve::ExponentialDecay {
+                numerator: 3,
+                denominator: 2,
+            },
+        ];
+        for curve in invalid {
+            let schedule = RewardSchedule {
+                steps: vec![RewardStep {
+                    until: 10,
+                    amount: token::BaseUnits::new(1000, token::Denomination::NATIVE),
+                    curve: Some(curve.clone()),
+                }],
+            };
+            schedule.validate_basic().expect_err(&format!(
+                "validation of invalid curve {curve:?} should fail"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reward_curve_exponential_decay_large() {
+        let step = RewardStep {
+            until: beacon::EpochTime::MAX,
+            amount: token::BaseUnits::new(u128::MAX, token::Denomination::NATIVE),
+            curve: Some(RewardCurve::ExponentialDecay {
+                numerator: 999_999,
+                denominator: 1_000_000,
+            }),
+        };
+        assert!(step.amount_at(0, 1) < u128::MAX);
+        assert!(step.amount_at(0, 1) > u128::MAX / 100 * 99);
+        assert_eq!(step.amount_at(0, beacon::EpochTime::MAX), 0);
+    }
+
+    #[test]
+    fn test_delegation_split() {
+        let entity = keys::alice::address();
+        let params = DelegationParameters {
+            default_commission_rate: 10_000, // 10%
+            commission_rates: {
+                let mut rates = BTreeMap::new();
+                rates.insert(keys::dave::address(), COMMISSION_RATE_DENOMINATOR);
+                rates
+            },
+        };
+
+        // Without delegations, everything is retained by the entity.
+        assert_eq!(params.split(&entity, 1000, &[]), (1000, vec![]));
+
+        // Rewards are split proportionally to shares after commission, with any rounding
+        // remainder retained by the entity.
+        let delegators = vec![(keys::bob::address(), 100), (keys::charlie::address(), 200)];
+        assert_eq!(
+            params.split(&entity, 1001, &delegators),
+            (
+                101,
+                vec![(keys::bob::address(), 300), (keys::charlie::address(), 600)]
+            )
+        );
+
+        // Entities with full commission retain everything.
+        assert_eq!(
+            params.split(&keys::dave::address(), 1000, &delegators),
+            (
+                1000,
+                vec![(keys::bob::address(), 0), (keys::charlie::address(), 0)]
+            )
+        );
+
+        // Large amounts and shares should not overflow.
+        let delegators = vec![
+            (keys::bob::address(), u128::MAX / 2),
+            (keys::charlie::address(), u128::MAX / 2),
+        ];
+        let (retained, shares) = params.split(&entity, u128::MAX, &delegators);
+        assert_eq!(shares[0].1, shares[1].1);
+        assert_eq!(
+            retained + shares[0].1 + shares[1].1,
+            u128::MAX,
+            "the whole reward should be distributed"
+        );
+        assert!(retained >= u128::MAX / 10);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(10, 3, 4), 7);
+        assert_eq!(mul_div(u128::MAX, 1, 2), u128::MAX / 2);
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(mul_div(u128::MAX, u128::MAX - 1, u128::MAX), u128::MAX - 1);
+        assert_eq!(
+            mul_div(u128::MAX - 1, 3, 7),
+            145835300108973627198589117470757804908
+        );
+    }
+
     #[test]
     fn test_epoch_rewards() {
         let epoch_rewards = EpochRewards {
@@ -314,20 +767,26 @@ mod test {
         };
 
         // Alice and Charlie have >= 0.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0, 0).collect();
         assert_eq!(
             rewards,
             vec![keys::charlie::address(), keys::alice::address()]
         );
         // Alice and Charlie have >= 0.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0, 0).collect();
         assert_eq!(
             rewards,
             vec![keys::charlie::address(), keys::alice::address()]
         );
         // Only Alice has >= 7.5.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(3, 4).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(3, 4, 0).collect();
+        assert_eq!(rewards, vec![keys::alice::address()]);
+        // Only Alice has >= 6 rounds.
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0, 6).collect();
         assert_eq!(rewards, vec![keys::alice::address()]);
+        // Nobody has >= 11 rounds.
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0, 11).collect();
+        assert!(rewards.is_empty());
     }
 
     #[test]
@@ -342,19 +801,19 @@ mod test {
         };
 
         // Alice and Charlie have >= 0.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(0, 0, 0).collect();
         assert_eq!(
             rewards,
             vec![keys::charlie::address(), keys::alice::address()]
         );
         // Alice and Charlie have >= 1/2.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(1, 2).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(1, 2, 0).collect();
         assert_eq!(
             rewards,
             vec![keys::charlie::address(), keys::alice::address()]
         );
         // Only Alice has >= 3/4, but due to overflow both will be counted.
-        let rewards: Vec<_> = epoch_rewards.for_disbursement(3, 4).collect();
+        let rewards: Vec<_> = epoch_rewards.for_disbursement(3, 4, 0).collect();
         assert_eq!(rewards, vec![keys::alice::address()]);
     }
 }
@@ -0,0 +1,712 @@
+//! On-chain coordination for ROFL (runtime off-chain logic) components.
+//!
+//! Keeps a registry of ROFL applications, each with an administrator and a policy describing the
+//! TEE enclave identities and node endorsements that application instances must present, and of
+//! the registrations endorsing the keys of individual application instances. Contracts and other
+//! modules can use the registry to authenticate calls originating from off-chain components.
+use std::convert::TryFrom;
+
+use thiserror::Error;
+
+use oasis_core_runtime::{
+    common::{crypto::signature::PublicKey as CorePublicKey, sgx::EnclaveIdentity},
+    consensus::beacon::EpochTime,
+};
+
+use crate::{
+    context::{Context, TxContext},
+    crypto::signature::PublicKey,
+    export, handler, migration,
+    module::{self, Module as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{
+        app_id::{self, AppId},
+        pagination::{paginate, PageResponse},
+        transaction::{AddressSpec, Transaction},
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "rofl";
+
+/// Errors emitted by the ROFL module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("unknown application")]
+    #[sdk_error(code = 2)]
+    UnknownApp,
+
+    #[error("unknown instance")]
+    #[sdk_error(code = 3)]
+    UnknownInstance,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 4)]
+    Forbidden,
+
+    #[error("invalid attestation: {0}")]
+    #[sdk_error(code = 5)]
+    InvalidAttestation(String),
+
+    #[error("endorsement not allowed by policy")]
+    #[sdk_error(code = 6)]
+    EndorsementNotAllowed,
+
+    #[error("transaction not signed by {0:?}")]
+    #[sdk_error(code = 7)]
+    NotSignedByKey(PublicKey),
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the ROFL module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    AppCreated { id: AppId },
+
+    #[sdk_event(code = 2)]
+    AppUpdated { id: AppId },
+
+    #[sdk_event(code = 3)]
+    AppRemoved { id: AppId },
+
+    #[sdk_event(code = 4)]
+    InstanceRegistered {
+        app_id: AppId,
+        rak: CorePublicKey,
+        expiration: EpochTime,
+    },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_create: u64,
+    pub tx_update: u64,
+    pub tx_remove: u64,
+    pub tx_register: u64,
+}
+
+/// Parameters for the ROFL module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ();
+}
+
+/// Genesis state for the ROFL module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Applications registered at genesis.
+    #[cbor(optional)]
+    pub apps: Vec<types::AppConfig>,
+
+    /// Module state other than parameters and applications, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of application identifiers to application configurations.
+    pub const APPS: &[u8] = &[0x01];
+    /// Map of application identifiers to map of instance runtime attestation keys to
+    /// registrations.
+    pub const REGISTRATIONS: &[u8] = &[0x02];
+    /// Set of registrations keyed by expiration epoch, application identifier and instance
+    /// runtime attestation key.
+    pub const EXPIRATION_QUEUE: &[u8] = &[0x03];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[REGISTRATIONS, EXPIRATION_QUEUE];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the configuration of the given application.
+    fn get_app(id: AppId) -> Result<types::AppConfig, Error>;
+
+    /// Fetch the configurations of all applications.
+    fn get_apps() -> Vec<types::AppConfig>;
+
+    /// Fetch the registration of the given application instance. Expired registrations are
+    /// returned as well.
+    fn get_registration(app: AppId, rak: &CorePublicKey) -> Result<types::Registration, Error>;
+
+    /// Fetch the registrations of all instances of the given application that are active in the
+    /// given epoch.
+    fn get_active_registrations(app: AppId, epoch: EpochTime) -> Vec<types::Registration>;
+
+    /// Check that all keys used by the ROFL origin signers of the given transaction are endorsed
+    /// by currently registered instances of the respective applications.
+    fn check_origins<C: Context>(ctx: &C, tx: &Transaction) -> Result<(), modules::core::Error>;
+
+    /// Application whose instance originated the current transaction, authenticated using the
+    /// ROFL origin address specification.
+    fn authorized_origin<C: TxContext>(ctx: &C) -> Option<AppId>;
+
+    /// Whether the caller of the current transaction is an instance of the given application,
+    /// authenticated using the ROFL origin address specification.
+    fn is_authorized_origin<C: TxContext>(ctx: &C, app: AppId) -> bool {
+        Self::authorized_origin(ctx) == Some(app)
+    }
+}
+
+pub struct Module;
+
+impl Module {
+    fn set_app(cfg: types::AppConfig) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut apps = storage::TypedStore::new(storage::PrefixStore::new(store, &state::APPS));
+            apps.insert(cfg.id, cfg);
+        });
+    }
+
+    /// Whether the given key is endorsed by an instance of the given application that is active
+    /// in the given epoch.
+    fn is_endorsed(app: AppId, key: &PublicKey, epoch: EpochTime) -> bool {
+        Self::get_active_registrations(app, epoch)
+            .iter()
+            .any(|registration| {
+                PublicKey::Ed25519(registration.rak.into()) == *key
+                    || registration.extra_keys.contains(key)
+            })
+    }
+
+    fn remove_app(id: AppId) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut apps = storage::TypedStore::new(storage::PrefixStore::new(store, &state::APPS));
+            apps.remove(id);
+        });
+
+        // Remove all instance registrations of the application.
+        for registration in Self::get_registrations(id) {
+            Self::remove_registration(&registration);
+        }
+    }
+
+    fn get_registrations(app: AppId) -> Vec<types::Registration> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let registrations = storage::PrefixStore::new(store, &state::REGISTRATIONS);
+            let app_registrations =
+                storage::TypedStore::new(storage::PrefixStore::new(registrations, &app));
+            app_registrations
+                .iter::<Vec<u8>, types::Registration>()
+                .map(|(_, registration)| registration)
+                .collect()
+        })
+    }
+
+    fn set_registration(registration: types::Registration) {
+        // Replace any existing registration of the same instance.
+        if let Ok(existing) = Self::get_registration(registration.app, &registration.rak) {
+            Self::remove_registration(&existing);
+        }
+
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue = storage::TypedStore::new(storage::PrefixStore::new(
+                &mut store,
+                &state::EXPIRATION_QUEUE,
+            ));
+            queue.insert(
+                ExpirationQueueEntry::key(
+                    registration.expiration,
+                    &registration.app,
+                    &registration.rak,
+                ),
+                (),
+            );
+
+            let registrations = storage::PrefixStore::new(store, &state::REGISTRATIONS);
+            let mut app_registrations = storage::TypedStore::new(storage::PrefixStore::new(
+                registrations,
+                &registration.app,
+            ));
+            app_registrations.insert(registration.rak, registration);
+        });
+    }
+
+    fn remove_registration(registration: &types::Registration) {
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue = storage::TypedStore::new(storage::PrefixStore::new(
+                &mut store,
+                &state::EXPIRATION_QUEUE,
+            ));
+            queue.remove(ExpirationQueueEntry::key(
+                registration.expiration,
+                &registration.app,
+                &registration.rak,
+            ));
+
+            let registrations = storage::PrefixStore::new(store, &state::REGISTRATIONS);
+            let mut app_registrations = storage::TypedStore::new(storage::PrefixStore::new(
+                registrations,
+                &registration.app,
+            ));
+            app_registrations.remove(registration.rak);
+        });
+    }
+
+    /// Remove all registrations that have expired before the given epoch.
+    fn expire_registrations(epoch: EpochTime) {
+        let expired: Vec<ExpirationQueueEntry> = CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let queue = storage::TypedStore::new(storage::PrefixStore::new(
+                store,
+                &state::EXPIRATION_QUEUE,
+            ));
+            queue
+                .iter::<ExpirationQueueEntry, ()>()
+                .map(|(entry, _)| entry)
+                .take_while(|entry| entry.expiration < epoch)
+                .collect()
+        });
+
+        for entry in expired {
+            if let Ok(registration) = Self::get_registration(entry.app, &entry.rak) {
+                Self::remove_registration(&registration);
+            }
+        }
+    }
+
+    /// Register an application instance whose remote attestation quote has been verified to be
+    /// produced by the given enclave and to contain the given report data.
+    fn register_verified<C: TxContext>(
+        ctx: &mut C,
+        cfg: &types::AppConfig,
+        registration: types::Registration,
+        identity: &EnclaveIdentity,
+        report_data: &[u8],
+    ) -> Result<(), Error> {
+        let policy = &cfg.policy;
+        if !policy.allows_enclave(identity) {
+            return Err(Error::InvalidAttestation(
+                "enclave identity not allowed by policy".to_string(),
+            ));
+        }
+        let binding = types::rak_binding(&cfg.id, &registration.rak);
+        if report_data.get(..binding.as_ref().len()) != Some(binding.as_ref()) {
+            return Err(Error::InvalidAttestation(
+                "runtime attestation key not bound to quote".to_string(),
+            ));
+        }
+
+        let epoch = ctx.epoch();
+        if registration.expiration <= epoch
+            || registration.expiration > epoch.saturating_add(policy.max_expiration)
+        {
+            return Err(Error::InvalidArgument);
+        }
+
+        if !policy.allows_endorsement(&registration.node_id, registration.entity_id.as_ref()) {
+            return Err(Error::EndorsementNotAllowed);
+        }
+
+        // Ensure that the transaction is signed by all of the endorsing and endorsed keys.
+        let mut keys = vec![
+            PublicKey::Ed25519(registration.rak.into()),
+            PublicKey::Ed25519(registration.node_id.into()),
+        ];
+        if let Some(entity_id) = registration.entity_id {
+            keys.push(PublicKey::Ed25519(entity_id.into()));
+        }
+        keys.extend(registration.extra_keys.iter().cloned());
+        for key in keys {
+            if !Self::is_signed_by(ctx, &key) {
+                return Err(Error::NotSignedByKey(key));
+            }
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        ctx.emit_event(Event::InstanceRegistered {
+            app_id: registration.app,
+            rak: registration.rak,
+            expiration: registration.expiration,
+        });
+
+        Self::set_registration(registration);
+
+        Ok(())
+    }
+
+    /// Whether the current transaction is signed by the given key.
+    fn is_signed_by<C: TxContext>(ctx: &C, key: &PublicKey) -> bool {
+        ctx.tx_auth_info()
+            .signer_info
+            .iter()
+            .any(|si| match &si.address_spec {
+                AddressSpec::Signature(spec) => &spec.public_key() == key,
+                _ => false,
+            })
+    }
+
+    /// Fetch the given application and ensure that the caller is its administrator.
+    fn get_app_for_admin<C: TxContext>(ctx: &C, id: AppId) -> Result<types::AppConfig, Error> {
+        let cfg = Self::get_app(id)?;
+        if cfg.admin != Some(ctx.tx_caller_address()) {
+            return Err(Error::Forbidden);
+        }
+        Ok(cfg)
+    }
+}
+
+impl API for Module {
+    fn get_app(id: AppId) -> Result<types::AppConfig, Error> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let apps = storage::TypedStore::new(storage::PrefixStore::new(store, &state::APPS));
+            apps.get(id).ok_or(Error::UnknownApp)
+        })
+    }
+
+    fn get_apps() -> Vec<types::AppConfig> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let apps = storage::TypedStore::new(storage::PrefixStore::new(store, &state::APPS));
+            apps.iter::<AppId, types::AppConfig>()
+                .map(|(_, cfg)| cfg)
+                .collect()
+        })
+    }
+
+    fn get_registration(app: AppId, rak: &CorePublicKey) -> Result<types::Registration, Error> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let registrations = storage::PrefixStore::new(store, &state::REGISTRATIONS);
+            let app_registrations =
+                storage::TypedStore::new(storage::PrefixStore::new(registrations, &app));
+            app_registrations.get(rak).ok_or(Error::UnknownInstance)
+        })
+    }
+
+    fn get_active_registrations(app: AppId, epoch: EpochTime) -> Vec<types::Registration> {
+        Self::get_registrations(app)
+            .into_iter()
+            .filter(|registration| registration.expiration >= epoch)
+            .collect()
+    }
+
+    fn check_origins<C: Context>(ctx: &C, tx: &Transaction) -> Result<(), modules::core::Error> {
+        let epoch = ctx.epoch();
+        for si in tx.auth_info.signers() {
+            let spec = match &si.address_spec {
+                AddressSpec::Rofl(spec) => spec,
+                _ => continue,
+            };
+
+            if !Self::is_endorsed(spec.app, &spec.key.public_key(), epoch) {
+                return Err(modules::core::Error::RoflOriginNotAuthorized(format!(
+                    "key not endorsed by a registered instance of {}",
+                    spec.app
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn authorized_origin<C: TxContext>(ctx: &C) -> Option<AppId> {
+        match &ctx.tx_auth_info().signer_info[0].address_spec {
+            // Origins are checked during authentication, but only in runtimes that include this
+            // module, so make sure the key is endorsed.
+            AddressSpec::Rofl(spec)
+                if Self::is_endorsed(spec.app, &spec.key.public_key(), ctx.epoch()) =>
+            {
+                Some(spec.app)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[sdk_derive(Module)]
+impl Module {
+    const NAME: &'static str = MODULE_NAME;
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        for cfg in genesis.apps {
+            Self::set_app(cfg);
+        }
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Create a new application. The caller becomes the application administrator.
+    #[handler(call = "rofl.Create")]
+    fn tx_create<C: TxContext>(ctx: &mut C, body: types::Create) -> Result<AppId, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_create)?;
+
+        if body.policy.max_expiration == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        // Derive the application identifier from the caller and the transaction nonce so that it
+        // is unique and known to the caller in advance.
+        let caller = ctx.tx_caller_address();
+        let nonce = ctx.tx_auth_info().signer_info[0].nonce;
+        let id = AppId::from_creator_nonce(&caller, nonce);
+
+        if ctx.is_check_only() {
+            return Ok(id);
+        }
+
+        Self::set_app(types::AppConfig {
+            id,
+            policy: body.policy,
+            admin: Some(caller),
+        });
+
+        ctx.emit_event(Event::AppCreated { id });
+
+        Ok(id)
+    }
+
+    /// Update the configuration of an application. Only the application administrator can update
+    /// the application.
+    #[handler(call = "rofl.Update")]
+    fn tx_update<C: TxContext>(ctx: &mut C, body: types::Update) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_update)?;
+
+        if body.policy.max_expiration == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        let mut cfg = Self::get_app_for_admin(ctx, body.id)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        cfg.policy = body.policy;
+        cfg.admin = body.admin;
+        Self::set_app(cfg);
+
+        ctx.emit_event(Event::AppUpdated { id: body.id });
+
+        Ok(())
+    }
+
+    /// Remove an application together with all of its instance registrations. Only the
+    /// application administrator can remove the application.
+    #[handler(call = "rofl.Remove")]
+    fn tx_remove<C: TxContext>(ctx: &mut C, body: types::Remove) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_remove)?;
+
+        Self::get_app_for_admin(ctx, body.id)?;
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::remove_app(body.id);
+
+        ctx.emit_event(Event::AppRemoved { id: body.id });
+
+        Ok(())
+    }
+
+    /// Register an instance of an application. The instance must present a remote attestation
+    /// quote satisfying the application policy and the transaction must be signed by the keys
+    /// being registered as well as by the endorsing node.
+    #[handler(call = "rofl.Register")]
+    fn tx_register<C: TxContext>(ctx: &mut C, body: types::Register) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_register)?;
+
+        let cfg = Self::get_app(body.app)?;
+
+        let verified = body
+            .quote
+            .verify(&cfg.policy.quotes)
+            .map_err(|err| Error::InvalidAttestation(err.to_string()))?;
+
+        let registration = types::Registration {
+            app: body.app,
+            node_id: body.node_id,
+            entity_id: body.entity_id,
+            rak: body.rak,
+            expiration: body.expiration,
+            extra_keys: body.extra_keys,
+        };
+        Self::register_verified(
+            ctx,
+            &cfg,
+            registration,
+            &verified.identity,
+            &verified.report_data,
+        )
+    }
+
+    #[handler(query = "rofl.App")]
+    fn query_app<C: Context>(
+        _ctx: &mut C,
+        args: types::AppQuery,
+    ) -> Result<types::AppConfig, Error> {
+        Self::get_app(args.id)
+    }
+
+    #[handler(query = "rofl.Apps")]
+    fn query_apps<C: Context>(
+        _ctx: &mut C,
+        args: types::AppsQuery,
+    ) -> Result<PageResponse<types::AppConfig>, Error> {
+        let start = args
+            .pagination
+            .start::<AppId>()
+            .map_err(|_| Error::InvalidArgument)?;
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let apps = storage::StoreMap::<_, AppId, types::AppConfig>::new(
+                storage::PrefixStore::new(store, &state::APPS),
+            );
+            let page = apps.page(start.as_ref(), args.pagination.limit());
+            Ok(PageResponse::from_page(page, |(_, cfg)| cfg))
+        })
+    }
+
+    /// Registration of an active application instance.
+    #[handler(query = "rofl.AppInstance")]
+    fn query_app_instance<C: Context>(
+        ctx: &mut C,
+        args: types::AppInstanceQuery,
+    ) -> Result<types::Registration, Error> {
+        let registration = Self::get_registration(args.app, &args.rak)?;
+        if registration.expiration < ctx.epoch() {
+            return Err(Error::UnknownInstance);
+        }
+        Ok(registration)
+    }
+
+    /// Registrations of all active instances of an application.
+    #[handler(query = "rofl.AppInstances")]
+    fn query_app_instances<C: Context>(
+        ctx: &mut C,
+        args: types::AppInstancesQuery,
+    ) -> Result<PageResponse<types::Registration>, Error> {
+        Self::get_app(args.id)?;
+        let start = args
+            .pagination
+            .start::<Vec<u8>>()
+            .map_err(|_| Error::InvalidArgument)?;
+        let epoch = ctx.epoch();
+
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let registrations = storage::PrefixStore::new(store, &state::REGISTRATIONS);
+            let app_registrations = storage::StoreMap::<_, Vec<u8>, types::Registration>::new(
+                storage::PrefixStore::new(registrations, &args.id),
+            );
+            let active = app_registrations
+                .iter_from(&start.unwrap_or_default())
+                .filter(|(_, registration)| registration.expiration >= epoch);
+            Ok(paginate(active, &args.pagination))
+        })
+    }
+}
+
+impl module::TransactionHandler for Module {
+    fn authenticate_tx<C: Context>(
+        ctx: &mut C,
+        tx: &Transaction,
+    ) -> Result<(), modules::core::Error> {
+        // Check that any ROFL origin keys are endorsed by registered application instances.
+        Self::check_origins(ctx, tx)
+    }
+}
+
+impl module::BlockHandler for Module {
+    fn end_block<C: Context>(ctx: &mut C) {
+        // Expire registrations at the start of each epoch.
+        if <C::Runtime as Runtime>::Core::has_epoch_changed(ctx) {
+            Self::expire_registrations(ctx.epoch());
+        }
+    }
+}
+
+impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            apps: Self::get_apps(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
+
+/// A struct that exists solely to decode expiration queue keys.
+struct ExpirationQueueEntry {
+    expiration: EpochTime,
+    app: AppId,
+    rak: CorePublicKey,
+}
+
+impl ExpirationQueueEntry {
+    /// Size of the expiration queue keys in bytes.
+    const SIZE: usize = 8 + AppId::SIZE + 32;
+
+    /// Expiration queue key of the given registration.
+    fn key(expiration: EpochTime, app: &AppId, rak: &CorePublicKey) -> Vec<u8> {
+        [&expiration.to_be_bytes(), app.as_ref(), rak.as_ref()].concat()
+    }
+}
+
+impl TryFrom<&[u8]> for ExpirationQueueEntry {
+    type Error = app_id::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != Self::SIZE {
+            return Err(app_id::Error::MalformedIdentifier);
+        }
+        let mut expiration = [0; 8];
+        expiration.copy_from_slice(&value[..8]);
+        let app = AppId::from_bytes(&value[8..8 + AppId::SIZE])?;
+        let rak = CorePublicKey::from(&value[8 + AppId::SIZE..]);
+        Ok(ExpirationQueueEntry {
+            expiration: EpochTime::from_be_bytes(expiration),
+            app,
+            rak,
+        })
+    }
+}
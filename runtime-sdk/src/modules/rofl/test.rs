@@ -0,0 +1,522 @@
+//! Tests for the ROFL module.
+use oasis_core_runtime::common::{
+    crypto::signature::PublicKey as CorePublicKey,
+    sgx::{EnclaveIdentity, MrEnclave, MrSigner},
+};
+
+use crate::{
+    context::BatchContext,
+    crypto::signature::PublicKey,
+    module::{BlockHandler, TransactionHandler as _},
+    runtime::Runtime,
+    testing::{
+        keys,
+        mock::{self, EmptyRuntime},
+    },
+    types::{app_id::AppId, transaction},
+};
+
+use super::{types, Error, Genesis, Module as Rofl, API as _};
+
+fn test_policy() -> types::AppPolicy {
+    types::AppPolicy {
+        quotes: Default::default(),
+        enclaves: vec![EnclaveIdentity {
+            mr_enclave: MrEnclave::from(
+                "c3a3e4d5a2b6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f70",
+            ),
+            mr_signer: MrSigner::from(
+                "9affcfae47b848ec2caf1c49b4b283531e1cc425f93582b36806e52a43d78d1a",
+            ),
+        }],
+        endorsements: vec![types::AllowedEndorsement::Entity(
+            keys::bob::pk_ed25519().into(),
+        )],
+        max_expiration: 2,
+    }
+}
+
+#[test]
+fn test_app_policy() {
+    let policy = test_policy();
+
+    assert!(policy.allows_enclave(&policy.enclaves[0]));
+    assert!(!policy.allows_enclave(&EnclaveIdentity {
+        mr_enclave: policy.enclaves[0].mr_enclave,
+        mr_signer: MrSigner::from(
+            "0000000000000000000000000000000000000000000000000000000000000000"
+        ),
+    }));
+
+    let node_id: CorePublicKey = keys::charlie::pk_ed25519().into();
+    assert!(policy.allows_endorsement(&node_id, Some(&keys::bob::pk_ed25519().into())));
+    assert!(!policy.allows_endorsement(&node_id, Some(&node_id)));
+    assert!(!policy.allows_endorsement(&node_id, None));
+
+    let policy = types::AppPolicy {
+        endorsements: vec![types::AllowedEndorsement::Node(node_id)],
+        ..policy
+    };
+    assert!(policy.allows_endorsement(&node_id, None));
+}
+
+#[test]
+fn test_app_lifecycle() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    Rofl::init(Genesis::default());
+
+    let alice = transaction::SignerInfo::new_sigspec(keys::alice::sigspec(), 3);
+    let bob = keys::bob::signer_info();
+
+    // Create an application.
+    let tx = mock::transaction_from(&alice, "rofl.Create");
+    let id = ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let result = Rofl::tx_create(
+            &mut tx_ctx,
+            types::Create {
+                policy: types::AppPolicy {
+                    max_expiration: 0,
+                    ..test_policy()
+                },
+            },
+        );
+        assert!(matches!(result, Err(Error::InvalidArgument)));
+
+        let id = Rofl::tx_create(
+            &mut tx_ctx,
+            types::Create {
+                policy: test_policy(),
+            },
+        )
+        .expect("app creation should succeed");
+
+        let state = tx_ctx.commit();
+        let tags = state.events.into_tags();
+        assert_eq!(tags.len(), 1, "app created event should be emitted");
+        assert_eq!(tags[0].key, b"rofl\x00\x00\x00\x01"); // rofl.AppCreated (code = 1) event
+
+        id
+    });
+    assert_eq!(id, AppId::from_creator_nonce(&keys::alice::address(), 3));
+
+    let cfg = Rofl::get_app(id).expect("app should exist");
+    assert_eq!(
+        cfg,
+        types::AppConfig {
+            id,
+            policy: test_policy(),
+            admin: Some(keys::alice::address()),
+        }
+    );
+    assert_eq!(Rofl::get_apps(), vec![cfg.clone()]);
+
+    let apps =
+        Rofl::query_apps(&mut ctx, types::AppsQuery::default()).expect("apps query should succeed");
+    assert_eq!(apps.items, vec![cfg]);
+    assert_eq!(apps.next, None);
+
+    // Only the administrator can update the application.
+    let update = types::Update {
+        id,
+        policy: types::AppPolicy {
+            max_expiration: 5,
+            ..test_policy()
+        },
+        admin: Some(keys::alice::address()),
+    };
+    let tx = mock::transaction_from(&bob, "rofl.Update");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let result = Rofl::tx_update(&mut tx_ctx, update.clone());
+        assert!(matches!(result, Err(Error::Forbidden)));
+    });
+    let tx = mock::transaction_from(&alice, "rofl.Update");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Rofl::tx_update(&mut tx_ctx, update.clone()).expect("app update should succeed");
+    });
+    assert_eq!(Rofl::get_app(id).unwrap().policy.max_expiration, 5);
+
+    // Register some instances.
+    let active = types::Registration {
+        app: id,
+        node_id: keys::charlie::pk_ed25519().into(),
+        entity_id: Some(keys::bob::pk_ed25519().into()),
+        rak: CorePublicKey::from(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ),
+        expiration: 3,
+        extra_keys: vec![],
+    };
+    let expired = types::Registration {
+        rak: CorePublicKey::from(
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        ),
+        expiration: 0,
+        ..active.clone()
+    };
+    Rofl::set_registration(active.clone());
+    Rofl::set_registration(expired.clone());
+
+    let instances = Rofl::query_app_instances(
+        &mut ctx,
+        types::AppInstancesQuery {
+            id,
+            ..Default::default()
+        },
+    )
+    .expect("instances query should succeed");
+    assert_eq!(
+        instances.items,
+        vec![active.clone()],
+        "only active instances should be returned"
+    );
+
+    let instance = Rofl::query_app_instance(
+        &mut ctx,
+        types::AppInstanceQuery {
+            app: id,
+            rak: active.rak,
+        },
+    )
+    .expect("instance query should succeed");
+    assert_eq!(instance, active);
+
+    let result = Rofl::query_app_instance(
+        &mut ctx,
+        types::AppInstanceQuery {
+            app: id,
+            rak: expired.rak,
+        },
+    );
+    assert!(matches!(result, Err(Error::UnknownInstance)));
+
+    // Remove the application.
+    let tx = mock::transaction_from(&bob, "rofl.Remove");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let result = Rofl::tx_remove(&mut tx_ctx, types::Remove { id });
+        assert!(matches!(result, Err(Error::Forbidden)));
+    });
+    let tx = mock::transaction_from(&alice, "rofl.Remove");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Rofl::tx_remove(&mut tx_ctx, types::Remove { id }).expect("app removal should succeed");
+    });
+
+    assert!(matches!(Rofl::get_app(id), Err(Error::UnknownApp)));
+    assert!(matches!(
+        Rofl::get_registration(id, &active.rak),
+        Err(Error::UnknownInstance)
+    ));
+    assert!(Rofl::get_apps().is_empty());
+}
+
+#[test]
+fn test_app_without_admin() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    let id = AppId::from_creator_nonce(&keys::alice::address(), 0);
+    Rofl::init(Genesis {
+        apps: vec![types::AppConfig {
+            id,
+            policy: test_policy(),
+            admin: None,
+        }],
+        ..Default::default()
+    });
+
+    let tx = mock::transaction_from(&keys::alice::signer_info(), "rofl.Remove");
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let result = Rofl::tx_remove(&mut tx_ctx, types::Remove { id });
+        assert!(
+            matches!(result, Err(Error::Forbidden)),
+            "apps without an administrator should be immutable"
+        );
+    });
+    assert!(Rofl::get_app(id).is_ok());
+}
+
+#[test]
+fn test_register_instance() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+    <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+
+    let id = AppId::from_creator_nonce(&keys::alice::address(), 0);
+    let cfg = types::AppConfig {
+        id,
+        policy: types::AppPolicy {
+            endorsements: vec![types::AllowedEndorsement::Node(
+                keys::charlie::pk_ed25519().into(),
+            )],
+            ..test_policy()
+        },
+        admin: None,
+    };
+    Rofl::init(Genesis {
+        apps: vec![cfg.clone()],
+        ..Default::default()
+    });
+
+    // The instance's runtime attestation key is Alice's key and it is hosted by Charlie's node.
+    let rak: CorePublicKey = keys::alice::pk_ed25519().into();
+    let registration = types::Registration {
+        app: id,
+        node_id: keys::charlie::pk_ed25519().into(),
+        entity_id: None,
+        rak,
+        expiration: 3,
+        extra_keys: vec![],
+    };
+    let identity = &cfg.policy.enclaves[0];
+    let report_data = types::rak_binding(&id, &rak);
+
+    let mut tx = mock::transaction_from(&keys::alice::signer_info(), "rofl.Register");
+    tx.auth_info
+        .signer_info
+        .push(transaction::SignerInfo::new_sigspec(
+            keys::charlie::sigspec(),
+            0,
+        ));
+
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        let other_identity = EnclaveIdentity {
+            mr_enclave: identity.mr_enclave,
+            mr_signer: MrSigner::from(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        };
+        for (registration, identity, report_data, check) in [
+            (
+                registration.clone(),
+                &other_identity,
+                report_data.as_ref(),
+                "enclave identity should be checked",
+            ),
+            (
+                registration.clone(),
+                identity,
+                types::rak_binding(&id, &registration.node_id).as_ref(),
+                "runtime attestation key binding should be checked",
+            ),
+        ] {
+            let result =
+                Rofl::register_verified(&mut tx_ctx, &cfg, registration, identity, report_data);
+            assert!(
+                matches!(result, Err(Error::InvalidAttestation(_))),
+                "{check}"
+            );
+        }
+
+        for expiration in [1, 4] {
+            let result = Rofl::register_verified(
+                &mut tx_ctx,
+                &cfg,
+                types::Registration {
+                    expiration,
+                    ..registration.clone()
+                },
+                identity,
+                report_data.as_ref(),
+            );
+            assert!(
+                matches!(result, Err(Error::InvalidArgument)),
+                "expiration should be within policy limits"
+            );
+        }
+
+        let result = Rofl::register_verified(
+            &mut tx_ctx,
+            &cfg,
+            types::Registration {
+                node_id: keys::bob::pk_ed25519().into(),
+                ..registration.clone()
+            },
+            identity,
+            report_data.as_ref(),
+        );
+        assert!(matches!(result, Err(Error::EndorsementNotAllowed)));
+
+        let result = Rofl::register_verified(
+            &mut tx_ctx,
+            &cfg,
+            types::Registration {
+                extra_keys: vec![keys::bob::pk()],
+                ..registration.clone()
+            },
+            identity,
+            report_data.as_ref(),
+        );
+        assert!(
+            matches!(result, Err(Error::NotSignedByKey(PublicKey::Ed25519(_)))),
+            "extra keys should sign the registration"
+        );
+
+        Rofl::register_verified(
+            &mut tx_ctx,
+            &cfg,
+            registration.clone(),
+            identity,
+            report_data.as_ref(),
+        )
+        .expect("registration should succeed");
+
+        let state = tx_ctx.commit();
+        let tags = state.events.into_tags();
+        assert_eq!(tags.len(), 1, "instance registered event should be emitted");
+        assert_eq!(tags[0].key, b"rofl\x00\x00\x00\x04"); // rofl.InstanceRegistered (code = 4) event
+    });
+
+    assert_eq!(
+        Rofl::get_registration(id, &rak).expect("instance should be registered"),
+        registration
+    );
+    Rofl::end_block(&mut ctx);
+
+    // Registrations should expire after their expiration epoch.
+    for (epoch, registered) in [(3, true), (4, false)] {
+        mock.epoch = epoch;
+
+        let mut ctx = mock.create_ctx();
+        <EmptyRuntime as Runtime>::Core::begin_block(&mut ctx);
+        Rofl::end_block(&mut ctx);
+
+        assert_eq!(Rofl::get_registration(id, &rak).is_ok(), registered);
+    }
+}
+
+#[test]
+fn test_rofl_origin() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx();
+
+    let id = AppId::from_creator_nonce(&keys::alice::address(), 0);
+    let other_id = AppId::from_creator_nonce(&keys::alice::address(), 1);
+    Rofl::init(Genesis {
+        apps: vec![types::AppConfig {
+            id,
+            policy: test_policy(),
+            admin: None,
+        }],
+        ..Default::default()
+    });
+
+    // The instance's runtime attestation key is Alice's key and it endorses Bob's key.
+    Rofl::set_registration(types::Registration {
+        app: id,
+        node_id: keys::charlie::pk_ed25519().into(),
+        entity_id: None,
+        rak: keys::alice::pk_ed25519().into(),
+        expiration: 2,
+        extra_keys: vec![keys::bob::pk()],
+    });
+
+    let origin = |app: AppId, key| transaction::SignerInfo {
+        address_spec: transaction::AddressSpec::Rofl(transaction::RoflOriginSpec { app, key }),
+        nonce: 0,
+    };
+    let signer = origin(id, keys::bob::sigspec());
+    assert_ne!(
+        signer.address_spec.address(),
+        keys::bob::address(),
+        "ROFL origin signers should not share the address of the key"
+    );
+    assert_ne!(
+        signer.address_spec.address(),
+        origin(other_id, keys::bob::sigspec())
+            .address_spec
+            .address(),
+        "ROFL origin signers of different apps should not share an address"
+    );
+    assert_eq!(
+        signer.address_spec.caller_address().address(),
+        signer.address_spec.address(),
+        "ROFL origin callers should use the origin address"
+    );
+
+    for (signer, authorized, check) in [
+        (
+            origin(id, keys::alice::sigspec()),
+            true,
+            "rak should be endorsed",
+        ),
+        (signer.clone(), true, "extra keys should be endorsed"),
+        (
+            origin(id, keys::charlie::sigspec()),
+            false,
+            "other keys should not be endorsed",
+        ),
+        (
+            origin(other_id, keys::bob::sigspec()),
+            false,
+            "keys should be endorsed for the app",
+        ),
+    ] {
+        let tx = mock::transaction_from(&signer, "test.Method");
+        assert_eq!(
+            Rofl::check_origins(&ctx, &tx).is_ok(),
+            authorized,
+            "{check}"
+        );
+    }
+
+    let tx = mock::transaction_from(&signer, "test.Method");
+    ctx.with_tx(tx.clone().into(), |tx_ctx, _call| {
+        assert_eq!(Rofl::authorized_origin(&tx_ctx), Some(id));
+        assert!(Rofl::is_authorized_origin(&tx_ctx, id));
+        assert!(!Rofl::is_authorized_origin(&tx_ctx, other_id));
+    });
+    let tx_plain = mock::transaction_from(&keys::bob::signer_info(), "test.Method");
+    ctx.with_tx(tx_plain.into(), |tx_ctx, _call| {
+        assert!(
+            !Rofl::is_authorized_origin(&tx_ctx, id),
+            "plain signatures should not be authorized origins"
+        );
+    });
+
+    // Keys are no longer endorsed after the registration expires.
+    mock.epoch = 3;
+    let mut ctx = mock.create_ctx();
+    let result = Rofl::authenticate_tx(&mut ctx, &tx);
+    assert!(matches!(
+        result,
+        Err(crate::modules::core::Error::RoflOriginNotAuthorized(_))
+    ));
+    ctx.with_tx(tx.into(), |tx_ctx, _call| {
+        assert_eq!(
+            Rofl::authorized_origin(&tx_ctx),
+            None,
+            "expired origins should not be authorized"
+        );
+    });
+}
+
+#[test]
+fn test_export_state() {
+    let _mock = mock::Mock::default();
+    let id = AppId::from_creator_nonce(&keys::alice::address(), 0);
+    Rofl::init(Genesis {
+        apps: vec![types::AppConfig {
+            id,
+            policy: test_policy(),
+            admin: Some(keys::alice::address()),
+        }],
+        ..Default::default()
+    });
+    Rofl::set_registration(types::Registration {
+        app: id,
+        node_id: keys::charlie::pk_ed25519().into(),
+        entity_id: None,
+        rak: keys::alice::pk_ed25519().into(),
+        expiration: 3,
+        extra_keys: vec![],
+    });
+
+    let genesis = mock::assert_export_round_trip::<Rofl>();
+    assert_eq!(genesis.apps.len(), 1);
+    for prefix in [super::state::REGISTRATIONS, super::state::EXPIRATION_QUEUE] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "registrations and the expiration queue should be exported"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! ROFL module types.
+use oasis_core_runtime::{
+    common::{
+        crypto::{hash::Hash, signature::PublicKey as CorePublicKey},
+        sgx::{EnclaveIdentity, Quote, QuotePolicy},
+    },
+    consensus::beacon::EpochTime,
+};
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::{
+    crypto::signature::PublicKey,
+    types::{address::Address, app_id::AppId, pagination::PageRequest},
+};
+
+/// Context used for binding runtime attestation keys to remote attestation quotes.
+const RAK_BINDING_CONTEXT: &[u8] = b"oasis-sdk/rofl: rak binding";
+
+/// Report data that an application instance must include in its remote attestation quote in
+/// order to bind its runtime attestation key to the quote.
+pub fn rak_binding(app: &AppId, rak: &CorePublicKey) -> Hash {
+    Hash::digest_bytes_list(&[RAK_BINDING_CONTEXT, app.as_ref(), rak.as_ref()])
+}
+
+/// An endorsement that an application instance may present as part of its registration.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub enum AllowedEndorsement {
+    /// Any node can endorse the instance.
+    #[cbor(rename = "any")]
+    Any,
+    /// The instance must be endorsed by a node owned by the given entity.
+    #[cbor(rename = "entity")]
+    Entity(CorePublicKey),
+    /// The instance must be endorsed by the given node.
+    #[cbor(rename = "node")]
+    Node(CorePublicKey),
+}
+
+/// Policy that application instances must satisfy in order to register.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppPolicy {
+    /// Policy that remote attestation quotes of application instances must satisfy.
+    #[cbor(optional)]
+    pub quotes: QuotePolicy,
+    /// Identities of the TEE enclaves allowed to run application instances.
+    pub enclaves: Vec<EnclaveIdentity>,
+    /// Endorsements of which at least one must be presented by application instances. An empty
+    /// list means that no instances can be registered.
+    pub endorsements: Vec<AllowedEndorsement>,
+    /// Maximum number of epochs for which an instance registration is valid.
+    pub max_expiration: EpochTime,
+}
+
+impl AppPolicy {
+    /// Whether the policy allows instances running in the given enclave.
+    pub fn allows_enclave(&self, identity: &EnclaveIdentity) -> bool {
+        self.enclaves.iter().any(|allowed| allowed == identity)
+    }
+
+    /// Whether the policy allows instances endorsed by the given node owned by the given entity.
+    pub fn allows_endorsement(
+        &self,
+        node_id: &CorePublicKey,
+        entity_id: Option<&CorePublicKey>,
+    ) -> bool {
+        self.endorsements.iter().any(|allowed| match allowed {
+            AllowedEndorsement::Any => true,
+            AllowedEndorsement::Entity(id) => Some(id) == entity_id,
+            AllowedEndorsement::Node(id) => id == node_id,
+        })
+    }
+}
+
+/// Application configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppConfig {
+    /// Application identifier.
+    pub id: AppId,
+    /// Application instance registration policy.
+    pub policy: AppPolicy,
+    /// Application administrator address. Applications without an administrator can no longer be
+    /// updated or removed.
+    #[cbor(optional)]
+    pub admin: Option<Address>,
+}
+
+/// Registration of an application instance, endorsing the instance's keys.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Registration {
+    /// Application identifier.
+    pub app: AppId,
+    /// Identifier of the node hosting the instance.
+    pub node_id: CorePublicKey,
+    /// Identifier of the entity owning the node hosting the instance.
+    #[cbor(optional)]
+    pub entity_id: Option<CorePublicKey>,
+    /// Runtime attestation key of the instance.
+    pub rak: CorePublicKey,
+    /// Epoch after which the registration is no longer valid.
+    pub expiration: EpochTime,
+    /// Additional keys endorsed by the instance, for example for signing transactions.
+    #[cbor(optional)]
+    pub extra_keys: Vec<PublicKey>,
+}
+
+/// Create new application call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Create {
+    /// Application instance registration policy.
+    pub policy: AppPolicy,
+}
+
+/// Update application configuration call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Update {
+    /// Application identifier.
+    pub id: AppId,
+    /// New application instance registration policy.
+    pub policy: AppPolicy,
+    /// New application administrator address. None means that the application can no longer be
+    /// updated or removed.
+    #[cbor(optional)]
+    pub admin: Option<Address>,
+}
+
+/// Remove application call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Remove {
+    /// Application identifier.
+    pub id: AppId,
+}
+
+/// Register application instance call.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+pub struct Register {
+    /// Application identifier.
+    pub app: AppId,
+    /// Remote attestation quote of the enclave running the instance. The first 32 bytes of the
+    /// report data must be the binding of the runtime attestation key, see `rak_binding`.
+    pub quote: Quote,
+    /// Identifier of the node hosting the instance. The transaction must be signed by the node.
+    pub node_id: CorePublicKey,
+    /// Identifier of the entity owning the node hosting the instance. In case it is set, the
+    /// transaction must be signed by the entity.
+    #[cbor(optional)]
+    pub entity_id: Option<CorePublicKey>,
+    /// Runtime attestation key of the instance. The transaction must be signed by this key.
+    pub rak: CorePublicKey,
+    /// Epoch after which the registration is no longer valid.
+    pub expiration: EpochTime,
+    /// Additional keys endorsed by the instance. The transaction must be signed by all of them.
+    #[cbor(optional)]
+    pub extra_keys: Vec<PublicKey>,
+}
+
+/// Application-related query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppQuery {
+    /// Application identifier.
+    pub id: AppId,
+}
+
+/// Applications query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppsQuery {
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
+
+/// Application instances query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppInstancesQuery {
+    /// Application identifier.
+    pub id: AppId,
+
+    #[cbor(optional)]
+    pub pagination: PageRequest,
+}
+
+/// Application instance query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct AppInstanceQuery {
+    /// Application identifier.
+    pub app: AppId,
+    /// Runtime attestation key of the instance.
+    pub rak: CorePublicKey,
+}
@@ -0,0 +1,423 @@
+//! Safety module acting as a circuit breaker on anomalous token flows.
+//!
+//! Modules moving tokens out of the runtime (e.g. consensus withdrawals or bridge transfers)
+//! report the outflows to this module, which tracks the volume of each denomination per block and
+//! per epoch. Once a configured threshold is exceeded, the circuit breaker of the denomination is
+//! tripped and an alert event is emitted. While tripped, further outflows of the denomination are
+//! rejected and so are calls to any of the configured paused methods. Guardians can also trip the
+//! circuit breakers manually and need to reset them to resume normal operation.
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    types::{
+        address::Address,
+        token::{self, Denomination},
+        transaction::Call,
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "safety";
+
+/// Errors emitted by the safety module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 2)]
+    Forbidden,
+
+    #[error("circuit breaker tripped")]
+    #[sdk_error(code = 3)]
+    Tripped,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the safety module.
+///
+/// Operators should watch for `Tripped` events as they signal that an anomalous outflow has been
+/// detected (or that a guardian has intervened) and that outflows have been halted.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    Tripped {
+        denomination: Denomination,
+        outflow: types::Outflow,
+    },
+
+    #[sdk_event(code = 2)]
+    Reset { denomination: Denomination },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_trip: u64,
+    pub tx_reset: u64,
+}
+
+/// Parameters for the safety module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Outflow thresholds. Outflows of denominations without a threshold are not tracked.
+    #[cbor(optional)]
+    pub thresholds: Vec<types::Threshold>,
+    /// Methods that cannot be called while any circuit breaker is tripped. A method of the form
+    /// `module.*` matches all methods of the module. Methods of this module are never paused.
+    #[cbor(optional)]
+    pub paused_methods: Vec<String>,
+    /// Addresses allowed to trip and reset the circuit breakers.
+    #[cbor(optional)]
+    pub guardians: Vec<Address>,
+}
+
+impl Parameters {
+    /// Threshold for the given denomination.
+    pub fn threshold(&self, denomination: &Denomination) -> Option<&types::Threshold> {
+        self.thresholds
+            .iter()
+            .find(|threshold| &threshold.denomination == denomination)
+    }
+
+    /// Whether the given method is paused while any circuit breaker is tripped.
+    pub fn is_paused_method(&self, method: &str) -> bool {
+        let module = method.split('.').next().unwrap_or_default();
+        if module == MODULE_NAME {
+            return false;
+        }
+        self.paused_methods
+            .iter()
+            .any(|paused| paused == method || paused.strip_suffix(".*") == Some(module))
+    }
+}
+
+/// Errors emitted during safety parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("duplicate threshold")]
+    DuplicateThreshold,
+
+    #[error("invalid paused method")]
+    InvalidPausedMethod,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        for (i, threshold) in self.thresholds.iter().enumerate() {
+            if self.thresholds[..i]
+                .iter()
+                .any(|other| other.denomination == threshold.denomination)
+            {
+                return Err(ParameterValidationError::DuplicateThreshold);
+            }
+        }
+        for method in &self.paused_methods {
+            match method.split_once('.') {
+                Some((module, name)) if !module.is_empty() && !name.is_empty() => {}
+                _ => return Err(ParameterValidationError::InvalidPausedMethod),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the safety module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of denominations to their recent outflows.
+    pub const OUTFLOWS: &[u8] = &[0x01];
+    /// Set of denominations whose circuit breakers are tripped.
+    pub const TRIPPED: &[u8] = &[0x02];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[OUTFLOWS, TRIPPED];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Account for an amount flowing out of the runtime.
+    ///
+    /// Fails in case the circuit breaker of the denomination is tripped. In case the outflow
+    /// exceeds any of the thresholds, the outflow itself is allowed but the circuit breaker is
+    /// tripped so that any further outflows are rejected.
+    fn record_outflow<C: Context>(ctx: &mut C, amount: &token::BaseUnits) -> Result<(), Error>;
+
+    /// Whether the circuit breaker of the given denomination is tripped.
+    fn is_tripped(denomination: &Denomination) -> bool;
+
+    /// Denominations whose circuit breakers are tripped.
+    fn get_tripped() -> Vec<Denomination>;
+
+    /// Trip the circuit breaker of the given denomination.
+    fn trip<C: Context>(ctx: &mut C, denomination: Denomination);
+
+    /// Reset the circuit breaker of the given denomination, also clearing its recent outflows.
+    fn reset<C: Context>(ctx: &mut C, denomination: Denomination);
+}
+
+pub struct Module;
+
+impl Module {
+    fn get_outflow(denomination: &Denomination) -> types::Outflow {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let outflows =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::OUTFLOWS));
+            outflows.get(denomination).unwrap_or_default()
+        })
+    }
+
+    fn set_outflow(denomination: &Denomination, outflow: Option<types::Outflow>) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut outflows =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::OUTFLOWS));
+            match outflow {
+                Some(outflow) => outflows.insert(denomination, outflow),
+                None => outflows.remove(denomination),
+            }
+        });
+    }
+
+    fn set_tripped(denomination: &Denomination, tripped: bool) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut denominations =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::TRIPPED));
+            if tripped {
+                denominations.insert(denomination, true);
+            } else {
+                denominations.remove(denomination);
+            }
+        });
+    }
+
+    /// Recent outflows of the given denomination, with the volumes of past blocks and epochs
+    /// cleared.
+    fn current_outflow<C: Context>(ctx: &C, denomination: &Denomination) -> types::Outflow {
+        let mut outflow = Self::get_outflow(denomination);
+        let round = ctx.time().round();
+        if outflow.round != round {
+            outflow.round = round;
+            outflow.block_volume = 0;
+        }
+        let epoch = ctx.epoch();
+        if outflow.epoch != epoch {
+            outflow.epoch = epoch;
+            outflow.epoch_volume = 0;
+        }
+        outflow
+    }
+
+    fn ensure_guardian<C: TxContext>(ctx: &C) -> Result<(), Error> {
+        if !Self::params().guardians.contains(&ctx.tx_caller_address()) {
+            return Err(Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+impl API for Module {
+    fn record_outflow<C: Context>(ctx: &mut C, amount: &token::BaseUnits) -> Result<(), Error> {
+        let denomination = amount.denomination();
+        if Self::is_tripped(denomination) {
+            return Err(Error::Tripped);
+        }
+        let params = Self::params();
+        let threshold = match params.threshold(denomination) {
+            Some(threshold) => threshold,
+            None => return Ok(()),
+        };
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        let mut outflow = Self::current_outflow(ctx, denomination);
+        outflow.block_volume = outflow.block_volume.saturating_add(amount.amount());
+        outflow.epoch_volume = outflow.epoch_volume.saturating_add(amount.amount());
+        Self::set_outflow(denomination, Some(outflow.clone()));
+
+        if (threshold.max_per_block > 0 && outflow.block_volume > threshold.max_per_block)
+            || (threshold.max_per_epoch > 0 && outflow.epoch_volume > threshold.max_per_epoch)
+        {
+            Self::set_tripped(denomination, true);
+            ctx.emit_event(Event::Tripped {
+                denomination: denomination.clone(),
+                outflow,
+            });
+        }
+        Ok(())
+    }
+
+    fn is_tripped(denomination: &Denomination) -> bool {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let denominations =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::TRIPPED));
+            denominations
+                .get::<_, bool>(denomination)
+                .unwrap_or_default()
+        })
+    }
+
+    fn get_tripped() -> Vec<Denomination> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let denominations =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::TRIPPED));
+            denominations
+                .iter::<Denomination, bool>()
+                .map(|(denomination, _)| denomination)
+                .collect()
+        })
+    }
+
+    fn trip<C: Context>(ctx: &mut C, denomination: Denomination) {
+        let outflow = Self::current_outflow(ctx, &denomination);
+        Self::set_tripped(&denomination, true);
+        ctx.emit_event(Event::Tripped {
+            denomination,
+            outflow,
+        });
+    }
+
+    fn reset<C: Context>(ctx: &mut C, denomination: Denomination) {
+        Self::set_tripped(&denomination, false);
+        Self::set_outflow(&denomination, None);
+        ctx.emit_event(Event::Reset { denomination });
+    }
+}
+
+#[sdk_derive(Module)]
+impl Module {
+    const NAME: &'static str = MODULE_NAME;
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Trip the circuit breaker of a denomination. Only guardians can trip circuit breakers.
+    #[handler(call = "safety.Trip")]
+    fn tx_trip<C: TxContext>(ctx: &mut C, body: types::Trip) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_trip)?;
+
+        Self::ensure_guardian(ctx)?;
+        if Self::is_tripped(&body.denomination) {
+            return Err(Error::InvalidArgument);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::trip(ctx, body.denomination);
+
+        Ok(())
+    }
+
+    /// Reset the circuit breaker of a denomination. Only guardians can reset circuit breakers.
+    #[handler(call = "safety.Reset")]
+    fn tx_reset<C: TxContext>(ctx: &mut C, body: types::Reset) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_reset)?;
+
+        Self::ensure_guardian(ctx)?;
+        if !Self::is_tripped(&body.denomination) {
+            return Err(Error::InvalidArgument);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::reset(ctx, body.denomination);
+
+        Ok(())
+    }
+
+    /// Denominations whose circuit breakers are tripped.
+    #[handler(query = "safety.Tripped")]
+    fn query_tripped<C: Context>(_ctx: &mut C, _args: ()) -> Result<Vec<Denomination>, Error> {
+        Ok(Self::get_tripped())
+    }
+
+    /// Outflows of a denomination during the current block and epoch.
+    #[handler(query = "safety.Outflow")]
+    fn query_outflow<C: Context>(
+        ctx: &mut C,
+        args: types::OutflowQuery,
+    ) -> Result<types::Outflow, Error> {
+        Ok(Self::current_outflow(ctx, &args.denomination))
+    }
+}
+
+impl module::TransactionHandler for Module {
+    fn before_authorized_call_dispatch<C: TxContext>(
+        _ctx: &mut C,
+        call: &Call,
+    ) -> Result<(), modules::core::Error> {
+        if Self::params().is_paused_method(&call.method) && !Self::get_tripped().is_empty() {
+            return Err(modules::core::Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+impl module::BlockHandler for Module {}
+
+impl module::InvariantHandler for Module {}
+
+impl module::StateExporter for Module {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
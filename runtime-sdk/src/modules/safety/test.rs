@@ -0,0 +1,218 @@
+//! Tests for the safety module.
+use crate::{
+    context::BatchContext,
+    module::{self, TransactionHandler},
+    modules::core,
+    testing::{keys, mock},
+    types::{
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+};
+
+use super::{types, Error, Genesis, Module as Safety, Parameters, API as _};
+
+fn test_params() -> Parameters {
+    Parameters {
+        gas_costs: Default::default(),
+        thresholds: vec![types::Threshold {
+            denomination: Denomination::NATIVE,
+            max_per_block: 100,
+            max_per_epoch: 150,
+        }],
+        paused_methods: vec!["accounts.Transfer".to_owned(), "bridge.*".to_owned()],
+        guardians: vec![keys::alice::address()],
+    }
+}
+
+fn init() {
+    Safety::init(Genesis {
+        parameters: test_params(),
+        ..Default::default()
+    });
+}
+
+fn native(amount: u128) -> BaseUnits {
+    BaseUnits::new(amount, Denomination::NATIVE)
+}
+
+fn dispatch(mock: &mut mock::Mock, method: &str) -> Result<(), core::Error> {
+    let mut ctx = mock.create_ctx();
+    let mut tx = mock::transaction();
+    tx.call.method = method.to_owned();
+    ctx.with_tx(tx.into(), |mut tx_ctx, call| {
+        Safety::before_authorized_call_dispatch(&mut tx_ctx, &call)
+    })
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    test_params()
+        .validate_basic()
+        .expect("test parameters should be valid");
+
+    let mut params = test_params();
+    params.thresholds.push(types::Threshold {
+        denomination: Denomination::NATIVE,
+        ..Default::default()
+    });
+    assert!(
+        params.validate_basic().is_err(),
+        "duplicate thresholds should be rejected"
+    );
+
+    let mut params = test_params();
+    params.paused_methods.push("accounts".to_owned());
+    assert!(
+        params.validate_basic().is_err(),
+        "malformed paused methods should be rejected"
+    );
+
+    let params = test_params();
+    assert!(params.is_paused_method("accounts.Transfer"));
+    assert!(params.is_paused_method("bridge.Lock"));
+    assert!(!params.is_paused_method("accounts.AuthorizeSessionKey"));
+}
+
+#[test]
+fn test_record_outflow() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    // Untracked denominations are not limited.
+    let mut ctx = mock.create_ctx();
+    let other = BaseUnits::new(1_000, "OTHER".parse().unwrap());
+    Safety::record_outflow(&mut ctx, &other).expect("untracked outflows should be allowed");
+
+    // Exceeding the block threshold trips the circuit breaker.
+    Safety::record_outflow(&mut ctx, &native(60)).expect("outflow should be allowed");
+    assert!(!Safety::is_tripped(&Denomination::NATIVE));
+    Safety::record_outflow(&mut ctx, &native(60)).expect("tripping outflow should be allowed");
+    assert!(Safety::is_tripped(&Denomination::NATIVE));
+    assert_eq!(Safety::get_tripped(), vec![Denomination::NATIVE]);
+
+    // Further outflows are rejected, including in later blocks.
+    let result = Safety::record_outflow(&mut ctx, &native(1));
+    assert!(matches!(result, Err(Error::Tripped)));
+    mock.runtime_header.round = 1;
+    let mut ctx = mock.create_ctx();
+    let result = Safety::record_outflow(&mut ctx, &native(1));
+    assert!(matches!(result, Err(Error::Tripped)));
+
+    // Resetting clears the recorded outflows.
+    Safety::reset(&mut ctx, Denomination::NATIVE);
+    assert!(Safety::get_tripped().is_empty());
+    Safety::record_outflow(&mut ctx, &native(100)).expect("outflow should be allowed");
+
+    // Volumes of past blocks count towards the epoch threshold.
+    mock.runtime_header.round = 2;
+    let mut ctx = mock.create_ctx();
+    Safety::record_outflow(&mut ctx, &native(50)).expect("outflow should be allowed");
+    assert!(!Safety::is_tripped(&Denomination::NATIVE));
+    Safety::record_outflow(&mut ctx, &native(1)).expect("tripping outflow should be allowed");
+    assert!(Safety::is_tripped(&Denomination::NATIVE));
+    Safety::reset(&mut ctx, Denomination::NATIVE);
+
+    // Volumes of past epochs do not.
+    Safety::record_outflow(&mut ctx, &native(100)).expect("outflow should be allowed");
+    mock.runtime_header.round = 3;
+    mock.epoch += 1;
+    let mut ctx = mock.create_ctx();
+    Safety::record_outflow(&mut ctx, &native(100)).expect("outflow should be allowed");
+    assert!(!Safety::is_tripped(&Denomination::NATIVE));
+}
+
+#[test]
+fn test_paused_methods() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    dispatch(&mut mock, "accounts.Transfer").expect("methods should not be paused");
+
+    let mut ctx = mock.create_ctx();
+    Safety::trip(&mut ctx, Denomination::NATIVE);
+
+    for method in ["accounts.Transfer", "bridge.Lock"] {
+        let result = dispatch(&mut mock, method);
+        assert!(
+            matches!(result, Err(core::Error::Forbidden)),
+            "{method} should be paused"
+        );
+    }
+    dispatch(&mut mock, "consensus.Deposit").expect("unlisted methods should not be paused");
+    dispatch(&mut mock, "safety.Reset").expect("safety methods should never be paused");
+}
+
+#[test]
+fn test_guardian_calls() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+
+    let trip = |mock: &mut mock::Mock, who: &transaction::SignerInfo| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Safety::tx_trip(
+                &mut tx_ctx,
+                types::Trip {
+                    denomination: Denomination::NATIVE,
+                },
+            )
+        })
+    };
+    let reset = |mock: &mut mock::Mock, who: &transaction::SignerInfo| {
+        let mut ctx = mock.create_ctx();
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = vec![who.clone()];
+        ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+            Safety::tx_reset(
+                &mut tx_ctx,
+                types::Reset {
+                    denomination: Denomination::NATIVE,
+                },
+            )
+        })
+    };
+
+    assert!(matches!(trip(&mut mock, &bob), Err(Error::Forbidden)));
+    assert!(matches!(
+        reset(&mut mock, &alice),
+        Err(Error::InvalidArgument)
+    ));
+    trip(&mut mock, &alice).expect("guardians should be able to trip circuit breakers");
+    assert!(Safety::is_tripped(&Denomination::NATIVE));
+    assert!(matches!(
+        trip(&mut mock, &alice),
+        Err(Error::InvalidArgument)
+    ));
+
+    assert!(matches!(reset(&mut mock, &bob), Err(Error::Forbidden)));
+    reset(&mut mock, &alice).expect("guardians should be able to reset circuit breakers");
+    assert!(!Safety::is_tripped(&Denomination::NATIVE));
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    init();
+
+    let mut ctx = mock.create_ctx();
+    Safety::record_outflow(&mut ctx, &native(60)).expect("outflow should be allowed");
+    Safety::record_outflow(&mut ctx, &native(60)).expect("tripping outflow should be allowed");
+    assert!(Safety::is_tripped(&Denomination::NATIVE));
+
+    let genesis = mock::assert_export_round_trip::<Safety>();
+    assert_eq!(genesis.parameters.guardians, vec![keys::alice::address()]);
+    for prefix in [super::state::OUTFLOWS, super::state::TRIPPED] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "outflows and tripped circuit breakers should be exported"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Safety module types.
+use oasis_core_runtime::consensus::beacon::EpochTime;
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::token::Denomination;
+
+/// Outflow limits for a denomination.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Threshold {
+    /// Denomination the limits apply to.
+    pub denomination: Denomination,
+    /// Maximum amount that can flow out of the runtime in a single block. Zero means that the
+    /// amount is not limited.
+    #[cbor(optional)]
+    pub max_per_block: u128,
+    /// Maximum amount that can flow out of the runtime in a single epoch. Zero means that the
+    /// amount is not limited.
+    #[cbor(optional)]
+    pub max_per_epoch: u128,
+}
+
+/// Amounts of a denomination that flowed out of the runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+pub struct Outflow {
+    /// Round the block volume was accounted in.
+    pub round: u64,
+    /// Amount that flowed out during the round.
+    pub block_volume: u128,
+    /// Epoch the epoch volume was accounted in.
+    pub epoch: EpochTime,
+    /// Amount that flowed out during the epoch.
+    pub epoch_volume: u128,
+}
+
+/// Trip the circuit breaker of a denomination call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Trip {
+    pub denomination: Denomination,
+}
+
+/// Reset the circuit breaker of a denomination call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Reset {
+    pub denomination: Denomination,
+}
+
+/// Outflow query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct OutflowQuery {
+    pub denomination: Denomination,
+}
@@ -0,0 +1,479 @@
+//! Scheduler module for delayed and recurring runtime calls.
+//!
+//! Any account can schedule a call to be performed on its behalf at a future round, optionally
+//! repeating it at a fixed interval. Gas for all executions is prepaid at the time of scheduling
+//! and held in escrow. Due jobs are executed at the end of each block, up to a per-block gas
+//! budget, with any jobs that do not fit being postponed to the following blocks. Unused escrow is
+//! refunded once the job completes or is cancelled.
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::{
+    context::{Context, TxContext},
+    export, handler, migration,
+    module::{self, Module as _, Parameters as _},
+    modules::{self, core::API as _},
+    runtime::Runtime,
+    sdk_derive,
+    storage::{self, CurrentStore},
+    subcall::{self, SubcallInfo},
+    types::{
+        address::Address,
+        token::{self, Denomination},
+        transaction::CallerAddress,
+    },
+};
+
+#[cfg(test)]
+mod test;
+pub mod types;
+
+/// Unique module name.
+const MODULE_NAME: &str = "scheduler";
+
+/// Maximum subcall depth of scheduled calls.
+const MAX_CALL_DEPTH: u16 = 8;
+
+/// Errors emitted by the scheduler module.
+#[derive(Error, Debug, oasis_runtime_sdk_macros::Error)]
+pub enum Error {
+    #[error("invalid argument")]
+    #[sdk_error(code = 1)]
+    InvalidArgument,
+
+    #[error("unknown job")]
+    #[sdk_error(code = 2)]
+    UnknownJob,
+
+    #[error("forbidden")]
+    #[sdk_error(code = 3)]
+    Forbidden,
+
+    #[error("insufficient balance")]
+    #[sdk_error(code = 4)]
+    InsufficientBalance,
+
+    #[error("core: {0}")]
+    #[sdk_error(transparent)]
+    Core(#[from] modules::core::Error),
+}
+
+/// Events emitted by the scheduler module.
+#[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+#[cbor(untagged)]
+pub enum Event {
+    #[sdk_event(code = 1)]
+    JobScheduled { id: u64, owner: Address, round: u64 },
+
+    #[sdk_event(code = 2)]
+    JobExecuted {
+        id: u64,
+        success: bool,
+        gas_used: u64,
+    },
+
+    #[sdk_event(code = 3)]
+    JobCancelled { id: u64 },
+}
+
+/// Gas costs.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct GasCosts {
+    pub tx_schedule: u64,
+    pub tx_cancel: u64,
+}
+
+/// Parameters for the scheduler module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Parameters {
+    pub gas_costs: GasCosts,
+
+    /// Maximum amount of gas that a single job execution can use.
+    #[cbor(optional)]
+    pub max_job_gas: u64,
+    /// Maximum amount of gas that can be used by job executions in a single block.
+    #[cbor(optional)]
+    pub max_block_gas: u64,
+    /// Maximum number of executions of a single job.
+    #[cbor(optional)]
+    pub max_executions: u64,
+}
+
+/// Errors emitted during scheduler parameter validation.
+#[derive(Error, Debug)]
+pub enum ParameterValidationError {
+    #[error("maximum job gas exceeds the maximum block gas")]
+    JobGasExceedsBlockGas,
+}
+
+impl module::Parameters for Parameters {
+    type Error = ParameterValidationError;
+
+    fn validate_basic(&self) -> Result<(), Self::Error> {
+        if self.max_job_gas > self.max_block_gas {
+            return Err(ParameterValidationError::JobGasExceedsBlockGas);
+        }
+        Ok(())
+    }
+}
+
+/// Genesis state for the scheduler module.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct Genesis {
+    pub parameters: Parameters,
+
+    /// Module state other than parameters, as exported by the state exporter.
+    #[cbor(optional)]
+    pub state: export::RawState,
+}
+
+/// State schema constants.
+pub mod state {
+    /// Map of job identifiers to jobs.
+    pub const JOBS: &[u8] = &[0x01];
+    /// Queue of jobs ordered by the round at which they are next due.
+    pub const QUEUE: &[u8] = &[0x02];
+    /// Identifier of the next job.
+    pub const NEXT_ID: &[u8] = &[0x03];
+
+    /// Prefixes of module state exported as raw state.
+    pub const RAW: &[&[u8]] = &[JOBS, QUEUE, NEXT_ID];
+}
+
+/// Interface that can be called from other modules.
+pub trait API {
+    /// Fetch the job with the given identifier.
+    fn get_job(id: u64) -> Option<types::Job>;
+}
+
+pub struct Module<Accounts: modules::accounts::API> {
+    _accounts: std::marker::PhantomData<Accounts>,
+}
+
+/// Module's address that holds the escrowed gas payments.
+pub static ADDRESS_ESCROW: Lazy<Address> =
+    Lazy::new(|| Address::from_module(MODULE_NAME, "escrow"));
+
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    fn next_id() -> u64 {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let tstore = storage::TypedStore::new(store);
+            tstore.get(state::NEXT_ID).unwrap_or_default()
+        })
+    }
+
+    fn set_next_id(id: u64) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut tstore = storage::TypedStore::new(store);
+            tstore.insert(state::NEXT_ID, id);
+        });
+    }
+
+    /// Store the job and enqueue it for its next execution.
+    fn set_job(id: u64, job: types::Job) {
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue =
+                storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::QUEUE));
+            queue.insert(QueueEntry::key(job.next_round, id), ());
+
+            let mut jobs = storage::TypedStore::new(storage::PrefixStore::new(store, &state::JOBS));
+            jobs.insert(id.to_be_bytes(), job);
+        });
+    }
+
+    /// Remove the job together with its queue entry.
+    fn remove_job(id: u64, job: &types::Job) {
+        CurrentStore::with(|store| {
+            let mut store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue =
+                storage::TypedStore::new(storage::PrefixStore::new(&mut store, &state::QUEUE));
+            queue.remove(QueueEntry::key(job.next_round, id));
+
+            let mut jobs = storage::TypedStore::new(storage::PrefixStore::new(store, &state::JOBS));
+            jobs.remove(id.to_be_bytes());
+        });
+    }
+
+    /// Refund any escrow left in the job to its owner.
+    fn refund_escrow<C: Context>(ctx: &mut C, job: &types::Job) -> Result<(), Error> {
+        Accounts::transfer(
+            ctx,
+            *ADDRESS_ESCROW,
+            job.owner,
+            &token::BaseUnits::new(job.escrow, Denomination::NATIVE),
+        )
+        .map_err(|_| Error::InsufficientBalance)
+    }
+
+    /// Next job that is due at or before the given round, in execution order.
+    fn next_due_job(round: u64) -> Option<QueueEntry> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let queue = storage::TypedStore::new(storage::PrefixStore::new(store, &state::QUEUE));
+            queue
+                .iter::<QueueEntry, ()>()
+                .next()
+                .map(|(entry, _)| entry)
+                .filter(|entry| entry.round <= round)
+        })
+    }
+
+    /// Remove a queue entry that does not belong to any job.
+    fn remove_queue_entry(entry: &QueueEntry) {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let mut queue =
+                storage::TypedStore::new(storage::PrefixStore::new(store, &state::QUEUE));
+            queue.remove(QueueEntry::key(entry.round, entry.id));
+        });
+    }
+
+    /// Execute the given job, paying for the used gas from its escrow.
+    fn execute_job<C: Context>(ctx: &mut C, id: u64, mut job: types::Job) {
+        // Remove the job while it executes so that its call cannot cancel it and get the escrow
+        // refunded before the fees are paid.
+        Self::remove_job(id, &job);
+
+        let result = subcall::call_detached(
+            ctx,
+            SubcallInfo {
+                caller: CallerAddress::Address(job.owner),
+                method: job.method.clone(),
+                body: job.body.clone(),
+                max_depth: MAX_CALL_DEPTH,
+                max_gas: job.max_gas,
+                read_only: false,
+            },
+        );
+        let success = result.call_result.is_success();
+        let gas_used = result.gas_used.saturating_sub(result.gas_refund);
+        ctx.emit_etags(result.state.events);
+
+        // The escrow covers the maximum gas of all remaining executions, so the fee never exceeds
+        // it.
+        let fee = (gas_used as u128).saturating_mul(job.gas_price);
+        Accounts::transfer(
+            ctx,
+            *ADDRESS_ESCROW,
+            *modules::accounts::ADDRESS_FEE_ACCUMULATOR,
+            &token::BaseUnits::new(fee, Denomination::NATIVE),
+        )
+        .expect("escrow should cover the job fees");
+        job.escrow = job.escrow.saturating_sub(fee);
+        job.remaining_executions = job.remaining_executions.saturating_sub(1);
+
+        ctx.emit_event(Event::JobExecuted {
+            id,
+            success,
+            gas_used,
+        });
+
+        if job.remaining_executions == 0 {
+            Self::refund_escrow(ctx, &job).expect("escrow should cover the refund");
+            return;
+        }
+
+        // Postponed executions of recurring jobs do not accumulate.
+        job.next_round = job
+            .next_round
+            .saturating_add(job.interval)
+            .max(ctx.time().round().saturating_add(1));
+        Self::set_job(id, job);
+    }
+}
+
+impl<Accounts: modules::accounts::API> API for Module<Accounts> {
+    fn get_job(id: u64) -> Option<types::Job> {
+        CurrentStore::with(|store| {
+            let store = storage::PrefixStore::new(store, &MODULE_NAME);
+            let jobs = storage::TypedStore::new(storage::PrefixStore::new(store, &state::JOBS));
+            jobs.get(id.to_be_bytes())
+        })
+    }
+}
+
+#[sdk_derive(Module)]
+impl<Accounts: modules::accounts::API> Module<Accounts> {
+    const NAME: &'static str = MODULE_NAME;
+    const DEPENDENCIES: &'static [&'static str] = &["accounts"];
+    type Error = Error;
+    type Event = Event;
+    type Parameters = Parameters;
+    type Genesis = Genesis;
+
+    #[migration(init)]
+    pub fn init(genesis: Genesis) {
+        genesis
+            .parameters
+            .validate_basic()
+            .expect("invalid genesis parameters");
+
+        // Import exported module state.
+        export::import_raw_state(MODULE_NAME, state::RAW, genesis.state);
+
+        // Set genesis parameters.
+        Self::set_params(genesis.parameters);
+    }
+
+    /// Schedule a call to be performed on behalf of the caller. Gas for all executions is paid
+    /// upfront at the current minimum gas price. Returns the job identifier.
+    #[handler(call = "scheduler.Schedule")]
+    fn tx_schedule<C: TxContext>(ctx: &mut C, body: types::Schedule) -> Result<u64, Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_schedule)?;
+
+        if body.method.is_empty()
+            || body.round <= ctx.time().round()
+            || body.executions == 0
+            || body.executions > params.max_executions
+            || (body.executions > 1 && body.interval == 0)
+            || body.max_gas == 0
+            || body.max_gas > params.max_job_gas
+        {
+            return Err(Error::InvalidArgument);
+        }
+
+        let gas_price = <C::Runtime as Runtime>::Core::min_gas_price(ctx, &Denomination::NATIVE)
+            .unwrap_or_default();
+        let escrow = (body.max_gas as u128)
+            .checked_mul(gas_price)
+            .and_then(|fee| fee.checked_mul(body.executions as u128))
+            .ok_or(Error::InvalidArgument)?;
+
+        let id = Self::next_id();
+        if ctx.is_check_only() {
+            return Ok(id);
+        }
+
+        let owner = ctx.tx_caller_address();
+        Accounts::transfer(
+            ctx,
+            owner,
+            *ADDRESS_ESCROW,
+            &token::BaseUnits::new(escrow, Denomination::NATIVE),
+        )
+        .map_err(|_| Error::InsufficientBalance)?;
+
+        Self::set_next_id(id + 1);
+        Self::set_job(
+            id,
+            types::Job {
+                owner,
+                method: body.method,
+                body: body.body,
+                max_gas: body.max_gas,
+                gas_price,
+                next_round: body.round,
+                interval: body.interval,
+                remaining_executions: body.executions,
+                escrow,
+            },
+        );
+
+        ctx.emit_event(Event::JobScheduled {
+            id,
+            owner,
+            round: body.round,
+        });
+
+        Ok(id)
+    }
+
+    /// Cancel a job, refunding the remaining escrow to its owner. Only the owner can cancel a job.
+    #[handler(call = "scheduler.Cancel")]
+    fn tx_cancel<C: TxContext>(ctx: &mut C, body: types::Cancel) -> Result<(), Error> {
+        let params = Self::params();
+        <C::Runtime as Runtime>::Core::use_tx_gas(ctx, params.gas_costs.tx_cancel)?;
+
+        let job = Self::get_job(body.id).ok_or(Error::UnknownJob)?;
+        if job.owner != ctx.tx_caller_address() {
+            return Err(Error::Forbidden);
+        }
+
+        if ctx.is_check_only() {
+            return Ok(());
+        }
+
+        Self::remove_job(body.id, &job);
+        Self::refund_escrow(ctx, &job)?;
+
+        ctx.emit_event(Event::JobCancelled { id: body.id });
+
+        Ok(())
+    }
+
+    #[handler(query = "scheduler.Job")]
+    fn query_job<C: Context>(_ctx: &mut C, args: types::JobQuery) -> Result<types::Job, Error> {
+        Self::get_job(args.id).ok_or(Error::UnknownJob)
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::TransactionHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::BlockHandler for Module<Accounts> {
+    fn end_block<C: Context>(ctx: &mut C) {
+        // Execute due jobs in order until the block gas budget is exhausted. Jobs that do not fit
+        // remain queued and are executed in the following blocks. Executed jobs are removed from
+        // the queue, so only the jobs that are actually executed are read.
+        let round = ctx.time().round();
+        let mut budget = Self::params().max_block_gas;
+        while let Some(entry) = Self::next_due_job(round) {
+            let job = match Self::get_job(entry.id) {
+                Some(job) if job.next_round == entry.round => job,
+                _ => {
+                    Self::remove_queue_entry(&entry);
+                    continue;
+                }
+            };
+            if job.max_gas > budget {
+                break;
+            }
+            budget -= job.max_gas;
+
+            Self::execute_job(ctx, entry.id, job);
+        }
+    }
+}
+
+impl<Accounts: modules::accounts::API> module::InvariantHandler for Module<Accounts> {}
+
+impl<Accounts: modules::accounts::API> module::StateExporter for Module<Accounts> {
+    fn export_state() -> Genesis {
+        Genesis {
+            parameters: Self::params(),
+            state: export::export_raw_state(MODULE_NAME, state::RAW),
+        }
+    }
+}
+
+/// A struct that exists solely to decode job queue keys.
+struct QueueEntry {
+    round: u64,
+    id: u64,
+}
+
+impl QueueEntry {
+    /// Size of the job queue keys in bytes.
+    const SIZE: usize = 8 + 8;
+
+    /// Job queue key of the given job.
+    fn key(round: u64, id: u64) -> Vec<u8> {
+        [round.to_be_bytes(), id.to_be_bytes()].concat()
+    }
+}
+
+impl TryFrom<&[u8]> for QueueEntry {
+    type Error = std::array::TryFromSliceError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let key: [u8; Self::SIZE] = value.try_into()?;
+        let (round, id) = key.split_at(8);
+        Ok(QueueEntry {
+            round: u64::from_be_bytes(round.try_into()?),
+            id: u64::from_be_bytes(id.try_into()?),
+        })
+    }
+}
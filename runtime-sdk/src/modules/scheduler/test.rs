@@ -0,0 +1,379 @@
+//! Tests for the scheduler module.
+use std::collections::BTreeMap;
+
+use crate::{
+    context::{BatchContext, Mode},
+    module::{self, BlockHandler},
+    modules::{
+        accounts::{self, Module as Accounts},
+        core,
+    },
+    runtime::Runtime,
+    storage::{self, CurrentStore},
+    testing::{keys, mock},
+    types::{
+        token::{BaseUnits, Denomination},
+        transaction,
+    },
+    Version,
+};
+
+use super::{types, Error, Genesis, Parameters, ADDRESS_ESCROW, API as _};
+
+type Scheduler = super::Module<Accounts>;
+
+struct CoreConfig;
+impl core::Config for CoreConfig {}
+type Core = core::Module<CoreConfig>;
+
+struct SchedulerRuntime;
+
+impl Runtime for SchedulerRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+    type Core = Core;
+    type Modules = (Core, Accounts, Scheduler);
+
+    fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+        (
+            core::Genesis {
+                parameters: core::Parameters {
+                    max_batch_gas: u64::MAX,
+                    min_gas_price: {
+                        let mut mgp = BTreeMap::new();
+                        mgp.insert(Denomination::NATIVE, 1);
+                        mgp
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                parameters: accounts::Parameters {
+                    gas_costs: accounts::GasCosts {
+                        tx_transfer: 1_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                balances: {
+                    let mut balances = BTreeMap::new();
+                    balances.insert(keys::alice::address(), {
+                        let mut denominations = BTreeMap::new();
+                        denominations.insert(Denomination::NATIVE, 100_000);
+                        denominations
+                    });
+                    balances
+                },
+                total_supplies: {
+                    let mut total_supplies = BTreeMap::new();
+                    total_supplies.insert(Denomination::NATIVE, 100_000);
+                    total_supplies
+                },
+                ..Default::default()
+            },
+            Genesis {
+                parameters: Parameters {
+                    gas_costs: Default::default(),
+                    max_job_gas: 10_000,
+                    max_block_gas: 15_000,
+                    max_executions: 10,
+                },
+                ..Default::default()
+            },
+        )
+    }
+}
+
+fn schedule(
+    mock: &mut mock::Mock,
+    signer: &transaction::SignerInfo,
+    body: types::Schedule,
+) -> Result<u64, Error> {
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    let mut tx = mock::transaction();
+    tx.call.method = "scheduler.Schedule".to_owned();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Scheduler::tx_schedule(&mut tx_ctx, body)
+    })
+}
+
+fn cancel(mock: &mut mock::Mock, signer: &transaction::SignerInfo, id: u64) -> Result<(), Error> {
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    let mut tx = mock::transaction();
+    tx.call.method = "scheduler.Cancel".to_owned();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    ctx.with_tx(tx.into(), |mut tx_ctx, _call| {
+        Scheduler::tx_cancel(&mut tx_ctx, types::Cancel { id })
+    })
+}
+
+fn end_block(mock: &mut mock::Mock, round: u64) {
+    mock.runtime_header.round = round;
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    Scheduler::end_block(&mut ctx);
+}
+
+fn transfer_to_bob(amount: u128) -> types::Schedule {
+    types::Schedule {
+        method: "accounts.Transfer".to_owned(),
+        body: cbor::to_value(accounts::types::Transfer {
+            to: keys::bob::address(),
+            amount: BaseUnits::new(amount, Denomination::NATIVE),
+        }),
+        round: 5,
+        interval: 0,
+        executions: 1,
+        max_gas: 10_000,
+    }
+}
+
+#[test]
+fn test_parameters_validation() {
+    use module::Parameters as _;
+
+    let params = Parameters {
+        max_job_gas: 10,
+        max_block_gas: 5,
+        ..Default::default()
+    };
+    assert!(
+        params.validate_basic().is_err(),
+        "maximum job gas should not exceed the maximum block gas"
+    );
+}
+
+#[test]
+fn test_schedule_and_execute() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+
+    for (body, check) in [
+        (
+            types::Schedule {
+                round: 0,
+                ..transfer_to_bob(100)
+            },
+            "round should be in the future",
+        ),
+        (
+            types::Schedule {
+                executions: 0,
+                ..transfer_to_bob(100)
+            },
+            "number of executions should be non-zero",
+        ),
+        (
+            types::Schedule {
+                executions: 2,
+                ..transfer_to_bob(100)
+            },
+            "recurring jobs should have a non-zero interval",
+        ),
+        (
+            types::Schedule {
+                max_gas: 10_001,
+                ..transfer_to_bob(100)
+            },
+            "maximum gas should not exceed the maximum job gas",
+        ),
+    ] {
+        let result = schedule(&mut mock, &alice, body);
+        assert!(matches!(result, Err(Error::InvalidArgument)), "{check}");
+    }
+
+    // Gas for all executions is escrowed upfront.
+    let id = schedule(
+        &mut mock,
+        &alice,
+        types::Schedule {
+            interval: 3,
+            executions: 2,
+            ..transfer_to_bob(100)
+        },
+    )
+    .expect("schedule should succeed");
+    assert_eq!(id, 0);
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 20_000);
+    assert_eq!(mock::balance(keys::alice::address()), 80_000);
+
+    // Jobs are not executed before they are due.
+    end_block(&mut mock, 4);
+    assert_eq!(mock::balance(keys::bob::address()), 0);
+
+    end_block(&mut mock, 5);
+    assert_eq!(mock::balance(keys::bob::address()), 100);
+    let job = Scheduler::get_job(id).expect("recurring job should remain scheduled");
+    assert_eq!(job.next_round, 8);
+    assert_eq!(job.remaining_executions, 1);
+    assert!(
+        job.escrow < 20_000,
+        "used gas should be paid from the escrow"
+    );
+    assert_eq!(
+        job.escrow + mock::balance(*accounts::ADDRESS_FEE_ACCUMULATOR),
+        20_000
+    );
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), job.escrow);
+
+    // Unused escrow is refunded after the last execution.
+    end_block(&mut mock, 8);
+    assert_eq!(mock::balance(keys::bob::address()), 200);
+    assert!(
+        Scheduler::get_job(id).is_none(),
+        "completed job should be removed"
+    );
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 0);
+    assert_eq!(
+        mock::balance(keys::alice::address()) + mock::balance(*accounts::ADDRESS_FEE_ACCUMULATOR),
+        99_800
+    );
+}
+
+#[test]
+fn test_block_gas_budget() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let first = schedule(&mut mock, &alice, transfer_to_bob(100)).expect("schedule should succeed");
+    let second =
+        schedule(&mut mock, &alice, transfer_to_bob(200)).expect("schedule should succeed");
+
+    // Only one of the jobs fits into the block gas budget.
+    end_block(&mut mock, 6);
+    assert!(Scheduler::get_job(first).is_none());
+    assert!(Scheduler::get_job(second).is_some());
+    assert_eq!(mock::balance(keys::bob::address()), 100);
+
+    end_block(&mut mock, 7);
+    assert!(Scheduler::get_job(second).is_none());
+    assert_eq!(mock::balance(keys::bob::address()), 300);
+}
+
+#[test]
+fn test_stale_queue_entries() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let id = schedule(&mut mock, &alice, transfer_to_bob(100)).expect("schedule should succeed");
+    CurrentStore::with(|store| {
+        let store = storage::PrefixStore::new(store, &super::MODULE_NAME);
+        let mut queue =
+            storage::TypedStore::new(storage::PrefixStore::new(store, &super::state::QUEUE));
+        queue.insert(super::QueueEntry::key(1, id + 1), ());
+    });
+
+    // Queue entries without a job are dropped and do not prevent other jobs from executing.
+    end_block(&mut mock, 6);
+    assert!(Scheduler::get_job(id).is_none());
+    assert_eq!(mock::balance(keys::bob::address()), 100);
+    assert!(Scheduler::next_due_job(6).is_none());
+}
+
+#[test]
+fn test_cancel() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let bob = keys::bob::signer_info();
+    let id = schedule(&mut mock, &alice, transfer_to_bob(100)).expect("schedule should succeed");
+
+    let result = cancel(&mut mock, &bob, id);
+    assert!(matches!(result, Err(Error::Forbidden)));
+    let result = cancel(&mut mock, &alice, id + 1);
+    assert!(matches!(result, Err(Error::UnknownJob)));
+
+    cancel(&mut mock, &alice, id).expect("owner should be able to cancel");
+    assert!(Scheduler::get_job(id).is_none());
+    assert_eq!(mock::balance(keys::alice::address()), 100_000);
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 0);
+
+    // Cancelled jobs are not executed.
+    end_block(&mut mock, 5);
+    assert_eq!(mock::balance(keys::bob::address()), 0);
+}
+
+#[test]
+fn test_cancel_from_job() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    let other = schedule(
+        &mut mock,
+        &alice,
+        types::Schedule {
+            round: 100,
+            ..transfer_to_bob(100)
+        },
+    )
+    .expect("schedule should succeed");
+    let id = schedule(
+        &mut mock,
+        &alice,
+        types::Schedule {
+            method: "scheduler.Cancel".to_owned(),
+            body: cbor::to_value(types::Cancel { id: other + 1 }),
+            round: 4,
+            interval: 2,
+            executions: 2,
+            ..transfer_to_bob(0)
+        },
+    )
+    .expect("schedule should succeed");
+    assert_eq!(id, other + 1);
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 30_000);
+
+    // Jobs cannot cancel themselves while executing.
+    end_block(&mut mock, 4);
+    let job = Scheduler::get_job(id).expect("job should remain scheduled");
+    assert_eq!(job.remaining_executions, 1);
+    assert_eq!(
+        mock::balance(*ADDRESS_ESCROW),
+        10_000 + job.escrow,
+        "escrow of other jobs should not be used"
+    );
+
+    // The last execution refunds the escrow exactly once.
+    end_block(&mut mock, 6);
+    assert!(Scheduler::get_job(id).is_none());
+    assert_eq!(mock::balance(*ADDRESS_ESCROW), 10_000);
+    assert_eq!(
+        mock::balance(keys::alice::address()) + mock::balance(*accounts::ADDRESS_FEE_ACCUMULATOR),
+        90_000
+    );
+    assert!(Scheduler::get_job(other).is_some());
+}
+
+#[test]
+fn test_export_state() {
+    let mut mock = mock::Mock::default();
+    let mut ctx = mock.create_ctx_for_runtime::<SchedulerRuntime>(Mode::ExecuteTx, false);
+    SchedulerRuntime::migrate(&mut ctx);
+
+    let alice = keys::alice::signer_info();
+    schedule(&mut mock, &alice, transfer_to_bob(100)).expect("schedule should succeed");
+
+    let genesis = mock::assert_export_round_trip::<Scheduler>();
+    assert_eq!(genesis.parameters.max_job_gas, 10_000);
+    for prefix in [
+        super::state::JOBS,
+        super::state::QUEUE,
+        super::state::NEXT_ID,
+    ] {
+        assert!(
+            genesis.state.keys().any(|key| key.starts_with(prefix)),
+            "jobs, the job queue and the next job identifier should be exported"
+        );
+    }
+}
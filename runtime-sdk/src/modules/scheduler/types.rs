@@ -0,0 +1,60 @@
+//! Scheduler module types.
+use oasis_runtime_sdk_macros::Schema;
+
+use crate::types::address::Address;
+
+/// A scheduled job.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+pub struct Job {
+    /// Address of the account that scheduled the job. Calls are performed on its behalf.
+    pub owner: Address,
+    /// Method to call.
+    pub method: String,
+    /// Call body.
+    pub body: cbor::Value,
+    /// Maximum amount of gas that a single execution of the job can use.
+    pub max_gas: u64,
+    /// Price of gas used by the job executions, in the native denomination.
+    pub gas_price: u128,
+    /// Round at which the job is next due.
+    pub next_round: u64,
+    /// Number of rounds between executions of a recurring job.
+    pub interval: u64,
+    /// Remaining number of executions.
+    pub remaining_executions: u64,
+    /// Amount held in escrow for paying for the remaining executions.
+    pub escrow: u128,
+}
+
+/// Schedule job call.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+pub struct Schedule {
+    /// Method to call.
+    pub method: String,
+    /// Call body.
+    pub body: cbor::Value,
+    /// Round at which the job is first due. Must be in the future.
+    pub round: u64,
+    /// Number of rounds between executions of a recurring job. Must be non-zero for jobs with
+    /// more than one execution.
+    #[cbor(optional)]
+    pub interval: u64,
+    /// Number of executions.
+    pub executions: u64,
+    /// Maximum amount of gas that a single execution of the job can use.
+    pub max_gas: u64,
+}
+
+/// Cancel job call.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct Cancel {
+    /// Job identifier.
+    pub id: u64,
+}
+
+/// Job query.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+pub struct JobQuery {
+    /// Job identifier.
+    pub id: u64,
+}
@@ -11,15 +11,17 @@ use oasis_core_runtime::{
 };
 
 use crate::{
+    admission::{self, TxAdmissionPolicy as _},
     config,
     context::Context,
     crypto, dispatcher,
     keymanager::{KeyManagerClient, TrustedPolicySigners},
     module::{
-        BlockHandler, InvariantHandler, MethodHandler, MigrationHandler, ModuleInfoHandler,
-        TransactionHandler,
+        BlockHandler, FeeGrantHandler, InvariantHandler, MethodHandler, MigrationHandler,
+        ModuleInfoHandler, TransactionHandler,
     },
     modules,
+    schedule_control::{self, TransactionPrioritizer as _},
     storage::{self, CurrentStore},
 };
 
@@ -40,6 +42,10 @@ pub trait Runtime {
     type Core: modules::core::API;
 
     /// Supported modules.
+    ///
+    /// Modules are initialized and their hooks invoked in an order derived from the modules'
+    /// declared dependencies (see `Module::DEPENDENCIES`), which otherwise follows the order
+    /// given here.
     type Modules: TransactionHandler
         + MigrationHandler
         + MethodHandler
@@ -47,6 +53,28 @@ pub trait Runtime {
         + InvariantHandler
         + ModuleInfoHandler;
 
+    /// Handler of fee grants used when charging transaction fees. By default fee grants are not
+    /// supported; set it to `modules::feegrant::Module` (which must also be one of the runtime
+    /// modules) to support them.
+    type FeeGrants: FeeGrantHandler = ();
+
+    /// Order transactions that are being considered for inclusion in a batch when the runtime
+    /// controls scheduling. By default higher fee-per-gas transactions are scheduled first (see
+    /// `schedule_control::FeePerGas`).
+    fn prioritize_transactions(txs: &mut Vec<schedule_control::ScheduledTransaction>) {
+        schedule_control::FeePerGas::prioritize(txs)
+    }
+
+    /// Decide whether a transaction should be admitted into the local transaction pool. This is
+    /// only called when checking transactions and before any signatures are verified. By default
+    /// the policy is configured via local configuration (see `admission::LocalConfigPolicy`).
+    fn admit_tx<C: Context>(
+        ctx: &mut C,
+        info: &admission::TxAdmissionInfo<'_>,
+    ) -> Result<(), modules::core::Error> {
+        admission::LocalConfigPolicy::admit(ctx, info)
+    }
+
     /// Return the trusted policy signers for this runtime; if `None`, a key manager connection will
     /// not be established on startup.
     fn trusted_policy_signers() -> Option<TrustedPolicySigners> {
@@ -149,6 +177,9 @@ pub trait Runtime {
     where
         Self: Sized + Send + Sync + 'static,
     {
+        // Make sure module dependencies can be satisfied before doing anything else.
+        Self::Modules::module_order();
+
         // Initializer.
         let init = |state: PreInitState<'_>| -> PostInitState {
             // Fetch host information and configure domain separation context.
@@ -1,8 +1,18 @@
 //! Types related to schedule control.
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+};
+
 use oasis_core_runtime::{
     common::crypto::hash::Hash, transaction::types::TxnBatch, types::Body, Protocol,
 };
 
+use crate::types::{
+    address::Address,
+    transaction::{AddressSpec, Transaction},
+};
+
 /// Unique module name.
 const MODULE_NAME: &str = "schedule_control";
 
@@ -32,3 +42,302 @@ impl ScheduleControlHost for Protocol {
         }
     }
 }
+
+/// A decoded transaction that is being considered for inclusion in a batch.
+#[derive(Clone, Debug)]
+pub struct ScheduledTransaction {
+    /// Raw transaction.
+    pub raw: Vec<u8>,
+    /// Hash of the raw transaction.
+    pub hash: Hash,
+    /// Decoded transaction.
+    pub tx: Transaction,
+    /// Gas price of the transaction, converted to the native denomination.
+    pub gas_price: u128,
+}
+
+impl ScheduledTransaction {
+    /// Address and nonce of the first signer (if any). Transactions signed by session keys are
+    /// not sequenced by nonce and have no sender.
+    fn sender(&self) -> Option<(Address, u64)> {
+        self.tx
+            .auth_info
+            .signer_info
+            .first()
+            .filter(|si| !matches!(si.address_spec, AddressSpec::SessionKey(_)))
+            .map(|si| (si.address_spec.address(), si.nonce))
+    }
+
+    /// Whether all dependencies of the transaction that are part of the given set of candidate
+    /// transactions have already been scheduled.
+    fn is_ready(&self, candidates: &BTreeSet<Hash>, scheduled: &BTreeSet<Hash>) -> bool {
+        self.tx
+            .auth_info
+            .after
+            .iter()
+            .all(|dependency| !candidates.contains(dependency) || scheduled.contains(dependency))
+    }
+}
+
+/// Heuristic that determines the order in which transactions are scheduled.
+pub trait TransactionPrioritizer {
+    /// Reorder the given transactions so that they are scheduled in the resulting order.
+    fn prioritize(txs: &mut Vec<ScheduledTransaction>);
+}
+
+/// Prioritizer that keeps transactions in the order provided by the host.
+pub struct HostOrder;
+
+impl TransactionPrioritizer for HostOrder {
+    fn prioritize(_txs: &mut Vec<ScheduledTransaction>) {}
+}
+
+/// Prioritizer that schedules transactions with a higher gas price first while preserving the
+/// nonce order of transactions from the same sender.
+///
+/// Transactions declaring a conflict with an account (see `AuthInfo::conflicts`) keep their order
+/// relative to the transactions of that account. Transactions with equal gas prices keep the
+/// order provided by the host.
+pub struct FeePerGas;
+
+impl TransactionPrioritizer for FeePerGas {
+    fn prioritize(txs: &mut Vec<ScheduledTransaction>) {
+        // Group transactions by sender, merging the groups of any conflicting accounts.
+        // Transactions without any signers or conflicts form their own groups.
+        let mut groups: Vec<Vec<(usize, ScheduledTransaction)>> = Vec::new();
+        let mut accounts: BTreeMap<Address, usize> = BTreeMap::new();
+        for (index, tx) in txs.drain(..).enumerate() {
+            let keys: Vec<Address> = tx
+                .sender()
+                .map(|(address, _)| address)
+                .into_iter()
+                .chain(tx.tx.auth_info.conflicts.iter().copied())
+                .collect();
+            let mut existing: Vec<usize> = keys
+                .iter()
+                .filter_map(|address| accounts.get(address).copied())
+                .collect();
+            existing.sort_unstable();
+            existing.dedup();
+
+            let group = match existing.split_first() {
+                Some((&group, others)) => {
+                    for &other in others {
+                        let merged = std::mem::take(&mut groups[other]);
+                        groups[group].extend(merged);
+                        for id in accounts.values_mut().filter(|id| **id == other) {
+                            *id = group;
+                        }
+                    }
+                    group
+                }
+                None => {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                }
+            };
+            for address in keys {
+                accounts.insert(address, group);
+            }
+            groups[group].push((index, tx));
+        }
+        let mut groups: Vec<VecDeque<(usize, ScheduledTransaction)>> = groups
+            .into_iter()
+            .filter(|group| !group.is_empty())
+            .map(order_group)
+            .collect();
+
+        // Repeatedly pick the highest priced transaction among the next transactions of each
+        // group, keeping the heads of all groups in a heap. Ties are broken by the original
+        // position.
+        let mut heads: BinaryHeap<(u128, Reverse<usize>, usize)> = groups
+            .iter()
+            .enumerate()
+            .filter_map(|(group, txs)| {
+                txs.front()
+                    .map(|(index, tx)| (tx.gas_price, Reverse(*index), group))
+            })
+            .collect();
+        while let Some((_, _, group)) = heads.pop() {
+            txs.push(groups[group].pop_front().unwrap().1);
+            if let Some((index, tx)) = groups[group].front() {
+                heads.push((tx.gas_price, Reverse(*index), group));
+            }
+        }
+    }
+}
+
+/// Order a group of transactions by their original position while preserving the nonce order of
+/// each sender. Transactions of each sender are reordered among the positions they occupy.
+fn order_group(
+    mut group: Vec<(usize, ScheduledTransaction)>,
+) -> VecDeque<(usize, ScheduledTransaction)> {
+    group.sort_by_key(|(index, _)| *index);
+
+    let mut positions: BTreeMap<Address, Vec<usize>> = BTreeMap::new();
+    for (position, (_, tx)) in group.iter().enumerate() {
+        if let Some((address, _)) = tx.sender() {
+            positions.entry(address).or_default().push(position);
+        }
+    }
+    let mut order: Vec<usize> = (0..group.len()).collect();
+    for positions in positions.values() {
+        let mut sorted = positions.clone();
+        sorted.sort_by_key(|&position| {
+            let (index, tx) = &group[position];
+            (tx.sender().map(|(_, nonce)| nonce), *index)
+        });
+        for (&slot, source) in positions.iter().zip(sorted) {
+            order[slot] = source;
+        }
+    }
+
+    let mut slots: Vec<_> = group.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|source| slots[source].take().unwrap())
+        .collect()
+}
+
+/// Reorder the given transactions so that each transaction is scheduled after the transactions
+/// among them that it depends on (see `AuthInfo::after`), otherwise keeping the given order.
+///
+/// A transaction waiting for its dependencies is scheduled right after the last of them.
+/// Transactions with dependencies that can never be satisfied (e.g. due to cycles) are scheduled
+/// last and will be skipped during execution.
+pub fn order_dependencies(txs: &mut Vec<ScheduledTransaction>) {
+    if txs.iter().all(|tx| tx.tx.auth_info.after.is_empty()) {
+        return;
+    }
+
+    let candidates: BTreeSet<Hash> = txs.iter().map(|tx| tx.hash).collect();
+    let mut scheduled = BTreeSet::new();
+    let mut waiting: Vec<ScheduledTransaction> = Vec::new();
+    for tx in std::mem::take(txs) {
+        if !tx.is_ready(&candidates, &scheduled) {
+            waiting.push(tx);
+            continue;
+        }
+        scheduled.insert(tx.hash);
+        txs.push(tx);
+
+        // Schedule any waiting transactions that have become ready.
+        while let Some(position) = waiting
+            .iter()
+            .position(|tx| tx.is_ready(&candidates, &scheduled))
+        {
+            let tx = waiting.remove(position);
+            scheduled.insert(tx.hash);
+            txs.push(tx);
+        }
+    }
+    txs.extend(waiting);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{keys, mock},
+        types::transaction::SignerInfo,
+    };
+
+    fn scheduled(signer: Option<&SignerInfo>, gas_price: u128) -> ScheduledTransaction {
+        let mut tx = mock::transaction();
+        tx.auth_info.signer_info = signer.into_iter().cloned().collect();
+        ScheduledTransaction {
+            raw: vec![],
+            hash: Hash::empty_hash(),
+            tx,
+            gas_price,
+        }
+    }
+
+    fn order(txs: &[ScheduledTransaction]) -> Vec<(Option<(Address, u64)>, u128)> {
+        txs.iter().map(|tx| (tx.sender(), tx.gas_price)).collect()
+    }
+
+    #[test]
+    fn test_fee_per_gas_prioritization() {
+        let alice = |nonce| SignerInfo::new_sigspec(keys::alice::sigspec(), nonce);
+        let bob = |nonce| SignerInfo::new_sigspec(keys::bob::sigspec(), nonce);
+
+        let mut txs = vec![
+            scheduled(Some(&alice(1)), 100),
+            scheduled(Some(&bob(0)), 50),
+            scheduled(Some(&alice(0)), 10),
+            scheduled(None, 50),
+            scheduled(Some(&bob(1)), 200),
+        ];
+        let mut host_order = txs.clone();
+        HostOrder::prioritize(&mut host_order);
+        assert_eq!(order(&host_order), order(&txs));
+
+        FeePerGas::prioritize(&mut txs);
+        assert_eq!(
+            order(&txs),
+            vec![
+                (Some((keys::bob::address(), 0)), 50),
+                (Some((keys::bob::address(), 1)), 200),
+                (None, 50),
+                (Some((keys::alice::address(), 0)), 10),
+                (Some((keys::alice::address(), 1)), 100),
+            ],
+            "higher priced transactions should go first while preserving nonce order"
+        );
+    }
+
+    #[test]
+    fn test_fee_per_gas_conflicts() {
+        let alice = |nonce| SignerInfo::new_sigspec(keys::alice::sigspec(), nonce);
+        let bob = |nonce| SignerInfo::new_sigspec(keys::bob::sigspec(), nonce);
+
+        let mut conflicting = scheduled(Some(&bob(0)), 200);
+        conflicting.tx.auth_info.conflicts = vec![keys::alice::address()];
+        let mut txs = vec![
+            scheduled(Some(&alice(0)), 10),
+            conflicting,
+            scheduled(Some(&bob(1)), 100),
+        ];
+
+        FeePerGas::prioritize(&mut txs);
+        assert_eq!(
+            order(&txs),
+            vec![
+                (Some((keys::alice::address(), 0)), 10),
+                (Some((keys::bob::address(), 0)), 200),
+                (Some((keys::bob::address(), 1)), 100),
+            ],
+            "conflicting transactions should keep their relative order"
+        );
+    }
+
+    #[test]
+    fn test_order_dependencies() {
+        let hash = |name: &str| Hash::digest_bytes(name.as_bytes());
+        let tx = |name: &str, after: &[&str]| {
+            let mut tx = scheduled(None, 0);
+            tx.hash = hash(name);
+            tx.tx.auth_info.after = after.iter().map(|name| hash(name)).collect();
+            tx
+        };
+
+        let mut txs = vec![
+            tx("a", &["c"]),
+            tx("b", &[]),
+            tx("c", &["b", "missing"]),
+            tx("d", &[]),
+            tx("e", &["f"]),
+            tx("f", &["e"]),
+        ];
+        order_dependencies(&mut txs);
+        assert_eq!(
+            txs.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+            ["b", "c", "a", "d", "e", "f"]
+                .iter()
+                .map(|name| hash(name))
+                .collect::<Vec<_>>(),
+            "transactions should be scheduled after their dependencies"
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Schemas describing the CBOR encoding of types exposed by runtime modules.
+//!
+//! Schemas are reported in the runtime metadata so that clients can generate typed bindings for
+//! module methods and events. Each schema is registered under a unique name and refers to other
+//! types by their names, so recursive types can be described as well.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+};
+
+/// Kind of a type schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TypeKind {
+    /// The unit type, encoded as CBOR null.
+    #[cbor(rename = "unit")]
+    #[cfg_attr(feature = "serde", serde(rename = "unit"))]
+    Unit,
+    /// A boolean.
+    #[cbor(rename = "bool")]
+    #[cfg_attr(feature = "serde", serde(rename = "bool"))]
+    Bool,
+    /// An unsigned integer of `size` bits. Integers wider than 64 bits are encoded as big-endian
+    /// byte strings without leading zeros.
+    #[cbor(rename = "uint")]
+    #[cfg_attr(feature = "serde", serde(rename = "uint"))]
+    Uint,
+    /// A signed integer of `size` bits.
+    #[cbor(rename = "int")]
+    #[cfg_attr(feature = "serde", serde(rename = "int"))]
+    Int,
+    /// A text string.
+    #[cbor(rename = "string")]
+    #[cfg_attr(feature = "serde", serde(rename = "string"))]
+    String,
+    /// A byte string of exactly `size` bytes, or of any length if `size` is zero.
+    #[cbor(rename = "bytes")]
+    #[cfg_attr(feature = "serde", serde(rename = "bytes"))]
+    Bytes,
+    /// An array of elements of type `items[0]`, of exactly `size` elements if `size` is non-zero.
+    #[cbor(rename = "array")]
+    #[cfg_attr(feature = "serde", serde(rename = "array"))]
+    Array,
+    /// An optional value of type `items[0]`, encoded as CBOR null when missing.
+    #[cbor(rename = "optional")]
+    #[cfg_attr(feature = "serde", serde(rename = "optional"))]
+    Optional,
+    /// A map with keys of type `items[0]` and values of type `items[1]`.
+    #[cbor(rename = "map")]
+    #[cfg_attr(feature = "serde", serde(rename = "map"))]
+    Map,
+    /// An array whose elements are of types `items`, in order.
+    #[cbor(rename = "tuple")]
+    #[cfg_attr(feature = "serde", serde(rename = "tuple"))]
+    Tuple,
+    /// A map of named `fields`.
+    #[cbor(rename = "struct")]
+    #[cfg_attr(feature = "serde", serde(rename = "struct"))]
+    Struct,
+    /// One of the `variants`.
+    #[cbor(rename = "enum")]
+    #[cfg_attr(feature = "serde", serde(rename = "enum"))]
+    Enum,
+    /// A type whose encoding is not described.
+    #[default]
+    #[cbor(rename = "opaque")]
+    #[cfg_attr(feature = "serde", serde(rename = "opaque"))]
+    Opaque,
+}
+
+/// Schema of a type.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeSchema {
+    pub kind: TypeKind,
+    /// Size in bits of integers, or the fixed length of byte strings and arrays.
+    #[cbor(optional)]
+    pub size: u64,
+    /// Names of the element types of arrays, optionals, maps and tuples.
+    #[cbor(optional)]
+    pub items: Vec<String>,
+    /// Fields of structs in declaration order.
+    #[cbor(optional)]
+    pub fields: Vec<FieldSchema>,
+    /// Variants of enums in declaration order.
+    #[cbor(optional)]
+    pub variants: Vec<VariantSchema>,
+    /// Whether enum variants are encoded without the variant name.
+    #[cbor(optional)]
+    pub untagged: bool,
+}
+
+impl TypeSchema {
+    /// Schema of a type of the given kind without any further details.
+    pub fn new(kind: TypeKind) -> Self {
+        Self {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    /// Schema of a type of the given kind with the given size.
+    pub fn sized(kind: TypeKind, size: u64) -> Self {
+        Self {
+            kind,
+            size,
+            ..Default::default()
+        }
+    }
+
+    /// Schema of a type of the given kind with the given element types.
+    pub fn with_items(kind: TypeKind, items: Vec<String>) -> Self {
+        Self {
+            kind,
+            items,
+            ..Default::default()
+        }
+    }
+}
+
+/// Schema of a struct field.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldSchema {
+    /// Key under which the field is encoded.
+    pub name: String,
+    /// Name of the field's type.
+    pub ty: String,
+    /// Whether the field is omitted from the encoding when empty.
+    #[cbor(optional)]
+    pub optional: bool,
+}
+
+/// Schema of an enum variant.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VariantSchema {
+    /// Key under which the variant is encoded.
+    pub name: String,
+    /// Discriminant under which a unit variant is encoded instead of its name.
+    #[cbor(optional)]
+    pub discriminant: Option<u64>,
+    /// Name of the variant's payload type, missing for unit variants.
+    #[cbor(optional)]
+    pub ty: Option<String>,
+}
+
+/// A type whose CBOR encoding can be described by a schema.
+///
+/// This trait can be derived for types deriving `cbor::Encode`, in which case the `cbor`
+/// attributes are taken into account:
+/// ```
+/// # #[cfg(feature = "oasis-runtime-sdk-macros")]
+/// # mod example {
+/// # use oasis_runtime_sdk::types::address::Address;
+/// # use oasis_runtime_sdk_macros::Schema;
+/// #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+/// struct Transfer {
+///     to: Address,
+///     #[cbor(optional)]
+///     memo: Option<String>,
+/// }
+/// # }
+/// ```
+pub trait Schema {
+    /// Name under which the type's schema is registered.
+    fn schema_name() -> String;
+
+    /// Describes the type, registering the schemas of any types it refers to.
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema;
+
+    #[doc(hidden)]
+    fn vec_schema(types: &mut SchemaRegistry) -> TypeSchema
+    where
+        Self: Sized,
+    {
+        TypeSchema::with_items(TypeKind::Array, vec![types.register::<Self>()])
+    }
+
+    #[doc(hidden)]
+    fn array_schema(len: usize, types: &mut SchemaRegistry) -> TypeSchema
+    where
+        Self: Sized,
+    {
+        TypeSchema {
+            size: len as u64,
+            ..Self::vec_schema(types)
+        }
+    }
+}
+
+/// A set of named type schemas.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    types: BTreeMap<String, TypeSchema>,
+}
+
+impl SchemaRegistry {
+    /// Registers the schema of the given type together with all types it refers to and returns
+    /// the name under which it was registered.
+    pub fn register<T: Schema + ?Sized>(&mut self) -> String {
+        let name = T::schema_name();
+        if !self.types.contains_key(&name) {
+            // Reserve the name first so that recursive types terminate.
+            self.types.insert(name.clone(), TypeSchema::default());
+            let schema = T::schema(self);
+            self.types.insert(name.clone(), schema);
+        }
+        name
+    }
+
+    /// Registers the given schema under the given name, unless the name is already taken, and
+    /// returns the name.
+    pub fn define(&mut self, name: String, schema: TypeSchema) -> String {
+        self.types.entry(name.clone()).or_insert(schema);
+        name
+    }
+
+    /// Registers an opaque type under the given name and returns the name.
+    pub fn opaque(&mut self, name: &str) -> String {
+        self.types.entry(name.to_string()).or_default();
+        name.to_string()
+    }
+
+    /// Whether no schemas have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Returns the registered schemas by name.
+    pub fn into_types(self) -> BTreeMap<String, TypeSchema> {
+        self.types
+    }
+}
+
+macro_rules! impl_integer_schema {
+    ($kind:ident, $($ty:ty),*) => {
+        $(
+            impl Schema for $ty {
+                fn schema_name() -> String {
+                    stringify!($ty).to_string()
+                }
+
+                fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+                    TypeSchema::sized(TypeKind::$kind, <$ty>::BITS.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_schema!(Uint, u16, u32, u64, u128);
+impl_integer_schema!(Int, i8, i16, i32, i64, i128);
+
+impl Schema for u8 {
+    fn schema_name() -> String {
+        "u8".to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::sized(TypeKind::Uint, 8)
+    }
+
+    fn vec_schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::new(TypeKind::Bytes)
+    }
+
+    fn array_schema(len: usize, _types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::sized(TypeKind::Bytes, len as u64)
+    }
+}
+
+impl Schema for bool {
+    fn schema_name() -> String {
+        "bool".to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::new(TypeKind::Bool)
+    }
+}
+
+impl Schema for String {
+    fn schema_name() -> String {
+        "String".to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::new(TypeKind::String)
+    }
+}
+
+impl Schema for () {
+    fn schema_name() -> String {
+        "()".to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::new(TypeKind::Unit)
+    }
+}
+
+impl Schema for cbor::Value {
+    fn schema_name() -> String {
+        "cbor::Value".to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::new(TypeKind::Opaque)
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema_name() -> String {
+        format!("Vec<{}>", T::schema_name())
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        T::vec_schema(types)
+    }
+}
+
+impl<T: Schema, const N: usize> Schema for [T; N] {
+    fn schema_name() -> String {
+        format!("[{}; {}]", T::schema_name(), N)
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        T::array_schema(N, types)
+    }
+}
+
+impl<T: Schema> Schema for BTreeSet<T> {
+    fn schema_name() -> String {
+        format!("BTreeSet<{}>", T::schema_name())
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::with_items(TypeKind::Array, vec![types.register::<T>()])
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema_name() -> String {
+        format!("Option<{}>", T::schema_name())
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::with_items(TypeKind::Optional, vec![types.register::<T>()])
+    }
+}
+
+impl<T: Schema> Schema for Box<T> {
+    fn schema_name() -> String {
+        T::schema_name()
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        T::schema(types)
+    }
+}
+
+impl<K: Schema, V: Schema> Schema for BTreeMap<K, V> {
+    fn schema_name() -> String {
+        format!("BTreeMap<{}, {}>", K::schema_name(), V::schema_name())
+    }
+
+    fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::with_items(
+            TypeKind::Map,
+            vec![types.register::<K>(), types.register::<V>()],
+        )
+    }
+}
+
+macro_rules! impl_tuple_schema {
+    ($($ty:ident),+) => {
+        impl<$($ty: Schema),+> Schema for ($($ty,)+) {
+            fn schema_name() -> String {
+                format!("({})", [$($ty::schema_name()),+].join(", "))
+            }
+
+            fn schema(types: &mut SchemaRegistry) -> TypeSchema {
+                TypeSchema::with_items(TypeKind::Tuple, vec![$(types.register::<$ty>()),+])
+            }
+        }
+    };
+}
+
+impl_tuple_schema!(A, B);
+impl_tuple_schema!(A, B, C);
+impl_tuple_schema!(A, B, C, D);
+
+/// Support for registering the schemas of types that might not implement [`Schema`], used by
+/// the derived method and event metadata.
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    /// Probe for a type's schema. Types implementing [`Schema`] are resolved through
+    /// [`ViaSchema`] while all other types fall back to [`ViaFallback`] and are registered as
+    /// opaque, e.g. `(&SchemaProbe::<T>::new()).register(types)`.
+    pub struct SchemaProbe<T: ?Sized>(PhantomData<T>);
+
+    impl<T: ?Sized> SchemaProbe<T> {
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Self(PhantomData)
+        }
+    }
+
+    pub trait ViaSchema {
+        fn register(&self, types: &mut SchemaRegistry) -> String;
+    }
+
+    impl<T: Schema + ?Sized> ViaSchema for SchemaProbe<T> {
+        fn register(&self, types: &mut SchemaRegistry) -> String {
+            types.register::<T>()
+        }
+    }
+
+    pub trait ViaFallback {
+        fn register(&self, types: &mut SchemaRegistry) -> String;
+    }
+
+    impl<T: ?Sized> ViaFallback for &SchemaProbe<T> {
+        fn register(&self, types: &mut SchemaRegistry) -> String {
+            types.opaque(std::any::type_name::<T>())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        __private::{SchemaProbe, ViaFallback as _, ViaSchema as _},
+        *,
+    };
+
+    #[test]
+    fn test_builtin_schemas() {
+        let mut types = SchemaRegistry::default();
+        assert_eq!(types.register::<Vec<u8>>(), "Vec<u8>");
+        assert_eq!(types.register::<[u8; 20]>(), "[u8; 20]");
+        assert_eq!(
+            types.register::<BTreeMap<String, Option<Vec<u64>>>>(),
+            "BTreeMap<String, Option<Vec<u64>>>"
+        );
+        assert_eq!(types.register::<(u128, bool)>(), "(u128, bool)");
+
+        let types = types.into_types();
+        assert_eq!(types["Vec<u8>"], TypeSchema::new(TypeKind::Bytes));
+        assert_eq!(types["[u8; 20]"], TypeSchema::sized(TypeKind::Bytes, 20));
+        assert_eq!(
+            types["Vec<u64>"],
+            TypeSchema::with_items(TypeKind::Array, vec!["u64".to_string()])
+        );
+        assert_eq!(types["u64"], TypeSchema::sized(TypeKind::Uint, 64));
+        assert_eq!(types["u128"], TypeSchema::sized(TypeKind::Uint, 128));
+        assert_eq!(
+            types["BTreeMap<String, Option<Vec<u64>>>"],
+            TypeSchema::with_items(
+                TypeKind::Map,
+                vec!["String".to_string(), "Option<Vec<u64>>".to_string()]
+            )
+        );
+        assert!(
+            !types.contains_key("u8"),
+            "byte strings should not register u8"
+        );
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)] // Mirrors the code generated by the derive macros.
+    fn test_probe() {
+        struct NoSchema;
+
+        let mut types = SchemaRegistry::default();
+        assert_eq!((&SchemaProbe::<u32>::new()).register(&mut types), "u32");
+        let name = (&SchemaProbe::<NoSchema>::new()).register(&mut types);
+        assert!(name.ends_with("NoSchema"));
+        assert_eq!(types.into_types()[&name], TypeSchema::new(TypeKind::Opaque));
+    }
+}
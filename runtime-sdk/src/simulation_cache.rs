@@ -0,0 +1,365 @@
+//! Node-local cache of transaction simulation results.
+//!
+//! Simulations (e.g. in `core.EstimateGas` and `evm.SimulateCall`) are only ever performed while
+//! serving queries, so caching their results has no effect on consensus and different nodes may
+//! use different cache configurations. Results are keyed by the block (round and state root) that
+//! the simulation was performed against and a hash of the simulated call which only covers the
+//! parts of the payload that determine its outcome (caller, call and gas limit). This way the same
+//! call simulated via different queries (e.g. a gateway estimating gas for a call and then
+//! simulating it) maps to the same cache entry.
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+use oasis_core_runtime::common::crypto::hash::Hash;
+
+use crate::{
+    context::Context,
+    modules,
+    types::{address::Address, transaction},
+};
+
+/// Unique module name.
+const MODULE_NAME: &str = "simulation_cache";
+
+/// Default maximum number of cached simulation results.
+pub const DEFAULT_MAX_ENTRIES: usize = 1_024;
+
+/// Default amount of time for which simulation results are cached.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(10);
+
+/// Simulation cache configuration that can be provided by the node operator.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct LocalConfig {
+    /// Whether simulation results should be cached. Caching is disabled by default as it relies
+    /// on the runtime header reflecting the state that the simulation is performed against which
+    /// is not the case for mock contexts used in tests.
+    #[cbor(optional)]
+    pub enabled: bool,
+
+    /// Maximum number of cached simulation results. When more results are cached, the least
+    /// recently used ones are evicted. The special value of 0 means that the default of
+    /// `DEFAULT_MAX_ENTRIES` is used.
+    #[cbor(optional)]
+    pub max_entries: u64,
+
+    /// Amount of time (in milliseconds) for which simulation results are cached. The special value
+    /// of 0 means that the default of `DEFAULT_TTL` is used.
+    #[cbor(optional)]
+    pub ttl_ms: u64,
+}
+
+impl LocalConfig {
+    fn max_entries(&self) -> NonZeroUsize {
+        usize::try_from(self.max_entries)
+            .ok()
+            .and_then(NonZeroUsize::new)
+            .unwrap_or_else(|| NonZeroUsize::new(DEFAULT_MAX_ENTRIES).unwrap())
+    }
+
+    fn ttl(&self) -> Duration {
+        match self.ttl_ms {
+            0 => DEFAULT_TTL,
+            ttl_ms => Duration::from_millis(ttl_ms),
+        }
+    }
+}
+
+/// Cache key identifying a simulated payload and the state it was simulated against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// Round of the block that the simulation was performed against.
+    pub round: u64,
+    /// State root of the block that the simulation was performed against.
+    pub state_root: Hash,
+    /// Hash of the simulated call.
+    pub call_hash: Hash,
+}
+
+/// Parts of a simulated payload that determine the outcome of the simulation.
+#[derive(cbor::Encode)]
+struct SimulatedCall {
+    caller: Address,
+    format: transaction::CallFormat,
+    method: String,
+    body: cbor::Value,
+    gas_limit: u64,
+}
+
+impl Key {
+    /// Create a key for simulating the given call made by the given caller with the given gas
+    /// limit against the state of the given context.
+    ///
+    /// Fields that do not affect the outcome of the call (e.g. nonces, fee amounts or
+    /// authentication proofs) are not part of the key.
+    pub fn for_call<C: Context>(
+        ctx: &C,
+        caller: Address,
+        call: &transaction::Call,
+        gas_limit: u64,
+    ) -> Self {
+        let header = ctx.runtime_header();
+        let call = SimulatedCall {
+            caller,
+            format: call.format,
+            method: call.method.clone(),
+            body: call.body.clone(),
+            gas_limit,
+        };
+        Self {
+            round: header.round,
+            state_root: header.state_root,
+            call_hash: Hash::digest_bytes(&cbor::to_vec(call)),
+        }
+    }
+}
+
+/// Result of a simulation.
+#[derive(Clone, Debug)]
+pub struct SimulationResult {
+    /// Result of the simulated call.
+    pub result: Result<cbor::Value, modules::core::TxSimulationFailure>,
+    /// Amount of gas used by the simulated call.
+    pub gas_used: u64,
+    /// Size of the simulated transaction accounted for in `gas_used`. In case the call was not
+    /// simulated as part of a full transaction (e.g. in `evm.SimulateCall`), this is `None` and
+    /// `gas_used` is not meaningful.
+    pub tx_size: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    result: SimulationResult,
+    expires_at: Instant,
+}
+
+/// A size-bounded cache of simulation results where each result expires after a fixed time.
+pub struct SimulationCache {
+    entries: lru::LruCache<Key, Entry>,
+    ttl: Duration,
+}
+
+impl SimulationCache {
+    /// Create a new cache holding at most the given number of results for the given time.
+    pub fn new(max_entries: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            entries: lru::LruCache::new(max_entries),
+            ttl,
+        }
+    }
+
+    /// Look up a result that has not yet expired at the given time.
+    pub fn get(&mut self, key: &Key, now: Instant) -> Option<SimulationResult> {
+        match self.entries.get(key) {
+            Some(entry) if entry.expires_at > now => Some(entry.result.clone()),
+            Some(_) => {
+                self.entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cache a result obtained at the given time.
+    pub fn insert(&mut self, key: Key, result: SimulationResult, now: Instant) {
+        self.entries.put(
+            key,
+            Entry {
+                result,
+                expires_at: now + self.ttl,
+            },
+        );
+    }
+
+    /// Number of cached results (including expired ones that have not been evicted yet).
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+static CACHE: Lazy<Mutex<SimulationCache>> = Lazy::new(|| {
+    Mutex::new(SimulationCache::new(
+        NonZeroUsize::new(DEFAULT_MAX_ENTRIES).unwrap(),
+        DEFAULT_TTL,
+    ))
+});
+
+/// Return the cached result of simulating the call identified by the given key or, in case there
+/// is none or it is not accepted by `reuse`, perform the simulation and cache its result in the
+/// node-wide cache.
+///
+/// Simulations that fail to produce a result (`f` returns an error) are not cached.
+pub fn get_or_simulate<C, R, F, E>(
+    ctx: &mut C,
+    key: Key,
+    reuse: R,
+    f: F,
+) -> Result<SimulationResult, E>
+where
+    C: Context,
+    R: FnOnce(&SimulationResult) -> bool,
+    F: FnOnce(&mut C) -> Result<SimulationResult, E>,
+{
+    let cfg: LocalConfig = ctx.local_config(MODULE_NAME).unwrap_or_default();
+    if !cfg.enabled {
+        return f(ctx);
+    }
+
+    {
+        let mut cache = CACHE.lock().unwrap();
+        let max_entries = cfg.max_entries();
+        if cache.entries.cap() != max_entries {
+            cache.entries.resize(max_entries);
+        }
+        cache.ttl = cfg.ttl();
+
+        match cache.get(&key, Instant::now()) {
+            Some(result) if reuse(&result) => return Ok(result),
+            _ => {}
+        }
+    }
+
+    // Do not hold the lock while simulating as simulations may be expensive.
+    let result = f(ctx)?;
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(key, result.clone(), Instant::now());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, collections::BTreeMap};
+
+    use super::*;
+    use crate::testing::{configmap, keys, mock};
+
+    #[test]
+    fn test_simulation_cache() {
+        let key = |round, tx: &[u8]| Key {
+            round,
+            state_root: Hash::empty_hash(),
+            call_hash: Hash::digest_bytes(tx),
+        };
+        let result = |gas_used| SimulationResult {
+            result: Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+            gas_used,
+            tx_size: Some(100),
+        };
+
+        let now = Instant::now();
+        let mut cache = SimulationCache::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(1));
+        assert!(cache.get(&key(1, b"a"), now).is_none());
+
+        cache.insert(key(1, b"a"), result(10), now);
+        assert_eq!(cache.get(&key(1, b"a"), now).unwrap().gas_used, 10);
+        assert!(
+            cache.get(&key(2, b"a"), now).is_none(),
+            "results should not be shared between blocks"
+        );
+        assert!(cache.get(&key(1, b"b"), now).is_none());
+
+        // Results should expire.
+        let later = now + Duration::from_secs(1);
+        assert!(cache.get(&key(1, b"a"), later).is_none());
+        assert!(cache.is_empty(), "expired results should be evicted");
+
+        // Cache should be size-bounded.
+        cache.insert(key(1, b"a"), result(10), now);
+        cache.insert(key(1, b"b"), result(20), now);
+        cache.insert(key(1, b"c"), result(30), now);
+        assert_eq!(cache.len(), 2);
+        assert!(
+            cache.get(&key(1, b"a"), now).is_none(),
+            "least recently used result should be evicted"
+        );
+        assert_eq!(cache.get(&key(1, b"c"), now).unwrap().gas_used, 30);
+    }
+
+    #[test]
+    fn test_get_or_simulate() {
+        let key = Key {
+            round: 1,
+            state_root: Hash::empty_hash(),
+            call_hash: Hash::digest_bytes(b"simulation_cache::test_get_or_simulate"),
+        };
+        let simulations = Cell::new(0);
+        let simulate = || -> Result<SimulationResult, ()> {
+            simulations.set(simulations.get() + 1);
+            Ok(SimulationResult {
+                result: Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+                gas_used: 10,
+                tx_size: Some(100),
+            })
+        };
+
+        // Caching should be disabled by default.
+        let mut mock = mock::Mock::default();
+        let mut ctx = mock.create_check_ctx();
+        get_or_simulate(&mut ctx, key, |_| true, |_| simulate()).unwrap();
+        get_or_simulate(&mut ctx, key, |_| true, |_| simulate()).unwrap();
+        assert_eq!(simulations.get(), 2);
+
+        let mut mock = mock::Mock::with_local_config(configmap! {
+            "simulation_cache" => configmap! {
+                "enabled" => true,
+            },
+        });
+        let mut ctx = mock.create_check_ctx();
+        get_or_simulate(&mut ctx, key, |_| true, |_| simulate()).unwrap();
+        let result = get_or_simulate(&mut ctx, key, |_| true, |_| simulate()).unwrap();
+        assert_eq!(result.gas_used, 10);
+        assert_eq!(simulations.get(), 3, "cached result should be reused");
+
+        get_or_simulate(&mut ctx, key, |r| r.tx_size == Some(200), |_| simulate()).unwrap();
+        assert_eq!(simulations.get(), 4, "rejected result should not be reused");
+    }
+
+    #[test]
+    fn test_key_for_call() {
+        let mut mock = mock::Mock::default();
+        let ctx = mock.create_ctx();
+
+        let caller = keys::alice::address();
+        let call = transaction::Call {
+            format: transaction::CallFormat::Plain,
+            method: "test.Method".to_owned(),
+            body: cbor::to_value(42u64),
+            ..Default::default()
+        };
+        let key = Key::for_call(&ctx, caller, &call, 1_000);
+
+        let read_only = transaction::Call {
+            read_only: true,
+            ..call.clone()
+        };
+        assert_eq!(
+            Key::for_call(&ctx, caller, &read_only, 1_000),
+            key,
+            "fields not affecting the outcome should be ignored"
+        );
+
+        assert_ne!(Key::for_call(&ctx, keys::bob::address(), &call, 1_000), key);
+        assert_ne!(Key::for_call(&ctx, caller, &call, 2_000), key);
+        let other_body = transaction::Call {
+            body: cbor::to_value(43u64),
+            ..call.clone()
+        };
+        assert_ne!(Key::for_call(&ctx, caller, &other_body, 1_000), key);
+        let other_method = transaction::Call {
+            method: "test.Other".to_owned(),
+            ..call
+        };
+        assert_ne!(Key::for_call(&ctx, caller, &other_method, 1_000), key);
+    }
+}
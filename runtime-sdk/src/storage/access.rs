@@ -0,0 +1,198 @@
+//! Storage access tracking.
+use std::{cell::RefCell, collections::BTreeSet};
+
+use oasis_core_runtime::storage::mkvs;
+
+use super::{Prefix, Store};
+
+/// Number of storage accesses recorded while tracking was enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessStats {
+    /// Number of accesses to keys that have not been accessed before.
+    pub cold: u64,
+    /// Number of accesses to keys that have already been accessed before.
+    pub warm: u64,
+}
+
+impl std::ops::AddAssign for AccessStats {
+    fn add_assign(&mut self, other: Self) {
+        self.cold = self.cold.saturating_add(other.cold);
+        self.warm = self.warm.saturating_add(other.warm);
+    }
+}
+
+/// Accesses recorded in a single tracking scope.
+#[derive(Default)]
+struct Scope {
+    /// All accesses made in the scope, including those of nested scopes.
+    total: AccessStats,
+    /// Accesses made in the scope that have not yet been taken.
+    pending: AccessStats,
+}
+
+/// Tracker of storage keys accessed since tracking has started.
+///
+/// Accesses are recorded in a stack of nested scopes. Keys accessed in any scope remain warm for
+/// all scopes until tracking stops.
+#[derive(Default)]
+pub(crate) struct AccessTracker {
+    keys: BTreeSet<Vec<u8>>,
+    scopes: Vec<Scope>,
+}
+
+impl AccessTracker {
+    /// Record an access to the given key in the innermost scope.
+    fn access(&mut self, key: &[u8]) {
+        let stats = if self.keys.contains(key) {
+            AccessStats { cold: 0, warm: 1 }
+        } else {
+            self.keys.insert(key.to_vec());
+            AccessStats { cold: 1, warm: 0 }
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.total += stats;
+            scope.pending += stats;
+        }
+    }
+
+    /// Open a new nested scope.
+    pub(crate) fn push_scope(&mut self) {
+        self.scopes.push(Default::default());
+    }
+
+    /// Close the innermost scope and return all accesses made in it. The accesses are accumulated
+    /// into the parent scope, including any that have not been taken yet.
+    pub(crate) fn pop_scope(&mut self) -> AccessStats {
+        let scope = self.scopes.pop().unwrap_or_default();
+        if let Some(parent) = self.scopes.last_mut() {
+            parent.total += scope.total;
+            parent.pending += scope.pending;
+        }
+        scope.total
+    }
+
+    /// Whether there are no open scopes.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+
+    /// Statistics of all accesses made in the innermost scope.
+    pub(crate) fn stats(&self) -> AccessStats {
+        self.scopes
+            .last()
+            .map(|scope| scope.total)
+            .unwrap_or_default()
+    }
+
+    /// Take the accesses made in the innermost scope since they were last taken.
+    pub(crate) fn take_pending(&mut self) -> AccessStats {
+        self.scopes
+            .last_mut()
+            .map(|scope| std::mem::take(&mut scope.pending))
+            .unwrap_or_default()
+    }
+}
+
+/// A store that records all accessed keys in the given tracker.
+pub(crate) struct AccessTrackingStore<'a> {
+    inner: &'a mut dyn Store,
+    tracker: &'a RefCell<AccessTracker>,
+}
+
+impl<'a> AccessTrackingStore<'a> {
+    /// Create a new access tracking store.
+    pub(crate) fn new(inner: &'a mut dyn Store, tracker: &'a RefCell<AccessTracker>) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<'a> Store for AccessTrackingStore<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.tracker.borrow_mut().access(key);
+        self.inner.get(key)
+    }
+
+    fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.tracker.borrow_mut().access(key);
+        self.inner.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.tracker.borrow_mut().access(key);
+        self.inner.remove(key)
+    }
+
+    fn iter(&self) -> Box<dyn mkvs::Iterator + '_> {
+        Box::new(AccessTrackingIterator {
+            inner: self.inner.iter(),
+            tracker: self.tracker,
+        })
+    }
+
+    fn prefetch_prefixes(&mut self, prefixes: Vec<Prefix>, limit: u16) {
+        self.inner.prefetch_prefixes(prefixes, limit)
+    }
+}
+
+/// An iterator that records each key it is positioned at.
+struct AccessTrackingIterator<'a> {
+    inner: Box<dyn mkvs::Iterator + 'a>,
+    tracker: &'a RefCell<AccessTracker>,
+}
+
+impl<'a> AccessTrackingIterator<'a> {
+    fn record(&self) {
+        if let Some(key) = self.inner.get_key() {
+            self.tracker.borrow_mut().access(key);
+        }
+    }
+}
+
+impl<'a> Iterator for AccessTrackingIterator<'a> {
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = Iterator::next(&mut *self.inner)?;
+        // The returned key has been recorded when the iterator was positioned at it, so only record
+        // the key the iterator has advanced to.
+        self.record();
+        Some(item)
+    }
+}
+
+impl<'a> mkvs::Iterator for AccessTrackingIterator<'a> {
+    fn set_prefetch(&mut self, prefetch: usize) {
+        self.inner.set_prefetch(prefetch)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    fn error(&self) -> &Option<anyhow::Error> {
+        self.inner.error()
+    }
+
+    fn rewind(&mut self) {
+        self.inner.rewind();
+        self.record();
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.inner.seek(key);
+        self.record();
+    }
+
+    fn get_key(&self) -> &Option<mkvs::Key> {
+        self.inner.get_key()
+    }
+
+    fn get_value(&self) -> &Option<Vec<u8>> {
+        self.inner.get_value()
+    }
+
+    fn next(&mut self) {
+        mkvs::Iterator::next(&mut *self.inner);
+        self.record();
+    }
+}
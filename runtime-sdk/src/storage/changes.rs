@@ -0,0 +1,165 @@
+//! State change tracking.
+use std::{cell::RefCell, collections::BTreeMap};
+
+use oasis_core_runtime::storage::mkvs;
+
+use super::{Prefix, Store};
+
+/// Change in the number of keys and bytes used by state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateDelta {
+    /// Change in the number of keys.
+    pub keys: i64,
+    /// Change in the number of bytes used by keys and values.
+    pub bytes: i64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct KeyChange {
+    /// Size of the value before the first change.
+    before: Option<u64>,
+    /// Size of the value after the last change.
+    after: Option<u64>,
+}
+
+impl KeyChange {
+    fn delta(&self, key: &[u8]) -> StateDelta {
+        let size = |value: Option<u64>| value.map(|size| key.len() as i64 + size as i64);
+        StateDelta {
+            keys: self.after.is_some() as i64 - self.before.is_some() as i64,
+            bytes: size(self.after).unwrap_or_default() - size(self.before).unwrap_or_default(),
+        }
+    }
+}
+
+/// A set of changes made to state.
+#[derive(Clone, Debug, Default)]
+pub struct StateChanges {
+    keys: BTreeMap<Vec<u8>, KeyChange>,
+}
+
+impl StateChanges {
+    fn record(&mut self, key: &[u8], before: impl FnOnce() -> Option<u64>, after: Option<u64>) {
+        self.keys
+            .entry(key.to_vec())
+            .or_insert_with(|| KeyChange {
+                before: before(),
+                after,
+            })
+            .after = after;
+    }
+
+    /// Whether there have been no changes.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Iterate over all changed keys together with the resulting change in state size.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], StateDelta)> {
+        self.keys
+            .iter()
+            .map(|(key, change)| (key.as_slice(), change.delta(key)))
+    }
+}
+
+/// A store that records all changes made to the underlying store.
+pub struct ChangeTrackingStore<'a, S: Store> {
+    inner: S,
+    changes: Option<&'a RefCell<StateChanges>>,
+}
+
+impl<'a, S: Store> ChangeTrackingStore<'a, S> {
+    /// Create a new change tracking store recording changes into the given set. In case no set is
+    /// given, changes are not recorded.
+    pub fn new(inner: S, changes: Option<&'a RefCell<StateChanges>>) -> Self {
+        Self { inner, changes }
+    }
+
+    fn record(&self, key: &[u8], after: Option<u64>) {
+        if let Some(changes) = self.changes {
+            changes.borrow_mut().record(
+                key,
+                || self.inner.get(key).map(|value| value.len() as u64),
+                after,
+            );
+        }
+    }
+}
+
+impl<'a, S: Store> Store for ChangeTrackingStore<'a, S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.inner.get(key)
+    }
+
+    fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.record(key, Some(value.len() as u64));
+        self.inner.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.record(key, None);
+        self.inner.remove(key)
+    }
+
+    fn iter(&self) -> Box<dyn mkvs::Iterator + '_> {
+        self.inner.iter()
+    }
+
+    fn prefetch_prefixes(&mut self, prefixes: Vec<Prefix>, limit: u16) {
+        self.inner.prefetch_prefixes(prefixes, limit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::mock::empty_store;
+
+    #[test]
+    fn test_change_tracking() {
+        let mut root = empty_store();
+        root.insert(b"existing", b"value");
+        root.insert(b"removed", b"value");
+
+        let changes = RefCell::new(StateChanges::default());
+        let mut store = ChangeTrackingStore::new(&mut root, Some(&changes));
+        store.insert(b"existing", b"longer value");
+        store.remove(b"removed");
+        store.insert(b"created", b"value");
+        store.insert(b"temporary", b"value");
+        store.remove(b"temporary");
+        store.remove(b"missing");
+        drop(store);
+
+        let changes = changes.into_inner();
+        let deltas: BTreeMap<&[u8], StateDelta> = changes.iter().collect();
+        assert_eq!(
+            deltas.get(b"existing".as_slice()),
+            Some(&StateDelta { keys: 0, bytes: 7 })
+        );
+        assert_eq!(
+            deltas.get(b"removed".as_slice()),
+            Some(&StateDelta {
+                keys: -1,
+                bytes: -12
+            })
+        );
+        assert_eq!(
+            deltas.get(b"created".as_slice()),
+            Some(&StateDelta { keys: 1, bytes: 12 })
+        );
+        assert_eq!(
+            deltas.get(b"temporary".as_slice()),
+            Some(&StateDelta::default())
+        );
+        assert_eq!(
+            deltas.get(b"missing".as_slice()),
+            Some(&StateDelta::default())
+        );
+
+        // Changes should not be recorded when tracking is disabled.
+        let mut store = ChangeTrackingStore::new(&mut root, None);
+        store.insert(b"other", b"value");
+        assert_eq!(store.get(b"other"), Some(b"value".to_vec()));
+    }
+}
@@ -0,0 +1,486 @@
+//! Typed storage collections.
+use std::{convert::TryInto, marker::PhantomData};
+
+use oasis_core_runtime::storage::mkvs::Iterator as _;
+
+use super::Store;
+use crate::types::{address::Address, app_id::AppId, token};
+
+/// A key that can be used in typed storage collections.
+///
+/// Iteration over collections follows the lexicographic order of encoded keys, so encodings
+/// should preserve the natural ordering of keys (e.g. integers are encoded in big-endian).
+pub trait StoreKey: Sized {
+    /// Encode the key into its storage representation.
+    fn encode_key(&self) -> Vec<u8>;
+
+    /// Decode the key from its storage representation.
+    fn decode_key(data: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_store_key_for_int {
+    ($($ty:ty),*) => {
+        $(
+            impl StoreKey for $ty {
+                fn encode_key(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+
+                fn decode_key(data: &[u8]) -> Option<Self> {
+                    Some(<$ty>::from_be_bytes(data.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_store_key_for_int!(u8, u16, u32, u64, u128);
+
+impl StoreKey for Vec<u8> {
+    fn encode_key(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode_key(data: &[u8]) -> Option<Self> {
+        Some(data.to_vec())
+    }
+}
+
+impl StoreKey for String {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode_key(data: &[u8]) -> Option<Self> {
+        String::from_utf8(data.to_vec()).ok()
+    }
+}
+
+impl StoreKey for Address {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn decode_key(data: &[u8]) -> Option<Self> {
+        Address::from_bytes(data).ok()
+    }
+}
+
+impl StoreKey for AppId {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn decode_key(data: &[u8]) -> Option<Self> {
+        AppId::from_bytes(data).ok()
+    }
+}
+
+impl StoreKey for token::Denomination {
+    fn encode_key(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn decode_key(data: &[u8]) -> Option<Self> {
+        data.try_into().ok()
+    }
+}
+
+/// A page of collection entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<T, K> {
+    /// Entries in the page.
+    pub items: Vec<T>,
+    /// Key of the first entry of the next page, if any.
+    pub next: Option<K>,
+}
+
+fn decode_entry<K: StoreKey, V: cbor::Decode>((k, v): (Vec<u8>, Vec<u8>)) -> (K, V) {
+    let key = K::decode_key(&k).expect("corrupted storage key");
+    (key, cbor::from_slice(&v).unwrap())
+}
+
+fn paginate<K: StoreKey, T>(iter: impl Iterator<Item = (K, T)>, limit: usize) -> Page<(K, T), K> {
+    let mut items = Vec::new();
+    let mut next = None;
+    for (key, item) in iter {
+        if items.len() == limit {
+            next = Some(key);
+            break;
+        }
+        items.push((key, item));
+    }
+    Page { items, next }
+}
+
+/// A map with typed keys and values.
+pub struct StoreMap<S: Store, K: StoreKey, V> {
+    store: S,
+
+    _key: PhantomData<K>,
+    _value: PhantomData<V>,
+}
+
+impl<S: Store, K: StoreKey, V: cbor::Encode + cbor::Decode> StoreMap<S, K, V> {
+    /// Create a new map backed by the given store (usually a `PrefixStore`).
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            _key: PhantomData,
+            _value: PhantomData,
+        }
+    }
+
+    /// Fetch the value stored under the given key.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.store
+            .get(&key.encode_key())
+            .map(|data| cbor::from_slice(&data).unwrap())
+    }
+
+    /// Whether a value is stored under the given key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.store.get(&key.encode_key()).is_some()
+    }
+
+    /// Store the given value under the given key.
+    pub fn insert(&mut self, key: &K, value: V) {
+        self.store.insert(&key.encode_key(), &cbor::to_vec(value));
+    }
+
+    /// Remove the value stored under the given key.
+    pub fn remove(&mut self, key: &K) {
+        self.store.remove(&key.encode_key());
+    }
+
+    /// Iterate over all entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.store.iter().map(decode_entry)
+    }
+
+    /// Iterate over entries in key order, starting with the given key (inclusive).
+    pub fn iter_from(&self, start: &K) -> impl Iterator<Item = (K, V)> + '_ {
+        let mut it = self.store.iter();
+        it.seek(&start.encode_key());
+        it.map(decode_entry)
+    }
+
+    /// Return at most `limit` entries in key order starting with the given key (inclusive) or the
+    /// first key in case no start key is given.
+    pub fn page(&self, start: Option<&K>, limit: usize) -> Page<(K, V), K> {
+        match start {
+            Some(start) => paginate(self.iter_from(start), limit),
+            None => paginate(self.iter(), limit),
+        }
+    }
+}
+
+/// An ordered set of typed keys.
+pub struct StoreSet<S: Store, K: StoreKey> {
+    store: S,
+
+    _key: PhantomData<K>,
+}
+
+impl<S: Store, K: StoreKey> StoreSet<S, K> {
+    /// Marker value stored for each set member.
+    const MEMBER: &'static [u8] = &[0x01];
+
+    /// Create a new set backed by the given store (usually a `PrefixStore`).
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            _key: PhantomData,
+        }
+    }
+
+    /// Whether the given key is a member of the set.
+    pub fn contains(&self, key: &K) -> bool {
+        self.store.get(&key.encode_key()).is_some()
+    }
+
+    /// Add the given key to the set.
+    pub fn insert(&mut self, key: &K) {
+        self.store.insert(&key.encode_key(), Self::MEMBER);
+    }
+
+    /// Remove the given key from the set.
+    pub fn remove(&mut self, key: &K) {
+        self.store.remove(&key.encode_key());
+    }
+
+    /// Iterate over all members in order.
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        self.store
+            .iter()
+            .map(|(k, _)| K::decode_key(&k).expect("corrupted storage key"))
+    }
+
+    /// Return at most `limit` members in order starting with the given key (inclusive) or the
+    /// first member in case no start key is given.
+    pub fn page(&self, start: Option<&K>, limit: usize) -> Page<K, K> {
+        let mut it = self.store.iter();
+        if let Some(start) = start {
+            it.seek(&start.encode_key());
+        }
+        let page = paginate(
+            it.map(|(k, _)| (K::decode_key(&k).expect("corrupted storage key"), ())),
+            limit,
+        );
+        Page {
+            items: page.items.into_iter().map(|(k, _)| k).collect(),
+            next: page.next,
+        }
+    }
+}
+
+/// A double-ended queue of typed values.
+pub struct StoreQueue<S: Store, V> {
+    store: S,
+
+    _value: PhantomData<V>,
+}
+
+impl<S: Store, V: cbor::Encode + cbor::Decode> StoreQueue<S, V> {
+    /// Key under which the queue bounds are stored.
+    const BOUNDS_KEY: &'static [u8] = &[0x00];
+    /// Prefix of keys under which the queue items are stored.
+    const ITEMS_PREFIX: u8 = 0x01;
+    /// Index of the first item of an empty queue. Starting in the middle of the index space
+    /// allows the queue to grow in both directions.
+    const INITIAL_INDEX: u64 = 1 << 63;
+
+    /// Create a new queue backed by the given store (usually a `PrefixStore`).
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            _value: PhantomData,
+        }
+    }
+
+    /// Index of the first item and index after the last item.
+    fn bounds(&self) -> (u64, u64) {
+        self.store
+            .get(Self::BOUNDS_KEY)
+            .map(|data| cbor::from_slice(&data).unwrap())
+            .unwrap_or((Self::INITIAL_INDEX, Self::INITIAL_INDEX))
+    }
+
+    fn set_bounds(&mut self, head: u64, tail: u64) {
+        if head == tail {
+            self.store.remove(Self::BOUNDS_KEY);
+        } else {
+            self.store
+                .insert(Self::BOUNDS_KEY, &cbor::to_vec((head, tail)));
+        }
+    }
+
+    fn item_key(index: u64) -> Vec<u8> {
+        [&[Self::ITEMS_PREFIX][..], &index.to_be_bytes()].concat()
+    }
+
+    fn take(&mut self, index: u64) -> V {
+        let key = Self::item_key(index);
+        let data = self.store.get(&key).expect("corrupted queue");
+        self.store.remove(&key);
+        cbor::from_slice(&data).unwrap()
+    }
+
+    /// Number of items in the queue.
+    pub fn len(&self) -> u64 {
+        let (head, tail) = self.bounds();
+        tail - head
+    }
+
+    /// Whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetch the item at the given position from the front of the queue.
+    pub fn get(&self, position: u64) -> Option<V> {
+        let (head, tail) = self.bounds();
+        if position >= tail - head {
+            return None;
+        }
+        self.store
+            .get(&Self::item_key(head + position))
+            .map(|data| cbor::from_slice(&data).unwrap())
+    }
+
+    /// Fetch the item at the front of the queue.
+    pub fn front(&self) -> Option<V> {
+        self.get(0)
+    }
+
+    /// Fetch the item at the back of the queue.
+    pub fn back(&self) -> Option<V> {
+        self.len().checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    /// Append an item to the back of the queue.
+    pub fn push_back(&mut self, value: V) {
+        let (head, tail) = self.bounds();
+        self.store
+            .insert(&Self::item_key(tail), &cbor::to_vec(value));
+        self.set_bounds(head, tail + 1);
+    }
+
+    /// Prepend an item to the front of the queue.
+    pub fn push_front(&mut self, value: V) {
+        let (head, tail) = self.bounds();
+        self.store
+            .insert(&Self::item_key(head - 1), &cbor::to_vec(value));
+        self.set_bounds(head - 1, tail);
+    }
+
+    /// Remove and return the item at the front of the queue.
+    pub fn pop_front(&mut self) -> Option<V> {
+        let (head, tail) = self.bounds();
+        if head == tail {
+            return None;
+        }
+        let value = self.take(head);
+        self.set_bounds(head + 1, tail);
+        Some(value)
+    }
+
+    /// Remove and return the item at the back of the queue.
+    pub fn pop_back(&mut self) -> Option<V> {
+        let (head, tail) = self.bounds();
+        if head == tail {
+            return None;
+        }
+        let value = self.take(tail - 1);
+        self.set_bounds(head, tail - 1);
+        Some(value)
+    }
+
+    /// Iterate over all items from the front to the back of the queue.
+    pub fn iter(&self) -> impl Iterator<Item = V> + '_ {
+        let (head, tail) = self.bounds();
+        let mut it = self.store.iter();
+        it.seek(&Self::item_key(head));
+        it.take((tail - head) as usize)
+            .map(|(_, v)| cbor::from_slice(&v).unwrap())
+    }
+
+    /// Return at most `limit` items starting at the given position from the front of the queue.
+    pub fn page(&self, start: u64, limit: usize) -> Page<V, u64> {
+        let len = self.len();
+        let start = start.min(len);
+        let count = (len - start).min(limit as u64);
+        let (head, _) = self.bounds();
+        let mut it = self.store.iter();
+        it.seek(&Self::item_key(head + start));
+        Page {
+            items: it
+                .take(count as usize)
+                .map(|(_, v)| cbor::from_slice(&v).unwrap())
+                .collect(),
+            next: (start + count < len).then_some(start + count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        storage::PrefixStore,
+        testing::{keys, mock::empty_store},
+    };
+
+    #[test]
+    fn test_store_map() {
+        let mut store = empty_store();
+        let mut map = StoreMap::<_, u64, String>::new(PrefixStore::new(&mut store, b"map"));
+
+        assert!(map.get(&1).is_none());
+        for i in [3u64, 1, 2, 300] {
+            map.insert(&i, format!("value {i}"));
+        }
+        assert!(map.contains(&2));
+        assert_eq!(map.get(&300), Some("value 300".to_owned()));
+
+        // Iteration should follow key order.
+        let keys: Vec<_> = map.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![1, 2, 3, 300]);
+        let keys: Vec<_> = map.iter_from(&3).map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![3, 300]);
+
+        let page = map.page(None, 2);
+        assert_eq!(
+            page.items,
+            vec![(1, "value 1".to_owned()), (2, "value 2".to_owned())]
+        );
+        assert_eq!(page.next, Some(3));
+        let page = map.page(page.next.as_ref(), 2);
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.next, None);
+
+        map.remove(&2);
+        assert!(!map.contains(&2));
+        assert_eq!(map.iter().count(), 3);
+
+        // Other collections must not interfere.
+        let other = StoreMap::<_, Address, u64>::new(PrefixStore::new(&mut store, b"other"));
+        assert_eq!(other.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_store_set() {
+        let mut store = empty_store();
+        let mut set = StoreSet::<_, Address>::new(PrefixStore::new(&mut store, b"set"));
+
+        set.insert(&keys::alice::address());
+        set.insert(&keys::bob::address());
+        set.insert(&keys::bob::address());
+        assert!(set.contains(&keys::alice::address()));
+        assert!(!set.contains(&keys::charlie::address()));
+
+        let mut expected = vec![keys::alice::address(), keys::bob::address()];
+        expected.sort();
+        assert_eq!(set.iter().collect::<Vec<_>>(), expected);
+
+        let page = set.page(None, 1);
+        assert_eq!(page.items, vec![expected[0]]);
+        assert_eq!(page.next, Some(expected[1]));
+
+        set.remove(&keys::alice::address());
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![keys::bob::address()]);
+    }
+
+    #[test]
+    fn test_store_queue() {
+        let mut store = empty_store();
+        let mut queue = StoreQueue::<_, u32>::new(PrefixStore::new(&mut store, b"queue"));
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+        assert_eq!(queue.pop_back(), None);
+
+        queue.push_back(2);
+        queue.push_back(3);
+        queue.push_front(1);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.front(), Some(1));
+        assert_eq!(queue.back(), Some(3));
+        assert_eq!(queue.get(1), Some(2));
+        assert_eq!(queue.get(3), None);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let page = queue.page(1, 1);
+        assert_eq!(page.items, vec![2]);
+        assert_eq!(page.next, Some(2));
+        let page = queue.page(2, 10);
+        assert_eq!(page.items, vec![3]);
+        assert_eq!(page.next, None);
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert_eq!(queue.pop_back(), Some(3));
+        assert_eq!(queue.pop_back(), Some(2));
+        assert!(queue.is_empty());
+        assert_eq!(queue.iter().count(), 0);
+    }
+}
@@ -2,6 +2,7 @@ use std::convert::TryInto as _;
 
 use anyhow;
 use hmac::{Hmac, Mac as _};
+use rand_core::{OsRng, RngCore as _};
 use sha2::Sha512_256;
 use slog::error;
 use thiserror::Error;
@@ -22,6 +23,73 @@ use crate::{
 type Nonce = [u8; NONCE_SIZE];
 type Kdf = Hmac<Sha512_256>;
 
+/// Associated data used when sealing padded values. This makes padded values distinguishable from
+/// unpadded ones so that the padding policy of an existing store can be changed.
+const PADDED_VALUE_AD: &[u8] = b"oasis-runtime-sdk/confidential-store: padded value";
+/// Marker byte separating the plaintext value from its padding.
+const PADDING_MARKER: u8 = 0x80;
+
+/// Padding applied to values before encryption in order to hide their exact size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValuePadding {
+    /// Values are stored without padding.
+    None,
+    /// Values are padded to the next multiple of the given bucket size.
+    Bucket(u32),
+    /// Values are padded to the next power of two, but at least to the given size.
+    PowerOfTwo(u32),
+}
+
+impl ValuePadding {
+    /// Size of the padded value for a plaintext value of the given size.
+    fn padded_size(&self, size: usize) -> Option<usize> {
+        // Additional byte needed for the padding marker.
+        let size = size + 1;
+        match *self {
+            ValuePadding::None => None,
+            ValuePadding::Bucket(bucket) => {
+                let bucket = (bucket as usize).max(1);
+                Some((size + bucket - 1) / bucket * bucket)
+            }
+            ValuePadding::PowerOfTwo(min) => Some(size.next_power_of_two().max(min as usize)),
+        }
+    }
+}
+
+/// Policy for hardening a confidential store against size and access pattern side channels.
+///
+/// Encryption alone does not hide how large the stored values are or which keys are being read,
+/// both of which are observable by the untrusted host. Modules storing privacy-sensitive data
+/// can configure padding of values to fixed size buckets and injection of dummy reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// Padding applied to values before encryption.
+    pub value_padding: ValuePadding,
+    /// Number of dummy reads of randomly chosen existing keys performed for each read of a key.
+    /// The actual read is performed at a random position among the dummy reads.
+    ///
+    /// Dummy reads use node-local randomness, which is fine as they never affect the results of
+    /// execution. Note that writes are always visible in the write log, so dummy reads only hide
+    /// reads.
+    pub dummy_reads: u8,
+}
+
+impl Policy {
+    /// Policy that performs no hardening.
+    pub const fn none() -> Self {
+        Self {
+            value_padding: ValuePadding::None,
+            dummy_reads: 0,
+        }
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
 /// Unpack the concatenation of (nonce || byte_slice) into (Nonce, &[u8]).
 fn unpack_nonce_slice<'a>(packed: &'a [u8]) -> Option<(&'a Nonce, &'a [u8])> {
     if packed.len() <= NONCE_SIZE {
@@ -53,6 +121,7 @@ pub struct ConfidentialStore<S: Store> {
     base_value_prefix: Vec<u8>,
     nonce_counter: usize,
     nonce_key: Zeroizing<Vec<u8>>,
+    policy: Policy,
 }
 
 impl<S: Store> ConfidentialStore<S> {
@@ -76,9 +145,19 @@ impl<S: Store> ConfidentialStore<S> {
             base_value_prefix: value_context.concat(),
             nonce_counter: 0,
             nonce_key: Zeroizing::new(derived.as_slice().to_vec()),
+            policy: Policy::none(),
         }
     }
 
+    /// Use the given side channel hardening policy.
+    ///
+    /// Values stored under any policy can be read under all other policies, so the policy of an
+    /// existing store may be changed at any time. Changes only apply to newly written values.
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
     fn pack_nonce_slice(&self, nonce: &Nonce, slice: &[u8]) -> Vec<u8> {
         let mut ret = Vec::with_capacity(nonce.len() + slice.len());
         ret.extend_from_slice(nonce);
@@ -119,11 +198,94 @@ impl<S: Store> ConfidentialStore<S> {
         ]);
         nonce.copy_from_slice(hash.truncated(NONCE_SIZE));
 
-        let enc_value = self.deoxys.seal(&nonce, plain_value, vec![]);
+        let enc_value = match self.policy.value_padding.padded_size(plain_value.len()) {
+            Some(size) => {
+                let mut padded = Vec::with_capacity(size);
+                padded.extend_from_slice(plain_value);
+                padded.push(PADDING_MARKER);
+                padded.resize(size, 0);
+                self.deoxys.seal(&nonce, padded, PADDED_VALUE_AD.to_vec())
+            }
+            None => self.deoxys.seal(&nonce, plain_value, vec![]),
+        };
         let value = self.pack_nonce_slice(&nonce, &enc_value);
         (nonce, value)
     }
 
+    fn get_value(&self, raw: &[u8]) -> Result<Vec<u8>, Error> {
+        let (nonce, enc_ref) = unpack_nonce_slice(raw).ok_or(Error::CorruptValue)?;
+
+        // Try the format matching the current policy first, falling back to the other one for
+        // values written under a different policy.
+        let padded_first = self.policy.value_padding != ValuePadding::None;
+        let open = |padded: bool| {
+            let ad = if padded { PADDED_VALUE_AD } else { &[] };
+            self.deoxys.open(nonce, enc_ref.to_vec(), ad.to_vec())
+        };
+        let (padded, plain) = match open(padded_first) {
+            Ok(plain) => (padded_first, plain),
+            Err(_) => (
+                !padded_first,
+                open(!padded_first).map_err(|err| Error::DecryptionFailure(err.into()))?,
+            ),
+        };
+        if !padded {
+            return Ok(plain);
+        }
+
+        // Strip padding.
+        let end = plain
+            .iter()
+            .rposition(|b| *b != 0)
+            .filter(|end| plain[*end] == PADDING_MARKER)
+            .ok_or(Error::CorruptValue)?;
+        let mut plain = plain;
+        plain.truncate(end);
+        Ok(plain)
+    }
+
+    fn read(&self, plain_key: &[u8]) -> Option<Vec<u8>> {
+        let (_, inner_key) = self.make_key(plain_key);
+        let dummy_reads = self.policy.dummy_reads as usize;
+        if dummy_reads == 0 {
+            return self.inner.get(&inner_key);
+        }
+
+        // Perform the actual read at a random position so that it is not always the first or the
+        // last among the dummy reads.
+        let position = OsRng.next_u32() as usize % (dummy_reads + 1);
+
+        let mut result = None;
+        for i in 0..=dummy_reads {
+            if i == position {
+                result = self.inner.get(&inner_key);
+                continue;
+            }
+            self.dummy_read();
+        }
+        result
+    }
+
+    /// Read a random existing key so that the read is indistinguishable from an actual one.
+    fn dummy_read(&self) {
+        // Encrypted keys start with a nonce derived from a hash of the plaintext key, so seeking
+        // to a random nonce selects an existing key uniformly at random.
+        let mut seek_key = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut seek_key);
+
+        let mut iter = self.inner.iter();
+        iter.seek(&seek_key);
+        if !iter.is_valid() {
+            // Wrap around to the first key.
+            iter.rewind();
+        }
+        let key = iter.get_key().clone();
+        drop(iter);
+
+        // If the store is empty, any read will be of a missing key.
+        self.inner.get(&key.unwrap_or_else(|| seek_key.to_vec()));
+    }
+
     fn get_item(&self, raw: &[u8]) -> Result<(Nonce, Vec<u8>), Error> {
         match unpack_nonce_slice(raw) {
             Some((nonce, enc_ref)) => {
@@ -148,11 +310,9 @@ impl<S: Store> Zeroize for ConfidentialStore<S> {
 
 impl<S: Store> Store for ConfidentialStore<S> {
     fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
-        let (_, inner_key) = self.make_key(key);
-        self.inner.get(&inner_key).map(|inner_value| {
-            self.get_item(&inner_value)
+        self.read(key).map(|inner_value| {
+            self.get_value(&inner_value)
                 .expect("error decrypting value")
-                .1
         })
     }
 
@@ -208,8 +368,8 @@ impl<'store, S: Store> ConfidentialStoreIterator<'store, S> {
         }
 
         match self.store.get_item(inner_key) {
-            Ok((_, key)) => match self.store.get_item(inner_value) {
-                Ok((_, value)) => {
+            Ok((_, key)) => match self.store.get_value(inner_value) {
+                Ok(value) => {
                     self.key = Some(key);
                     self.value = Some(value);
                 }
@@ -509,6 +669,144 @@ mod test {
         plain_store.remove(&key);
     }
 
+    fn with_policy<S: Store>(inner: S, policy: Policy) -> ConfidentialStore<S> {
+        ConfidentialStore::new_with_key(inner, [0xaau8; 32], &[b"confidential store unit tests"])
+            .with_policy(policy)
+    }
+
+    fn raw_value_sizes(store: &impl Store) -> Vec<usize> {
+        let mut iter = store.iter();
+        iter.rewind();
+        Iterator::map(iter, |(_, value)| value.len()).collect()
+    }
+
+    #[test]
+    fn value_padding() {
+        for (padding, expected) in [
+            (ValuePadding::Bucket(64), 64),
+            (ValuePadding::PowerOfTwo(16), 64),
+        ] {
+            let mut plain_store = empty_store();
+            let mut store = with_policy(
+                &mut plain_store,
+                Policy {
+                    value_padding: padding,
+                    ..Default::default()
+                },
+            );
+            let values: Vec<Vec<u8>> = vec![vec![], vec![0x00; 10], vec![0x80; 33], vec![0xff; 63]];
+            for (i, value) in values.iter().enumerate() {
+                store.insert(&[i as u8], value);
+            }
+            for (i, value) in values.iter().enumerate() {
+                assert_eq!(&store.get(&[i as u8]).expect("value should exist"), value);
+            }
+            drop(store);
+
+            // All values should have the same size in the underlying store.
+            let sizes = raw_value_sizes(&plain_store);
+            assert_eq!(sizes.len(), values.len());
+            assert!(
+                sizes.iter().all(|size| *size == sizes[0]),
+                "padded values should have the same size"
+            );
+            assert_eq!(sizes[0], NONCE_SIZE + expected + 16); // Tag is 16 bytes.
+
+            // Padded values should also be readable without a policy.
+            let store = with_policy(&mut plain_store, Policy::none());
+            let mut sizes = raw_value_sizes(&store);
+            sizes.sort_unstable();
+            assert_eq!(sizes, vec![0, 10, 33, 63]);
+            for (i, value) in values.iter().enumerate() {
+                assert_eq!(&store.get(&[i as u8]).expect("value should exist"), value);
+            }
+        }
+
+        // Unpadded values should be readable with a policy.
+        let mut plain_store = empty_store();
+        let mut store = with_policy(&mut plain_store, Policy::none());
+        store.insert(b"key", b"value");
+        drop(store);
+        let store = with_policy(
+            &mut plain_store,
+            Policy {
+                value_padding: ValuePadding::Bucket(32),
+                ..Default::default()
+            },
+        );
+        assert_eq!(store.get(b"key").expect("value should exist"), b"value");
+    }
+
+    /// A store that counts reads and reads of existing keys.
+    struct CountingStore<S: Store> {
+        inner: S,
+        reads: std::cell::Cell<usize>,
+        hits: std::cell::Cell<usize>,
+    }
+
+    impl<S: Store> Store for CountingStore<S> {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.reads.set(self.reads.get() + 1);
+            let value = self.inner.get(key);
+            if value.is_some() {
+                self.hits.set(self.hits.get() + 1);
+            }
+            value
+        }
+
+        fn insert(&mut self, key: &[u8], value: &[u8]) {
+            self.inner.insert(key, value)
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.inner.remove(key)
+        }
+
+        fn iter(&self) -> Box<dyn mkvs::Iterator + '_> {
+            self.inner.iter()
+        }
+
+        fn prefetch_prefixes(&mut self, prefixes: Vec<mkvs::Prefix>, limit: u16) {
+            self.inner.prefetch_prefixes(prefixes, limit)
+        }
+    }
+
+    #[test]
+    fn dummy_reads() {
+        let mut plain_store = empty_store();
+        let mut store = with_policy(
+            CountingStore {
+                inner: &mut plain_store,
+                reads: Default::default(),
+                hits: Default::default(),
+            },
+            Policy {
+                dummy_reads: 3,
+                ..Default::default()
+            },
+        );
+        let items = make_items(10);
+        for (k, v) in items.iter() {
+            store.insert(k, v);
+        }
+        assert_eq!(store.inner.reads.get(), 0, "writes should not cause reads");
+
+        for (k, v) in items.iter() {
+            assert_eq!(&store.get(k).expect("item should exist"), v);
+        }
+        assert!(store.get(b"missing").is_none());
+        assert_eq!(store.inner.reads.get(), 4 * (items.len() + 1));
+        assert_eq!(
+            store.inner.hits.get(),
+            4 * items.len() + 3,
+            "dummy reads should read existing keys"
+        );
+
+        // Dummy reads should not create any entries.
+        drop(store);
+        assert_eq!(raw_value_sizes(&plain_store).len(), items.len());
+    }
+
     fn run<F>(confidential: bool, inserts: usize, mut cb: F)
     where
         F: FnMut(&mut Box<dyn Store + '_>, &Vec<(Vec<u8>, Vec<u8>)>),
@@ -3,7 +3,10 @@ use std::cell::RefCell;
 
 use oasis_core_runtime::storage::mkvs;
 
-use crate::storage::{MKVSStore, NestedStore, OverlayStore, Store};
+use crate::storage::{
+    access::{AccessTracker, AccessTrackingStore},
+    AccessStats, MKVSStore, NestedStore, OverlayStore, PendingUpdateSummary, Store,
+};
 
 thread_local! {
     static CURRENT: RefCell<Vec<CurrentStore>> = RefCell::new(Vec::new());
@@ -45,6 +48,8 @@ pub struct CurrentStore {
     store: *mut dyn Store,
     #[allow(clippy::vec_box)] // Must be boxed to survive the vector extending, moving elements.
     transactions: Vec<Box<OverlayStore<&'static mut (dyn Store + 'static)>>>,
+    /// Tracker of accessed keys in case access tracking is enabled.
+    access_tracker: Option<RefCell<AccessTracker>>,
 }
 
 impl CurrentStore {
@@ -65,6 +70,7 @@ impl CurrentStore {
                 std::mem::transmute::<_, *mut (dyn Store + 'static)>(&mut root as &mut dyn Store)
             },
             transactions: vec![],
+            access_tracker: None,
         };
 
         CURRENT.with(|c| {
@@ -113,6 +119,7 @@ impl CurrentStore {
             let base = CurrentStore {
                 store: store as *mut dyn Store,
                 transactions: vec![],
+                access_tracker: None,
             };
 
             CURRENT.with(|c| {
@@ -199,6 +206,15 @@ impl CurrentStore {
         });
     }
 
+    /// Number of currently open transactions.
+    fn transaction_level() -> usize {
+        CURRENT.with(|c| {
+            let current_ref = c.try_borrow().expect("must not re-enter from with block");
+            let current = current_ref.last().expect("must enter context");
+            current.transactions.len()
+        })
+    }
+
     /// Whether there are any store updates pending to be committed in the current transaction.
     ///
     /// If there is no current transaction, the method returns `true`.
@@ -222,6 +238,77 @@ impl CurrentStore {
         })
     }
 
+    /// Total size (in bytes) of store updates pending to be committed in the current transaction.
+    ///
+    /// If there is no current transaction, the method returns zero.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn pending_update_bytes() -> u64 {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+
+            current
+                .transactions
+                .last()
+                .map(|store| store.pending_update_bytes())
+                .unwrap_or_default()
+        })
+    }
+
+    /// All store updates pending to be committed in the current transaction, ordered by key.
+    /// Removals are represented by a `None` value.
+    ///
+    /// If there is no current transaction, the method returns no updates.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn pending_updates() -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+
+            current
+                .transactions
+                .last()
+                .map(|store| store.pending_updates())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Summary of store updates pending to be committed in the current transaction compared to
+    /// the state before the transaction.
+    ///
+    /// If there is no current transaction, the method returns an empty summary.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn pending_update_summary() -> PendingUpdateSummary {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+
+            current
+                .transactions
+                .last()
+                .map(|store| store.pending_update_summary())
+                .unwrap_or_default()
+        })
+    }
+
     /// Run a closure with the currently active store.
     ///
     /// # Panics
@@ -240,7 +327,109 @@ impl CurrentStore {
             // while we are inside the storage context.
             let store = unsafe { &mut *current.store };
 
-            f(store)
+            match current.access_tracker {
+                Some(ref tracker) => f(&mut AccessTrackingStore::new(store, tracker)),
+                None => f(store),
+            }
+        })
+    }
+
+    /// Start tracking accesses to storage keys made through `CurrentStore::with` in a new
+    /// tracking scope.
+    ///
+    /// The first access to each key after tracking has started is counted as a cold access and
+    /// any further accesses to the same key as warm accesses. Keys remain warm even if the
+    /// transaction that accessed them is reverted. In case tracking is already enabled, a nested
+    /// scope is opened which shares the access history of its parent scopes. Each scope must be
+    /// closed via `CurrentStore::stop_access_tracking`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn start_access_tracking() {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+            current
+                .access_tracker
+                .get_or_insert_with(Default::default)
+                .get_mut()
+                .push_scope();
+        });
+    }
+
+    /// Statistics of storage accesses made in the innermost tracking scope, including any nested
+    /// scopes.
+    ///
+    /// In case access tracking is not enabled, no accesses are reported.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn access_stats() -> AccessStats {
+        CURRENT.with(|c| {
+            let current_ref = c.try_borrow().expect("must not re-enter from with block");
+            let current = current_ref.last().expect("must enter context");
+            current
+                .access_tracker
+                .as_ref()
+                .map(|tracker| tracker.borrow().stats())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Take the storage accesses made in the innermost tracking scope since they were last taken.
+    ///
+    /// This allows accesses to be paid for as they happen instead of only once the scope is
+    /// closed. Accesses of nested scopes that have not been taken by the time the nested scope is
+    /// closed are included. In case access tracking is not enabled, no accesses are reported.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn take_pending_access_stats() -> AccessStats {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+            current
+                .access_tracker
+                .as_mut()
+                .map(|tracker| tracker.get_mut().take_pending())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Close the innermost tracking scope and return the statistics of accesses made in it.
+    ///
+    /// The accesses are accumulated into the parent scope, if any. Once the outermost scope is
+    /// closed, tracking stops and all access history is discarded.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn stop_access_tracking() -> AccessStats {
+        CURRENT.with(|c| {
+            let mut current_ref = c
+                .try_borrow_mut()
+                .expect("must not re-enter from with block");
+            let current = current_ref.last_mut().expect("must enter context");
+            let tracker = match current.access_tracker.as_mut() {
+                Some(tracker) => tracker.get_mut(),
+                None => return Default::default(),
+            };
+            let stats = tracker.pop_scope();
+            if tracker.is_empty() {
+                current.access_tracker = None;
+            }
+            stats
         })
     }
 
@@ -266,6 +455,56 @@ impl CurrentStore {
             }
         }
     }
+
+    /// Run a closure within a savepoint of the current transaction.
+    ///
+    /// The closure may call `Savepoint::rollback` to revert all updates made since the savepoint
+    /// was created without affecting earlier updates of the enclosing transaction. This allows
+    /// modules implementing multi-step operations to revert a part of their own work without
+    /// aborting the whole transaction. Once the closure returns, any remaining updates are merged
+    /// into the enclosing transaction. If the closure panics, all of its updates are reverted.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called outside `CurrentStore::enter` or if called within a
+    /// `CurrentStore::with` block.
+    pub fn with_savepoint<F, R>(f: F) -> R
+    where
+        F: FnOnce(&Savepoint) -> R,
+    {
+        let level = Self::start_transaction();
+        let _guard = TransactionGuard(level); // Ensure transaction is always closed.
+
+        let result = f(&Savepoint { level });
+        Self::commit_transaction();
+        result
+    }
+}
+
+/// A savepoint within the current transaction, created via `CurrentStore::with_savepoint`.
+pub struct Savepoint {
+    level: usize,
+}
+
+impl Savepoint {
+    /// Revert all updates made since the savepoint was created.
+    ///
+    /// The savepoint remains active so further updates can again be reverted.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if called while a transaction or savepoint nested within this
+    /// savepoint is still open or if called within a `CurrentStore::with` block.
+    pub fn rollback(&self) {
+        assert_eq!(
+            CurrentStore::transaction_level(),
+            self.level,
+            "must not roll back a savepoint from within a nested transaction"
+        );
+
+        CurrentStore::rollback_transaction();
+        CurrentStore::start_transaction();
+    }
 }
 
 #[cfg(test)]
@@ -273,7 +512,7 @@ mod test {
     use oasis_core_runtime::storage::mkvs;
 
     use super::{CurrentStore, TransactionResult};
-    use crate::storage::{MKVSStore, Store};
+    use crate::storage::{AccessStats, MKVSStore, Store};
 
     fn test_store_basic() {
         CurrentStore::start_transaction();
@@ -291,6 +530,15 @@ mod test {
             CurrentStore::has_pending_updates(),
             "should have pending updates after insert"
         );
+        assert_eq!(
+            CurrentStore::pending_update_bytes(),
+            9,
+            "pending update size should include key and value"
+        );
+        assert_eq!(
+            CurrentStore::pending_updates(),
+            vec![(b"test".to_vec(), Some(b"value".to_vec()))]
+        );
 
         // Transaction helper.
         CurrentStore::with_transaction(|| {
@@ -395,6 +643,198 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_savepoints() {
+        let mut root = MKVSStore::new(mkvs::OverlayTree::new(
+            mkvs::Tree::builder()
+                .with_root_type(mkvs::RootType::State)
+                .build(Box::new(mkvs::sync::NoopReadSyncer)),
+        ));
+
+        CurrentStore::enter(&mut root, || {
+            CurrentStore::with_transaction(|| {
+                CurrentStore::with(|store| store.insert(b"a", b"1"));
+
+                CurrentStore::with_savepoint(|first| {
+                    CurrentStore::with(|store| store.insert(b"b", b"2"));
+
+                    CurrentStore::with_savepoint(|second| {
+                        CurrentStore::with(|store| {
+                            store.insert(b"c", b"3");
+                            store.remove(b"a");
+                        });
+
+                        // Rolling back the inner savepoint only reverts the latest updates.
+                        second.rollback();
+                        CurrentStore::with(|store| {
+                            assert_eq!(store.get(b"a"), Some(b"1".to_vec()));
+                            assert_eq!(store.get(b"b"), Some(b"2".to_vec()));
+                            assert_eq!(store.get(b"c"), None);
+                        });
+
+                        // The savepoint remains active after a rollback.
+                        CurrentStore::with(|store| store.insert(b"d", b"4"));
+                    });
+
+                    // Rolling back the outer savepoint reverts everything since it was created.
+                    first.rollback();
+                    CurrentStore::with(|store| {
+                        assert_eq!(store.get(b"a"), Some(b"1".to_vec()));
+                        assert_eq!(store.get(b"b"), None);
+                        assert_eq!(store.get(b"d"), None);
+                    });
+
+                    CurrentStore::with(|store| store.insert(b"e", b"5"));
+                });
+
+                // Savepoints that panic are reverted.
+                let result = std::panic::catch_unwind(|| {
+                    CurrentStore::with_savepoint(|_| {
+                        CurrentStore::with(|store| store.insert(b"f", b"6"));
+                        panic!("aborted");
+                    })
+                });
+                assert!(result.is_err());
+                CurrentStore::with(|store| assert_eq!(store.get(b"f"), None));
+
+                TransactionResult::Commit(())
+            });
+        });
+
+        assert_eq!(root.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(root.get(b"b"), None);
+        assert_eq!(root.get(b"c"), None);
+        assert_eq!(root.get(b"d"), None);
+        assert_eq!(root.get(b"e"), Some(b"5".to_vec()));
+        assert_eq!(root.get(b"f"), None);
+    }
+
+    #[test]
+    fn test_access_tracking() {
+        let mut root = MKVSStore::new(mkvs::OverlayTree::new(
+            mkvs::Tree::builder()
+                .with_root_type(mkvs::RootType::State)
+                .build(Box::new(mkvs::sync::NoopReadSyncer)),
+        ));
+        root.insert(b"a", b"1");
+        root.insert(b"b", b"2");
+
+        CurrentStore::enter(&mut root, || {
+            // Accesses are not tracked by default.
+            CurrentStore::with(|store| store.get(b"a"));
+            assert_eq!(CurrentStore::access_stats(), AccessStats::default());
+
+            CurrentStore::start_access_tracking();
+            CurrentStore::with(|store| {
+                store.get(b"a");
+                store.get(b"a");
+                store.insert(b"c", b"3");
+                store.remove(b"a");
+            });
+            assert_eq!(
+                CurrentStore::access_stats(),
+                AccessStats { cold: 2, warm: 2 }
+            );
+
+            // Keys remain warm after a rollback.
+            CurrentStore::with_transaction(|| {
+                CurrentStore::with(|store| store.insert(b"d", b"4"));
+                TransactionResult::Rollback(())
+            });
+            CurrentStore::with(|store| store.get(b"d"));
+            assert_eq!(
+                CurrentStore::access_stats(),
+                AccessStats { cold: 3, warm: 3 }
+            );
+
+            // Iteration accesses each visited key.
+            CurrentStore::with(|store| {
+                let mut it = store.iter();
+                it.rewind();
+                assert_eq!(it.count(), 2); // Keys b and c.
+            });
+            assert_eq!(
+                CurrentStore::stop_access_tracking(),
+                AccessStats { cold: 4, warm: 4 }
+            );
+
+            CurrentStore::with(|store| store.get(b"b"));
+            assert_eq!(CurrentStore::access_stats(), AccessStats::default());
+        });
+    }
+
+    #[test]
+    fn test_nested_access_tracking() {
+        let mut root = MKVSStore::new(mkvs::OverlayTree::new(
+            mkvs::Tree::builder()
+                .with_root_type(mkvs::RootType::State)
+                .build(Box::new(mkvs::sync::NoopReadSyncer)),
+        ));
+
+        CurrentStore::enter(&mut root, || {
+            CurrentStore::start_access_tracking();
+            CurrentStore::with(|store| store.get(b"a"));
+            assert_eq!(
+                CurrentStore::take_pending_access_stats(),
+                AccessStats { cold: 1, warm: 0 }
+            );
+            assert_eq!(
+                CurrentStore::take_pending_access_stats(),
+                AccessStats::default(),
+                "taken accesses should not be reported again"
+            );
+
+            // Nested scopes share the access history of their parents.
+            CurrentStore::start_access_tracking();
+            CurrentStore::with(|store| {
+                store.get(b"a");
+                store.get(b"b");
+            });
+            assert_eq!(
+                CurrentStore::access_stats(),
+                AccessStats { cold: 1, warm: 1 }
+            );
+            CurrentStore::take_pending_access_stats();
+            CurrentStore::with(|store| store.get(b"c"));
+            assert_eq!(
+                CurrentStore::stop_access_tracking(),
+                AccessStats { cold: 2, warm: 1 }
+            );
+
+            // Accesses of nested scopes accumulate into the parent, and accesses that have not
+            // been taken in the nested scope remain pending in the parent.
+            assert_eq!(
+                CurrentStore::access_stats(),
+                AccessStats { cold: 3, warm: 1 }
+            );
+            assert_eq!(
+                CurrentStore::take_pending_access_stats(),
+                AccessStats { cold: 1, warm: 0 }
+            );
+            assert_eq!(
+                CurrentStore::stop_access_tracking(),
+                AccessStats { cold: 3, warm: 1 }
+            );
+
+            // Tracking stops once the outermost scope is closed.
+            CurrentStore::with(|store| store.get(b"d"));
+            assert_eq!(CurrentStore::access_stats(), AccessStats::default());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "must not roll back a savepoint from within a nested transaction")]
+    fn test_fail_rollback_savepoint_nested() {
+        CurrentStore::init_local_fallback();
+
+        CurrentStore::with_savepoint(|savepoint| {
+            CurrentStore::with_transaction(|| {
+                savepoint.rollback(); // Should panic.
+                TransactionResult::Commit(())
+            })
+        });
+    }
+
     #[test]
     #[should_panic(expected = "must enter context")]
     fn test_fail_not_entered() {
@@ -1,6 +1,9 @@
 //! Storage.
 use oasis_core_runtime::storage::mkvs::Iterator;
 
+mod access;
+mod changes;
+mod collections;
 pub mod confidential;
 pub mod current;
 mod hashed;
@@ -88,11 +91,16 @@ impl<S: Store + ?Sized> Store for Box<S> {
     }
 }
 
-pub use confidential::{ConfidentialStore, Error as ConfidentialStoreError};
+pub use access::AccessStats;
+pub use changes::{ChangeTrackingStore, StateChanges, StateDelta};
+pub use collections::{Page, StoreKey, StoreMap, StoreQueue, StoreSet};
+pub use confidential::{
+    ConfidentialStore, Error as ConfidentialStoreError, Policy as ConfidentialStorePolicy,
+};
 pub use current::CurrentStore;
 pub use hashed::HashedStore;
 pub use mkvs::MKVSStore;
-pub use overlay::OverlayStore;
+pub use overlay::{OverlayStore, PendingUpdateSummary};
 pub use prefix::PrefixStore;
 pub use typed::TypedStore;
 
@@ -7,11 +7,23 @@ use oasis_core_runtime::storage::mkvs;
 
 use super::{NestedStore, Prefix, Store};
 
+/// Summary of pending updates compared to the contents of the parent store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PendingUpdateSummary {
+    /// Number of keys present in the parent store that are removed.
+    pub removed: u64,
+    /// Number of keys that are changed and then written back to the value they have in the
+    /// parent store (including keys that are created and then removed again).
+    pub restored: u64,
+}
+
 /// An overlay store which keeps values locally until explicitly committed.
 pub struct OverlayStore<S: Store> {
     parent: S,
     overlay: BTreeMap<Vec<u8>, Vec<u8>>,
     dirty: HashSet<Vec<u8>>,
+    /// Keys whose value has been changed by at least one update.
+    changed: HashSet<Vec<u8>>,
 }
 
 impl<S: Store> OverlayStore<S> {
@@ -21,6 +33,57 @@ impl<S: Store> OverlayStore<S> {
             parent,
             overlay: BTreeMap::new(),
             dirty: HashSet::new(),
+            changed: HashSet::new(),
+        }
+    }
+
+    /// Total size (in bytes) of all pending updates. Inserts count both the key and the value
+    /// while removals only count the key.
+    pub fn pending_update_bytes(&self) -> u64 {
+        self.dirty
+            .iter()
+            .map(|key| {
+                let value_len = self.overlay.get(key).map(|v| v.len()).unwrap_or_default();
+                (key.len() + value_len) as u64
+            })
+            .sum()
+    }
+
+    /// All pending updates ordered by key. Removals are represented by a `None` value.
+    pub fn pending_updates(&self) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        let mut updates: Vec<_> = self
+            .dirty
+            .iter()
+            .map(|key| (key.clone(), self.overlay.get(key).cloned()))
+            .collect();
+        updates.sort();
+        updates
+    }
+
+    /// Summary of pending updates compared to the contents of the parent store.
+    ///
+    /// Updates that write the value a key already has are not counted.
+    pub fn pending_update_summary(&self) -> PendingUpdateSummary {
+        let mut summary = PendingUpdateSummary::default();
+        for key in &self.changed {
+            match (self.overlay.get(key), self.parent.get(key)) {
+                (None, Some(_)) => summary.removed += 1,
+                (None, None) => summary.restored += 1,
+                (Some(value), Some(original)) if *value == original => summary.restored += 1,
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Record that the given key has been changed in case the update does not leave the current
+    /// value intact.
+    fn mark_changed(&mut self, key: &[u8], value: Option<&[u8]>) {
+        if self.changed.contains(key) {
+            return;
+        }
+        if self.get(key).as_deref() != value {
+            self.changed.insert(key.to_owned());
         }
     }
 }
@@ -64,11 +127,14 @@ impl<S: Store> Store for OverlayStore<S> {
     }
 
     fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.mark_changed(key, Some(value));
         self.overlay.insert(key.to_owned(), value.to_owned());
         self.dirty.insert(key.to_owned());
     }
 
     fn remove(&mut self, key: &[u8]) {
+        self.mark_changed(key, None);
+
         // For dirty values, remove from the overlay.
         if self.dirty.contains(key) {
             self.overlay.remove(key);
@@ -222,3 +288,35 @@ impl<'store, S: Store> mkvs::Iterator for OverlayStoreIterator<'store, S> {
         OverlayStoreIterator::next(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::mock::empty_store;
+
+    #[test]
+    fn test_pending_update_summary() {
+        let mut root = empty_store();
+        root.insert(b"removed", b"value");
+        root.insert(b"restored", b"value");
+        root.insert(b"rewritten", b"value");
+
+        let mut overlay = OverlayStore::new(&mut root);
+        overlay.remove(b"removed");
+        overlay.insert(b"restored", b"other");
+        overlay.insert(b"restored", b"value");
+        overlay.insert(b"temporary", b"value");
+        overlay.remove(b"temporary");
+        // Updates that do not change the value should not be counted.
+        overlay.insert(b"rewritten", b"value");
+        overlay.remove(b"missing");
+
+        assert_eq!(
+            overlay.pending_update_summary(),
+            PendingUpdateSummary {
+                removed: 1,
+                restored: 2,
+            }
+        );
+    }
+}
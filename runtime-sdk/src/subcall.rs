@@ -1,25 +1,63 @@
 //! Subcall dispatch.
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, VecDeque},
+    rc::Rc,
+};
 
 use crate::{
     context::{BatchContext, Context, State, TransactionWithMeta, TxContext},
     dispatcher,
+    error::{self, Error as _},
     module::CallResult,
     modules::core::{Error, API as _},
     runtime::Runtime,
     storage::{current::TransactionResult, CurrentStore},
+    trace,
     types::{token, transaction, transaction::CallerAddress},
 };
 
+/// Context key for the subcalls deferred by the current transaction.
+const CONTEXT_KEY_DEFERRED_SUBCALLS: &str = "subcall.DeferredSubcalls";
+
+/// Maximum number of subcalls that can be deferred by a single transaction, including any nested
+/// subcalls.
+const MAX_DEFERRED_SUBCALLS: usize = 16;
+/// Maximum depth of subcalls deferred by deferred subcalls.
+const MAX_DEFERRED_DEPTH: u16 = 4;
+/// Amount of gas charged for each deferred subcall in addition to its prepaid gas.
+const DEFERRED_SUBCALL_GAS: u64 = 1_000;
+
 thread_local! {
     /// The subcall stack for tracking depth and other metadata.
     static SUBCALL_STACK: RefCell<SubcallStack> = RefCell::new(SubcallStack::new());
 }
 
 /// Subcall validator.
+///
+/// A validator is used to validate the subcall it was passed with and also any nested subcalls
+/// performed while it is executing.
 pub trait Validator {
     /// Validate a subcall before it is performed.
     fn validate(&self, info: &SubcallInfo) -> Result<(), Error>;
+
+    /// Validate the result of the subcall this validator was passed with after it has been
+    /// performed. Note that the result includes the effects of any nested subcalls.
+    ///
+    /// This is run before the effects of the subcall are committed. In case this returns an error,
+    /// the effects are reverted and the error is returned from `subcall::call` instead of the
+    /// result.
+    fn after_call(&self, _info: &SubcallInfo, _result: &SubcallResult) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Maximum amount of gas that subcalls validated by this validator may use, if limited.
+    ///
+    /// The maximum gas of the subcall this validator was passed with and of any nested subcalls is
+    /// clamped to this limit before validation.
+    fn gas_limit(&self) -> Option<u64> {
+        None
+    }
 }
 
 /// A validator which allows everything.
@@ -31,6 +69,119 @@ impl Validator for AllowAllValidator {
     }
 }
 
+/// A validator which only passes in case all of the inner validators pass.
+pub struct AllOf(pub Vec<Box<dyn Validator>>);
+
+impl Validator for AllOf {
+    fn validate(&self, info: &SubcallInfo) -> Result<(), Error> {
+        for validator in &self.0 {
+            validator.validate(info)?;
+        }
+        Ok(())
+    }
+
+    fn after_call(&self, info: &SubcallInfo, result: &SubcallResult) -> Result<(), Error> {
+        for validator in &self.0 {
+            validator.after_call(info, result)?;
+        }
+        Ok(())
+    }
+
+    fn gas_limit(&self) -> Option<u64> {
+        self.0
+            .iter()
+            .filter_map(|validator| validator.gas_limit())
+            .min()
+    }
+}
+
+/// A validator which only allows calling the given methods.
+///
+/// Methods are either specified by their full name (e.g. `accounts.Transfer`) or by a module
+/// wildcard (e.g. `accounts.*`) which allows all methods of the given module.
+pub struct MethodAllowlistValidator {
+    methods: BTreeSet<String>,
+}
+
+impl MethodAllowlistValidator {
+    /// Create a new validator allowing the given methods.
+    pub fn new<I, S>(methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            methods: methods.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    fn is_allowed(&self, method: &str) -> bool {
+        if self.methods.contains(method) {
+            return true;
+        }
+        match method.split_once('.') {
+            Some((module, _)) => self.methods.contains(&format!("{module}.*")),
+            None => false,
+        }
+    }
+}
+
+impl Validator for MethodAllowlistValidator {
+    fn validate(&self, info: &SubcallInfo) -> Result<(), Error> {
+        if !self.is_allowed(&info.method) {
+            return Err(Error::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+/// A validator which limits the cumulative amount of gas used by subcalls.
+///
+/// Clones of the validator share the same budget so it can be used to limit the total amount of
+/// gas used by multiple subcalls.
+#[derive(Clone)]
+pub struct GasBudgetValidator {
+    budget: u64,
+    used: Rc<Cell<u64>>,
+}
+
+impl GasBudgetValidator {
+    /// Create a new validator with the given gas budget.
+    pub fn new(budget: u64) -> Self {
+        Self {
+            budget,
+            used: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Amount of gas remaining in the budget.
+    pub fn remaining(&self) -> u64 {
+        self.budget.saturating_sub(self.used.get())
+    }
+}
+
+impl Validator for GasBudgetValidator {
+    fn validate(&self, _info: &SubcallInfo) -> Result<(), Error> {
+        if self.remaining() == 0 {
+            return Err(Error::OutOfGas(self.budget, self.used.get()));
+        }
+        Ok(())
+    }
+
+    fn after_call(&self, _info: &SubcallInfo, result: &SubcallResult) -> Result<(), Error> {
+        let used = self.used.get().saturating_add(result.gas_used);
+        self.used.set(used);
+        if used > self.budget {
+            return Err(Error::OutOfGas(self.budget, used));
+        }
+        Ok(())
+    }
+
+    fn gas_limit(&self) -> Option<u64> {
+        Some(self.remaining())
+    }
+}
+
 /// Information about a subcall to be dispatched.
 #[derive(Clone, Debug)]
 pub struct SubcallInfo {
@@ -44,6 +195,9 @@ pub struct SubcallInfo {
     pub max_depth: u16,
     /// Maximum gas amount that can be consumed.
     pub max_gas: u64,
+    /// Whether the subcall is read-only. Read-only subcalls fail in case they attempt to modify
+    /// storage, emit events or emit consensus messages.
+    pub read_only: bool,
 }
 
 /// Result of dispatching a subcall.
@@ -55,6 +209,57 @@ pub struct SubcallResult {
     pub call_result: CallResult,
     /// Gas used by the subcall.
     pub gas_used: u64,
+    /// Gas refund recorded by the subcall (including any nested subcalls). The refund has already
+    /// been credited to the caller's transaction and is only applied once the top-level call
+    /// completes. Refunds of failed subcalls are discarded.
+    pub gas_refund: u64,
+}
+
+impl SubcallResult {
+    /// Failure returned by the subcall in case it failed.
+    ///
+    /// Batch aborts are not considered subcall failures as they cannot be handled by the caller.
+    pub fn failure(&self) -> Option<SubcallFailure> {
+        match &self.call_result {
+            CallResult::Failed {
+                module,
+                code,
+                message,
+            } => Some(SubcallFailure {
+                module: module.clone(),
+                code: *code,
+                message: message.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Failure of a subcall.
+///
+/// Preserves the module name, code and message of the original error so that the failure can be
+/// propagated to the caller (e.g. by converting it into `modules::core::Error::SubcallFailed`)
+/// through any number of nested subcalls without losing information.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, cbor::Encode, cbor::Decode)]
+#[error("{message}")]
+pub struct SubcallFailure {
+    /// Name of the module that emitted the error.
+    pub module: String,
+    /// Error code uniquely identifying the error within the module.
+    pub code: u32,
+    /// Error message.
+    #[cbor(optional)]
+    pub message: String,
+}
+
+impl error::Error for SubcallFailure {
+    fn module_name(&self) -> &str {
+        &self.module
+    }
+
+    fn code(&self) -> u32 {
+        self.code
+    }
 }
 
 struct SubcallStackEntry {
@@ -63,11 +268,18 @@ struct SubcallStackEntry {
 
 struct SubcallStack {
     stack: Vec<SubcallStackEntry>,
+    guards: BTreeSet<(String, Vec<u8>)>,
+    /// Depth of the deferred subcall currently being performed (zero if none).
+    deferred_depth: u16,
 }
 
 impl SubcallStack {
     fn new() -> Self {
-        Self { stack: Vec::new() }
+        Self {
+            stack: Vec::new(),
+            guards: BTreeSet::new(),
+            deferred_depth: 0,
+        }
     }
 
     fn depth(&self) -> u16 {
@@ -88,6 +300,20 @@ impl SubcallStack {
         }
         Ok(())
     }
+
+    fn gas_limit(&self) -> Option<u64> {
+        self.stack
+            .iter()
+            .filter_map(|entry| entry.validator.gas_limit())
+            .min()
+    }
+
+    fn run_after_call(&self, info: &SubcallInfo, result: &SubcallResult) -> Result<(), Error> {
+        match self.stack.last() {
+            Some(entry) => entry.validator.after_call(info, result),
+            None => Ok(()),
+        }
+    }
 }
 
 struct SubcallStackGuard;
@@ -100,6 +326,83 @@ impl Drop for SubcallStackGuard {
     }
 }
 
+/// Generate an internal transaction performing the given subcall.
+fn internal_transaction(info: &SubcallInfo, consensus_messages: u32) -> transaction::Transaction {
+    transaction::Transaction {
+        version: transaction::LATEST_TRANSACTION_VERSION,
+        call: transaction::Call {
+            format: transaction::CallFormat::Plain,
+            method: info.method.clone(),
+            body: info.body.clone(),
+            read_only: info.read_only,
+            memo: None,
+        },
+        auth_info: transaction::AuthInfo {
+            signer_info: vec![transaction::SignerInfo {
+                // The call is being performed on the caller's behalf.
+                address_spec: transaction::AddressSpec::Internal(info.caller.clone()),
+                nonce: 0,
+            }],
+            fee: transaction::Fee {
+                amount: token::BaseUnits::new(0, token::Denomination::NATIVE),
+                // Limit gas usage inside the child context to the allocated maximum.
+                gas: info.max_gas,
+                consensus_messages,
+            },
+            ..Default::default()
+        },
+    }
+}
+
+/// A guard preventing reentrancy into a section of code identified by a module name and key.
+///
+/// The guarded section is entered when the guard is acquired and left when the guard is dropped.
+/// Any attempt to acquire the same guard while it is held, e.g. from a nested subcall performed
+/// within the guarded section, fails with `Error::ReentrantCall`.
+pub struct ReentrancyGuard {
+    module: String,
+    key: Vec<u8>,
+}
+
+impl ReentrancyGuard {
+    /// Enter the section guarded by the given module name and key.
+    pub fn enter(module: &str, key: &[u8]) -> Result<Self, Error> {
+        SUBCALL_STACK.with(|ss| {
+            if !ss
+                .borrow_mut()
+                .guards
+                .insert((module.to_owned(), key.to_vec()))
+            {
+                return Err(Error::ReentrantCall);
+            }
+            Ok(Self {
+                module: module.to_owned(),
+                key: key.to_vec(),
+            })
+        })
+    }
+
+    /// Whether the section guarded by the given module name and key is currently entered.
+    pub fn is_entered(module: &str, key: &[u8]) -> bool {
+        SUBCALL_STACK.with(|ss| {
+            ss.borrow()
+                .guards
+                .contains(&(module.to_owned(), key.to_vec()))
+        })
+    }
+}
+
+impl Drop for ReentrancyGuard {
+    fn drop(&mut self) {
+        SUBCALL_STACK.with(|ss| {
+            ss.borrow_mut().guards.remove(&(
+                std::mem::take(&mut self.module),
+                std::mem::take(&mut self.key),
+            ));
+        });
+    }
+}
+
 /// The current subcall depth.
 pub fn get_current_subcall_depth<C: Context>(_ctx: &mut C) -> u16 {
     SUBCALL_STACK.with(|ss| ss.borrow().depth())
@@ -111,11 +414,31 @@ pub fn call<C: TxContext, V: Validator + 'static>(
     info: SubcallInfo,
     validator: V,
 ) -> Result<SubcallResult, Error> {
+    // Apply the runtime-wide subcall limits so callers cannot exceed them.
+    let limits = <C::Runtime as Runtime>::Core::subcall_parameters();
+    let remaining_gas = <C::Runtime as Runtime>::Core::remaining_tx_gas(ctx);
+    let mut info = SubcallInfo {
+        max_depth: limits.max_depth(info.max_depth),
+        max_gas: info.max_gas.min(limits.max_forwarded_gas(remaining_gas)),
+        ..info
+    };
+
+    // Clamp the gas to the limits of the validator and any validators on the subcall stack.
+    let stack_gas_limit = SUBCALL_STACK.with(|ss| ss.borrow().gas_limit());
+    if let Some(limit) = validator
+        .gas_limit()
+        .into_iter()
+        .chain(stack_gas_limit)
+        .min()
+    {
+        info.max_gas = info.max_gas.min(limit);
+    }
+
     // Run validator first.
     validator.validate(&info)?;
 
     // Update the subcall stack after doing validation.
-    SUBCALL_STACK.with(|ss| {
+    let depth = SUBCALL_STACK.with(|ss| {
         let mut stack = ss.borrow_mut();
 
         // Ensure the call depth is not too large.
@@ -131,39 +454,24 @@ pub fn call<C: TxContext, V: Validator + 'static>(
             validator: Box::new(validator) as Box<dyn Validator>,
         });
 
-        Ok(())
+        Ok(stack.depth())
     })?;
     let _guard = SubcallStackGuard; // Ensure subcall is popped from stack.
+    let span = trace::subcall(&info, depth);
 
     // Calculate how many consensus messages the child call can emit.
     let remaining_messages = ctx.remaining_messages();
+    // Calculate how many subcalls the child call can defer.
+    let deferred_count = ctx
+        .tx_value::<Vec<SubcallInfo>>(CONTEXT_KEY_DEFERRED_SUBCALLS)
+        .get()
+        .map(Vec::len)
+        .unwrap_or_default();
 
     // Execute a transaction in a child context.
-    let (call_result, gas, state) = ctx.with_child(ctx.mode(), |mut ctx| {
+    let (result, deferred) = ctx.with_child(ctx.mode(), |mut ctx| {
         // Generate an internal transaction.
-        let tx = transaction::Transaction {
-            version: transaction::LATEST_TRANSACTION_VERSION,
-            call: transaction::Call {
-                format: transaction::CallFormat::Plain,
-                method: info.method,
-                body: info.body,
-                ..Default::default()
-            },
-            auth_info: transaction::AuthInfo {
-                signer_info: vec![transaction::SignerInfo {
-                    // The call is being performed on the caller's behalf.
-                    address_spec: transaction::AddressSpec::Internal(info.caller),
-                    nonce: 0,
-                }],
-                fee: transaction::Fee {
-                    amount: token::BaseUnits::new(0, token::Denomination::NATIVE),
-                    // Limit gas usage inside the child context to the allocated maximum.
-                    gas: info.max_gas,
-                    consensus_messages: remaining_messages,
-                },
-                ..Default::default()
-            },
-        };
+        let tx = internal_transaction(&info, remaining_messages);
 
         let result = CurrentStore::with_transaction(|| {
             ctx.with_tx(TransactionWithMeta::internal(tx), |ctx, call| {
@@ -178,15 +486,47 @@ pub fn call<C: TxContext, V: Validator + 'static>(
                 );
                 // Retrieve remaining gas.
                 let gas = <C::Runtime as Runtime>::Core::remaining_tx_gas(&mut ctx);
+                let gas_used = info.max_gas.saturating_sub(gas);
 
                 // Commit store and return emitted tags and messages on successful dispatch,
                 // otherwise revert state and ignore any emitted events/messages.
-                if result.is_success() {
-                    let state = ctx.commit();
-                    TransactionResult::Commit((result, gas, state))
-                } else {
+                if !result.is_success() {
                     // Ignore tags/messages on failure.
-                    TransactionResult::Rollback((result, gas, Default::default()))
+                    return TransactionResult::Rollback((
+                        after_call(&info, result, gas_used, 0, Default::default()),
+                        Vec::new(),
+                    ));
+                }
+                let gas_refund = <C::Runtime as Runtime>::Core::refunded_tx_gas(&mut ctx);
+                let deferred = take_deferred(&mut ctx);
+                if deferred_count.saturating_add(deferred.len()) > MAX_DEFERRED_SUBCALLS {
+                    return TransactionResult::Rollback((
+                        Err(Error::TooManyDeferredSubcalls),
+                        Vec::new(),
+                    ));
+                }
+                let state = ctx.commit();
+
+                // Make sure that a read-only subcall did not emit any events or messages. Storage
+                // modifications are already checked by the dispatcher.
+                if info.read_only && (!state.events.is_empty() || !state.messages.is_empty()) {
+                    return TransactionResult::Rollback((
+                        after_call(
+                            &info,
+                            Error::ReadOnlyTransaction.into_call_result(),
+                            gas_used,
+                            0,
+                            Default::default(),
+                        ),
+                        Vec::new(),
+                    ));
+                }
+
+                // Run the post-call hook of the subcall's own validator before committing so
+                // that it can still revert the subcall's effects.
+                match after_call(&info, result, gas_used, gas_refund, state) {
+                    Ok(result) => TransactionResult::Commit((Ok(result), deferred)),
+                    Err(err) => TransactionResult::Rollback((Err(err), Vec::new())),
                 }
             })
         });
@@ -196,13 +536,967 @@ pub fn call<C: TxContext, V: Validator + 'static>(
 
         result
     });
+    let result = result?;
 
-    // Compute the amount of gas used.
-    let gas_used = info.max_gas.saturating_sub(gas);
+    span.record_gas_used(result.gas_used);
+    span.record_success(result.call_result.is_success());
 
-    Ok(SubcallResult {
+    // Credit any gas refund generated by the child to the current transaction.
+    <C::Runtime as Runtime>::Core::refund_tx_gas(ctx, result.gas_refund);
+
+    // Propagate any subcalls deferred by the child to the current transaction.
+    if !deferred.is_empty() {
+        ctx.tx_value::<Vec<SubcallInfo>>(CONTEXT_KEY_DEFERRED_SUBCALLS)
+            .or_default()
+            .extend(deferred);
+    }
+
+    Ok(result)
+}
+
+/// Run the post-call hook of the validator of the current subcall on the given result.
+fn after_call(
+    info: &SubcallInfo,
+    call_result: CallResult,
+    gas_used: u64,
+    gas_refund: u64,
+    state: State,
+) -> Result<SubcallResult, Error> {
+    let result = SubcallResult {
         state,
         call_result,
         gas_used,
-    })
+        gas_refund,
+    };
+    SUBCALL_STACK.with(|ss| ss.borrow().run_after_call(info, &result))?;
+    Ok(result)
+}
+
+/// Defer a subcall to be performed after the current transaction has been committed.
+///
+/// The maximum amount of gas of the subcall, which must be non-zero, is prepaid from the current
+/// transaction at the time the subcall is deferred together with a fixed per-subcall fee and any
+/// unused gas is not refunded. The number of subcalls deferred by a transaction and the depth of
+/// subcalls deferred by deferred subcalls are limited. Deferred subcalls are only performed
+/// in case the current transaction succeeds and are executed in the order in which they were
+/// deferred, after all state updates of the transaction have been committed. A failing deferred
+/// subcall only reverts its own effects.
+///
+/// Deferred subcalls are not subject to validators of the current subcall stack.
+pub fn defer<C: TxContext>(ctx: &mut C, info: SubcallInfo) -> Result<(), Error> {
+    if ctx.is_read_only() {
+        return Err(Error::ReadOnlyTransaction);
+    }
+
+    // Apply the runtime-wide subcall limits and prepay the gas.
+    let limits = <C::Runtime as Runtime>::Core::subcall_parameters();
+    let remaining_gas =
+        <C::Runtime as Runtime>::Core::remaining_tx_gas(ctx).saturating_sub(DEFERRED_SUBCALL_GAS);
+    let info = SubcallInfo {
+        max_gas: info.max_gas.min(limits.max_forwarded_gas(remaining_gas)),
+        ..info
+    };
+    if info.max_gas == 0 {
+        return Err(Error::InvalidArgument(anyhow::anyhow!(
+            "deferred subcall must have non-zero gas"
+        )));
+    }
+
+    // Enforce the queue limits.
+    if SUBCALL_STACK.with(|ss| ss.borrow().deferred_depth) >= MAX_DEFERRED_DEPTH {
+        return Err(Error::TooManyDeferredSubcalls);
+    }
+    let deferred = ctx
+        .tx_value::<Vec<SubcallInfo>>(CONTEXT_KEY_DEFERRED_SUBCALLS)
+        .get()
+        .map(Vec::len)
+        .unwrap_or_default();
+    if deferred >= MAX_DEFERRED_SUBCALLS {
+        return Err(Error::TooManyDeferredSubcalls);
+    }
+
+    <C::Runtime as Runtime>::Core::use_tx_gas(
+        ctx,
+        info.max_gas.saturating_add(DEFERRED_SUBCALL_GAS),
+    )?;
+
+    ctx.tx_value::<Vec<SubcallInfo>>(CONTEXT_KEY_DEFERRED_SUBCALLS)
+        .or_default()
+        .push(info);
+
+    Ok(())
+}
+
+/// Take the subcalls deferred by the current transaction.
+pub(crate) fn take_deferred<C: TxContext>(ctx: &mut C) -> Vec<SubcallInfo> {
+    ctx.tx_value::<Vec<SubcallInfo>>(CONTEXT_KEY_DEFERRED_SUBCALLS)
+        .take()
+        .unwrap_or_default()
+}
+
+/// Perform the given deferred subcalls, including any subcalls that are deferred by them, and
+/// return the combined state of all successful subcalls.
+pub(crate) fn run_deferred<C: Context>(
+    ctx: &mut C,
+    deferred: Vec<SubcallInfo>,
+    max_messages: u32,
+) -> State {
+    let mut state = State::default();
+    let mut queue: VecDeque<(SubcallInfo, u16)> =
+        deferred.into_iter().map(|info| (info, 1)).collect();
+    while let Some((info, depth)) = queue.pop_front() {
+        let remaining_messages = max_messages.saturating_sub(state.messages.len() as u32);
+        let _guard = DeferredDepthGuard::enter(depth);
+
+        let (call_state, deferred) = ctx.with_child(ctx.mode(), |mut ctx| {
+            let tx = internal_transaction(&info, remaining_messages);
+
+            let result = CurrentStore::with_transaction(|| {
+                ctx.with_tx(TransactionWithMeta::internal(tx), |ctx, call| {
+                    let mut ctx = ctx.internal();
+
+                    let (result, _) = dispatcher::Dispatcher::<C::Runtime>::dispatch_tx_call(
+                        &mut ctx,
+                        call,
+                        &Default::default(),
+                    );
+                    if !result.is_success() {
+                        return TransactionResult::Rollback(Default::default());
+                    }
+                    let deferred = take_deferred(&mut ctx);
+                    let state = ctx.commit();
+
+                    if info.read_only && (!state.events.is_empty() || !state.messages.is_empty()) {
+                        return TransactionResult::Rollback(Default::default());
+                    }
+
+                    TransactionResult::Commit((state, deferred))
+                })
+            });
+
+            ctx.commit();
+
+            result
+        });
+
+        state.merge_from(call_state);
+        queue.extend(deferred.into_iter().map(|info| (info, depth + 1)));
+    }
+    state
+}
+
+/// A guard setting the depth of the deferred subcall currently being performed.
+struct DeferredDepthGuard(u16);
+
+impl DeferredDepthGuard {
+    fn enter(depth: u16) -> Self {
+        SUBCALL_STACK.with(|ss| {
+            Self(std::mem::replace(
+                &mut ss.borrow_mut().deferred_depth,
+                depth,
+            ))
+        })
+    }
+}
+
+impl Drop for DeferredDepthGuard {
+    fn drop(&mut self) {
+        SUBCALL_STACK.with(|ss| ss.borrow_mut().deferred_depth = self.0);
+    }
+}
+
+/// Perform a call outside of any transaction, for example from a block handler.
+///
+/// The call is dispatched as an internal transaction on behalf of the caller and its effects are
+/// reverted in case it fails. Detached calls cannot emit consensus messages. Any subcalls deferred
+/// by the call are performed right after it and their state is included in the result.
+///
+/// Detached calls are not subject to validators of the current subcall stack.
+pub fn call_detached<C: Context>(ctx: &mut C, info: SubcallInfo) -> SubcallResult {
+    let (call_result, gas, gas_refund, mut state, deferred) =
+        ctx.with_child(ctx.mode(), |mut ctx| {
+            let tx = internal_transaction(&info, 0);
+
+            let result = CurrentStore::with_transaction(|| {
+                ctx.with_tx(TransactionWithMeta::internal(tx), |ctx, call| {
+                    let mut ctx = ctx.internal();
+
+                    let (result, _) = dispatcher::Dispatcher::<C::Runtime>::dispatch_tx_call(
+                        &mut ctx,
+                        call,
+                        &Default::default(),
+                    );
+                    let gas = <C::Runtime as Runtime>::Core::remaining_tx_gas(&mut ctx);
+
+                    if !result.is_success() {
+                        return TransactionResult::Rollback((
+                            result,
+                            gas,
+                            0,
+                            Default::default(),
+                            Vec::new(),
+                        ));
+                    }
+                    let gas_refund = <C::Runtime as Runtime>::Core::refunded_tx_gas(&mut ctx);
+                    let deferred = take_deferred(&mut ctx);
+                    let state = ctx.commit();
+
+                    if info.read_only && !state.events.is_empty() {
+                        return TransactionResult::Rollback((
+                            Error::ReadOnlyTransaction.into_call_result(),
+                            gas,
+                            0,
+                            Default::default(),
+                            Vec::new(),
+                        ));
+                    }
+
+                    TransactionResult::Commit((result, gas, gas_refund, state, deferred))
+                })
+            });
+
+            ctx.commit();
+
+            result
+        });
+
+    if !deferred.is_empty() {
+        state.merge_from(run_deferred(ctx, deferred, 0));
+    }
+
+    SubcallResult {
+        state,
+        call_result,
+        gas_used: info.max_gas.saturating_sub(gas),
+        gas_refund,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Mode, RuntimeBatchContext},
+        handler,
+        module::{self, Module as _},
+        modules::core::{self, API as _},
+        sdk_derive,
+        storage::Store,
+        testing::{keys, mock, mock::Mock},
+        Version,
+    };
+
+    const MODULE_NAME: &str = "subcalltest";
+
+    struct CoreConfig;
+    impl core::Config for CoreConfig {}
+    type Core = core::Module<CoreConfig>;
+
+    #[derive(Debug, cbor::Encode, oasis_runtime_sdk_macros::Event)]
+    #[cbor(untagged)]
+    enum Event {
+        #[sdk_event(code = 1)]
+        Emitted { value: u64 },
+    }
+
+    /// A module with methods that are invoked via subcalls.
+    struct SubcallModule;
+
+    #[sdk_derive(Module)]
+    impl SubcallModule {
+        const NAME: &'static str = MODULE_NAME;
+        type Error = core::Error;
+        type Event = Event;
+        type Parameters = ();
+        type Genesis = ();
+
+        #[handler(call = "subcalltest.Read")]
+        fn read<C: TxContext>(_ctx: &mut C, _args: ()) -> Result<u64, core::Error> {
+            CurrentStore::with(|store| {
+                let _ = store.get(b"key"); // Read something and ignore result.
+            });
+            Ok(42)
+        }
+
+        #[handler(call = "subcalltest.Write")]
+        fn write<C: TxContext>(_ctx: &mut C, _args: ()) -> Result<(), core::Error> {
+            CurrentStore::with(|store| {
+                store.insert(b"key", b"value");
+            });
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.Emit")]
+        fn emit<C: TxContext>(ctx: &mut C, _args: ()) -> Result<(), core::Error> {
+            ctx.emit_event(Event::Emitted { value: 42 });
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.Defer")]
+        fn defer_write<C: TxContext>(ctx: &mut C, fail: bool) -> Result<(), core::Error> {
+            for method in ["subcalltest.Write", "subcalltest.Emit"] {
+                let mut info = subcall_info(method, false);
+                info.max_gas = 10_000;
+                defer(ctx, info)?;
+            }
+            // Deferred subcalls must not be performed before the transaction completes.
+            if CurrentStore::with(|store| store.get(b"key")).is_some() {
+                return Err(core::Error::InvalidArgument(anyhow::anyhow!(
+                    "deferred subcall performed early"
+                )));
+            }
+            if fail {
+                return Err(core::Error::InvalidArgument(anyhow::anyhow!(
+                    "requested failure"
+                )));
+            }
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.DeferSelf")]
+        fn defer_self<C: TxContext>(ctx: &mut C, _args: ()) -> Result<(), core::Error> {
+            // Count the invocations and defer another invocation until the depth limit is hit.
+            CurrentStore::with(|store| {
+                let count = store.get(b"count").map(|v| v[0]).unwrap_or_default();
+                store.insert(b"count", &[count + 1]);
+            });
+            let mut info = subcall_info("subcalltest.DeferSelf", false);
+            info.max_gas = Core::remaining_tx_gas(ctx) / 2;
+            let _ = defer(ctx, info);
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.Forward")]
+        fn forward<C: TxContext>(ctx: &mut C, methods: Vec<String>) -> Result<(), core::Error> {
+            // Call the first method, passing it the remaining methods, and propagate any failure.
+            let (method, rest) = methods
+                .split_first()
+                .ok_or_else(|| core::Error::InvalidArgument(anyhow::anyhow!("no methods")))?;
+            let mut info = subcall_info(method, false);
+            if !rest.is_empty() {
+                info.body = cbor::to_value(rest.to_vec());
+            }
+            let result = call(ctx, info, AllowAllValidator)?;
+            if let Some(failure) = result.failure() {
+                return Err(failure.into());
+            }
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.Guarded")]
+        fn guarded<C: TxContext>(ctx: &mut C, reenter: bool) -> Result<(), core::Error> {
+            let _guard = ReentrancyGuard::enter(MODULE_NAME, b"guarded")?;
+            if reenter {
+                let mut info = subcall_info("subcalltest.Guarded", false);
+                info.body = cbor::to_value(false);
+                if let Some(failure) = call(ctx, info, AllowAllValidator)?.failure() {
+                    return Err(failure.into());
+                }
+            }
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.Refund")]
+        fn refund<C: TxContext>(ctx: &mut C, _args: ()) -> Result<(), core::Error> {
+            Core::refund_tx_gas(ctx, 100);
+            Ok(())
+        }
+
+        #[handler(call = "subcalltest.RefundAndFail")]
+        fn refund_and_fail<C: TxContext>(ctx: &mut C, _args: ()) -> Result<(), core::Error> {
+            Core::refund_tx_gas(ctx, 100);
+            Err(core::Error::InvalidArgument(anyhow::anyhow!(
+                "requested failure"
+            )))
+        }
+
+        #[handler(call = "subcalltest.Recurse")]
+        fn recurse<C: TxContext>(ctx: &mut C, _args: ()) -> Result<u16, core::Error> {
+            // Recurse until the maximum depth is reached and return the reached depth.
+            let info = subcall_info("subcalltest.Recurse", false);
+            match call(ctx, info, AllowAllValidator) {
+                Ok(SubcallResult {
+                    call_result: CallResult::Ok(value),
+                    ..
+                }) => Ok(cbor::from_value(value).unwrap()),
+                Ok(_) => Err(core::Error::InvalidArgument(anyhow::anyhow!(
+                    "unexpected subcall result"
+                ))),
+                Err(core::Error::CallDepthExceeded(..)) => Ok(get_current_subcall_depth(ctx)),
+                Err(err) => Err(err),
+            }
+        }
+    }
+
+    impl module::BlockHandler for SubcallModule {}
+    impl module::TransactionHandler for SubcallModule {}
+    impl module::InvariantHandler for SubcallModule {}
+
+    struct SubcallRuntime;
+
+    impl Runtime for SubcallRuntime {
+        const VERSION: Version = Version::new(0, 0, 0);
+        type Core = Core;
+        type Modules = (Core, SubcallModule);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            (
+                core::Genesis {
+                    parameters: core::Parameters {
+                        max_batch_gas: u64::MAX,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                (),
+            )
+        }
+    }
+
+    /// A validator which records the gas used by the subcalls it was passed with.
+    struct RecordingValidator(Rc<RefCell<Vec<u64>>>);
+
+    impl Validator for RecordingValidator {
+        fn validate(&self, _info: &SubcallInfo) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn after_call(&self, _info: &SubcallInfo, result: &SubcallResult) -> Result<(), Error> {
+            self.0.borrow_mut().push(result.gas_used);
+            Ok(())
+        }
+    }
+
+    /// A validator which only allows subcalls with the given maximum gas amount.
+    struct MaxGasValidator(u64);
+
+    impl Validator for MaxGasValidator {
+        fn validate(&self, info: &SubcallInfo) -> Result<(), Error> {
+            if info.max_gas != self.0 {
+                return Err(Error::Forbidden);
+            }
+            Ok(())
+        }
+    }
+
+    /// A validator which rejects all subcalls after they have been performed.
+    struct RejectAfterCallValidator;
+
+    impl Validator for RejectAfterCallValidator {
+        fn validate(&self, _info: &SubcallInfo) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn after_call(&self, _info: &SubcallInfo, _result: &SubcallResult) -> Result<(), Error> {
+            Err(Error::Forbidden)
+        }
+    }
+
+    fn subcall_info(method: &str, read_only: bool) -> SubcallInfo {
+        SubcallInfo {
+            caller: CallerAddress::Address(keys::alice::address()),
+            method: method.to_owned(),
+            body: cbor::Value::Simple(cbor::SimpleValue::NullValue),
+            max_depth: 8,
+            max_gas: 100_000,
+            read_only,
+        }
+    }
+
+    #[test]
+    fn test_read_only_subcall() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Read", true),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            assert!(
+                matches!(result.call_result, CallResult::Ok(_)),
+                "read-only subcall without modifications should succeed"
+            );
+
+            for method in ["subcalltest.Write", "subcalltest.Emit"] {
+                let result = call(&mut tx_ctx, subcall_info(method, true), AllowAllValidator)
+                    .expect("subcall should be dispatched");
+                match result.call_result {
+                    CallResult::Failed { module, code, .. } => {
+                        assert_eq!(module, "core");
+                        assert_eq!(code, 25, "subcall should fail as read-only");
+                    }
+                    _ => panic!("read-only subcall {method} with modifications should fail"),
+                }
+                assert!(result.state.events.is_empty());
+            }
+            assert!(CurrentStore::with(|store| store.get(b"key")).is_none());
+
+            for method in ["subcalltest.Write", "subcalltest.Emit"] {
+                let result = call(&mut tx_ctx, subcall_info(method, false), AllowAllValidator)
+                    .expect("subcall should be dispatched");
+                assert!(
+                    matches!(result.call_result, CallResult::Ok(_)),
+                    "regular subcall {method} should succeed"
+                );
+            }
+            assert!(CurrentStore::with(|store| store.get(b"key")).is_some());
+        });
+    }
+
+    #[test]
+    fn test_method_allowlist_validator() {
+        let validator = MethodAllowlistValidator::new(["subcalltest.Read", "accounts.*"]);
+        for (method, allowed) in [
+            ("subcalltest.Read", true),
+            ("subcalltest.Write", false),
+            ("accounts.Transfer", true),
+            ("accountsx.Transfer", false),
+            ("accounts", false),
+        ] {
+            assert_eq!(
+                validator.validate(&subcall_info(method, false)).is_ok(),
+                allowed,
+                "method {method} should be allowed: {allowed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validators() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Write", false),
+                MethodAllowlistValidator::new(["subcalltest.Read"]),
+            );
+            assert!(matches!(result, Err(Error::Forbidden)));
+
+            // Gas budget is shared between multiple subcalls.
+            let recorded = Rc::new(RefCell::new(Vec::new()));
+            let budget = GasBudgetValidator::new(1);
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Read", false),
+                AllOf(vec![
+                    Box::new(budget.clone()),
+                    Box::new(RecordingValidator(recorded.clone())),
+                ]),
+            )
+            .expect("first subcall should be dispatched");
+            assert_eq!(*recorded.borrow(), vec![result.gas_used]);
+            assert_eq!(budget.remaining(), 1u64.saturating_sub(result.gas_used));
+
+            // Exhaust the budget.
+            budget.used.set(1);
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Read", false),
+                budget.clone(),
+            );
+            assert!(matches!(result, Err(Error::OutOfGas(1, 1))));
+
+            // Maximum gas should be clamped to the remaining budget, including the budgets of
+            // validators on the subcall stack.
+            let budget = GasBudgetValidator::new(10_000);
+            call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Read", false),
+                AllOf(vec![
+                    Box::new(budget.clone()),
+                    Box::new(MaxGasValidator(10_000)),
+                ]),
+            )
+            .expect("maximum gas should be clamped to the budget");
+
+            // Effects of a subcall rejected after it has been performed should be reverted.
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Write", false),
+                RejectAfterCallValidator,
+            );
+            assert!(matches!(result, Err(Error::Forbidden)));
+            assert!(
+                CurrentStore::with(|store| store.get(b"key")).is_none(),
+                "rejected subcall should be reverted"
+            );
+        });
+    }
+
+    #[test]
+    fn test_subcall_parameters() {
+        let limits = core::SubcallParameters {
+            max_depth: 4,
+            max_gas_percentage: 50,
+            gas_retention_denominator: 64,
+        };
+        assert_eq!(limits.max_depth(8), 4);
+        assert_eq!(limits.max_depth(2), 2);
+        assert_eq!(limits.max_forwarded_gas(1_000), 500);
+
+        let limits = core::SubcallParameters {
+            gas_retention_denominator: 64,
+            ..Default::default()
+        };
+        assert_eq!(limits.max_depth(8), 8, "no depth limit should apply");
+        assert_eq!(limits.max_forwarded_gas(6_400), 6_300);
+        assert_eq!(
+            core::SubcallParameters::default().max_forwarded_gas(1_000),
+            1_000,
+            "no gas limit should apply"
+        );
+    }
+
+    #[test]
+    fn test_subcall_limits() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Recurse", false),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            let depth: u16 = match result.call_result {
+                CallResult::Ok(value) => cbor::from_value(value).unwrap(),
+                _ => panic!("subcall should succeed"),
+            };
+            assert_eq!(depth, 8, "caller-provided depth limit should apply");
+
+            Core::set_params(core::Parameters {
+                subcall: core::SubcallParameters {
+                    max_depth: 3,
+                    max_gas_percentage: 10,
+                    ..Default::default()
+                },
+                ..Core::params()
+            });
+
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Recurse", false),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            let depth: u16 = match result.call_result {
+                CallResult::Ok(value) => cbor::from_value(value).unwrap(),
+                _ => panic!("subcall should succeed"),
+            };
+            assert_eq!(depth, 3, "global depth limit should apply");
+
+            let remaining_gas = Core::remaining_tx_gas(&mut tx_ctx);
+            let mut info = subcall_info("subcalltest.Read", false);
+            info.max_gas = u64::MAX;
+            call(&mut tx_ctx, info, MaxGasValidator(remaining_gas / 10))
+                .expect("global gas limit should apply");
+        });
+    }
+
+    #[test]
+    fn test_deferred_subcalls() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        let dispatch = |ctx: &mut RuntimeBatchContext<'_, SubcallRuntime>, fail: bool| {
+            let mut tx = mock::transaction();
+            tx.call.method = "subcalltest.Defer".to_owned();
+            tx.call.body = cbor::to_value(fail);
+            dispatcher::Dispatcher::<SubcallRuntime>::dispatch_tx_opts(
+                ctx,
+                tx,
+                &dispatcher::DispatchOptions {
+                    skip_authentication: true,
+                    ..Default::default()
+                },
+            )
+            .expect("dispatch should succeed")
+        };
+
+        let emitted = |result: &dispatcher::DispatchResult| {
+            let key = [MODULE_NAME.as_bytes(), &1u32.to_be_bytes()].concat();
+            result.tags.iter().any(|tag| tag.key == key)
+        };
+
+        // Deferred subcalls of failed transactions are discarded.
+        let result = dispatch(&mut ctx, true);
+        assert!(!result.result.is_success());
+        assert!(!emitted(&result));
+        assert!(CurrentStore::with(|store| store.get(b"key")).is_none());
+
+        // Deferred subcalls are performed after the transaction.
+        let result = dispatch(&mut ctx, false);
+        assert!(result.result.is_success(), "transaction should succeed");
+        assert!(
+            CurrentStore::with(|store| store.get(b"key")).is_some(),
+            "deferred subcall should be performed"
+        );
+        assert!(
+            emitted(&result),
+            "events of deferred subcalls should be included"
+        );
+    }
+
+    #[test]
+    fn test_deferred_subcall_block_resources() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+        Core::set_params(core::Parameters {
+            block_limits: core::BlockLimits {
+                max_storage_write_bytes: 4,
+                ..Default::default()
+            },
+            ..Core::params()
+        });
+
+        let dispatch =
+            |ctx: &mut RuntimeBatchContext<'_, SubcallRuntime>, method: &str, body: cbor::Value| {
+                let mut tx = mock::transaction();
+                tx.call.method = method.to_owned();
+                tx.call.body = body;
+                dispatcher::Dispatcher::<SubcallRuntime>::dispatch_tx_opts(
+                    ctx,
+                    tx,
+                    &dispatcher::DispatchOptions {
+                        skip_authentication: true,
+                        ..Default::default()
+                    },
+                )
+                .expect("dispatch should succeed")
+            };
+        let exhausted = |result: &dispatcher::DispatchResult| matches!(&result.result, CallResult::Failed { module, code, .. } if module == "core" && *code == 30);
+
+        // Writes of deferred subcalls count towards the block limits.
+        let result = dispatch(&mut ctx, "subcalltest.Defer", cbor::to_value(false));
+        assert!(exhausted(&result), "block limits should be exceeded");
+        assert!(
+            CurrentStore::with(|store| store.get(b"key")).is_none(),
+            "deferred subcall should be reverted"
+        );
+        assert_eq!(Core::used_block_resources(&mut ctx), Default::default());
+
+        // Transactions exceeding the block limits are detected before they are scheduled.
+        let result = ctx.with_pre_schedule(|mut ctx| {
+            dispatch(
+                &mut ctx,
+                "subcalltest.Write",
+                cbor::Value::Simple(cbor::SimpleValue::NullValue),
+            )
+        });
+        assert!(exhausted(&result), "block limits should be exceeded");
+        assert!(CurrentStore::with(|store| store.get(b"key")).is_none());
+    }
+
+    #[test]
+    fn test_deferred_subcall_limits() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let mut info = subcall_info("subcalltest.Write", false);
+            info.max_gas = 0;
+            assert!(
+                matches!(
+                    defer(&mut tx_ctx, info.clone()),
+                    Err(Error::InvalidArgument(_))
+                ),
+                "deferred subcalls without gas should be rejected"
+            );
+
+            // Each deferred subcall is charged in addition to its prepaid gas.
+            info.max_gas = 1;
+            let used = Core::used_tx_gas(&mut tx_ctx);
+            for _ in 0..MAX_DEFERRED_SUBCALLS {
+                defer(&mut tx_ctx, info.clone()).expect("subcall should be deferred");
+            }
+            assert_eq!(
+                Core::used_tx_gas(&mut tx_ctx) - used,
+                MAX_DEFERRED_SUBCALLS as u64 * (1 + DEFERRED_SUBCALL_GAS)
+            );
+            assert!(
+                matches!(
+                    defer(&mut tx_ctx, info.clone()),
+                    Err(Error::TooManyDeferredSubcalls)
+                ),
+                "deferred queue length should be limited"
+            );
+
+            // Subcalls deferred by nested subcalls count towards the same limit.
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Defer", false),
+                AllowAllValidator,
+            );
+            assert!(matches!(result, Err(Error::TooManyDeferredSubcalls)));
+        });
+
+        // Subcalls deferred by deferred subcalls are limited in depth.
+        let mut tx = mock::transaction();
+        tx.call.method = "subcalltest.DeferSelf".to_owned();
+        let result = dispatcher::Dispatcher::<SubcallRuntime>::dispatch_tx_opts(
+            &mut ctx,
+            tx,
+            &dispatcher::DispatchOptions {
+                skip_authentication: true,
+                ..Default::default()
+            },
+        )
+        .expect("dispatch should succeed");
+        assert!(result.result.is_success(), "transaction should succeed");
+        assert_eq!(
+            CurrentStore::with(|store| store.get(b"count")),
+            Some(vec![MAX_DEFERRED_DEPTH as u8 + 1]),
+            "deferred subcall depth should be limited"
+        );
+    }
+
+    #[test]
+    fn test_detached_call() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        // Effects of failed calls are reverted.
+        let mut info = subcall_info("subcalltest.Defer", false);
+        info.body = cbor::to_value(true);
+        let result = call_detached(&mut ctx, info);
+        assert!(result.failure().is_some(), "call should fail");
+        assert!(result.state.events.is_empty());
+        assert!(CurrentStore::with(|store| store.get(b"key")).is_none());
+
+        // Subcalls deferred by detached calls are performed right after them.
+        let mut info = subcall_info("subcalltest.Defer", false);
+        info.body = cbor::to_value(false);
+        let result = call_detached(&mut ctx, info);
+        assert!(result.call_result.is_success(), "call should succeed");
+        assert!(
+            result.gas_used > 0,
+            "prepaid gas of deferred subcalls should be used"
+        );
+        assert!(
+            CurrentStore::with(|store| store.get(b"key")).is_some(),
+            "deferred subcall should be performed"
+        );
+        let key = [MODULE_NAME.as_bytes(), &1u32.to_be_bytes()].concat();
+        assert!(
+            result.state.events.contains_key(&key),
+            "events of deferred subcalls should be included"
+        );
+    }
+
+    #[test]
+    fn test_subcall_failure() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let mut info = subcall_info("subcalltest.Forward", false);
+            info.body = cbor::to_value(vec!["subcalltest.Forward", "subcalltest.Missing"]);
+            let result =
+                call(&mut tx_ctx, info, AllowAllValidator).expect("subcall should be dispatched");
+            assert_eq!(
+                result.failure(),
+                Some(SubcallFailure {
+                    module: "core".to_owned(),
+                    code: 3,
+                    message: "invalid method: subcalltest.Missing".to_owned(),
+                }),
+                "original failure should be propagated through nested subcalls"
+            );
+
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Read", false),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            assert_eq!(result.failure(), None);
+        });
+    }
+
+    #[test]
+    fn test_reentrancy_guard() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        {
+            let _guard = ReentrancyGuard::enter("test", b"a").expect("guard should be acquired");
+            assert!(ReentrancyGuard::is_entered("test", b"a"));
+            assert!(matches!(
+                ReentrancyGuard::enter("test", b"a"),
+                Err(Error::ReentrantCall)
+            ));
+            let _other = ReentrancyGuard::enter("test", b"b").expect("other keys are independent");
+        }
+        assert!(
+            !ReentrancyGuard::is_entered("test", b"a"),
+            "guard should be released on drop"
+        );
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            for (reenter, expected) in [(false, None), (true, Some(29))] {
+                let mut info = subcall_info("subcalltest.Guarded", false);
+                info.body = cbor::to_value(reenter);
+                let result = call(&mut tx_ctx, info, AllowAllValidator)
+                    .expect("subcall should be dispatched");
+                assert_eq!(
+                    result.failure().map(|failure| failure.code),
+                    expected,
+                    "nested reentrant call should fail (reenter: {reenter})"
+                );
+            }
+        });
+        assert!(!ReentrancyGuard::is_entered(MODULE_NAME, b"guarded"));
+    }
+
+    #[test]
+    fn test_gas_refund_propagation() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx_for_runtime::<SubcallRuntime>(Mode::ExecuteTx, false);
+        SubcallRuntime::migrate(&mut ctx);
+
+        ctx.with_tx(mock::transaction().into(), |mut tx_ctx, _call| {
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.Refund", false),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            assert_eq!(result.gas_refund, 100);
+            assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 100);
+
+            // Refunds are propagated through nested subcalls.
+            let mut info = subcall_info("subcalltest.Forward", false);
+            info.body = cbor::to_value(vec!["subcalltest.Forward", "subcalltest.Refund"]);
+            let result =
+                call(&mut tx_ctx, info, AllowAllValidator).expect("subcall should be dispatched");
+            assert_eq!(result.gas_refund, 100);
+            assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 200);
+
+            // Refunds of failed subcalls are discarded.
+            let result = call(
+                &mut tx_ctx,
+                subcall_info("subcalltest.RefundAndFail", false),
+                AllowAllValidator,
+            )
+            .expect("subcall should be dispatched");
+            assert!(!result.call_result.is_success());
+            assert_eq!(result.gas_refund, 0);
+            assert_eq!(Core::refunded_tx_gas(&mut tx_ctx), 200);
+        });
+    }
 }
@@ -0,0 +1,229 @@
+//! Simulated consensus layer.
+//!
+//! Modules such as `consensus_accounts` and `rewards` depend on signals from the consensus layer:
+//! epoch transitions, historic consensus state and events, and results of emitted messages.
+//! `SimulatedConsensus` keeps a deterministic consensus history that can be advanced on demand
+//! and serves it to the runtime via the `HistoryHost` interface. See `Mock::simulate_consensus`
+//! for attaching it to a mock dispatch context factory.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use oasis_core_runtime::{
+    consensus::{
+        beacon, roothash,
+        state::{beacon::MutableState as BeaconMutableState, ConsensusState},
+        Event, HEIGHT_LATEST,
+    },
+    storage::mkvs,
+    types::EventKind,
+};
+
+use crate::history;
+
+struct State {
+    height: u64,
+    /// Epochs by the height at which they started.
+    epochs: BTreeMap<u64, beacon::EpochTime>,
+    /// Events emitted at each height.
+    events: BTreeMap<u64, Vec<Event>>,
+}
+
+impl State {
+    fn epoch_at(&self, height: u64) -> Option<(u64, beacon::EpochTime)> {
+        self.epochs
+            .range(..=height)
+            .next_back()
+            .map(|(height, epoch)| (*height, *epoch))
+    }
+}
+
+/// Handle to a simulated consensus layer. Clones share the same history.
+#[derive(Clone)]
+pub struct SimulatedConsensus {
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for SimulatedConsensus {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl SimulatedConsensus {
+    /// Create a new simulated consensus layer at height 1 in the given epoch.
+    pub fn new(epoch: beacon::EpochTime) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(State {
+                height: 1,
+                epochs: BTreeMap::from([(1, epoch)]),
+                events: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Latest consensus height.
+    pub fn height(&self) -> u64 {
+        self.state.borrow().height
+    }
+
+    /// Epoch at the latest consensus height.
+    pub fn epoch(&self) -> beacon::EpochTime {
+        let state = self.state.borrow();
+        state.epoch_at(state.height).unwrap().1
+    }
+
+    /// Height at which the given epoch started (if it did).
+    pub fn epoch_height(&self, epoch: beacon::EpochTime) -> Option<u64> {
+        self.state
+            .borrow()
+            .epochs
+            .iter()
+            .find(|(_, e)| **e == epoch)
+            .map(|(height, _)| *height)
+    }
+
+    /// Advance the consensus layer by the given number of blocks within the current epoch.
+    pub fn advance_blocks(&self, count: u64) {
+        self.state.borrow_mut().height += count;
+    }
+
+    /// Advance the consensus layer by one block which starts the next epoch. Returns the new
+    /// epoch.
+    pub fn transition_epoch(&self) -> beacon::EpochTime {
+        let mut state = self.state.borrow_mut();
+        let epoch = state.epoch_at(state.height).unwrap().1 + 1;
+        state.height += 1;
+        let height = state.height;
+        state.epochs.insert(height, epoch);
+        epoch
+    }
+
+    /// Emit the given event at the latest consensus height.
+    pub fn emit_event(&self, event: Event) {
+        let mut state = self.state.borrow_mut();
+        let height = state.height;
+        state.events.entry(height).or_default().push(event);
+    }
+
+    /// Consensus state at the latest height.
+    pub fn state(&self) -> ConsensusState {
+        self.state_at(self.height()).unwrap()
+    }
+
+    /// Consensus state after executing the block at the given height. Only the beacon state is
+    /// populated.
+    fn state_at(&self, height: u64) -> Option<ConsensusState> {
+        let state = self.state.borrow();
+        let height = match height {
+            HEIGHT_LATEST => state.height,
+            height if height > state.height => return None,
+            height => height,
+        };
+        let (epoch_height, epoch) = state.epoch_at(height)?;
+
+        let mut tree = mkvs::Tree::builder()
+            .with_root_type(mkvs::RootType::State)
+            .build(Box::new(mkvs::sync::NoopReadSyncer));
+        let epoch_state = || beacon::EpochTimeState {
+            epoch,
+            height: epoch_height.try_into().unwrap(),
+        };
+        // The next epoch is not scheduled in advance, so the future epoch is the current one.
+        BeaconMutableState::set_epoch_state(&mut tree, epoch_state()).unwrap();
+        BeaconMutableState::set_future_epoch_state(&mut tree, epoch_state()).unwrap();
+
+        Some(ConsensusState::new(height, tree))
+    }
+}
+
+impl history::HistoryHost for SimulatedConsensus {
+    fn consensus_state_at(&self, height: u64) -> Result<ConsensusState, history::Error> {
+        self.state_at(height)
+            .ok_or(history::Error::FailedToFetchBlock)
+    }
+
+    /// Events emitted at the given height. Events are not filtered by kind.
+    fn consensus_events_at(
+        &self,
+        height: u64,
+        _kind: EventKind,
+    ) -> Result<Vec<Event>, history::Error> {
+        let state = self.state.borrow();
+        let height = match height {
+            HEIGHT_LATEST => state.height,
+            height if height > state.height => return Err(history::Error::FailedToFetchEvents),
+            height => height,
+        };
+        Ok(state.events.get(&height).cloned().unwrap_or_default())
+    }
+}
+
+/// Result of a successfully executed consensus message with the given index.
+pub fn message_success<R: cbor::Encode>(
+    module: &str,
+    index: u32,
+    result: R,
+) -> roothash::MessageEvent {
+    roothash::MessageEvent {
+        module: module.to_owned(),
+        code: 0,
+        index,
+        result: Some(cbor::to_value(result)),
+    }
+}
+
+/// Result of a failed consensus message with the given index.
+pub fn message_failure(module: &str, index: u32, code: u32) -> roothash::MessageEvent {
+    roothash::MessageEvent {
+        module: module.to_owned(),
+        code,
+        index,
+        result: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use oasis_core_runtime::consensus::{staking, state::beacon::ImmutableState};
+
+    use super::*;
+    use crate::history::HistoryHost as _;
+
+    #[test]
+    fn test_simulated_consensus() {
+        let consensus = SimulatedConsensus::new(3);
+        assert_eq!(consensus.height(), 1);
+        assert_eq!(consensus.epoch(), 3);
+
+        consensus.advance_blocks(9);
+        assert_eq!(consensus.transition_epoch(), 4);
+        assert_eq!(consensus.height(), 11);
+        assert_eq!(consensus.epoch_height(4), Some(11));
+        assert_eq!(consensus.epoch_height(5), None);
+
+        consensus.emit_event(Event::Staking(staking::Event::default()));
+        consensus.advance_blocks(1);
+
+        // Historic state should report the epoch active at each height.
+        for (height, epoch, epoch_height) in [(5, 3, 1), (11, 4, 11), (HEIGHT_LATEST, 4, 11)] {
+            let state = consensus.consensus_state_at(height).unwrap();
+            let epoch_state = ImmutableState::new(&state).epoch_state().unwrap();
+            assert_eq!(epoch_state.epoch, epoch);
+            assert_eq!(epoch_state.height, epoch_height);
+        }
+        assert!(consensus.consensus_state_at(13).is_err());
+
+        // Events should be reported at the height at which they were emitted.
+        let clone = consensus.clone();
+        assert_eq!(
+            clone
+                .consensus_events_at(11, EventKind::Staking)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(clone
+            .consensus_events_at(HEIGHT_LATEST, EventKind::Staking)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,522 @@
+//! Differential testing between runtime versions.
+//!
+//! A `Differential` executes the same transaction corpus against two runtimes starting from the
+//! same state snapshot and reports any differences in state roots, transaction results, events,
+//! emitted messages and query results. This can be used to validate that refactors (e.g. storage
+//! layer changes) are semantically neutral by comparing a runtime against a previous version of
+//! itself, for example imported as a renamed dependency:
+//!
+//! ```toml
+//! my-runtime-v1 = { package = "my-runtime", git = "...", tag = "v1.0.0" }
+//! ```
+use std::{fmt, marker::PhantomData};
+
+use oasis_core_runtime::{consensus::beacon, transaction::tags::Tags};
+
+use crate::{
+    runtime::Runtime,
+    testing::{
+        replay::StateSnapshot,
+        scenario::{BlockResult, Error, Scenario, Tx},
+    },
+};
+
+/// A block of the transaction corpus.
+#[derive(Clone, Debug, Default)]
+pub struct CorpusBlock {
+    /// Epoch in which the block should be executed. Defaults to the epoch of the previous block.
+    pub epoch: Option<beacon::EpochTime>,
+    /// Block timestamp. Defaults to the timestamp of the previous block.
+    pub timestamp: Option<u64>,
+    /// Transactions in the block.
+    pub txs: Vec<Tx>,
+}
+
+impl CorpusBlock {
+    /// Create a new corpus block with the given transactions.
+    pub fn new(txs: Vec<Tx>) -> Self {
+        Self {
+            txs,
+            ..Default::default()
+        }
+    }
+}
+
+/// What differs between the two runtimes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Subject {
+    /// Outcome of executing the block (success or the block execution error).
+    Execution,
+    /// State root after executing the block.
+    StateRoot,
+    /// Messages emitted to the consensus layer.
+    Messages,
+    /// Block-level events.
+    BlockEvents,
+    /// Result of the transaction with the given index.
+    TxResult(usize),
+    /// Events emitted by the transaction with the given index.
+    TxEvents(usize),
+    /// Priority of the transaction with the given index.
+    TxPriority(usize),
+    /// Result of the query to the given method.
+    Query(String),
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Execution => write!(f, "execution outcome"),
+            Self::StateRoot => write!(f, "state root"),
+            Self::Messages => write!(f, "emitted messages"),
+            Self::BlockEvents => write!(f, "block events"),
+            Self::TxResult(index) => write!(f, "result of transaction {index}"),
+            Self::TxEvents(index) => write!(f, "events of transaction {index}"),
+            Self::TxPriority(index) => write!(f, "priority of transaction {index}"),
+            Self::Query(method) => write!(f, "result of query {method}"),
+        }
+    }
+}
+
+/// A difference observed between the two runtimes.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    /// Index of the corpus block after which the difference was observed.
+    pub block: usize,
+    /// What differs.
+    pub subject: Subject,
+    /// Observation for the first runtime.
+    pub a: String,
+    /// Observation for the second runtime.
+    pub b: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {}: {} differs\n  a: {}\n  b: {}",
+            self.block, self.subject, self.a, self.b
+        )
+    }
+}
+
+/// Result of a differential run.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// Number of corpus blocks that have been executed.
+    pub blocks: usize,
+    /// Observed differences.
+    pub divergences: Vec<Divergence>,
+}
+
+impl Report {
+    /// Whether both runtimes behaved identically.
+    pub fn is_neutral(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "executed {} blocks, {} divergences",
+            self.blocks,
+            self.divergences.len()
+        )?;
+        for divergence in &self.divergences {
+            write!(f, "\n{divergence}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Harness running the same transaction corpus against two runtimes and diffing the outcomes.
+pub struct Differential<A: Runtime, B: Runtime> {
+    snapshot: StateSnapshot,
+    compare_state_roots: bool,
+    queries: Vec<(String, cbor::Value)>,
+    _runtimes: PhantomData<(A, B)>,
+}
+
+impl<A: Runtime, B: Runtime> Default for Differential<A, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Runtime, B: Runtime> Differential<A, B> {
+    /// Create a new harness starting both runtimes from empty state, so that each runtime applies
+    /// its own genesis state in the first block.
+    pub fn new() -> Self {
+        Self {
+            snapshot: StateSnapshot::default(),
+            compare_state_roots: true,
+            queries: vec![],
+            _runtimes: PhantomData,
+        }
+    }
+
+    /// Start both runtimes from the given state snapshot (e.g. exported from a live network or
+    /// taken from a `Scenario`).
+    pub fn with_snapshot(mut self, snapshot: StateSnapshot) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+
+    /// Do not compare state roots.
+    ///
+    /// This is useful for refactors that intentionally change how state is laid out in storage,
+    /// in which case equivalence of the observable state should be checked via queries.
+    pub fn ignore_state_roots(mut self) -> Self {
+        self.compare_state_roots = false;
+        self
+    }
+
+    /// Compare the results of querying the given method after each block.
+    pub fn query<T: cbor::Encode>(mut self, method: &str, args: T) -> Self {
+        self.queries.push((method.to_owned(), cbor::to_value(args)));
+        self
+    }
+
+    /// Execute the given corpus against both runtimes.
+    ///
+    /// Execution stops after the first block that results in any differences as subsequent blocks
+    /// would be executed on top of different states.
+    pub fn run(&self, corpus: &[CorpusBlock]) -> Report {
+        let mut a = Scenario::<A>::from_snapshot(&self.snapshot);
+        let mut b = Scenario::<B>::from_snapshot(&self.snapshot);
+
+        let mut report = Report::default();
+        for (index, block) in corpus.iter().enumerate() {
+            let mut divergences = vec![];
+            compare_blocks(
+                &mut divergences,
+                index,
+                run_block(&mut a, block),
+                run_block(&mut b, block),
+                self.compare_state_roots,
+            );
+            for (method, args) in &self.queries {
+                push_if_differs(
+                    &mut divergences,
+                    index,
+                    Subject::Query(method.clone()),
+                    format!("{:?}", a.query::<_, cbor::Value>(method, args.clone())),
+                    format!("{:?}", b.query::<_, cbor::Value>(method, args.clone())),
+                );
+            }
+
+            report.blocks += 1;
+            if !divergences.is_empty() {
+                report.divergences = divergences;
+                break;
+            }
+        }
+        report
+    }
+
+    /// Execute the given corpus against both runtimes and panic in case there are any
+    /// differences.
+    pub fn assert_neutral(&self, corpus: &[CorpusBlock]) -> Report {
+        let report = self.run(corpus);
+        if !report.is_neutral() {
+            panic!("runtimes diverged: {report}");
+        }
+        report
+    }
+}
+
+fn run_block<R: Runtime>(
+    scenario: &mut Scenario<R>,
+    block: &CorpusBlock,
+) -> Result<BlockResult, Error> {
+    let mut builder = scenario.block();
+    if let Some(epoch) = block.epoch {
+        builder = builder.epoch(epoch);
+    }
+    if let Some(timestamp) = block.timestamp {
+        builder = builder.timestamp(timestamp);
+    }
+    for tx in &block.txs {
+        builder = builder.tx(tx.clone());
+    }
+    builder.run()
+}
+
+fn compare_blocks(
+    divergences: &mut Vec<Divergence>,
+    block: usize,
+    a: Result<BlockResult, Error>,
+    b: Result<BlockResult, Error>,
+    compare_state_roots: bool,
+) {
+    let (a, b) = match (a, b) {
+        (Ok(a), Ok(b)) => (a, b),
+        (a, b) => {
+            let outcome = |result: Result<BlockResult, Error>| match result {
+                Ok(_) => "ok".to_owned(),
+                Err(err) => format!("error: {err}"),
+            };
+            push_if_differs(
+                divergences,
+                block,
+                Subject::Execution,
+                outcome(a),
+                outcome(b),
+            );
+            return;
+        }
+    };
+
+    if compare_state_roots {
+        push_if_differs(
+            divergences,
+            block,
+            Subject::StateRoot,
+            format!("{:?}", a.state_root),
+            format!("{:?}", b.state_root),
+        );
+    }
+    push_if_differs(
+        divergences,
+        block,
+        Subject::Messages,
+        format!("{:?}", a.messages),
+        format!("{:?}", b.messages),
+    );
+    push_if_differs(
+        divergences,
+        block,
+        Subject::BlockEvents,
+        format_tags(&a.tags),
+        format_tags(&b.tags),
+    );
+
+    // Both runtimes execute the same transactions so the number of results is the same.
+    for (index, (a, b)) in a.txs.into_iter().zip(b.txs).enumerate() {
+        push_if_differs(
+            divergences,
+            block,
+            Subject::TxResult(index),
+            format!("{:?}", a.result),
+            format!("{:?}", b.result),
+        );
+        push_if_differs(
+            divergences,
+            block,
+            Subject::TxEvents(index),
+            format_tags(&a.tags),
+            format_tags(&b.tags),
+        );
+        push_if_differs(
+            divergences,
+            block,
+            Subject::TxPriority(index),
+            a.priority.to_string(),
+            b.priority.to_string(),
+        );
+    }
+}
+
+fn push_if_differs(
+    divergences: &mut Vec<Divergence>,
+    block: usize,
+    subject: Subject,
+    a: String,
+    b: String,
+) {
+    if a != b {
+        divergences.push(Divergence {
+            block,
+            subject,
+            a,
+            b,
+        });
+    }
+}
+
+fn format_tags(tags: &Tags) -> String {
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|tag| format!("{}={}", hex::encode(&tag.key), hex::encode(&tag.value)))
+        .collect();
+    format!("[{}]", tags.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        module,
+        modules::{self, accounts},
+        testing::keys,
+        types::{
+            address::Address,
+            token::{BaseUnits, Denomination},
+        },
+        Version,
+    };
+
+    struct Config;
+
+    impl modules::core::Config for Config {
+        const EMIT_GAS_USED_EVENTS: bool = true;
+    }
+
+    /// Same as `Config`, so that runtimes using it are equivalent.
+    struct OtherConfig;
+
+    impl modules::core::Config for OtherConfig {
+        const EMIT_GAS_USED_EVENTS: bool = true;
+    }
+
+    fn genesis(tx_transfer: u64) -> (modules::core::Genesis, accounts::Genesis) {
+        (
+            modules::core::Genesis {
+                parameters: modules::core::Parameters {
+                    max_batch_gas: 10_000_000,
+                    max_tx_size: 32 * 1024,
+                    max_tx_signers: 1,
+                    max_multisig_signers: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                parameters: accounts::Parameters {
+                    gas_costs: accounts::GasCosts {
+                        tx_transfer,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                balances: BTreeMap::from([(
+                    keys::alice::address(),
+                    BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                )]),
+                total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                ..Default::default()
+            },
+        )
+    }
+
+    struct RuntimeA;
+
+    impl Runtime for RuntimeA {
+        const VERSION: Version = Version::new(0, 0, 1);
+
+        type Core = modules::core::Module<Config>;
+
+        type Modules = (modules::core::Module<Config>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            genesis(1_000)
+        }
+    }
+
+    struct RuntimeB;
+
+    impl Runtime for RuntimeB {
+        const VERSION: Version = Version::new(0, 0, 1);
+
+        type Core = modules::core::Module<OtherConfig>;
+
+        type Modules = (modules::core::Module<OtherConfig>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            genesis(1_000)
+        }
+    }
+
+    /// Charges more gas for transfers.
+    struct RuntimeC;
+
+    impl Runtime for RuntimeC {
+        const VERSION: Version = Version::new(0, 0, 1);
+
+        type Core = modules::core::Module<Config>;
+
+        type Modules = (modules::core::Module<Config>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            genesis(2_000)
+        }
+    }
+
+    fn transfer(to: Address, amount: u128) -> Tx {
+        Tx::new(
+            keys::alice::sigspec(),
+            "accounts.Transfer",
+            accounts::types::Transfer {
+                to,
+                amount: BaseUnits::new(amount, Denomination::NATIVE),
+            },
+        )
+    }
+
+    fn corpus() -> Vec<CorpusBlock> {
+        vec![
+            CorpusBlock::new(vec![
+                transfer(keys::bob::address(), 100),
+                transfer(keys::charlie::address(), 2_000),
+            ]),
+            CorpusBlock {
+                epoch: Some(2),
+                ..Default::default()
+            },
+            CorpusBlock::new(vec![transfer(keys::bob::address(), 50)]),
+        ]
+    }
+
+    fn balances_query() -> accounts::types::BalancesQuery {
+        accounts::types::BalancesQuery {
+            address: keys::bob::address(),
+        }
+    }
+
+    #[test]
+    fn test_differential_neutral() {
+        let report = Differential::<RuntimeA, RuntimeB>::new()
+            .query("accounts.Balances", balances_query())
+            .assert_neutral(&corpus());
+        assert_eq!(report.blocks, 3);
+    }
+
+    #[test]
+    fn test_differential_divergence() {
+        let report = Differential::<RuntimeA, RuntimeC>::new().run(&corpus());
+        assert!(!report.is_neutral());
+        assert_eq!(
+            report.blocks, 1,
+            "execution should stop at the first divergent block"
+        );
+        assert!(report.divergences.iter().all(|d| d.block == 0));
+        assert!(
+            report
+                .divergences
+                .iter()
+                .any(|d| d.subject == Subject::TxEvents(0)),
+            "gas used events should differ: {report}"
+        );
+
+        // Both runtimes run the same code when starting from the same state.
+        let mut scenario = Scenario::<RuntimeA>::new();
+        scenario.block().run().expect("block should execute");
+        Differential::<RuntimeA, RuntimeC>::new()
+            .with_snapshot(scenario.snapshot())
+            .query("accounts.Balances", balances_query())
+            .assert_neutral(&corpus());
+    }
+
+    #[test]
+    #[should_panic(expected = "runtimes diverged")]
+    fn test_differential_assert_neutral() {
+        Differential::<RuntimeA, RuntimeC>::new()
+            .ignore_state_roots()
+            .assert_neutral(&corpus());
+    }
+}
@@ -0,0 +1,286 @@
+//! Storage fault injection.
+//!
+//! Storage backends treat failures as unrecoverable and panic, so error handling paths that
+//! depend on storage failures (e.g. query abort handling) cannot be exercised with a regular
+//! store. `FaultyStore` wraps any store and fails or delays selected operations according to a
+//! set of deterministic rules.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, thread, time::Duration};
+
+use oasis_core_runtime::storage::mkvs;
+
+use crate::storage::{NestedStore, Prefix, Store};
+
+/// Storage operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    /// Fetching a key.
+    Read,
+    /// Inserting a key.
+    Write,
+    /// Removing a key.
+    Remove,
+    /// Creating an iterator.
+    Iterate,
+    /// Committing a nested store.
+    Commit,
+}
+
+/// Fault injected into a matching operation.
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Panic with the given message, as storage backends do on unrecoverable errors.
+    Fail(String),
+    /// Block for the given duration before performing the operation.
+    Delay(Duration),
+}
+
+/// Rule describing which operations should be faulty.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    operation: Operation,
+    key_prefix: Vec<u8>,
+    skip: u64,
+    times: Option<u64>,
+    fault: Fault,
+
+    matched: u64,
+}
+
+impl Rule {
+    /// Create a rule that fails all matching operations.
+    pub fn fail(operation: Operation) -> Self {
+        Self::new(
+            operation,
+            Fault::Fail(format!("injected storage fault: {operation:?}")),
+        )
+    }
+
+    /// Create a rule that delays all matching operations by the given duration.
+    pub fn delay(operation: Operation, duration: Duration) -> Self {
+        Self::new(operation, Fault::Delay(duration))
+    }
+
+    /// Create a rule that injects the given fault into all matching operations.
+    pub fn new(operation: Operation, fault: Fault) -> Self {
+        Self {
+            operation,
+            key_prefix: vec![],
+            skip: 0,
+            times: None,
+            fault,
+            matched: 0,
+        }
+    }
+
+    /// Only match operations on keys with the given prefix. Commits and iterator creation have no
+    /// key and are not matched by rules with a prefix.
+    pub fn with_key_prefix(mut self, prefix: &[u8]) -> Self {
+        self.key_prefix = prefix.to_vec();
+        self
+    }
+
+    /// Let the first `count` matching operations succeed.
+    pub fn after(mut self, count: u64) -> Self {
+        self.skip = count;
+        self
+    }
+
+    /// Only inject the fault `count` times.
+    pub fn times(mut self, count: u64) -> Self {
+        self.times = Some(count);
+        self
+    }
+
+    fn check(&mut self, operation: Operation, key: Option<&[u8]>) -> Option<&Fault> {
+        if operation != self.operation {
+            return None;
+        }
+        match key {
+            Some(key) if !key.starts_with(&self.key_prefix) => return None,
+            None if !self.key_prefix.is_empty() => return None,
+            _ => {}
+        }
+
+        self.matched += 1;
+        if self.matched <= self.skip {
+            return None;
+        }
+        if let Some(times) = self.times {
+            if self.matched - self.skip > times {
+                return None;
+            }
+        }
+        Some(&self.fault)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    rules: Vec<Rule>,
+    counts: BTreeMap<Operation, u64>,
+}
+
+/// Shared set of fault injection rules.
+///
+/// The same rules can be shared by multiple stores and can be modified after the store has been
+/// created (e.g. when the store has been moved into `CurrentStore::enter`).
+#[derive(Clone, Default)]
+pub struct Faults {
+    state: Rc<RefCell<State>>,
+}
+
+impl Faults {
+    /// Create an empty set of rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule. Rules are checked in the order in which they were added and the first rule
+    /// that injects a fault wins.
+    pub fn add(&self, rule: Rule) -> &Self {
+        self.state.borrow_mut().rules.push(rule);
+        self
+    }
+
+    /// Remove all rules.
+    pub fn clear(&self) {
+        self.state.borrow_mut().rules.clear();
+    }
+
+    /// Number of performed operations of the given kind (including faulty ones).
+    pub fn count(&self, operation: Operation) -> u64 {
+        self.state
+            .borrow()
+            .counts
+            .get(&operation)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn apply(&self, operation: Operation, key: Option<&[u8]>) {
+        let fault = {
+            let mut state = self.state.borrow_mut();
+            *state.counts.entry(operation).or_default() += 1;
+            state.rules.iter_mut().fold(None, |fault, rule| {
+                // Make sure all rules see the operation so their counters stay consistent.
+                let matched = rule.check(operation, key).cloned();
+                fault.or(matched)
+            })
+        };
+
+        match fault {
+            Some(Fault::Fail(msg)) => panic!("{}", msg),
+            Some(Fault::Delay(duration)) => thread::sleep(duration),
+            None => {}
+        }
+    }
+}
+
+/// A store that injects faults into operations of the inner store.
+pub struct FaultyStore<S: Store> {
+    inner: S,
+    faults: Faults,
+}
+
+impl<S: Store> FaultyStore<S> {
+    /// Create a new faulty store using the given rules.
+    pub fn new(inner: S, faults: Faults) -> Self {
+        Self { inner, faults }
+    }
+}
+
+impl<S: Store> Store for FaultyStore<S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.faults.apply(Operation::Read, Some(key));
+        self.inner.get(key)
+    }
+
+    fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.faults.apply(Operation::Write, Some(key));
+        self.inner.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.faults.apply(Operation::Remove, Some(key));
+        self.inner.remove(key)
+    }
+
+    fn iter(&self) -> Box<dyn mkvs::Iterator + '_> {
+        self.faults.apply(Operation::Iterate, None);
+        self.inner.iter()
+    }
+
+    fn prefetch_prefixes(&mut self, prefixes: Vec<Prefix>, limit: u16) {
+        self.inner.prefetch_prefixes(prefixes, limit)
+    }
+}
+
+impl<S: NestedStore> NestedStore for FaultyStore<S> {
+    type Inner = S::Inner;
+
+    fn commit(self) -> Self::Inner {
+        self.faults.apply(Operation::Commit, None);
+        self.inner.commit()
+    }
+
+    fn rollback(self) -> Self::Inner {
+        self.inner.rollback()
+    }
+
+    fn has_pending_updates(&self) -> bool {
+        self.inner.has_pending_updates()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        storage::{CurrentStore, OverlayStore},
+        testing::mock::empty_store,
+    };
+
+    #[test]
+    fn test_fault_rules() {
+        let faults = Faults::new();
+        faults.add(
+            Rule::fail(Operation::Read)
+                .with_key_prefix(b"fail")
+                .after(1)
+                .times(1),
+        );
+        let mut store = FaultyStore::new(empty_store(), faults.clone());
+        store.insert(b"fail", b"value");
+
+        // The first read should succeed, the second should fail and subsequent ones succeed.
+        assert_eq!(store.get(b"fail"), Some(b"value".to_vec()));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| store.get(b"fail")));
+        assert!(result.is_err(), "second read should fail");
+        assert_eq!(store.get(b"fail"), Some(b"value".to_vec()));
+
+        // Other keys should not be affected.
+        assert_eq!(store.get(b"other"), None);
+        assert_eq!(faults.count(Operation::Read), 4);
+        assert_eq!(faults.count(Operation::Write), 1);
+
+        // Delays should not affect results.
+        faults.add(Rule::delay(Operation::Write, Duration::from_millis(1)));
+        store.insert(b"delayed", b"value");
+        assert_eq!(store.get(b"delayed"), Some(b"value".to_vec()));
+    }
+
+    #[test]
+    #[should_panic(expected = "injected storage fault: Commit")]
+    fn test_fault_commit() {
+        let faults = Faults::new();
+        let mut root = empty_store();
+        let mut store = FaultyStore::new(OverlayStore::new(&mut root), faults.clone());
+
+        CurrentStore::enter(&mut store, || {
+            CurrentStore::with(|store| store.insert(b"key", b"value"));
+        });
+
+        faults.add(Rule::fail(Operation::Commit));
+        store.commit(); // Should panic.
+    }
+}
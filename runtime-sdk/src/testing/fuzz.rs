@@ -0,0 +1,247 @@
+//! Fuzzing entry points.
+//!
+//! Each entry point feeds arbitrary bytes through a stage of transaction processing on top of a
+//! freshly initialized runtime state, the same way transactions received from the mempool are
+//! processed in check mode. Malformed input is expected to be rejected with an error, any panic
+//! is a bug. All entry points are deterministic so that crashes can be reproduced from the input
+//! alone.
+use std::collections::BTreeMap;
+
+use crate::{
+    callformat,
+    context::{Mode, RuntimeBatchContext},
+    dispatcher::Dispatcher,
+    module::{self, MigrationHandler},
+    modules::{self, accounts},
+    runtime::Runtime,
+    storage::CurrentStore,
+    testing::{
+        keys,
+        mock::{self, Mock},
+    },
+    types::{callformat::CallEnvelopeX25519DeoxysII, token::Denomination, transaction},
+    Version,
+};
+
+/// Maximum transaction size used by `FuzzRuntime`.
+const MAX_TX_SIZE: u32 = 32 * 1024;
+
+/// Core module configuration used by `FuzzRuntime`.
+pub struct Config;
+
+impl modules::core::Config for Config {}
+
+/// A runtime with the core and accounts modules, suitable for fuzzing generic transaction
+/// processing.
+pub struct FuzzRuntime;
+
+impl Runtime for FuzzRuntime {
+    const VERSION: Version = Version::new(0, 0, 0);
+
+    type Core = modules::core::Module<Config>;
+
+    type Modules = (modules::core::Module<Config>, accounts::Module);
+
+    fn genesis_state() -> <Self::Modules as MigrationHandler>::Genesis {
+        (
+            modules::core::Genesis {
+                parameters: modules::core::Parameters {
+                    max_batch_gas: 10_000_000,
+                    max_tx_size: MAX_TX_SIZE,
+                    max_tx_signers: 8,
+                    max_multisig_signers: 8,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            accounts::Genesis {
+                balances: BTreeMap::from([(
+                    keys::alice::address(),
+                    BTreeMap::from([(Denomination::NATIVE, 1_000_000)]),
+                )]),
+                total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000_000)]),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+/// Run the given closure in a check context of a freshly initialized runtime.
+fn with_check_ctx<R, F>(confidential: bool, f: F)
+where
+    R: Runtime,
+    F: FnOnce(&mut RuntimeBatchContext<'_, R>),
+{
+    let mut mock = Mock::default();
+    CurrentStore::enter(mock::empty_store(), || {
+        let mut ctx = mock.create_ctx_for_runtime::<R>(Mode::CheckTx, confidential);
+        R::migrate(&mut ctx);
+        f(&mut ctx);
+    });
+}
+
+/// Decode the given bytes as a raw transaction envelope, including decompression and signature
+/// verification.
+pub fn decode_tx<R: Runtime>(data: &[u8]) {
+    with_check_ctx::<R, _>(false, |ctx| {
+        let _ = Dispatcher::<R>::decode_tx(ctx, data);
+    });
+}
+
+/// Decode the given bytes as a call and process it according to its call format. In case the
+/// call could be decoded, a result is encoded using the resulting call format metadata.
+pub fn decode_call<R: Runtime>(data: &[u8]) {
+    let call: transaction::Call = match cbor::from_slice(data) {
+        Ok(call) => call,
+        Err(_) => return,
+    };
+
+    with_check_ctx::<R, _>(true, |ctx| {
+        if let Ok(Some((_, metadata))) = callformat::decode_call(ctx, call, 0) {
+            let _ = callformat::encode_result(
+                ctx,
+                module::CallResult::Ok(cbor::Value::Simple(cbor::SimpleValue::NullValue)),
+                metadata,
+            );
+        }
+    });
+}
+
+/// Dispatch the given bytes in check mode after decoding them as an unsigned transaction. This
+/// skips signature verification so that dispatch can be reached without valid signatures.
+pub fn dispatch_tx<R: Runtime>(data: &[u8]) {
+    let tx: transaction::Transaction = match cbor::from_slice(data) {
+        Ok(tx) => tx,
+        Err(_) => return,
+    };
+
+    with_check_ctx::<R, _>(false, |ctx| {
+        let _ = Dispatcher::<R>::check_tx(ctx, data.len().try_into().unwrap(), tx);
+    });
+}
+
+/// Process the given bytes exactly as a transaction received from the mempool: decode the raw
+/// envelope and, in case it is valid, dispatch it in check mode.
+pub fn check_tx<R: Runtime>(data: &[u8]) {
+    with_check_ctx::<R, _>(false, |ctx| {
+        if let Ok(tx) = Dispatcher::<R>::decode_tx(ctx, data) {
+            let size = transaction::envelope_size(data).try_into().unwrap();
+            let _ = Dispatcher::<R>::check_tx(ctx, size, tx);
+        }
+    });
+}
+
+/// Seed inputs for the `decode_call` entry point.
+pub fn corpus_calls() -> Vec<Vec<u8>> {
+    let formats = [
+        transaction::CallFormat::Plain,
+        transaction::CallFormat::EncryptedX25519DeoxysII,
+        transaction::CallFormat::EncryptedX25519XChaCha20Poly1305,
+    ];
+    let bodies = [
+        cbor::Value::Simple(cbor::SimpleValue::NullValue),
+        cbor::to_value(CallEnvelopeX25519DeoxysII::default()),
+    ];
+
+    formats
+        .iter()
+        .flat_map(|format| {
+            bodies.iter().map(move |body| {
+                cbor::to_vec(transaction::Call {
+                    format: *format,
+                    method: "accounts.Transfer".to_owned(),
+                    body: body.clone(),
+                    ..Default::default()
+                })
+            })
+        })
+        .collect()
+}
+
+/// Seed inputs for the `dispatch_tx` entry point.
+pub fn corpus_txs() -> Vec<Vec<u8>> {
+    let calls = [
+        (
+            "accounts.Transfer",
+            cbor::to_value(accounts::types::Transfer {
+                to: keys::bob::address(),
+                amount: Default::default(),
+            }),
+        ),
+        (
+            "core.CallDataPublicKey",
+            cbor::Value::Simple(cbor::SimpleValue::NullValue),
+        ),
+        (
+            "unknown.Method",
+            cbor::Value::Simple(cbor::SimpleValue::NullValue),
+        ),
+    ];
+
+    calls
+        .into_iter()
+        .map(|(method, body)| {
+            let mut tx = mock::transaction();
+            tx.call.method = method.to_owned();
+            tx.call.body = body;
+            tx.auth_info.signer_info = vec![transaction::SignerInfo::new_sigspec(
+                keys::alice::sigspec(),
+                0,
+            )];
+            tx.auth_info.fee.consensus_messages = 0;
+            cbor::to_vec(tx)
+        })
+        .collect()
+}
+
+/// Seed inputs for the `decode_tx` and `check_tx` entry points. Signatures are not valid, but
+/// the envelopes are well-formed in both plain and compressed form.
+pub fn corpus_envelopes() -> Vec<Vec<u8>> {
+    corpus_txs()
+        .into_iter()
+        .flat_map(|tx| {
+            let envelope = cbor::to_vec(transaction::UnverifiedTransaction(
+                tx,
+                vec![transaction::AuthProof::Signature(vec![0; 64].into())],
+            ));
+            let compressed = transaction::compress_envelope(&envelope);
+            [envelope, compressed]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_corpus() {
+        // All entry points should handle their own corpus as well as garbage without panicking.
+        let garbage: Vec<Vec<u8>> = vec![vec![], vec![0xff; 16], vec![0x01, 0xff, 0xff]];
+
+        for data in corpus_envelopes().iter().chain(&garbage) {
+            decode_tx::<FuzzRuntime>(data);
+            check_tx::<FuzzRuntime>(data);
+        }
+        for data in corpus_calls().iter().chain(&garbage) {
+            decode_call::<FuzzRuntime>(data);
+        }
+        for data in corpus_txs().iter().chain(&garbage) {
+            dispatch_tx::<FuzzRuntime>(data);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_dispatch_tx() {
+        // The unsigned transfer from the corpus should pass check mode dispatch.
+        let tx: transaction::Transaction = cbor::from_slice(&corpus_txs()[0]).unwrap();
+        with_check_ctx::<FuzzRuntime, _>(false, |ctx| {
+            let result = Dispatcher::<FuzzRuntime>::check_tx(ctx, 1024, tx).unwrap();
+            assert_eq!(
+                result.error.code, 0,
+                "check should pass: {:?}",
+                result.error
+            );
+        });
+    }
+}
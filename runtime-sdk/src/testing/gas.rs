@@ -0,0 +1,479 @@
+//! Gas cost regression benchmarks.
+//!
+//! Workloads execute representative transactions through a full in-process runtime (see
+//! `testing::scenario`) and record the gas used and the execution time of each transaction. The
+//! resulting `Report` uses a simple tab-separated text format so that it can be kept as a baseline
+//! and compared against later runs using `compare`.
+use std::{collections::BTreeMap, fmt, str::FromStr, time::Instant};
+
+use thiserror::Error;
+
+use crate::{
+    runtime::Runtime,
+    testing::scenario::{Scenario, Tx},
+};
+
+/// Header line of a report.
+const REPORT_HEADER: &str = "# workload\tgas\ttime_ns\titerations";
+
+/// Error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("malformed report line {0}: {1}")]
+    MalformedLine(usize, String),
+
+    #[error("duplicate workload: {0}")]
+    DuplicateWorkload(String),
+}
+
+/// Gas and time measurement of a workload.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Measurement {
+    /// Mean amount of gas used by a single transaction.
+    pub gas: u64,
+    /// Mean time in nanoseconds needed to execute a single transaction.
+    pub time_ns: u64,
+    /// Number of measured transactions.
+    pub iterations: u64,
+}
+
+/// Measurements of a set of workloads.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Measurements by workload name.
+    pub workloads: BTreeMap<String, Measurement>,
+}
+
+impl Report {
+    /// Add the measurements from another report, replacing any existing workloads.
+    pub fn merge(&mut self, other: Report) {
+        self.workloads.extend(other.workloads);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{REPORT_HEADER}")?;
+        for (name, m) in &self.workloads {
+            writeln!(f, "{}\t{}\t{}\t{}", name, m.gas, m.time_ns, m.iterations)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Report {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut report = Report::default();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = || Error::MalformedLine(index + 1, line.to_owned());
+            let fields: Vec<&str> = line.split('\t').collect();
+            let (name, gas, time_ns, iterations) = match fields[..] {
+                [name, gas, time_ns, iterations] => (name, gas, time_ns, iterations),
+                _ => return Err(malformed()),
+            };
+            let measurement = Measurement {
+                gas: gas.parse().map_err(|_| malformed())?,
+                time_ns: time_ns.parse().map_err(|_| malformed())?,
+                iterations: iterations.parse().map_err(|_| malformed())?,
+            };
+            if report
+                .workloads
+                .insert(name.to_owned(), measurement)
+                .is_some()
+            {
+                return Err(Error::DuplicateWorkload(name.to_owned()));
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Allowed drift between a baseline and a current report.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Thresholds {
+    /// Maximum allowed gas change in percent. The default of zero means that any change in gas
+    /// costs is reported.
+    pub gas_percent: u64,
+    /// Maximum allowed time change in percent. Timings are only compared when set as they depend
+    /// on the machine running the benchmarks.
+    pub time_percent: Option<u64>,
+}
+
+/// Difference between a baseline and a current report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Drift {
+    /// Workload is present in the baseline, but has not been measured.
+    Missing(String),
+    /// Workload has been measured, but is not present in the baseline.
+    Added(String),
+    /// Gas used by the workload changed beyond the threshold.
+    Gas {
+        workload: String,
+        baseline: u64,
+        current: u64,
+    },
+    /// Execution time of the workload changed beyond the threshold.
+    Time {
+        workload: String,
+        baseline: u64,
+        current: u64,
+    },
+}
+
+impl Drift {
+    /// Whether the drift should fail the comparison.
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Drift::Added(_))
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Missing(workload) => write!(f, "{workload}: missing"),
+            Drift::Added(workload) => write!(f, "{workload}: added"),
+            Drift::Gas {
+                workload,
+                baseline,
+                current,
+            } => write!(f, "{workload}: gas changed from {baseline} to {current}"),
+            Drift::Time {
+                workload,
+                baseline,
+                current,
+            } => write!(
+                f,
+                "{workload}: time changed from {baseline}ns to {current}ns"
+            ),
+        }
+    }
+}
+
+/// Result of comparing a current report against a baseline.
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    /// All differences found, in workload order.
+    pub drifts: Vec<Drift>,
+}
+
+impl Comparison {
+    /// Whether the current report is within the thresholds.
+    pub fn is_ok(&self) -> bool {
+        !self.drifts.iter().any(Drift::is_failure)
+    }
+}
+
+fn exceeds(baseline: u64, current: u64, percent: u64) -> bool {
+    u128::from(baseline.abs_diff(current)) * 100 > u128::from(baseline) * u128::from(percent)
+}
+
+/// Compare a current report against a baseline.
+pub fn compare(baseline: &Report, current: &Report, thresholds: Thresholds) -> Comparison {
+    let mut drifts = vec![];
+    for (workload, base) in &baseline.workloads {
+        let cur = match current.workloads.get(workload) {
+            Some(cur) => cur,
+            None => {
+                drifts.push(Drift::Missing(workload.clone()));
+                continue;
+            }
+        };
+
+        if exceeds(base.gas, cur.gas, thresholds.gas_percent) {
+            drifts.push(Drift::Gas {
+                workload: workload.clone(),
+                baseline: base.gas,
+                current: cur.gas,
+            });
+        }
+        if let Some(percent) = thresholds.time_percent {
+            if exceeds(base.time_ns, cur.time_ns, percent) {
+                drifts.push(Drift::Time {
+                    workload: workload.clone(),
+                    baseline: base.time_ns,
+                    current: cur.time_ns,
+                });
+            }
+        }
+    }
+    for workload in current.workloads.keys() {
+        if !baseline.workloads.contains_key(workload) {
+            drifts.push(Drift::Added(workload.clone()));
+        }
+    }
+
+    Comparison { drifts }
+}
+
+/// Benchmark runner measuring workloads on top of a scenario.
+///
+/// The runtime must emit gas used events (see `modules::core::Config::EMIT_GAS_USED_EVENTS`).
+pub struct Bench<R: Runtime> {
+    scenario: Scenario<R>,
+    iterations: u64,
+    report: Report,
+}
+
+impl<R: Runtime> Bench<R> {
+    /// Create a new benchmark runner executing each workload the given number of times.
+    pub fn new(iterations: u64) -> Self {
+        assert!(iterations > 0, "at least one iteration is required");
+
+        Self {
+            scenario: Scenario::new(),
+            iterations,
+            report: Report::default(),
+        }
+    }
+
+    /// Scenario used to execute the workloads, e.g. for preparing state shared by workloads.
+    pub fn scenario(&mut self) -> &mut Scenario<R> {
+        &mut self.scenario
+    }
+
+    /// Measure a workload consisting of the transactions returned by the given closure for each
+    /// iteration. Each transaction is executed in its own block and must succeed.
+    ///
+    /// The measured time includes the (constant) overhead of executing an empty block.
+    pub fn measure<F>(&mut self, name: &str, mut tx: F) -> Measurement
+    where
+        F: FnMut(u64) -> Tx,
+    {
+        let mut gas: u128 = 0;
+        let mut time_ns: u128 = 0;
+        for iteration in 0..self.iterations {
+            let block = self.scenario.block().tx(tx(iteration));
+
+            let start = Instant::now();
+            let result = block
+                .run()
+                .unwrap_or_else(|err| panic!("{name}: block execution failed: {err}"));
+            time_ns += start.elapsed().as_nanos();
+
+            let tx = &result.txs[0];
+            assert!(
+                tx.is_success(),
+                "{name}: transaction failed: {:?}",
+                tx.result
+            );
+            gas += u128::from(tx.gas_used().expect("runtime should emit gas used events"));
+        }
+
+        let iterations = u128::from(self.iterations);
+        let measurement = Measurement {
+            gas: (gas / iterations).try_into().unwrap(),
+            time_ns: (time_ns / iterations).try_into().unwrap_or(u64::MAX),
+            iterations: self.iterations,
+        };
+        self.report.workloads.insert(name.to_owned(), measurement);
+        measurement
+    }
+
+    /// Finish benchmarking and return the report of all measured workloads.
+    pub fn finish(self) -> Report {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        module,
+        modules::{self, accounts},
+        testing::keys,
+        types::token::{self, Denomination},
+        Version,
+    };
+
+    struct Config;
+
+    impl modules::core::Config for Config {
+        const EMIT_GAS_USED_EVENTS: bool = true;
+    }
+
+    struct BenchRuntime;
+
+    impl Runtime for BenchRuntime {
+        const VERSION: Version = Version::new(0, 0, 0);
+
+        type Core = modules::core::Module<Config>;
+
+        type Modules = (modules::core::Module<Config>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            (
+                modules::core::Genesis {
+                    parameters: modules::core::Parameters {
+                        max_batch_gas: 10_000_000,
+                        max_tx_size: 32 * 1024,
+                        max_tx_signers: 1,
+                        max_multisig_signers: 1,
+                        gas_costs: modules::core::GasCosts {
+                            tx_byte: 1,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                accounts::Genesis {
+                    parameters: accounts::Parameters {
+                        gas_costs: accounts::GasCosts {
+                            tx_transfer: 1_000,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    balances: BTreeMap::from([(
+                        keys::alice::address(),
+                        BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                    )]),
+                    total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                    ..Default::default()
+                },
+            )
+        }
+    }
+
+    fn measurement(gas: u64, time_ns: u64) -> Measurement {
+        Measurement {
+            gas,
+            time_ns,
+            iterations: 10,
+        }
+    }
+
+    #[test]
+    fn test_report_format() {
+        let report = Report {
+            workloads: BTreeMap::from([
+                ("accounts.Transfer".to_owned(), measurement(1_200, 5_000)),
+                ("evm.Call".to_owned(), measurement(21_000, 15_000)),
+            ]),
+        };
+        let encoded = report.to_string();
+        assert!(encoded.starts_with(REPORT_HEADER));
+        assert_eq!(encoded.parse::<Report>().unwrap(), report);
+
+        assert!(matches!(
+            "foo\t1\t2".parse::<Report>(),
+            Err(Error::MalformedLine(1, _))
+        ));
+        assert!(matches!(
+            "# comment\nfoo\tbar\t2\t3".parse::<Report>(),
+            Err(Error::MalformedLine(2, _))
+        ));
+        assert!(matches!(
+            "foo\t1\t2\t3\nfoo\t1\t2\t3".parse::<Report>(),
+            Err(Error::DuplicateWorkload(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = Report {
+            workloads: BTreeMap::from([
+                ("a".to_owned(), measurement(1_000, 1_000)),
+                ("b".to_owned(), measurement(1_000, 1_000)),
+                ("c".to_owned(), measurement(1_000, 1_000)),
+            ]),
+        };
+        let current = Report {
+            workloads: BTreeMap::from([
+                ("a".to_owned(), measurement(1_000, 5_000)),
+                ("b".to_owned(), measurement(1_050, 1_000)),
+                ("d".to_owned(), measurement(1_000, 1_000)),
+            ]),
+        };
+
+        // By default any gas change is a failure and timings are ignored.
+        let result = compare(&baseline, &current, Default::default());
+        assert!(!result.is_ok());
+        assert_eq!(
+            result.drifts,
+            vec![
+                Drift::Gas {
+                    workload: "b".to_owned(),
+                    baseline: 1_000,
+                    current: 1_050,
+                },
+                Drift::Missing("c".to_owned()),
+                Drift::Added("d".to_owned()),
+            ]
+        );
+
+        let thresholds = Thresholds {
+            gas_percent: 5,
+            time_percent: Some(100),
+        };
+        let result = compare(&baseline, &current, thresholds);
+        assert_eq!(
+            result.drifts,
+            vec![
+                Drift::Time {
+                    workload: "a".to_owned(),
+                    baseline: 1_000,
+                    current: 5_000,
+                },
+                Drift::Missing("c".to_owned()),
+                Drift::Added("d".to_owned()),
+            ]
+        );
+
+        // Added workloads alone are not a failure.
+        let result = compare(&baseline, &baseline, Default::default());
+        assert!(result.is_ok());
+        assert!(result.drifts.is_empty());
+        let result = compare(&Report::default(), &baseline, Default::default());
+        assert!(result.is_ok());
+        assert_eq!(result.drifts.len(), 3);
+    }
+
+    #[test]
+    fn test_bench() {
+        let mut bench = Bench::<BenchRuntime>::new(3);
+        let measurement = bench.measure("accounts.Transfer", |_| {
+            Tx::new(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                accounts::types::Transfer {
+                    to: keys::bob::address(),
+                    amount: token::BaseUnits::new(10, Denomination::NATIVE),
+                },
+            )
+        });
+        assert_eq!(measurement.iterations, 3);
+        assert!(
+            measurement.gas > 1_000,
+            "gas should include transaction size"
+        );
+
+        let report = bench.finish();
+        assert_eq!(
+            report.workloads.get("accounts.Transfer"),
+            Some(&measurement)
+        );
+
+        // Gas use must be deterministic.
+        let mut bench = Bench::<BenchRuntime>::new(3);
+        let other = bench.measure("accounts.Transfer", |_| {
+            Tx::new(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                accounts::types::Transfer {
+                    to: keys::bob::address(),
+                    amount: token::BaseUnits::new(10, Denomination::NATIVE),
+                },
+            )
+        });
+        assert_eq!(other.gas, measurement.gas);
+    }
+}
@@ -1,16 +1,33 @@
 //! Mock key manager implementation.
 
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 pub use crate::keymanager::{
     KeyManagerError, KeyPair, KeyPairId, SignedPublicKey, StateKey, TrustedPolicySigners,
 };
 use crate::{core::consensus::beacon::EpochTime, keymanager::KeyManager};
 
+/// Simulated status of the mock key manager.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MockKeyManagerStatus {
+    /// Key manager is available and serves key requests.
+    #[default]
+    Ready,
+    /// Key manager has not been initialized yet.
+    NotInitialized,
+    /// Key manager is unreachable.
+    Unavailable,
+}
+
 #[derive(Default)]
 pub struct MockKeyManagerClient {
     keys: Mutex<HashMap<KeyPairId, KeyPair>>,
     ephemeral_keys: Mutex<HashMap<KeyPairId, KeyPair>>,
+    /// Status shared between all clones so that it can be changed while a client is in use.
+    status: Arc<Mutex<MockKeyManagerStatus>>,
 }
 
 impl Clone for MockKeyManagerClient {
@@ -20,6 +37,7 @@ impl Clone for MockKeyManagerClient {
         Self {
             keys: Mutex::new(keys.clone()),
             ephemeral_keys: Mutex::new(ephemeral_keys.clone()),
+            status: self.status.clone(),
         }
     }
 }
@@ -28,6 +46,27 @@ impl MockKeyManagerClient {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Current simulated key manager status.
+    pub fn status(&self) -> MockKeyManagerStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Change the simulated key manager status. The change affects this client and all of its
+    /// clones, including ones already handed out to dispatch contexts.
+    pub fn set_status(&self, status: MockKeyManagerStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    fn check_status(&self) -> Result<(), KeyManagerError> {
+        match self.status() {
+            MockKeyManagerStatus::Ready => Ok(()),
+            MockKeyManagerStatus::NotInitialized => Err(KeyManagerError::NotInitialized),
+            MockKeyManagerStatus::Unavailable => Err(KeyManagerError::Other(anyhow::anyhow!(
+                "mock key manager is unavailable"
+            ))),
+        }
+    }
 }
 
 impl KeyManager for MockKeyManagerClient {
@@ -36,6 +75,8 @@ impl KeyManager for MockKeyManagerClient {
     }
 
     fn get_or_create_keys(&self, key_pair_id: KeyPairId) -> Result<KeyPair, KeyManagerError> {
+        self.check_status()?;
+
         let mut keys = self.keys.lock().unwrap();
         Ok(keys
             .entry(key_pair_id)
@@ -56,6 +97,8 @@ impl KeyManager for MockKeyManagerClient {
         key_pair_id: KeyPairId,
         _epoch: EpochTime,
     ) -> Result<KeyPair, KeyManagerError> {
+        self.check_status()?;
+
         let mut ephemeral_keys = self.ephemeral_keys.lock().unwrap();
         Ok(ephemeral_keys
             .entry(key_pair_id)
@@ -11,7 +11,10 @@ macro_rules! test_key_ed25519 {
         pub mod $name {
             use crate::{
                 crypto::signature::{ed25519, PublicKey},
-                types::address::{Address, SignatureAddressSpec},
+                types::{
+                    address::{Address, SignatureAddressSpec},
+                    transaction::SignerInfo,
+                },
             };
 
             #[doc = " Test public key "]
@@ -41,6 +44,13 @@ macro_rules! test_key_ed25519 {
             pub fn address() -> Address {
                 Address::from_sigspec(&sigspec())
             }
+
+            #[doc = " Test transaction signer information (with a zero nonce) "]
+            #[doc=$doc]
+            #[doc = "."]
+            pub fn signer_info() -> SignerInfo {
+                SignerInfo::new_sigspec(sigspec(), 0)
+            }
         }
     };
 }
@@ -54,7 +64,10 @@ macro_rules! test_key_secp256k1 {
         pub mod $name {
             use crate::{
                 crypto::signature::{secp256k1, PublicKey},
-                types::address::{Address, SignatureAddressSpec},
+                types::{
+                    address::{Address, SignatureAddressSpec},
+                    transaction::SignerInfo,
+                },
             };
 
             #[doc = " Test public key "]
@@ -84,6 +97,13 @@ macro_rules! test_key_secp256k1 {
             pub fn address() -> Address {
                 Address::from_sigspec(&sigspec())
             }
+
+            #[doc = " Test transaction signer information (with a zero nonce) "]
+            #[doc=$doc]
+            #[doc = "."]
+            pub fn signer_info() -> SignerInfo {
+                SignerInfo::new_sigspec(sigspec(), 0)
+            }
         }
     };
 }
@@ -97,7 +117,10 @@ macro_rules! test_key_sr25519 {
         pub mod $name {
             use crate::{
                 crypto::signature::{sr25519, PublicKey},
-                types::address::{Address, SignatureAddressSpec},
+                types::{
+                    address::{Address, SignatureAddressSpec},
+                    transaction::SignerInfo,
+                },
             };
 
             #[doc = " Test public key "]
@@ -127,6 +150,13 @@ macro_rules! test_key_sr25519 {
             pub fn address() -> Address {
                 Address::from_sigspec(&sigspec())
             }
+
+            #[doc = " Test transaction signer information (with a zero nonce) "]
+            #[doc=$doc]
+            #[doc = "."]
+            pub fn signer_info() -> SignerInfo {
+                SignerInfo::new_sigspec(sigspec(), 0)
+            }
         }
     };
 }
@@ -1,5 +1,5 @@
 //! Mock dispatch context for use in tests.
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, rc::Rc};
 
 use oasis_core_runtime::{
     common::{namespace::Namespace, version::Version},
@@ -10,18 +10,26 @@ use oasis_core_runtime::{
 };
 
 use crate::{
-    context::{BatchContext, Mode, RuntimeBatchContext},
+    context::{self, BatchContext, Context, Mode, RuntimeBatchContext},
     crypto::random::RootRng,
     dispatcher,
     error::RuntimeError,
     history,
     keymanager::KeyManager,
-    module::MigrationHandler,
-    modules,
+    module::{MigrationHandler, Module, StateExporter},
+    modules::{self, accounts::API as _},
     runtime::Runtime,
     storage::{CurrentStore, MKVSStore},
-    testing::{configmap, keymanager::MockKeyManagerClient},
-    types::{address::SignatureAddressSpec, transaction},
+    testing::{
+        configmap, consensus::SimulatedConsensus, keymanager::MockKeyManagerClient,
+        time::MockTimeSource,
+    },
+    time::BlockTime,
+    types::{
+        address::{Address, SignatureAddressSpec},
+        message::MessageEventHookInvocation,
+        token, transaction,
+    },
 };
 
 pub struct Config;
@@ -70,16 +78,36 @@ pub struct Mock {
     pub rng: RootRng,
 
     pub max_messages: u32,
+
+    /// Whether contexts created via `create_ctx` and `create_check_ctx` have access to a mock key
+    /// manager.
+    pub confidential: bool,
+    /// Key manager client handed out to confidential contexts. Its status can be changed via
+    /// `MockKeyManagerClient::set_status` to simulate key manager failures.
+    pub key_manager: MockKeyManagerClient,
+
+    /// Simulated consensus layer, if any. When set, the epoch and consensus state of created
+    /// contexts follow the simulation.
+    pub consensus: Option<SimulatedConsensus>,
+
+    /// Time source for created contexts, if any. When set, it takes precedence over the round and
+    /// timestamp of the runtime header and over the epoch.
+    pub time: Option<MockTimeSource>,
 }
 
 impl Mock {
+    /// Create a builder for configuring a mock dispatch context factory.
+    pub fn builder() -> MockBuilder {
+        MockBuilder::default()
+    }
+
     /// Create a new mock dispatch context.
     pub fn create_ctx(&mut self) -> RuntimeBatchContext<'_, EmptyRuntime> {
-        self.create_ctx_for_runtime(Mode::ExecuteTx, false)
+        self.create_ctx_for_runtime(Mode::ExecuteTx, self.confidential)
     }
 
     pub fn create_check_ctx(&mut self) -> RuntimeBatchContext<'_, EmptyRuntime> {
-        self.create_ctx_for_runtime(Mode::CheckTx, false)
+        self.create_ctx_for_runtime(Mode::CheckTx, self.confidential)
     }
 
     /// Create a new mock dispatch context.
@@ -88,11 +116,16 @@ impl Mock {
         mode: Mode,
         confidential: bool,
     ) -> RuntimeBatchContext<'_, R> {
-        RuntimeBatchContext::new(
+        if let Some(consensus) = &self.consensus {
+            self.epoch = consensus.epoch();
+            self.consensus_state = consensus.state();
+        }
+
+        let ctx = RuntimeBatchContext::new(
             mode,
             &self.host_info,
             if confidential {
-                Some(Box::new(MockKeyManagerClient::new()) as Box<dyn KeyManager>)
+                Some(Box::new(self.key_manager.clone()) as Box<dyn KeyManager>)
             } else {
                 None
             },
@@ -103,7 +136,62 @@ impl Mock {
             self.epoch,
             &self.rng,
             self.max_messages,
-        )
+        );
+        match &self.time {
+            Some(time) => ctx.with_time_source(Rc::new(time.clone())),
+            None => ctx,
+        }
+    }
+
+    /// Control time of created contexts via a mock time source starting at the current runtime
+    /// header and epoch and return a handle to it.
+    pub fn mock_time(&mut self) -> MockTimeSource {
+        let time = MockTimeSource::new(BlockTime::new(&self.runtime_header, self.epoch));
+        self.time = Some(time.clone());
+        time
+    }
+
+    /// Attach a simulated consensus layer starting at the current epoch and return a handle to it.
+    ///
+    /// The simulation serves historic consensus state and events to created contexts, so modules
+    /// that look up epoch transitions or consensus events see a consistent history.
+    pub fn simulate_consensus(&mut self) -> SimulatedConsensus {
+        let consensus = SimulatedConsensus::new(self.epoch);
+        self.consensus = Some(consensus.clone());
+        self.history = Box::new(consensus.clone());
+        self.consensus_state = consensus.state();
+        consensus
+    }
+
+    /// Simulate an epoch transition and return the new epoch.
+    ///
+    /// In case a simulated consensus layer is attached, the transition is also recorded there.
+    pub fn transition_epoch(&mut self) -> beacon::EpochTime {
+        match &self.consensus {
+            Some(consensus) => {
+                self.epoch = consensus.transition_epoch();
+                self.consensus_state = consensus.state();
+            }
+            None => self.epoch += 1,
+        }
+        self.epoch
+    }
+
+    /// Simulate the consensus layer reporting results of messages emitted in the previous round.
+    ///
+    /// The given handlers are stored as if the messages were emitted by the previous round and
+    /// the results are dispatched to them in a fresh context, as at the start of a block.
+    pub fn deliver_message_results<R: Runtime>(
+        &mut self,
+        handlers: Vec<MessageEventHookInvocation>,
+        results: Vec<roothash::MessageEvent>,
+    ) -> Result<context::State, modules::core::Error> {
+        dispatcher::Dispatcher::<R>::save_emitted_message_handlers(handlers);
+        self.runtime_round_results.messages = results;
+
+        let mut ctx = self.create_ctx_for_runtime::<R>(Mode::ExecuteTx, self.confidential);
+        dispatcher::Dispatcher::<R>::handle_last_round_messages(&mut ctx)?;
+        Ok(ctx.commit())
     }
 
     /// Create an instance with the given local configuration.
@@ -131,7 +219,129 @@ impl Mock {
             epoch: 1,
             rng: RootRng::new(),
             max_messages: 32,
+            confidential: false,
+            key_manager: MockKeyManagerClient::new(),
+            consensus: None,
+            time: None,
+        }
+    }
+}
+
+type SetupFn = Box<dyn FnOnce(&mut Mock)>;
+
+/// Builder for a mock dispatch context factory.
+///
+/// Besides configuring the mock environment, the builder can pre-populate state (e.g. account
+/// balances and module parameters) in the current store when the mock is built.
+#[derive(Default)]
+pub struct MockBuilder {
+    local_config: Option<BTreeMap<String, cbor::Value>>,
+    epoch: Option<beacon::EpochTime>,
+    round: Option<u64>,
+    block_time: Option<u64>,
+    max_messages: Option<u32>,
+    confidential: bool,
+    setup: Vec<SetupFn>,
+}
+
+impl MockBuilder {
+    /// Use the given local runtime configuration instead of the default test configuration.
+    pub fn runtime_config(mut self, local_config: BTreeMap<String, cbor::Value>) -> Self {
+        self.local_config = Some(local_config);
+        self
+    }
+
+    /// Set the current epoch.
+    pub fn epoch(mut self, epoch: beacon::EpochTime) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Set the current round.
+    pub fn round(mut self, round: u64) -> Self {
+        self.round = Some(round);
+        self
+    }
+
+    /// Set the current block timestamp (in seconds since the UNIX epoch).
+    pub fn block_time(mut self, timestamp: u64) -> Self {
+        self.block_time = Some(timestamp);
+        self
+    }
+
+    /// Set the maximum number of emitted consensus messages.
+    pub fn max_messages(mut self, max_messages: u32) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Provide a mock key manager to created contexts.
+    pub fn confidential(mut self, confidential: bool) -> Self {
+        self.confidential = confidential;
+        self
+    }
+
+    /// Mint the given balances in the accounts module.
+    pub fn balances(
+        mut self,
+        balances: BTreeMap<Address, BTreeMap<token::Denomination, u128>>,
+    ) -> Self {
+        self.setup.push(Box::new(move |mock| {
+            let mut ctx = mock.create_ctx();
+            for (address, denominations) in balances {
+                for (denomination, amount) in denominations {
+                    modules::accounts::Module::mint(
+                        &mut ctx,
+                        address,
+                        &token::BaseUnits::new(amount, denomination),
+                    )
+                    .expect("minting balances should succeed");
+                }
+            }
+        }));
+        self
+    }
+
+    /// Set the parameters of the given module.
+    pub fn parameters<M: Module + 'static>(mut self, params: M::Parameters) -> Self
+    where
+        M::Parameters: 'static,
+    {
+        self.setup.push(Box::new(move |_| M::set_params(params)));
+        self
+    }
+
+    /// Run the given closure once the mock has been built, e.g. to populate custom state.
+    pub fn setup(mut self, f: impl FnOnce(&mut Mock) + 'static) -> Self {
+        self.setup.push(Box::new(f));
+        self
+    }
+
+    /// Build the mock dispatch context factory, performing any configured state setup in the
+    /// current store.
+    pub fn build(self) -> Mock {
+        let mut mock = match self.local_config {
+            Some(local_config) => Mock::with_local_config(local_config),
+            None => Mock::default(),
+        };
+        if let Some(epoch) = self.epoch {
+            mock.epoch = epoch;
+        }
+        if let Some(round) = self.round {
+            mock.runtime_header.round = round;
+        }
+        if let Some(timestamp) = self.block_time {
+            mock.runtime_header.timestamp = timestamp;
+        }
+        if let Some(max_messages) = self.max_messages {
+            mock.max_messages = max_messages;
+        }
+        mock.confidential = self.confidential;
+
+        for f in self.setup {
+            f(&mut mock);
         }
+        mock
     }
 }
 
@@ -158,6 +368,29 @@ pub fn empty_store() -> MKVSStore<mkvs::OverlayTree<mkvs::Tree>> {
     MKVSStore::new(root)
 }
 
+/// Export the state of the given module from the current store, initialize an empty store from
+/// the exported genesis and check that exporting it again results in the same state.
+///
+/// Returns the exported genesis.
+pub fn assert_export_round_trip<M>() -> M::Genesis
+where
+    M: StateExporter,
+    M::Genesis: Clone + cbor::Encode,
+{
+    let genesis = M::export_state();
+    let exported = CurrentStore::enter(empty_store(), || {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx();
+        M::init_or_migrate(&mut ctx, &mut Default::default(), genesis.clone());
+        M::export_state()
+    });
+    assert!(
+        cbor::to_vec(exported) == cbor::to_vec(genesis.clone()),
+        "exported state should round-trip through genesis"
+    );
+    genesis
+}
+
 /// Create a new mock transaction.
 pub fn transaction() -> transaction::Transaction {
     transaction::Transaction {
@@ -180,6 +413,41 @@ pub fn transaction() -> transaction::Transaction {
     }
 }
 
+/// Create a new mock transaction calling the given method and signed by the given signer.
+pub fn transaction_from(
+    signer: &transaction::SignerInfo,
+    method: &str,
+) -> transaction::Transaction {
+    let mut tx = transaction();
+    tx.call.method = method.to_owned();
+    tx.auth_info.signer_info = vec![signer.clone()];
+    tx
+}
+
+/// Initialize the accounts module with the given balances in the native denomination.
+pub fn init_balances<C: Context>(ctx: &mut C, balances: &[(Address, u128)]) {
+    let mut genesis = modules::accounts::Genesis::default();
+    for (address, amount) in balances {
+        genesis
+            .balances
+            .entry(*address)
+            .or_default()
+            .insert(token::Denomination::NATIVE, *amount);
+        let total = genesis
+            .total_supplies
+            .entry(token::Denomination::NATIVE)
+            .or_default();
+        *total += amount;
+    }
+    modules::accounts::Module::init_or_migrate(ctx, &mut Default::default(), genesis);
+}
+
+/// Return the balance of the given account in the native denomination.
+pub fn balance(address: Address) -> u128 {
+    modules::accounts::Module::get_balance(address, token::Denomination::NATIVE)
+        .expect("get_balance should succeed")
+}
+
 /// Options that can be used during mock signer calls.
 #[derive(Clone, Debug)]
 pub struct CallOptions {
@@ -276,3 +544,157 @@ impl Signer {
         Ok(cbor::from_slice(&result).expect("result should decode correctly"))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        keymanager::get_key_pair_id,
+        testing::{consensus, keymanager::MockKeyManagerStatus, keys},
+    };
+
+    #[test]
+    fn test_mock_builder() {
+        let mut mock = Mock::builder()
+            .epoch(42)
+            .round(7)
+            .block_time(1_000)
+            .confidential(true)
+            .balances(BTreeMap::from([(
+                keys::alice::address(),
+                BTreeMap::from([(token::Denomination::NATIVE, 1_000)]),
+            )]))
+            .parameters::<modules::core::Module<Config>>(modules::core::Parameters {
+                max_batch_gas: 123,
+                ..Default::default()
+            })
+            .build();
+
+        let ctx = mock.create_ctx();
+        assert_eq!(ctx.epoch(), 42);
+        assert_eq!(ctx.runtime_header().round, 7);
+        assert_eq!(ctx.runtime_header().timestamp, 1_000);
+        assert!(ctx.is_confidential());
+        assert_eq!(
+            modules::accounts::Module::get_balance(
+                keys::alice::address(),
+                token::Denomination::NATIVE
+            )
+            .unwrap(),
+            1_000
+        );
+        assert_eq!(
+            modules::accounts::Module::get_total_supplies()
+                .unwrap()
+                .get(&token::Denomination::NATIVE),
+            Some(&1_000)
+        );
+        assert_eq!(
+            <modules::core::Module<Config> as Module>::params().max_batch_gas,
+            123
+        );
+    }
+
+    #[test]
+    fn test_mock_helpers() {
+        let mut mock = Mock::default();
+        let mut ctx = mock.create_ctx();
+        init_balances(
+            &mut ctx,
+            &[(keys::alice::address(), 1_000), (keys::bob::address(), 500)],
+        );
+        assert_eq!(balance(keys::alice::address()), 1_000);
+        assert_eq!(balance(keys::bob::address()), 500);
+        assert_eq!(balance(keys::charlie::address()), 0);
+        assert_eq!(
+            modules::accounts::Module::get_total_supplies()
+                .unwrap()
+                .get(&token::Denomination::NATIVE),
+            Some(&1_500)
+        );
+
+        let signer = keys::alice::signer_info();
+        assert_eq!(signer.address_spec.address(), keys::alice::address());
+        assert_eq!(signer.nonce, 0);
+        let tx = transaction_from(&signer, "accounts.Transfer");
+        assert_eq!(tx.call.method, "accounts.Transfer");
+        assert_eq!(tx.auth_info.signer_info.len(), 1);
+        assert_eq!(
+            tx.auth_info.signer_info[0].address_spec.address(),
+            keys::alice::address()
+        );
+    }
+
+    #[test]
+    fn test_mock_simulated_signals() {
+        let mut mock = Mock::builder().epoch(3).confidential(true).build();
+        let simulation = mock.simulate_consensus();
+
+        // Epoch transitions should be reflected in both the context and the consensus history.
+        simulation.advance_blocks(4);
+        assert_eq!(mock.transition_epoch(), 4);
+        {
+            let ctx = mock.create_ctx();
+            assert_eq!(ctx.epoch(), 4);
+            assert_eq!(ctx.consensus_state().height(), 6);
+        }
+        assert_eq!(simulation.epoch_height(4), Some(6));
+
+        // Key manager status changes should affect already created contexts.
+        let key_manager = mock.key_manager.clone();
+        {
+            let ctx = mock.create_ctx();
+            let km = ctx.key_manager().unwrap();
+            let kid = || get_key_pair_id([b"test".as_slice()]);
+            assert!(km.get_or_create_keys(kid()).is_ok());
+
+            key_manager.set_status(MockKeyManagerStatus::NotInitialized);
+            assert!(km.get_or_create_keys(kid()).is_err());
+
+            key_manager.set_status(MockKeyManagerStatus::Ready);
+            assert!(km.get_or_create_keys(kid()).is_ok());
+        }
+
+        // Results for messages without a handler should be rejected.
+        let result = mock.deliver_message_results::<EmptyRuntime>(
+            vec![],
+            vec![consensus::message_failure("staking", 0, 1)],
+        );
+        assert!(matches!(
+            result,
+            Err(modules::core::Error::MessageHandlerMissing(0))
+        ));
+        mock.deliver_message_results::<EmptyRuntime>(vec![], vec![])
+            .expect("empty message results should be handled");
+    }
+
+    #[test]
+    fn test_mock_time() {
+        let mut mock = Mock::builder().epoch(2).round(10).block_time(1_000).build();
+        {
+            let ctx = mock.create_ctx();
+            assert_eq!(ctx.time().round(), 10);
+            assert_eq!(ctx.time().timestamp(), 1_000);
+            assert_eq!(ctx.epoch(), 2);
+        }
+
+        let time = mock.mock_time();
+        let mut ctx = mock.create_ctx();
+        time.advance(5, 30);
+        time.set_epoch(3);
+        assert_eq!(ctx.time().round(), 15);
+        assert_eq!(ctx.time().timestamp(), 1_030);
+        assert_eq!(ctx.epoch(), 3);
+
+        // Child contexts should share the time source.
+        ctx.with_child(Mode::SimulateTx, |child_ctx| {
+            assert_eq!(child_ctx.time().round(), 15);
+            assert_eq!(child_ctx.epoch(), 3);
+        });
+        assert_eq!(
+            ctx.runtime_header().round,
+            10,
+            "runtime header should be unaffected"
+        );
+    }
+}
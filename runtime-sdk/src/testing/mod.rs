@@ -1,8 +1,20 @@
 //! Module which contains utilities useful for testing and development.
 
+pub mod consensus;
+pub mod differential;
+pub mod faults;
+pub mod fuzz;
+pub mod gas;
 pub mod keymanager;
 pub mod keys;
 pub mod mock;
+#[cfg(any(test, feature = "proptest"))]
+pub mod property;
+pub mod replay;
+pub mod scenario;
+pub mod time;
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod vectors;
 
 /// Constructs a BTreeMap where keys are coerced to strings, and values to cbor::Value.
 /// Syntax: `configmap! { "key" => value, ... }`.
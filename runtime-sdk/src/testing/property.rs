@@ -0,0 +1,391 @@
+//! Property-based testing helpers.
+//!
+//! Provides `proptest` strategies for core SDK types and an `InvariantRunner` that executes
+//! random sequences of calls through a full in-process runtime (see `testing::scenario`) while
+//! checking module invariants after each call. Failing sequences are shrunk to a minimal example.
+use std::{fmt, marker::PhantomData};
+
+use proptest::{
+    collection,
+    prelude::*,
+    sample,
+    test_runner::{TestCaseError, TestRunner},
+};
+
+use crate::{
+    core::common::crypto::{mrae::deoxysii, x25519},
+    crypto::signature::{MemorySigner, PublicKey, SignatureType},
+    runtime::Runtime,
+    testing::{
+        keys,
+        scenario::{Scenario, Tx},
+    },
+    types::{
+        address::{Address, SignatureAddressSpec},
+        callformat::CallEnvelopeX25519DeoxysII,
+        token,
+        transaction::{self, AuthProof, CallFormat, UnverifiedTransaction},
+    },
+};
+
+/// Arbitrary addresses, derived either from public keys or from module identifiers.
+pub fn address() -> impl Strategy<Value = Address> {
+    prop_oneof![
+        3 => key_address(),
+        1 => collection::vec(any::<u8>(), 0..32)
+            .prop_map(|kind| Address::from_module_raw("proptest", &kind)),
+    ]
+}
+
+/// Arbitrary addresses derived from Ed25519 and Secp256k1 public keys.
+pub fn key_address() -> impl Strategy<Value = Address> {
+    (
+        sample::select(vec![
+            SignatureType::Ed25519_Oasis,
+            SignatureType::Secp256k1_Oasis,
+        ]),
+        any::<[u8; 32]>(),
+    )
+        .prop_filter_map("seed should be a valid secret key", |(sig_type, seed)| {
+            let signer = MemorySigner::new_from_seed(sig_type, &seed).ok()?;
+            let sigspec = match signer.public_key() {
+                PublicKey::Ed25519(pk) => SignatureAddressSpec::Ed25519(pk),
+                PublicKey::Secp256k1(pk) => SignatureAddressSpec::Secp256k1Eth(pk),
+                _ => unreachable!("only Ed25519 and Secp256k1 signers are generated"),
+            };
+            Some(Address::from_sigspec(&sigspec))
+        })
+}
+
+/// Signers of the well-known test keys, so that random calls can be authenticated.
+pub fn test_signer() -> impl Strategy<Value = SignatureAddressSpec> {
+    sample::select(vec![
+        keys::alice::sigspec(),
+        keys::bob::sigspec(),
+        keys::charlie::sigspec(),
+        keys::dave::sigspec(),
+    ])
+}
+
+/// Addresses of the well-known test keys.
+pub fn test_address() -> impl Strategy<Value = Address> {
+    test_signer().prop_map(|sigspec| Address::from_sigspec(&sigspec))
+}
+
+/// Arbitrary token denominations, biased towards the native denomination.
+pub fn denomination() -> impl Strategy<Value = token::Denomination> {
+    prop_oneof![
+        3 => Just(token::Denomination::NATIVE),
+        1 => collection::vec(any::<u8>(), 1..=token::Denomination::MAX_LENGTH).prop_map(|raw| {
+            token::Denomination::try_from(raw.as_slice()).expect("denomination should be valid")
+        }),
+    ]
+}
+
+/// Amounts of the given denomination up to and including `max`.
+pub fn base_units(
+    denomination: token::Denomination,
+    max: u128,
+) -> impl Strategy<Value = token::BaseUnits> {
+    (0..=max).prop_map(move |amount| token::BaseUnits::new(amount, denomination.clone()))
+}
+
+/// Arbitrary call formats.
+pub fn call_format() -> impl Strategy<Value = CallFormat> {
+    prop_oneof![
+        Just(CallFormat::Plain),
+        Just(CallFormat::EncryptedX25519DeoxysII),
+        Just(CallFormat::EncryptedX25519XChaCha20Poly1305),
+    ]
+}
+
+/// Arbitrary (undecryptable) encrypted call envelopes.
+pub fn call_envelope() -> impl Strategy<Value = CallEnvelopeX25519DeoxysII> {
+    (
+        any::<[u8; 32]>(),
+        any::<[u8; deoxysii::NONCE_SIZE]>(),
+        any::<u64>(),
+        collection::vec(any::<u8>(), 0..256),
+    )
+        .prop_map(|(pk, nonce, epoch, data)| CallEnvelopeX25519DeoxysII {
+            pk: x25519::PublicKey(x25519_dalek::PublicKey::from(pk)),
+            nonce,
+            epoch,
+            data,
+        })
+}
+
+/// Arbitrary calls to one of the given methods. Encrypted calls carry an arbitrary envelope.
+pub fn call(methods: &[&str]) -> impl Strategy<Value = transaction::Call> {
+    let methods: Vec<String> = methods.iter().map(|m| m.to_string()).collect();
+    (sample::select(methods), call_format(), call_envelope()).prop_map(
+        |(method, format, envelope)| transaction::Call {
+            format,
+            method: match format {
+                CallFormat::Plain => method,
+                _ => String::new(),
+            },
+            body: match format {
+                CallFormat::Plain => cbor::Value::Simple(cbor::SimpleValue::NullValue),
+                _ => cbor::to_value(envelope),
+            },
+            ..Default::default()
+        },
+    )
+}
+
+/// Arbitrary transactions calling one of the given methods, signed by one of the test keys.
+pub fn transaction(methods: &[&str]) -> impl Strategy<Value = transaction::Transaction> {
+    (call(methods), test_signer(), any::<u64>(), 0..1_000_000u64).prop_map(
+        |(call, signer, nonce, gas)| transaction::Transaction {
+            version: transaction::LATEST_TRANSACTION_VERSION,
+            call,
+            auth_info: transaction::AuthInfo {
+                signer_info: vec![transaction::SignerInfo::new_sigspec(signer, nonce)],
+                fee: transaction::Fee {
+                    amount: Default::default(),
+                    gas,
+                    consensus_messages: 0,
+                },
+                ..Default::default()
+            },
+        },
+    )
+}
+
+/// Arbitrary unverified transactions with invalid signatures.
+pub fn unverified_transaction(methods: &[&str]) -> impl Strategy<Value = UnverifiedTransaction> {
+    (transaction(methods), collection::vec(any::<u8>(), 0..96)).prop_map(|(tx, signature)| {
+        UnverifiedTransaction(
+            cbor::to_vec(tx),
+            vec![AuthProof::Signature(signature.into())],
+        )
+    })
+}
+
+/// Scenario transactions calling the given method with bodies from the given strategy, signed by
+/// one of the test keys. Nonces are tracked by the scenario.
+pub fn tx<B, S>(method: &'static str, body: S) -> impl Strategy<Value = Tx>
+where
+    B: cbor::Encode + fmt::Debug,
+    S: Strategy<Value = B>,
+{
+    (test_signer(), body).prop_map(move |(signer, body)| Tx::new(signer, method, body))
+}
+
+type Invariant = Box<dyn Fn() -> Result<(), String>>;
+
+/// Runner applying random call sequences to a runtime and checking invariants after each call.
+///
+/// The invariants of all runtime modules (see `module::InvariantHandler`) are always checked in
+/// addition to the invariants registered with the runner.
+pub struct InvariantRunner<R: Runtime> {
+    config: ProptestConfig,
+    invariants: Vec<(String, Invariant)>,
+    _runtime: PhantomData<R>,
+}
+
+impl<R: Runtime> Default for InvariantRunner<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Runtime> InvariantRunner<R> {
+    /// Create a new runner with the default proptest configuration.
+    pub fn new() -> Self {
+        Self {
+            config: ProptestConfig::default(),
+            invariants: vec![],
+            _runtime: PhantomData,
+        }
+    }
+
+    /// Use the given proptest configuration.
+    pub fn with_config(mut self, config: ProptestConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register an invariant. The closure is run with access to the runtime state after each call
+    /// and should return an error describing the violation.
+    pub fn invariant<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn() -> Result<(), String> + 'static,
+    {
+        self.invariants.push((name.to_owned(), Box::new(f)));
+        self
+    }
+
+    /// Apply call sequences generated by the given strategy, each to a fresh runtime state with
+    /// every call in its own block.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the minimal failing call sequence in case any invariant is violated.
+    pub fn run<S>(&self, calls: S)
+    where
+        S: Strategy<Value = Vec<Tx>>,
+    {
+        let mut runner = TestRunner::new(self.config.clone());
+        let result = runner.run(&calls, |txs| {
+            let mut scenario = Scenario::<R>::new();
+            for (index, tx) in txs.into_iter().enumerate() {
+                scenario
+                    .block()
+                    .tx(tx)
+                    .run()
+                    .map_err(|err| TestCaseError::fail(format!("call {index}: {err}")))?;
+
+                scenario.check_invariants().map_err(|err| {
+                    TestCaseError::fail(format!("call {index}: module invariants violated: {err}"))
+                })?;
+                for (name, invariant) in &self.invariants {
+                    scenario.with_state(invariant).map_err(|err| {
+                        TestCaseError::fail(format!(
+                            "call {index}: invariant {name} violated: {err}"
+                        ))
+                    })?;
+                }
+            }
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            panic!("{err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        module,
+        modules::{
+            self,
+            accounts::{self, API as _},
+        },
+        Version,
+    };
+
+    struct Config;
+
+    impl modules::core::Config for Config {}
+
+    struct PropertyRuntime;
+
+    impl Runtime for PropertyRuntime {
+        const VERSION: Version = Version::new(0, 0, 0);
+
+        type Core = modules::core::Module<Config>;
+
+        type Modules = (modules::core::Module<Config>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            (
+                modules::core::Genesis {
+                    parameters: modules::core::Parameters {
+                        max_batch_gas: 10_000_000,
+                        max_tx_size: 32 * 1024,
+                        max_tx_signers: 1,
+                        max_multisig_signers: 1,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                accounts::Genesis {
+                    balances: BTreeMap::from([
+                        (
+                            keys::alice::address(),
+                            BTreeMap::from([(token::Denomination::NATIVE, 1_000)]),
+                        ),
+                        (
+                            keys::bob::address(),
+                            BTreeMap::from([(token::Denomination::NATIVE, 1_000)]),
+                        ),
+                    ]),
+                    total_supplies: BTreeMap::from([(token::Denomination::NATIVE, 2_000)]),
+                    ..Default::default()
+                },
+            )
+        }
+    }
+
+    fn transfers() -> impl Strategy<Value = Vec<Tx>> {
+        let transfer = (
+            test_address(),
+            base_units(token::Denomination::NATIVE, 1_500),
+        )
+            .prop_map(|(to, amount)| accounts::types::Transfer { to, amount });
+        collection::vec(tx("accounts.Transfer", transfer), 1..8)
+    }
+
+    proptest! {
+        #[test]
+        fn test_transaction_roundtrip(tx in transaction(&["accounts.Transfer", "core.Noop"])) {
+            let decoded: transaction::Transaction = cbor::from_slice(&cbor::to_vec(tx.clone())).unwrap();
+            prop_assert_eq!(cbor::to_vec(decoded), cbor::to_vec(tx));
+        }
+
+        #[test]
+        fn test_base_units_bounds(amount in base_units(token::Denomination::NATIVE, 10)) {
+            prop_assert!(amount.amount() <= 10);
+            prop_assert!(amount.denomination().is_native());
+        }
+    }
+
+    #[test]
+    fn test_invariant_runner() {
+        InvariantRunner::<PropertyRuntime>::new()
+            .with_config(ProptestConfig::with_cases(16))
+            .invariant("supply conservation", || {
+                let total: u128 = [
+                    keys::alice::address(),
+                    keys::bob::address(),
+                    keys::charlie::address(),
+                    keys::dave::address(),
+                ]
+                .into_iter()
+                .map(|address| {
+                    accounts::Module::get_balance(address, token::Denomination::NATIVE).unwrap()
+                })
+                .sum();
+                if total != 2_000 {
+                    return Err(format!("total balance is {total}"));
+                }
+                Ok(())
+            })
+            .run(transfers());
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant bob is poor violated")]
+    fn test_invariant_runner_violation() {
+        InvariantRunner::<PropertyRuntime>::new()
+            .with_config(ProptestConfig::with_cases(16))
+            .invariant("bob is poor", || {
+                let balance = accounts::Module::get_balance(
+                    keys::bob::address(),
+                    token::Denomination::NATIVE,
+                )
+                .unwrap();
+                if balance > 1_000 {
+                    return Err(format!("bob has {balance}"));
+                }
+                Ok(())
+            })
+            .run(collection::vec(
+                tx(
+                    "accounts.Transfer",
+                    Just(accounts::types::Transfer {
+                        to: keys::bob::address(),
+                        amount: token::BaseUnits::new(1, token::Denomination::NATIVE),
+                    }),
+                ),
+                8..16,
+            ));
+    }
+}
@@ -0,0 +1,180 @@
+//! Deterministic replay of historical blocks.
+//!
+//! Given a snapshot of the runtime state and an exported block, the block is re-executed through
+//! the dispatcher and the resulting state root is compared with the expected one. This can be used
+//! to validate that runtime upgrades do not change the outcome of historical blocks and to debug
+//! consensus faults.
+use std::collections::BTreeMap;
+
+use oasis_core_runtime::{
+    common::crypto::hash::Hash,
+    consensus::{beacon, roothash},
+    storage::mkvs::{self, MKVS as _},
+    transaction::dispatcher::ExecuteTxResult,
+};
+
+use crate::{
+    context::{BatchContext, Mode},
+    dispatcher,
+    module::{self, BlockHandler as _},
+    modules,
+    runtime::Runtime,
+    storage::{CurrentStore, MKVSStore},
+    testing::mock::Mock,
+    types,
+};
+
+/// Errors emitted during replay.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("malformed transaction at index {0}: {1}")]
+    MalformedTransaction(usize, #[source] modules::core::Error),
+
+    #[error("dispatch failed: {0}")]
+    Dispatch(#[from] dispatcher::Error),
+
+    #[error("failed to handle message results: {0}")]
+    MessageResults(#[source] modules::core::Error),
+
+    #[error("storage error: {0}")]
+    Storage(#[source] anyhow::Error),
+
+    #[error("state root mismatch (expected: {expected:?} actual: {actual:?})")]
+    StateRootMismatch { expected: Hash, actual: Hash },
+}
+
+/// A snapshot of the runtime state.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct StateSnapshot {
+    /// All key/value pairs in runtime state.
+    pub entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// A block exported for replay.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct ExportedBlock {
+    /// Runtime block header that was provided to the runtime when executing the block.
+    pub header: roothash::Header,
+    /// Epoch in which the block was executed.
+    pub epoch: beacon::EpochTime,
+    /// Raw transactions in the order in which they were executed.
+    pub txs: Vec<Vec<u8>>,
+    /// Expected state root after executing the block.
+    pub state_root: Hash,
+}
+
+/// Result of replaying a block.
+pub struct ReplayResult {
+    /// State root after executing the block.
+    pub state_root: Hash,
+    /// Results of executing each transaction.
+    pub results: Vec<ExecuteTxResult>,
+}
+
+/// Replay the given block on top of the given state snapshot and verify that the resulting state
+/// root matches the one recorded in the exported block.
+pub fn replay<R: Runtime>(
+    snapshot: &StateSnapshot,
+    block: &ExportedBlock,
+) -> Result<ReplayResult, Error> {
+    let mut tree = mkvs::Tree::builder()
+        .with_root_type(mkvs::RootType::State)
+        .build(Box::new(mkvs::sync::NoopReadSyncer));
+    for (key, value) in &snapshot.entries {
+        tree.insert(key, value);
+    }
+
+    let mut mock = Mock::default();
+    mock.runtime_header = block.header.clone();
+    mock.epoch = block.epoch;
+
+    let results = CurrentStore::enter(MKVSStore::new(&mut tree), || {
+        let mut ctx = mock.create_ctx_for_runtime::<R>(Mode::ExecuteTx, false);
+
+        // Mirror the steps performed during batch execution.
+        R::migrate(&mut ctx);
+        dispatcher::Dispatcher::<R>::handle_last_round_messages(&mut ctx)
+            .map_err(Error::MessageResults)?;
+        R::Modules::begin_block(&mut ctx);
+        dispatcher::Dispatcher::<R>::run_block_workers(&mut ctx, module::BlockPhase::Begin);
+
+        let mut results = Vec::with_capacity(block.txs.len());
+        for (index, raw_tx) in block.txs.iter().enumerate() {
+            let tx = dispatcher::Dispatcher::<R>::decode_tx(&mut ctx, raw_tx)
+                .map_err(|err| Error::MalformedTransaction(index, err))?;
+            results.push(dispatcher::Dispatcher::<R>::execute_tx(
+                &mut ctx,
+                types::transaction::envelope_size(raw_tx)
+                    .try_into()
+                    .unwrap(),
+                Hash::digest_bytes(raw_tx),
+                tx,
+                index,
+            )?);
+        }
+
+        dispatcher::Dispatcher::<R>::run_block_workers(&mut ctx, module::BlockPhase::End);
+        R::Modules::end_block(&mut ctx);
+        ctx.commit();
+
+        Ok::<_, Error>(results)
+    })?;
+
+    let (_, state_root) = tree
+        .commit(mock.host_info.runtime_id, block.header.round)
+        .map_err(Error::Storage)?;
+    if state_root != block.state_root {
+        return Err(Error::StateRootMismatch {
+            expected: block.state_root,
+            actual: state_root,
+        });
+    }
+
+    Ok(ReplayResult {
+        state_root,
+        results,
+    })
+}
+
+/// Replay the given block and panic in case the resulting state root does not match.
+pub fn assert_replay<R: Runtime>(snapshot: &StateSnapshot, block: &ExportedBlock) -> ReplayResult {
+    match replay::<R>(snapshot, block) {
+        Ok(result) => result,
+        Err(err) => panic!("block replay failed: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::mock::EmptyRuntime;
+
+    #[test]
+    fn test_replay() {
+        let snapshot = StateSnapshot::default();
+        let mut block = ExportedBlock::default();
+
+        // Determine the state root of the block.
+        let state_root = match replay::<EmptyRuntime>(&snapshot, &block) {
+            Err(Error::StateRootMismatch { actual, .. }) => actual,
+            _ => panic!("replay should report a state root mismatch"),
+        };
+
+        // Replaying should be deterministic.
+        block.state_root = state_root;
+        let result = assert_replay::<EmptyRuntime>(&snapshot, &block);
+        assert_eq!(result.state_root, state_root);
+        assert!(result.results.is_empty());
+
+        // A different snapshot should result in a different state root.
+        let mut other = snapshot.clone();
+        other.entries.insert(b"foo".to_vec(), b"bar".to_vec());
+        let result = replay::<EmptyRuntime>(&other, &block);
+        assert!(matches!(result, Err(Error::StateRootMismatch { .. })));
+
+        // Malformed transactions should be reported.
+        block.txs.push(b"not a transaction".to_vec());
+        let result = replay::<EmptyRuntime>(&snapshot, &block);
+        assert!(matches!(result, Err(Error::MalformedTransaction(0, _))));
+    }
+}
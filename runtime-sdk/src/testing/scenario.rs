@@ -0,0 +1,626 @@
+//! End-to-end scenarios for multi-transaction integration tests.
+//!
+//! A `Scenario` runs a full in-process runtime (dispatcher and all modules) on top of an in-memory
+//! state tree. Each block is executed through the same steps as during batch execution, so
+//! scripted scenarios spanning multiple blocks, epoch transitions and consensus message results
+//! can be used to test cross-module behaviour without a live network.
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use oasis_core_runtime::{
+    common::crypto::hash::Hash,
+    consensus::{beacon, roothash},
+    storage::mkvs::{self, MKVS as _},
+    transaction::tags::Tags,
+};
+
+use crate::{
+    context::{BatchContext as _, Mode},
+    dispatcher,
+    error::RuntimeError,
+    event::IntoTags as _,
+    module::{self, BlockHandler as _, InvariantHandler as _},
+    modules::{self, accounts::API as _},
+    runtime::Runtime,
+    storage::{CurrentStore, MKVSStore, NestedStore as _, OverlayStore},
+    testing::{
+        mock::{CallOptions, Mock},
+        replay::StateSnapshot,
+    },
+    types::{
+        address::{Address, SignatureAddressSpec},
+        transaction,
+    },
+};
+
+/// Errors emitted while executing a scenario block.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("dispatch of transaction {0} failed: {1}")]
+    Dispatch(usize, #[source] dispatcher::Error),
+
+    #[error("failed to handle message results: {0}")]
+    MessageResults(#[source] modules::core::Error),
+
+    #[error("storage error: {0}")]
+    Storage(#[source] anyhow::Error),
+}
+
+/// A transaction to be executed in a scenario block.
+#[derive(Clone, Debug)]
+pub struct Tx {
+    signer: SignatureAddressSpec,
+    nonce: Option<u64>,
+    method: String,
+    body: cbor::Value,
+    opts: CallOptions,
+}
+
+impl Tx {
+    /// Create a new transaction calling the given method, signed by the given signer.
+    ///
+    /// Unless overridden, the nonce is the signer's next nonce as recorded in the runtime state.
+    pub fn new<B: cbor::Encode>(signer: SignatureAddressSpec, method: &str, body: B) -> Self {
+        Self {
+            signer,
+            nonce: None,
+            method: method.to_owned(),
+            body: cbor::to_value(body),
+            opts: Default::default(),
+        }
+    }
+
+    /// Use the given nonce instead of the signer's next nonce.
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Use the given transaction fee.
+    pub fn with_fee(mut self, fee: transaction::Fee) -> Self {
+        self.opts.fee = fee;
+        self
+    }
+
+    fn into_transaction(self, nonce: u64) -> transaction::Transaction {
+        transaction::Transaction {
+            version: 1,
+            call: transaction::Call {
+                format: transaction::CallFormat::Plain,
+                method: self.method,
+                body: self.body,
+                ..Default::default()
+            },
+            auth_info: transaction::AuthInfo {
+                signer_info: vec![transaction::SignerInfo::new_sigspec(self.signer, nonce)],
+                fee: self.opts.fee,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Result of executing a transaction in a scenario block.
+#[derive(Debug)]
+pub struct TxResult {
+    /// Transaction call result.
+    pub result: module::CallResult,
+    /// Transaction tags.
+    pub tags: Tags,
+    /// Transaction priority.
+    pub priority: u64,
+}
+
+impl TxResult {
+    /// Check whether the transaction has been executed successfully.
+    pub fn is_success(&self) -> bool {
+        self.result.is_success()
+    }
+
+    /// Events with the given code emitted by the given module.
+    pub fn events<E: cbor::Decode>(&self, module: &str, code: u32) -> Vec<E> {
+        decode_events(&self.tags, module, code)
+    }
+
+    /// Amount of gas used by the transaction as reported by the core module.
+    ///
+    /// Only available in case the runtime emits gas used events.
+    pub fn gas_used(&self) -> Option<u64> {
+        #[derive(cbor::Decode)]
+        struct GasUsed {
+            amount: u64,
+        }
+
+        // Core module's GasUsed event (code = 1).
+        self.events::<GasUsed>(modules::core::MODULE_NAME, 1)
+            .pop()
+            .map(|ev| ev.amount)
+    }
+}
+
+impl From<dispatcher::DispatchResult> for TxResult {
+    fn from(result: dispatcher::DispatchResult) -> Self {
+        Self {
+            result: result.result,
+            tags: result.tags,
+            priority: result.priority,
+        }
+    }
+}
+
+/// Result of executing a scenario block.
+#[derive(Debug)]
+pub struct BlockResult {
+    /// Round of the executed block.
+    pub round: u64,
+    /// State root after executing the block.
+    pub state_root: Hash,
+    /// Results of executing each transaction.
+    pub txs: Vec<TxResult>,
+    /// Messages emitted to the consensus layer.
+    pub messages: Vec<roothash::Message>,
+    /// Block-level tags (e.g. events emitted by block hooks).
+    pub tags: Tags,
+}
+
+impl BlockResult {
+    /// Block-level events with the given code emitted by the given module.
+    pub fn events<E: cbor::Decode>(&self, module: &str, code: u32) -> Vec<E> {
+        decode_events(&self.tags, module, code)
+    }
+}
+
+fn decode_events<E: cbor::Decode>(tags: &Tags, module: &str, code: u32) -> Vec<E> {
+    let key = [module.as_bytes(), &code.to_be_bytes()].concat();
+    tags.iter()
+        .filter(|tag| tag.key == key)
+        .flat_map(|tag| {
+            cbor::from_slice::<Vec<E>>(&tag.value).expect("event should decode correctly")
+        })
+        .collect()
+}
+
+/// A scripted scenario executed by a full in-process runtime.
+pub struct Scenario<R: Runtime> {
+    tree: mkvs::Tree,
+    mock: Mock,
+    _runtime: PhantomData<R>,
+}
+
+impl<R: Runtime> Default for Scenario<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Runtime> Scenario<R> {
+    /// Create a new scenario with empty state. The runtime genesis state is applied when the
+    /// first block is executed.
+    pub fn new() -> Self {
+        let mut mock = Mock::default();
+        mock.runtime_header.round = 1;
+
+        Self {
+            tree: mkvs::Tree::builder()
+                .with_root_type(mkvs::RootType::State)
+                .build(Box::new(mkvs::sync::NoopReadSyncer)),
+            mock,
+            _runtime: PhantomData,
+        }
+    }
+
+    /// Create a new scenario starting from the given state snapshot. The runtime genesis state is
+    /// only applied in case the snapshot does not contain any runtime state.
+    pub fn from_snapshot(snapshot: &StateSnapshot) -> Self {
+        let mut scenario = Self::new();
+        for (key, value) in &snapshot.entries {
+            scenario.tree.insert(key, value);
+        }
+        scenario
+    }
+
+    /// Snapshot of the current runtime state.
+    pub fn snapshot(&mut self) -> StateSnapshot {
+        self.with_state(|| {
+            CurrentStore::with(|store| {
+                let mut it = store.iter();
+                it.rewind();
+                StateSnapshot {
+                    entries: it.collect(),
+                }
+            })
+        })
+    }
+
+    /// Round of the next block.
+    pub fn round(&self) -> u64 {
+        self.mock.runtime_header.round
+    }
+
+    /// Current epoch.
+    pub fn epoch(&self) -> beacon::EpochTime {
+        self.mock.epoch
+    }
+
+    /// Start building the next block.
+    pub fn block(&mut self) -> Block<'_, R> {
+        Block {
+            scenario: self,
+            epoch: None,
+            timestamp: None,
+            message_results: vec![],
+            txs: vec![],
+        }
+    }
+
+    /// Run the given closure with access to the current runtime state (e.g. to assert on module
+    /// state or to prepare state before a block). Any changes made by the closure are persisted.
+    pub fn with_state<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        CurrentStore::enter(MKVSStore::new(&mut self.tree), f)
+    }
+
+    /// Check the invariants of all runtime modules against the current runtime state.
+    pub fn check_invariants(&mut self) -> Result<(), modules::core::Error> {
+        let Self { tree, mock, .. } = self;
+        let confidential = mock.confidential;
+
+        CurrentStore::enter(MKVSStore::new(tree), || {
+            let mut ctx = mock.create_ctx_for_runtime::<R>(Mode::CheckTx, confidential);
+            R::Modules::check_invariants(&mut ctx)
+        })
+    }
+
+    /// Dispatch a query to the given method against the current runtime state.
+    pub fn query<A, T>(&mut self, method: &str, args: A) -> Result<T, RuntimeError>
+    where
+        A: cbor::Encode,
+        T: cbor::Decode,
+    {
+        let Self { tree, mock, .. } = self;
+        let confidential = mock.confidential;
+
+        CurrentStore::enter(MKVSStore::new(tree), || {
+            let mut ctx = mock.create_ctx_for_runtime::<R>(Mode::CheckTx, confidential);
+            let result =
+                dispatcher::Dispatcher::<R>::dispatch_query(&mut ctx, method, cbor::to_vec(args))?;
+            Ok(cbor::from_slice(&result).expect("result should decode correctly"))
+        })
+    }
+}
+
+/// Builder for a scenario block.
+pub struct Block<'a, R: Runtime> {
+    scenario: &'a mut Scenario<R>,
+    epoch: Option<beacon::EpochTime>,
+    timestamp: Option<u64>,
+    message_results: Vec<roothash::MessageEvent>,
+    txs: Vec<Tx>,
+}
+
+impl<'a, R: Runtime> Block<'a, R> {
+    /// Execute the block in the given epoch. Subsequent blocks remain in this epoch.
+    pub fn epoch(mut self, epoch: beacon::EpochTime) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Set the block timestamp.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Provide the result of a message emitted in the previous block, as reported by the
+    /// consensus layer.
+    pub fn message_result(mut self, event: roothash::MessageEvent) -> Self {
+        self.message_results.push(event);
+        self
+    }
+
+    /// Add a transaction to the block.
+    pub fn tx(mut self, tx: Tx) -> Self {
+        self.txs.push(tx);
+        self
+    }
+
+    /// Add a transaction calling the given method to the block.
+    pub fn call<B: cbor::Encode>(
+        self,
+        signer: SignatureAddressSpec,
+        method: &str,
+        body: B,
+    ) -> Self {
+        self.tx(Tx::new(signer, method, body))
+    }
+
+    /// Execute the block and commit the resulting state.
+    ///
+    /// In case block execution fails, none of the changes made by the block are persisted and the
+    /// scenario remains at the same round.
+    pub fn run(self) -> Result<BlockResult, Error> {
+        let Scenario { tree, mock, .. } = self.scenario;
+
+        if let Some(epoch) = self.epoch {
+            mock.epoch = epoch;
+        }
+        if let Some(timestamp) = self.timestamp {
+            mock.runtime_header.timestamp = timestamp;
+        }
+        mock.runtime_round_results.messages = self.message_results;
+        let round = mock.runtime_header.round;
+        let confidential = mock.confidential;
+        let txs = self.txs;
+
+        let mut batch = OverlayStore::new(MKVSStore::new(&mut *tree));
+        let (results, messages, tags) = CurrentStore::enter(&mut batch, || {
+            let mut ctx = mock.create_ctx_for_runtime::<R>(Mode::ExecuteTx, confidential);
+
+            // Mirror the steps performed during batch execution.
+            R::migrate(&mut ctx);
+            dispatcher::Dispatcher::<R>::handle_last_round_messages(&mut ctx)
+                .map_err(Error::MessageResults)?;
+            R::Modules::begin_block(&mut ctx);
+            dispatcher::Dispatcher::<R>::run_block_workers(&mut ctx, module::BlockPhase::Begin);
+
+            // Nonces are read from state the first time a signer is seen in the block, so they
+            // match the state the block executes on (e.g. when starting from a snapshot).
+            let mut nonces = BTreeMap::new();
+            let mut results = Vec::with_capacity(txs.len());
+            for (index, tx) in txs.into_iter().enumerate() {
+                let address = Address::from_sigspec(&tx.signer);
+                let next_nonce = nonces.entry(address).or_insert_with(|| {
+                    modules::accounts::Module::get_nonce(address).expect("nonce should be readable")
+                });
+                let nonce = tx.nonce.unwrap_or(*next_nonce);
+                *next_nonce = nonce + 1;
+
+                let tx = tx.into_transaction(nonce);
+                let tx_size = cbor::to_vec(tx.clone()).len().try_into().unwrap();
+                let result = dispatcher::Dispatcher::<R>::dispatch_tx(&mut ctx, tx_size, tx, index)
+                    .map_err(|err| Error::Dispatch(index, err))?;
+                results.push(TxResult::from(result));
+            }
+
+            dispatcher::Dispatcher::<R>::run_block_workers(&mut ctx, module::BlockPhase::End);
+            R::Modules::end_block(&mut ctx);
+
+            let state = ctx.commit();
+            let (messages, handlers) = state.messages.into_iter().unzip();
+            dispatcher::Dispatcher::<R>::save_emitted_message_handlers(handlers);
+
+            Ok::<_, Error>((results, messages, state.events.into_tags()))
+        })?;
+        batch.commit();
+
+        let (_, state_root) = tree
+            .commit(mock.host_info.runtime_id, round)
+            .map_err(Error::Storage)?;
+        mock.runtime_header.round += 1;
+
+        Ok(BlockResult {
+            round,
+            state_root,
+            txs: results,
+            messages,
+            tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        modules::accounts::{self, API as _},
+        testing::keys,
+        types::token::{self, Denomination},
+        Version,
+    };
+
+    struct Config;
+
+    impl modules::core::Config for Config {
+        const EMIT_GAS_USED_EVENTS: bool = true;
+    }
+
+    struct ScenarioRuntime;
+
+    impl Runtime for ScenarioRuntime {
+        const VERSION: Version = Version::new(0, 0, 0);
+
+        type Core = modules::core::Module<Config>;
+
+        type Modules = (modules::core::Module<Config>, accounts::Module);
+
+        fn genesis_state() -> <Self::Modules as module::MigrationHandler>::Genesis {
+            (
+                modules::core::Genesis {
+                    parameters: modules::core::Parameters {
+                        max_batch_gas: 10_000_000,
+                        max_tx_size: 32 * 1024,
+                        max_tx_signers: 1,
+                        max_multisig_signers: 1,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                accounts::Genesis {
+                    parameters: accounts::Parameters {
+                        gas_costs: accounts::GasCosts {
+                            tx_transfer: 1_000,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    balances: BTreeMap::from([(
+                        keys::alice::address(),
+                        BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                    )]),
+                    total_supplies: BTreeMap::from([(Denomination::NATIVE, 1_000)]),
+                    ..Default::default()
+                },
+            )
+        }
+    }
+
+    #[derive(Debug, cbor::Decode)]
+    struct TransferEvent {
+        from: Address,
+        to: Address,
+        amount: token::BaseUnits,
+    }
+
+    fn transfer(to: Address, amount: u128) -> accounts::types::Transfer {
+        accounts::types::Transfer {
+            to,
+            amount: token::BaseUnits::new(amount, Denomination::NATIVE),
+        }
+    }
+
+    #[test]
+    fn test_scenario() {
+        let mut scenario = Scenario::<ScenarioRuntime>::new();
+
+        // First block applies genesis and executes transfers with tracked nonces.
+        let result = scenario
+            .block()
+            .call(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::bob::address(), 100),
+            )
+            .call(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::charlie::address(), 50),
+            )
+            .run()
+            .expect("block should execute");
+        assert_eq!(result.round, 1);
+        assert_eq!(result.txs.len(), 2);
+        for tx in &result.txs {
+            assert!(tx.is_success(), "transfer should succeed: {:?}", tx.result);
+            assert!(tx.gas_used().unwrap() >= 1_000);
+        }
+        let events: Vec<TransferEvent> = result.txs[0].events(accounts::MODULE_NAME, 1);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].from, keys::alice::address());
+        assert_eq!(events[0].to, keys::bob::address());
+        assert_eq!(events[0].amount.amount(), 100);
+        assert!(result.messages.is_empty());
+
+        // Assert on state directly and through queries.
+        scenario.with_state(|| {
+            assert_eq!(
+                accounts::Module::get_balance(keys::alice::address(), Denomination::NATIVE)
+                    .unwrap(),
+                850
+            );
+        });
+        let balances: accounts::types::AccountBalances = scenario
+            .query(
+                "accounts.Balances",
+                accounts::types::BalancesQuery {
+                    address: keys::bob::address(),
+                },
+            )
+            .unwrap();
+        assert_eq!(balances.balances.get(&Denomination::NATIVE), Some(&100));
+
+        // Second block happens in a new epoch and includes an invalid transaction.
+        let result = scenario
+            .block()
+            .epoch(2)
+            .call(
+                keys::bob::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::alice::address(), 1_000),
+            )
+            .tx(Tx::new(
+                keys::bob::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::alice::address(), 10),
+            )
+            .with_nonce(1))
+            .run()
+            .expect("block should execute");
+        assert_eq!(result.round, 2);
+        assert_eq!(scenario.epoch(), 2);
+        assert!(!result.txs[0].is_success(), "transfer should fail");
+        assert!(result.txs[1].is_success(), "transfer should succeed");
+        scenario.with_state(|| {
+            assert_eq!(
+                accounts::Module::get_balance(keys::alice::address(), Denomination::NATIVE)
+                    .unwrap(),
+                860
+            );
+        });
+
+        // Results for messages that have not been emitted should fail the block without changing
+        // state or the tracked nonces.
+        let result = scenario
+            .block()
+            .message_result(roothash::MessageEvent {
+                module: "staking".to_string(),
+                code: 0,
+                index: 0,
+                result: None,
+            })
+            .call(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::bob::address(), 10),
+            )
+            .run();
+        assert!(matches!(result, Err(Error::MessageResults(_))));
+        assert_eq!(scenario.round(), 3);
+        scenario.check_invariants().expect("invariants should hold");
+
+        let result = scenario
+            .block()
+            .call(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::bob::address(), 10),
+            )
+            .run()
+            .expect("block should execute");
+        assert_eq!(result.round, 3);
+        assert!(
+            result.txs[0].is_success(),
+            "transfer should use the nonce of the failed block: {:?}",
+            result.txs[0].result
+        );
+        assert_eq!(scenario.round(), 4);
+
+        // Scenarios can start from a snapshot of another scenario.
+        let snapshot = scenario.snapshot();
+        assert!(!snapshot.entries.is_empty());
+        let mut restored = Scenario::<ScenarioRuntime>::from_snapshot(&snapshot);
+        restored.with_state(|| {
+            assert_eq!(
+                accounts::Module::get_balance(keys::alice::address(), Denomination::NATIVE)
+                    .unwrap(),
+                850
+            );
+        });
+
+        // Nonces are read from the snapshot.
+        let result = restored
+            .block()
+            .call(
+                keys::alice::sigspec(),
+                "accounts.Transfer",
+                transfer(keys::bob::address(), 10),
+            )
+            .run()
+            .expect("block should execute");
+        assert!(
+            result.txs[0].is_success(),
+            "transfer should use the nonce from the snapshot: {:?}",
+            result.txs[0].result
+        );
+    }
+}
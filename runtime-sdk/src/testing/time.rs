@@ -0,0 +1,63 @@
+//! Mock time source implementation.
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    core::consensus::beacon::EpochTime,
+    time::{BlockTime, TimeSource},
+};
+
+/// Time source that can be controlled by tests. Clones share the same time so that it can be
+/// changed while a context using it is alive.
+#[derive(Clone, Default)]
+pub struct MockTimeSource {
+    time: Rc<Cell<BlockTime>>,
+}
+
+impl MockTimeSource {
+    /// Create a new mock time source starting at the given time.
+    pub fn new(time: BlockTime) -> Self {
+        Self {
+            time: Rc::new(Cell::new(time)),
+        }
+    }
+
+    /// Current time.
+    pub fn get(&self) -> BlockTime {
+        self.time.get()
+    }
+
+    /// Set the current time.
+    pub fn set(&self, time: BlockTime) {
+        self.time.set(time);
+    }
+
+    /// Advance the round by the given number of rounds and the timestamp by the given number of
+    /// seconds.
+    pub fn advance(&self, rounds: u64, seconds: u64) {
+        let mut time = self.get();
+        time.round += rounds;
+        time.timestamp += seconds;
+        self.set(time);
+    }
+
+    /// Set the current epoch.
+    pub fn set_epoch(&self, epoch: EpochTime) {
+        let mut time = self.get();
+        time.epoch = epoch;
+        self.set(time);
+    }
+}
+
+impl TimeSource for MockTimeSource {
+    fn round(&self) -> u64 {
+        self.get().round
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.get().timestamp
+    }
+
+    fn epoch(&self) -> EpochTime {
+        self.get().epoch
+    }
+}
@@ -0,0 +1,613 @@
+//! Wire format test vectors.
+//!
+//! Canonical CBOR encodings of addresses, calls, transactions and events that are shared with the
+//! Go and TypeScript client SDKs. Every SDK decodes each vector into its own types, encodes it
+//! again and checks that the result is identical and matches the expected properties. This way a
+//! wire format change in one SDK is caught before it breaks clients written in other languages.
+//!
+//! Vectors are exchanged as a JSON array of objects with the following fields:
+//!
+//! * `kind` is one of `address`, `call`, `transaction` or `event`.
+//! * `name` is a human-readable description of the vector.
+//! * `encoded` is the Base64-encoded canonical CBOR encoding.
+//! * `expected` holds the kind-specific properties that the decoded value must have.
+use std::{fmt, str::FromStr};
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::{
+    core::common::crypto::{mrae::deoxysii, x25519},
+    event::Event as _,
+    modules::{self, accounts},
+    testing::keys,
+    types::{
+        address::{Address, SignatureAddressSpec},
+        callformat::{CallEnvelopeX25519DeoxysII, CallEnvelopeX25519XChaCha20Poly1305},
+        token,
+        transaction::{self, CallFormat},
+    },
+};
+
+/// Test vector errors.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("malformed test vectors: {0}")]
+    Malformed(String),
+
+    #[error("test vector '{name}': {reason}")]
+    Mismatch { name: String, reason: String },
+}
+
+/// Kind of the encoded value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// An `Address`.
+    Address,
+    /// A `transaction::Call`.
+    Call,
+    /// An unsigned `transaction::Transaction`.
+    Transaction,
+    /// The value of a single event, as included in the value of an event tag.
+    Event,
+}
+
+impl Kind {
+    /// Name of the kind as used in the JSON representation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Address => "address",
+            Self::Call => "call",
+            Self::Transaction => "transaction",
+            Self::Event => "event",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "address" => Ok(Self::Address),
+            "call" => Ok(Self::Call),
+            "transaction" => Ok(Self::Transaction),
+            "event" => Ok(Self::Event),
+            _ => Err(Error::Malformed(format!("unknown kind: {s}"))),
+        }
+    }
+}
+
+/// A single wire format test vector.
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    /// Kind of the encoded value.
+    pub kind: Kind,
+    /// Human-readable description.
+    pub name: String,
+    /// Canonical CBOR encoding.
+    pub encoded: Vec<u8>,
+    /// Kind-specific properties of the decoded value (a JSON object).
+    pub expected: Value,
+}
+
+impl TestVector {
+    fn new(kind: Kind, name: &str, encoded: Vec<u8>, expected: Value) -> Self {
+        Self {
+            kind,
+            name: name.to_owned(),
+            encoded,
+            expected,
+        }
+    }
+
+    /// JSON representation of the test vector.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "kind": self.kind.as_str(),
+            "name": self.name,
+            "encoded": base64::encode(&self.encoded),
+            "expected": self.expected,
+        })
+    }
+
+    /// Parse a test vector from its JSON representation.
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .ok_or_else(|| Error::Malformed(format!("missing field '{name}'")))
+        };
+        let string = |name: &str| {
+            field(name)?
+                .as_str()
+                .ok_or_else(|| Error::Malformed(format!("field '{name}' is not a string")))
+        };
+
+        let expected = field("expected")?;
+        if !expected.is_object() {
+            return Err(Error::Malformed(
+                "field 'expected' is not an object".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            kind: string("kind")?.parse()?,
+            name: string("name")?.to_owned(),
+            encoded: base64::decode(string("encoded")?)
+                .map_err(|err| Error::Malformed(format!("field 'encoded': {err}")))?,
+            expected: expected.clone(),
+        })
+    }
+
+    /// Verify that the encoding is canonical and that the decoded value has the expected
+    /// properties.
+    pub fn verify(&self) -> Result<(), Error> {
+        match self.kind {
+            Kind::Address => {
+                let address: Address = self.roundtrip()?;
+                self.check(
+                    "bech32",
+                    self.expect_str("bech32")?,
+                    address.to_bech32().as_str(),
+                )?;
+
+                if self.expected.get("sigspec").is_some() {
+                    let sigspec: SignatureAddressSpec =
+                        cbor::from_slice(&self.expect_bytes("sigspec")?).map_err(|err| {
+                            self.mismatch(format!("malformed signature address spec: {err}"))
+                        })?;
+                    self.check("derived address", Address::from_sigspec(&sigspec), address)?;
+                }
+                if self.expected.get("module").is_some() {
+                    let derived = Address::from_module_raw(
+                        self.expect_str("module")?,
+                        &self.expect_bytes("module_kind")?,
+                    );
+                    self.check("derived address", derived, address)?;
+                }
+            }
+            Kind::Call => {
+                let call: transaction::Call = self.roundtrip()?;
+                self.check("format", self.expect_u64("format")?, call.format as u64)?;
+                self.check("method", self.expect_str("method")?, call.method.as_str())?;
+                self.check("read_only", self.expect_bool("read_only")?, call.read_only)?;
+            }
+            Kind::Transaction => {
+                let tx: transaction::Transaction = self.roundtrip()?;
+                let signer = tx
+                    .auth_info
+                    .signer_info
+                    .first()
+                    .ok_or_else(|| self.mismatch("transaction has no signers".to_owned()))?;
+
+                self.check(
+                    "method",
+                    self.expect_str("method")?,
+                    tx.call.method.as_str(),
+                )?;
+                self.check(
+                    "signer",
+                    self.expect_str("signer")?,
+                    signer.address_spec.address().to_bech32().as_str(),
+                )?;
+                self.check("nonce", self.expect_u64("nonce")?, signer.nonce)?;
+                self.check("gas", self.expect_u64("gas")?, tx.auth_info.fee.gas)?;
+            }
+            Kind::Event => {
+                let _: cbor::Value = self.roundtrip()?;
+                let code: u32 = self
+                    .expect_u64("code")?
+                    .try_into()
+                    .map_err(|_| self.mismatch("event code out of range".to_owned()))?;
+                let key = [self.expect_str("module")?.as_bytes(), &code.to_be_bytes()].concat();
+                self.check("key", self.expect_bytes("key")?, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn roundtrip<T: cbor::Decode + cbor::Encode + Clone>(&self) -> Result<T, Error> {
+        let value: T = cbor::from_slice(&self.encoded)
+            .map_err(|err| self.mismatch(format!("failed to decode: {err}")))?;
+        let encoded = cbor::to_vec(value.clone());
+        if encoded != self.encoded {
+            return Err(self.mismatch(format!(
+                "encoding is not canonical (re-encoded as {})",
+                base64::encode(&encoded)
+            )));
+        }
+        Ok(value)
+    }
+
+    fn check<T: PartialEq + fmt::Debug>(
+        &self,
+        what: &str,
+        expected: T,
+        actual: T,
+    ) -> Result<(), Error> {
+        if expected != actual {
+            return Err(self.mismatch(format!(
+                "{what} mismatch (expected {expected:?}, got {actual:?})"
+            )));
+        }
+        Ok(())
+    }
+
+    fn mismatch(&self, reason: String) -> Error {
+        Error::Mismatch {
+            name: self.name.clone(),
+            reason,
+        }
+    }
+
+    fn expect(&self, name: &str) -> Result<&Value, Error> {
+        self.expected
+            .get(name)
+            .ok_or_else(|| self.mismatch(format!("missing expected property '{name}'")))
+    }
+
+    fn expect_str(&self, name: &str) -> Result<&str, Error> {
+        self.expect(name)?
+            .as_str()
+            .ok_or_else(|| self.mismatch(format!("expected property '{name}' is not a string")))
+    }
+
+    fn expect_u64(&self, name: &str) -> Result<u64, Error> {
+        self.expect(name)?
+            .as_u64()
+            .ok_or_else(|| self.mismatch(format!("expected property '{name}' is not a number")))
+    }
+
+    fn expect_bool(&self, name: &str) -> Result<bool, Error> {
+        self.expect(name)?
+            .as_bool()
+            .ok_or_else(|| self.mismatch(format!("expected property '{name}' is not a boolean")))
+    }
+
+    fn expect_bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        base64::decode(self.expect_str(name)?)
+            .map_err(|err| self.mismatch(format!("expected property '{name}': {err}")))
+    }
+}
+
+/// Encode the given test vectors as a JSON document.
+pub fn to_json(vectors: &[TestVector]) -> String {
+    let vectors: Vec<Value> = vectors.iter().map(TestVector::to_json).collect();
+    serde_json::to_string_pretty(&vectors).unwrap()
+}
+
+/// Parse test vectors from a JSON document.
+pub fn from_json(data: &str) -> Result<Vec<TestVector>, Error> {
+    let value: Value =
+        serde_json::from_str(data).map_err(|err| Error::Malformed(err.to_string()))?;
+    value
+        .as_array()
+        .ok_or_else(|| Error::Malformed("document is not an array".to_owned()))?
+        .iter()
+        .map(TestVector::from_json)
+        .collect()
+}
+
+/// Verify all of the given test vectors.
+pub fn verify(vectors: &[TestVector]) -> Result<(), Error> {
+    vectors.iter().try_for_each(TestVector::verify)
+}
+
+/// Generate the test vectors for the current wire format.
+pub fn generate() -> Vec<TestVector> {
+    let mut vectors = vec![];
+    vectors.extend(address_vectors());
+    vectors.extend(call_vectors());
+    vectors.extend(transaction_vectors());
+    vectors.extend(event_vectors());
+    vectors
+}
+
+fn address_vectors() -> Vec<TestVector> {
+    let signers = [
+        ("alice", keys::alice::sigspec()),
+        ("bob", keys::bob::sigspec()),
+        ("charlie", keys::charlie::sigspec()),
+        ("dave", keys::dave::sigspec()),
+        ("erin", keys::erin::sigspec()),
+        ("frank", keys::frank::sigspec()),
+        ("grace", keys::grace::sigspec()),
+    ];
+    let modules: [(&str, &[u8]); 3] = [
+        ("accounts", b"common-pool"),
+        ("accounts", b"fee-accumulator"),
+        ("contracts", &42u64.to_be_bytes()),
+    ];
+
+    let signers = signers.into_iter().map(|(name, sigspec)| {
+        let address = Address::from_sigspec(&sigspec);
+        TestVector::new(
+            Kind::Address,
+            &format!("signer {name}"),
+            cbor::to_vec(address),
+            json!({
+                "bech32": address.to_bech32(),
+                "sigspec": base64::encode(cbor::to_vec(sigspec)),
+            }),
+        )
+    });
+    let modules = modules.into_iter().map(|(module, kind)| {
+        let address = Address::from_module_raw(module, kind);
+        TestVector::new(
+            Kind::Address,
+            &format!("module {module} {}", hex::encode(kind)),
+            cbor::to_vec(address),
+            json!({
+                "bech32": address.to_bech32(),
+                "module": module,
+                "module_kind": base64::encode(kind),
+            }),
+        )
+    });
+    signers.chain(modules).collect()
+}
+
+fn transfer_body(amount: u128, denomination: token::Denomination) -> cbor::Value {
+    cbor::to_value(accounts::types::Transfer {
+        to: keys::bob::address(),
+        amount: token::BaseUnits::new(amount, denomination),
+    })
+}
+
+fn envelope_pk() -> x25519::PublicKey {
+    x25519::PublicKey(x25519_dalek::PublicKey::from([1; 32]))
+}
+
+fn calls() -> Vec<(&'static str, transaction::Call)> {
+    vec![
+        (
+            "plain transfer",
+            transaction::Call {
+                format: CallFormat::Plain,
+                method: "accounts.Transfer".to_owned(),
+                body: transfer_body(1_000, token::Denomination::NATIVE),
+                ..Default::default()
+            },
+        ),
+        (
+            "plain read-only query",
+            transaction::Call {
+                format: CallFormat::Plain,
+                method: "accounts.Nonce".to_owned(),
+                body: cbor::to_value(accounts::types::NonceQuery {
+                    address: keys::alice::address(),
+                }),
+                read_only: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "encrypted x25519-deoxysii",
+            transaction::Call {
+                format: CallFormat::EncryptedX25519DeoxysII,
+                method: String::new(),
+                body: cbor::to_value(CallEnvelopeX25519DeoxysII {
+                    pk: envelope_pk(),
+                    nonce: [2; deoxysii::NONCE_SIZE],
+                    epoch: 42,
+                    data: vec![3; 32],
+                }),
+                ..Default::default()
+            },
+        ),
+        (
+            "encrypted x25519-xchacha20poly1305",
+            transaction::Call {
+                format: CallFormat::EncryptedX25519XChaCha20Poly1305,
+                method: String::new(),
+                body: cbor::to_value(CallEnvelopeX25519XChaCha20Poly1305 {
+                    pk: envelope_pk(),
+                    epoch: 42,
+                    data: vec![3; 32],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+fn call_vectors() -> Vec<TestVector> {
+    calls()
+        .into_iter()
+        .map(|(name, call)| {
+            let expected = json!({
+                "format": call.format as u8,
+                "method": call.method,
+                "read_only": call.read_only,
+            });
+            TestVector::new(Kind::Call, name, cbor::to_vec(call), expected)
+        })
+        .collect()
+}
+
+fn transaction_vectors() -> Vec<TestVector> {
+    let test_denomination: token::Denomination = "TEST".parse().unwrap();
+    let encrypted = calls().remove(2).1;
+
+    let txs = [
+        (
+            "ed25519 signer transfer",
+            keys::alice::sigspec(),
+            0,
+            transaction::Call {
+                method: "accounts.Transfer".to_owned(),
+                body: transfer_body(1_000, token::Denomination::NATIVE),
+                ..Default::default()
+            },
+            transaction::Fee {
+                amount: token::BaseUnits::new(100, token::Denomination::NATIVE),
+                gas: 1_000,
+                consensus_messages: 0,
+            },
+            (None, None),
+        ),
+        (
+            "secp256k1eth signer transfer with custom fee denomination",
+            keys::dave::sigspec(),
+            7,
+            transaction::Call {
+                method: "accounts.Transfer".to_owned(),
+                body: transfer_body(u64::MAX as u128 + 1, test_denomination.clone()),
+                ..Default::default()
+            },
+            transaction::Fee {
+                amount: token::BaseUnits::new(1, test_denomination),
+                gas: 50_000,
+                consensus_messages: 1,
+            },
+            (None, None),
+        ),
+        (
+            "sr25519 signer transfer with validity range",
+            keys::frank::sigspec(),
+            u64::MAX,
+            transaction::Call {
+                method: "accounts.Transfer".to_owned(),
+                body: transfer_body(0, token::Denomination::NATIVE),
+                ..Default::default()
+            },
+            transaction::Fee {
+                amount: Default::default(),
+                gas: 0,
+                consensus_messages: 0,
+            },
+            (Some(10), Some(20)),
+        ),
+        (
+            "ed25519 signer encrypted call",
+            keys::charlie::sigspec(),
+            1,
+            encrypted,
+            transaction::Fee {
+                amount: token::BaseUnits::new(100, token::Denomination::NATIVE),
+                gas: 1_000,
+                consensus_messages: 0,
+            },
+            (None, None),
+        ),
+    ];
+
+    txs.into_iter()
+        .map(
+            |(name, signer, nonce, call, fee, (not_before, not_after))| {
+                let expected = json!({
+                    "method": call.method,
+                    "signer": Address::from_sigspec(&signer).to_bech32(),
+                    "nonce": nonce,
+                    "gas": fee.gas,
+                });
+                let tx = transaction::Transaction {
+                    version: transaction::LATEST_TRANSACTION_VERSION,
+                    call,
+                    auth_info: transaction::AuthInfo {
+                        signer_info: vec![transaction::SignerInfo::new_sigspec(signer, nonce)],
+                        fee,
+                        not_before,
+                        not_after,
+                        ..Default::default()
+                    },
+                };
+                TestVector::new(Kind::Transaction, name, cbor::to_vec(tx), expected)
+            },
+        )
+        .collect()
+}
+
+fn event_vector<E: crate::event::Event>(name: &str, event: E) -> TestVector {
+    let (module, code) = (E::module_name(), event.code());
+    let tag = event.into_event_tag();
+    TestVector::new(
+        Kind::Event,
+        name,
+        cbor::to_vec(tag.value),
+        json!({
+            "module": module,
+            "code": code,
+            "key": base64::encode(tag.key),
+        }),
+    )
+}
+
+fn event_vectors() -> Vec<TestVector> {
+    let amount = token::BaseUnits::new(1_000, token::Denomination::NATIVE);
+    vec![
+        event_vector(
+            "accounts.Transfer",
+            accounts::Event::Transfer {
+                from: keys::alice::address(),
+                to: keys::bob::address(),
+                amount: amount.clone(),
+            },
+        ),
+        event_vector(
+            "accounts.Burn",
+            accounts::Event::Burn {
+                owner: keys::alice::address(),
+                amount: amount.clone(),
+            },
+        ),
+        event_vector(
+            "accounts.Mint",
+            accounts::Event::Mint {
+                owner: keys::alice::address(),
+                amount,
+            },
+        ),
+        event_vector("core.GasUsed", modules::core::Event::GasUsed { amount: 42 }),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors_verify() {
+        let vectors = generate();
+        verify(&vectors).expect("generated vectors should verify");
+
+        let decoded = from_json(&to_json(&vectors)).expect("vectors should round-trip via JSON");
+        assert_eq!(decoded.len(), vectors.len());
+        verify(&decoded).expect("decoded vectors should verify");
+    }
+
+    #[test]
+    fn test_vectors_mismatch() {
+        let mut vectors = generate();
+
+        // Non-canonical encoding (indefinite length map) of an otherwise valid value.
+        let call = vectors.iter_mut().find(|v| v.kind == Kind::Call).unwrap();
+        let mut encoded = call.encoded.clone();
+        assert_eq!(encoded[0] & 0xe0, 0xa0, "call should be encoded as a map");
+        encoded[0] = 0xbf;
+        encoded.push(0xff);
+        call.encoded = encoded;
+        assert!(matches!(call.verify(), Err(Error::Mismatch { .. })));
+
+        // Wrong expected properties.
+        let address = vectors
+            .iter_mut()
+            .find(|v| v.kind == Kind::Address)
+            .unwrap();
+        address.expected["bech32"] = json!(keys::bob::address().to_bech32());
+        assert!(matches!(address.verify(), Err(Error::Mismatch { .. })));
+
+        assert!(matches!(
+            from_json(r#"[{"kind": "unknown", "name": "", "encoded": "", "expected": {}}]"#),
+            Err(Error::Malformed(_))
+        ));
+    }
+}
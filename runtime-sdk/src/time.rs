@@ -0,0 +1,51 @@
+//! Deterministic time source.
+//!
+//! Modules must never use host time as it differs between nodes. All notions of time available
+//! to a runtime are derived from the last runtime block and the consensus layer and are exposed
+//! through the `TimeSource` available via `Context::time`.
+use oasis_core_runtime::consensus::{beacon::EpochTime, roothash};
+
+/// Source of deterministic time for the runtime.
+pub trait TimeSource {
+    /// Current runtime round. This is a monotonically increasing counter.
+    fn round(&self) -> u64;
+
+    /// Timestamp of the last runtime block (in seconds since the UNIX epoch).
+    fn timestamp(&self) -> u64;
+
+    /// Current consensus layer epoch.
+    fn epoch(&self) -> EpochTime;
+}
+
+/// Time source derived from the last runtime block header and the current epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockTime {
+    pub round: u64,
+    pub timestamp: u64,
+    pub epoch: EpochTime,
+}
+
+impl BlockTime {
+    /// Create a time source from the given runtime block header and epoch.
+    pub fn new(header: &roothash::Header, epoch: EpochTime) -> Self {
+        Self {
+            round: header.round,
+            timestamp: header.timestamp,
+            epoch,
+        }
+    }
+}
+
+impl TimeSource for BlockTime {
+    fn round(&self) -> u64 {
+        self.round
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn epoch(&self) -> EpochTime {
+        self.epoch
+    }
+}
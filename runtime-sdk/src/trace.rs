@@ -0,0 +1,170 @@
+//! Structured tracing of the dispatch process.
+//!
+//! When the `tracing` feature is enabled, spans are emitted via the `tracing` crate so that node
+//! operators can install a subscriber (e.g. an OpenTelemetry exporter) and diagnose slow paths.
+//! Without the feature all functions in this module compile to no-ops.
+//!
+//! Span names and fields are considered stable:
+//!
+//! * `execute_batch` (`round`) -- executing a batch of transactions.
+//! * `dispatch_tx` (`method`, `signer`, `gas_limit`, `gas_used`, `success`) -- dispatching a
+//!   transaction, including authentication and post-dispatch hooks.
+//! * `dispatch_call` (`method`) -- dispatching a (decoded) call to the handling module.
+//! * `dispatch_query` (`method`) -- dispatching a query.
+//! * `subcall` (`method`, `caller`, `depth`, `max_gas`, `gas_used`, `success`) -- performing a
+//!   subcall.
+//! * `module_hook` (`hook`) -- invoking a module hook (e.g. `authenticate_tx` or `end_block`).
+//! * `block_worker` (`worker`, `gas_used`) -- running a block worker.
+use crate::{subcall::SubcallInfo, types::transaction::Transaction};
+
+/// An entered span. The span is exited when the guard is dropped.
+#[must_use = "the span is exited when the guard is dropped"]
+pub struct Span {
+    #[cfg(feature = "tracing")]
+    inner: tracing::span::EnteredSpan,
+}
+
+impl Span {
+    #[cfg(feature = "tracing")]
+    fn enter(span: tracing::Span) -> Self {
+        Self {
+            inner: span.entered(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn disabled() -> Self {
+        Self {}
+    }
+
+    /// Record the amount of gas used.
+    pub fn record_gas_used(&self, gas_used: u64) {
+        #[cfg(feature = "tracing")]
+        self.inner.record("gas_used", gas_used);
+        #[cfg(not(feature = "tracing"))]
+        let _ = gas_used;
+    }
+
+    /// Record whether the operation was successful.
+    pub fn record_success(&self, success: bool) {
+        #[cfg(feature = "tracing")]
+        self.inner.record("success", success);
+        #[cfg(not(feature = "tracing"))]
+        let _ = success;
+    }
+}
+
+/// Enter the span for executing a batch of transactions.
+pub fn execute_batch(round: u64) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::info_span!("execute_batch", round))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = round;
+        Span::disabled()
+    }
+}
+
+/// Enter the span for dispatching the given transaction.
+pub fn dispatch_tx(tx: &Transaction) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        let signer = tx
+            .auth_info
+            .signer_info
+            .first()
+            .map(|si| tracing::field::display(si.address_spec.address()));
+        Span::enter(tracing::info_span!(
+            "dispatch_tx",
+            method = tx.call.method.as_str(),
+            signer,
+            gas_limit = tx.auth_info.fee.gas,
+            gas_used = tracing::field::Empty,
+            success = tracing::field::Empty,
+        ))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = tx;
+        Span::disabled()
+    }
+}
+
+/// Enter the span for dispatching a call to the given method.
+pub fn dispatch_call(method: &str) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::debug_span!("dispatch_call", method))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = method;
+        Span::disabled()
+    }
+}
+
+/// Enter the span for dispatching a query to the given method.
+pub fn dispatch_query(method: &str) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::info_span!("dispatch_query", method))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = method;
+        Span::disabled()
+    }
+}
+
+/// Enter the span for performing the given subcall at the given depth.
+pub fn subcall(info: &SubcallInfo, depth: u16) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::info_span!(
+            "subcall",
+            method = info.method.as_str(),
+            caller = %info.caller.address(),
+            depth,
+            max_gas = info.max_gas,
+            gas_used = tracing::field::Empty,
+            success = tracing::field::Empty,
+        ))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (info, depth);
+        Span::disabled()
+    }
+}
+
+/// Enter the span for invoking the given module hook.
+pub fn module_hook(hook: &'static str) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::debug_span!("module_hook", hook))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = hook;
+        Span::disabled()
+    }
+}
+
+/// Enter the span for running the given block worker.
+pub fn block_worker(worker: &'static str) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        Span::enter(tracing::debug_span!(
+            "block_worker",
+            worker,
+            gas_used = tracing::field::Empty,
+        ))
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = worker;
+        Span::disabled()
+    }
+}
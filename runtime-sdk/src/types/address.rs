@@ -2,6 +2,7 @@
 use std::{convert::TryFrom, fmt};
 
 use bech32::{self, FromBase32, ToBase32, Variant};
+use sha3::Digest as _;
 use thiserror::Error;
 
 use oasis_core_runtime::{
@@ -9,9 +10,15 @@ use oasis_core_runtime::{
     consensus::address::Address as ConsensusAddress,
 };
 
-use crate::crypto::{
-    multisig,
-    signature::{ed25519, secp256k1, sr25519, PublicKey},
+#[cfg(feature = "experimental-dilithium")]
+use crate::crypto::signature::dilithium;
+use crate::{
+    crypto::{
+        multisig,
+        signature::{bls12381, ed25519, secp256k1, secp256k1schnorr, sr25519, PublicKey},
+        webauthn,
+    },
+    schema::{Schema, SchemaRegistry, TypeKind, TypeSchema},
 };
 
 const ADDRESS_VERSION_SIZE: usize = 1;
@@ -26,6 +33,14 @@ pub const ADDRESS_V0_ED25519_CONTEXT: &[u8] = b"oasis-core/address: staking";
 pub const ADDRESS_V0_SECP256K1ETH_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: secp256k1eth";
 /// V0 Sr25519 address context.
 pub const ADDRESS_V0_SR25519_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: sr25519";
+/// V0 BLS12-381 address context.
+pub const ADDRESS_V0_BLS12381_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: bls12381";
+/// V0 Secp256k1 Schnorr (BIP-340) address context.
+pub const ADDRESS_V0_SECP256K1SCHNORR_CONTEXT: &[u8] =
+    b"oasis-runtime-sdk/address: secp256k1schnorr";
+/// V0 Dilithium3 address context.
+#[cfg(feature = "experimental-dilithium")]
+pub const ADDRESS_V0_DILITHIUM3_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: dilithium3";
 
 /// V0 module address context.
 pub const ADDRESS_V0_MODULE_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: module";
@@ -38,6 +53,12 @@ pub const ADDRESS_RUNTIME_V0_VERSION: u8 = 0;
 /// V0 multisig address context.
 pub const ADDRESS_V0_MULTISIG_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: multisig";
 
+/// V0 WebAuthn address context.
+pub const ADDRESS_V0_WEBAUTHN_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: webauthn";
+
+/// V0 ROFL origin address context.
+pub const ADDRESS_V0_ROFL_ORIGIN_CONTEXT: &[u8] = b"oasis-runtime-sdk/address: rofl origin";
+
 /// Human readable part for Bech32-encoded addresses.
 pub const ADDRESS_BECH32_HRP: &str = "oasis";
 
@@ -55,6 +76,26 @@ pub enum SignatureAddressSpec {
     /// Sr25519 address derivation.
     #[cbor(rename = "sr25519")]
     Sr25519(sr25519::PublicKey),
+
+    /// BLS12-381 address derivation.
+    #[cbor(rename = "bls12381")]
+    Bls12381(bls12381::PublicKey),
+
+    /// Secp256k1 Schnorr (BIP-340) address derivation from x-only public keys.
+    #[cbor(rename = "secp256k1schnorr")]
+    Secp256k1Schnorr(secp256k1schnorr::PublicKey),
+
+    /// Dilithium3 (post-quantum) address derivation.
+    #[cfg(feature = "experimental-dilithium")]
+    #[cbor(rename = "dilithium3")]
+    Dilithium3(dilithium::PublicKey),
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SignatureAddressSpec {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::types::json::encoded::serialize(self, serializer)
+    }
 }
 
 impl SignatureAddressSpec {
@@ -64,6 +105,10 @@ impl SignatureAddressSpec {
             Self::Ed25519(pk) => PublicKey::Ed25519(pk.clone()),
             Self::Secp256k1Eth(pk) => PublicKey::Secp256k1(pk.clone()),
             Self::Sr25519(pk) => PublicKey::Sr25519(pk.clone()),
+            Self::Bls12381(pk) => PublicKey::Bls12381(pk.clone()),
+            Self::Secp256k1Schnorr(pk) => PublicKey::Secp256k1Schnorr(pk.clone()),
+            #[cfg(feature = "experimental-dilithium")]
+            Self::Dilithium3(pk) => PublicKey::Dilithium3(pk.clone()),
         }
     }
 }
@@ -73,6 +118,8 @@ impl SignatureAddressSpec {
 pub enum Error {
     #[error("malformed address")]
     MalformedAddress,
+    #[error("invalid address checksum")]
+    InvalidChecksum,
 }
 
 /// An account address.
@@ -154,6 +201,22 @@ impl Address {
                 ADDRESS_V0_VERSION,
                 pk.as_bytes(),
             ),
+            SignatureAddressSpec::Bls12381(pk) => Address::new(
+                ADDRESS_V0_BLS12381_CONTEXT,
+                ADDRESS_V0_VERSION,
+                pk.as_bytes(),
+            ),
+            SignatureAddressSpec::Secp256k1Schnorr(pk) => Address::new(
+                ADDRESS_V0_SECP256K1SCHNORR_CONTEXT,
+                ADDRESS_V0_VERSION,
+                pk.as_bytes(),
+            ),
+            #[cfg(feature = "experimental-dilithium")]
+            SignatureAddressSpec::Dilithium3(pk) => Address::new(
+                ADDRESS_V0_DILITHIUM3_CONTEXT,
+                ADDRESS_V0_VERSION,
+                pk.as_bytes(),
+            ),
         }
     }
 
@@ -163,6 +226,12 @@ impl Address {
         Address::new(ADDRESS_V0_MULTISIG_CONTEXT, ADDRESS_V0_VERSION, &config_vec)
     }
 
+    /// Creates a new address from a WebAuthn credential configuration.
+    pub fn from_webauthn(config: webauthn::Config) -> Self {
+        let config_vec = cbor::to_vec(config);
+        Address::new(ADDRESS_V0_WEBAUTHN_CONTEXT, ADDRESS_V0_VERSION, &config_vec)
+    }
+
     /// Creates a new address from an Ethereum-compatible address.
     pub fn from_eth(eth_address: &[u8]) -> Self {
         Address::new(
@@ -256,12 +325,201 @@ impl cbor::Decode for Address {
     }
 }
 
+impl Schema for Address {
+    fn schema_name() -> String {
+        concat!(module_path!(), "::Address").to_string()
+    }
+
+    fn schema(_types: &mut SchemaRegistry) -> TypeSchema {
+        TypeSchema::sized(TypeKind::Bytes, Self::SIZE as u64)
+    }
+}
+
 impl From<Address> for ConsensusAddress {
     fn from(addr: Address) -> ConsensusAddress {
         ConsensusAddress::from(&addr.0)
     }
 }
 
+impl From<ConsensusAddress> for Address {
+    fn from(addr: ConsensusAddress) -> Address {
+        Address::from_bytes(addr.as_ref()).unwrap()
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bech32(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Address {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_bech32())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Address {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address = <String as serde::Deserialize>::deserialize(deserializer)?;
+        address.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Size of an Ethereum-compatible address in bytes.
+const ETH_ADDRESS_SIZE: usize = 20;
+
+/// An Ethereum-compatible address.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EthAddress([u8; ETH_ADDRESS_SIZE]);
+
+impl EthAddress {
+    /// Size of an Ethereum-compatible address in bytes.
+    pub const SIZE: usize = ETH_ADDRESS_SIZE;
+
+    /// Tries to create a new Ethereum-compatible address from raw bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        Ok(EthAddress(
+            data.try_into().map_err(|_| Error::MalformedAddress)?,
+        ))
+    }
+
+    /// Tries to parse a `0x`-prefixed hex-encoded Ethereum-compatible address. Mixed-case
+    /// addresses must have a valid EIP-55 checksum.
+    pub fn from_hex(data: &str) -> Result<Self, Error> {
+        let hex_data = data.strip_prefix("0x").ok_or(Error::MalformedAddress)?;
+        let address =
+            Self::from_bytes(&hex::decode(hex_data).map_err(|_| Error::MalformedAddress)?)?;
+
+        let is_lower = hex_data == hex_data.to_ascii_lowercase();
+        let is_upper = hex_data == hex_data.to_ascii_uppercase();
+        if !is_lower && !is_upper && address.to_checksum_hex() != data {
+            return Err(Error::InvalidChecksum);
+        }
+        Ok(address)
+    }
+
+    /// Converts the address to a `0x`-prefixed hex representation with an EIP-55 checksum.
+    pub fn to_checksum_hex(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = sha3::Keccak256::digest(lower.as_bytes());
+        let checksummed: String = lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                // Each hex character is checksummed by the corresponding nibble of the hash.
+                let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        format!("0x{checksummed}")
+    }
+
+    /// Derives the runtime account address corresponding to this Ethereum-compatible address.
+    pub fn to_address(&self) -> Address {
+        Address::from_eth(&self.0)
+    }
+}
+
+impl AsRef<[u8]> for EthAddress {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_checksum_hex())
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_checksum_hex())
+    }
+}
+
+impl std::str::FromStr for EthAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+impl cbor::Encode for EthAddress {
+    fn into_cbor_value(self) -> cbor::Value {
+        cbor::Value::ByteString(self.as_ref().to_vec())
+    }
+}
+
+impl cbor::Decode for EthAddress {
+    fn try_default() -> Result<Self, cbor::DecodeError> {
+        Ok(Default::default())
+    }
+
+    fn try_from_cbor_value(value: cbor::Value) -> Result<Self, cbor::DecodeError> {
+        match value {
+            cbor::Value::ByteString(data) => {
+                Self::from_bytes(&data).map_err(|_| cbor::DecodeError::UnexpectedType)
+            }
+            _ => Err(cbor::DecodeError::UnexpectedType),
+        }
+    }
+}
+
+/// An address in one of the supported text formats. The format is preserved so that addresses can
+/// be displayed back to users in the same form in which they were given.
+#[derive(Clone, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub enum AddressText {
+    /// Bech32-encoded runtime or consensus address.
+    #[cbor(rename = "bech32")]
+    Bech32(Address),
+    /// Hex-encoded Ethereum-compatible address.
+    #[cbor(rename = "eth")]
+    Eth(EthAddress),
+}
+
+impl AddressText {
+    /// Runtime account address corresponding to the given text address.
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Bech32(address) => *address,
+            Self::Eth(address) => address.to_address(),
+        }
+    }
+}
+
+impl fmt::Display for AddressText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bech32(address) => write!(f, "{address}"),
+            Self::Eth(address) => write!(f, "{address}"),
+        }
+    }
+}
+
+impl std::str::FromStr for AddressText {
+    type Err = Error;
+
+    /// Parses either a Bech32-encoded or a `0x`-prefixed hex-encoded Ethereum-compatible address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("0x") {
+            Ok(Self::Eth(EthAddress::from_hex(s)?))
+        } else {
+            Ok(Self::Bech32(Address::from_bech32(s)?))
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -292,6 +550,45 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_address_bls12381() {
+        let spec = SignatureAddressSpec::Bls12381(
+            "l/HTpzGX15QmlWOMT6msD8NojE+XdLkFoU46PxcbrFhsVeg/+Xoa7/s68ArbIsa7".into(),
+        );
+
+        let addr = Address::from_sigspec(&spec);
+        assert_eq!(
+            addr.to_bech32(),
+            "oasis1qzync8fdlcw57wgjry2ftefuvlywnff3jyw5tpyv"
+        );
+    }
+
+    #[test]
+    fn test_address_secp256k1schnorr() {
+        let spec = SignatureAddressSpec::Secp256k1Schnorr(
+            "+TCKAZJYwxBJNE+F+J1SKbUxyEWDb5mwhgHxE7zgNvk=".into(),
+        );
+
+        let addr = Address::from_sigspec(&spec);
+        assert_eq!(
+            addr.to_bech32(),
+            "oasis1qzkrj2pqrt7vxh5zjlkh4rxeuqq2en90rvn3tqaz"
+        );
+    }
+
+    #[cfg(feature = "experimental-dilithium")]
+    #[test]
+    fn test_address_dilithium3() {
+        let pk: Vec<u8> = (0..1952).map(|i| i as u8).collect();
+        let spec = SignatureAddressSpec::Dilithium3(dilithium::PublicKey::from_bytes(&pk).unwrap());
+
+        let addr = Address::from_sigspec(&spec);
+        assert_eq!(
+            addr.to_bech32(),
+            "oasis1qqq6hmfmw767w0cqnt9qa6yz5c5um8p22y7e8al7"
+        );
+    }
+
     #[test]
     fn test_address_multisig() {
         let config = multisig::Config {
@@ -395,6 +692,85 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_eth_address_hex() {
+        // Test vectors from EIP-55.
+        for checksummed in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = EthAddress::from_hex(checksummed).unwrap();
+            assert_eq!(address.to_checksum_hex(), checksummed);
+            assert_eq!(address.to_string(), checksummed);
+
+            // Single-case addresses carry no checksum.
+            let lower = format!("0x{}", checksummed[2..].to_ascii_lowercase());
+            let upper = format!("0x{}", checksummed[2..].to_ascii_uppercase());
+            assert_eq!(EthAddress::from_hex(&lower).unwrap(), address);
+            assert_eq!(EthAddress::from_hex(&upper).unwrap(), address);
+        }
+
+        assert!(matches!(
+            EthAddress::from_hex("0x5aaEb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            Err(Error::InvalidChecksum)
+        ));
+        for malformed in [
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            "0xzzAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        ] {
+            assert!(matches!(
+                EthAddress::from_hex(malformed),
+                Err(Error::MalformedAddress)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_address_text() {
+        let eth: AddressText = "0xDce075E1C39b1ae0b75D554558b6451A226ffe00"
+            .parse()
+            .unwrap();
+        assert!(matches!(eth, AddressText::Eth(_)));
+        assert_eq!(
+            eth.address().to_bech32(),
+            "oasis1qrk58a6j2qn065m6p06jgjyt032f7qucy5wqeqpt"
+        );
+        assert_eq!(
+            eth.to_string(),
+            "0xDce075E1C39b1ae0b75D554558b6451A226ffe00"
+        );
+
+        let bech32: AddressText = "oasis1qrk58a6j2qn065m6p06jgjyt032f7qucy5wqeqpt"
+            .parse()
+            .unwrap();
+        assert!(matches!(bech32, AddressText::Bech32(_)));
+        assert_eq!(bech32.address(), eth.address());
+        assert_eq!(
+            bech32.to_string(),
+            "oasis1qrk58a6j2qn065m6p06jgjyt032f7qucy5wqeqpt"
+        );
+
+        // The text format is preserved across serialization.
+        for text in [eth, bech32] {
+            let dec: AddressText = cbor::from_slice(&cbor::to_vec(text.clone())).unwrap();
+            assert_eq!(dec, text);
+        }
+
+        assert!("oasis1invalid".parse::<AddressText>().is_err());
+        assert!("0xinvalid".parse::<AddressText>().is_err());
+    }
+
+    #[test]
+    fn test_address_consensus_round_trip() {
+        let addr = keys::alice::address();
+        let consensus: ConsensusAddress = addr.into();
+        assert_eq!(Address::from(consensus), addr);
+        assert_eq!(addr.to_string().parse::<Address>().unwrap(), addr);
+    }
+
     #[test]
     fn test_address_raw() {
         let eth_address = hex::decode("dce075e1c39b1ae0b75d554558b6451a226ffe00").unwrap();
@@ -0,0 +1,199 @@
+//! ROFL application identifier.
+use std::{convert::TryFrom, fmt};
+
+use bech32::{self, FromBase32, ToBase32, Variant};
+use thiserror::Error;
+
+use oasis_core_runtime::common::crypto::hash::Hash;
+
+use crate::types::address::Address;
+
+const APP_ID_VERSION_SIZE: usize = 1;
+const APP_ID_DATA_SIZE: usize = 20;
+const APP_ID_SIZE: usize = APP_ID_VERSION_SIZE + APP_ID_DATA_SIZE;
+
+/// V0 identifier version.
+const APP_ID_V0_VERSION: u8 = 0;
+/// Creator/nonce identifier context.
+const APP_ID_CREATOR_NONCE_CONTEXT: &[u8] = b"oasis-sdk/rofl: creator nonce app id";
+
+/// Human readable part for Bech32-encoded application identifiers.
+pub const APP_ID_BECH32_HRP: &str = "rofl";
+
+/// Error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("malformed identifier")]
+    MalformedIdentifier,
+}
+
+/// ROFL application identifier.
+///
+/// The application identifier is similar to an address, but using its own separate namespace and
+/// derivation scheme as it is not meant to be used as an address.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AppId([u8; APP_ID_SIZE]);
+
+impl AppId {
+    /// Size of an application identifier in bytes.
+    pub const SIZE: usize = APP_ID_SIZE;
+
+    /// Creates a new v0 application identifier from a context and data.
+    fn new(ctx: &'static [u8], data: &[&[u8]]) -> Self {
+        let mut parts: Vec<&[u8]> = vec![ctx, &[APP_ID_V0_VERSION]];
+        parts.extend_from_slice(data);
+        let h = Hash::digest_bytes_list(&parts);
+
+        let mut a = [0; APP_ID_SIZE];
+        a[..APP_ID_VERSION_SIZE].copy_from_slice(&[APP_ID_V0_VERSION]);
+        a[APP_ID_VERSION_SIZE..].copy_from_slice(h.truncated(APP_ID_DATA_SIZE));
+
+        AppId(a)
+    }
+
+    /// Creates a new application identifier from the address of its creator and the nonce of the
+    /// transaction that created it.
+    pub fn from_creator_nonce(creator: &Address, nonce: u64) -> Self {
+        Self::new(
+            APP_ID_CREATOR_NONCE_CONTEXT,
+            &[creator.as_ref(), &nonce.to_be_bytes()],
+        )
+    }
+
+    /// Tries to create a new identifier from raw bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != APP_ID_SIZE {
+            return Err(Error::MalformedIdentifier);
+        }
+
+        let mut a = [0; APP_ID_SIZE];
+        a.copy_from_slice(data);
+
+        Ok(AppId(a))
+    }
+
+    /// Convert the identifier into raw bytes.
+    pub fn into_bytes(self) -> [u8; APP_ID_SIZE] {
+        self.0
+    }
+
+    /// Tries to create a new identifier from Bech32-encoded string.
+    pub fn from_bech32(data: &str) -> Result<Self, Error> {
+        let (hrp, data, variant) = bech32::decode(data).map_err(|_| Error::MalformedIdentifier)?;
+        if hrp != APP_ID_BECH32_HRP {
+            return Err(Error::MalformedIdentifier);
+        }
+        if variant != Variant::Bech32 {
+            return Err(Error::MalformedIdentifier);
+        }
+        let data: Vec<u8> =
+            FromBase32::from_base32(&data).map_err(|_| Error::MalformedIdentifier)?;
+
+        AppId::from_bytes(&data)
+    }
+
+    /// Converts an identifier to Bech32 representation.
+    pub fn to_bech32(self) -> String {
+        bech32::encode(APP_ID_BECH32_HRP, self.0.to_base32(), Variant::Bech32).unwrap()
+    }
+}
+
+impl AsRef<[u8]> for AppId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for AppId {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl From<&'static str> for AppId {
+    fn from(s: &'static str) -> AppId {
+        AppId::from_bech32(s).unwrap()
+    }
+}
+
+impl std::str::FromStr for AppId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bech32(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AppId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_bech32())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AppId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = <String as serde::Deserialize>::deserialize(deserializer)?;
+        id.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Debug for AppId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_bech32())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for AppId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_bech32())?;
+        Ok(())
+    }
+}
+
+impl cbor::Encode for AppId {
+    fn into_cbor_value(self) -> cbor::Value {
+        cbor::Value::ByteString(self.as_ref().to_vec())
+    }
+}
+
+impl cbor::Decode for AppId {
+    fn try_default() -> Result<Self, cbor::DecodeError> {
+        Ok(Default::default())
+    }
+
+    fn try_from_cbor_value(value: cbor::Value) -> Result<Self, cbor::DecodeError> {
+        match value {
+            cbor::Value::ByteString(data) => {
+                Self::from_bytes(&data).map_err(|_| cbor::DecodeError::UnexpectedType)
+            }
+            _ => Err(cbor::DecodeError::UnexpectedType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::keys;
+
+    #[test]
+    fn test_app_id_from_creator_nonce() {
+        let id = AppId::from_creator_nonce(&keys::alice::address(), 0);
+        assert_eq!(id.as_ref()[0], APP_ID_V0_VERSION);
+        assert_ne!(id, AppId::from_creator_nonce(&keys::alice::address(), 1));
+        assert_ne!(id, AppId::from_creator_nonce(&keys::bob::address(), 0));
+
+        let encoded = id.to_bech32();
+        assert!(encoded.starts_with("rofl1"));
+        assert_eq!(AppId::from_bech32(&encoded).unwrap(), id);
+        assert!(
+            AppId::from_bech32(&keys::alice::address().to_bech32()).is_err(),
+            "addresses should not decode as application identifiers"
+        );
+    }
+}
@@ -1,9 +1,16 @@
 //! Types related to call formats.
-use crate::core::common::crypto::{mrae::deoxysii, x25519};
+use crate::{
+    core::common::crypto::{mrae::deoxysii, x25519},
+    crypto::{mrae::xchacha20poly1305, signature::Signature},
+    types::{address::SignatureAddressSpec, transaction::CallResult},
+};
 
 /// Call data key pair ID domain separation context base.
 pub const CALL_DATA_KEY_PAIR_ID_CONTEXT_BASE: &[u8] = b"oasis-runtime-sdk/private: tx";
 
+/// Signed query signature domain separation context base.
+pub const SIGNED_QUERY_SIGNATURE_CONTEXT_BASE: &[u8] = b"oasis-runtime-sdk/signed-query: v0";
+
 /// A call envelope when using the EncryptedX25519DeoxysII format.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct CallEnvelopeX25519DeoxysII {
@@ -18,6 +25,45 @@ pub struct CallEnvelopeX25519DeoxysII {
     pub data: Vec<u8>,
 }
 
+/// Plaintext header of an encrypted call result.
+///
+/// The header allows clients to determine the outcome of a call without decrypting the result.
+/// It is authenticated as the additional data of the sealed result box so it cannot be altered
+/// without the result failing to decrypt.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+pub struct ResultHeader {
+    /// Whether the call was successful.
+    pub success: bool,
+    /// Module name of the failed call.
+    #[cbor(optional)]
+    pub module: String,
+    /// Error code of the failed call.
+    #[cbor(optional)]
+    pub code: u32,
+}
+
+impl ResultHeader {
+    /// Create a header describing the given call result.
+    pub fn new(result: &CallResult) -> Self {
+        match result {
+            CallResult::Failed { module, code, .. } => Self {
+                success: false,
+                module: module.clone(),
+                code: *code,
+            },
+            _ => Self {
+                success: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Additional data used when sealing the result box.
+    pub fn additional_data(&self) -> Vec<u8> {
+        cbor::to_vec(self.clone())
+    }
+}
+
 /// A result envelope when using the EncryptedX25519DeoxysII format.
 #[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
 pub struct ResultEnvelopeX25519DeoxysII {
@@ -25,4 +71,71 @@ pub struct ResultEnvelopeX25519DeoxysII {
     pub nonce: [u8; deoxysii::NONCE_SIZE],
     /// Encrypted call data.
     pub data: Vec<u8>,
+    /// Authenticated plaintext result header. Envelopes without a header use empty additional
+    /// data.
+    #[cbor(optional)]
+    pub header: Option<ResultHeader>,
+}
+
+/// A call envelope when using the EncryptedX25519XChaCha20Poly1305 format.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct CallEnvelopeX25519XChaCha20Poly1305 {
+    /// Caller's ephemeral public key used for X25519.
+    pub pk: x25519::PublicKey,
+    /// Nonce. Since XChaCha20-Poly1305 is not nonce-misuse resistant, it should be random.
+    pub nonce: [u8; xchacha20poly1305::NONCE_SIZE],
+    /// Epoch of the ephemeral runtime key.
+    #[cbor(optional)]
+    pub epoch: u64,
+    /// Encrypted call data.
+    pub data: Vec<u8>,
+}
+
+/// A result envelope when using the EncryptedX25519XChaCha20Poly1305 format.
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+pub struct ResultEnvelopeX25519XChaCha20Poly1305 {
+    /// Nonce.
+    pub nonce: [u8; xchacha20poly1305::NONCE_SIZE],
+    /// Encrypted call data.
+    pub data: Vec<u8>,
+    /// Authenticated plaintext result header. Envelopes without a header use empty additional
+    /// data.
+    #[cbor(optional)]
+    pub header: Option<ResultHeader>,
+}
+
+/// Authentication information of a signed query.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct QueryAuthInfo {
+    /// Signer of the query.
+    pub signer: SignatureAddressSpec,
+    /// Nonce of the signer's account. Queries signed with a nonce lower than the current account
+    /// nonce are rejected, allowing the signer to revoke any outstanding signed queries by
+    /// submitting a transaction.
+    pub nonce: u64,
+    /// Last round in which the signed query is valid.
+    pub expiration: u64,
+}
+
+/// Signed part of a signed query.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct SignedQueryBody {
+    /// Query method name.
+    pub method: String,
+    /// Query arguments.
+    pub args: cbor::Value,
+    /// Authentication information.
+    pub auth: QueryAuthInfo,
+}
+
+/// A query envelope authenticating the caller of a query that requires authentication.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct SignedQueryEnvelope {
+    /// CBOR-serialized `SignedQueryBody`.
+    pub body: Vec<u8>,
+    /// Signature over the body using the `SIGNED_QUERY_SIGNATURE_CONTEXT_BASE` chain context.
+    pub signature: Signature,
 }
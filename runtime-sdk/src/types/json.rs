@@ -0,0 +1,357 @@
+//! Canonical JSON mapping of SDK types.
+//!
+//! When the `serde` feature is enabled, transactions, events and the types of the core and
+//! accounts modules implement `serde::Serialize` using the mapping below. The same value always results
+//! in the same JSON document so that gateways and tools can compare encodings byte-for-byte.
+//!
+//! * Field and variant names are the same as in the CBOR encoding (e.g. a transaction's
+//!   `auth_info` is encoded as `ai`). Fields are always present, with missing optional values
+//!   encoded as `null`.
+//! * Byte strings (including hashes and public keys) are lowercase hex strings without a prefix.
+//! * 128-bit integers are decimal strings as JSON numbers cannot represent them exactly. Other
+//!   integers are JSON numbers.
+//! * Addresses are Bech32 strings.
+//! * Token amounts are `[amount, denomination]` pairs with the amount as a decimal string.
+//! * Arbitrary CBOR values (e.g. call bodies, call results and parameter values) are mapped
+//!   structurally: maps become objects, arrays become arrays, byte strings become hex strings and
+//!   simple values become booleans or `null`. Map keys that are not text strings are replaced by
+//!   the hex of their CBOR encoding and tags are omitted. As the schema of such values is not
+//!   known, their byte strings are never interpreted (e.g. an address in a call body is encoded
+//!   as hex and a 128-bit integer as the hex of its big-endian representation).
+//!
+//! The mapping is only defined for serialization. Types that have a lossless JSON representation
+//! (e.g. addresses and token amounts) also implement `serde::Deserialize`.
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::event;
+
+/// Serialize a byte string as hex.
+pub mod bytes {
+    use serde::Serializer;
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(value))
+    }
+}
+
+/// Serialize an optional byte string as hex or `null`.
+pub mod option_bytes {
+    use serde::Serializer;
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&hex::encode(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serialize a sequence of byte strings as an array of hex strings.
+pub mod bytes_seq {
+    use serde::Serializer;
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(hex::encode))
+    }
+}
+
+/// Serialize a 128-bit integer as a decimal string.
+pub mod u128_string {
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+}
+
+/// Serialize a map of 128-bit integers as an object of decimal strings.
+pub mod u128_map {
+    use std::collections::BTreeMap;
+
+    use serde::{Serialize, Serializer};
+
+    pub fn serialize<K: Serialize, S: Serializer>(
+        value: &BTreeMap<K, u128>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(value.iter().map(|(k, v)| (k, v.to_string())))
+    }
+}
+
+/// Serialize an arbitrary CBOR value using the structural mapping.
+pub mod value {
+    use serde::{Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &cbor::Value, serializer: S) -> Result<S::Ok, S::Error> {
+        super::Value(value).serialize(serializer)
+    }
+}
+
+/// Serialize an optional CBOR value using the structural mapping or as `null`.
+pub mod option_value {
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<cbor::Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&super::Value(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serialize a value by applying the structural mapping to its CBOR encoding.
+///
+/// Only appropriate for types whose encoding contains no addresses or 128-bit integers (e.g.
+/// public keys and signatures) as those would not follow the canonical mapping.
+pub mod encoded {
+    use serde::{Serialize, Serializer};
+
+    pub fn serialize<T: cbor::Encode + Clone, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::Value(&cbor::to_value(value.clone())).serialize(serializer)
+    }
+}
+
+/// Serialize an optional value by applying the structural mapping to its CBOR encoding or as
+/// `null`.
+pub mod option_encoded {
+    use serde::Serializer;
+
+    pub fn serialize<T: cbor::Encode + Clone, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&super::Value(&cbor::to_value(value.clone()))),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Wrapper serializing an arbitrary CBOR value using the structural mapping.
+pub struct Value<'a>(pub &'a cbor::Value);
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            cbor::Value::Unsigned(v) => serializer.serialize_u64(*v),
+            cbor::Value::Negative(v) => serializer.serialize_i64(*v),
+            cbor::Value::ByteString(v) => serializer.serialize_str(&hex::encode(v)),
+            cbor::Value::TextString(v) => serializer.serialize_str(v),
+            cbor::Value::Array(v) => serializer.collect_seq(v.iter().map(Value)),
+            cbor::Value::Map(v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (key, value) in v {
+                    match key {
+                        cbor::Value::TextString(key) => map.serialize_entry(key, &Value(value))?,
+                        key => map.serialize_entry(
+                            &hex::encode(cbor::to_vec(key.clone())),
+                            &Value(value),
+                        )?,
+                    }
+                }
+                map.end()
+            }
+            cbor::Value::Tag(_, v) => Value(v).serialize(serializer),
+            cbor::Value::Simple(cbor::SimpleValue::FalseValue) => serializer.serialize_bool(false),
+            cbor::Value::Simple(cbor::SimpleValue::TrueValue) => serializer.serialize_bool(true),
+            cbor::Value::Simple(cbor::SimpleValue::NullValue)
+            | cbor::Value::Simple(cbor::SimpleValue::Undefined) => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Wrapper serializing a module event together with the module that emitted it and its code.
+///
+/// ```json
+/// {"module": "accounts", "code": 1, "value": {"from": "oasis1...", ...}}
+/// ```
+pub struct Event<'a, E>(pub &'a E);
+
+impl<E: event::Event + Serialize> Serialize for Event<'_, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Event", 3)?;
+        state.serialize_field("module", E::module_name())?;
+        state.serialize_field("code", &self.0.code())?;
+        state.serialize_field("value", self.0)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        modules::{accounts, core},
+        testing::keys,
+        types::{token, transaction},
+    };
+
+    #[test]
+    fn test_value() {
+        let value = cbor::to_value(BTreeMap::from([
+            (
+                "bytes".to_owned(),
+                cbor::Value::ByteString(vec![0xde, 0xad]),
+            ),
+            ("neg".to_owned(), cbor::Value::Negative(-3)),
+            (
+                "list".to_owned(),
+                cbor::Value::Array(vec![
+                    cbor::Value::Unsigned(1),
+                    cbor::Value::Simple(cbor::SimpleValue::TrueValue),
+                    cbor::Value::Simple(cbor::SimpleValue::NullValue),
+                ]),
+            ),
+        ]));
+        assert_eq!(
+            serde_json::to_value(Value(&value)).unwrap(),
+            json!({"neg": -3, "list": [1, true, null], "bytes": "dead"}),
+        );
+
+        // Non-text keys are replaced by the hex of their encoding.
+        let value = cbor::Value::Map(vec![(
+            cbor::Value::Unsigned(1),
+            cbor::Value::TextString("one".to_owned()),
+        )]);
+        assert_eq!(
+            serde_json::to_string(&Value(&value)).unwrap(),
+            r#"{"01":"one"}"#
+        );
+    }
+
+    #[test]
+    fn test_transaction() {
+        let tx = transaction::Transaction {
+            version: transaction::LATEST_TRANSACTION_VERSION,
+            call: transaction::Call {
+                format: transaction::CallFormat::Plain,
+                method: "accounts.Transfer".to_owned(),
+                body: cbor::to_value(accounts::types::Transfer {
+                    to: keys::bob::address(),
+                    amount: token::BaseUnits::new(1_000, token::Denomination::NATIVE),
+                }),
+                read_only: false,
+                memo: Some(b"hi".to_vec()),
+            },
+            auth_info: transaction::AuthInfo {
+                signer_info: vec![transaction::SignerInfo::new_sigspec(
+                    keys::alice::sigspec(),
+                    7,
+                )],
+                fee: transaction::Fee {
+                    amount: token::BaseUnits::new(u128::MAX, token::Denomination::NATIVE),
+                    gas: 1_000,
+                    consensus_messages: 0,
+                },
+                conflicts: vec![keys::charlie::address()],
+                ..Default::default()
+            },
+        };
+
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["v"], json!(1));
+        assert_eq!(json["call"]["format"], json!(0));
+        assert_eq!(json["call"]["method"], json!("accounts.Transfer"));
+        assert_eq!(json["call"]["ro"], json!(false));
+        assert_eq!(json["call"]["memo"], json!("6869"));
+        assert_eq!(
+            json["call"]["body"]["amount"],
+            json!(["03e8", ""]),
+            "call bodies should use the structural mapping"
+        );
+        assert_eq!(
+            json["ai"]["si"][0]["address_spec"]["signature"]["ed25519"],
+            json!(hex::encode(keys::alice::pk_ed25519().as_bytes())),
+        );
+        assert_eq!(json["ai"]["si"][0]["nonce"], json!(7));
+        assert_eq!(
+            json["ai"]["fee"]["amount"],
+            json!([u128::MAX.to_string(), ""])
+        );
+        assert_eq!(json["ai"]["not_before"], json!(null));
+        assert_eq!(json["ai"]["fee_payer"], json!(null));
+        assert_eq!(json["ai"]["after"], json!([]));
+        assert_eq!(
+            json["ai"]["conflicts"],
+            json!([keys::charlie::address().to_bech32()])
+        );
+
+        // The encoding is deterministic.
+        assert_eq!(
+            serde_json::to_string(&tx).unwrap(),
+            serde_json::to_string(&tx.clone()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_events() {
+        let event = accounts::Event::Transfer {
+            from: keys::alice::address(),
+            to: keys::bob::address(),
+            amount: token::BaseUnits::new(1_000, "TEST".parse().unwrap()),
+        };
+        assert_eq!(
+            serde_json::to_value(Event(&event)).unwrap(),
+            json!({
+                "module": "accounts",
+                "code": 1,
+                "value": {
+                    "from": keys::alice::address().to_bech32(),
+                    "to": keys::bob::address().to_bech32(),
+                    "amount": ["1000", "TEST"],
+                },
+            }),
+        );
+
+        let event = core::Event::Memo {
+            memo: b"hello".to_vec(),
+        };
+        assert_eq!(
+            serde_json::to_value(Event(&event)).unwrap(),
+            json!({"module": "core", "code": 2, "value": {"memo": "68656c6c6f"}}),
+        );
+    }
+
+    #[test]
+    fn test_module_types() {
+        let balances = accounts::types::AccountBalances {
+            balances: BTreeMap::from([
+                (token::Denomination::NATIVE, u128::MAX),
+                ("TEST".parse().unwrap(), 1),
+            ]),
+        };
+        assert_eq!(
+            serde_json::to_value(&balances).unwrap(),
+            json!({"balances": {"": u128::MAX.to_string(), "TEST": "1"}}),
+        );
+
+        let result = transaction::CallResult::Failed {
+            module: "accounts".to_owned(),
+            code: 2,
+            message: "insufficient balance".to_owned(),
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({"fail": {"module": "accounts", "code": 2, "message": "insufficient balance"}}),
+        );
+    }
+}
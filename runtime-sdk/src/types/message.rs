@@ -2,6 +2,8 @@ use std::fmt::Debug;
 
 use oasis_core_runtime::consensus;
 
+use crate::event::EventAttribution;
+
 /// Result of a message being processed by the consensus layer.
 pub type MessageEvent = consensus::roothash::MessageEvent;
 
@@ -11,6 +13,10 @@ pub type MessageEvent = consensus::roothash::MessageEvent;
 pub struct MessageEventHookInvocation {
     pub hook_name: String,
     pub payload: cbor::Value,
+    /// Attribution of the nested call that emitted the message. Only set for messages emitted by
+    /// nested calls when the runtime enables event attribution.
+    #[cbor(optional)]
+    pub origin: Option<EventAttribution>,
 }
 
 impl MessageEventHookInvocation {
@@ -19,6 +25,7 @@ impl MessageEventHookInvocation {
         Self {
             hook_name: name,
             payload: cbor::to_value(payload),
+            origin: None,
         }
     }
 }
@@ -30,3 +37,65 @@ pub struct MessageResult {
     pub event: MessageEvent,
     pub context: cbor::Value,
 }
+
+/// Result of a message being processed by the consensus layer with the result decoded into the
+/// type expected by the message result handler.
+#[derive(Clone, Debug)]
+pub struct TypedMessageEvent<T> {
+    /// The original message event.
+    pub event: MessageEvent,
+    /// Decoded result. Only set in case the message was successfully processed and the consensus
+    /// layer returned a result.
+    pub result: Option<T>,
+}
+
+impl<T> TypedMessageEvent<T> {
+    /// Whether the message was successfully processed.
+    pub fn is_success(&self) -> bool {
+        self.event.is_success()
+    }
+}
+
+/// A type that message result handlers can use to receive message events.
+///
+/// Message result handlers (see `#[handler(message_result = ...)]`) may take either the raw
+/// `MessageEvent` or a `TypedMessageEvent<T>` in which case the result is decoded as `T` before
+/// invoking the handler.
+pub trait FromMessageEvent: Sized {
+    /// Convert the given message event, returning it back unchanged in case it cannot be decoded.
+    fn from_message_event(event: MessageEvent) -> Result<Self, MessageEvent>;
+}
+
+impl FromMessageEvent for MessageEvent {
+    fn from_message_event(event: MessageEvent) -> Result<Self, MessageEvent> {
+        Ok(event)
+    }
+}
+
+impl<T: cbor::Decode> FromMessageEvent for TypedMessageEvent<T> {
+    fn from_message_event(event: MessageEvent) -> Result<Self, MessageEvent> {
+        let result = match event.result {
+            Some(ref result) if event.is_success() => match cbor::from_value(result.clone()) {
+                Ok(result) => Some(result),
+                Err(_) => return Err(event),
+            },
+            _ => None,
+        };
+
+        Ok(Self { event, result })
+    }
+}
+
+/// A message result that could not be processed by any of the message result handlers, either
+/// because no handler was registered under the hook name or because the result could not be
+/// decoded. Such results are persisted and retried at the start of each subsequent round.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[cbor(no_default)]
+pub struct PendingMessageResult {
+    /// Round in which the message result was first seen.
+    pub round: u64,
+    /// The message event.
+    pub event: MessageEvent,
+    /// Handler invocation registered when the message was emitted.
+    pub hook: MessageEventHookInvocation,
+}
@@ -1,7 +1,11 @@
 //! Types defined by the SDK.
 
 pub mod address;
+pub mod app_id;
 pub mod callformat;
+#[cfg(feature = "serde")]
+pub mod json;
 pub mod message;
+pub mod pagination;
 pub mod token;
 pub mod transaction;
@@ -0,0 +1,162 @@
+//! Pagination of query results.
+//!
+//! Queries that list an unbounded number of items accept a `PageRequest` and return a
+//! `PageResponse`. Clients fetch the next page by passing the returned `next` cursor in the
+//! subsequent request and stop once no cursor is returned.
+use oasis_runtime_sdk_macros::Schema;
+use thiserror::Error;
+
+use crate::storage::{Page, StoreKey};
+
+/// Number of items returned when the request does not specify a limit.
+pub const DEFAULT_LIMIT: u16 = 100;
+/// Maximum number of items returned in a single page.
+pub const MAX_LIMIT: u16 = 1000;
+
+/// Error.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid cursor")]
+    InvalidCursor,
+}
+
+/// A request for a page of query results.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PageRequest {
+    /// Opaque cursor returned in a previous response. In case it is not specified, the first page
+    /// is returned.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_bytes"))]
+    pub cursor: Option<Vec<u8>>,
+
+    /// Maximum number of items to return. Zero means `DEFAULT_LIMIT` and values above
+    /// `MAX_LIMIT` are capped.
+    #[cbor(optional)]
+    pub limit: u16,
+}
+
+impl PageRequest {
+    /// Effective maximum number of items to return.
+    pub fn limit(&self) -> usize {
+        usize::from(match self.limit {
+            0 => DEFAULT_LIMIT,
+            limit => limit.min(MAX_LIMIT),
+        })
+    }
+
+    /// Decode the cursor as a storage key to start iteration from.
+    pub fn start<K: StoreKey>(&self) -> Result<Option<K>, Error> {
+        self.cursor
+            .as_ref()
+            .map(|cursor| K::decode_key(cursor).ok_or(Error::InvalidCursor))
+            .transpose()
+    }
+}
+
+/// A page of query results.
+#[derive(Clone, Debug, Default, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PageResponse<T> {
+    /// Items in the page.
+    pub items: Vec<T>,
+
+    /// Cursor for fetching the next page. Not present in case this is the last page.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_bytes"))]
+    pub next: Option<Vec<u8>>,
+}
+
+impl<T> PageResponse<T> {
+    /// Convert a page of storage collection entries into a query response, mapping each entry
+    /// into a response item.
+    pub fn from_page<U, K: StoreKey>(page: Page<U, K>, f: impl FnMut(U) -> T) -> Self {
+        Self {
+            items: page.items.into_iter().map(f).collect(),
+            next: page.next.as_ref().map(StoreKey::encode_key),
+        }
+    }
+}
+
+/// Collect at most `limit` items from the given iterator over keyed items into a query response.
+///
+/// This allows paginating iterators that are not backed by a storage collection. The iterator
+/// should already be positioned at the requested cursor.
+pub fn paginate<K: StoreKey, T>(
+    iter: impl Iterator<Item = (K, T)>,
+    request: &PageRequest,
+) -> PageResponse<T> {
+    let limit = request.limit();
+    let mut items = Vec::new();
+    let mut next = None;
+    for (key, item) in iter {
+        if items.len() == limit {
+            next = Some(key.encode_key());
+            break;
+        }
+        items.push(item);
+    }
+    PageResponse { items, next }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        storage::{PrefixStore, StoreMap},
+        testing::mock::empty_store,
+    };
+
+    #[test]
+    fn test_limit() {
+        let req = |limit| PageRequest {
+            cursor: None,
+            limit,
+        };
+        assert_eq!(req(0).limit(), DEFAULT_LIMIT as usize);
+        assert_eq!(req(5).limit(), 5);
+        assert_eq!(req(u16::MAX).limit(), MAX_LIMIT as usize);
+    }
+
+    #[test]
+    fn test_paginate_store_map() {
+        let mut store = empty_store();
+        let mut map = StoreMap::<_, u32, u32>::new(PrefixStore::new(&mut store, b"test"));
+        for i in 0..5 {
+            map.insert(&i, i * 10);
+        }
+
+        let mut request = PageRequest {
+            cursor: None,
+            limit: 2,
+        };
+        let mut items = Vec::new();
+        loop {
+            let start = request.start::<u32>().unwrap();
+            let page =
+                PageResponse::from_page(map.page(start.as_ref(), request.limit()), |(_, v)| v);
+            assert!(page.items.len() <= 2);
+            items.extend(page.items);
+            match page.next {
+                Some(next) => request.cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(items, vec![0, 10, 20, 30, 40]);
+
+        // Plain iterators can be paginated directly.
+        let request = PageRequest {
+            cursor: None,
+            limit: 2,
+        };
+        let page = paginate(map.iter_from(&3), &request);
+        assert_eq!(page.items, vec![30, 40]);
+        assert_eq!(page.next, None);
+
+        let request = PageRequest {
+            cursor: Some(vec![1, 2, 3]),
+            limit: 0,
+        };
+        assert!(matches!(request.start::<u32>(), Err(Error::InvalidCursor)));
+    }
+}
@@ -1,8 +1,10 @@
 //! Token types.
 use std::{convert::TryFrom, fmt};
 
+use oasis_runtime_sdk_macros::Schema;
+
 /// Name/type of the token.
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, cbor::Encode)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, cbor::Encode, Schema)]
 #[cbor(transparent)]
 pub struct Denomination(Vec<u8>);
 
@@ -83,10 +85,24 @@ pub enum Error {
         Denomination::MAX_LENGTH
     )]
     NameTooLong { length: usize },
+    #[error("denomination mismatch: {0} and {1}")]
+    DenominationMismatch(Denomination, Denomination),
+    #[error("amount overflow")]
+    AmountOverflow,
+    #[error("amount underflow")]
+    AmountUnderflow,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("malformed amount")]
+    MalformedAmount,
+    #[error("conversion would lose precision")]
+    PrecisionLoss,
 }
 
 /// Token amount of given denomination in base units.
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, cbor::Encode, cbor::Decode)]
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, cbor::Encode, cbor::Decode, Schema,
+)]
 pub struct BaseUnits(pub u128, pub Denomination);
 
 impl BaseUnits {
@@ -104,6 +120,99 @@ impl BaseUnits {
     pub fn denomination(&self) -> &Denomination {
         &self.1
     }
+
+    fn ensure_same_denomination(&self, other: &BaseUnits) -> Result<(), Error> {
+        if self.1 != other.1 {
+            return Err(Error::DenominationMismatch(self.1.clone(), other.1.clone()));
+        }
+        Ok(())
+    }
+
+    /// Add two token amounts of the same denomination.
+    pub fn checked_add(&self, other: &BaseUnits) -> Result<BaseUnits, Error> {
+        self.ensure_same_denomination(other)?;
+        let amount = self.0.checked_add(other.0).ok_or(Error::AmountOverflow)?;
+        Ok(BaseUnits(amount, self.1.clone()))
+    }
+
+    /// Subtract a token amount of the same denomination.
+    pub fn checked_sub(&self, other: &BaseUnits) -> Result<BaseUnits, Error> {
+        self.ensure_same_denomination(other)?;
+        let amount = self.0.checked_sub(other.0).ok_or(Error::AmountUnderflow)?;
+        Ok(BaseUnits(amount, self.1.clone()))
+    }
+
+    /// Multiply the token amount by the given factor.
+    pub fn checked_mul(&self, factor: u128) -> Result<BaseUnits, Error> {
+        let amount = self.0.checked_mul(factor).ok_or(Error::AmountOverflow)?;
+        Ok(BaseUnits(amount, self.1.clone()))
+    }
+
+    /// Divide the token amount by the given divisor, rounding down.
+    pub fn checked_div(&self, divisor: u128) -> Result<BaseUnits, Error> {
+        let amount = self.0.checked_div(divisor).ok_or(Error::DivisionByZero)?;
+        Ok(BaseUnits(amount, self.1.clone()))
+    }
+
+    /// Convert the amount between representations using different numbers of decimals (e.g. for
+    /// bridging to an 18-decimal representation). Conversions that would lose precision fail.
+    pub fn rescale(&self, from_decimals: u8, to_decimals: u8) -> Result<BaseUnits, Error> {
+        let scale = |diff: u8| 10u128.checked_pow(diff.into()).ok_or(Error::AmountOverflow);
+
+        let amount = if to_decimals >= from_decimals {
+            self.0
+                .checked_mul(scale(to_decimals - from_decimals)?)
+                .ok_or(Error::AmountOverflow)?
+        } else {
+            let divisor = match scale(from_decimals - to_decimals) {
+                Ok(divisor) => divisor,
+                // Any non-zero amount is smaller than such a divisor.
+                Err(_) if self.0 == 0 => return Ok(self.clone()),
+                Err(_) => return Err(Error::PrecisionLoss),
+            };
+            if self.0 % divisor != 0 {
+                return Err(Error::PrecisionLoss);
+            }
+            self.0 / divisor
+        };
+        Ok(BaseUnits(amount, self.1.clone()))
+    }
+
+    /// Render the amount as a decimal number with the given number of decimals (e.g. `1.5` for
+    /// `1500` base units with 3 decimals). Trailing fractional zeros are omitted.
+    pub fn to_decimal_string(&self, decimals: u8) -> String {
+        let decimals = decimals as usize;
+        let digits = format!("{:0>width$}", self.0, width = decimals + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            integer.to_owned()
+        } else {
+            format!("{integer}.{fraction}")
+        }
+    }
+
+    /// Parse an amount given as a decimal number with at most the given number of decimals.
+    pub fn from_decimal_str(
+        value: &str,
+        decimals: u8,
+        denomination: Denomination,
+    ) -> Result<BaseUnits, Error> {
+        let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty()
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || fraction.len() > decimals as usize
+            || (value.contains('.') && fraction.is_empty())
+        {
+            return Err(Error::MalformedAmount);
+        }
+
+        let digits = format!("{integer}{fraction:0<width$}", width = decimals as usize);
+        let amount = digits.parse().map_err(|_| Error::AmountOverflow)?;
+        Ok(BaseUnits(amount, denomination))
+    }
 }
 
 impl fmt::Display for BaseUnits {
@@ -113,6 +222,42 @@ impl fmt::Display for BaseUnits {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Denomination {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = std::str::from_utf8(&self.0)
+            .map_err(|_| serde::ser::Error::custom("denomination is not valid UTF-8"))?;
+        serializer.serialize_str(name)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Denomination {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Token amounts are serialized as an `(amount, denomination)` pair where the amount is encoded as
+/// a decimal string as not all formats (e.g. JSON) can represent 128-bit integers.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BaseUnits {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&(self.0.to_string(), &self.1), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BaseUnits {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (amount, denomination): (String, Denomination) =
+            serde::Deserialize::deserialize(deserializer)?;
+        let amount = amount.parse().map_err(serde::de::Error::custom)?;
+        Ok(BaseUnits(amount, denomination))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -140,6 +285,111 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_checked_arithmetic() {
+        let test: Denomination = "test".parse().unwrap();
+        let a = BaseUnits::new(1_000, Denomination::NATIVE);
+        let b = BaseUnits::new(300, Denomination::NATIVE);
+
+        assert_eq!(
+            a.checked_add(&b).unwrap(),
+            BaseUnits::new(1_300, Denomination::NATIVE)
+        );
+        assert_eq!(
+            a.checked_sub(&b).unwrap(),
+            BaseUnits::new(700, Denomination::NATIVE)
+        );
+        assert!(matches!(b.checked_sub(&a), Err(Error::AmountUnderflow)));
+        assert!(matches!(
+            BaseUnits::new(u128::MAX, Denomination::NATIVE).checked_add(&b),
+            Err(Error::AmountOverflow)
+        ));
+        assert!(matches!(
+            a.checked_add(&BaseUnits::new(1, test.clone())),
+            Err(Error::DenominationMismatch(..))
+        ));
+        assert!(matches!(
+            a.checked_sub(&BaseUnits::new(1, test)),
+            Err(Error::DenominationMismatch(..))
+        ));
+
+        assert_eq!(
+            a.checked_mul(3).unwrap(),
+            BaseUnits::new(3_000, Denomination::NATIVE)
+        );
+        assert!(matches!(
+            a.checked_mul(u128::MAX),
+            Err(Error::AmountOverflow)
+        ));
+        assert_eq!(
+            a.checked_div(3).unwrap(),
+            BaseUnits::new(333, Denomination::NATIVE)
+        );
+        assert!(matches!(a.checked_div(0), Err(Error::DivisionByZero)));
+    }
+
+    #[test]
+    fn test_decimals() {
+        let cases = [
+            (0, 9, "0"),
+            (1, 9, "0.000000001"),
+            (1_500_000_000, 9, "1.5"),
+            (1_000_000_000, 9, "1"),
+            (123, 0, "123"),
+            (u128::MAX, 18, "340282366920938463463.374607431768211455"),
+        ];
+        for (amount, decimals, expected) in cases {
+            let units = BaseUnits::new(amount, Denomination::NATIVE);
+            assert_eq!(units.to_decimal_string(decimals), expected);
+            assert_eq!(
+                BaseUnits::from_decimal_str(expected, decimals, Denomination::NATIVE).unwrap(),
+                units,
+                "decimal representation should round-trip"
+            );
+        }
+
+        assert_eq!(
+            BaseUnits::from_decimal_str("1.50", 9, Denomination::NATIVE).unwrap(),
+            BaseUnits::new(1_500_000_000, Denomination::NATIVE)
+        );
+        for malformed in ["", ".5", "1.", "1.5.0", "-1", "1e9", " 1", "0.0000000001"] {
+            assert!(
+                matches!(
+                    BaseUnits::from_decimal_str(malformed, 9, Denomination::NATIVE),
+                    Err(Error::MalformedAmount)
+                ),
+                "{malformed:?} should be rejected"
+            );
+        }
+        assert!(matches!(
+            BaseUnits::from_decimal_str("340282366920938463464", 18, Denomination::NATIVE),
+            Err(Error::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_rescale() {
+        let units = BaseUnits::new(1_500_000_000, Denomination::NATIVE);
+        assert_eq!(
+            units.rescale(9, 18).unwrap(),
+            BaseUnits::new(1_500_000_000_000_000_000, Denomination::NATIVE)
+        );
+        assert_eq!(
+            units.rescale(9, 8).unwrap(),
+            BaseUnits::new(150_000_000, Denomination::NATIVE)
+        );
+        assert_eq!(units.rescale(9, 9).unwrap(), units);
+        assert!(matches!(units.rescale(9, 0), Err(Error::PrecisionLoss)));
+        assert!(matches!(units.rescale(0, 40), Err(Error::AmountOverflow)));
+        assert!(matches!(units.rescale(40, 0), Err(Error::PrecisionLoss)));
+        assert_eq!(
+            BaseUnits::new(0, Denomination::NATIVE)
+                .rescale(40, 0)
+                .unwrap(),
+            BaseUnits::new(0, Denomination::NATIVE)
+        );
+    }
+
     #[test]
     fn test_decoding_denomination() {
         macro_rules! assert_rountrip_ok {
@@ -1,15 +1,23 @@
 //! Transaction types.
+use std::{borrow::Cow, fmt};
+
 use anyhow::anyhow;
 use thiserror::Error;
 
+use oasis_core_runtime::common::crypto::hash::Hash;
+use oasis_runtime_sdk_macros::Schema;
+
 use crate::{
     crypto::{
         multisig,
-        signature::{self, PublicKey, Signature},
+        signature::{self, ed25519, PublicKey, Signature},
+        webauthn,
     },
+    module,
     types::{
         address,
         address::{Address, SignatureAddressSpec},
+        app_id::AppId,
         token,
     },
 };
@@ -18,6 +26,16 @@ use crate::{
 pub const SIGNATURE_CONTEXT_BASE: &[u8] = b"oasis-runtime-sdk/tx: v0";
 /// The latest transaction format version.
 pub const LATEST_TRANSACTION_VERSION: u16 = 1;
+/// Maximum size of a call memo (in bytes).
+pub const MAX_MEMO_SIZE: usize = 256;
+/// Maximum number of dependency hints (dependencies and conflicting accounts) of a transaction.
+pub const MAX_DEPENDENCY_HINTS: usize = 16;
+
+/// Format byte prefixed to Snappy-compressed transaction envelopes.
+///
+/// Uncompressed envelopes are CBOR-encoded arrays which always start with `0x82` so the formats
+/// cannot be confused.
+pub const ENVELOPE_FORMAT_SNAPPY: u8 = 0x01;
 
 /// Error.
 #[derive(Debug, Error)]
@@ -26,10 +44,56 @@ pub enum Error {
     UnsupportedVersion,
     #[error("malformed transaction: {0}")]
     MalformedTransaction(anyhow::Error),
+    #[error("oversized transaction")]
+    OversizedTransaction,
+}
+
+/// Compress an encoded `UnverifiedTransaction` envelope.
+pub fn compress_envelope(envelope: &[u8]) -> Vec<u8> {
+    let compressed = snap::raw::Encoder::new()
+        .compress_vec(envelope)
+        .expect("envelope size should be within Snappy limits");
+    [&[ENVELOPE_FORMAT_SNAPPY][..], &compressed].concat()
+}
+
+/// Size of the given raw transaction envelope after decompression, without decompressing it.
+///
+/// In case the envelope is malformed, the raw size is returned as decoding will fail anyway.
+pub fn envelope_size(raw: &[u8]) -> usize {
+    match raw.split_first() {
+        Some((&ENVELOPE_FORMAT_SNAPPY, data)) => {
+            snap::raw::decompress_len(data).unwrap_or(raw.len())
+        }
+        _ => raw.len(),
+    }
+}
+
+/// Decode a raw transaction envelope, decompressing it if needed. The size of the decompressed
+/// envelope is bounded by `max_size` and must match the size declared in the envelope.
+pub fn decode_envelope(raw: &[u8], max_size: usize) -> Result<Cow<'_, [u8]>, Error> {
+    let data = match raw.split_first() {
+        Some((&ENVELOPE_FORMAT_SNAPPY, data)) => data,
+        _ => return Ok(Cow::Borrowed(raw)),
+    };
+
+    let declared =
+        snap::raw::decompress_len(data).map_err(|e| Error::MalformedTransaction(e.into()))?;
+    if declared > max_size {
+        return Err(Error::OversizedTransaction);
+    }
+    let envelope = snap::raw::Decoder::new()
+        .decompress_vec(data)
+        .map_err(|e| Error::MalformedTransaction(e.into()))?;
+    if envelope.len() != declared {
+        return Err(Error::MalformedTransaction(anyhow!(
+            "decompressed envelope size mismatch"
+        )));
+    }
+    Ok(Cow::Owned(envelope))
 }
 
 /// A container for data that authenticates a transaction.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
 pub enum AuthProof {
     /// For _signature_ authentication.
     #[cbor(rename = "signature")]
@@ -37,30 +101,55 @@ pub enum AuthProof {
     /// For _multisig_ authentication.
     #[cbor(rename = "multisig")]
     Multisig(multisig::SignatureSetOwned),
+    /// For _WebAuthn_ authentication.
+    #[cbor(rename = "webauthn")]
+    WebAuthn(webauthn::Assertion),
     /// A flag to use module-controlled decoding. The string is an encoding scheme name that a
     /// module must handle. The scheme name must not be empty.
     #[cbor(rename = "module")]
     Module(String),
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for AuthProof {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::types::json::encoded::serialize(self, serializer)
+    }
+}
+
 /// An unverified signed transaction.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(no_default)]
-pub struct UnverifiedTransaction(pub Vec<u8>, pub Vec<AuthProof>);
+pub struct UnverifiedTransaction(
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))] pub Vec<u8>,
+    pub Vec<AuthProof>,
+);
 
 impl UnverifiedTransaction {
     /// Verify and deserialize the unverified transaction.
     pub fn verify(self) -> Result<Transaction, Error> {
+        let (body, entries) = self.verify_deferred()?;
+        ed25519::verify_batch(&entries).map_err(|e| Error::MalformedTransaction(e.into()))?;
+
+        Ok(body)
+    }
+
+    /// Deserialize the unverified transaction and verify all of its signatures except for Ed25519
+    /// signatures which are returned so that the caller can batch verify them (e.g. together with
+    /// signatures of other transactions) using `ed25519::verify_batch`.
+    pub fn verify_deferred(self) -> Result<(Transaction, Vec<ed25519::BatchEntry>), Error> {
         // Deserialize the inner body.
         let body: Transaction =
             cbor::from_slice(&self.0).map_err(|e| Error::MalformedTransaction(e.into()))?;
         body.validate_basic()?;
 
         // Basic structure validation.
-        if self.1.len() != body.auth_info.signer_info.len() {
+        let num_signers = body.auth_info.signers().count();
+        if self.1.len() != num_signers {
             return Err(Error::MalformedTransaction(anyhow!(
                 "unexpected number of auth proofs. expected {} but found {}",
-                body.auth_info.signer_info.len(),
+                num_signers,
                 self.1.len()
             )));
         }
@@ -69,28 +158,42 @@ impl UnverifiedTransaction {
         let ctx = signature::context::get_chain_context_for(SIGNATURE_CONTEXT_BASE);
         let mut public_keys = vec![];
         let mut signatures = vec![];
-        for (si, auth_proof) in body.auth_info.signer_info.iter().zip(self.1.iter()) {
+        for (si, auth_proof) in body.auth_info.signers().zip(self.1.iter()) {
+            // WebAuthn assertions sign over a challenge derived from the transaction instead of
+            // the transaction itself so they cannot be batch verified.
+            if let (AddressSpec::WebAuthn(config), AuthProof::WebAuthn(assertion)) =
+                (&si.address_spec, auth_proof)
+            {
+                config
+                    .verify(&webauthn::challenge(&ctx, &self.0), assertion)
+                    .map_err(|e| Error::MalformedTransaction(e.into()))?;
+                continue;
+            }
+
             let (mut batch_pks, mut batch_sigs) = si.address_spec.batch(auth_proof)?;
             public_keys.append(&mut batch_pks);
             signatures.append(&mut batch_sigs);
         }
-        PublicKey::verify_batch_multisig(&ctx, &self.0, &public_keys, &signatures)
+        let entries = PublicKey::prepare_batch_multisig(&ctx, &self.0, &public_keys, &signatures)
             .map_err(|e| Error::MalformedTransaction(e.into()))?;
 
-        Ok(body)
+        Ok((body, entries))
     }
 }
 
 /// Transaction.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(no_default)]
 pub struct Transaction {
     #[cbor(rename = "v")]
+    #[cfg_attr(feature = "serde", serde(rename = "v"))]
     pub version: u16,
 
     pub call: Call,
 
     #[cbor(rename = "ai")]
+    #[cfg_attr(feature = "serde", serde(rename = "ai"))]
     pub auth_info: AuthInfo,
 }
 
@@ -105,12 +208,127 @@ impl Transaction {
                 "transaction has no signers"
             )));
         }
+        if let Some(fee_payer) = self.auth_info.fee_payer_address() {
+            if fee_payer == self.auth_info.signer_info[0].address_spec.address() {
+                return Err(Error::MalformedTransaction(anyhow!(
+                    "fee payer must differ from the caller"
+                )));
+            }
+        }
+        if self.auth_info.after.len() + self.auth_info.conflicts.len() > MAX_DEPENDENCY_HINTS {
+            return Err(Error::MalformedTransaction(anyhow!(
+                "too many dependency hints"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Derive the signing preview of the transaction using the call rendering rules of the given
+    /// module(s). Calls without a rendering rule are shown as a hash of the call body.
+    pub fn preview<H: module::PreviewHandler>(&self) -> Preview {
+        let mut fields = vec![PreviewField::new("Method", &self.call.method)];
+
+        let body_fields = match self.call.format {
+            CallFormat::Plain => H::preview_call(&self.call.method, &self.call.body),
+            _ => {
+                fields.push(PreviewField::new("Format", "encrypted"));
+                None
+            }
+        };
+        match body_fields {
+            Some(mut body_fields) => fields.append(&mut body_fields),
+            None => fields.push(PreviewField::new(
+                "Body hash",
+                hex::encode(Hash::digest_bytes(&cbor::to_vec(self.call.body.clone()))),
+            )),
+        }
+        if self.call.read_only {
+            fields.push(PreviewField::new("Read-only", "yes"));
+        }
+        if let Some(memo) = &self.call.memo {
+            fields.push(PreviewField::new("Memo", String::from_utf8_lossy(memo)));
+        }
+
+        for (index, si) in self.auth_info.signer_info.iter().enumerate() {
+            let label = if index == 0 { "Caller" } else { "Signer" };
+            fields.push(PreviewField::new(label, si.address_spec.address()));
+        }
+        if let Some(fee_payer) = self.auth_info.fee_payer_address() {
+            fields.push(PreviewField::new("Fee payer", fee_payer));
+        }
+        if let Some(fee_granter) = self.auth_info.fee_granter {
+            fields.push(PreviewField::new("Fee granter", fee_granter));
+        }
+        fields.push(PreviewField::new("Fee", &self.auth_info.fee.amount));
+        fields.push(PreviewField::new("Gas limit", self.auth_info.fee.gas));
+        if self.auth_info.fee.consensus_messages > 0 {
+            fields.push(PreviewField::new(
+                "Consensus messages",
+                self.auth_info.fee.consensus_messages,
+            ));
+        }
+        if let Some(not_before) = self.auth_info.not_before {
+            fields.push(PreviewField::new("Valid from round", not_before));
+        }
+        if let Some(not_after) = self.auth_info.not_after {
+            fields.push(PreviewField::new("Valid until round", not_after));
+        }
+        for dependency in &self.auth_info.after {
+            fields.push(PreviewField::new("Executes after", hex::encode(dependency)));
+        }
+
+        Preview { fields }
+    }
+}
+
+/// A labeled line of a transaction signing preview.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreviewField {
+    /// Field label.
+    pub label: String,
+    /// Rendered field value.
+    pub value: String,
+}
+
+impl PreviewField {
+    /// Create a new preview field. Any characters in the value that are not printable ASCII are
+    /// escaped so that they can be shown on constrained displays and cannot spoof other fields.
+    pub fn new<T: fmt::Display>(label: &str, value: T) -> Self {
+        let value = value
+            .to_string()
+            .chars()
+            .map(|c| match c {
+                ' '..='~' if c != '\\' => c.to_string(),
+                c => c.escape_default().to_string(),
+            })
+            .collect();
+
+        Self {
+            label: label.to_owned(),
+            value,
+        }
+    }
+}
+
+/// A deterministic human-readable summary of a transaction that can be shown by signing devices
+/// instead of an opaque hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preview {
+    /// Preview fields in display order.
+    pub fields: Vec<PreviewField>,
+}
+
+impl fmt::Display for Preview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            writeln!(f, "{}: {}", field.label, field.value)?;
+        }
         Ok(())
     }
 }
 
 /// Format used for encoding the call (and output) information.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, cbor::Encode, cbor::Decode, Schema)]
 #[repr(u8)]
 #[cbor(with_default)]
 pub enum CallFormat {
@@ -118,6 +336,16 @@ pub enum CallFormat {
     Plain = 0,
     /// Encrypted call data using X25519 for key exchange and Deoxys-II for symmetric encryption.
     EncryptedX25519DeoxysII = 1,
+    /// Encrypted call data using X25519 for key exchange and XChaCha20-Poly1305 for symmetric
+    /// encryption.
+    EncryptedX25519XChaCha20Poly1305 = 2,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CallFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
 }
 
 impl Default for CallFormat {
@@ -127,7 +355,8 @@ impl Default for CallFormat {
 }
 
 /// Method call.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Call {
     /// Call format.
     #[cbor(optional)]
@@ -136,13 +365,23 @@ pub struct Call {
     #[cbor(optional)]
     pub method: String,
     /// Method body.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))]
     pub body: cbor::Value,
     /// Read-only flag.
     ///
     /// A read-only call cannot make any changes to runtime state. Any attempt at modifying state
     /// will result in the call failing.
     #[cbor(optional, rename = "ro")]
+    #[cfg_attr(feature = "serde", serde(rename = "ro"))]
     pub read_only: bool,
+    /// Optional memo (e.g. a payment reference).
+    ///
+    /// The memo is limited to `MAX_MEMO_SIZE` bytes, priced per byte and included in the events
+    /// emitted by the transaction. Memos of encrypted calls are not included in the events as that
+    /// would reveal confidential content.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::option_bytes"))]
+    pub memo: Option<Vec<u8>>,
 }
 
 impl Default for Call {
@@ -152,15 +391,18 @@ impl Default for Call {
             method: Default::default(),
             body: cbor::Value::Simple(cbor::SimpleValue::NullValue),
             read_only: false,
+            memo: None,
         }
     }
 }
 
 /// Transaction authentication information.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AuthInfo {
     /// Transaction signer information.
     #[cbor(rename = "si")]
+    #[cfg_attr(feature = "serde", serde(rename = "si"))]
     pub signer_info: Vec<SignerInfo>,
     /// Fee payment information.
     pub fee: Fee,
@@ -170,10 +412,45 @@ pub struct AuthInfo {
     /// Latest round when the transaction is valid.
     #[cbor(optional)]
     pub not_after: Option<u64>,
+    /// Optional signer that only authorizes fee payment. When set, fees are drawn from the fee
+    /// payer instead of the first signer which remains the caller. Its auth proof must follow the
+    /// auth proofs of all other signers. The fee payer's nonce is neither checked nor incremented
+    /// as the transaction is protected against replays by the nonces of the other signers.
+    #[cbor(optional)]
+    pub fee_payer: Option<SignerInfo>,
+    /// Optional account that has granted the fee payer an allowance from which the fee should be
+    /// paid (see the `feegrant` module). The transaction is rejected in case the allowance does
+    /// not cover the fee.
+    #[cbor(optional)]
+    pub fee_granter: Option<Address>,
+    /// Hashes of transactions that must have been successfully executed earlier in the same block
+    /// before this transaction can be executed. The hash of a transaction is the hash of its
+    /// serialized signed envelope. Transactions executed before their dependencies fail, but
+    /// still pay the fee.
+    #[cbor(optional)]
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes_seq"))]
+    pub after: Vec<Hash>,
+    /// Accounts with whose transactions this transaction may conflict. Schedulers preserve the
+    /// relative order of the transaction and any transactions of these accounts.
+    #[cbor(optional)]
+    pub conflicts: Vec<Address>,
+}
+
+impl AuthInfo {
+    /// Iterate over all signers, including the fee payer (if any) as the last signer.
+    pub fn signers(&self) -> impl Iterator<Item = &SignerInfo> {
+        self.signer_info.iter().chain(self.fee_payer.iter())
+    }
+
+    /// Address of the account paying the fees, if it differs from the caller.
+    pub fn fee_payer_address(&self) -> Option<Address> {
+        self.fee_payer.as_ref().map(|si| si.address_spec.address())
+    }
 }
 
 /// Transaction fee.
-#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, Default, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Fee {
     /// Amount of base units paid as fee for transaction processing.
     pub amount: token::BaseUnits,
@@ -189,19 +466,22 @@ impl Fee {
     /// Calculates gas price from fee amount and gas.
     pub fn gas_price(&self) -> u128 {
         self.amount
-            .amount()
             .checked_div(self.gas.into())
+            .map(|price| price.amount())
             .unwrap_or_default()
     }
 }
 
 /// A caller address.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CallerAddress {
     #[cbor(rename = "address")]
+    #[cfg_attr(feature = "serde", serde(rename = "address"))]
     Address(Address),
     #[cbor(rename = "eth_address")]
-    EthAddress([u8; 20]),
+    #[cfg_attr(feature = "serde", serde(rename = "eth_address"))]
+    EthAddress(#[cfg_attr(feature = "serde", serde(with = "crate::types::json::bytes"))] [u8; 20]),
 }
 
 impl CallerAddress {
@@ -227,17 +507,39 @@ impl CallerAddress {
 }
 
 /// Common information that specifies an address as well as how to authenticate.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AddressSpec {
     /// For _signature_ authentication.
     #[cbor(rename = "signature")]
+    #[cfg_attr(feature = "serde", serde(rename = "signature"))]
     Signature(SignatureAddressSpec),
     /// For _multisig_ authentication.
     #[cbor(rename = "multisig")]
-    Multisig(multisig::Config),
+    #[cfg_attr(feature = "serde", serde(rename = "multisig"))]
+    Multisig(
+        #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+        multisig::Config,
+    ),
+    /// For _WebAuthn_ (passkey) authentication.
+    #[cbor(rename = "webauthn")]
+    #[cfg_attr(feature = "serde", serde(rename = "webauthn"))]
+    WebAuthn(
+        #[cfg_attr(feature = "serde", serde(with = "crate::types::json::encoded"))]
+        webauthn::Config,
+    ),
+    /// For _session key_ authentication.
+    #[cbor(rename = "session_key")]
+    #[cfg_attr(feature = "serde", serde(rename = "session_key"))]
+    SessionKey(SessionKeySpec),
+    /// For _ROFL origin_ authentication.
+    #[cbor(rename = "rofl")]
+    #[cfg_attr(feature = "serde", serde(rename = "rofl"))]
+    Rofl(RoflOriginSpec),
 
     /// For internal child calls (cannot be serialized/deserialized).
     #[cbor(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     Internal(CallerAddress),
 }
 
@@ -247,6 +549,9 @@ impl AddressSpec {
         match self {
             AddressSpec::Signature(spec) => Address::from_sigspec(spec),
             AddressSpec::Multisig(config) => Address::from_multisig(config.clone()),
+            AddressSpec::WebAuthn(config) => Address::from_webauthn(config.clone()),
+            AddressSpec::SessionKey(spec) => spec.account,
+            AddressSpec::Rofl(spec) => spec.address(),
             AddressSpec::Internal(caller) => caller.address(),
         }
     }
@@ -257,6 +562,7 @@ impl AddressSpec {
             AddressSpec::Signature(SignatureAddressSpec::Secp256k1Eth(pk)) => {
                 CallerAddress::EthAddress(pk.to_eth_address().try_into().unwrap())
             }
+            AddressSpec::Rofl(spec) => CallerAddress::EthAddress(spec.eth_address()),
             AddressSpec::Internal(caller) => caller.clone(),
             _ => CallerAddress::Address(self.address()),
         }
@@ -269,6 +575,12 @@ impl AddressSpec {
             (AddressSpec::Signature(spec), AuthProof::Signature(signature)) => {
                 Ok((vec![spec.public_key()], vec![signature.clone()]))
             }
+            (AddressSpec::SessionKey(spec), AuthProof::Signature(signature)) => {
+                Ok((vec![spec.key.public_key()], vec![signature.clone()]))
+            }
+            (AddressSpec::Rofl(spec), AuthProof::Signature(signature)) => {
+                Ok((vec![spec.key.public_key()], vec![signature.clone()]))
+            }
             (AddressSpec::Multisig(config), AuthProof::Multisig(signature_set)) => Ok(config
                 .batch(signature_set)
                 .map_err(|e| Error::MalformedTransaction(e.into()))?),
@@ -282,6 +594,21 @@ impl AddressSpec {
                     "transaction signer used multisig, but auth proof was a single signature"
                 )))
             }
+            (AddressSpec::SessionKey(_), AuthProof::Multisig(_)) => {
+                Err(Error::MalformedTransaction(anyhow!(
+                    "transaction signer used a session key, but auth proof was multisig"
+                )))
+            }
+            (AddressSpec::Rofl(_), AuthProof::Multisig(_)) => {
+                Err(Error::MalformedTransaction(anyhow!(
+                    "transaction signer used a ROFL origin key, but auth proof was multisig"
+                )))
+            }
+            (AddressSpec::WebAuthn(_), _) | (_, AuthProof::WebAuthn(_)) => {
+                Err(Error::MalformedTransaction(anyhow!(
+                    "webauthn auth proofs must be used with a webauthn address spec and cannot be batch verified"
+                )))
+            }
             (AddressSpec::Internal(_), _) => Err(Error::MalformedTransaction(anyhow!(
                 "transaction signer used internal address spec"
             ))),
@@ -292,8 +619,59 @@ impl AddressSpec {
     }
 }
 
+/// Session key address specification.
+///
+/// The transaction is signed by a short-lived session key that the account has authorized
+/// on-chain. Instead of the account nonce, the signer nonce must be unique within the session's
+/// replay protection window.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct SessionKeySpec {
+    /// Account on whose behalf the session key signs.
+    pub account: Address,
+    /// Session key.
+    pub key: SignatureAddressSpec,
+}
+
+/// ROFL origin address specification.
+///
+/// The transaction is signed by a key endorsed by a currently registered instance of the given
+/// ROFL application. The signer address is derived from both the application and the key, so it
+/// differs from the address used when the same key signs with signature authentication.
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cbor(no_default)]
+pub struct RoflOriginSpec {
+    /// Application whose instance endorsed the key.
+    pub app: AppId,
+    /// Endorsed key.
+    pub key: SignatureAddressSpec,
+}
+
+impl RoflOriginSpec {
+    /// Derives the Ethereum-compatible address of the origin.
+    pub fn eth_address(&self) -> [u8; 20] {
+        let h = Hash::digest_bytes_list(&[
+            address::ADDRESS_V0_ROFL_ORIGIN_CONTEXT,
+            self.app.as_ref(),
+            Address::from_sigspec(&self.key).as_ref(),
+        ]);
+        h.truncated(20).try_into().unwrap()
+    }
+
+    /// Derives the address of the origin.
+    ///
+    /// The address lives in the Ethereum-compatible address space so that the origin can also be
+    /// the caller of EVM transactions.
+    pub fn address(&self) -> Address {
+        Address::from_eth(&self.eth_address())
+    }
+}
+
 /// Transaction signer information.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cbor(no_default)]
 pub struct SignerInfo {
     pub address_spec: AddressSpec,
@@ -319,12 +697,15 @@ impl SignerInfo {
 }
 
 /// Call result.
-#[derive(Clone, Debug, cbor::Encode, cbor::Decode)]
+#[derive(Clone, Debug, cbor::Encode, cbor::Decode, Schema)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CallResult {
     #[cbor(rename = "ok")]
-    Ok(cbor::Value),
+    #[cfg_attr(feature = "serde", serde(rename = "ok"))]
+    Ok(#[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))] cbor::Value),
 
     #[cbor(rename = "fail")]
+    #[cfg_attr(feature = "serde", serde(rename = "fail"))]
     Failed {
         module: String,
         code: u32,
@@ -334,7 +715,8 @@ pub enum CallResult {
     },
 
     #[cbor(rename = "unknown")]
-    Unknown(cbor::Value),
+    #[cfg_attr(feature = "serde", serde(rename = "unknown"))]
+    Unknown(#[cfg_attr(feature = "serde", serde(with = "crate::types::json::value"))] cbor::Value),
 }
 
 impl Default for CallResult {
@@ -431,4 +813,134 @@ mod test {
         };
         assert_eq!(2, fee.gas_price(), "non empty fee - gas price should match");
     }
+
+    #[test]
+    fn test_fee_payer() {
+        use crate::testing::keys;
+
+        let mut tx = Transaction {
+            version: LATEST_TRANSACTION_VERSION,
+            call: Default::default(),
+            auth_info: AuthInfo {
+                signer_info: vec![SignerInfo::new_sigspec(keys::alice::sigspec(), 0)],
+                fee_payer: Some(SignerInfo::new_sigspec(keys::bob::sigspec(), 0)),
+                ..Default::default()
+            },
+        };
+        tx.validate_basic().expect("transaction should be valid");
+        assert_eq!(tx.auth_info.signers().count(), 2);
+        assert_eq!(tx.auth_info.fee_payer_address(), Some(keys::bob::address()));
+
+        // An envelope must carry an auth proof for the fee payer.
+        let utx = UnverifiedTransaction(cbor::to_vec(tx.clone()), vec![]);
+        assert!(utx.verify().is_err());
+
+        tx.auth_info.fee_payer = Some(SignerInfo::new_sigspec(keys::alice::sigspec(), 0));
+        tx.validate_basic()
+            .expect_err("fee payer that is also the caller should be rejected");
+    }
+
+    #[test]
+    fn test_dependency_hints() {
+        use crate::testing::keys;
+
+        let mut tx = Transaction {
+            version: LATEST_TRANSACTION_VERSION,
+            call: Default::default(),
+            auth_info: AuthInfo {
+                signer_info: vec![SignerInfo::new_sigspec(keys::alice::sigspec(), 0)],
+                after: vec![Hash::digest_bytes(b"first")],
+                conflicts: vec![keys::bob::address()],
+                ..Default::default()
+            },
+        };
+        tx.validate_basic().expect("transaction should be valid");
+
+        let decoded: Transaction = cbor::from_slice(&cbor::to_vec(tx.clone())).unwrap();
+        assert_eq!(decoded.auth_info.after, tx.auth_info.after);
+        assert_eq!(decoded.auth_info.conflicts, tx.auth_info.conflicts);
+
+        tx.auth_info.conflicts = vec![keys::bob::address(); MAX_DEPENDENCY_HINTS];
+        tx.validate_basic()
+            .expect_err("transactions with too many hints should be rejected");
+    }
+
+    #[test]
+    fn test_preview() {
+        use crate::{modules::accounts, testing::keys};
+
+        let mut tx = Transaction {
+            version: LATEST_TRANSACTION_VERSION,
+            call: Call {
+                method: "accounts.Transfer".to_owned(),
+                body: cbor::to_value(accounts::types::Transfer {
+                    to: keys::bob::address(),
+                    amount: BaseUnits::new(1_000, Denomination::NATIVE),
+                }),
+                ..Default::default()
+            },
+            auth_info: AuthInfo {
+                signer_info: vec![SignerInfo::new_sigspec(keys::alice::sigspec(), 0)],
+                fee: Fee {
+                    amount: BaseUnits::new(10, Denomination::NATIVE),
+                    gas: 1_000,
+                    consensus_messages: 0,
+                },
+                not_after: Some(42),
+                ..Default::default()
+            },
+        };
+
+        let preview = tx.preview::<accounts::Module>();
+        assert_eq!(
+            preview.to_string(),
+            format!(
+                "Method: accounts.Transfer\n\
+                 To: {}\n\
+                 Amount: 1000 <native>\n\
+                 Caller: {}\n\
+                 Fee: 10 <native>\n\
+                 Gas limit: 1000\n\
+                 Valid until round: 42\n",
+                keys::bob::address(),
+                keys::alice::address(),
+            )
+        );
+
+        // Methods without rendering rules fall back to the body hash.
+        tx.call.method = "unknown.Method\nTo: spoofed".to_owned();
+        let preview = tx.preview::<accounts::Module>();
+        assert_eq!(preview.fields[0].value, "unknown.Method\\nTo: spoofed");
+        assert_eq!(preview.fields[1].label, "Body hash");
+    }
+
+    #[test]
+    fn test_envelope_compression() {
+        let utx = UnverifiedTransaction(vec![0u8; 1024], vec![]);
+        let envelope = cbor::to_vec(utx);
+
+        // Uncompressed envelopes are passed through.
+        assert_eq!(envelope_size(&envelope), envelope.len());
+        let decoded = decode_envelope(&envelope, envelope.len()).unwrap();
+        assert!(matches!(decoded, Cow::Borrowed(_)));
+
+        let compressed = compress_envelope(&envelope);
+        assert_eq!(compressed[0], ENVELOPE_FORMAT_SNAPPY);
+        assert!(compressed.len() < envelope.len());
+        assert_eq!(envelope_size(&compressed), envelope.len());
+        let decoded = decode_envelope(&compressed, envelope.len()).unwrap();
+        assert_eq!(decoded.as_ref(), envelope.as_slice());
+        let _: UnverifiedTransaction = cbor::from_slice(&decoded).unwrap();
+
+        // Decompressed size must be within bounds.
+        assert!(matches!(
+            decode_envelope(&compressed, envelope.len() - 1),
+            Err(Error::OversizedTransaction)
+        ));
+        // Malformed compressed data should be rejected.
+        assert!(matches!(
+            decode_envelope(&compressed[..compressed.len() / 2], envelope.len()),
+            Err(Error::MalformedTransaction(_))
+        ));
+    }
 }
@@ -0,0 +1,8 @@
+//! Generate the wire format test vectors shared with the client SDKs.
+//!
+//! Usage: `gen-test-vectors > vectors.json`.
+use oasis_runtime_sdk::testing::vectors;
+
+fn main() {
+    println!("{}", vectors::to_json(&vectors::generate()));
+}
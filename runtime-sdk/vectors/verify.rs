@@ -0,0 +1,37 @@
+//! Verify wire format test vectors (e.g. generated by another SDK) against the Rust types.
+//!
+//! Usage: `verify-test-vectors VECTORS`.
+//! Exits with a non-zero status in case any of the vectors does not verify.
+use std::{env, fs, process};
+
+use oasis_runtime_sdk::testing::vectors;
+
+fn main() {
+    let path = match &env::args().skip(1).collect::<Vec<_>>()[..] {
+        [path] => path.clone(),
+        _ => {
+            eprintln!("usage: verify-test-vectors VECTORS");
+            process::exit(2);
+        }
+    };
+    let data = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("failed to read test vectors {path}: {err}");
+        process::exit(2);
+    });
+    let vectors = vectors::from_json(&data).unwrap_or_else(|err| {
+        eprintln!("failed to parse test vectors {path}: {err}");
+        process::exit(2);
+    });
+
+    let mut failed = false;
+    for vector in &vectors {
+        if let Err(err) = vector.verify() {
+            println!("{err}");
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+    println!("verified {} test vectors", vectors.len());
+}
@@ -63,6 +63,7 @@ impl sdk::Runtime for Runtime {
                     gas_costs: Default::default(),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             modules::core::Genesis {
                 parameters: modules::core::Parameters {
@@ -78,7 +79,13 @@ impl sdk::Runtime for Runtime {
                         mgp
                     },
                     dynamic_min_gas_price: Default::default(),
+                    // Runtime-wide subcall limits are opt-in; zero values leave them disabled.
+                    subcall: Default::default(),
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
         )
     }
@@ -86,10 +86,17 @@ impl sdk::Runtime for Runtime {
                         mgp
                     },
                     dynamic_min_gas_price: Default::default(),
+                    // Runtime-wide subcall limits are opt-in; zero values leave them disabled.
+                    subcall: Default::default(),
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
             contracts::Genesis {
                 parameters: Default::default(),
+                ..Default::default()
             },
         )
     }
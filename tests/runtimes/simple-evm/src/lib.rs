@@ -94,6 +94,7 @@ impl sdk::Runtime for Runtime {
                     gas_costs: Default::default(),
                     ..Default::default()
                 },
+                ..Default::default()
             },
             modules::core::Genesis {
                 parameters: modules::core::Parameters {
@@ -112,12 +113,23 @@ impl sdk::Runtime for Runtime {
                         mgp
                     },
                     dynamic_min_gas_price: Default::default(),
+                    // Subcall limits are opt-in; retain 1/64 of the gas like EVM calls do.
+                    subcall: modules::core::SubcallParameters {
+                        max_depth: 8,
+                        gas_retention_denominator: 64,
+                        ..Default::default()
+                    },
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
             evm::Genesis {
                 parameters: evm::Parameters {
                     gas_costs: Default::default(),
                 },
+                ..Default::default()
             },
         )
     }
@@ -67,7 +67,10 @@ impl sdk::Runtime for Runtime {
             },
             modules::accounts::Genesis {
                 parameters: modules::accounts::Parameters {
-                    gas_costs: modules::accounts::GasCosts { tx_transfer: 100 },
+                    gas_costs: modules::accounts::GasCosts {
+                        tx_transfer: 100,
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 balances: {
@@ -117,11 +120,16 @@ impl sdk::Runtime for Runtime {
                         steps: vec![modules::rewards::types::RewardStep {
                             until: 1000,
                             amount: BaseUnits::new(100, Denomination::NATIVE),
+                            curve: None,
                         }],
                     },
                     participation_threshold_numerator: 1, // These are updated below.
                     participation_threshold_denominator: 1,
+                    min_participation_rounds: 0,
+                    delegation: None,
+                    penalties: Default::default(),
                 },
+                ..Default::default()
             },
             modules::core::Genesis {
                 parameters: modules::core::Parameters {
@@ -133,7 +141,16 @@ impl sdk::Runtime for Runtime {
                         tx_byte: 1,
                         auth_signature: 10,
                         auth_multisig_signer: 10,
+                        auth_signature_bls12381: 0,
+                        auth_signature_secp256k1schnorr: 0,
+                        auth_signature_dilithium3: 0,
                         callformat_x25519_deoxysii: 50,
+                        callformat_x25519_xchacha20poly1305: 50,
+                        tx_memo_byte: 0,
+                        storage_remove_refund: 0,
+                        storage_restore_refund: 0,
+                        storage_cold_access: 0,
+                        storage_warm_access: 0,
                     },
                     min_gas_price: {
                         let mut mgp = BTreeMap::new();
@@ -141,7 +158,13 @@ impl sdk::Runtime for Runtime {
                         mgp
                     },
                     dynamic_min_gas_price: Default::default(),
+                    // Runtime-wide subcall limits are opt-in; zero values leave them disabled.
+                    subcall: Default::default(),
+                    block_limits: Default::default(),
+                    ephemeral_keys: Default::default(),
+                    storage_deposit: Default::default(),
                 },
+                ..Default::default()
             },
         )
     }
@@ -25,6 +25,10 @@ fn test_impl_for_tuple() {
             mgp
         },
         dynamic_min_gas_price: Default::default(),
+        subcall: Default::default(),
+        block_limits: Default::default(),
+        ephemeral_keys: Default::default(),
+        storage_deposit: Default::default(),
     });
 
     let dummy_bytes = b"you look, you die".to_vec();